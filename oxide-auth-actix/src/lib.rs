@@ -27,7 +27,7 @@ use url::Url;
 
 mod operations;
 
-pub use operations::{Authorize, Refresh, Resource, Token};
+pub use operations::{Authorize, Discover, Introspect, Jwks, Refresh, Resource, Revoke, Token};
 
 /// Describes an operation that can be performed in the presence of an `Endpoint`
 ///
@@ -117,6 +117,8 @@ pub struct OAuthRequest {
     auth: Option<String>,
     query: Option<NormalizedParameter>,
     body: Option<NormalizedParameter>,
+    dpop: Option<String>,
+    target: Option<(String, Url)>,
 }
 
 impl OAuthResponse {
@@ -137,6 +139,8 @@ impl OAuthResponse {
 /// request upon extraction
 pub struct OAuthResource {
     auth: Option<String>,
+    dpop: Option<String>,
+    target: Option<(String, Url)>,
 }
 
 #[derive(Clone, Debug)]
@@ -193,16 +197,17 @@ impl OAuthRequest {
             .ok()
             .map(|b: Form<NormalizedParameter>| b.into_inner());
 
-        let mut all_auth = req.headers().get_all(header::AUTHORIZATION);
-        let optional = all_auth.next();
+        let auth = single_header(&req, header::AUTHORIZATION.as_str())?;
+        let dpop = single_header(&req, "DPoP")?;
+        let target = request_target(&req);
 
-        let auth = if all_auth.next().is_some() {
-            return Err(WebError::Authorization);
-        } else {
-            optional.and_then(|hv| hv.to_str().ok().map(str::to_owned))
-        };
-
-        Ok(OAuthRequest { auth, query, body })
+        Ok(OAuthRequest {
+            auth,
+            query,
+            body,
+            dpop,
+            target,
+        })
     }
 
     /// Fetch the authorization header from the request
@@ -229,16 +234,11 @@ impl OAuthRequest {
 impl OAuthResource {
     /// Create a new OAuthResource from an HttpRequest
     pub fn new(req: &HttpRequest) -> Result<Self, WebError> {
-        let mut all_auth = req.headers().get_all(header::AUTHORIZATION);
-        let optional = all_auth.next();
+        let auth = single_header(req, header::AUTHORIZATION.as_str())?;
+        let dpop = single_header(req, "DPoP")?;
+        let target = request_target(req);
 
-        let auth = if all_auth.next().is_some() {
-            return Err(WebError::Authorization);
-        } else {
-            optional.and_then(|hv| hv.to_str().ok().map(str::to_owned))
-        };
-
-        Ok(OAuthResource { auth })
+        Ok(OAuthResource { auth, dpop, target })
     }
 
     /// Turn this OAuthResource into an OAuthRequest for processing
@@ -247,10 +247,32 @@ impl OAuthResource {
             query: None,
             body: None,
             auth: self.auth,
+            dpop: self.dpop,
+            target: self.target,
         }
     }
 }
 
+/// Fetch a header that must not occur more than once.
+fn single_header(req: &HttpRequest, name: &str) -> Result<Option<String>, WebError> {
+    let mut all = req.headers().get_all(name);
+    let optional = all.next();
+
+    if all.next().is_some() {
+        return Err(WebError::Authorization);
+    }
+
+    Ok(optional.and_then(|hv| hv.to_str().ok().map(str::to_owned)))
+}
+
+/// The method and absolute uri of the request, as addressed by the client.
+fn request_target(req: &HttpRequest) -> Option<(String, Url)> {
+    let info = req.connection_info();
+    let uri = format!("{}://{}{}", info.scheme(), info.host(), req.uri());
+    let uri = uri.parse().ok()?;
+    Some((req.method().as_str().to_owned(), uri))
+}
+
 impl OAuthResponse {
     /// Create a simple response with no body and a '200 OK' HTTP Status
     pub fn ok() -> Self {
@@ -303,6 +325,21 @@ impl WebRequest for OAuthRequest {
     fn authheader(&mut self) -> Result<Option<Cow<str>>, Self::Error> {
         Ok(self.auth.as_deref().map(Cow::Borrowed))
     }
+
+    fn header(&mut self, name: &str) -> Result<Option<Cow<'_, str>>, Self::Error> {
+        if name.eq_ignore_ascii_case("DPoP") {
+            Ok(self.dpop.as_deref().map(Cow::Borrowed))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn target(&mut self) -> Result<Option<(Cow<'_, str>, Url)>, Self::Error> {
+        Ok(self
+            .target
+            .as_ref()
+            .map(|(method, uri)| (Cow::Borrowed(method.as_str()), uri.clone())))
+    }
 }
 
 impl WebResponse for OAuthResponse {
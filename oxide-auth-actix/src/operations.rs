@@ -1,7 +1,10 @@
 use crate::{OAuthRequest, OAuthResponse, OAuthOperation, WebError};
 use oxide_auth::{
-    endpoint::{AccessTokenFlow, AuthorizationFlow, Endpoint, RefreshFlow, ResourceFlow},
-    primitives::grant::Grant,
+    endpoint::{
+        AccessTokenFlow, AuthorizationFlow, Endpoint, IntrospectionFlow, JwksFlow, Metadata,
+        MetadataFlow, RefreshFlow, ResourceFlow, RevokeFlow,
+    },
+    primitives::{grant::Grant, jwk::JwkSet},
 };
 
 /// Authorization-related operations
@@ -58,6 +61,78 @@ impl OAuthOperation for Refresh {
     }
 }
 
+/// Revocation-related operations
+pub struct Revoke(pub OAuthRequest);
+
+impl OAuthOperation for Revoke {
+    type Item = OAuthResponse;
+    type Error = WebError;
+
+    fn run<E>(self, endpoint: E) -> Result<Self::Item, Self::Error>
+    where
+        E: Endpoint<OAuthRequest>,
+        WebError: From<E::Error>,
+    {
+        RevokeFlow::prepare(endpoint)?
+            .execute(self.0)
+            .map_err(WebError::from)
+    }
+}
+
+/// Introspection-related operations
+pub struct Introspect(pub OAuthRequest);
+
+impl OAuthOperation for Introspect {
+    type Item = OAuthResponse;
+    type Error = WebError;
+
+    fn run<E>(self, endpoint: E) -> Result<Self::Item, Self::Error>
+    where
+        E: Endpoint<OAuthRequest>,
+        WebError: From<E::Error>,
+    {
+        IntrospectionFlow::prepare(endpoint)?
+            .execute(self.0)
+            .map_err(WebError::from)
+    }
+}
+
+/// Authorization server metadata operations
+pub struct Discover(pub OAuthRequest, pub Metadata);
+
+impl OAuthOperation for Discover {
+    type Item = OAuthResponse;
+    type Error = WebError;
+
+    fn run<E>(self, endpoint: E) -> Result<Self::Item, Self::Error>
+    where
+        E: Endpoint<OAuthRequest>,
+        WebError: From<E::Error>,
+    {
+        MetadataFlow::new(endpoint, self.1)
+            .execute(self.0)
+            .map_err(WebError::from)
+    }
+}
+
+/// JSON Web Key Set operations
+pub struct Jwks(pub OAuthRequest, pub JwkSet);
+
+impl OAuthOperation for Jwks {
+    type Item = OAuthResponse;
+    type Error = WebError;
+
+    fn run<E>(self, endpoint: E) -> Result<Self::Item, Self::Error>
+    where
+        E: Endpoint<OAuthRequest>,
+        WebError: From<E::Error>,
+    {
+        JwksFlow::new(endpoint, self.1)
+            .execute(self.0)
+            .map_err(WebError::from)
+    }
+}
+
 /// Resource-related operations
 pub struct Resource(pub OAuthRequest);
 
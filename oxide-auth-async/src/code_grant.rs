@@ -1,6 +1,7 @@
 pub mod refresh {
+    use oxide_auth::code_grant::error::AccessTokenErrorType;
     use oxide_auth::code_grant::refresh::{BearerToken, Error, Input, Output, Refresh, Request};
-    use oxide_auth::primitives::{grant::Grant, registrar::RegistrarError};
+    use oxide_auth::primitives::{grant::Grant, mtls::ClientCertificate, registrar::RegistrarError};
 
     pub trait Endpoint {
         /// Authenticate the requesting confidential client.
@@ -15,9 +16,18 @@ pub mod refresh {
     ) -> Result<BearerToken, Error> {
         enum Requested {
             None,
-            Refresh { token: String, grant: Box<Grant> },
-            RecoverRefresh { token: String },
-            Authenticate { client: String, pass: Option<Vec<u8>> },
+            Refresh {
+                token: String,
+                grant: Box<Grant>,
+            },
+            RecoverRefresh {
+                token: String,
+            },
+            Authenticate {
+                client: String,
+                pass: Option<Vec<u8>>,
+                certificate: Option<ClientCertificate>,
+            },
         }
         let mut refresh = Refresh::new(request);
         let mut requested = Requested::None;
@@ -25,6 +35,16 @@ pub mod refresh {
             let input = match requested {
                 Requested::None => Input::None,
                 Requested::Refresh { token, grant } => {
+                    handler
+                        .registrar()
+                        .check_grant_type(&grant.client_id, "refresh_token")
+                        .await
+                        .map_err(|err| match err {
+                            RegistrarError::PrimitiveError => Error::Primitive,
+                            RegistrarError::Unspecified => {
+                                Error::invalid(AccessTokenErrorType::UnauthorizedClient)
+                            }
+                        })?;
                     let refreshed = handler
                         .issuer()
                         .refresh(&token, *grant)
@@ -43,159 +63,1022 @@ pub mod refresh {
                         grant: recovered.map(Box::new),
                     }
                 }
-                Requested::Authenticate { client, pass } => {
-                    let _: () = handler
+                Requested::Authenticate {
+                    client,
+                    pass,
+                    certificate,
+                } => {
+                    let _: () = super::authenticate(
+                        handler.registrar(),
+                        &client,
+                        pass.as_deref(),
+                        certificate.as_ref(),
+                    )
+                    .await
+                    .map_err(|err| match err {
+                        RegistrarError::PrimitiveError => Error::Primitive,
+                        RegistrarError::Unspecified => Error::unauthorized("basic"),
+                    })?;
+                    Input::Authenticated {
+                        scope: request.scope(),
+                    }
+                }
+            };
+
+            requested = match refresh.advance(input) {
+                Output::Err(error) => return Err(error),
+                Output::Ok(token) => return Ok(token),
+                Output::Refresh { token, grant } => Requested::Refresh {
+                    token: token.to_string(),
+                    grant,
+                },
+                Output::RecoverRefresh { token } => Requested::RecoverRefresh {
+                    token: token.to_string(),
+                },
+                Output::Unauthenticated {
+                    client,
+                    pass,
+                    certificate,
+                } => Requested::Authenticate {
+                    client: client.to_string(),
+                    pass: pass.map(|p| p.to_vec()),
+                    certificate: certificate.cloned(),
+                },
+            };
+        }
+    }
+}
+
+pub mod resource {
+    use oxide_auth::code_grant::resource::{Error, Input, Output, Request, Resource};
+    use oxide_auth::primitives::grant::Grant;
+    use oxide_auth::primitives::scope::Scope;
+
+    pub trait Endpoint {
+        /// The list of possible scopes required by the resource endpoint.
+        fn scopes(&mut self) -> &[Scope];
+
+        /// Recover and test the provided refresh token then issue new tokens.
+        fn issuer(&mut self) -> &mut (dyn crate::primitives::Issuer + Send);
+    }
+
+    pub async fn protect(
+        handler: &mut (dyn Endpoint + Send + Sync), req: &(dyn Request + Sync),
+    ) -> Result<Grant, Error> {
+        enum Requested {
+            None,
+            Request,
+            Scopes,
+            Grant(String),
+        }
+
+        let mut resource = Resource::new();
+        let mut requested = Requested::None;
+        loop {
+            let input = match requested {
+                Requested::None => Input::None,
+                Requested::Request => Input::Request { request: req },
+                Requested::Scopes => Input::Scopes(handler.scopes()),
+                Requested::Grant(token) => {
+                    let grant = handler
+                        .issuer()
+                        .recover_token(&token)
+                        .await
+                        .map_err(|_| Error::PrimitiveError)?;
+                    Input::Recovered(grant)
+                }
+            };
+
+            requested = match resource.advance(input) {
+                Output::Err(error) => return Err(error),
+                Output::Ok(grant) => return Ok(*grant),
+                Output::GetRequest => Requested::Request,
+                Output::DetermineScopes => Requested::Scopes,
+                Output::Recover { token } => Requested::Grant(token.to_string()),
+            };
+        }
+    }
+}
+
+pub mod access_token {
+    use async_trait::async_trait;
+    use oxide_auth::{
+        code_grant::{
+            accesstoken::{AccessToken, BearerToken, Error, Input, Output, Request as TokenRequest},
+            error::AccessTokenErrorType,
+        },
+        primitives::{
+            grant::{Extensions, Grant},
+            mtls::ClientCertificate,
+            registrar::RegistrarError,
+        },
+    };
+    // use crate::endpoint::access_token::WrappedRequest;
+
+    #[async_trait]
+    pub trait Extension {
+        /// Inspect the request and extension data to produce extension data.
+        ///
+        /// The input data comes from the extension data produced in the handling of the
+        /// authorization code request.
+        async fn extend(
+            &mut self, request: &(dyn TokenRequest + Sync), data: Extensions,
+        ) -> std::result::Result<Extensions, ()>;
+    }
+
+    #[async_trait]
+    impl Extension for () {
+        async fn extend(
+            &mut self, _: &(dyn TokenRequest + Sync), _: Extensions,
+        ) -> std::result::Result<Extensions, ()> {
+            Ok(Extensions::new())
+        }
+    }
+
+    pub trait Endpoint {
+        /// Get the client corresponding to some id.
+        fn registrar(&self) -> &(dyn crate::primitives::Registrar + Sync);
+
+        /// Get the authorizer from which we can recover the authorization.
+        fn authorizer(&mut self) -> &mut (dyn crate::primitives::Authorizer + Send);
+
+        /// Return the issuer instance to create the access token.
+        fn issuer(&mut self) -> &mut (dyn crate::primitives::Issuer + Send);
+
+        /// The system of used extension, extending responses.
+        ///
+        /// It is possible to use `&mut ()`.
+        fn extension(&mut self) -> &mut (dyn Extension + Send);
+    }
+
+    pub async fn access_token(
+        handler: &mut (dyn Endpoint + Send + Sync), request: &(dyn TokenRequest + Sync),
+    ) -> Result<BearerToken, Error> {
+        enum Requested<'a> {
+            None,
+            Authenticate {
+                client: &'a str,
+                passdata: Option<&'a [u8]>,
+                certificate: Option<&'a ClientCertificate>,
+            },
+            Recover(&'a str),
+            Extend {
+                extensions: &'a mut Extensions,
+            },
+            Issue {
+                grant: &'a Grant,
+            },
+        }
+
+        let mut access_token = AccessToken::new(request);
+        let mut requested = Requested::None;
+
+        loop {
+            let input = match requested {
+                Requested::None => Input::None,
+                Requested::Authenticate {
+                    client,
+                    passdata,
+                    certificate,
+                } => {
+                    super::authenticate(handler.registrar(), client, passdata, certificate)
+                        .await
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => Error::unauthorized("basic"),
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        })?;
+                    handler
+                        .registrar()
+                        .check_grant_type(client, "authorization_code")
+                        .await
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => {
+                                Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
+                            }
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        })?;
+                    Input::Authenticated
+                }
+                Requested::Recover(code) => {
+                    let opt_grant = handler
+                        .authorizer()
+                        .extract(code)
+                        .await
+                        .map_err(|_| Error::primitive(None))?;
+                    Input::Recovered(opt_grant.map(Box::new))
+                }
+                Requested::Extend { extensions } => {
+                    let access_extensions = handler
+                        .extension()
+                        .extend(request, extensions.clone())
+                        .await
+                        .map_err(|_| Error::invalid())?;
+
+                    Input::Extended { access_extensions }
+                }
+                Requested::Issue { grant } => {
+                    let token = handler.issuer().issue(grant.clone()).await.map_err(|_| {
+                        // FIXME: endpoint should get and handle these.
+                        Error::primitive(None)
+                    })?;
+                    Input::Issued(token)
+                }
+            };
+
+            requested = match access_token.advance(input) {
+                Output::Authenticate {
+                    client,
+                    passdata,
+                    certificate,
+                } => Requested::Authenticate {
+                    client,
+                    passdata,
+                    certificate,
+                },
+                Output::Recover { code } => Requested::Recover(code),
+                Output::Extend { extensions, .. } => Requested::Extend { extensions },
+                Output::Issue { grant } => Requested::Issue { grant },
+                Output::Ok(token) => return Ok(token),
+                Output::Err(e) => return Err(*e),
+            };
+        }
+    }
+}
+
+pub mod client_credentials {
+    use std::borrow::Cow;
+
+    use oxide_auth::{
+        code_grant::{
+            accesstoken::{BearerToken, Error},
+            client_credentials::{ClientCredentials, Input, Output, Request},
+            error::AccessTokenErrorType,
+        },
+        primitives::{
+            grant::Grant,
+            mtls::ClientCertificate,
+            registrar::{BoundClient, ClientUrl, RegistrarError},
+            scope::Scope,
+        },
+    };
+
+    pub trait Endpoint {
+        /// Authenticate the client and negotiate the scope of the grant.
+        fn registrar(&self) -> &(dyn crate::primitives::Registrar + Sync);
+
+        /// Return the issuer instance to create the access token.
+        fn issuer(&mut self) -> &mut (dyn crate::primitives::Issuer + Send);
+    }
+
+    pub async fn client_credentials(
+        handler: &mut (dyn Endpoint + Send + Sync), request: &(dyn Request + Sync),
+    ) -> Result<BearerToken, Error> {
+        enum Requested {
+            None,
+            Authenticate {
+                client: String,
+                passdata: Option<Vec<u8>>,
+                certificate: Option<ClientCertificate>,
+            },
+            Bind { client_id: String },
+            Negotiate { bound_client: BoundClient<'static>, scope: Option<Scope> },
+            Issue { grant: Grant },
+        }
+
+        let mut client_credentials = ClientCredentials::new(request);
+        let mut requested = Requested::None;
+
+        loop {
+            let input = match requested {
+                Requested::None => Input::None,
+                Requested::Authenticate {
+                    client,
+                    passdata,
+                    certificate,
+                } => {
+                    super::authenticate(
+                        handler.registrar(),
+                        &client,
+                        passdata.as_deref(),
+                        certificate.as_ref(),
+                    )
+                    .await
+                    .map_err(|err| match err {
+                        RegistrarError::Unspecified => Error::unauthorized("basic"),
+                        RegistrarError::PrimitiveError => Error::primitive(None),
+                    })?;
+                    handler
+                        .registrar()
+                        .check_grant_type(&client, "client_credentials")
+                        .await
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => {
+                                Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
+                            }
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        })?;
+                    Input::Authenticated
+                }
+                Requested::Bind { client_id } => {
+                    let client_url = ClientUrl {
+                        client_id: Cow::Owned(client_id),
+                        redirect_uri: None,
+                    };
+                    let bound_client = handler
+                        .registrar()
+                        .bound_redirect(client_url)
+                        .await
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => {
+                                Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
+                            }
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        },
+                    )?;
+                    Input::Bound { bound_client }
+                }
+                Requested::Negotiate { bound_client, scope } => {
+                    let pre_grant = handler
+                        .registrar()
+                        .negotiate(bound_client, scope)
+                        .await
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => {
+                                Error::invalid_with(AccessTokenErrorType::InvalidScope)
+                            }
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        },
+                    )?;
+                    Input::Negotiated { pre_grant }
+                }
+                Requested::Issue { grant } => {
+                    let token = handler
+                        .issuer()
+                        .issue(grant.clone())
+                        .await
+                        .map_err(|_| Error::primitive(Some(grant)))?;
+                    Input::Issued(token)
+                }
+            };
+
+            requested = match client_credentials.advance(input) {
+                Output::Authenticate {
+                    client,
+                    passdata,
+                    certificate,
+                } => Requested::Authenticate {
+                    client: client.to_string(),
+                    passdata: passdata.map(Vec::from),
+                    certificate: certificate.cloned(),
+                },
+                Output::Binding { client_id } => Requested::Bind {
+                    client_id: client_id.to_string(),
+                },
+                Output::Negotiate { bound_client, scope } => Requested::Negotiate {
+                    bound_client: bound_client.clone(),
+                    scope,
+                },
+                Output::Issue { grant } => Requested::Issue { grant: grant.clone() },
+                Output::Ok(token) => return Ok(token),
+                Output::Err(e) => return Err(*e),
+            };
+        }
+    }
+}
+
+pub mod password {
+    use std::borrow::Cow;
+
+    use oxide_auth::{
+        code_grant::{
+            accesstoken::{BearerToken, Error},
+            error::AccessTokenErrorType,
+            password::{Password, Input, Output, Request},
+        },
+        primitives::{
+            grant::Grant,
+            registrar::{BoundClient, ClientUrl, RegistrarError},
+            scope::Scope,
+        },
+    };
+
+    pub trait Endpoint {
+        /// Authenticate the client, check its permission and negotiate the scope of the grant.
+        fn registrar(&self) -> &(dyn crate::primitives::Registrar + Sync);
+
+        /// Check the credentials of the resource owner.
+        fn authenticator(&self) -> &(dyn crate::primitives::OwnerAuthenticator + Sync);
+
+        /// Return the issuer instance to create the access token.
+        fn issuer(&mut self) -> &mut (dyn crate::primitives::Issuer + Send);
+    }
+
+    pub async fn password(
+        handler: &mut (dyn Endpoint + Send + Sync), request: &(dyn Request + Sync),
+    ) -> Result<BearerToken, Error> {
+        enum Requested {
+            None,
+            Authenticate { client: String, passdata: Option<Vec<u8>> },
+            Permit { client: String },
+            AuthenticateOwner { username: String, password: String },
+            Bind { client_id: String },
+            Negotiate { bound_client: BoundClient<'static>, scope: Option<Scope> },
+            Issue { grant: Grant },
+        }
+
+        let mut password = Password::new(request);
+        let mut requested = Requested::None;
+
+        loop {
+            let input = match requested {
+                Requested::None => Input::None,
+                Requested::Authenticate { client, passdata } => {
+                    handler
+                        .registrar()
+                        .check(&client, passdata.as_deref())
+                        .await
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => Error::unauthorized("basic"),
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        })?;
+                    Input::Authenticated
+                }
+                Requested::Permit { client } => {
+                    handler
+                        .registrar()
+                        .check_grant_type(&client, "password")
+                        .await
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => {
+                                Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
+                            }
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        })?;
+                    Input::Permitted
+                }
+                Requested::AuthenticateOwner { username, password } => {
+                    let owner_id = handler
+                        .authenticator()
+                        .authenticate(&username, password.as_bytes())
+                        .await
+                        .map_err(|()| Error::primitive(None))?;
+                    Input::OwnerAuthenticated(owner_id)
+                }
+                Requested::Bind { client_id } => {
+                    let client_url = ClientUrl {
+                        client_id: Cow::Owned(client_id),
+                        redirect_uri: None,
+                    };
+                    let bound_client = handler
+                        .registrar()
+                        .bound_redirect(client_url)
+                        .await
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => {
+                                Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
+                            }
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        },
+                    )?;
+                    Input::Bound { bound_client }
+                }
+                Requested::Negotiate { bound_client, scope } => {
+                    let pre_grant = handler
+                        .registrar()
+                        .negotiate(bound_client, scope)
+                        .await
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => {
+                                Error::invalid_with(AccessTokenErrorType::InvalidScope)
+                            }
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        },
+                    )?;
+                    Input::Negotiated { pre_grant }
+                }
+                Requested::Issue { grant } => {
+                    let token = handler
+                        .issuer()
+                        .issue(grant.clone())
+                        .await
+                        .map_err(|_| Error::primitive(Some(grant)))?;
+                    Input::Issued(token)
+                }
+            };
+
+            requested = match password.advance(input) {
+                Output::Authenticate { client, passdata } => Requested::Authenticate {
+                    client: client.to_string(),
+                    passdata: passdata.map(Vec::from),
+                },
+                Output::Permit { client } => Requested::Permit {
+                    client: client.to_string(),
+                },
+                Output::AuthenticateOwner { username, password } => Requested::AuthenticateOwner {
+                    username: username.to_string(),
+                    password: password.to_string(),
+                },
+                Output::Binding { client_id } => Requested::Bind {
+                    client_id: client_id.to_string(),
+                },
+                Output::Negotiate { bound_client, scope } => Requested::Negotiate {
+                    bound_client: bound_client.clone(),
+                    scope,
+                },
+                Output::Issue { grant } => Requested::Issue { grant: grant.clone() },
+                Output::Ok(token) => return Ok(token),
+                Output::Err(e) => return Err(*e),
+            };
+        }
+    }
+}
+
+pub mod jwt_bearer {
+    use std::borrow::Cow;
+
+    use oxide_auth::{
+        code_grant::{
+            accesstoken::{BearerToken, Error},
+            error::AccessTokenErrorType,
+            jwt_bearer::{JwtBearer, Input, Output, Request, JWT_BEARER_GRANT_TYPE},
+        },
+        primitives::{
+            grant::Grant,
+            jwt::TrustedIssuers,
+            registrar::{BoundClient, ClientUrl, RegistrarError},
+            scope::Scope,
+        },
+    };
+
+    pub trait Endpoint {
+        /// Authenticate the client and negotiate the scope of the grant.
+        fn registrar(&self) -> &(dyn crate::primitives::Registrar + Sync);
+
+        /// Check the signature and claims of the assertion.
+        fn verifier(&self) -> &(dyn crate::primitives::AssertionVerifier + Sync);
+
+        /// The issuers whose assertions are accepted.
+        fn trusted_issuers(&self) -> &TrustedIssuers;
+
+        /// Return the issuer instance to create the access token.
+        fn issuer(&mut self) -> &mut (dyn crate::primitives::Issuer + Send);
+    }
+
+    pub async fn jwt_bearer(
+        handler: &mut (dyn Endpoint + Send + Sync), request: &(dyn Request + Sync),
+    ) -> Result<BearerToken, Error> {
+        enum Requested {
+            None,
+            Authenticate { client: String, passdata: Option<Vec<u8>> },
+            Verify { assertion: String },
+            Bind { client_id: String },
+            Negotiate { bound_client: BoundClient<'static>, scope: Option<Scope> },
+            Issue { grant: Grant },
+        }
+
+        let mut jwt_bearer = JwtBearer::new(request);
+        let mut requested = Requested::None;
+
+        loop {
+            let input = match requested {
+                Requested::None => Input::None,
+                Requested::Authenticate { client, passdata } => {
+                    handler
+                        .registrar()
+                        .check(&client, passdata.as_deref())
+                        .await
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => Error::unauthorized("basic"),
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        })?;
+                    handler
+                        .registrar()
+                        .check_grant_type(&client, JWT_BEARER_GRANT_TYPE)
+                        .await
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => {
+                                Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
+                            }
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        })?;
+                    Input::Authenticated
+                }
+                Requested::Verify { assertion } => {
+                    let verified = handler
+                        .verifier()
+                        .verify(&assertion, handler.trusted_issuers())
+                        .await
+                        .map_err(|()| Error::primitive(None))?;
+                    Input::Verified(verified)
+                }
+                Requested::Bind { client_id } => {
+                    let client_url = ClientUrl {
+                        client_id: Cow::Owned(client_id),
+                        redirect_uri: None,
+                    };
+                    let bound_client = handler
+                        .registrar()
+                        .bound_redirect(client_url)
+                        .await
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => {
+                                Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
+                            }
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        },
+                    )?;
+                    Input::Bound { bound_client }
+                }
+                Requested::Negotiate { bound_client, scope } => {
+                    let pre_grant = handler
+                        .registrar()
+                        .negotiate(bound_client, scope)
+                        .await
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => {
+                                Error::invalid_with(AccessTokenErrorType::InvalidScope)
+                            }
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        },
+                    )?;
+                    Input::Negotiated { pre_grant }
+                }
+                Requested::Issue { grant } => {
+                    let token = handler
+                        .issuer()
+                        .issue(grant.clone())
+                        .await
+                        .map_err(|_| Error::primitive(Some(grant)))?;
+                    Input::Issued(token)
+                }
+            };
+
+            requested = match jwt_bearer.advance(input) {
+                Output::Authenticate { client, passdata } => Requested::Authenticate {
+                    client: client.to_string(),
+                    passdata: passdata.map(Vec::from),
+                },
+                Output::Verify { assertion } => Requested::Verify {
+                    assertion: assertion.to_string(),
+                },
+                Output::Binding { client_id } => Requested::Bind {
+                    client_id: client_id.to_string(),
+                },
+                Output::Negotiate { bound_client, scope } => Requested::Negotiate {
+                    bound_client: bound_client.clone(),
+                    scope,
+                },
+                Output::Issue { grant } => Requested::Issue { grant: grant.clone() },
+                Output::Ok(token) => return Ok(token),
+                Output::Err(e) => return Err(*e),
+            };
+        }
+    }
+}
+
+pub mod token_exchange {
+    use std::borrow::Cow;
+
+    use oxide_auth::{
+        code_grant::{
+            accesstoken::Error,
+            error::AccessTokenErrorType,
+            token_exchange::{
+                ExchangedToken, Input, Output, Request, TokenExchange, TOKEN_EXCHANGE_GRANT_TYPE,
+            },
+        },
+        primitives::{
+            grant::Grant,
+            registrar::{ClientUrl, RegistrarError},
+        },
+    };
+
+    pub trait Endpoint {
+        /// Authenticate and bind the client.
+        fn registrar(&self) -> &(dyn crate::primitives::Registrar + Sync);
+
+        /// Return the issuer instance to recover the presented tokens and create the new one.
+        fn issuer(&mut self) -> &mut (dyn crate::primitives::Issuer + Send);
+    }
+
+    pub async fn token_exchange(
+        handler: &mut (dyn Endpoint + Send + Sync), request: &(dyn Request + Sync),
+    ) -> Result<ExchangedToken, Error> {
+        enum Requested {
+            None,
+            Authenticate { client: String, passdata: Option<Vec<u8>> },
+            Recover { token: String },
+            Bind { client_id: String },
+            Issue { grant: Grant },
+        }
+
+        let mut token_exchange = TokenExchange::new(request);
+        let mut requested = Requested::None;
+
+        loop {
+            let input = match requested {
+                Requested::None => Input::None,
+                Requested::Authenticate { client, passdata } => {
+                    handler
+                        .registrar()
+                        .check(&client, passdata.as_deref())
+                        .await
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => Error::unauthorized("basic"),
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        })?;
+                    handler
+                        .registrar()
+                        .check_grant_type(&client, TOKEN_EXCHANGE_GRANT_TYPE)
+                        .await
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => {
+                                Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
+                            }
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        })?;
+                    Input::Authenticated
+                }
+                Requested::Recover { token } => {
+                    let grant = handler
+                        .issuer()
+                        .recover_token(&token)
+                        .await
+                        .map_err(|()| Error::primitive(None))?;
+                    Input::Recovered(grant)
+                }
+                Requested::Bind { client_id } => {
+                    let client_url = ClientUrl {
+                        client_id: Cow::Owned(client_id),
+                        redirect_uri: None,
+                    };
+                    let bound_client = handler
+                        .registrar()
+                        .bound_redirect(client_url)
+                        .await
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => {
+                                Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
+                            }
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        },
+                    )?;
+                    Input::Bound { bound_client }
+                }
+                Requested::Issue { grant } => {
+                    let token = handler
+                        .issuer()
+                        .issue(grant.clone())
+                        .await
+                        .map_err(|_| Error::primitive(Some(grant)))?;
+                    Input::Issued(token)
+                }
+            };
+
+            requested = match token_exchange.advance(input) {
+                Output::Authenticate { client, passdata } => Requested::Authenticate {
+                    client: client.to_string(),
+                    passdata: passdata.map(Vec::from),
+                },
+                Output::RecoverSubject { token } | Output::RecoverActor { token } => {
+                    Requested::Recover {
+                        token: token.to_string(),
+                    }
+                }
+                Output::Binding { client_id } => Requested::Bind {
+                    client_id: client_id.to_string(),
+                },
+                Output::Issue { grant } => Requested::Issue { grant: grant.clone() },
+                Output::Ok(token) => return Ok(token),
+                Output::Err(e) => return Err(*e),
+            };
+        }
+    }
+}
+
+pub mod revoke {
+    use oxide_auth::{
+        code_grant::{
+            accesstoken::Error,
+            error::AccessTokenErrorType,
+            revoke::{Input, Output, Request, Revoke, TokenTypeHint},
+        },
+        primitives::registrar::RegistrarError,
+    };
+
+    pub trait Endpoint {
+        /// Authenticate the client.
+        fn registrar(&self) -> &(dyn crate::primitives::Registrar + Sync);
+
+        /// Return the issuer instance to recover and revoke the token.
+        fn issuer(&mut self) -> &mut (dyn crate::primitives::Issuer + Send);
+    }
+
+    pub async fn revoke(
+        handler: &mut (dyn Endpoint + Send + Sync), request: &(dyn Request + Sync),
+    ) -> Result<(), Error> {
+        enum Requested {
+            None,
+            Authenticate { client: String, passdata: Option<Vec<u8>> },
+            Recover { token: String, kind: TokenTypeHint },
+            Revoke { token: String, kind: TokenTypeHint },
+        }
+
+        let mut revoke = Revoke::new(request);
+        let mut requested = Requested::None;
+
+        loop {
+            let input = match requested {
+                Requested::None => Input::None,
+                Requested::Authenticate { client, passdata } => {
+                    handler
                         .registrar()
-                        .check(&client, pass.as_deref())
+                        .check(&client, passdata.as_deref())
                         .await
                         .map_err(|err| match err {
-                            RegistrarError::PrimitiveError => Error::Primitive,
                             RegistrarError::Unspecified => Error::unauthorized("basic"),
+                            RegistrarError::PrimitiveError => Error::primitive(None),
                         })?;
-                    Input::Authenticated {
-                        scope: request.scope(),
+                    Input::Authenticated
+                }
+                Requested::Recover { token, kind } => {
+                    let issuer = handler.issuer();
+                    let grant = match kind {
+                        TokenTypeHint::AccessToken => issuer.recover_token(&token).await,
+                        TokenTypeHint::RefreshToken => issuer.recover_refresh(&token).await,
                     }
+                    .map_err(|()| Error::primitive(None))?;
+                    Input::Recovered(grant)
+                }
+                Requested::Revoke { token, kind } => {
+                    let issuer = handler.issuer();
+                    match kind {
+                        TokenTypeHint::AccessToken => issuer.revoke_token(&token).await,
+                        TokenTypeHint::RefreshToken => issuer.revoke_refresh(&token).await,
+                    }
+                    .map_err(|()| Error::invalid_with(AccessTokenErrorType::UnsupportedTokenType))?;
+                    Input::Revoked
                 }
             };
 
-            requested = match refresh.advance(input) {
-                Output::Err(error) => return Err(error),
-                Output::Ok(token) => return Ok(token),
-                Output::Refresh { token, grant } => Requested::Refresh {
-                    token: token.to_string(),
-                    grant,
+            requested = match revoke.advance(input) {
+                Output::Authenticate { client, passdata } => Requested::Authenticate {
+                    client: client.to_string(),
+                    passdata: passdata.map(Vec::from),
                 },
-                Output::RecoverRefresh { token } => Requested::RecoverRefresh {
+                Output::Recover { token, kind } => Requested::Recover {
                     token: token.to_string(),
+                    kind,
                 },
-                Output::Unauthenticated { client, pass } => Requested::Authenticate {
-                    client: client.to_string(),
-                    pass: pass.map(|p| p.to_vec()),
+                Output::Revoke { token, kind } => Requested::Revoke {
+                    token: token.to_string(),
+                    kind,
                 },
+                Output::Ok => return Ok(()),
+                Output::Err(e) => return Err(*e),
             };
         }
     }
 }
 
-pub mod resource {
-    use oxide_auth::code_grant::resource::{Error, Input, Output, Request, Resource};
-    use oxide_auth::primitives::grant::Grant;
-    use oxide_auth::primitives::scope::Scope;
+pub mod introspection {
+    use oxide_auth::{
+        code_grant::{
+            accesstoken::Error,
+            introspection::{Input, Introspected, Introspection, Output, Request},
+            revoke::TokenTypeHint,
+        },
+        primitives::{registrar::RegistrarError, scope::Scope},
+    };
 
     pub trait Endpoint {
-        /// The list of possible scopes required by the resource endpoint.
-        fn scopes(&mut self) -> &[Scope];
+        /// Authenticate callers presenting client credentials.
+        fn registrar(&self) -> &(dyn crate::primitives::Registrar + Sync);
 
-        /// Recover and test the provided refresh token then issue new tokens.
+        /// Return the issuer instance to recover the tokens.
         fn issuer(&mut self) -> &mut (dyn crate::primitives::Issuer + Send);
+
+        /// The list of possible scopes of which a bearer token of the caller must fulfill one.
+        fn scopes(&mut self) -> &[Scope];
     }
 
-    pub async fn protect(
-        handler: &mut (dyn Endpoint + Send + Sync), req: &(dyn Request + Sync),
-    ) -> Result<Grant, Error> {
+    pub async fn introspect(
+        handler: &mut (dyn Endpoint + Send + Sync), request: &(dyn Request + Sync),
+    ) -> Result<Introspected, Error> {
         enum Requested {
             None,
-            Request,
+            Authenticate { client: String, passdata: Option<Vec<u8>> },
+            Recover { token: String, kind: TokenTypeHint },
             Scopes,
-            Grant(String),
         }
 
-        let mut resource = Resource::new();
+        let mut introspection = Introspection::new(request);
         let mut requested = Requested::None;
+
         loop {
             let input = match requested {
                 Requested::None => Input::None,
-                Requested::Request => Input::Request { request: req },
-                Requested::Scopes => Input::Scopes(handler.scopes()),
-                Requested::Grant(token) => {
-                    let grant = handler
-                        .issuer()
-                        .recover_token(&token)
+                Requested::Authenticate { client, passdata } => {
+                    handler
+                        .registrar()
+                        .check(&client, passdata.as_deref())
                         .await
-                        .map_err(|_| Error::PrimitiveError)?;
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => Error::unauthorized("basic"),
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        })?;
+                    Input::Authenticated
+                }
+                Requested::Recover { token, kind } => {
+                    let issuer = handler.issuer();
+                    let grant = match kind {
+                        TokenTypeHint::AccessToken => issuer.recover_token(&token).await,
+                        TokenTypeHint::RefreshToken => issuer.recover_refresh(&token).await,
+                    }
+                    .map_err(|()| Error::primitive(None))?;
                     Input::Recovered(grant)
                 }
+                Requested::Scopes => Input::Scopes(handler.scopes()),
             };
 
-            requested = match resource.advance(input) {
-                Output::Err(error) => return Err(error),
-                Output::Ok(grant) => return Ok(*grant),
-                Output::GetRequest => Requested::Request,
+            requested = match introspection.advance(input) {
+                Output::Authenticate { client, passdata } => Requested::Authenticate {
+                    client: client.to_string(),
+                    passdata: passdata.map(Vec::from),
+                },
+                Output::RecoverCaller { token } => Requested::Recover {
+                    token: token.to_string(),
+                    kind: TokenTypeHint::AccessToken,
+                },
                 Output::DetermineScopes => Requested::Scopes,
-                Output::Recover { token } => Requested::Grant(token.to_string()),
+                Output::Recover { token, kind } => Requested::Recover {
+                    token: token.to_string(),
+                    kind,
+                },
+                Output::Ok(introspected) => return Ok(introspected),
+                Output::Err(err) => return Err(*err),
             };
         }
     }
 }
 
-pub mod access_token {
-    use async_trait::async_trait;
+pub mod device {
+    use std::borrow::Cow;
+
     use oxide_auth::{
-        code_grant::accesstoken::{
-            AccessToken, BearerToken, Error, Input, Output, PrimitiveError, Request as TokenRequest,
+        code_grant::{
+            accesstoken::{BearerToken, Error},
+            device::{
+                DeviceAuthorization, DeviceToken, Input, IssuedCodes, Output, Request, TokenInput,
+                TokenOutput, TokenRequest, VerificationError, VerificationRequest,
+                DEVICE_CODE_GRANT_TYPE,
+            },
+            error::AccessTokenErrorType,
         },
+        endpoint::Solicitation,
         primitives::{
-            grant::{Extensions, Grant},
-            registrar::RegistrarError,
+            grant::Grant,
+            registrar::{BoundClient, ClientMetadata, ClientUrl, PreGrant, RegistrarError},
+            scope::Scope,
         },
     };
-    // use crate::endpoint::access_token::WrappedRequest;
 
-    #[async_trait]
-    pub trait Extension {
-        /// Inspect the request and extension data to produce extension data.
-        ///
-        /// The input data comes from the extension data produced in the handling of the
-        /// authorization code request.
-        async fn extend(
-            &mut self, request: &(dyn TokenRequest + Sync), data: Extensions,
-        ) -> std::result::Result<Extensions, ()>;
+    pub trait Endpoint {
+        /// Authenticate the client and negotiate the scope of the grant.
+        fn registrar(&self) -> &(dyn crate::primitives::Registrar + Sync);
+
+        /// Return the device authorizer storing the pending grant.
+        fn device_authorizer(&mut self) -> &mut (dyn crate::primitives::DeviceAuthorizer + Send);
     }
 
-    #[async_trait]
-    impl Extension for () {
-        async fn extend(
-            &mut self, _: &(dyn TokenRequest + Sync), _: Extensions,
-        ) -> std::result::Result<Extensions, ()> {
-            Ok(Extensions::new())
-        }
+    pub trait VerificationEndpoint {
+        /// Return the device authorizer storing the pending grants.
+        fn device_authorizer(&mut self) -> &mut (dyn crate::primitives::DeviceAuthorizer + Send);
     }
 
-    pub trait Endpoint {
-        /// Get the client corresponding to some id.
+    pub trait TokenEndpoint {
+        /// Authenticate the polling client.
         fn registrar(&self) -> &(dyn crate::primitives::Registrar + Sync);
 
-        /// Get the authorizer from which we can recover the authorization.
-        fn authorizer(&mut self) -> &mut (dyn crate::primitives::Authorizer + Send);
+        /// Return the device authorizer storing the pending grants.
+        fn device_authorizer(&mut self) -> &mut (dyn crate::primitives::DeviceAuthorizer + Send);
 
         /// Return the issuer instance to create the access token.
         fn issuer(&mut self) -> &mut (dyn crate::primitives::Issuer + Send);
-
-        /// The system of used extension, extending responses.
-        ///
-        /// It is possible to use `&mut ()`.
-        fn extension(&mut self) -> &mut (dyn Extension + Send);
     }
 
-    pub async fn access_token(
-        handler: &mut (dyn Endpoint + Send + Sync), request: &(dyn TokenRequest + Sync),
-    ) -> Result<BearerToken, Error> {
-        enum Requested<'a> {
+    pub async fn device_authorization(
+        handler: &mut (dyn Endpoint + Send + Sync), request: &(dyn Request + Sync),
+    ) -> Result<IssuedCodes, Error> {
+        enum Requested {
             None,
-            Authenticate {
-                client: &'a str,
-                passdata: Option<&'a [u8]>,
-            },
-            Recover(&'a str),
-            Extend {
-                extensions: &'a mut Extensions,
-            },
-            Issue {
-                grant: &'a Grant,
-            },
+            Authenticate { client: String, passdata: Option<Vec<u8>> },
+            Bind { client_id: String },
+            Negotiate { bound_client: BoundClient<'static>, scope: Option<Scope> },
+            Authorize { grant: Grant },
         }
 
-        let mut access_token = AccessToken::new(request);
+        let mut authorization = DeviceAuthorization::new(request);
         let mut requested = Requested::None;
 
         loop {
@@ -204,58 +1087,217 @@ pub mod access_token {
                 Requested::Authenticate { client, passdata } => {
                     handler
                         .registrar()
-                        .check(client, passdata)
+                        .check(&client, passdata.as_deref())
                         .await
                         .map_err(|err| match err {
                             RegistrarError::Unspecified => Error::unauthorized("basic"),
-                            RegistrarError::PrimitiveError => {
-                                Error::Primitive(Box::new(PrimitiveError {
-                                    grant: None,
-                                    extensions: None,
-                                }))
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        })?;
+                    handler
+                        .registrar()
+                        .check_grant_type(&client, DEVICE_CODE_GRANT_TYPE)
+                        .await
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => {
+                                Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
                             }
+                            RegistrarError::PrimitiveError => Error::primitive(None),
                         })?;
                     Input::Authenticated
                 }
-                Requested::Recover(code) => {
-                    let opt_grant = handler.authorizer().extract(code).await.map_err(|_| {
-                        Error::Primitive(Box::new(PrimitiveError {
-                            grant: None,
-                            extensions: None,
-                        }))
-                    })?;
-                    Input::Recovered(opt_grant.map(Box::new))
+                Requested::Bind { client_id } => {
+                    let client_url = ClientUrl {
+                        client_id: Cow::Owned(client_id),
+                        redirect_uri: None,
+                    };
+                    let bound_client = handler
+                        .registrar()
+                        .bound_redirect(client_url)
+                        .await
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => {
+                                Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
+                            }
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        },
+                    )?;
+                    Input::Bound { bound_client }
                 }
-                Requested::Extend { extensions } => {
-                    let access_extensions = handler
-                        .extension()
-                        .extend(request, extensions.clone())
+                Requested::Negotiate { bound_client, scope } => {
+                    let pre_grant = handler
+                        .registrar()
+                        .negotiate(bound_client, scope)
                         .await
-                        .map_err(|_| Error::invalid())?;
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => {
+                                Error::invalid_with(AccessTokenErrorType::InvalidScope)
+                            }
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        },
+                    )?;
+                    Input::Negotiated { pre_grant }
+                }
+                Requested::Authorize { grant } => {
+                    let codes = handler
+                        .device_authorizer()
+                        .authorize(grant.clone())
+                        .await
+                        .map_err(|()| Error::primitive(Some(grant)))?;
+                    Input::Authorized(codes)
+                }
+            };
 
-                    Input::Extended { access_extensions }
+            requested = match authorization.advance(input) {
+                Output::Authenticate { client, passdata } => Requested::Authenticate {
+                    client: client.to_string(),
+                    passdata: passdata.map(Vec::from),
+                },
+                Output::Binding { client_id } => Requested::Bind {
+                    client_id: client_id.to_string(),
+                },
+                Output::Negotiate { bound_client, scope } => Requested::Negotiate {
+                    bound_client: bound_client.clone(),
+                    scope,
+                },
+                Output::Authorize { grant } => Requested::Authorize { grant: grant.clone() },
+                Output::Ok(codes) => return Ok(codes),
+                Output::Err(e) => return Err(*e),
+            };
+        }
+    }
+
+    /// A device authorization waiting for the decision of a resource owner.
+    pub struct PendingDevice {
+        user_code: String,
+        pre_grant: PreGrant,
+    }
+
+    pub async fn device_verification(
+        handler: &mut (dyn VerificationEndpoint + Send + Sync), request: &(dyn VerificationRequest + Sync),
+    ) -> Result<PendingDevice, VerificationError> {
+        if !request.valid() {
+            return Err(VerificationError::Invalid);
+        }
+
+        let user_code = request.user_code().ok_or(VerificationError::Invalid)?;
+        let grant = handler
+            .device_authorizer()
+            .pending(&user_code)
+            .await
+            .map_err(|()| VerificationError::PrimitiveError)?
+            .ok_or(VerificationError::Unknown)?;
+
+        Ok(PendingDevice {
+            user_code: user_code.into_owned(),
+            pre_grant: PreGrant {
+                client_id: grant.client_id,
+                redirect_uri: grant.redirect_uri.into(),
+                scope: grant.scope,
+                metadata: ClientMetadata::default(),
+            },
+        })
+    }
+
+    impl PendingDevice {
+        /// Reference this pending state as a solicitation.
+        pub fn as_solicitation(&self) -> Solicitation<'_> {
+            Solicitation::new(&self.pre_grant)
+        }
+
+        /// Retrieve a reference to the negotiated parameters (e.g. scope).
+        pub fn pre_grant(&self) -> &PreGrant {
+            &self.pre_grant
+        }
+
+        /// Inform the backend about consent from a resource owner.
+        pub async fn authorize(
+            self, handler: &mut (dyn VerificationEndpoint + Send + Sync), owner_id: Cow<'_, str>,
+        ) -> Result<(), VerificationError> {
+            handler
+                .device_authorizer()
+                .approve(&self.user_code, &owner_id)
+                .await
+                .map_err(|()| VerificationError::PrimitiveError)
+        }
+
+        /// Denies the request, the device will be told so when it next polls the token endpoint.
+        pub async fn deny(
+            self, handler: &mut (dyn VerificationEndpoint + Send + Sync),
+        ) -> Result<(), VerificationError> {
+            handler
+                .device_authorizer()
+                .deny(&self.user_code)
+                .await
+                .map_err(|()| VerificationError::PrimitiveError)
+        }
+    }
+
+    pub async fn device_token(
+        handler: &mut (dyn TokenEndpoint + Send + Sync), request: &(dyn TokenRequest + Sync),
+    ) -> Result<BearerToken, Error> {
+        enum Requested {
+            None,
+            Authenticate { client: String, passdata: Option<Vec<u8>> },
+            Poll { device_code: String },
+            Issue { grant: Grant },
+        }
+
+        let mut device_token = DeviceToken::new(request);
+        let mut requested = Requested::None;
+
+        loop {
+            let input = match requested {
+                Requested::None => TokenInput::None,
+                Requested::Authenticate { client, passdata } => {
+                    handler
+                        .registrar()
+                        .check(&client, passdata.as_deref())
+                        .await
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => Error::unauthorized("basic"),
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        })?;
+                    handler
+                        .registrar()
+                        .check_grant_type(&client, DEVICE_CODE_GRANT_TYPE)
+                        .await
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => {
+                                Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
+                            }
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        })?;
+                    TokenInput::Authenticated
+                }
+                Requested::Poll { device_code } => {
+                    let status = handler
+                        .device_authorizer()
+                        .poll(&device_code)
+                        .await
+                        .map_err(|()| Error::primitive(None))?;
+                    TokenInput::Polled(status)
                 }
                 Requested::Issue { grant } => {
-                    let token = handler.issuer().issue(grant.clone()).await.map_err(|_| {
-                        Error::Primitive(Box::new(PrimitiveError {
-                            // FIXME: endpoint should get and handle these.
-                            grant: None,
-                            extensions: None,
-                        }))
-                    })?;
-                    Input::Issued(token)
+                    let token = handler
+                        .issuer()
+                        .issue(grant.clone())
+                        .await
+                        .map_err(|_| Error::primitive(Some(grant)))?;
+                    TokenInput::Issued(token)
                 }
             };
 
-            requested = match access_token.advance(input) {
-                Output::Authenticate { client, passdata } => {
-                    Requested::Authenticate { client, passdata }
-                }
-                Output::Recover { code } => Requested::Recover(code),
-                Output::Extend { extensions, .. } => Requested::Extend { extensions },
-                Output::Issue { grant } => Requested::Issue { grant },
-                Output::Ok(token) => return Ok(token),
-                Output::Err(e) => return Err(*e),
+            requested = match device_token.advance(input) {
+                TokenOutput::Authenticate { client, passdata } => Requested::Authenticate {
+                    client: client.to_string(),
+                    passdata: passdata.map(Vec::from),
+                },
+                TokenOutput::Poll { device_code } => Requested::Poll {
+                    device_code: device_code.to_string(),
+                },
+                TokenOutput::Issue { grant } => Requested::Issue { grant: grant.clone() },
+                TokenOutput::Ok(token) => return Ok(token),
+                TokenOutput::Err(e) => return Err(*e),
             };
         }
     }
@@ -451,6 +1493,21 @@ pub mod authorization {
                     redirect_uri,
                     scope,
                 } => {
+                    handler
+                        .registrar()
+                        .check_response_type(&client_id, "code")
+                        .await
+                        .map_err(|err| match err {
+                            RegistrarError::PrimitiveError => Error::PrimitiveError,
+                            RegistrarError::Unspecified => {
+                                let prepared_error = ErrorUrl::with_request(
+                                    request,
+                                    redirect_uri.clone(),
+                                    AuthorizationErrorType::UnauthorizedClient,
+                                );
+                                Error::Redirect(prepared_error)
+                            }
+                        })?;
                     let bound_client = BoundClient {
                         client_id: Cow::Owned(client_id),
                         redirect_uri: Cow::Owned(redirect_uri.clone().into()),
@@ -505,3 +1562,14 @@ pub mod authorization {
         }
     }
 }
+
+/// Authenticate a client with its passdata or, if it has none, the certificate of the connection.
+async fn authenticate(
+    registrar: &(dyn crate::primitives::Registrar + Sync), client: &str, passdata: Option<&[u8]>,
+    certificate: Option<&oxide_auth::primitives::mtls::ClientCertificate>,
+) -> Result<(), oxide_auth::primitives::registrar::RegistrarError> {
+    match (passdata, certificate) {
+        (None, Some(certificate)) => registrar.check_certificate(client, certificate).await,
+        (passdata, _) => registrar.check(client, passdata).await,
+    }
+}
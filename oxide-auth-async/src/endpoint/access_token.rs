@@ -2,11 +2,14 @@ use std::str::from_utf8;
 use std::{borrow::Cow, marker::PhantomData};
 
 use oxide_auth::{
-    endpoint::{QueryParameter, WebRequest, OAuthError, WebResponse, Template, NormalizedParameter},
+    endpoint::{
+        ClientCertificate, QueryParameter, WebRequest, OAuthError, WebResponse, Template,
+        NormalizedParameter,
+    },
     code_grant::accesstoken::{Error as TokenError, Request as TokenRequest},
 };
 
-use super::Endpoint;
+use super::{dpop_proof, DpopVerifier, Endpoint};
 use crate::{
     code_grant::access_token::{Extension, Endpoint as TokenEndpoint, access_token},
     primitives::{Issuer, Registrar, Authorizer},
@@ -56,6 +59,12 @@ pub struct WrappedRequest<R: WebRequest> {
 
     /// The credentials-in-body flag from the flow.
     allow_credentials_in_body: bool,
+
+    /// The thumbprint of the key proven by a DPoP proof, an error if the proof was invalid.
+    dpop: Result<Option<String>, ()>,
+
+    /// The client certificate of the connection.
+    certificate: Option<ClientCertificate>,
 }
 
 struct Invalid;
@@ -126,11 +135,9 @@ where
     /// When the registrar, authorizer, or issuer returned by the endpoint is suddenly
     /// `None` when previously it was `Some(_)`.
     pub async fn execute(&mut self, mut request: R) -> Result<R::Response, E::Error> {
-        let issued = access_token(
-            &mut self.endpoint,
-            &WrappedRequest::new(&mut request, self.allow_credentials_in_body),
-        )
-        .await;
+        let mut wrapped = WrappedRequest::new(&mut request, self.allow_credentials_in_body);
+        wrapped.verify_dpop(&mut request, self.endpoint.inner.dpop_verifier());
+        let issued = access_token(&mut self.endpoint, &wrapped).await;
 
         let token = match issued {
             Err(error) => return token_error(&mut self.endpoint.inner, &mut request, error),
@@ -145,7 +152,7 @@ where
     }
 }
 
-fn token_error<E, R>(
+pub(crate) fn token_error<E, R>(
     endpoint: &mut E, request: &mut R, error: TokenError,
 ) -> Result<R::Response, E::Error>
 where
@@ -220,20 +227,47 @@ impl<R: WebRequest> WrappedRequest<R> {
             Ok(None) => None,
         };
 
+        let certificate = request
+            .certificate()
+            .map_err(FailParse::Err)?
+            .map(Cow::into_owned);
+
         Ok(WrappedRequest {
             body: request.urlbody().map_err(FailParse::Err)?.into_owned(),
             authorization,
             error: None,
             allow_credentials_in_body: credentials,
+            dpop: Ok(None),
+            certificate,
         })
     }
 
+    /// Check the DPoP proof of the request with the verifier of the endpoint.
+    ///
+    /// The issued token is bound to the proven key. Requests without a proof are unaffected.
+    pub fn verify_dpop(&mut self, request: &mut R, verifier: Option<&(dyn DpopVerifier + Sync)>) {
+        if self.error.is_some() {
+            return;
+        }
+
+        match dpop_proof(
+            verifier.map(|verifier| verifier as &dyn DpopVerifier),
+            request,
+            None,
+        ) {
+            Ok(thumbprint) => self.dpop = thumbprint,
+            Err(err) => self.error = Some(FailParse::Err(err)),
+        }
+    }
+
     fn from_err(err: FailParse<R::Error>) -> Self {
         WrappedRequest {
             body: Default::default(),
             authorization: None,
             error: Some(err),
             allow_credentials_in_body: false,
+            dpop: Ok(None),
+            certificate: None,
         }
     }
 
@@ -304,6 +338,14 @@ impl<R: WebRequest> TokenRequest for WrappedRequest<R> {
     fn allow_credentials_in_body(&self) -> bool {
         self.allow_credentials_in_body
     }
+
+    fn dpop_thumbprint(&self) -> Result<Option<Cow<'_, str>>, ()> {
+        self.dpop.clone().map(|thumbprint| thumbprint.map(Cow::Owned))
+    }
+
+    fn certificate(&self) -> Option<Cow<'_, ClientCertificate>> {
+        self.certificate.as_ref().map(Cow::Borrowed)
+    }
 }
 
 impl<E> From<Invalid> for FailParse<E> {
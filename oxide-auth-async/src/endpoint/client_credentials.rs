@@ -0,0 +1,276 @@
+use std::str::from_utf8;
+use std::{borrow::Cow, marker::PhantomData};
+
+use oxide_auth::{
+    endpoint::{
+        ClientCertificate, QueryParameter, WebRequest, OAuthError, WebResponse, Template,
+        NormalizedParameter,
+    },
+    code_grant::client_credentials::Request as ClientCredentialsRequest,
+};
+
+use super::Endpoint;
+use super::access_token::token_error;
+use crate::{
+    code_grant::client_credentials::{client_credentials, Endpoint as ClientCredentialsEndpoint},
+    primitives::{Issuer, Registrar},
+};
+
+/// Offers access tokens to confidential clients acting on their own behalf.
+///
+/// The client authenticates itself directly at the OAuth endpoint and receives an access token
+/// for the scope negotiated with the registrar. No resource owner is involved and no refresh
+/// token is returned. This request MUST be protected by TLS.
+///
+/// Client credentials can be allowed to appear in the request body instead of being
+/// required to be passed as HTTP Basic authorization. This is not recommended and must be
+/// enabled explicitely. See [`allow_credentials_in_body`] for details.
+///
+/// [`allow_credentials_in_body`]: #method.allow_credentials_in_body
+pub struct ClientCredentialsFlow<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    endpoint: WrappedClientCredentials<E, R>,
+    allow_credentials_in_body: bool,
+}
+
+struct WrappedClientCredentials<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    inner: E,
+    r_type: PhantomData<R>,
+}
+
+#[derive(Clone)]
+pub struct WrappedRequest<R: WebRequest> {
+    /// The query in the body.
+    body: NormalizedParameter,
+
+    /// The authorization tuple
+    authorization: Option<Authorization>,
+
+    /// An error if one occurred.
+    error: Option<FailParse<R::Error>>,
+
+    /// The credentials-in-body flag from the flow.
+    allow_credentials_in_body: bool,
+
+    /// The client certificate of the connection.
+    certificate: Option<ClientCertificate>,
+}
+
+struct Invalid;
+
+#[derive(Clone)]
+enum FailParse<E> {
+    Invalid,
+    Err(E),
+}
+
+#[derive(Clone)]
+struct Authorization(String, Vec<u8>);
+
+impl<E, R> ClientCredentialsFlow<E, R>
+where
+    E: Endpoint<R> + Send + Sync,
+    R: WebRequest + Send + Sync,
+    <R as WebRequest>::Error: Send + Sync,
+{
+    /// Check that the endpoint supports the necessary operations for handling requests.
+    ///
+    /// Binds the endpoint to a particular type of request that it supports, for many
+    /// implementations this is probably single type anyways. The endpoint needs to provide
+    /// (return `Some`):
+    ///
+    /// * a `Registrar` from `registrar`
+    /// * an `Issuer` from `issuer_mut`
+    ///
+    /// ## Panics
+    ///
+    /// Indirectly `execute` may panic when this flow is instantiated with an inconsistent
+    /// endpoint, for details see the documentation of `Endpoint` and `execute`. For
+    /// consistent endpoints, the panic is instead caught as an error here.
+    pub fn prepare(mut endpoint: E) -> Result<Self, E::Error> {
+        if endpoint.registrar().is_none() {
+            return Err(endpoint.error(OAuthError::PrimitiveError));
+        }
+
+        if endpoint.issuer_mut().is_none() {
+            return Err(endpoint.error(OAuthError::PrimitiveError));
+        }
+
+        Ok(ClientCredentialsFlow {
+            endpoint: WrappedClientCredentials {
+                inner: endpoint,
+                r_type: PhantomData,
+            },
+            allow_credentials_in_body: false,
+        })
+    }
+
+    /// Credentials in body should only be enabled if use of HTTP Basic is not possible.
+    ///
+    /// Allows the request body to contain the `client_secret` as a form parameter. This is NOT
+    /// RECOMMENDED and need not be supported. The parameters MUST NOT appear in the request URI
+    /// itself.
+    ///
+    /// Thus support is disabled by default and must be explicitely enabled.
+    pub fn allow_credentials_in_body(&mut self, allow: bool) {
+        self.allow_credentials_in_body = allow;
+    }
+
+    /// Use the checked endpoint to issue a token to the authenticated client.
+    ///
+    /// ## Panics
+    ///
+    /// When the registrar or issuer returned by the endpoint is suddenly `None` when previously it
+    /// was `Some(_)`.
+    pub async fn execute(&mut self, mut request: R) -> Result<R::Response, E::Error> {
+        let issued = client_credentials(
+            &mut self.endpoint,
+            &WrappedRequest::new(&mut request, self.allow_credentials_in_body),
+        )
+        .await;
+
+        let token = match issued {
+            Err(error) => return token_error(&mut self.endpoint.inner, &mut request, error),
+            Ok(token) => token,
+        };
+
+        let mut response = self.endpoint.inner.response(&mut request, Template::new_ok())?;
+        response
+            .body_json(&token.to_json())
+            .map_err(|err| self.endpoint.inner.web_error(err))?;
+        Ok(response)
+    }
+}
+
+impl<E, R> ClientCredentialsEndpoint for WrappedClientCredentials<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    fn registrar(&self) -> &(dyn Registrar + Sync) {
+        self.inner.registrar().unwrap()
+    }
+
+    fn issuer(&mut self) -> &mut (dyn Issuer + Send) {
+        self.inner.issuer_mut().unwrap()
+    }
+}
+
+impl<R: WebRequest> WrappedRequest<R> {
+    pub fn new(request: &mut R, credentials: bool) -> Self {
+        Self::new_or_fail(request, credentials).unwrap_or_else(Self::from_err)
+    }
+
+    fn new_or_fail(request: &mut R, credentials: bool) -> Result<Self, FailParse<R::Error>> {
+        // If there is a header, it must parse correctly.
+        let authorization = match request.authheader() {
+            Err(err) => return Err(FailParse::Err(err)),
+            Ok(Some(header)) => Self::parse_header(header).map(Some)?,
+            Ok(None) => None,
+        };
+
+        let certificate = request
+            .certificate()
+            .map_err(FailParse::Err)?
+            .map(Cow::into_owned);
+
+        Ok(WrappedRequest {
+            body: request.urlbody().map_err(FailParse::Err)?.into_owned(),
+            authorization,
+            error: None,
+            allow_credentials_in_body: credentials,
+            certificate,
+        })
+    }
+
+    fn from_err(err: FailParse<R::Error>) -> Self {
+        WrappedRequest {
+            body: Default::default(),
+            authorization: None,
+            error: Some(err),
+            allow_credentials_in_body: false,
+            certificate: None,
+        }
+    }
+
+    fn parse_header(header: Cow<str>) -> Result<Authorization, Invalid> {
+        let authorization = {
+            if !header.starts_with("Basic ") {
+                return Err(Invalid);
+            }
+
+            let combined = match base64::decode(&header[6..]) {
+                Err(_) => return Err(Invalid),
+                Ok(vec) => vec,
+            };
+
+            let mut split = combined.splitn(2, |&c| c == b':');
+            let client_bin = match split.next() {
+                None => return Err(Invalid),
+                Some(client) => client,
+            };
+            let passwd = match split.next() {
+                None => return Err(Invalid),
+                Some(passwd64) => passwd64,
+            };
+
+            let client = match from_utf8(client_bin) {
+                Err(_) => return Err(Invalid),
+                Ok(client) => client,
+            };
+
+            Authorization(client.to_string(), passwd.to_vec())
+        };
+
+        Ok(authorization)
+    }
+}
+
+impl<R: WebRequest> ClientCredentialsRequest for WrappedRequest<R> {
+    fn valid(&self) -> bool {
+        self.error.is_none()
+    }
+
+    fn authorization(&self) -> Option<(Cow<'_, str>, Cow<'_, [u8]>)> {
+        self.authorization
+            .as_ref()
+            .map(|auth| (auth.0.as_str().into(), auth.1.as_slice().into()))
+    }
+
+    fn client_id(&self) -> Option<Cow<'_, str>> {
+        self.body.unique_value("client_id")
+    }
+
+    fn scope(&self) -> Option<Cow<'_, str>> {
+        self.body.unique_value("scope")
+    }
+
+    fn grant_type(&self) -> Option<Cow<'_, str>> {
+        self.body.unique_value("grant_type")
+    }
+
+    fn extension(&self, key: &str) -> Option<Cow<'_, str>> {
+        self.body.unique_value(key)
+    }
+
+    fn allow_credentials_in_body(&self) -> bool {
+        self.allow_credentials_in_body
+    }
+
+    fn certificate(&self) -> Option<Cow<'_, ClientCertificate>> {
+        self.certificate.as_ref().map(Cow::Borrowed)
+    }
+}
+
+impl<E> From<Invalid> for FailParse<E> {
+    fn from(_: Invalid) -> Self {
+        FailParse::Invalid
+    }
+}
@@ -0,0 +1,573 @@
+use std::str::from_utf8;
+use std::{borrow::Cow, marker::PhantomData};
+
+use oxide_auth::{
+    endpoint::{QueryParameter, WebRequest, OAuthError, WebResponse, Template, NormalizedParameter},
+    code_grant::device::{
+        Request as DeviceRequest, TokenRequest as DeviceTokenRequest, VerificationError,
+        VerificationRequest as DeviceVerificationRequest,
+    },
+    endpoint::OwnerConsent,
+};
+use url::Url;
+
+use super::Endpoint;
+use super::access_token::token_error;
+use crate::{
+    code_grant::device::{
+        device_authorization, device_token, device_verification, Endpoint as DeviceEndpoint,
+        TokenEndpoint as DeviceTokenEndpoint, VerificationEndpoint as DeviceVerificationEndpoint,
+    },
+    primitives::{DeviceAuthorizer, Issuer, Registrar},
+};
+
+/// Offers device and user codes to devices with limited input capabilities.
+///
+/// The device authenticates itself as a client and receives a device code with which it polls the
+/// token endpoint, see [`DeviceTokenFlow`], and a user code that the resource owner enters at the
+/// verification uri, see [`DeviceVerificationFlow`]. This request MUST be protected by TLS.
+///
+/// Client credentials can be allowed to appear in the request body instead of being
+/// required to be passed as HTTP Basic authorization. This is not recommended and must be
+/// enabled explicitely. See [`allow_credentials_in_body`] for details.
+///
+/// [`DeviceTokenFlow`]: struct.DeviceTokenFlow.html
+/// [`DeviceVerificationFlow`]: struct.DeviceVerificationFlow.html
+/// [`allow_credentials_in_body`]: #method.allow_credentials_in_body
+pub struct DeviceAuthorizationFlow<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    endpoint: WrappedDevice<E, R>,
+    verification_uri: Url,
+    allow_credentials_in_body: bool,
+}
+
+/// Lets a resource owner approve or deny a device through its user code.
+///
+/// The user code is taken from the `user_code` query parameter. Consent is checked with the
+/// `OwnerSolicitor` of the endpoint, exactly as for the authorization code flow.
+pub struct DeviceVerificationFlow<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    endpoint: WrappedDevice<E, R>,
+}
+
+/// Offers access tokens to devices polling with their device code.
+///
+/// While the resource owner has not yet decided, the device receives an `authorization_pending`
+/// error or a `slow_down` error when polling too fast.
+pub struct DeviceTokenFlow<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    endpoint: WrappedDevice<E, R>,
+    allow_credentials_in_body: bool,
+}
+
+struct WrappedDevice<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    inner: E,
+    r_type: PhantomData<R>,
+}
+
+#[derive(Clone)]
+pub struct WrappedRequest<R: WebRequest> {
+    /// The query in the body.
+    body: NormalizedParameter,
+
+    /// The authorization tuple
+    authorization: Option<Authorization>,
+
+    /// An error if one occurred.
+    error: Option<FailParse<R::Error>>,
+
+    /// The credentials-in-body flag from the flow.
+    allow_credentials_in_body: bool,
+}
+
+#[derive(Clone)]
+pub struct WrappedVerification<R: WebRequest> {
+    /// The query in the url.
+    query: NormalizedParameter,
+
+    /// An error if one occurred.
+    error: Option<R::Error>,
+}
+
+struct Invalid;
+
+#[derive(Clone)]
+enum FailParse<E> {
+    Invalid,
+    Err(E),
+}
+
+#[derive(Clone)]
+struct Authorization(String, Vec<u8>);
+
+impl<E, R> DeviceAuthorizationFlow<E, R>
+where
+    E: Endpoint<R> + Send + Sync,
+    R: WebRequest + Send + Sync,
+    <R as WebRequest>::Error: Send + Sync,
+{
+    /// Check that the endpoint supports the necessary operations for handling requests.
+    ///
+    /// Binds the endpoint to a particular type of request that it supports, for many
+    /// implementations this is probably single type anyways. The endpoint needs to provide
+    /// (return `Some`):
+    ///
+    /// * a `Registrar` from `registrar`
+    /// * a `DeviceAuthorizer` from `device_authorizer_mut`
+    ///
+    /// The `verification_uri` is the page serving the `DeviceVerificationFlow`.
+    ///
+    /// ## Panics
+    ///
+    /// Indirectly `execute` may panic when this flow is instantiated with an inconsistent
+    /// endpoint, for details see the documentation of `Endpoint` and `execute`. For
+    /// consistent endpoints, the panic is instead caught as an error here.
+    pub fn prepare(mut endpoint: E, verification_uri: Url) -> Result<Self, E::Error> {
+        if endpoint.registrar().is_none() {
+            return Err(endpoint.error(OAuthError::PrimitiveError));
+        }
+
+        if endpoint.device_authorizer_mut().is_none() {
+            return Err(endpoint.error(OAuthError::PrimitiveError));
+        }
+
+        Ok(DeviceAuthorizationFlow {
+            endpoint: WrappedDevice {
+                inner: endpoint,
+                r_type: PhantomData,
+            },
+            verification_uri,
+            allow_credentials_in_body: false,
+        })
+    }
+
+    /// Credentials in body should only be enabled if use of HTTP Basic is not possible.
+    ///
+    /// Allows the request body to contain the `client_secret` as a form parameter. This is NOT
+    /// RECOMMENDED and need not be supported. The parameters MUST NOT appear in the request URI
+    /// itself.
+    ///
+    /// Thus support is disabled by default and must be explicitely enabled.
+    pub fn allow_credentials_in_body(&mut self, allow: bool) {
+        self.allow_credentials_in_body = allow;
+    }
+
+    /// Use the checked endpoint to create device and user codes for the client.
+    ///
+    /// ## Panics
+    ///
+    /// When the registrar or device authorizer returned by the endpoint is suddenly `None` when
+    /// previously it was `Some(_)`.
+    pub async fn execute(&mut self, mut request: R) -> Result<R::Response, E::Error> {
+        let issued = device_authorization(
+            &mut self.endpoint,
+            &WrappedRequest::new(&mut request, self.allow_credentials_in_body),
+        )
+        .await;
+
+        let codes = match issued {
+            Err(error) => return token_error(&mut self.endpoint.inner, &mut request, error),
+            Ok(codes) => codes,
+        };
+
+        let mut response = self.endpoint.inner.response(&mut request, Template::new_ok())?;
+        response
+            .body_json(&codes.to_json(&self.verification_uri))
+            .map_err(|err| self.endpoint.inner.web_error(err))?;
+        Ok(response)
+    }
+}
+
+impl<E, R> DeviceVerificationFlow<E, R>
+where
+    E: Endpoint<R> + Send + Sync,
+    R: WebRequest + Send + Sync,
+    <R as WebRequest>::Error: Send + Sync,
+{
+    /// Check that the endpoint supports the necessary operations for handling requests.
+    ///
+    /// The endpoint needs to provide (return `Some`):
+    ///
+    /// * a `DeviceAuthorizer` from `device_authorizer_mut`
+    /// * an `OwnerSolicitor` from `owner_solicitor`
+    ///
+    /// ## Panics
+    ///
+    /// Indirectly `execute` may panic when this flow is instantiated with an inconsistent
+    /// endpoint, for details see the documentation of `Endpoint` and `execute`. For
+    /// consistent endpoints, the panic is instead caught as an error here.
+    pub fn prepare(mut endpoint: E) -> Result<Self, E::Error> {
+        if endpoint.device_authorizer_mut().is_none() {
+            return Err(endpoint.error(OAuthError::PrimitiveError));
+        }
+
+        if endpoint.owner_solicitor().is_none() {
+            return Err(endpoint.error(OAuthError::PrimitiveError));
+        }
+
+        Ok(DeviceVerificationFlow {
+            endpoint: WrappedDevice {
+                inner: endpoint,
+                r_type: PhantomData,
+            },
+        })
+    }
+
+    /// Use the checked endpoint to let the resource owner decide over a pending device.
+    ///
+    /// Unknown and expired user codes are answered with a Bad Request response. Otherwise, the
+    /// response of the solicitor is returned while it has not yet decided, and a plain text
+    /// confirmation afterwards.
+    ///
+    /// ## Panics
+    ///
+    /// When the device authorizer or solicitor returned by the endpoint is suddenly `None` when
+    /// previously it was `Some(_)`.
+    pub async fn execute(&mut self, mut request: R) -> Result<R::Response, E::Error> {
+        let pending = device_verification(&mut self.endpoint, &WrappedVerification::new(&mut request)).await;
+
+        let pending = match pending {
+            Ok(pending) => pending,
+            Err(VerificationError::PrimitiveError) => {
+                return Err(self.endpoint.inner.error(OAuthError::PrimitiveError))
+            }
+            Err(VerificationError::Invalid) => {
+                return self.bad_request(&mut request, "The user code is missing.")
+            }
+            Err(VerificationError::Unknown) => {
+                return self.bad_request(&mut request, "The user code is unknown or has expired.")
+            }
+        };
+
+        let checked = self
+            .endpoint
+            .inner
+            .owner_solicitor()
+            .unwrap()
+            .check_consent(&mut request, pending.as_solicitation())
+            .await;
+
+        let (decided, text) = match checked {
+            OwnerConsent::InProgress(response) => return Ok(response),
+            OwnerConsent::Error(err) => return Err(self.endpoint.inner.web_error(err)),
+            OwnerConsent::Authorized(owner) => (
+                pending.authorize(&mut self.endpoint, owner.into()).await,
+                "The device has been authorized.",
+            ),
+            OwnerConsent::Denied => (
+                pending.deny(&mut self.endpoint).await,
+                "The device authorization has been denied.",
+            ),
+        };
+
+        if decided.is_err() {
+            return Err(self.endpoint.inner.error(OAuthError::PrimitiveError));
+        }
+
+        let mut response = self.endpoint.inner.response(&mut request, Template::new_ok())?;
+        response
+            .body_text(text)
+            .map_err(|err| self.endpoint.inner.web_error(err))?;
+        Ok(response)
+    }
+
+    fn bad_request(&mut self, request: &mut R, text: &str) -> Result<R::Response, E::Error> {
+        let mut response = self.endpoint.inner.response(request, Template::new_bad(None))?;
+        response
+            .client_error()
+            .map_err(|err| self.endpoint.inner.web_error(err))?;
+        response
+            .body_text(text)
+            .map_err(|err| self.endpoint.inner.web_error(err))?;
+        Ok(response)
+    }
+}
+
+impl<E, R> DeviceTokenFlow<E, R>
+where
+    E: Endpoint<R> + Send + Sync,
+    R: WebRequest + Send + Sync,
+    <R as WebRequest>::Error: Send + Sync,
+{
+    /// Check that the endpoint supports the necessary operations for handling requests.
+    ///
+    /// Binds the endpoint to a particular type of request that it supports, for many
+    /// implementations this is probably single type anyways. The endpoint needs to provide
+    /// (return `Some`):
+    ///
+    /// * a `Registrar` from `registrar`
+    /// * a `DeviceAuthorizer` from `device_authorizer_mut`
+    /// * an `Issuer` from `issuer_mut`
+    ///
+    /// ## Panics
+    ///
+    /// Indirectly `execute` may panic when this flow is instantiated with an inconsistent
+    /// endpoint, for details see the documentation of `Endpoint` and `execute`. For
+    /// consistent endpoints, the panic is instead caught as an error here.
+    pub fn prepare(mut endpoint: E) -> Result<Self, E::Error> {
+        if endpoint.registrar().is_none() {
+            return Err(endpoint.error(OAuthError::PrimitiveError));
+        }
+
+        if endpoint.device_authorizer_mut().is_none() {
+            return Err(endpoint.error(OAuthError::PrimitiveError));
+        }
+
+        if endpoint.issuer_mut().is_none() {
+            return Err(endpoint.error(OAuthError::PrimitiveError));
+        }
+
+        Ok(DeviceTokenFlow {
+            endpoint: WrappedDevice {
+                inner: endpoint,
+                r_type: PhantomData,
+            },
+            allow_credentials_in_body: false,
+        })
+    }
+
+    /// Credentials in body should only be enabled if use of HTTP Basic is not possible.
+    ///
+    /// Allows the request body to contain the `client_secret` as a form parameter. This is NOT
+    /// RECOMMENDED and need not be supported. The parameters MUST NOT appear in the request URI
+    /// itself.
+    ///
+    /// Thus support is disabled by default and must be explicitely enabled.
+    pub fn allow_credentials_in_body(&mut self, allow: bool) {
+        self.allow_credentials_in_body = allow;
+    }
+
+    /// Use the checked endpoint to answer a polling device.
+    ///
+    /// ## Panics
+    ///
+    /// When the registrar, device authorizer or issuer returned by the endpoint is suddenly `None`
+    /// when previously it was `Some(_)`.
+    pub async fn execute(&mut self, mut request: R) -> Result<R::Response, E::Error> {
+        let issued = device_token(
+            &mut self.endpoint,
+            &WrappedRequest::new(&mut request, self.allow_credentials_in_body),
+        )
+        .await;
+
+        let token = match issued {
+            Err(error) => return token_error(&mut self.endpoint.inner, &mut request, error),
+            Ok(token) => token,
+        };
+
+        let mut response = self.endpoint.inner.response(&mut request, Template::new_ok())?;
+        response
+            .body_json(&token.to_json())
+            .map_err(|err| self.endpoint.inner.web_error(err))?;
+        Ok(response)
+    }
+}
+
+impl<E, R> DeviceEndpoint for WrappedDevice<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    fn registrar(&self) -> &(dyn Registrar + Sync) {
+        self.inner.registrar().unwrap()
+    }
+
+    fn device_authorizer(&mut self) -> &mut (dyn DeviceAuthorizer + Send) {
+        self.inner.device_authorizer_mut().unwrap()
+    }
+}
+
+impl<E, R> DeviceVerificationEndpoint for WrappedDevice<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    fn device_authorizer(&mut self) -> &mut (dyn DeviceAuthorizer + Send) {
+        self.inner.device_authorizer_mut().unwrap()
+    }
+}
+
+impl<E, R> DeviceTokenEndpoint for WrappedDevice<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    fn registrar(&self) -> &(dyn Registrar + Sync) {
+        self.inner.registrar().unwrap()
+    }
+
+    fn device_authorizer(&mut self) -> &mut (dyn DeviceAuthorizer + Send) {
+        self.inner.device_authorizer_mut().unwrap()
+    }
+
+    fn issuer(&mut self) -> &mut (dyn Issuer + Send) {
+        self.inner.issuer_mut().unwrap()
+    }
+}
+
+impl<R: WebRequest> WrappedRequest<R> {
+    pub fn new(request: &mut R, credentials: bool) -> Self {
+        Self::new_or_fail(request, credentials).unwrap_or_else(Self::from_err)
+    }
+
+    fn new_or_fail(request: &mut R, credentials: bool) -> Result<Self, FailParse<R::Error>> {
+        // If there is a header, it must parse correctly.
+        let authorization = match request.authheader() {
+            Err(err) => return Err(FailParse::Err(err)),
+            Ok(Some(header)) => Self::parse_header(header).map(Some)?,
+            Ok(None) => None,
+        };
+
+        Ok(WrappedRequest {
+            body: request.urlbody().map_err(FailParse::Err)?.into_owned(),
+            authorization,
+            error: None,
+            allow_credentials_in_body: credentials,
+        })
+    }
+
+    fn from_err(err: FailParse<R::Error>) -> Self {
+        WrappedRequest {
+            body: Default::default(),
+            authorization: None,
+            error: Some(err),
+            allow_credentials_in_body: false,
+        }
+    }
+
+    fn parse_header(header: Cow<str>) -> Result<Authorization, Invalid> {
+        let authorization = {
+            if !header.starts_with("Basic ") {
+                return Err(Invalid);
+            }
+
+            let combined = match base64::decode(&header[6..]) {
+                Err(_) => return Err(Invalid),
+                Ok(vec) => vec,
+            };
+
+            let mut split = combined.splitn(2, |&c| c == b':');
+            let client_bin = match split.next() {
+                None => return Err(Invalid),
+                Some(client) => client,
+            };
+            let passwd = match split.next() {
+                None => return Err(Invalid),
+                Some(passwd64) => passwd64,
+            };
+
+            let client = match from_utf8(client_bin) {
+                Err(_) => return Err(Invalid),
+                Ok(client) => client,
+            };
+
+            Authorization(client.to_string(), passwd.to_vec())
+        };
+
+        Ok(authorization)
+    }
+}
+
+impl<R: WebRequest> WrappedVerification<R> {
+    pub fn new(request: &mut R) -> Self {
+        match request.query() {
+            Ok(query) => WrappedVerification {
+                query: query.into_owned(),
+                error: None,
+            },
+            Err(err) => WrappedVerification {
+                query: Default::default(),
+                error: Some(err),
+            },
+        }
+    }
+}
+
+impl<R: WebRequest> DeviceRequest for WrappedRequest<R> {
+    fn valid(&self) -> bool {
+        self.error.is_none()
+    }
+
+    fn authorization(&self) -> Option<(Cow<'_, str>, Cow<'_, [u8]>)> {
+        self.authorization
+            .as_ref()
+            .map(|auth| (auth.0.as_str().into(), auth.1.as_slice().into()))
+    }
+
+    fn client_id(&self) -> Option<Cow<'_, str>> {
+        self.body.unique_value("client_id")
+    }
+
+    fn scope(&self) -> Option<Cow<'_, str>> {
+        self.body.unique_value("scope")
+    }
+
+    fn extension(&self, key: &str) -> Option<Cow<'_, str>> {
+        self.body.unique_value(key)
+    }
+
+    fn allow_credentials_in_body(&self) -> bool {
+        self.allow_credentials_in_body
+    }
+}
+
+impl<R: WebRequest> DeviceTokenRequest for WrappedRequest<R> {
+    fn valid(&self) -> bool {
+        self.error.is_none()
+    }
+
+    fn authorization(&self) -> Option<(Cow<'_, str>, Cow<'_, [u8]>)> {
+        self.authorization
+            .as_ref()
+            .map(|auth| (auth.0.as_str().into(), auth.1.as_slice().into()))
+    }
+
+    fn client_id(&self) -> Option<Cow<'_, str>> {
+        self.body.unique_value("client_id")
+    }
+
+    fn device_code(&self) -> Option<Cow<'_, str>> {
+        self.body.unique_value("device_code")
+    }
+
+    fn grant_type(&self) -> Option<Cow<'_, str>> {
+        self.body.unique_value("grant_type")
+    }
+
+    fn extension(&self, key: &str) -> Option<Cow<'_, str>> {
+        self.body.unique_value(key)
+    }
+
+    fn allow_credentials_in_body(&self) -> bool {
+        self.allow_credentials_in_body
+    }
+}
+
+impl<R: WebRequest> DeviceVerificationRequest for WrappedVerification<R> {
+    fn valid(&self) -> bool {
+        self.error.is_none()
+    }
+
+    fn user_code(&self) -> Option<Cow<'_, str>> {
+        self.query.unique_value("user_code")
+    }
+}
+
+impl<E> From<Invalid> for FailParse<E> {
+    fn from(_: Invalid) -> Self {
+        FailParse::Invalid
+    }
+}
@@ -0,0 +1,300 @@
+use std::str::from_utf8;
+use std::{borrow::Cow, marker::PhantomData};
+
+use oxide_auth::{
+    endpoint::{QueryParameter, WebRequest, OAuthError, WebResponse, Template, NormalizedParameter},
+    code_grant::introspection::Request as IntrospectionRequest,
+    primitives::scope::Scope,
+};
+
+use super::Endpoint;
+use super::access_token::token_error;
+use crate::{
+    code_grant::introspection::{introspect, Endpoint as IntrospectionEndpoint},
+    primitives::{Issuer, Registrar},
+};
+
+/// Reveals the state and meta information of tokens to protected resources.
+///
+/// Resource servers that can not access the `Issuer` directly, for example because they run in a
+/// separate process, ask the authorization server about the tokens presented to them. The response
+/// contains the owner, client, scope and expiry of active tokens together with the public
+/// extensions of their grant, see RFC 7662. Unknown, expired and revoked tokens are reported as
+/// inactive. This request MUST be protected by TLS.
+///
+/// The caller either authenticates as a registered client or presents a bearer token of its own.
+/// Such a token must fulfill one of the scopes returned by the `Scopes` of the endpoint. Bearer
+/// authentication is always rejected when the endpoint does not provide any scopes.
+///
+/// Client credentials can be allowed to appear in the request body instead of being
+/// required to be passed as HTTP Basic authorization. This is not recommended and must be
+/// enabled explicitely. See [`allow_credentials_in_body`] for details.
+///
+/// [`allow_credentials_in_body`]: #method.allow_credentials_in_body
+pub struct IntrospectionFlow<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    endpoint: WrappedIntrospection<E, R>,
+    allow_credentials_in_body: bool,
+}
+
+struct WrappedIntrospection<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    inner: E,
+    r_type: PhantomData<R>,
+}
+
+#[derive(Clone)]
+pub struct WrappedRequest<R: WebRequest> {
+    /// The query in the body.
+    body: NormalizedParameter,
+
+    /// The authorization of the caller.
+    authorization: Option<Authorization>,
+
+    /// An error if one occurred.
+    error: Option<FailParse<R::Error>>,
+
+    /// The credentials-in-body flag from the flow.
+    allow_credentials_in_body: bool,
+}
+
+struct Scoped<'a, E: 'a, R: 'a> {
+    request: &'a mut R,
+    endpoint: &'a mut E,
+}
+
+struct Invalid;
+
+#[derive(Clone)]
+enum FailParse<E> {
+    Invalid,
+    Err(E),
+}
+
+#[derive(Clone)]
+enum Authorization {
+    Basic(String, Vec<u8>),
+    Bearer(String),
+}
+
+impl<E, R> IntrospectionFlow<E, R>
+where
+    E: Endpoint<R> + Send + Sync,
+    R: WebRequest + Send + Sync,
+    <R as WebRequest>::Error: Send + Sync,
+{
+    /// Check that the endpoint supports the necessary operations for handling requests.
+    ///
+    /// Binds the endpoint to a particular type of request that it supports, for many
+    /// implementations this is probably single type anyways. The endpoint needs to provide
+    /// (return `Some`):
+    ///
+    /// * a `Registrar` from `registrar`
+    /// * an `Issuer` from `issuer_mut`
+    ///
+    /// ## Panics
+    ///
+    /// Indirectly `execute` may panic when this flow is instantiated with an inconsistent
+    /// endpoint, for details see the documentation of `Endpoint` and `execute`. For
+    /// consistent endpoints, the panic is instead caught as an error here.
+    pub fn prepare(mut endpoint: E) -> Result<Self, E::Error> {
+        if endpoint.registrar().is_none() {
+            return Err(endpoint.error(OAuthError::PrimitiveError));
+        }
+
+        if endpoint.issuer_mut().is_none() {
+            return Err(endpoint.error(OAuthError::PrimitiveError));
+        }
+
+        Ok(IntrospectionFlow {
+            endpoint: WrappedIntrospection {
+                inner: endpoint,
+                r_type: PhantomData,
+            },
+            allow_credentials_in_body: false,
+        })
+    }
+
+    /// Credentials in body should only be enabled if use of HTTP Basic is not possible.
+    ///
+    /// Allows the request body to contain the `client_secret` as a form parameter. This is NOT
+    /// RECOMMENDED and need not be supported. The parameters MUST NOT appear in the request URI
+    /// itself.
+    ///
+    /// Thus support is disabled by default and must be explicitely enabled.
+    pub fn allow_credentials_in_body(&mut self, allow: bool) {
+        self.allow_credentials_in_body = allow;
+    }
+
+    /// Use the checked endpoint to introspect the token of the request.
+    ///
+    /// ## Panics
+    ///
+    /// When the registrar or issuer returned by the endpoint is suddenly `None` when previously it
+    /// was `Some(_)`.
+    pub async fn execute(&mut self, mut request: R) -> Result<R::Response, E::Error> {
+        let introspected = {
+            let wrapped = WrappedRequest::new(&mut request, self.allow_credentials_in_body);
+
+            let mut scoped = Scoped {
+                request: &mut request,
+                endpoint: &mut self.endpoint.inner,
+            };
+
+            introspect(&mut scoped, &wrapped).await
+        };
+
+        let introspected = match introspected {
+            Err(error) => return token_error(&mut self.endpoint.inner, &mut request, error),
+            Ok(introspected) => introspected,
+        };
+
+        let mut response = self.endpoint.inner.response(&mut request, Template::new_ok())?;
+        response
+            .body_json(&introspected.to_json())
+            .map_err(|err| self.endpoint.inner.web_error(err))?;
+        Ok(response)
+    }
+}
+
+impl<'a, E: 'a, R: 'a> IntrospectionEndpoint for Scoped<'a, E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    fn registrar(&self) -> &(dyn Registrar + Sync) {
+        self.endpoint.registrar().unwrap()
+    }
+
+    fn issuer(&mut self) -> &mut (dyn Issuer + Send) {
+        self.endpoint.issuer_mut().unwrap()
+    }
+
+    fn scopes(&mut self) -> &[Scope] {
+        match self.endpoint.scopes() {
+            Some(scopes) => scopes.scopes(self.request),
+            None => &[],
+        }
+    }
+}
+
+impl<R: WebRequest> WrappedRequest<R> {
+    pub fn new(request: &mut R, credentials: bool) -> Self {
+        Self::new_or_fail(request, credentials).unwrap_or_else(Self::from_err)
+    }
+
+    fn new_or_fail(request: &mut R, credentials: bool) -> Result<Self, FailParse<R::Error>> {
+        // If there is a header, it must parse correctly.
+        let authorization = match request.authheader() {
+            Err(err) => return Err(FailParse::Err(err)),
+            Ok(Some(header)) => Self::parse_header(header).map(Some)?,
+            Ok(None) => None,
+        };
+
+        Ok(WrappedRequest {
+            body: request.urlbody().map_err(FailParse::Err)?.into_owned(),
+            authorization,
+            error: None,
+            allow_credentials_in_body: credentials,
+        })
+    }
+
+    fn from_err(err: FailParse<R::Error>) -> Self {
+        WrappedRequest {
+            body: Default::default(),
+            authorization: None,
+            error: Some(err),
+            allow_credentials_in_body: false,
+        }
+    }
+
+    fn parse_header(header: Cow<str>) -> Result<Authorization, Invalid> {
+        if header.starts_with("Bearer ") {
+            return Ok(Authorization::Bearer(header[7..].to_string()));
+        }
+
+        let authorization = {
+            if !header.starts_with("Basic ") {
+                return Err(Invalid);
+            }
+
+            let combined = match base64::decode(&header[6..]) {
+                Err(_) => return Err(Invalid),
+                Ok(vec) => vec,
+            };
+
+            let mut split = combined.splitn(2, |&c| c == b':');
+            let client_bin = match split.next() {
+                None => return Err(Invalid),
+                Some(client) => client,
+            };
+            let passwd = match split.next() {
+                None => return Err(Invalid),
+                Some(passwd64) => passwd64,
+            };
+
+            let client = match from_utf8(client_bin) {
+                Err(_) => return Err(Invalid),
+                Ok(client) => client,
+            };
+
+            Authorization::Basic(client.to_string(), passwd.to_vec())
+        };
+
+        Ok(authorization)
+    }
+}
+
+impl<R: WebRequest> IntrospectionRequest for WrappedRequest<R> {
+    fn valid(&self) -> bool {
+        self.error.is_none()
+    }
+
+    fn authorization(&self) -> Option<(Cow<'_, str>, Cow<'_, [u8]>)> {
+        match &self.authorization {
+            Some(Authorization::Basic(client, passwd)) => {
+                Some((client.as_str().into(), passwd.as_slice().into()))
+            }
+            _ => None,
+        }
+    }
+
+    fn bearer(&self) -> Option<Cow<'_, str>> {
+        match &self.authorization {
+            Some(Authorization::Bearer(token)) => Some(token.as_str().into()),
+            _ => None,
+        }
+    }
+
+    fn client_id(&self) -> Option<Cow<'_, str>> {
+        self.body.unique_value("client_id")
+    }
+
+    fn token(&self) -> Option<Cow<'_, str>> {
+        self.body.unique_value("token")
+    }
+
+    fn token_type_hint(&self) -> Option<Cow<'_, str>> {
+        self.body.unique_value("token_type_hint")
+    }
+
+    fn extension(&self, key: &str) -> Option<Cow<'_, str>> {
+        self.body.unique_value(key)
+    }
+
+    fn allow_credentials_in_body(&self) -> bool {
+        self.allow_credentials_in_body
+    }
+}
+
+impl<E> From<Invalid> for FailParse<E> {
+    fn from(_: Invalid) -> Self {
+        FailParse::Invalid
+    }
+}
@@ -0,0 +1,48 @@
+use std::marker::PhantomData;
+
+use oxide_auth::{
+    endpoint::{Template, WebRequest, WebResponse},
+    primitives::jwk::JwkSet,
+};
+
+use super::Endpoint;
+
+/// Serves the public keys verifying the tokens of the server as a JSON Web Key Set.
+///
+/// The document is usually published at `/.well-known/jwks.json` and advertised as the `jwks_uri`
+/// of the authorization server metadata, see RFC 7517. No primitive of the endpoint is used, the
+/// keys are taken from the configured `JwkSet`.
+pub struct JwksFlow<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    endpoint: E,
+    keys: JwkSet,
+    r_type: PhantomData<R>,
+}
+
+impl<E, R> JwksFlow<E, R>
+where
+    E: Endpoint<R> + Send + Sync,
+    R: WebRequest + Send + Sync,
+    <R as WebRequest>::Error: Send + Sync,
+{
+    /// Publish the given keys.
+    pub fn new(endpoint: E, keys: JwkSet) -> Self {
+        JwksFlow {
+            endpoint,
+            keys,
+            r_type: PhantomData,
+        }
+    }
+
+    /// Respond with the key set as a json document.
+    pub async fn execute(&mut self, mut request: R) -> Result<R::Response, E::Error> {
+        let mut response = self.endpoint.response(&mut request, Template::new_ok())?;
+        response
+            .body_json(&self.keys.to_json())
+            .map_err(|err| self.endpoint.web_error(err))?;
+        Ok(response)
+    }
+}
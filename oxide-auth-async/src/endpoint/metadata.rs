@@ -0,0 +1,138 @@
+use std::marker::PhantomData;
+
+use oxide_auth::{
+    code_grant::{
+        device::DEVICE_CODE_GRANT_TYPE, jwt_bearer::JWT_BEARER_GRANT_TYPE, metadata::Metadata,
+        token_exchange::TOKEN_EXCHANGE_GRANT_TYPE,
+    },
+    endpoint::{Template, WebRequest, WebResponse},
+};
+
+use super::{Endpoint, Extension};
+
+/// Serves the authorization server metadata document.
+///
+/// The document is usually published at `/.well-known/oauth-authorization-server`, see RFC 8414.
+/// The configured `Metadata` provides the issuer and the locations of the endpoints. The supported
+/// response types, grant types and client authentication methods are derived from the primitives
+/// that the endpoint provides, following the requirements of the respective flows. Scopes are
+/// taken from `Scopes` and extensions can advertise additional capabilities.
+///
+/// The flows themselves are not inspected. An endpoint offering primitives for a flow that is not
+/// served should construct the `Metadata` manually instead.
+pub struct MetadataFlow<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    endpoint: E,
+    metadata: Metadata,
+    allow_credentials_in_body: bool,
+    r_type: PhantomData<R>,
+}
+
+impl<E, R> MetadataFlow<E, R>
+where
+    E: Endpoint<R> + Send + Sync,
+    R: WebRequest + Send + Sync,
+    <R as WebRequest>::Error: Send + Sync,
+{
+    /// Describe an endpoint based on the configured metadata.
+    ///
+    /// No primitive is required, the document will simply advertise fewer capabilities.
+    pub fn new(endpoint: E, metadata: Metadata) -> Self {
+        MetadataFlow {
+            endpoint,
+            metadata,
+            allow_credentials_in_body: false,
+            r_type: PhantomData,
+        }
+    }
+
+    /// Advertise support for client credentials in the request body.
+    ///
+    /// This should reflect the setting of the flows serving the token endpoint. It adds the
+    /// `client_secret_post` authentication method.
+    pub fn allow_credentials_in_body(&mut self, allow: bool) {
+        self.allow_credentials_in_body = allow;
+    }
+
+    /// Create the metadata for the request, without creating a response.
+    pub fn metadata(&mut self, request: &mut R) -> Metadata {
+        let mut metadata = self.metadata.clone();
+
+        let registrar = self.endpoint.registrar().is_some();
+        let authorizer = self.endpoint.authorizer_mut().is_some();
+        let issuer = self.endpoint.issuer_mut().is_some();
+        let solicitor = self.endpoint.owner_solicitor().is_some();
+        let authenticator = self.endpoint.owner_authenticator().is_some();
+        let device_authorizer = self.endpoint.device_authorizer_mut().is_some();
+        let assertion_verifier = self.endpoint.assertion_verifier().is_some();
+
+        let authorization = registrar && authorizer && solicitor;
+        let token = registrar && issuer;
+
+        if authorization {
+            metadata.add_response_type("code");
+        }
+
+        if authorization && issuer {
+            metadata.add_response_type("token");
+            metadata.add_grant_type("implicit");
+        }
+
+        if token && authorizer {
+            metadata.add_grant_type("authorization_code");
+        }
+
+        if token {
+            metadata.add_grant_type("refresh_token");
+            metadata.add_grant_type("client_credentials");
+            metadata.add_grant_type(TOKEN_EXCHANGE_GRANT_TYPE);
+        }
+
+        if token && authenticator {
+            metadata.add_grant_type("password");
+        }
+
+        if token && device_authorizer {
+            metadata.add_grant_type(DEVICE_CODE_GRANT_TYPE);
+        }
+
+        if token && assertion_verifier {
+            metadata.add_grant_type(JWT_BEARER_GRANT_TYPE);
+        }
+
+        if token {
+            metadata.add_token_endpoint_auth_method("client_secret_basic");
+            if self.allow_credentials_in_body {
+                metadata.add_token_endpoint_auth_method("client_secret_post");
+            }
+        }
+
+        if let Some(scopes) = self.endpoint.scopes() {
+            for scope in scopes.scopes(request) {
+                for token in scope.iter() {
+                    metadata.add_scope(token);
+                }
+            }
+        }
+
+        if let Some(extension) = self.endpoint.extension().and_then(Extension::metadata) {
+            extension.extend(&mut metadata);
+        }
+
+        metadata
+    }
+
+    /// Respond with the metadata as a json document.
+    pub async fn execute(&mut self, mut request: R) -> Result<R::Response, E::Error> {
+        let metadata = self.metadata(&mut request);
+
+        let mut response = self.endpoint.response(&mut request, Template::new_ok())?;
+        response
+            .body_json(&metadata.to_json())
+            .map_err(|err| self.endpoint.web_error(err))?;
+        Ok(response)
+    }
+}
@@ -1,14 +1,27 @@
 use async_trait::async_trait;
 use oxide_auth::endpoint::{OAuthError, Template, WebRequest, OwnerConsent, Solicitation, Scopes};
+use oxide_auth::primitives::dpop::DpopVerifier;
 
 pub use crate::code_grant::access_token::{Extension as AccessTokenExtension};
 pub use crate::code_grant::authorization::Extension as AuthorizationExtension;
-use crate::primitives::{Authorizer, Registrar, Issuer};
+pub use oxide_auth::code_grant::metadata::Extension as MetadataExtension;
+use crate::primitives::{
+    AssertionVerifier, Authorizer, DeviceAuthorizer, OwnerAuthenticator, Registrar, Issuer,
+};
 
 pub mod authorization;
 pub mod access_token;
+pub mod client_credentials;
+pub mod device;
+pub mod introspection;
+pub mod jwks;
+pub mod jwt_bearer;
+pub mod metadata;
+pub mod password;
 pub mod refresh;
 pub mod resource;
+pub mod revoke;
+pub mod token_exchange;
 
 pub trait Endpoint<Request>
 where
@@ -47,6 +60,38 @@ where
     /// request.
     fn scopes(&mut self) -> Option<&mut dyn Scopes<Request>>;
 
+    /// An authenticator for the credentials of resource owners.
+    ///
+    /// Returning `None` is the default implementation and will fail the resource owner password
+    /// credentials flow but does not have any effect on other flows.
+    fn owner_authenticator(&self) -> Option<&(dyn OwnerAuthenticator + Sync)> {
+        None
+    }
+
+    /// A device authorizer if this endpoint can access one.
+    ///
+    /// Returning `None` is the default implementation and will fail the flows of the device
+    /// authorization grant but does not have any effect on other flows.
+    fn device_authorizer_mut(&mut self) -> Option<&mut (dyn DeviceAuthorizer + Send)> {
+        None
+    }
+
+    /// A verifier for the assertions of trusted issuers if this endpoint can access one.
+    ///
+    /// Returning `None` is the default implementation and will fail the JWT bearer flow but does
+    /// not have any effect on other flows.
+    fn assertion_verifier(&self) -> Option<&(dyn AssertionVerifier + Sync)> {
+        None
+    }
+
+    /// A verifier for DPoP proofs if this endpoint can access one.
+    ///
+    /// Returning `None` is the default implementation. Proofs are then ignored and only bearer
+    /// tokens are issued, while resources reject tokens presented with the `DPoP` scheme.
+    fn dpop_verifier(&self) -> Option<&(dyn DpopVerifier + Sync)> {
+        None
+    }
+
     /// Generate a prototype response.
     ///
     /// The endpoint can rely on this being called at most once for each flow, if it wants
@@ -79,6 +124,36 @@ pub trait Extension {
     fn access_token(&mut self) -> Option<&mut (dyn AccessTokenExtension + Send)> {
         None
     }
+
+    /// The handler for advertising extensions in the authorization server metadata.
+    fn metadata(&mut self) -> Option<&mut (dyn MetadataExtension + Send)> {
+        None
+    }
+}
+
+/// Verify the DPoP proof of a request, if it contains one and the endpoint has a verifier.
+///
+/// The outer error is a web error of the request. The inner error indicates an invalid proof,
+/// including proofs of requests that do not describe their target.
+fn dpop_proof<R: WebRequest>(
+    verifier: Option<&dyn DpopVerifier>, request: &mut R, access_token: Option<&str>,
+) -> Result<Result<Option<String>, ()>, R::Error> {
+    let verifier = match verifier {
+        Some(verifier) => verifier,
+        None => return Ok(Ok(None)),
+    };
+
+    let proof = match request.header("DPoP")? {
+        Some(proof) => proof.into_owned(),
+        None => return Ok(Ok(None)),
+    };
+
+    let thumbprint = match request.target()? {
+        Some((method, uri)) => verifier.verify(&proof, &method, &uri, access_token),
+        None => None,
+    };
+
+    Ok(thumbprint.map(Some).ok_or(()))
 }
 
 /// Checks consent with the owner of a resource, identified in a request.
@@ -2,7 +2,10 @@ use std::{borrow::Cow, marker::PhantomData, str::from_utf8};
 
 use oxide_auth::{
     code_grant::refresh::{Error, Request},
-    endpoint::{WebRequest, WebResponse, OAuthError, QueryParameter, Template, NormalizedParameter},
+    endpoint::{
+        ClientCertificate, WebRequest, WebResponse, OAuthError, QueryParameter, Template,
+        NormalizedParameter,
+    },
 };
 
 use super::Endpoint;
@@ -38,6 +41,9 @@ struct WrappedRequest<R: WebRequest> {
 
     /// An error if one occurred.
     error: Option<Option<R::Error>>,
+
+    /// The client certificate of the connection.
+    certificate: Option<ClientCertificate>,
 }
 
 struct Authorization(String, Vec<u8>);
@@ -142,10 +148,13 @@ impl<'a, R: WebRequest> WrappedRequest<R> {
             Ok(None) => None,
         };
 
+        let certificate = request.certificate()?.map(Cow::into_owned);
+
         Ok(WrappedRequest {
             body: request.urlbody()?.into_owned(),
             authorization,
             error: None,
+            certificate,
         })
     }
 
@@ -154,6 +163,7 @@ impl<'a, R: WebRequest> WrappedRequest<R> {
             body: Default::default(),
             authorization: None,
             error: Some(err),
+            certificate: None,
         }
     }
 
@@ -230,4 +240,8 @@ impl<R: WebRequest> Request for WrappedRequest<R> {
     fn extension(&self, key: &str) -> Option<Cow<str>> {
         self.body.unique_value(key)
     }
+
+    fn certificate(&self) -> Option<Cow<'_, ClientCertificate>> {
+        self.certificate.as_ref().map(Cow::Borrowed)
+    }
 }
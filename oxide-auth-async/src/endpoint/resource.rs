@@ -35,6 +35,12 @@ struct WrappedRequest<R: WebRequest> {
     ///
     /// Actual parsing of the authorization header is done in the lower level.
     error: Option<R::Error>,
+
+    /// The thumbprint of the key proven by a valid DPoP proof for the token.
+    dpop: Option<String>,
+
+    /// The thumbprint of the client certificate of the connection.
+    certificate: Option<String>,
 }
 
 struct Scoped<'a, E: 'a, R: 'a> {
@@ -80,7 +86,8 @@ where
     /// was `Some(_)`.
     pub async fn execute(&mut self, mut request: R) -> Result<Grant, Result<R::Response, E::Error>> {
         let protected = {
-            let wrapped = WrappedRequest::new(&mut request);
+            let verifier = self.endpoint.0.dpop_verifier();
+            let wrapped = WrappedRequest::new(&mut request, verifier.map(|v| v as &dyn DpopVerifier));
 
             let mut scoped = Scoped {
                 request: &mut request,
@@ -113,7 +120,7 @@ where
 }
 
 impl<R: WebRequest> WrappedRequest<R> {
-    fn new(request: &mut R) -> Self {
+    fn new(request: &mut R, dpop: Option<&dyn DpopVerifier>) -> Self {
         let token = match request.authheader() {
             // TODO: this is unecessarily wasteful, we always clone.
             Ok(Some(token)) => Some(token.into_owned()),
@@ -121,10 +128,27 @@ impl<R: WebRequest> WrappedRequest<R> {
             Err(error) => return Self::from_error(error),
         };
 
+        // Only tokens presented with the `DPoP` scheme are checked against a proof.
+        let access_token = token.as_deref().and_then(|token| token.strip_prefix("DPoP "));
+        let dpop = match access_token {
+            Some(access_token) => match dpop_proof(dpop, request, Some(access_token)) {
+                Ok(thumbprint) => thumbprint.ok().flatten(),
+                Err(error) => return Self::from_error(error),
+            },
+            None => None,
+        };
+
+        let certificate = match request.certificate() {
+            Ok(certificate) => certificate.map(|certificate| certificate.thumbprint().to_string()),
+            Err(error) => return Self::from_error(error),
+        };
+
         WrappedRequest {
             request: PhantomData,
             authorization: token,
             error: None,
+            dpop,
+            certificate,
         }
     }
 
@@ -133,6 +157,8 @@ impl<R: WebRequest> WrappedRequest<R> {
             request: PhantomData,
             authorization: None,
             error: Some(error),
+            dpop: None,
+            certificate: None,
         }
     }
 }
@@ -159,4 +185,12 @@ impl<R: WebRequest> ResourceRequest for WrappedRequest<R> {
     fn token(&self) -> Option<Cow<str>> {
         self.authorization.as_deref().map(Cow::Borrowed)
     }
+
+    fn dpop_thumbprint(&self) -> Option<Cow<'_, str>> {
+        self.dpop.as_deref().map(Cow::Borrowed)
+    }
+
+    fn certificate_thumbprint(&self) -> Option<Cow<'_, str>> {
+        self.certificate.as_deref().map(Cow::Borrowed)
+    }
 }
@@ -1,6 +1,7 @@
 pub mod code_grant;
 pub mod endpoint;
 pub mod primitives;
+pub mod remote;
 
 #[cfg(test)]
 mod tests;
@@ -3,7 +3,10 @@ use async_trait::async_trait;
 use oxide_auth::primitives::{grant::Grant, scope::Scope};
 use oxide_auth::primitives::issuer::{IssuedToken, RefreshedToken};
 use oxide_auth::primitives::{
-    authorizer, registrar, issuer,
+    authenticator, authorizer, device, jwt, registrar, issuer,
+    device::{DeviceCodes, DeviceStatus},
+    jwt::{TrustedIssuers, VerifiedAssertion},
+    mtls::ClientCertificate,
     registrar::{ClientUrl, BoundClient, RegistrarError, PreGrant},
 };
 
@@ -37,6 +40,16 @@ pub trait Issuer {
     async fn recover_token(&mut self, _: &str) -> Result<Option<Grant>, ()>;
 
     async fn recover_refresh(&mut self, _: &str) -> Result<Option<Grant>, ()>;
+
+    async fn revoke_token(&mut self, token: &str) -> Result<(), ()> {
+        let _ = token;
+        Err(())
+    }
+
+    async fn revoke_refresh(&mut self, refresh: &str) -> Result<(), ()> {
+        let _ = refresh;
+        Err(())
+    }
 }
 
 #[async_trait]
@@ -59,6 +72,14 @@ where
     async fn recover_refresh(&mut self, token: &str) -> Result<Option<Grant>, ()> {
         issuer::Issuer::recover_refresh(self, token)
     }
+
+    async fn revoke_token(&mut self, token: &str) -> Result<(), ()> {
+        issuer::Issuer::revoke_token(self, token)
+    }
+
+    async fn revoke_refresh(&mut self, refresh: &str) -> Result<(), ()> {
+        issuer::Issuer::revoke_refresh(self, refresh)
+    }
 }
 
 #[async_trait]
@@ -70,6 +91,28 @@ pub trait Registrar {
     ) -> Result<PreGrant, RegistrarError>;
 
     async fn check(&self, client_id: &str, passphrase: Option<&[u8]>) -> Result<(), RegistrarError>;
+
+    async fn check_certificate(
+        &self, client_id: &str, certificate: &ClientCertificate,
+    ) -> Result<(), RegistrarError> {
+        let _ = (client_id, certificate);
+        Err(RegistrarError::Unspecified)
+    }
+
+    async fn check_grant_type(&self, client_id: &str, grant_type: &str) -> Result<(), RegistrarError> {
+        let _ = client_id;
+        match grant_type {
+            "password" => Err(RegistrarError::Unspecified),
+            _ => Ok(()),
+        }
+    }
+
+    async fn check_response_type(
+        &self, client_id: &str, response_type: &str,
+    ) -> Result<(), RegistrarError> {
+        let _ = (client_id, response_type);
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -90,4 +133,93 @@ where
     async fn check(&self, client_id: &str, passphrase: Option<&[u8]>) -> Result<(), RegistrarError> {
         registrar::Registrar::check(self, client_id, passphrase)
     }
+
+    async fn check_certificate(
+        &self, client_id: &str, certificate: &ClientCertificate,
+    ) -> Result<(), RegistrarError> {
+        registrar::Registrar::check_certificate(self, client_id, certificate)
+    }
+
+    async fn check_grant_type(&self, client_id: &str, grant_type: &str) -> Result<(), RegistrarError> {
+        registrar::Registrar::check_grant_type(self, client_id, grant_type)
+    }
+
+    async fn check_response_type(
+        &self, client_id: &str, response_type: &str,
+    ) -> Result<(), RegistrarError> {
+        registrar::Registrar::check_response_type(self, client_id, response_type)
+    }
+}
+
+#[async_trait]
+pub trait OwnerAuthenticator {
+    async fn authenticate(&self, username: &str, password: &[u8]) -> Result<Option<String>, ()>;
+}
+
+#[async_trait]
+impl<T> OwnerAuthenticator for T
+where
+    T: authenticator::OwnerAuthenticator + Send + Sync + ?Sized,
+{
+    async fn authenticate(&self, username: &str, password: &[u8]) -> Result<Option<String>, ()> {
+        authenticator::OwnerAuthenticator::authenticate(self, username, password)
+    }
+}
+
+#[async_trait]
+pub trait DeviceAuthorizer {
+    async fn authorize(&mut self, _: Grant) -> Result<DeviceCodes, ()>;
+
+    async fn pending(&mut self, _: &str) -> Result<Option<Grant>, ()>;
+
+    async fn approve(&mut self, _: &str, _: &str) -> Result<(), ()>;
+
+    async fn deny(&mut self, _: &str) -> Result<(), ()>;
+
+    async fn poll(&mut self, _: &str) -> Result<Option<DeviceStatus>, ()>;
+}
+
+#[async_trait]
+impl<T> DeviceAuthorizer for T
+where
+    T: device::DeviceAuthorizer + Send + ?Sized,
+{
+    async fn authorize(&mut self, grant: Grant) -> Result<DeviceCodes, ()> {
+        device::DeviceAuthorizer::authorize(self, grant)
+    }
+
+    async fn pending(&mut self, user_code: &str) -> Result<Option<Grant>, ()> {
+        device::DeviceAuthorizer::pending(self, user_code)
+    }
+
+    async fn approve(&mut self, user_code: &str, owner_id: &str) -> Result<(), ()> {
+        device::DeviceAuthorizer::approve(self, user_code, owner_id)
+    }
+
+    async fn deny(&mut self, user_code: &str) -> Result<(), ()> {
+        device::DeviceAuthorizer::deny(self, user_code)
+    }
+
+    async fn poll(&mut self, device_code: &str) -> Result<Option<DeviceStatus>, ()> {
+        device::DeviceAuthorizer::poll(self, device_code)
+    }
+}
+
+#[async_trait]
+pub trait AssertionVerifier {
+    async fn verify(
+        &self, assertion: &str, trusted: &TrustedIssuers,
+    ) -> Result<Option<VerifiedAssertion>, ()>;
+}
+
+#[async_trait]
+impl<T> AssertionVerifier for T
+where
+    T: jwt::AssertionVerifier + Send + Sync + ?Sized,
+{
+    async fn verify(
+        &self, assertion: &str, trusted: &TrustedIssuers,
+    ) -> Result<Option<VerifiedAssertion>, ()> {
+        jwt::AssertionVerifier::verify(self, assertion, trusted)
+    }
 }
@@ -0,0 +1,251 @@
+//! Resolve tokens of a remote authorization server.
+//!
+//! Resource servers running separately from the authorization server can not recover the grants
+//! of tokens from a shared `Issuer`. Instead, they ask the introspection endpoint of the
+//! authorization server about each token, see RFC 7662. The `RemoteIssuer` performs these requests
+//! and caches their results so that it can be used as the issuer of a `ResourceFlow`.
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use oxide_auth::primitives::dpop;
+use oxide_auth::primitives::grant::{Extensions, Grant, Value};
+use oxide_auth::primitives::issuer::{IssuedToken, RefreshedToken};
+use oxide_auth::primitives::mtls;
+use serde_json::{Map, Value as JsonValue};
+use url::{form_urlencoded, Url};
+
+use crate::primitives::Issuer;
+
+/// Sends the requests of the `RemoteIssuer` to the introspection endpoint.
+///
+/// This library does not choose an http client, implement this for the client of your web
+/// framework instead. The connection to the authorization server MUST be protected by TLS.
+#[async_trait]
+pub trait Transport {
+    /// Post an `application/x-www-form-urlencoded` body with the given authorization header.
+    ///
+    /// Returns the status code and body of the response. An `Err` indicates that no response could
+    /// be retrieved at all.
+    async fn post_form(&self, url: &Url, authorization: &str, body: String)
+        -> Result<(u16, String), ()>;
+}
+
+/// An `Issuer` recovering access tokens through a remote introspection endpoint.
+///
+/// Active tokens are cached until they expire but at most for the configured maximum duration,
+/// since a token could be revoked in the meantime. Inactive tokens are only cached briefly. The
+/// cache holds a bounded number of results, the one expiring first is dropped when it is full. The
+/// grant is reconstructed from the members `client_id`, `sub`, `scope` and `exp` of the response,
+/// all other members are added as public extensions. The thumbprints in `cnf` become the
+/// extensions of `dpop::CONFIRMATION` and `mtls::CONFIRMATION`, so the resource flow checks the
+/// binding of the token. Tokens bound by any other confirmation method are refused. Because the
+/// redirect uri is not part of the response, it is set to the url of the introspection endpoint.
+///
+/// Only access tokens can be recovered, this issuer can neither issue nor refresh tokens.
+pub struct RemoteIssuer<T> {
+    transport: T,
+    endpoint: Url,
+    authorization: String,
+    cache: HashMap<String, Cached>,
+    max_ttl: Duration,
+    negative_ttl: Duration,
+    max_entries: usize,
+}
+
+struct Cached {
+    grant: Option<Grant>,
+    until: DateTime<Utc>,
+}
+
+/// Members of the response that are not represented as extensions of the same name.
+const STANDARD_MEMBERS: &[&str] = &["active", "scope", "client_id", "sub", "exp", "token_type", "cnf"];
+
+impl<T: Transport> RemoteIssuer<T> {
+    /// Authenticate at the introspection endpoint as a confidential client.
+    pub fn new(transport: T, endpoint: Url, client_id: &str, passphrase: &[u8]) -> Self {
+        let mut credentials = client_id.as_bytes().to_vec();
+        credentials.push(b':');
+        credentials.extend_from_slice(passphrase);
+        let authorization = format!("Basic {}", base64::encode(&credentials));
+        Self::with_authorization(transport, endpoint, authorization)
+    }
+
+    /// Authenticate at the introspection endpoint with a bearer token of the resource server.
+    pub fn with_bearer(transport: T, endpoint: Url, token: &str) -> Self {
+        Self::with_authorization(transport, endpoint, format!("Bearer {}", token))
+    }
+
+    fn with_authorization(transport: T, endpoint: Url, authorization: String) -> Self {
+        RemoteIssuer {
+            transport,
+            endpoint,
+            authorization,
+            cache: HashMap::new(),
+            max_ttl: Duration::minutes(5),
+            negative_ttl: Duration::seconds(10),
+            max_entries: 1024,
+        }
+    }
+
+    /// Set the longest duration for which an active token is cached.
+    ///
+    /// Defaults to five minutes. Revoked tokens may be accepted for this long.
+    pub fn set_max_ttl(&mut self, max_ttl: Duration) {
+        self.max_ttl = max_ttl;
+    }
+
+    /// Set the duration for which an inactive token is cached.
+    ///
+    /// Defaults to ten seconds.
+    pub fn set_negative_ttl(&mut self, negative_ttl: Duration) {
+        self.negative_ttl = negative_ttl;
+    }
+
+    /// Set the maximum number of cached results.
+    ///
+    /// Defaults to 1024. A value of zero disables the cache.
+    pub fn set_max_entries(&mut self, max_entries: usize) {
+        self.max_entries = max_entries;
+    }
+
+    /// Forget all cached results.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+
+    async fn introspect(&self, token: &str) -> Result<Option<Grant>, ()> {
+        let body = form_urlencoded::Serializer::new(String::new())
+            .append_pair("token", token)
+            .append_pair("token_type_hint", "access_token")
+            .finish();
+
+        let (status, body) = self
+            .transport
+            .post_form(&self.endpoint, &self.authorization, body)
+            .await?;
+
+        if status != 200 {
+            return Err(());
+        }
+
+        let response: Map<String, JsonValue> = serde_json::from_str(&body).map_err(|_| ())?;
+        self.grant(response)
+    }
+
+    fn grant(&self, response: Map<String, JsonValue>) -> Result<Option<Grant>, ()> {
+        match response.get("active") {
+            Some(JsonValue::Bool(true)) => (),
+            Some(JsonValue::Bool(false)) => return Ok(None),
+            _ => return Err(()),
+        }
+
+        let string = |key: &str| response.get(key).and_then(JsonValue::as_str);
+        let client_id = string("client_id").ok_or(())?;
+        let owner_id = string("sub").ok_or(())?;
+        let scope = string("scope").ok_or(())?.parse().map_err(|_| ())?;
+        let until = match response.get("exp").and_then(JsonValue::as_i64) {
+            Some(exp) => Utc.timestamp_opt(exp, 0).single().ok_or(())?,
+            None => Utc::now() + self.max_ttl,
+        };
+
+        let mut extensions = Extensions::new();
+        match response.get("cnf") {
+            None => (),
+            Some(JsonValue::Object(cnf)) => {
+                for (method, thumbprint) in cnf.iter() {
+                    let key = match method.as_str() {
+                        "jkt" => dpop::CONFIRMATION,
+                        "x5t#S256" => mtls::CONFIRMATION,
+                        // A binding that can not be checked must not turn into a bearer token.
+                        _ => return Err(()),
+                    };
+                    let thumbprint = thumbprint.as_str().ok_or(())?;
+                    extensions.set_raw(key.to_string(), Value::public(Some(thumbprint.to_string())));
+                }
+            }
+            Some(_) => return Err(()),
+        }
+
+        for (key, value) in response.iter() {
+            if STANDARD_MEMBERS.contains(&key.as_str()) {
+                continue;
+            }
+
+            let value = match value {
+                JsonValue::Bool(true) => None,
+                JsonValue::String(value) => Some(value.clone()),
+                other => Some(other.to_string()),
+            };
+            extensions.set_raw(key.clone(), Value::public(value));
+        }
+
+        Ok(Some(Grant {
+            client_id: client_id.to_string(),
+            owner_id: owner_id.to_string(),
+            redirect_uri: self.endpoint.clone(),
+            scope,
+            until,
+            extensions,
+        }))
+    }
+
+    fn cached(&self, token: &str, now: DateTime<Utc>) -> Option<Option<Grant>> {
+        match self.cache.get(token) {
+            Some(cached) if cached.until > now => Some(cached.grant.clone()),
+            _ => None,
+        }
+    }
+
+    fn store(&mut self, token: &str, grant: Option<Grant>, now: DateTime<Utc>) {
+        let until = match &grant {
+            Some(grant) => grant.until.min(now + self.max_ttl),
+            None => now + self.negative_ttl,
+        };
+
+        self.cache.retain(|_, cached| cached.until > now);
+        if self.max_entries == 0 {
+            return;
+        }
+
+        while self.cache.len() >= self.max_entries {
+            let first = self
+                .cache
+                .iter()
+                .min_by_key(|(_, cached)| cached.until)
+                .map(|(token, _)| token.clone());
+            match first {
+                Some(token) => self.cache.remove(&token),
+                None => break,
+            };
+        }
+
+        self.cache.insert(token.to_string(), Cached { grant, until });
+    }
+}
+
+#[async_trait]
+impl<T: Transport + Send + Sync> Issuer for RemoteIssuer<T> {
+    async fn issue(&mut self, _: Grant) -> Result<IssuedToken, ()> {
+        Err(())
+    }
+
+    async fn refresh(&mut self, _: &str, _: Grant) -> Result<RefreshedToken, ()> {
+        Err(())
+    }
+
+    async fn recover_token(&mut self, token: &str) -> Result<Option<Grant>, ()> {
+        let now = Utc::now();
+        if let Some(grant) = self.cached(token, now) {
+            return Ok(grant);
+        }
+
+        let grant = self.introspect(token).await?;
+        self.store(token, grant.clone(), now);
+        Ok(grant)
+    }
+
+    async fn recover_refresh(&mut self, _: &str) -> Result<Option<Grant>, ()> {
+        Err(())
+    }
+}
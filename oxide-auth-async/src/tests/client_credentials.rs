@@ -0,0 +1,211 @@
+use oxide_auth::primitives::issuer::TokenMap;
+use oxide_auth::{
+    code_grant::accesstoken::TokenResponse,
+    frontends::simple::endpoint::Error,
+    primitives::registrar::{Client, ClientMap, RegisteredUrl},
+    endpoint::WebRequest,
+};
+
+use crate::endpoint::{client_credentials::ClientCredentialsFlow, Endpoint};
+
+use std::collections::HashMap;
+
+use base64;
+use serde_json;
+
+use super::{Body, CraftedRequest, CraftedResponse, Status, TestGenerator, ToSingleValueQuery};
+use super::defaults::*;
+
+struct ClientCredentialsSetup {
+    registrar: ClientMap,
+    issuer: TokenMap<TestGenerator>,
+    basic_authorization: String,
+}
+
+struct ClientCredentialsEndpoint<'a> {
+    registrar: &'a ClientMap,
+    issuer: &'a mut TokenMap<TestGenerator>,
+}
+
+impl<'a> ClientCredentialsEndpoint<'a> {
+    pub fn new(registrar: &'a ClientMap, issuer: &'a mut TokenMap<TestGenerator>) -> Self {
+        ClientCredentialsEndpoint { registrar, issuer }
+    }
+}
+
+impl<'a> Endpoint<CraftedRequest> for ClientCredentialsEndpoint<'a> {
+    type Error = Error<CraftedRequest>;
+
+    fn registrar(&self) -> Option<&(dyn crate::primitives::Registrar + Sync)> {
+        Some(self.registrar)
+    }
+    fn authorizer_mut(&mut self) -> Option<&mut (dyn crate::primitives::Authorizer + Send)> {
+        None
+    }
+    fn issuer_mut(&mut self) -> Option<&mut (dyn crate::primitives::Issuer + Send)> {
+        Some(self.issuer)
+    }
+    fn response(
+        &mut self, _: &mut CraftedRequest, _: oxide_auth::endpoint::Template,
+    ) -> Result<<CraftedRequest as WebRequest>::Response, Self::Error> {
+        Ok(Default::default())
+    }
+    fn error(&mut self, _err: oxide_auth::endpoint::OAuthError) -> Self::Error {
+        unimplemented!()
+    }
+    fn web_error(&mut self, _err: <CraftedRequest as WebRequest>::Error) -> Self::Error {
+        unimplemented!()
+    }
+    fn scopes(&mut self) -> Option<&mut dyn oxide_auth::endpoint::Scopes<CraftedRequest>> {
+        None
+    }
+    fn owner_solicitor(
+        &mut self,
+    ) -> Option<&mut (dyn crate::endpoint::OwnerSolicitor<CraftedRequest> + Send)> {
+        None
+    }
+}
+
+impl ClientCredentialsSetup {
+    fn private_client() -> Self {
+        let mut registrar = ClientMap::new();
+        let issuer = TokenMap::new(TestGenerator("AccessToken".to_string()));
+
+        let client = Client::confidential(
+            EXAMPLE_CLIENT_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+            EXAMPLE_PASSPHRASE.as_bytes(),
+        );
+        registrar.register_client(client);
+
+        let basic_authorization =
+            base64::encode(&format!("{}:{}", EXAMPLE_CLIENT_ID, EXAMPLE_PASSPHRASE));
+
+        ClientCredentialsSetup {
+            registrar,
+            issuer,
+            basic_authorization,
+        }
+    }
+
+    fn public_client() -> Self {
+        let mut registrar = ClientMap::new();
+        let issuer = TokenMap::new(TestGenerator("AccessToken".to_string()));
+
+        let client = Client::public(
+            EXAMPLE_CLIENT_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+        );
+        registrar.register_client(client);
+
+        let basic_authorization =
+            base64::encode(&format!("{}:{}", EXAMPLE_CLIENT_ID, EXAMPLE_PASSPHRASE));
+
+        ClientCredentialsSetup {
+            registrar,
+            issuer,
+            basic_authorization,
+        }
+    }
+
+    fn assert_json_error_set(response: &CraftedResponse) {
+        match &response.body {
+            Some(Body::Json(ref json)) => {
+                let content: HashMap<String, String> = serde_json::from_str(json).unwrap();
+                assert!(content.get("error").is_some(), "Error not set in json response");
+            }
+            other => panic!("Expected json encoded body, got {:?}", other),
+        }
+
+        match response.status {
+            Status::Unauthorized => (),
+            Status::BadRequest => (),
+            _ => panic!("Expected error status, got {:?}", response),
+        }
+    }
+
+    fn test_simple_error(&mut self, request: CraftedRequest) {
+        let mut flow = ClientCredentialsFlow::prepare(ClientCredentialsEndpoint::new(
+            &self.registrar,
+            &mut self.issuer,
+        ))
+        .unwrap();
+        match smol::run(flow.execute(request)) {
+            Ok(ref response) => Self::assert_json_error_set(response),
+            resp => panic!("Expected non-error reponse, got {:?}", resp),
+        }
+    }
+
+    fn test_success(&mut self, request: CraftedRequest) {
+        let mut flow = ClientCredentialsFlow::prepare(ClientCredentialsEndpoint::new(
+            &self.registrar,
+            &mut self.issuer,
+        ))
+        .unwrap();
+        let response = smol::run(flow.execute(request)).expect("Expected non-error reponse");
+
+        assert_eq!(response.status, Status::Ok);
+        let token = match response.body {
+            Some(Body::Json(ref json)) => serde_json::from_str::<TokenResponse>(json).unwrap(),
+            other => panic!("Expected json encoded body, got {:?}", other),
+        };
+        assert!(token.access_token.is_some());
+        assert!(token.refresh_token.is_none());
+    }
+}
+
+#[test]
+fn client_credentials_success() {
+    let mut setup = ClientCredentialsSetup::private_client();
+
+    let valid = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![("grant_type", "client_credentials")]
+                .iter()
+                .to_single_value_query(),
+        ),
+        auth: Some("Basic ".to_string() + &setup.basic_authorization),
+    };
+
+    setup.test_success(valid);
+}
+
+#[test]
+fn client_credentials_public_client() {
+    let mut setup = ClientCredentialsSetup::public_client();
+
+    let public_client = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![("grant_type", "client_credentials"), ("client_id", EXAMPLE_CLIENT_ID)]
+                .iter()
+                .to_single_value_query(),
+        ),
+        auth: None,
+    };
+
+    setup.test_simple_error(public_client);
+}
+
+#[test]
+fn client_credentials_wrong_password() {
+    let mut setup = ClientCredentialsSetup::private_client();
+
+    let wrong_password = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![("grant_type", "client_credentials")]
+                .iter()
+                .to_single_value_query(),
+        ),
+        auth: Some(
+            "Basic ".to_string()
+                + &base64::encode(&format!("{}:{}", EXAMPLE_CLIENT_ID, "NotTheRightPassphrase")),
+        ),
+    };
+
+    setup.test_simple_error(wrong_password);
+}
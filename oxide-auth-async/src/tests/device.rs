@@ -0,0 +1,227 @@
+use oxide_auth::primitives::device::DeviceMap;
+use oxide_auth::primitives::issuer::TokenMap;
+use oxide_auth::{
+    code_grant::{
+        accesstoken::TokenResponse,
+        device::{DeviceAuthorizationResponse, DEVICE_CODE_GRANT_TYPE},
+    },
+    frontends::simple::endpoint::Error,
+    primitives::registrar::{Client, ClientMap, RegisteredUrl},
+    endpoint::WebRequest,
+};
+
+use crate::endpoint::{
+    device::{DeviceAuthorizationFlow, DeviceTokenFlow, DeviceVerificationFlow},
+    Endpoint, OwnerSolicitor,
+};
+
+use std::collections::HashMap;
+
+use serde_json;
+
+use super::{Allow, Body, CraftedRequest, CraftedResponse, Deny, Status, TestGenerator, ToSingleValueQuery};
+use super::defaults::*;
+
+const EXAMPLE_VERIFICATION_URI: &str = "https://example.com/device";
+
+struct DeviceSetup {
+    registrar: ClientMap,
+    devices: DeviceMap<TestGenerator>,
+    issuer: TokenMap<TestGenerator>,
+}
+
+struct DeviceEndpoint<'a> {
+    registrar: &'a ClientMap,
+    devices: &'a mut DeviceMap<TestGenerator>,
+    issuer: &'a mut TokenMap<TestGenerator>,
+    solicitor: Option<&'a mut (dyn OwnerSolicitor<CraftedRequest> + Send + Sync)>,
+}
+
+impl<'a> Endpoint<CraftedRequest> for DeviceEndpoint<'a> {
+    type Error = Error<CraftedRequest>;
+
+    fn registrar(&self) -> Option<&(dyn crate::primitives::Registrar + Sync)> {
+        Some(self.registrar)
+    }
+    fn authorizer_mut(&mut self) -> Option<&mut (dyn crate::primitives::Authorizer + Send)> {
+        None
+    }
+    fn issuer_mut(&mut self) -> Option<&mut (dyn crate::primitives::Issuer + Send)> {
+        Some(self.issuer)
+    }
+    fn response(
+        &mut self, _: &mut CraftedRequest, _: oxide_auth::endpoint::Template,
+    ) -> Result<<CraftedRequest as WebRequest>::Response, Self::Error> {
+        Ok(Default::default())
+    }
+    fn error(&mut self, _err: oxide_auth::endpoint::OAuthError) -> Self::Error {
+        unimplemented!()
+    }
+    fn web_error(&mut self, _err: <CraftedRequest as WebRequest>::Error) -> Self::Error {
+        unimplemented!()
+    }
+    fn scopes(&mut self) -> Option<&mut dyn oxide_auth::endpoint::Scopes<CraftedRequest>> {
+        None
+    }
+    fn device_authorizer_mut(&mut self) -> Option<&mut (dyn crate::primitives::DeviceAuthorizer + Send)> {
+        Some(self.devices)
+    }
+    fn owner_solicitor(
+        &mut self,
+    ) -> Option<&mut (dyn crate::endpoint::OwnerSolicitor<CraftedRequest> + Send)> {
+        match self.solicitor {
+            Some(ref mut solicitor) => Some(*solicitor),
+            None => None,
+        }
+    }
+}
+
+impl DeviceSetup {
+    fn new() -> Self {
+        let mut registrar = ClientMap::new();
+        registrar.register_client(Client::public(
+            EXAMPLE_CLIENT_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+        ));
+
+        DeviceSetup {
+            registrar,
+            devices: DeviceMap::new(TestGenerator("DeviceCode".to_string())),
+            issuer: TokenMap::new(TestGenerator("AccessToken".to_string())),
+        }
+    }
+
+    fn endpoint<'a>(
+        &'a mut self, solicitor: Option<&'a mut (dyn OwnerSolicitor<CraftedRequest> + Send + Sync)>,
+    ) -> DeviceEndpoint<'a> {
+        DeviceEndpoint {
+            registrar: &self.registrar,
+            devices: &mut self.devices,
+            issuer: &mut self.issuer,
+            solicitor,
+        }
+    }
+
+    fn authorize(&mut self) -> DeviceAuthorizationResponse {
+        let request = CraftedRequest {
+            query: None,
+            urlbody: Some(
+                vec![("client_id", EXAMPLE_CLIENT_ID)]
+                    .iter()
+                    .to_single_value_query(),
+            ),
+            auth: None,
+        };
+
+        let verification_uri = EXAMPLE_VERIFICATION_URI.parse().unwrap();
+        let mut flow = DeviceAuthorizationFlow::prepare(self.endpoint(None), verification_uri).unwrap();
+        let response = smol::run(flow.execute(request)).expect("Expected non-error response");
+
+        assert_eq!(response.status, Status::Ok);
+        match response.body {
+            Some(Body::Json(ref json)) => serde_json::from_str(json).unwrap(),
+            other => panic!("Expected json encoded body, got {:?}", other),
+        }
+    }
+
+    fn verify(
+        &mut self, user_code: &str, solicitor: &mut (dyn OwnerSolicitor<CraftedRequest> + Send + Sync),
+    ) -> CraftedResponse {
+        let request = CraftedRequest {
+            query: Some(vec![("user_code", user_code)].iter().to_single_value_query()),
+            urlbody: None,
+            auth: None,
+        };
+
+        let mut flow = DeviceVerificationFlow::prepare(self.endpoint(Some(solicitor))).unwrap();
+        smol::run(flow.execute(request)).expect("Expected non-error response")
+    }
+
+    fn poll(&mut self, grant_type: &str, device_code: &str) -> CraftedResponse {
+        let request = CraftedRequest {
+            query: None,
+            urlbody: Some(
+                vec![
+                    ("grant_type", grant_type),
+                    ("device_code", device_code),
+                    ("client_id", EXAMPLE_CLIENT_ID),
+                ]
+                .iter()
+                .to_single_value_query(),
+            ),
+            auth: None,
+        };
+
+        let mut flow = DeviceTokenFlow::prepare(self.endpoint(None)).unwrap();
+        smol::run(flow.execute(request)).expect("Expected non-error response")
+    }
+
+    fn assert_json_error(response: &CraftedResponse, error: &str) {
+        match &response.body {
+            Some(Body::Json(ref json)) => {
+                let content: HashMap<String, String> = serde_json::from_str(json).unwrap();
+                assert_eq!(content.get("error").map(String::as_str), Some(error));
+            }
+            other => panic!("Expected json encoded body, got {:?}", other),
+        }
+
+        match response.status {
+            Status::Unauthorized => (),
+            Status::BadRequest => (),
+            _ => panic!("Expected error status, got {:?}", response),
+        }
+    }
+}
+
+#[test]
+fn device_approved() {
+    let mut setup = DeviceSetup::new();
+    let codes = setup.authorize();
+
+    let pending = setup.poll(DEVICE_CODE_GRANT_TYPE, &codes.device_code);
+    DeviceSetup::assert_json_error(&pending, "authorization_pending");
+
+    let verified = setup.verify(&codes.user_code, &mut Allow(EXAMPLE_OWNER_ID.to_string()));
+    assert_eq!(verified.status, Status::Ok);
+
+    let response = setup.poll(DEVICE_CODE_GRANT_TYPE, &codes.device_code);
+    assert_eq!(response.status, Status::Ok);
+    let token = match response.body {
+        Some(Body::Json(ref json)) => serde_json::from_str::<TokenResponse>(json).unwrap(),
+        other => panic!("Expected json encoded body, got {:?}", other),
+    };
+    assert!(token.access_token.is_some());
+}
+
+#[test]
+fn device_denied() {
+    let mut setup = DeviceSetup::new();
+    let codes = setup.authorize();
+
+    let verified = setup.verify(&codes.user_code, &mut Deny);
+    assert_eq!(verified.status, Status::Ok);
+
+    let denied = setup.poll(DEVICE_CODE_GRANT_TYPE, &codes.device_code);
+    DeviceSetup::assert_json_error(&denied, "access_denied");
+}
+
+#[test]
+fn device_slow_down() {
+    let mut setup = DeviceSetup::new();
+    let codes = setup.authorize();
+
+    let pending = setup.poll(DEVICE_CODE_GRANT_TYPE, &codes.device_code);
+    DeviceSetup::assert_json_error(&pending, "authorization_pending");
+    let too_fast = setup.poll(DEVICE_CODE_GRANT_TYPE, &codes.device_code);
+    DeviceSetup::assert_json_error(&too_fast, "slow_down");
+}
+
+#[test]
+fn device_wrong_grant_type() {
+    let mut setup = DeviceSetup::new();
+    let codes = setup.authorize();
+
+    let wrong_grant_type = setup.poll("client_credentials", &codes.device_code);
+    DeviceSetup::assert_json_error(&wrong_grant_type, "unsupported_grant_type");
+}
@@ -0,0 +1,200 @@
+use oxide_auth::primitives::generator::RandomGenerator;
+use oxide_auth::primitives::issuer::{self, IssuedToken, TokenMap};
+use oxide_auth::{
+    frontends::simple::endpoint::Error,
+    primitives::grant::{Extensions, Grant, Value},
+    primitives::registrar::{Client, ClientMap, RegisteredUrl},
+    primitives::scope::Scope,
+    endpoint::WebRequest,
+};
+
+use crate::endpoint::{introspection::IntrospectionFlow, Endpoint};
+
+use std::collections::HashMap;
+
+use base64;
+use chrono::{Duration, Utc};
+use serde_json::{self, Value as JsonValue};
+
+use super::{Body, CraftedRequest, CraftedResponse, Status, ToSingleValueQuery};
+use super::defaults::*;
+
+struct IntrospectionSetup {
+    registrar: ClientMap,
+    issuer: TokenMap<RandomGenerator>,
+    issued: IssuedToken,
+    scopes: Vec<Scope>,
+    authorization: String,
+}
+
+struct IntrospectionEndpoint<'a> {
+    registrar: &'a ClientMap,
+    issuer: &'a mut TokenMap<RandomGenerator>,
+    scopes: &'a [Scope],
+}
+
+impl<'a> Endpoint<CraftedRequest> for IntrospectionEndpoint<'a> {
+    type Error = Error<CraftedRequest>;
+
+    fn registrar(&self) -> Option<&(dyn crate::primitives::Registrar + Sync)> {
+        Some(self.registrar)
+    }
+    fn authorizer_mut(&mut self) -> Option<&mut (dyn crate::primitives::Authorizer + Send)> {
+        None
+    }
+    fn issuer_mut(&mut self) -> Option<&mut (dyn crate::primitives::Issuer + Send)> {
+        Some(self.issuer)
+    }
+    fn response(
+        &mut self, _: &mut CraftedRequest, _: oxide_auth::endpoint::Template,
+    ) -> Result<<CraftedRequest as WebRequest>::Response, Self::Error> {
+        Ok(Default::default())
+    }
+    fn error(&mut self, _err: oxide_auth::endpoint::OAuthError) -> Self::Error {
+        unimplemented!()
+    }
+    fn web_error(&mut self, _err: <CraftedRequest as WebRequest>::Error) -> Self::Error {
+        unimplemented!()
+    }
+    fn scopes(&mut self) -> Option<&mut dyn oxide_auth::endpoint::Scopes<CraftedRequest>> {
+        Some(&mut self.scopes)
+    }
+    fn owner_solicitor(
+        &mut self,
+    ) -> Option<&mut (dyn crate::endpoint::OwnerSolicitor<CraftedRequest> + Send)> {
+        None
+    }
+}
+
+impl IntrospectionSetup {
+    fn new() -> Self {
+        let mut registrar = ClientMap::new();
+        registrar.register_client(Client::confidential(
+            EXAMPLE_CLIENT_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+            EXAMPLE_PASSPHRASE.as_bytes(),
+        ));
+
+        let mut issuer = TokenMap::new(RandomGenerator::new(16));
+        let mut grant = Self::grant(EXAMPLE_SCOPE);
+        grant
+            .extensions
+            .set_raw("audience".to_string(), Value::public(Some("api".to_string())));
+        let issued = issuer::Issuer::issue(&mut issuer, grant).unwrap();
+
+        let authorization = "Basic ".to_string()
+            + &base64::encode(&format!("{}:{}", EXAMPLE_CLIENT_ID, EXAMPLE_PASSPHRASE));
+
+        IntrospectionSetup {
+            registrar,
+            issuer,
+            issued,
+            scopes: vec!["introspect".parse().unwrap()],
+            authorization,
+        }
+    }
+
+    fn grant(scope: &str) -> Grant {
+        Grant {
+            client_id: EXAMPLE_CLIENT_ID.to_string(),
+            owner_id: EXAMPLE_OWNER_ID.to_string(),
+            redirect_uri: EXAMPLE_REDIRECT_URI.parse().unwrap(),
+            scope: scope.parse().unwrap(),
+            until: Utc::now() + Duration::hours(1),
+            extensions: Extensions::new(),
+        }
+    }
+
+    fn request(&self, token: &str) -> CraftedRequest {
+        CraftedRequest {
+            query: None,
+            urlbody: Some(vec![("token", token)].iter().to_single_value_query()),
+            auth: Some(self.authorization.clone()),
+        }
+    }
+
+    fn execute(&mut self, request: CraftedRequest) -> CraftedResponse {
+        let endpoint = IntrospectionEndpoint {
+            registrar: &self.registrar,
+            issuer: &mut self.issuer,
+            scopes: &self.scopes,
+        };
+        let mut flow = IntrospectionFlow::prepare(endpoint).unwrap();
+        smol::run(flow.execute(request)).expect("Expected non-error reponse")
+    }
+
+    fn test_error(&mut self, request: CraftedRequest, error: &str) {
+        let response = self.execute(request);
+        match &response.body {
+            Some(Body::Json(ref json)) => {
+                let content: HashMap<String, String> = serde_json::from_str(json).unwrap();
+                assert_eq!(content.get("error").map(String::as_str), Some(error));
+            }
+            other => panic!("Expected json encoded body, got {:?}", other),
+        }
+
+        match response.status {
+            Status::Unauthorized => (),
+            Status::BadRequest => (),
+            _ => panic!("Expected error status, got {:?}", response),
+        }
+    }
+
+    fn test_success(&mut self, request: CraftedRequest) -> HashMap<String, JsonValue> {
+        let response = self.execute(request);
+        assert_eq!(response.status, Status::Ok);
+        match &response.body {
+            Some(Body::Json(ref json)) => serde_json::from_str(json).unwrap(),
+            other => panic!("Expected json encoded body, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn introspect_access_token() {
+    let mut setup = IntrospectionSetup::new();
+    let request = setup.request(&setup.issued.token);
+    let content = setup.test_success(request);
+
+    assert_eq!(content.get("active"), Some(&JsonValue::Bool(true)));
+    assert_eq!(
+        content.get("client_id"),
+        Some(&JsonValue::from(EXAMPLE_CLIENT_ID))
+    );
+    assert_eq!(content.get("sub"), Some(&JsonValue::from(EXAMPLE_OWNER_ID)));
+    assert_eq!(content.get("token_type"), Some(&JsonValue::from("bearer")));
+    assert_eq!(content.get("audience"), Some(&JsonValue::from("api")));
+}
+
+#[test]
+fn introspect_unknown_token() {
+    let mut setup = IntrospectionSetup::new();
+    let request = setup.request("NotAnIssuedToken");
+    let content = setup.test_success(request);
+    assert_eq!(content.get("active"), Some(&JsonValue::Bool(false)));
+    assert_eq!(content.len(), 1);
+}
+
+#[test]
+fn introspect_with_bearer_caller() {
+    let mut setup = IntrospectionSetup::new();
+    let caller =
+        issuer::Issuer::issue(&mut setup.issuer, IntrospectionSetup::grant("introspect")).unwrap();
+    setup.authorization = "Bearer ".to_string() + &caller.token;
+
+    let request = setup.request(&setup.issued.token);
+    let content = setup.test_success(request);
+    assert_eq!(content.get("active"), Some(&JsonValue::Bool(true)));
+}
+
+#[test]
+fn introspect_bearer_caller_without_scope() {
+    let mut setup = IntrospectionSetup::new();
+    let caller =
+        issuer::Issuer::issue(&mut setup.issuer, IntrospectionSetup::grant(EXAMPLE_SCOPE)).unwrap();
+    setup.authorization = "Bearer ".to_string() + &caller.token;
+
+    let request = setup.request(&setup.issued.token);
+    setup.test_error(request, "invalid_client");
+}
@@ -0,0 +1,74 @@
+use oxide_auth::{
+    endpoint::WebRequest,
+    frontends::simple::endpoint::Error,
+    primitives::generator::{Assertion, AssertionKind, Keyring},
+    primitives::jwk::JwkSet,
+};
+
+use crate::endpoint::{jwks::JwksFlow, Endpoint};
+
+use serde_json::{self, Value as JsonValue};
+
+use super::{Body, CraftedRequest, Status};
+
+struct JwksEndpoint;
+
+impl Endpoint<CraftedRequest> for JwksEndpoint {
+    type Error = Error<CraftedRequest>;
+
+    fn registrar(&self) -> Option<&(dyn crate::primitives::Registrar + Sync)> {
+        None
+    }
+    fn authorizer_mut(&mut self) -> Option<&mut (dyn crate::primitives::Authorizer + Send)> {
+        None
+    }
+    fn issuer_mut(&mut self) -> Option<&mut (dyn crate::primitives::Issuer + Send)> {
+        None
+    }
+    fn response(
+        &mut self, _: &mut CraftedRequest, _: oxide_auth::endpoint::Template,
+    ) -> Result<<CraftedRequest as WebRequest>::Response, Self::Error> {
+        Ok(Default::default())
+    }
+    fn error(&mut self, _err: oxide_auth::endpoint::OAuthError) -> Self::Error {
+        unimplemented!()
+    }
+    fn web_error(&mut self, _err: <CraftedRequest as WebRequest>::Error) -> Self::Error {
+        unimplemented!()
+    }
+    fn scopes(&mut self) -> Option<&mut dyn oxide_auth::endpoint::Scopes<CraftedRequest>> {
+        None
+    }
+    fn owner_solicitor(
+        &mut self,
+    ) -> Option<&mut (dyn crate::endpoint::OwnerSolicitor<CraftedRequest> + Send)> {
+        None
+    }
+}
+
+#[test]
+fn jwks_publishes_keyring() {
+    let keyring = Keyring::new("secret", Assertion::new(AssertionKind::HmacSha256, b"secret"));
+    let public_key = [7u8; 32];
+    let verify_only = Assertion::verify_only(AssertionKind::Ed25519, &public_key).unwrap();
+    keyring.insert("previous", verify_only).unwrap();
+
+    let mut flow = JwksFlow::new(JwksEndpoint, JwkSet::from_keyring(&keyring));
+    let request = CraftedRequest {
+        query: None,
+        urlbody: None,
+        auth: None,
+    };
+    let response = smol::run(flow.execute(request)).expect("Expected non-error reponse");
+    assert_eq!(response.status, Status::Ok);
+
+    let document: JsonValue = match &response.body {
+        Some(Body::Json(ref json)) => serde_json::from_str(json).unwrap(),
+        other => panic!("Expected json encoded body, got {:?}", other),
+    };
+
+    let keys = document["keys"].as_array().unwrap();
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0]["kid"], "previous");
+    assert_eq!(keys[0]["crv"], "Ed25519");
+}
@@ -0,0 +1,177 @@
+use oxide_auth::primitives::issuer::TokenMap;
+use oxide_auth::primitives::jwt::{self, TrustedIssuers, VerifiedAssertion};
+use oxide_auth::{
+    code_grant::{accesstoken::TokenResponse, jwt_bearer::JWT_BEARER_GRANT_TYPE},
+    frontends::simple::endpoint::Error,
+    primitives::registrar::{Client, ClientMap, RegisteredUrl},
+    endpoint::WebRequest,
+};
+
+use crate::endpoint::{jwt_bearer::JwtBearerFlow, Endpoint};
+
+use std::collections::HashMap;
+
+use base64;
+use serde_json;
+
+use super::{Body, CraftedRequest, CraftedResponse, Status, TestGenerator, ToSingleValueQuery};
+use super::defaults::*;
+
+const EXAMPLE_AUDIENCE: &str = "https://example.com/token";
+const EXAMPLE_ASSERTION: &str = "ValidAssertion";
+
+struct JwtBearerSetup {
+    registrar: ClientMap,
+    issuer: TokenMap<TestGenerator>,
+    trusted: TrustedIssuers,
+    basic_authorization: String,
+}
+
+/// Accepts only one fixed assertion, the signature checks are covered by the main crate.
+struct FixedVerifier;
+
+impl jwt::AssertionVerifier for FixedVerifier {
+    fn verify(&self, assertion: &str, _: &TrustedIssuers) -> Result<Option<VerifiedAssertion>, ()> {
+        if assertion != EXAMPLE_ASSERTION {
+            return Ok(None);
+        }
+
+        Ok(Some(VerifiedAssertion {
+            issuer: "https://idp.example".to_string(),
+            subject: EXAMPLE_OWNER_ID.to_string(),
+        }))
+    }
+}
+
+struct JwtBearerEndpoint<'a> {
+    registrar: &'a ClientMap,
+    issuer: &'a mut TokenMap<TestGenerator>,
+}
+
+impl<'a> Endpoint<CraftedRequest> for JwtBearerEndpoint<'a> {
+    type Error = Error<CraftedRequest>;
+
+    fn registrar(&self) -> Option<&(dyn crate::primitives::Registrar + Sync)> {
+        Some(self.registrar)
+    }
+    fn authorizer_mut(&mut self) -> Option<&mut (dyn crate::primitives::Authorizer + Send)> {
+        None
+    }
+    fn issuer_mut(&mut self) -> Option<&mut (dyn crate::primitives::Issuer + Send)> {
+        Some(self.issuer)
+    }
+    fn response(
+        &mut self, _: &mut CraftedRequest, _: oxide_auth::endpoint::Template,
+    ) -> Result<<CraftedRequest as WebRequest>::Response, Self::Error> {
+        Ok(Default::default())
+    }
+    fn error(&mut self, _err: oxide_auth::endpoint::OAuthError) -> Self::Error {
+        unimplemented!()
+    }
+    fn web_error(&mut self, _err: <CraftedRequest as WebRequest>::Error) -> Self::Error {
+        unimplemented!()
+    }
+    fn scopes(&mut self) -> Option<&mut dyn oxide_auth::endpoint::Scopes<CraftedRequest>> {
+        None
+    }
+    fn assertion_verifier(&self) -> Option<&(dyn crate::primitives::AssertionVerifier + Sync)> {
+        Some(&FixedVerifier)
+    }
+    fn owner_solicitor(
+        &mut self,
+    ) -> Option<&mut (dyn crate::endpoint::OwnerSolicitor<CraftedRequest> + Send)> {
+        None
+    }
+}
+
+impl JwtBearerSetup {
+    fn new() -> Self {
+        let mut registrar = ClientMap::new();
+        registrar.register_client(Client::confidential(
+            EXAMPLE_CLIENT_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+            EXAMPLE_PASSPHRASE.as_bytes(),
+        ));
+
+        let basic_authorization =
+            base64::encode(&format!("{}:{}", EXAMPLE_CLIENT_ID, EXAMPLE_PASSPHRASE));
+
+        JwtBearerSetup {
+            registrar,
+            issuer: TokenMap::new(TestGenerator("AccessToken".to_string())),
+            trusted: TrustedIssuers::new(EXAMPLE_AUDIENCE),
+            basic_authorization,
+        }
+    }
+
+    fn request(&self, grant_type: &str, assertion: &str) -> CraftedRequest {
+        CraftedRequest {
+            query: None,
+            urlbody: Some(
+                vec![("grant_type", grant_type), ("assertion", assertion)]
+                    .iter()
+                    .to_single_value_query(),
+            ),
+            auth: Some("Basic ".to_string() + &self.basic_authorization),
+        }
+    }
+
+    fn execute(&mut self, request: CraftedRequest) -> CraftedResponse {
+        let endpoint = JwtBearerEndpoint {
+            registrar: &self.registrar,
+            issuer: &mut self.issuer,
+        };
+        let mut flow = JwtBearerFlow::prepare(endpoint, self.trusted.clone()).unwrap();
+        smol::run(flow.execute(request)).expect("Expected non-error reponse")
+    }
+
+    fn test_error(&mut self, request: CraftedRequest, error: &str) {
+        let response = self.execute(request);
+        match &response.body {
+            Some(Body::Json(ref json)) => {
+                let content: HashMap<String, String> = serde_json::from_str(json).unwrap();
+                assert_eq!(content.get("error").map(String::as_str), Some(error));
+            }
+            other => panic!("Expected json encoded body, got {:?}", other),
+        }
+
+        match response.status {
+            Status::Unauthorized => (),
+            Status::BadRequest => (),
+            _ => panic!("Expected error status, got {:?}", response),
+        }
+    }
+
+    fn test_success(&mut self, request: CraftedRequest) {
+        let response = self.execute(request);
+
+        assert_eq!(response.status, Status::Ok);
+        let token = match response.body {
+            Some(Body::Json(ref json)) => serde_json::from_str::<TokenResponse>(json).unwrap(),
+            other => panic!("Expected json encoded body, got {:?}", other),
+        };
+        assert!(token.access_token.is_some());
+    }
+}
+
+#[test]
+fn jwt_bearer_success() {
+    let mut setup = JwtBearerSetup::new();
+    let valid = setup.request(JWT_BEARER_GRANT_TYPE, EXAMPLE_ASSERTION);
+    setup.test_success(valid);
+}
+
+#[test]
+fn jwt_bearer_rejected_assertion() {
+    let mut setup = JwtBearerSetup::new();
+    let rejected = setup.request(JWT_BEARER_GRANT_TYPE, "NotAValidAssertion");
+    setup.test_error(rejected, "invalid_grant");
+}
+
+#[test]
+fn jwt_bearer_wrong_grant_type() {
+    let mut setup = JwtBearerSetup::new();
+    let wrong_grant_type = setup.request("client_credentials", EXAMPLE_ASSERTION);
+    setup.test_error(wrong_grant_type, "unsupported_grant_type");
+}
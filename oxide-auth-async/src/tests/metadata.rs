@@ -0,0 +1,103 @@
+use oxide_auth::primitives::generator::RandomGenerator;
+use oxide_auth::primitives::issuer::TokenMap;
+use oxide_auth::{
+    code_grant::metadata::Metadata,
+    frontends::simple::endpoint::Error,
+    primitives::registrar::{Client, ClientMap, RegisteredUrl},
+    primitives::scope::Scope,
+    endpoint::WebRequest,
+};
+
+use crate::endpoint::{metadata::MetadataFlow, Endpoint};
+
+use std::collections::HashMap;
+
+use serde_json::{self, Value as JsonValue};
+
+use super::{Body, CraftedRequest, Status};
+use super::defaults::*;
+
+struct MetadataEndpoint<'a> {
+    registrar: &'a ClientMap,
+    issuer: &'a mut TokenMap<RandomGenerator>,
+    scopes: &'a [Scope],
+}
+
+impl<'a> Endpoint<CraftedRequest> for MetadataEndpoint<'a> {
+    type Error = Error<CraftedRequest>;
+
+    fn registrar(&self) -> Option<&(dyn crate::primitives::Registrar + Sync)> {
+        Some(self.registrar)
+    }
+    fn authorizer_mut(&mut self) -> Option<&mut (dyn crate::primitives::Authorizer + Send)> {
+        None
+    }
+    fn issuer_mut(&mut self) -> Option<&mut (dyn crate::primitives::Issuer + Send)> {
+        Some(self.issuer)
+    }
+    fn response(
+        &mut self, _: &mut CraftedRequest, _: oxide_auth::endpoint::Template,
+    ) -> Result<<CraftedRequest as WebRequest>::Response, Self::Error> {
+        Ok(Default::default())
+    }
+    fn error(&mut self, _err: oxide_auth::endpoint::OAuthError) -> Self::Error {
+        unimplemented!()
+    }
+    fn web_error(&mut self, _err: <CraftedRequest as WebRequest>::Error) -> Self::Error {
+        unimplemented!()
+    }
+    fn scopes(&mut self) -> Option<&mut dyn oxide_auth::endpoint::Scopes<CraftedRequest>> {
+        Some(&mut self.scopes)
+    }
+    fn owner_solicitor(
+        &mut self,
+    ) -> Option<&mut (dyn crate::endpoint::OwnerSolicitor<CraftedRequest> + Send)> {
+        None
+    }
+}
+
+#[test]
+fn metadata_token_endpoint() {
+    let mut registrar = ClientMap::new();
+    registrar.register_client(Client::confidential(
+        EXAMPLE_CLIENT_ID,
+        RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+        EXAMPLE_SCOPE.parse().unwrap(),
+        EXAMPLE_PASSPHRASE.as_bytes(),
+    ));
+    let mut issuer = TokenMap::new(RandomGenerator::new(16));
+    let scopes: Vec<Scope> = vec!["introspect".parse().unwrap()];
+
+    let endpoint = MetadataEndpoint {
+        registrar: &registrar,
+        issuer: &mut issuer,
+        scopes: &scopes,
+    };
+    let metadata = Metadata::new("https://example.com".parse().unwrap());
+    let mut flow = MetadataFlow::new(endpoint, metadata);
+
+    let request = CraftedRequest {
+        query: None,
+        urlbody: None,
+        auth: None,
+    };
+    let response = smol::run(flow.execute(request)).expect("Expected non-error reponse");
+    assert_eq!(response.status, Status::Ok);
+
+    let document: HashMap<String, JsonValue> = match &response.body {
+        Some(Body::Json(ref json)) => serde_json::from_str(json).unwrap(),
+        other => panic!("Expected json encoded body, got {:?}", other),
+    };
+
+    assert_eq!(
+        document.get("response_types_supported"),
+        Some(&JsonValue::Array(vec![]))
+    );
+    assert_eq!(
+        document.get("scopes_supported"),
+        Some(&JsonValue::from(vec!["introspect"]))
+    );
+    let grant_types = document.get("grant_types_supported").unwrap().as_array().unwrap();
+    assert!(grant_types.contains(&JsonValue::from("client_credentials")));
+    assert!(!grant_types.contains(&JsonValue::from("authorization_code")));
+}
@@ -225,7 +225,17 @@ pub mod defaults {
 
 mod authorization;
 mod access_token;
+mod client_credentials;
+mod device;
+mod jwks;
+mod jwt_bearer;
+mod metadata;
+mod password;
 mod type_properties;
 mod resource;
 mod refresh;
+mod remote;
+mod introspection;
+mod revoke;
+mod token_exchange;
 // mod pkce;
@@ -0,0 +1,195 @@
+use oxide_auth::primitives::authenticator::OwnerMap;
+use oxide_auth::primitives::issuer::TokenMap;
+use oxide_auth::{
+    code_grant::accesstoken::TokenResponse,
+    frontends::simple::endpoint::Error,
+    primitives::registrar::{Client, ClientMap, RegisteredUrl},
+    endpoint::WebRequest,
+};
+
+use crate::endpoint::{password::PasswordFlow, Endpoint};
+
+use std::collections::HashMap;
+
+use base64;
+use serde_json;
+
+use super::{Body, CraftedRequest, CraftedResponse, Status, TestGenerator, ToSingleValueQuery};
+use super::defaults::*;
+
+const EXAMPLE_USERNAME: &str = "alice";
+const EXAMPLE_PASSWORD: &str = "correct horse battery staple";
+
+struct PasswordSetup {
+    registrar: ClientMap,
+    authenticator: OwnerMap,
+    issuer: TokenMap<TestGenerator>,
+    basic_authorization: String,
+}
+
+struct PasswordEndpoint<'a> {
+    registrar: &'a ClientMap,
+    authenticator: &'a OwnerMap,
+    issuer: &'a mut TokenMap<TestGenerator>,
+}
+
+impl<'a> PasswordEndpoint<'a> {
+    pub fn new(
+        registrar: &'a ClientMap, authenticator: &'a OwnerMap, issuer: &'a mut TokenMap<TestGenerator>,
+    ) -> Self {
+        PasswordEndpoint {
+            registrar,
+            authenticator,
+            issuer,
+        }
+    }
+}
+
+impl<'a> Endpoint<CraftedRequest> for PasswordEndpoint<'a> {
+    type Error = Error<CraftedRequest>;
+
+    fn registrar(&self) -> Option<&(dyn crate::primitives::Registrar + Sync)> {
+        Some(self.registrar)
+    }
+    fn authorizer_mut(&mut self) -> Option<&mut (dyn crate::primitives::Authorizer + Send)> {
+        None
+    }
+    fn issuer_mut(&mut self) -> Option<&mut (dyn crate::primitives::Issuer + Send)> {
+        Some(self.issuer)
+    }
+    fn response(
+        &mut self, _: &mut CraftedRequest, _: oxide_auth::endpoint::Template,
+    ) -> Result<<CraftedRequest as WebRequest>::Response, Self::Error> {
+        Ok(Default::default())
+    }
+    fn error(&mut self, _err: oxide_auth::endpoint::OAuthError) -> Self::Error {
+        unimplemented!()
+    }
+    fn web_error(&mut self, _err: <CraftedRequest as WebRequest>::Error) -> Self::Error {
+        unimplemented!()
+    }
+    fn scopes(&mut self) -> Option<&mut dyn oxide_auth::endpoint::Scopes<CraftedRequest>> {
+        None
+    }
+    fn owner_authenticator(&self) -> Option<&(dyn crate::primitives::OwnerAuthenticator + Sync)> {
+        Some(self.authenticator)
+    }
+    fn owner_solicitor(
+        &mut self,
+    ) -> Option<&mut (dyn crate::endpoint::OwnerSolicitor<CraftedRequest> + Send)> {
+        None
+    }
+}
+
+impl PasswordSetup {
+    fn new(client: Client) -> Self {
+        let mut registrar = ClientMap::new();
+        let mut authenticator = OwnerMap::new();
+        let issuer = TokenMap::new(TestGenerator("AccessToken".to_string()));
+
+        registrar.register_client(client);
+        authenticator.register_owner(EXAMPLE_USERNAME, EXAMPLE_PASSWORD.as_bytes(), EXAMPLE_OWNER_ID);
+
+        let basic_authorization =
+            base64::encode(&format!("{}:{}", EXAMPLE_CLIENT_ID, EXAMPLE_PASSPHRASE));
+
+        PasswordSetup {
+            registrar,
+            authenticator,
+            issuer,
+            basic_authorization,
+        }
+    }
+
+    fn client() -> Client {
+        Client::confidential(
+            EXAMPLE_CLIENT_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+            EXAMPLE_PASSPHRASE.as_bytes(),
+        )
+    }
+
+    fn assert_json_error(response: &CraftedResponse, error: &str) {
+        match &response.body {
+            Some(Body::Json(ref json)) => {
+                let content: HashMap<String, String> = serde_json::from_str(json).unwrap();
+                assert_eq!(content.get("error").map(String::as_str), Some(error));
+            }
+            other => panic!("Expected json encoded body, got {:?}", other),
+        }
+
+        match response.status {
+            Status::Unauthorized => (),
+            Status::BadRequest => (),
+            _ => panic!("Expected error status, got {:?}", response),
+        }
+    }
+
+    fn test_error(&mut self, request: CraftedRequest, error: &str) {
+        let mut flow = PasswordFlow::prepare(PasswordEndpoint::new(
+            &self.registrar,
+            &self.authenticator,
+            &mut self.issuer,
+        ))
+        .unwrap();
+        match smol::run(flow.execute(request)) {
+            Ok(ref response) => Self::assert_json_error(response, error),
+            resp => panic!("Expected non-error reponse, got {:?}", resp),
+        }
+    }
+
+    fn test_success(&mut self, request: CraftedRequest) {
+        let mut flow = PasswordFlow::prepare(PasswordEndpoint::new(
+            &self.registrar,
+            &self.authenticator,
+            &mut self.issuer,
+        ))
+        .unwrap();
+        let response = smol::run(flow.execute(request)).expect("Expected non-error reponse");
+
+        assert_eq!(response.status, Status::Ok);
+        let token = match response.body {
+            Some(Body::Json(ref json)) => serde_json::from_str::<TokenResponse>(json).unwrap(),
+            other => panic!("Expected json encoded body, got {:?}", other),
+        };
+        assert!(token.access_token.is_some());
+    }
+
+    fn request(&self, password: &str) -> CraftedRequest {
+        CraftedRequest {
+            query: None,
+            urlbody: Some(
+                vec![
+                    ("grant_type", "password"),
+                    ("username", EXAMPLE_USERNAME),
+                    ("password", password),
+                ]
+                .iter()
+                .to_single_value_query(),
+            ),
+            auth: Some("Basic ".to_string() + &self.basic_authorization),
+        }
+    }
+}
+
+#[test]
+fn password_success() {
+    let mut setup = PasswordSetup::new(PasswordSetup::client().with_password_grant());
+    let valid = setup.request(EXAMPLE_PASSWORD);
+    setup.test_success(valid);
+}
+
+#[test]
+fn password_client_not_permitted() {
+    let mut setup = PasswordSetup::new(PasswordSetup::client());
+    let valid = setup.request(EXAMPLE_PASSWORD);
+    setup.test_error(valid, "unauthorized_client");
+}
+
+#[test]
+fn password_wrong_owner_password() {
+    let mut setup = PasswordSetup::new(PasswordSetup::client().with_password_grant());
+    let wrong_password = setup.request("NotTheRightPassword");
+    setup.test_error(wrong_password, "invalid_grant");
+}
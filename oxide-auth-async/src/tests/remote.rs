@@ -0,0 +1,362 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use oxide_auth::primitives::dpop;
+use oxide_auth::primitives::grant::{Extensions, Grant};
+use oxide_auth::primitives::mtls;
+use oxide_auth::primitives::scope::Scope;
+use url::Url;
+
+use crate::endpoint::resource::ResourceFlow;
+use crate::primitives::Issuer;
+use crate::remote::{RemoteIssuer, Transport};
+
+use super::CraftedRequest;
+use super::defaults::*;
+use super::resource::ResourceEndpoint;
+
+const ACTIVE_TOKEN: &str = "ActiveToken";
+const OUT_OF_RANGE_TOKEN: &str = "OutOfRangeToken";
+const DPOP_BOUND_TOKEN: &str = "DpopBoundToken";
+const CERTIFICATE_BOUND_TOKEN: &str = "CertificateBoundToken";
+const UNKNOWN_BOUND_TOKEN: &str = "UnknownBoundToken";
+const THUMBPRINT: &str = "0ZcOCORZNYy-DWpqq30jZyJGHTN0d2HglBV3uiguA4I";
+
+/// A recorded request to the stand-in introspection endpoint.
+#[derive(Clone, Debug)]
+struct Recorded {
+    authorization: String,
+    body: String,
+}
+
+/// Serves introspection responses on a local port.
+struct StandIn {
+    url: Url,
+    requests: Arc<Mutex<Vec<Recorded>>>,
+}
+
+impl StandIn {
+    fn start(status: u16) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/introspect", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => return,
+                };
+                Self::serve(stream, status, &recorded);
+            }
+        });
+
+        StandIn {
+            url: url.parse().unwrap(),
+            requests,
+        }
+    }
+
+    fn serve(stream: TcpStream, status: u16, recorded: &Mutex<Vec<Recorded>>) {
+        let mut reader = BufReader::new(stream);
+        let mut authorization = String::new();
+        let mut length = 0;
+
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+
+            let mut header = line.splitn(2, ": ");
+            match (header.next(), header.next()) {
+                (Some("Authorization"), Some(value)) => authorization = value.to_string(),
+                (Some("Content-Length"), Some(value)) => length = value.parse().unwrap(),
+                _ => (),
+            }
+        }
+
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        let body = String::from_utf8(body).unwrap();
+
+        let content = if body.contains(&format!("token={}", ACTIVE_TOKEN)) {
+            format!(
+                r#"{{"active":true,"scope":"{}","client_id":"{}","sub":"{}","exp":{},"token_type":"bearer","aud":"api","confirmed":true}}"#,
+                EXAMPLE_SCOPE,
+                EXAMPLE_CLIENT_ID,
+                EXAMPLE_OWNER_ID,
+                (Utc::now() + Duration::hours(1)).timestamp(),
+            )
+        } else if let Some(cnf) = Self::confirmation(&body) {
+            format!(
+                r#"{{"active":true,"scope":"{}","client_id":"{}","sub":"{}","cnf":{}}}"#,
+                EXAMPLE_SCOPE, EXAMPLE_CLIENT_ID, EXAMPLE_OWNER_ID, cnf,
+            )
+        } else if body.contains(&format!("token={}", OUT_OF_RANGE_TOKEN)) {
+            format!(
+                r#"{{"active":true,"scope":"{}","client_id":"{}","sub":"{}","exp":{}}}"#,
+                EXAMPLE_SCOPE,
+                EXAMPLE_CLIENT_ID,
+                EXAMPLE_OWNER_ID,
+                i64::max_value(),
+            )
+        } else {
+            r#"{"active":false}"#.to_string()
+        };
+
+        recorded.lock().unwrap().push(Recorded { authorization, body });
+
+        let mut stream = reader.into_inner();
+        write!(
+            stream,
+            "HTTP/1.1 {} Status\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            content.len(),
+            content,
+        )
+        .unwrap();
+    }
+
+    fn confirmation(body: &str) -> Option<String> {
+        let method = if body.contains(&format!("token={}&", DPOP_BOUND_TOKEN)) {
+            "jkt"
+        } else if body.contains(&format!("token={}&", CERTIFICATE_BOUND_TOKEN)) {
+            "x5t#S256"
+        } else if body.contains(&format!("token={}&", UNKNOWN_BOUND_TOKEN)) {
+            "x5t"
+        } else {
+            return None;
+        };
+
+        Some(format!(r#"{{"{}":"{}"}}"#, method, THUMBPRINT))
+    }
+
+    fn requests(&self) -> Vec<Recorded> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// A blocking http client, sufficient for the stand-in server.
+struct TestTransport;
+
+#[async_trait]
+impl Transport for TestTransport {
+    async fn post_form(
+        &self, url: &Url, authorization: &str, body: String,
+    ) -> Result<(u16, String), ()> {
+        let address = format!("{}:{}", url.host_str().unwrap(), url.port().unwrap());
+        let mut stream = TcpStream::connect(address).map_err(|_| ())?;
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: {}\r\nAuthorization: {}\r\nContent-Type: application/x-www-form-urlencoded\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            url.path(),
+            url.host_str().unwrap(),
+            authorization,
+            body.len(),
+            body,
+        )
+        .map_err(|_| ())?;
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).map_err(|_| ())?;
+
+        let status = response.split(' ').nth(1).ok_or(())?.parse().map_err(|_| ())?;
+        let start = response.find("\r\n\r\n").ok_or(())? + 4;
+        Ok((status, response[start..].to_string()))
+    }
+}
+
+fn issuer(stand_in: &StandIn) -> RemoteIssuer<TestTransport> {
+    RemoteIssuer::new(
+        TestTransport,
+        stand_in.url.clone(),
+        EXAMPLE_CLIENT_ID,
+        EXAMPLE_PASSPHRASE.as_bytes(),
+    )
+}
+
+#[test]
+fn remote_active_token() {
+    let stand_in = StandIn::start(200);
+    let mut issuer = issuer(&stand_in);
+
+    let grant = smol::run(issuer.recover_token(ACTIVE_TOKEN))
+        .unwrap()
+        .expect("Expected the token to be active");
+    assert_eq!(grant.client_id, EXAMPLE_CLIENT_ID);
+    assert_eq!(grant.owner_id, EXAMPLE_OWNER_ID);
+    assert_eq!(grant.scope, EXAMPLE_SCOPE.parse().unwrap());
+    assert!(grant.until > Utc::now());
+
+    let public: Vec<_> = grant.extensions.public().collect();
+    assert!(public.contains(&("aud", Some("api"))));
+    assert!(public.contains(&("confirmed", None)));
+    assert_eq!(public.len(), 2);
+
+    let requests = stand_in.requests();
+    assert_eq!(requests.len(), 1);
+    let basic = base64::encode(format!("{}:{}", EXAMPLE_CLIENT_ID, EXAMPLE_PASSPHRASE));
+    assert_eq!(requests[0].authorization, format!("Basic {}", basic));
+    assert_eq!(
+        requests[0].body,
+        format!("token={}&token_type_hint=access_token", ACTIVE_TOKEN)
+    );
+}
+
+#[test]
+fn remote_caches_active_token() {
+    let stand_in = StandIn::start(200);
+    let mut issuer = issuer(&stand_in);
+
+    for _ in 0..3 {
+        let grant = smol::run(issuer.recover_token(ACTIVE_TOKEN)).unwrap();
+        assert!(grant.is_some());
+    }
+    assert_eq!(stand_in.requests().len(), 1);
+
+    issuer.set_max_ttl(Duration::zero());
+    issuer.clear();
+    for _ in 0..2 {
+        let grant = smol::run(issuer.recover_token(ACTIVE_TOKEN)).unwrap();
+        assert!(grant.is_some());
+    }
+    assert_eq!(stand_in.requests().len(), 3);
+}
+
+#[test]
+fn remote_inactive_token() {
+    let stand_in = StandIn::start(200);
+    let mut issuer = issuer(&stand_in);
+
+    for _ in 0..2 {
+        let grant = smol::run(issuer.recover_token("NotAnIssuedToken")).unwrap();
+        assert!(grant.is_none());
+    }
+    assert_eq!(stand_in.requests().len(), 1);
+
+    issuer.set_negative_ttl(Duration::zero());
+    issuer.clear();
+    for _ in 0..2 {
+        let grant = smol::run(issuer.recover_token("NotAnIssuedToken")).unwrap();
+        assert!(grant.is_none());
+    }
+    assert_eq!(stand_in.requests().len(), 3);
+}
+
+#[test]
+fn remote_bounded_cache() {
+    let stand_in = StandIn::start(200);
+    let mut issuer = issuer(&stand_in);
+    issuer.set_max_entries(2);
+
+    let tokens = ["FirstToken", "SecondToken", "ThirdToken"];
+    for token in tokens.iter() {
+        assert!(smol::run(issuer.recover_token(token)).unwrap().is_none());
+    }
+    assert_eq!(stand_in.requests().len(), 3);
+
+    // At least one result no longer fits into the cache.
+    for token in tokens.iter() {
+        assert!(smol::run(issuer.recover_token(token)).unwrap().is_none());
+    }
+    assert!(stand_in.requests().len() > 3);
+}
+
+#[test]
+fn remote_out_of_range_expiry() {
+    let stand_in = StandIn::start(200);
+    let mut issuer = issuer(&stand_in);
+
+    assert!(smol::run(issuer.recover_token(OUT_OF_RANGE_TOKEN)).is_err());
+}
+
+#[test]
+fn remote_bearer_authorization() {
+    let stand_in = StandIn::start(200);
+    let mut issuer = RemoteIssuer::with_bearer(TestTransport, stand_in.url.clone(), "ResourceToken");
+
+    assert!(smol::run(issuer.recover_token(ACTIVE_TOKEN)).unwrap().is_some());
+    assert_eq!(stand_in.requests()[0].authorization, "Bearer ResourceToken");
+}
+
+#[test]
+fn remote_error_response() {
+    let stand_in = StandIn::start(401);
+    let mut issuer = issuer(&stand_in);
+
+    assert!(smol::run(issuer.recover_token(ACTIVE_TOKEN)).is_err());
+    // Errors are not cached.
+    assert!(smol::run(issuer.recover_token(ACTIVE_TOKEN)).is_err());
+    assert_eq!(stand_in.requests().len(), 2);
+}
+
+#[test]
+fn remote_can_not_issue() {
+    let stand_in = StandIn::start(200);
+    let mut issuer = issuer(&stand_in);
+
+    let grant = Grant {
+        client_id: EXAMPLE_CLIENT_ID.to_string(),
+        owner_id: EXAMPLE_OWNER_ID.to_string(),
+        redirect_uri: EXAMPLE_REDIRECT_URI.parse().unwrap(),
+        scope: EXAMPLE_SCOPE.parse().unwrap(),
+        until: Utc::now() + Duration::hours(1),
+        extensions: Extensions::new(),
+    };
+    assert!(smol::run(issuer.issue(grant)).is_err());
+    assert!(smol::run(issuer.recover_refresh(ACTIVE_TOKEN)).is_err());
+    assert!(stand_in.requests().is_empty());
+}
+
+/// Access a resource through the remote issuer with a plain bearer token.
+fn access(issuer: &mut RemoteIssuer<TestTransport>, token: &str) -> bool {
+    let mut scopes: [Scope; 1] = [EXAMPLE_SCOPE.parse().unwrap()];
+    let request = CraftedRequest {
+        query: None,
+        urlbody: None,
+        auth: Some(format!("Bearer {}", token)),
+    };
+
+    let mut flow = ResourceFlow::prepare(ResourceEndpoint::new(issuer, &mut scopes)).unwrap();
+    smol::run(flow.execute(request)).is_ok()
+}
+
+#[test]
+fn remote_bound_tokens() {
+    let stand_in = StandIn::start(200);
+    let mut issuer = issuer(&stand_in);
+
+    let grant = smol::run(issuer.recover_token(DPOP_BOUND_TOKEN))
+        .unwrap()
+        .unwrap();
+    assert_eq!(dpop::confirmation(&grant), Some(THUMBPRINT));
+    assert_eq!(grant.extensions.public().count(), 1);
+
+    let grant = smol::run(issuer.recover_token(CERTIFICATE_BOUND_TOKEN))
+        .unwrap()
+        .unwrap();
+    assert_eq!(mtls::confirmation(&grant), Some(THUMBPRINT));
+    assert_eq!(grant.extensions.public().count(), 1);
+
+    // A binding the resource server can not check is refused.
+    assert!(smol::run(issuer.recover_token(UNKNOWN_BOUND_TOKEN)).is_err());
+}
+
+#[test]
+fn remote_bound_tokens_require_proof() {
+    let stand_in = StandIn::start(200);
+    let mut issuer = issuer(&stand_in);
+
+    assert!(access(&mut issuer, ACTIVE_TOKEN));
+    // Neither a DPoP proof nor a client certificate is presented.
+    assert!(!access(&mut issuer, DPOP_BOUND_TOKEN));
+    assert!(!access(&mut issuer, CERTIFICATE_BOUND_TOKEN));
+}
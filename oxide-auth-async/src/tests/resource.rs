@@ -10,7 +10,7 @@ use super::defaults::*;
 use crate::endpoint::{resource::ResourceFlow, Endpoint};
 
 pub struct ResourceEndpoint<'a> {
-    issuer: &'a mut TokenMap<RandomGenerator>,
+    issuer: &'a mut (dyn crate::primitives::Issuer + Send + Sync),
     scopes: &'a mut [Scope],
 }
 
@@ -48,7 +48,9 @@ impl<'a> Endpoint<CraftedRequest> for ResourceEndpoint<'a> {
 }
 
 impl<'a> ResourceEndpoint<'a> {
-    pub fn new(issuer: &'a mut TokenMap<RandomGenerator>, scopes: &'a mut [Scope]) -> Self {
+    pub fn new(
+        issuer: &'a mut (dyn crate::primitives::Issuer + Send + Sync), scopes: &'a mut [Scope],
+    ) -> Self {
         Self { issuer, scopes }
     }
 }
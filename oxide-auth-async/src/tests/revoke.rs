@@ -0,0 +1,183 @@
+use oxide_auth::primitives::generator::RandomGenerator;
+use oxide_auth::primitives::issuer::{self, IssuedToken, TokenMap};
+use oxide_auth::{
+    frontends::simple::endpoint::Error,
+    primitives::grant::{Extensions, Grant},
+    primitives::registrar::{Client, ClientMap, RegisteredUrl},
+    endpoint::WebRequest,
+};
+
+use crate::endpoint::{revoke::RevokeFlow, Endpoint};
+
+use std::collections::HashMap;
+
+use base64;
+use chrono::{Duration, Utc};
+use serde_json;
+
+use super::{Body, CraftedRequest, CraftedResponse, Status, ToSingleValueQuery};
+use super::defaults::*;
+
+struct RevokeSetup {
+    registrar: ClientMap,
+    issuer: TokenMap<RandomGenerator>,
+    issued: IssuedToken,
+    basic_authorization: String,
+}
+
+struct RevokeEndpoint<'a> {
+    registrar: &'a ClientMap,
+    issuer: &'a mut TokenMap<RandomGenerator>,
+}
+
+impl<'a> Endpoint<CraftedRequest> for RevokeEndpoint<'a> {
+    type Error = Error<CraftedRequest>;
+
+    fn registrar(&self) -> Option<&(dyn crate::primitives::Registrar + Sync)> {
+        Some(self.registrar)
+    }
+    fn authorizer_mut(&mut self) -> Option<&mut (dyn crate::primitives::Authorizer + Send)> {
+        None
+    }
+    fn issuer_mut(&mut self) -> Option<&mut (dyn crate::primitives::Issuer + Send)> {
+        Some(self.issuer)
+    }
+    fn response(
+        &mut self, _: &mut CraftedRequest, _: oxide_auth::endpoint::Template,
+    ) -> Result<<CraftedRequest as WebRequest>::Response, Self::Error> {
+        Ok(Default::default())
+    }
+    fn error(&mut self, _err: oxide_auth::endpoint::OAuthError) -> Self::Error {
+        unimplemented!()
+    }
+    fn web_error(&mut self, _err: <CraftedRequest as WebRequest>::Error) -> Self::Error {
+        unimplemented!()
+    }
+    fn scopes(&mut self) -> Option<&mut dyn oxide_auth::endpoint::Scopes<CraftedRequest>> {
+        None
+    }
+    fn owner_solicitor(
+        &mut self,
+    ) -> Option<&mut (dyn crate::endpoint::OwnerSolicitor<CraftedRequest> + Send)> {
+        None
+    }
+}
+
+impl RevokeSetup {
+    fn new() -> Self {
+        let mut registrar = ClientMap::new();
+        registrar.register_client(Client::confidential(
+            EXAMPLE_CLIENT_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+            EXAMPLE_PASSPHRASE.as_bytes(),
+        ));
+
+        let mut issuer = TokenMap::new(RandomGenerator::new(16));
+        let issued = issuer::Issuer::issue(&mut issuer, Self::grant(EXAMPLE_CLIENT_ID)).unwrap();
+
+        let basic_authorization =
+            base64::encode(&format!("{}:{}", EXAMPLE_CLIENT_ID, EXAMPLE_PASSPHRASE));
+
+        RevokeSetup {
+            registrar,
+            issuer,
+            issued,
+            basic_authorization,
+        }
+    }
+
+    fn grant(client_id: &str) -> Grant {
+        Grant {
+            client_id: client_id.to_string(),
+            owner_id: EXAMPLE_OWNER_ID.to_string(),
+            redirect_uri: EXAMPLE_REDIRECT_URI.parse().unwrap(),
+            scope: EXAMPLE_SCOPE.parse().unwrap(),
+            until: Utc::now() + Duration::hours(1),
+            extensions: Extensions::new(),
+        }
+    }
+
+    fn request(&self, token: &str, hint: Option<&str>) -> CraftedRequest {
+        let mut params = vec![("token", token)];
+        params.extend(hint.map(|hint| ("token_type_hint", hint)));
+        CraftedRequest {
+            query: None,
+            urlbody: Some(params.iter().to_single_value_query()),
+            auth: Some("Basic ".to_string() + &self.basic_authorization),
+        }
+    }
+
+    fn execute(&mut self, request: CraftedRequest) -> CraftedResponse {
+        let endpoint = RevokeEndpoint {
+            registrar: &self.registrar,
+            issuer: &mut self.issuer,
+        };
+        let mut flow = RevokeFlow::prepare(endpoint).unwrap();
+        smol::run(flow.execute(request)).expect("Expected non-error reponse")
+    }
+
+    fn test_error(&mut self, request: CraftedRequest, error: &str) {
+        let response = self.execute(request);
+        match &response.body {
+            Some(Body::Json(ref json)) => {
+                let content: HashMap<String, String> = serde_json::from_str(json).unwrap();
+                assert_eq!(content.get("error").map(String::as_str), Some(error));
+            }
+            other => panic!("Expected json encoded body, got {:?}", other),
+        }
+
+        match response.status {
+            Status::Unauthorized => (),
+            Status::BadRequest => (),
+            _ => panic!("Expected error status, got {:?}", response),
+        }
+    }
+
+    fn test_success(&mut self, request: CraftedRequest) {
+        let response = self.execute(request);
+        assert_eq!(response.status, Status::Ok);
+        assert!(response.body.is_none());
+    }
+
+    fn assert_revoked(&self, revoked: bool) {
+        let access = issuer::Issuer::recover_token(&self.issuer, &self.issued.token).unwrap();
+        let refresh = issuer::Issuer::recover_refresh(&self.issuer, self.issued.refresh.as_ref().unwrap())
+            .unwrap();
+        assert_eq!(access.is_none(), revoked);
+        assert_eq!(refresh.is_none(), revoked);
+    }
+}
+
+#[test]
+fn revoke_access_token() {
+    let mut setup = RevokeSetup::new();
+    let request = setup.request(&setup.issued.token, Some("access_token"));
+    setup.test_success(request);
+    setup.assert_revoked(true);
+}
+
+#[test]
+fn revoke_refresh_token_wrong_hint() {
+    let mut setup = RevokeSetup::new();
+    let request = setup.request(setup.issued.refresh.as_ref().unwrap(), Some("access_token"));
+    setup.test_success(request);
+    setup.assert_revoked(true);
+}
+
+#[test]
+fn revoke_unknown_token() {
+    let mut setup = RevokeSetup::new();
+    let request = setup.request("NotAnIssuedToken", None);
+    setup.test_success(request);
+    setup.assert_revoked(false);
+}
+
+#[test]
+fn revoke_other_client_token() {
+    let mut setup = RevokeSetup::new();
+    setup.issued = issuer::Issuer::issue(&mut setup.issuer, RevokeSetup::grant("OtherClient")).unwrap();
+    let request = setup.request(&setup.issued.token, None);
+    setup.test_error(request, "unauthorized_client");
+    setup.assert_revoked(false);
+}
@@ -0,0 +1,213 @@
+use oxide_auth::primitives::generator::RandomGenerator;
+use oxide_auth::primitives::issuer::{self, TokenMap};
+use oxide_auth::{
+    code_grant::{
+        accesstoken::TokenResponse,
+        token_exchange::{ACCESS_TOKEN_TYPE, ACTOR_EXTENSION, TOKEN_EXCHANGE_GRANT_TYPE},
+    },
+    frontends::simple::endpoint::Error,
+    primitives::grant::{Extensions, Grant},
+    primitives::registrar::{Client, ClientMap, RegisteredUrl},
+    endpoint::WebRequest,
+};
+
+use crate::endpoint::{token_exchange::TokenExchangeFlow, Endpoint};
+
+use std::collections::HashMap;
+
+use base64;
+use chrono::{Duration, Utc};
+use serde_json;
+
+use super::{Body, CraftedRequest, CraftedResponse, Status, ToSingleValueQuery};
+use super::defaults::*;
+
+const EXAMPLE_ACTOR: &str = "Gateway";
+
+struct TokenExchangeSetup {
+    registrar: ClientMap,
+    issuer: TokenMap<RandomGenerator>,
+    subject_token: String,
+    actor_token: String,
+    basic_authorization: String,
+}
+
+struct TokenExchangeEndpoint<'a> {
+    registrar: &'a ClientMap,
+    issuer: &'a mut TokenMap<RandomGenerator>,
+}
+
+impl<'a> Endpoint<CraftedRequest> for TokenExchangeEndpoint<'a> {
+    type Error = Error<CraftedRequest>;
+
+    fn registrar(&self) -> Option<&(dyn crate::primitives::Registrar + Sync)> {
+        Some(self.registrar)
+    }
+    fn authorizer_mut(&mut self) -> Option<&mut (dyn crate::primitives::Authorizer + Send)> {
+        None
+    }
+    fn issuer_mut(&mut self) -> Option<&mut (dyn crate::primitives::Issuer + Send)> {
+        Some(self.issuer)
+    }
+    fn response(
+        &mut self, _: &mut CraftedRequest, _: oxide_auth::endpoint::Template,
+    ) -> Result<<CraftedRequest as WebRequest>::Response, Self::Error> {
+        Ok(Default::default())
+    }
+    fn error(&mut self, _err: oxide_auth::endpoint::OAuthError) -> Self::Error {
+        unimplemented!()
+    }
+    fn web_error(&mut self, _err: <CraftedRequest as WebRequest>::Error) -> Self::Error {
+        unimplemented!()
+    }
+    fn scopes(&mut self) -> Option<&mut dyn oxide_auth::endpoint::Scopes<CraftedRequest>> {
+        None
+    }
+    fn owner_solicitor(
+        &mut self,
+    ) -> Option<&mut (dyn crate::endpoint::OwnerSolicitor<CraftedRequest> + Send)> {
+        None
+    }
+}
+
+impl TokenExchangeSetup {
+    fn new() -> Self {
+        let mut registrar = ClientMap::new();
+        registrar.register_client(Client::confidential(
+            EXAMPLE_CLIENT_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+            EXAMPLE_PASSPHRASE.as_bytes(),
+        ));
+
+        let mut issuer = TokenMap::new(RandomGenerator::new(16));
+        let subject_token = issuer::Issuer::issue(&mut issuer, Self::grant("OtherClient", EXAMPLE_OWNER_ID))
+            .unwrap()
+            .token;
+        let actor_token = issuer::Issuer::issue(&mut issuer, Self::grant(EXAMPLE_CLIENT_ID, EXAMPLE_ACTOR))
+            .unwrap()
+            .token;
+
+        let basic_authorization =
+            base64::encode(&format!("{}:{}", EXAMPLE_CLIENT_ID, EXAMPLE_PASSPHRASE));
+
+        TokenExchangeSetup {
+            registrar,
+            issuer,
+            subject_token,
+            actor_token,
+            basic_authorization,
+        }
+    }
+
+    fn grant(client_id: &str, owner_id: &str) -> Grant {
+        Grant {
+            client_id: client_id.to_string(),
+            owner_id: owner_id.to_string(),
+            redirect_uri: EXAMPLE_REDIRECT_URI.parse().unwrap(),
+            scope: EXAMPLE_SCOPE.parse().unwrap(),
+            until: Utc::now() + Duration::hours(1),
+            extensions: Extensions::new(),
+        }
+    }
+
+    fn request(&self, grant_type: &str, scope: Option<&str>, actor: bool) -> CraftedRequest {
+        let mut params = vec![
+            ("grant_type", grant_type),
+            ("subject_token", &self.subject_token),
+            ("subject_token_type", ACCESS_TOKEN_TYPE),
+        ];
+        params.extend(scope.map(|scope| ("scope", scope)));
+        if actor {
+            params.push(("actor_token", &self.actor_token));
+            params.push(("actor_token_type", ACCESS_TOKEN_TYPE));
+        }
+
+        CraftedRequest {
+            query: None,
+            urlbody: Some(params.iter().to_single_value_query()),
+            auth: Some("Basic ".to_string() + &self.basic_authorization),
+        }
+    }
+
+    fn execute(&mut self, request: CraftedRequest) -> CraftedResponse {
+        let endpoint = TokenExchangeEndpoint {
+            registrar: &self.registrar,
+            issuer: &mut self.issuer,
+        };
+        let mut flow = TokenExchangeFlow::prepare(endpoint).unwrap();
+        smol::run(flow.execute(request)).expect("Expected non-error reponse")
+    }
+
+    fn test_error(&mut self, request: CraftedRequest, error: &str) {
+        let response = self.execute(request);
+        match &response.body {
+            Some(Body::Json(ref json)) => {
+                let content: HashMap<String, String> = serde_json::from_str(json).unwrap();
+                assert_eq!(content.get("error").map(String::as_str), Some(error));
+            }
+            other => panic!("Expected json encoded body, got {:?}", other),
+        }
+
+        match response.status {
+            Status::Unauthorized => (),
+            Status::BadRequest => (),
+            _ => panic!("Expected error status, got {:?}", response),
+        }
+    }
+
+    fn test_success(&mut self, request: CraftedRequest) -> Grant {
+        let response = self.execute(request);
+
+        assert_eq!(response.status, Status::Ok);
+        let token = match response.body {
+            Some(Body::Json(ref json)) => serde_json::from_str::<TokenResponse>(json).unwrap(),
+            other => panic!("Expected json encoded body, got {:?}", other),
+        };
+        assert_eq!(token.issued_token_type.as_deref(), Some(ACCESS_TOKEN_TYPE));
+
+        let access_token = token.access_token.expect("Expected an access token");
+        let grant = issuer::Issuer::recover_token(&self.issuer, &access_token)
+            .unwrap()
+            .expect("Issued token should be recoverable");
+        assert_eq!(grant.owner_id, EXAMPLE_OWNER_ID);
+        grant
+    }
+}
+
+#[test]
+fn token_exchange_narrower_scope() {
+    let mut setup = TokenExchangeSetup::new();
+    let valid = setup.request(TOKEN_EXCHANGE_GRANT_TYPE, Some("example"), false);
+    let grant = setup.test_success(valid);
+    assert_eq!(grant.scope, "example".parse().unwrap());
+}
+
+#[test]
+fn token_exchange_delegation() {
+    let mut setup = TokenExchangeSetup::new();
+    let delegated = setup.request(TOKEN_EXCHANGE_GRANT_TYPE, None, true);
+    let grant = setup.test_success(delegated);
+
+    let actors = grant
+        .extensions
+        .public()
+        .find(|&(key, _)| key == ACTOR_EXTENSION)
+        .and_then(|(_, value)| value)
+        .map(|value| serde_json::from_str::<Vec<String>>(value).unwrap());
+    assert_eq!(actors, Some(vec![EXAMPLE_ACTOR.to_string()]));
+}
+
+#[test]
+fn token_exchange_scope_too_large() {
+    let mut setup = TokenExchangeSetup::new();
+    let larger = setup.request(TOKEN_EXCHANGE_GRANT_TYPE, Some("example default more"), false);
+    setup.test_error(larger, "invalid_scope");
+}
+
+#[test]
+fn token_exchange_wrong_grant_type() {
+    let mut setup = TokenExchangeSetup::new();
+    let wrong_grant_type = setup.request("client_credentials", None, false);
+    setup.test_error(wrong_grant_type, "unsupported_grant_type");
+}
@@ -1,5 +1,8 @@
 use oxide_auth::primitives::prelude::Scope;
-use oxide_auth::primitives::registrar::{ClientType, EncodedClient, RegisteredUrl, ExactUrl};
+use oxide_auth::primitives::registrar::{
+    ClientMetadata, ClientType, EncodedClient, RegisteredUrl, ExactUrl, LoopbackUrl, PrivateUseUrl,
+};
+use url::Url;
 use cdrs::types::prelude::*;
 use cdrs::types::from_cdrs::FromCDRSByName;
 use cdrs::frame::IntoBytes;
@@ -18,7 +21,7 @@ pub struct StringfiedEncodedClient {
     /// to the instance.
     pub client_id: String,
 
-    /// The registered redirect uri.
+    /// The registered redirect uri, see `encode_url` for its format.
     /// Unlike `additional_redirect_uris`, this is registered as the default redirect uri
     /// and will be replaced if, for example, no `redirect_uri` is specified in the request parameter.
     pub redirect_uri: Option<String>,
@@ -30,24 +33,71 @@ pub struct StringfiedEncodedClient {
     /// The scope the client gets if none was given.
     pub default_scope: Option<String>,
 
+    /// The largest scope the client may request, its default scope if not set.
+    pub max_scope: Option<String>,
+
     /// client_secret, for authentication.
     pub client_secret: Option<String>,
+
+    /// The subject distinguished name of the certificate of a `tls_client_auth` client.
+    pub tls_client_auth_subject_dn: Option<String>,
+
+    /// The thumbprint of the certificate of a `self_signed_tls_client_auth` client.
+    pub tls_client_certificate_thumbprint: Option<String>,
+
+    /// The encoded registration access token of a dynamically registered client.
+    pub registration_access_token: Option<String>,
+
+    /// The name of the client, as presented to the resource owner.
+    pub client_name: Option<String>,
+
+    /// An url referencing a logo of the client.
+    pub logo_uri: Option<String>,
+
+    /// An url pointing to the policy of the client on how it uses the data of resource owners.
+    pub policy_uri: Option<String>,
+
+    /// An url pointing to the terms of service of the client.
+    pub tos_uri: Option<String>,
+
+    /// Ways to contact the people responsible for the client.
+    pub contacts: Option<Vec<String>>,
+
+    /// The grant types the client may use.
+    pub grant_types: Option<Vec<String>>,
+
+    /// The response types the client may use at the authorization endpoint.
+    pub response_types: Option<Vec<String>>,
+
+    /// How the client authenticates at the token endpoint.
+    pub token_endpoint_auth_method: Option<String>,
 }
 
 impl StringfiedEncodedClient {
     pub fn to_encoded_client(&self) -> anyhow::Result<EncodedClient> {
-        let redirect_uri = RegisteredUrl::from(ExactUrl::from_str(&self.redirect_uri.clone().unwrap_or_default())?);
-        let uris = &self.additional_redirect_uris.clone().unwrap_or_default();
-        let additional_redirect_uris = uris.iter().fold(vec![], |mut us, u| {
-            us.push(RegisteredUrl::from(ExactUrl::from_str(u).unwrap()));
-            us
-        });
-
-        let client_type = match &self.client_secret {
-            None => ClientType::Public,
-            Some(secret) => ClientType::Confidential {
+        let redirect_uri = decode_url(self.redirect_uri.as_deref().unwrap_or_default())?;
+        let additional_redirect_uris = self
+            .additional_redirect_uris
+            .iter()
+            .flatten()
+            .map(|u| decode_url(u))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let client_type = match (
+            &self.client_secret,
+            &self.tls_client_auth_subject_dn,
+            &self.tls_client_certificate_thumbprint,
+        ) {
+            (Some(secret), _, _) => ClientType::Confidential {
                 passdata: secret.to_owned().into_bytes(),
             },
+            (None, Some(subject), _) => ClientType::TlsClientAuth {
+                subject: subject.to_owned(),
+            },
+            (None, None, Some(thumbprint)) => ClientType::SelfSignedTlsClientAuth {
+                thumbprint: thumbprint.to_owned(),
+            },
+            (None, None, None) => ClientType::Public,
         };
 
         Ok(EncodedClient {
@@ -58,7 +108,27 @@ impl StringfiedEncodedClient {
                 self.default_scope.as_ref().unwrap_or(&"".to_string()).as_ref(),
             )
                 .unwrap(),
+            max_scope: match &self.max_scope {
+                Some(scope) => {
+                    Some(Scope::from_str(scope).map_err(|err| anyhow::anyhow!("{}", err))?)
+                }
+                None => None,
+            },
             encoded_client: client_type,
+            registration_access_token: self
+                .registration_access_token
+                .as_ref()
+                .map(|token| token.to_owned().into_bytes()),
+            metadata: ClientMetadata {
+                client_name: self.client_name.clone(),
+                logo_uri: self.logo_uri.clone(),
+                policy_uri: self.policy_uri.clone(),
+                tos_uri: self.tos_uri.clone(),
+                contacts: self.contacts.clone().unwrap_or_default(),
+                grant_types: self.grant_types.clone().unwrap_or_default(),
+                response_types: self.response_types.clone().unwrap_or_default(),
+                token_endpoint_auth_method: self.token_endpoint_auth_method.clone(),
+            },
         })
     }
 
@@ -70,21 +140,90 @@ impl StringfiedEncodedClient {
                 Some(encoded_client
                     .additional_redirect_uris
                     .iter()
-                    .map(|u| u.to_owned().as_str().parse().unwrap())
+                    .map(encode_url)
                     .collect())
             }
         };
         let default_scope = Some(encoded_client.default_scope.to_string());
         let client_secret = match &encoded_client.encoded_client {
-            ClientType::Public => None,
             ClientType::Confidential { passdata } => Some(String::from_utf8(passdata.to_vec()).unwrap()),
+            _ => None,
+        };
+        let tls_client_auth_subject_dn = match &encoded_client.encoded_client {
+            ClientType::TlsClientAuth { subject } => Some(subject.to_owned()),
+            _ => None,
+        };
+        let tls_client_certificate_thumbprint = match &encoded_client.encoded_client {
+            ClientType::SelfSignedTlsClientAuth { thumbprint } => Some(thumbprint.to_owned()),
+            _ => None,
         };
+        let registration_access_token = encoded_client
+            .registration_access_token
+            .as_ref()
+            .map(|token| String::from_utf8(token.to_vec()).unwrap());
+        let metadata = &encoded_client.metadata;
         StringfiedEncodedClient {
             client_id: encoded_client.client_id.to_owned(),
-            redirect_uri: Some(encoded_client.redirect_uri.to_owned().as_str().parse().unwrap()),
+            redirect_uri: Some(encode_url(&encoded_client.redirect_uri)),
             additional_redirect_uris,
             default_scope,
+            max_scope: encoded_client.max_scope.as_ref().map(Scope::to_string),
             client_secret,
+            tls_client_auth_subject_dn,
+            tls_client_certificate_thumbprint,
+            registration_access_token,
+            client_name: metadata.client_name.clone(),
+            logo_uri: metadata.logo_uri.clone(),
+            policy_uri: metadata.policy_uri.clone(),
+            tos_uri: metadata.tos_uri.clone(),
+            contacts: non_empty(&metadata.contacts),
+            grant_types: non_empty(&metadata.grant_types),
+            response_types: non_empty(&metadata.response_types),
+            token_endpoint_auth_method: metadata.token_endpoint_auth_method.clone(),
         }
     }
-}
\ No newline at end of file
+}
+
+/// Prefix of stored loopback redirect uris.
+const LOOPBACK_TAG: &str = "loopback:";
+/// Prefix of stored private-use scheme redirect uris.
+const PRIVATE_USE_TAG: &str = "private:";
+/// Prefix of stored semantic redirect uris.
+const SEMANTIC_TAG: &str = "semantic:";
+
+/// Store a redirect uri together with the way it is matched.
+///
+/// Exact urls are stored as they are, all other variants are prefixed with a tag. None of the tags
+/// is a private-use scheme since they contain no period.
+fn encode_url(url: &RegisteredUrl) -> String {
+    match url {
+        RegisteredUrl::Exact(url) => url.as_str().to_string(),
+        RegisteredUrl::Semantic(url) => format!("{}{}", SEMANTIC_TAG, url),
+        RegisteredUrl::Loopback(url) => format!("{}{}", LOOPBACK_TAG, url.as_str()),
+        RegisteredUrl::PrivateUse(url) => format!("{}{}", PRIVATE_USE_TAG, url.as_str()),
+        // Variants added later are stored as exact urls until they get a tag of their own.
+        other => other.as_str().to_string(),
+    }
+}
+
+/// Recover a redirect uri stored by `encode_url`.
+fn decode_url(stored: &str) -> anyhow::Result<RegisteredUrl> {
+    let url = if let Some(url) = stored.strip_prefix(LOOPBACK_TAG) {
+        RegisteredUrl::from(LoopbackUrl::from_str(url)?)
+    } else if let Some(url) = stored.strip_prefix(PRIVATE_USE_TAG) {
+        RegisteredUrl::from(PrivateUseUrl::from_str(url)?)
+    } else if let Some(url) = stored.strip_prefix(SEMANTIC_TAG) {
+        RegisteredUrl::from(Url::parse(url)?)
+    } else {
+        RegisteredUrl::from(ExactUrl::from_str(stored)?)
+    };
+    Ok(url)
+}
+
+fn non_empty(list: &[String]) -> Option<Vec<String>> {
+    if list.is_empty() {
+        None
+    } else {
+        Some(list.to_vec())
+    }
+}
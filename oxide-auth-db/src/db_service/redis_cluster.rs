@@ -85,4 +85,8 @@ impl OauthClientDBRepository for RedisClusterDataSource {
         let detail = StringfiedEncodedClient::from_encoded_client(&client);
         self.regist_to_cache(&detail)
     }
+
+    fn delete_client_by_id(&self, id: &str) -> anyhow::Result<()> {
+        self.delete_from_cache(id)
+    }
 }
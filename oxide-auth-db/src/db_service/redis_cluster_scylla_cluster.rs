@@ -107,7 +107,7 @@ impl OauthClientDBRepository for RedisClusterScyllaCluster {
             }
         };
         if &client_str == ""{
-            let smt = format!("SELECT client_id, client_secret, redirect_uri, additional_redirect_uris, scopes as default_scope FROM {}.{} where client_id = ?", self.db_name, self.db_table);
+            let smt = format!("SELECT client_id, client_secret, redirect_uri, additional_redirect_uris, scopes as default_scope, max_scope, tls_client_auth_subject_dn, tls_client_certificate_thumbprint, registration_access_token, client_name, logo_uri, policy_uri, tos_uri, contacts, grant_types, response_types, token_endpoint_auth_method FROM {}.{} where client_id = ?", self.db_name, self.db_table);
             let r = self.scylla_session.query_with_values(smt, query_values!(id))?
                 .get_body()?
                 .into_rows().ok_or(anyhow::Error::msg("Record Not Found"))?;
@@ -130,4 +130,10 @@ impl OauthClientDBRepository for RedisClusterScyllaCluster {
         let detail = StringfiedEncodedClient::from_encoded_client(&client);
         self.regist_to_cache(&detail)
     }
+
+    fn delete_client_by_id(&self, id: &str) -> anyhow::Result<()> {
+        let smt = format!("DELETE FROM {}.{} WHERE client_id = ?", self.db_name, self.db_table);
+        self.scylla_session.query_with_values(smt, query_values!(id))?;
+        self.delete_from_cache(id)
+    }
 }
@@ -72,4 +72,8 @@ impl OauthClientDBRepository for RedisDataSource {
         let detail = StringfiedEncodedClient::from_encoded_client(&client);
         self.regist_to_cache(&detail)
     }
+
+    fn delete_client_by_id(&self, id: &str) -> anyhow::Result<()> {
+        self.delete_from_cache(id)
+    }
 }
@@ -60,7 +60,7 @@ impl OauthClientDBRepository for ScyllaClusterDataSource {
     }
 
     fn find_client_by_id(&self, id: &str) -> anyhow::Result<EncodedClient> {
-        let smt = format!("SELECT client_id, client_secret, redirect_uri, additional_redirect_uris, scopes as default_scope FROM {}.{} where client_id = ?", self.db_name, self.table_name);
+        let smt = format!("SELECT client_id, client_secret, redirect_uri, additional_redirect_uris, scopes as default_scope, max_scope, tls_client_auth_subject_dn, tls_client_certificate_thumbprint, registration_access_token, client_name, logo_uri, policy_uri, tos_uri, contacts, grant_types, response_types, token_endpoint_auth_method FROM {}.{} where client_id = ?", self.db_name, self.table_name);
         let r = self.session.query_with_values(smt, query_values!(id))?
             .get_body()?
             .into_rows().ok_or(anyhow::Error::msg("Record Not Found"))?;
@@ -76,5 +76,11 @@ impl OauthClientDBRepository for ScyllaClusterDataSource {
     fn regist_from_encoded_client(&self, client: EncodedClient) -> anyhow::Result<()> {
         self.regist(client)
     }
+
+    fn delete_client_by_id(&self, id: &str) -> anyhow::Result<()> {
+        let smt = format!("DELETE FROM {}.{} WHERE client_id = ?", self.db_name, self.table_name);
+        self.session.query_with_values(smt, query_values!(id))?;
+        Ok(())
+    }
 }
 
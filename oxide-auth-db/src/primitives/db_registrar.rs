@@ -3,8 +3,9 @@ use std::iter::Extend;
 use once_cell::sync::Lazy;
 use oxide_auth::primitives::registrar::{
     Argon2, BoundClient, Client, EncodedClient, PasswordPolicy, RegisteredClient, Registrar,
-    RegistrarError,
+    RegistrarError, RegistrarMut, ScopePolicy,
 };
+use oxide_auth::primitives::mtls::ClientCertificate;
 use oxide_auth::primitives::prelude::{ClientUrl, PreGrant, Scope};
 
 use crate::db_service::DataSource;
@@ -12,9 +13,11 @@ use crate::db_service::DataSource;
 /// A database client service which implemented Registrar.
 /// db: repository service to query stored clients or regist new client.
 /// password_policy: to encode client_secret.
+/// scope_policy: to limit requested scopes to the maximum scope of a client.
 pub struct DBRegistrar {
     pub repo: DataSource,
     password_policy: Option<Box<dyn PasswordPolicy>>,
+    scope_policy: ScopePolicy,
 }
 
 /// methods to search and regist clients from DataSource.
@@ -25,6 +28,8 @@ pub trait OauthClientDBRepository {
     fn find_client_by_id(&self, id: &str) -> anyhow::Result<EncodedClient>;
 
     fn regist_from_encoded_client(&self, client: EncodedClient) -> anyhow::Result<()>;
+
+    fn delete_client_by_id(&self, id: &str) -> anyhow::Result<()>;
 }
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
@@ -39,6 +44,7 @@ impl DBRegistrar {
         DBRegistrar {
             repo,
             password_policy: None,
+            scope_policy: ScopePolicy::default(),
         }
     }
 
@@ -57,6 +63,11 @@ impl DBRegistrar {
         self.password_policy = Some(Box::new(new_policy))
     }
 
+    /// Change how requested scopes exceeding the maximum scope of a client are treated.
+    pub fn set_scope_policy(&mut self, new_policy: ScopePolicy) {
+        self.scope_policy = new_policy
+    }
+
     // This is not an instance method because it needs to borrow the box but register needs &mut
     fn current_policy<'a>(policy: &'a Option<Box<dyn PasswordPolicy>>) -> &'a dyn PasswordPolicy {
         policy
@@ -95,7 +106,7 @@ impl Registrar for DBRegistrar{
                     .chain(alternatives)
                     .find(|&registered| *registered == *url.as_ref())
                 {
-                    registered.clone()
+                    registered.bind(url.as_ref())
                 } else {
                     return Err(RegistrarError::Unspecified);
                 }
@@ -108,16 +119,18 @@ impl Registrar for DBRegistrar{
     }
 
     fn negotiate<'a>(
-        &self, bound: BoundClient<'a>, _scope: Option<Scope>,
+        &self, bound: BoundClient<'a>, scope: Option<Scope>,
     ) -> Result<PreGrant, RegistrarError> {
         let client = self
             .repo
             .find_client_by_id(&bound.client_id)
             .map_err(|_e| RegistrarError::Unspecified)?;
+        let scope = self.scope_policy.negotiate(&client, scope)?;
         Ok(PreGrant {
             client_id: bound.client_id.into_owned(),
             redirect_uri: bound.redirect_uri.into_owned(),
-            scope: client.default_scope,
+            scope,
+            metadata: client.metadata,
         })
     }
 
@@ -139,6 +152,69 @@ impl Registrar for DBRegistrar{
         })?;
         Ok(())
     }
+
+    fn check_certificate(&self, client_id: &str, certificate: &ClientCertificate) -> Result<(), RegistrarError> {
+        let client = self
+            .repo
+            .find_client_by_id(client_id)
+            .map_err(|_e| RegistrarError::Unspecified)?;
+        let password_policy = Self::current_policy(&self.password_policy);
+        RegisteredClient::new(&client, password_policy).check_certificate(certificate)
+    }
+
+    fn check_grant_type(&self, client_id: &str, grant_type: &str) -> Result<(), RegistrarError> {
+        let client = self
+            .repo
+            .find_client_by_id(client_id)
+            .map_err(|_e| RegistrarError::Unspecified)?;
+        let password_policy = Self::current_policy(&self.password_policy);
+        RegisteredClient::new(&client, password_policy).check_grant_type(grant_type)
+    }
+
+    fn check_response_type(&self, client_id: &str, response_type: &str) -> Result<(), RegistrarError> {
+        let client = self
+            .repo
+            .find_client_by_id(client_id)
+            .map_err(|_e| RegistrarError::Unspecified)?;
+        let password_policy = Self::current_policy(&self.password_policy);
+        RegisteredClient::new(&client, password_policy).check_response_type(response_type)
+    }
+}
+
+impl RegistrarMut for DBRegistrar {
+    fn register(&mut self, client: Client) -> Result<(), RegistrarError> {
+        let password_policy = Self::current_policy(&self.password_policy);
+        self.repo
+            .regist_from_encoded_client(client.encode(password_policy))
+            .map_err(|_e| RegistrarError::PrimitiveError)
+    }
+
+    fn client(&self, client_id: &str) -> Result<Option<EncodedClient>, RegistrarError> {
+        // The data sources do not distinguish missing records from failures.
+        Ok(self.repo.find_client_by_id(client_id).ok())
+    }
+
+    fn update(&mut self, client: EncodedClient) -> Result<(), RegistrarError> {
+        self.repo
+            .regist_from_encoded_client(client)
+            .map_err(|_e| RegistrarError::PrimitiveError)
+    }
+
+    fn unregister(&mut self, client_id: &str) -> Result<(), RegistrarError> {
+        self.repo.delete_client_by_id(client_id).map_err(|_e| {
+            error!("{}", _e.to_string());
+            RegistrarError::PrimitiveError
+        })
+    }
+
+    fn check_registration(&self, client_id: &str, token: &[u8]) -> Result<(), RegistrarError> {
+        let client = self
+            .repo
+            .find_client_by_id(client_id)
+            .map_err(|_e| RegistrarError::Unspecified)?;
+        let password_policy = Self::current_policy(&self.password_policy);
+        RegisteredClient::new(&client, password_policy).check_registration(token)
+    }
 }
 
 #[cfg(test)]
@@ -13,6 +13,7 @@ use iron::middleware::Handler;
 
 use oxide_auth::endpoint::{OwnerConsent, Solicitation};
 use oxide_auth::frontends::simple::endpoint::{FnSolicitor, Generic, Vacant};
+use oxide_auth::primitives::jwk::JwkSet;
 use oxide_auth::primitives::prelude::*;
 use oxide_auth_iron::{OAuthRequest, OAuthResponse, OAuthError};
 
@@ -30,8 +31,13 @@ fn main_router() -> impl Handler + 'static {
     let state = Arc::new(EndpointState::preconfigured());
 
     // One clone for each of the move-closures below.
-    let (auth_get_state, auth_post_state, token_state, get_state) =
-        (state.clone(), state.clone(), state.clone(), state.clone());
+    let (auth_get_state, auth_post_state, token_state, jwks_state, get_state) = (
+        state.clone(),
+        state.clone(),
+        state.clone(),
+        state.clone(),
+        state.clone(),
+    );
     let mut router = router::Router::new();
     router.get(
         "/authorize",
@@ -83,6 +89,25 @@ fn main_router() -> impl Handler + 'static {
         },
         "token",
     );
+    router.get(
+        "/.well-known/jwks.json",
+        move |request: &mut Request| {
+            let state = jwks_state.clone();
+            // Only asymmetric keys are published, the ephemeral key of this example is a secret
+            // HMAC key and the set stays empty.
+            let keys = JwkSet::from_keyring(state.issuer.lock().unwrap().keyring());
+            let response = state
+                .endpoint()
+                .jwks_flow(keys)
+                .execute(request.into())
+                .map_err(|e| {
+                    let e: OAuthError = e.into();
+                    e.into()
+                })?;
+            Ok(response.into())
+        },
+        "jwks",
+    );
     router.get(
         "/",
         move |request: &mut Request| {
@@ -134,6 +134,23 @@ impl<'a, 'b, 'c: 'b> WebRequest for OAuthRequest<'a, 'b, 'c> {
     fn authheader(&mut self) -> Result<Option<Cow<str>>, Self::Error> {
         Ok(self.authorization_header())
     }
+
+    fn header(&mut self, name: &str) -> Result<Option<Cow<'_, str>>, Self::Error> {
+        let value = match self.0.headers.get_raw(name) {
+            Some([value]) => value,
+            Some(_) => return Err(Error::BadRequest),
+            None => return Ok(None),
+        };
+
+        std::str::from_utf8(value)
+            .map(|value| Some(Cow::Borrowed(value)))
+            .map_err(|_| Error::BadRequest)
+    }
+
+    fn target(&mut self) -> Result<Option<(Cow<'_, str>, Url)>, Self::Error> {
+        let uri = self.0.url.to_string().parse().ok();
+        Ok(uri.map(|uri| (Cow::Owned(self.0.method.to_string()), uri)))
+    }
 }
 
 impl WebResponse for OAuthResponse {
@@ -14,6 +14,7 @@ use std::sync::Mutex;
 
 use oxide_auth::endpoint::{OwnerConsent, Solicitation};
 use oxide_auth::frontends::simple::endpoint::{FnSolicitor, Generic, Vacant};
+use oxide_auth::primitives::jwk::JwkSet;
 use oxide_auth::primitives::prelude::*;
 use oxide_auth::primitives::registrar::RegisteredUrl;
 use oxide_auth_rocket::{OAuthResponse, OAuthRequest, OAuthFailure};
@@ -79,6 +80,18 @@ fn refresh<'r>(
         .map_err(|err| err.pack::<OAuthFailure>())
 }
 
+#[get("/.well-known/jwks.json")]
+fn jwks<'r>(oauth: OAuthRequest<'r>, state: State<MyState>) -> Result<OAuthResponse<'r>, OAuthFailure> {
+    // The random tokens of this example are not signed, so there are no keys to publish. A server
+    // with a `TokenSigner` would publish the asymmetric keys of its keyring with
+    // `JwkSet::from_keyring`.
+    state
+        .endpoint()
+        .jwks_flow(JwkSet::new())
+        .execute(oauth)
+        .map_err(|err| err.pack::<OAuthFailure>())
+}
+
 #[get("/")]
 fn protected_resource<'r>(oauth: OAuthRequest<'r>, state: State<MyState>) -> impl Responder<'r> {
     const DENY_TEXT: &str = "<html>
@@ -111,7 +124,14 @@ fn main() {
     rocket::ignite()
         .mount(
             "/",
-            routes![authorize, authorize_consent, token, protected_resource, refresh,],
+            routes![
+                authorize,
+                authorize_consent,
+                token,
+                protected_resource,
+                refresh,
+                jwks,
+            ],
         )
         // We only attach the test client here because there can only be one rocket.
         .attach(support::ClientFairing)
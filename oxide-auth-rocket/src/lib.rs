@@ -23,6 +23,8 @@ pub use self::failure::OAuthFailure;
 /// Request guard that also buffers OAuth data internally.
 pub struct OAuthRequest<'r> {
     auth: Option<String>,
+    dpop: Option<String>,
+    target: Option<(String, Url)>,
     query: Result<NormalizedParameter, WebError>,
     body: Result<Option<NormalizedParameter>, WebError>,
     lifetime: PhantomData<&'r ()>,
@@ -58,6 +60,9 @@ impl<'r> OAuthRequest<'r> {
     /// Create the request data from request headers.
     ///
     /// Some oauth methods need additionally the body data which you can attach later.
+    ///
+    /// The uri of the request, against which `DPoP` proofs are checked, is assumed to use https
+    /// unless an `X-Forwarded-Proto` header set by a trusted reverse proxy says otherwise.
     pub fn new<'a>(request: &'a Request<'r>) -> Self {
         let query = request.uri().query().unwrap_or("");
         let query = match serde_urlencoded::from_str(query) {
@@ -80,8 +85,33 @@ impl<'r> OAuthRequest<'r> {
             optional.map(str::to_owned)
         };
 
+        let mut all_dpop = request.headers().get("DPoP");
+        let optional = all_dpop.next();
+
+        // Duplicate proofs are invalid, treat them as no proof.
+        let dpop = if let Some(_) = all_dpop.next() {
+            None
+        } else {
+            optional.map(str::to_owned)
+        };
+
+        // Rocket does not terminate TLS itself, OAuth endpoints must only be reachable with https.
+        // The proxy in front of it may still report the scheme it received the request with.
+        let scheme = match request.headers().get_one("X-Forwarded-Proto") {
+            Some(proto) if proto.eq_ignore_ascii_case("http") => "http",
+            _ => "https",
+        };
+
+        let target = request
+            .headers()
+            .get_one("Host")
+            .and_then(|host| format!("{}://{}{}", scheme, host, request.uri()).parse().ok())
+            .map(|uri| (request.method().as_str().to_owned(), uri));
+
         OAuthRequest {
             auth,
+            dpop,
+            target,
             query,
             body,
             lifetime: PhantomData,
@@ -141,6 +171,21 @@ impl<'r> WebRequest for OAuthRequest<'r> {
     fn authheader(&mut self) -> Result<Option<Cow<str>>, Self::Error> {
         Ok(self.auth.as_ref().map(String::as_str).map(Cow::Borrowed))
     }
+
+    fn header(&mut self, name: &str) -> Result<Option<Cow<str>>, Self::Error> {
+        if name.eq_ignore_ascii_case("DPoP") {
+            Ok(self.dpop.as_deref().map(Cow::Borrowed))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn target(&mut self) -> Result<Option<(Cow<str>, Url)>, Self::Error> {
+        Ok(self
+            .target
+            .as_ref()
+            .map(|(method, uri)| (Cow::Borrowed(method.as_str()), uri.clone())))
+    }
 }
 
 impl<'r> WebResponse for OAuthResponse<'r> {
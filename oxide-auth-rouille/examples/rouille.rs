@@ -9,8 +9,9 @@ use std::sync::Mutex;
 use std::thread;
 
 use oxide_auth::endpoint::{
-    AuthorizationFlow, AccessTokenFlow, OwnerConsent, RefreshFlow, ResourceFlow, Solicitation,
+    AuthorizationFlow, AccessTokenFlow, JwksFlow, OwnerConsent, RefreshFlow, ResourceFlow, Solicitation,
 };
+use oxide_auth::primitives::jwk::JwkSet;
 use oxide_auth::primitives::prelude::*;
 use oxide_auth_rouille::{Request, Response as OAuthResponse};
 use oxide_auth_rouille::{FnSolicitor, GenericEndpoint};
@@ -110,6 +111,16 @@ here</a> to begin the authorization process.
                             .map(OAuthResponse::into_inner)
                             .unwrap_or_else(|_| Response::empty_400())
                     },
+                    (GET) ["/.well-known/jwks.json"] => {
+                        // The random tokens of this example are not signed, so there are no keys
+                        // to publish. A server with a `TokenSigner` would publish the asymmetric
+                        // keys of its keyring with `JwkSet::from_keyring`.
+                        let mut locked = endpoint.lock().unwrap();
+                        JwksFlow::new(&mut *locked, JwkSet::new())
+                            .execute(Request::new(request))
+                            .map(OAuthResponse::into_inner)
+                            .unwrap_or_else(|_| Response::empty_400())
+                    },
                     _ => Response::empty_404()
                 )
     });
@@ -88,6 +88,21 @@ impl<'a> WebRequest for Request<'a> {
     fn authheader(&mut self) -> Result<Option<Cow<str>>, Self::Error> {
         Ok(self.inner.header("Authorization").map(|st| st.into()))
     }
+
+    fn header(&mut self, name: &str) -> Result<Option<Cow<'_, str>>, Self::Error> {
+        Ok(self.inner.header(name).map(|st| st.into()))
+    }
+
+    fn target(&mut self) -> Result<Option<(Cow<'_, str>, Url)>, Self::Error> {
+        let host = match self.inner.header("Host") {
+            Some(host) => host,
+            None => return Ok(None),
+        };
+
+        let scheme = if self.inner.is_secure() { "https" } else { "http" };
+        let uri = format!("{}://{}{}", scheme, host, self.inner.raw_url());
+        Ok(uri.parse().ok().map(|uri| (self.inner.method().into(), uri)))
+    }
 }
 
 impl WebResponse for Response {
@@ -9,8 +9,10 @@ use serde_json;
 
 use crate::code_grant::error::{AccessTokenError, AccessTokenErrorType};
 use crate::primitives::authorizer::Authorizer;
-use crate::primitives::issuer::{IssuedToken, Issuer};
-use crate::primitives::grant::{Extensions, Grant};
+use crate::primitives::dpop;
+use crate::primitives::issuer::{IssuedToken, Issuer, TokenType};
+use crate::primitives::grant::{Extensions, Grant, Value};
+use crate::primitives::mtls::{self, ClientCertificate};
 use crate::primitives::registrar::{Registrar, RegistrarError};
 
 /// Token Response
@@ -36,6 +38,10 @@ pub struct TokenResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub scope: Option<String>,
 
+    /// The type identifier of the issued token, only present in token exchange responses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issued_token_type: Option<String>,
+
     /// Error code
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
@@ -77,6 +83,22 @@ pub trait Request {
     fn allow_credentials_in_body(&self) -> bool {
         false
     }
+
+    /// The thumbprint of the key proven by a DPoP proof of the request.
+    ///
+    /// `Ok(None)` if the request did not contain a proof, an error if the proof was invalid. The
+    /// issued token is bound to the key. The default implementation ignores any proofs.
+    fn dpop_thumbprint(&self) -> std::result::Result<Option<Cow<'_, str>>, ()> {
+        Ok(None)
+    }
+
+    /// The verified client certificate of a mutual-TLS connection.
+    ///
+    /// Clients that provide no passphrase authenticate with the certificate instead. The issued
+    /// token is bound to the certificate. The default implementation provides no certificate.
+    fn certificate(&self) -> Option<Cow<'_, ClientCertificate>> {
+        None
+    }
 }
 
 /// A system of addons provided additional data.
@@ -118,7 +140,7 @@ pub trait Endpoint {
     fn extension(&mut self) -> &mut dyn Extension;
 }
 
-enum Credentials<'a> {
+pub(crate) enum Credentials<'a> {
     /// No credentials were offered.
     None,
     /// One set of credentials was offered.
@@ -166,6 +188,7 @@ enum Credentials<'a> {
 /// 3. Query the backend for a new (bearer) token
 pub struct AccessToken {
     state: AccessTokenState,
+    certificate: Option<ClientCertificate>,
 }
 
 /// Inner state machine for access token
@@ -177,15 +200,18 @@ enum AccessTokenState {
         code: String,
         // TODO: parsing here is unnecessary if we compare a string representation.
         redirect_uri: url::Url,
+        dpop: Option<String>,
     },
     Recover {
         client: String,
         code: String,
         redirect_uri: url::Url,
+        dpop: Option<String>,
     },
     Extend {
         saved_params: Box<Grant>,
         extensions: Extensions,
+        dpop: Option<String>,
     },
     Issue {
         grant: Box<Grant>,
@@ -227,6 +253,8 @@ pub enum Output<'machine> {
         client: &'machine str,
         /// The supplied passdata/password.
         passdata: Option<&'machine [u8]>,
+        /// The certificate of the connection, used when no passdata was supplied.
+        certificate: Option<&'machine ClientCertificate>,
     },
     /// The issuer should try to recover the grant for this `code`
     ///
@@ -265,6 +293,7 @@ impl AccessToken {
     pub fn new(request: &dyn Request) -> Self {
         AccessToken {
             state: Self::validate(request).unwrap_or_else(AccessTokenState::Err),
+            certificate: request.certificate().map(Cow::into_owned),
         }
     }
 
@@ -277,24 +306,33 @@ impl AccessToken {
                     client,
                     code,
                     redirect_uri,
+                    dpop,
                     ..
                 },
                 Input::Authenticated,
-            ) => Self::authencicated(client, code, redirect_uri),
+            ) => Self::authencicated(client, code, redirect_uri, dpop),
             (
                 AccessTokenState::Recover {
-                    client, redirect_uri, ..
+                    client,
+                    redirect_uri,
+                    dpop,
+                    ..
                 },
                 Input::Recovered(grant),
-            ) => Self::recovered(client, redirect_uri, grant).unwrap_or_else(AccessTokenState::Err),
-            (AccessTokenState::Extend { saved_params, .. }, Input::Extended { access_extensions }) => {
-                Self::issue(saved_params, access_extensions)
+            ) => {
+                Self::recovered(client, redirect_uri, dpop, grant).unwrap_or_else(AccessTokenState::Err)
             }
+            (
+                AccessTokenState::Extend {
+                    saved_params, dpop, ..
+                },
+                Input::Extended { access_extensions },
+            ) => Self::issue(saved_params, access_extensions, dpop, self.certificate.as_ref()),
             (AccessTokenState::Issue { grant }, Input::Issued(token)) => {
                 return Output::Ok(Self::finish(grant, token));
             }
             (AccessTokenState::Err(err), _) => AccessTokenState::Err(err),
-            (_, _) => AccessTokenState::Err(Error::Primitive(Box::new(PrimitiveError::empty()))),
+            (_, _) => AccessTokenState::Err(Error::primitive(None)),
         };
 
         self.output()
@@ -306,6 +344,7 @@ impl AccessToken {
             AccessTokenState::Authenticate { client, passdata, .. } => Output::Authenticate {
                 client,
                 passdata: passdata.as_ref().map(Vec::as_slice),
+                certificate: self.certificate.as_ref(),
             },
             AccessTokenState::Recover { code, .. } => Output::Recover { code },
             AccessTokenState::Extend { extensions, .. } => Output::Extend { extensions },
@@ -314,10 +353,7 @@ impl AccessToken {
     }
 
     fn take(&mut self) -> AccessTokenState {
-        mem::replace(
-            &mut self.state,
-            AccessTokenState::Err(Error::Primitive(Box::new(PrimitiveError::empty()))),
-        )
+        mem::replace(&mut self.state, AccessTokenState::Err(Error::primitive(None)))
     }
 
     fn validate(request: &dyn Request) -> Result<AccessTokenState> {
@@ -360,24 +396,32 @@ impl AccessToken {
 
         let code = request.code().ok_or_else(Error::invalid)?;
 
+        let dpop = request
+            .dpop_thumbprint()
+            .map_err(|()| Error::invalid_with(AccessTokenErrorType::InvalidDpopProof))?;
+
         Ok(AccessTokenState::Authenticate {
             client: client_id.to_string(),
             passdata: passdata.map(Vec::from),
             redirect_uri,
             code: code.into_owned(),
+            dpop: dpop.map(Cow::into_owned),
         })
     }
 
-    fn authencicated(client: String, code: String, redirect_uri: url::Url) -> AccessTokenState {
+    fn authencicated(
+        client: String, code: String, redirect_uri: url::Url, dpop: Option<String>,
+    ) -> AccessTokenState {
         AccessTokenState::Recover {
             client,
             code,
             redirect_uri,
+            dpop,
         }
     }
 
     fn recovered(
-        client_id: String, redirect_uri: url::Url, grant: Option<Box<Grant>>,
+        client_id: String, redirect_uri: url::Url, dpop: Option<String>, grant: Option<Box<Grant>>,
     ) -> Result<AccessTokenState> {
         let mut saved_params = match grant {
             None => return Err(Error::invalid()),
@@ -396,16 +440,33 @@ impl AccessToken {
         Ok(AccessTokenState::Extend {
             saved_params,
             extensions,
+            dpop,
         })
     }
 
-    fn issue(grant: Box<Grant>, extensions: Extensions) -> AccessTokenState {
+    fn issue(
+        grant: Box<Grant>, mut extensions: Extensions, dpop: Option<String>,
+        certificate: Option<&ClientCertificate>,
+    ) -> AccessTokenState {
+        if let Some(thumbprint) = dpop {
+            extensions.set_raw(dpop::CONFIRMATION.to_string(), Value::public(Some(thumbprint)));
+        }
+
+        if let Some(certificate) = certificate {
+            let thumbprint = certificate.thumbprint().to_string();
+            extensions.set_raw(mtls::CONFIRMATION.to_string(), Value::public(Some(thumbprint)));
+        }
+
         AccessTokenState::Issue {
             grant: Box::new(Grant { extensions, ..*grant }),
         }
     }
 
-    fn finish(grant: Box<Grant>, token: IssuedToken) -> BearerToken {
+    fn finish(grant: Box<Grant>, mut token: IssuedToken) -> BearerToken {
+        if dpop::confirmation(&grant).is_some() {
+            token.token_type = TokenType::DPoP;
+        }
+
         BearerToken(token, grant.scope.to_string())
     }
 }
@@ -418,6 +479,7 @@ pub fn access_token(handler: &mut dyn Endpoint, request: &dyn Request) -> Result
         Authenticate {
             client: &'a str,
             passdata: Option<&'a [u8]>,
+            certificate: Option<&'a ClientCertificate>,
         },
         Recover(&'a str),
         Extend {
@@ -434,29 +496,33 @@ pub fn access_token(handler: &mut dyn Endpoint, request: &dyn Request) -> Result
     loop {
         let input = match requested {
             Requested::None => Input::None,
-            Requested::Authenticate { client, passdata } => {
+            Requested::Authenticate {
+                client,
+                passdata,
+                certificate,
+            } => {
+                authenticate(handler.registrar(), client, passdata, certificate).map_err(|err| {
+                    error!("{:?}", err);
+                    match err {
+                        RegistrarError::Unspecified => Error::unauthorized("basic"),
+                        RegistrarError::PrimitiveError => Error::primitive(None),
+                    }
+                })?;
                 handler
                     .registrar()
-                    .check(client, passdata)
-                    .map_err(|err| {
-                        error!("{:?}", err);
-                        match err {
-                            RegistrarError::Unspecified => Error::unauthorized("basic"),
-                            RegistrarError::PrimitiveError => Error::Primitive(Box::new(PrimitiveError {
-                                grant: None,
-                                extensions: None,
-                            })),
+                    .check_grant_type(client, "authorization_code")
+                    .map_err(|err| match err {
+                        RegistrarError::Unspecified => {
+                            Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
                         }
+                        RegistrarError::PrimitiveError => Error::primitive(None),
                     })?;
                 Input::Authenticated
             }
             Requested::Recover(code) => {
                 let opt_grant = handler.authorizer().extract(code).map_err(|_| {
                     error!("err on authorizer extract grant by code");
-                    Error::Primitive(Box::new(PrimitiveError {
-                        grant: None,
-                        extensions: None,
-                    }))
+                    Error::primitive(None)
                 })?;
                 Input::Recovered(opt_grant.map(Box::new))
             }
@@ -470,18 +536,23 @@ pub fn access_token(handler: &mut dyn Endpoint, request: &dyn Request) -> Result
             Requested::Issue { grant } => {
                 let token = handler.issuer().issue(grant.clone()).map_err(|_| {
                     error!("err on issuer issue token by grant");
-                    Error::Primitive(Box::new(PrimitiveError {
-                        // FIXME: endpoint should get and handle these.
-                        grant: None,
-                        extensions: None,
-                    }))
+                    // FIXME: endpoint should get and handle these.
+                    Error::primitive(None)
                 })?;
                 Input::Issued(token)
             }
         };
 
         requested = match access_token.advance(input) {
-            Output::Authenticate { client, passdata } => Requested::Authenticate { client, passdata },
+            Output::Authenticate {
+                client,
+                passdata,
+                certificate,
+            } => Requested::Authenticate {
+                client,
+                passdata,
+                certificate,
+            },
             Output::Recover { code } => Requested::Recover(code),
             Output::Extend { extensions } => Requested::Extend { extensions },
             Output::Issue { grant } => Requested::Issue { grant },
@@ -493,6 +564,17 @@ pub fn access_token(handler: &mut dyn Endpoint, request: &dyn Request) -> Result
     }
 }
 
+/// Authenticate a client with its passdata or, if it has none, the certificate of the connection.
+pub(crate) fn authenticate(
+    registrar: &dyn Registrar, client: &str, passdata: Option<&[u8]>,
+    certificate: Option<&ClientCertificate>,
+) -> std::result::Result<(), RegistrarError> {
+    match (passdata, certificate) {
+        (None, Some(certificate)) => registrar.check_certificate(client, certificate),
+        (passdata, _) => registrar.check(client, passdata),
+    }
+}
+
 impl<'a> Credentials<'a> {
     pub fn authenticate(&mut self, client_id: &'a str, passphrase: &'a [u8]) {
         self.add(Credentials::Authenticated {
@@ -610,6 +692,14 @@ impl Error {
         )
     }
 
+    /// Create an error of a primitive, with the grant that was being processed if any.
+    pub fn primitive(grant: Option<Grant>) -> Self {
+        Error::Primitive(Box::new(PrimitiveError {
+            grant,
+            extensions: None,
+        }))
+    }
+
     /// Get a handle to the description the client will receive.
     ///
     /// Some types of this error don't return any description which is represented by a `None`
@@ -623,15 +713,6 @@ impl Error {
     }
 }
 
-impl PrimitiveError {
-    fn empty() -> Self {
-        PrimitiveError {
-            grant: None,
-            extensions: None,
-        }
-    }
-}
-
 impl ErrorDescription {
     /// Convert the error into a json string, viable for being sent over a network with
     /// `application/json` encoding.
@@ -658,9 +739,10 @@ impl BearerToken {
         let token_response = TokenResponse {
             access_token: Some(self.0.token.clone()),
             refresh_token: self.0.refresh.clone(),
-            token_type: Some("bearer".to_owned()),
+            token_type: Some(self.0.token_type.as_str().to_owned()),
             expires_in: Some(remaining.num_seconds()),
             scope: Some(self.1.clone()),
+            issued_token_type: None,
             error: None,
         };
 
@@ -671,7 +753,6 @@ impl BearerToken {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::primitives::issuer::TokenType;
 
     #[test]
     fn bearer_token_encoding() {
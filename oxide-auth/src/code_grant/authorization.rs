@@ -2,7 +2,7 @@
 use std::borrow::Cow;
 use std::result::Result as StdResult;
 
-use url::Url;
+use url::{form_urlencoded, Url};
 use chrono::{Duration, Utc};
 
 use crate::code_grant::error::{AuthorizationError, AuthorizationErrorType};
@@ -10,7 +10,6 @@ use crate::primitives::authorizer::Authorizer;
 use crate::primitives::registrar::{ClientUrl, ExactUrl, Registrar, RegistrarError, PreGrant};
 use crate::primitives::grant::{Extensions, Grant};
 use crate::{endpoint::Scope, endpoint::Solicitation, primitives::registrar::BoundClient};
-use crate::primitives::issuer::{IssuedToken, Issuer};
 
 /// Interface required from a request to determine the handling in the backend.
 pub trait Request {
@@ -37,8 +36,6 @@ pub trait Request {
 
     /// Retrieve an additional parameter used in an extension
     fn extension(&self, key: &str) -> Option<Cow<str>>;
-
-    fn user_id(&self) -> Option<Cow<str>>;
 }
 
 /// A system of addons provided additional data.
@@ -71,9 +68,6 @@ pub trait Endpoint {
     ///
     /// It is possible to use `&mut ()`.
     fn extension(&mut self) -> &mut dyn Extension;
-
-    /// Return the issuer instance to create the access token.
-    fn issuer(&mut self) -> &mut dyn Issuer;
 }
 
 /// The result will indicate wether the authorization succeed or not.
@@ -375,6 +369,20 @@ pub fn authorization_code(handler: &mut dyn Endpoint, request: &dyn Request) ->
                 redirect_uri,
                 scope,
             } => {
+                handler
+                    .registrar()
+                    .check_response_type(&client_id, "code")
+                    .map_err(|err| match err {
+                        RegistrarError::PrimitiveError => Error::PrimitiveError,
+                        RegistrarError::Unspecified => {
+                            let prepared_error = ErrorUrl::with_request(
+                                request,
+                                redirect_uri.clone(),
+                                AuthorizationErrorType::UnauthorizedClient,
+                            );
+                            Error::Redirect(prepared_error)
+                        }
+                    })?;
                 let bound_client = BoundClient {
                     client_id: Cow::Owned(client_id),
                     redirect_uri: Cow::Owned(redirect_uri.clone().into()),
@@ -517,20 +525,25 @@ pub enum Error {
 #[derive(Clone)]
 pub struct ErrorUrl {
     base_uri: Url,
+    state: Option<String>,
     error: AuthorizationError,
+    in_fragment: bool,
 }
 
 type Result<T> = StdResult<T, Error>;
 
 impl ErrorUrl {
     /// Construct a new error, already fixing the state parameter if it exists.
-    fn new_generic<S>(mut url: Url, state: Option<S>, error: AuthorizationError) -> ErrorUrl
+    fn new_generic<S>(url: Url, state: Option<S>, error: AuthorizationError) -> ErrorUrl
     where
         S: AsRef<str>,
     {
-        url.query_pairs_mut()
-            .extend_pairs(state.as_ref().map(|st| ("state", st.as_ref())));
-        ErrorUrl { base_uri: url, error }
+        ErrorUrl {
+            base_uri: url,
+            state: state.map(|st| st.as_ref().to_string()),
+            error,
+            in_fragment: false,
+        }
     }
 
     /// Construct a new error, already fixing the state parameter if it exists.
@@ -551,6 +564,14 @@ impl ErrorUrl {
         err
     }
 
+    /// Deliver the error in the fragment of the redirect url instead of its query.
+    ///
+    /// The implicit grant responds with all parameters in the fragment, including errors.
+    pub fn in_fragment(mut self) -> Self {
+        self.in_fragment = true;
+        self
+    }
+
     /// Get a handle to the description the client will receive.
     pub fn description(&mut self) -> &mut AuthorizationError {
         &mut self.error
@@ -572,10 +593,24 @@ impl Error {
 }
 
 impl Into<Url> for ErrorUrl {
-    /// Finalize the error url by saving its parameters in the query part of the redirect_uri
+    /// Finalize the error url by saving its parameters in the query part of the redirect_uri, or
+    /// in its fragment if requested.
     fn into(self) -> Url {
         let mut url = self.base_uri;
-        url.query_pairs_mut().extend_pairs(self.error.into_iter());
+        let state = self.state.map(|st| ("state", Cow::Owned(st)));
+
+        if self.in_fragment {
+            let fragment = form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(state)
+                .extend_pairs(self.error.into_iter())
+                .finish();
+            url.set_fragment(Some(&fragment));
+        } else {
+            url.query_pairs_mut()
+                .extend_pairs(state)
+                .extend_pairs(self.error.into_iter());
+        }
+
         url
     }
 }
@@ -0,0 +1,444 @@
+//! Provides the handling for Client Credentials Requests
+//!
+//! See [Client Credentials Grant] in the rfc.
+//!
+//! [Client Credentials Grant]: https://tools.ietf.org/html/rfc6749#section-4.4
+use std::mem;
+use std::borrow::Cow;
+
+use chrono::{Duration, Utc};
+
+use crate::code_grant::accesstoken::{authenticate, BearerToken, Credentials, Error};
+use crate::code_grant::error::AccessTokenErrorType;
+use crate::primitives::issuer::{IssuedToken, Issuer};
+use crate::primitives::grant::{Extensions, Grant, Value};
+use crate::primitives::mtls::{self, ClientCertificate};
+use crate::primitives::registrar::{BoundClient, ClientUrl, PreGrant, Registrar, RegistrarError};
+use crate::primitives::scope::Scope;
+
+/// Trait based retrieval of parameters necessary for client credentials request handling.
+pub trait Request {
+    /// Received request might not be encoded correctly. This method gives implementors the chance
+    /// to signal that a request was received but its encoding was generally malformed. If this is
+    /// the case, then no other attribute will be queried. This method exists mainly to make
+    /// frontends straightforward by not having them handle special cases for malformed requests.
+    fn valid(&self) -> bool;
+
+    /// User:password of a basic authorization header.
+    fn authorization(&self) -> Option<(Cow<'_, str>, Cow<'_, [u8]>)>;
+
+    /// The client_id, only used together with credentials in the body.
+    fn client_id(&self) -> Option<Cow<'_, str>>;
+
+    /// Optionally specifies the requested scope
+    fn scope(&self) -> Option<Cow<'_, str>>;
+
+    /// Valid requests have this set to "client_credentials"
+    fn grant_type(&self) -> Option<Cow<'_, str>>;
+
+    /// Retrieve an additional parameter used in an extension
+    fn extension(&self, key: &str) -> Option<Cow<'_, str>>;
+
+    /// Credentials in body should only be enabled if use of HTTP Basic is not possible.
+    ///
+    /// Allows the request body to contain the `client_secret` as a form parameter. This is NOT
+    /// RECOMMENDED and need not be supported. The parameters MUST NOT appear in the request URI
+    /// itself.
+    ///
+    /// Under these considerations, support must be explicitely enabled.
+    fn allow_credentials_in_body(&self) -> bool {
+        false
+    }
+
+    /// The verified client certificate of a mutual-TLS connection.
+    ///
+    /// Clients that provide no passphrase authenticate with the certificate instead. The issued
+    /// token is bound to the certificate. The default implementation provides no certificate.
+    fn certificate(&self) -> Option<Cow<'_, ClientCertificate>> {
+        None
+    }
+}
+
+/// Required functionality to respond to client credentials requests.
+///
+/// Each method will only be invoked exactly once when processing a correct and authorized request,
+/// and potentially less than once when the request is faulty.  These methods should be implemented
+/// by internally using `primitives`, as it is implemented in the `frontend` module.
+pub trait Endpoint {
+    /// Authenticate the client and negotiate the scope of the grant.
+    fn registrar(&self) -> &dyn Registrar;
+
+    /// Return the issuer instance to create the access token.
+    fn issuer(&mut self) -> &mut dyn Issuer;
+}
+
+/// Client credentials issuing process
+///
+/// This state machine will go through four phases. On creation, the request will be validated and
+/// parameters for the first step will be extracted from it. It will pose some requests in the form
+/// of [`Output`] which should be satisfied with the next [`Input`] data. This will eventually
+/// produce a [`BearerToken`] or an [`Error`]. Note that the executing environment will need to use
+/// a [`Registrar`] and an [`Issuer`] to which some requests should be forwarded.
+///
+/// [`Input`]: struct.Input.html
+/// [`Output`]: struct.Output.html
+/// [`BearerToken`]: ../accesstoken/struct.BearerToken.html
+/// [`Error`]: ../accesstoken/struct.Error.html
+/// [`Issuer`] ../primitives/issuer/trait.Issuer.html
+/// [`Registrar`] ../primitives/registrar/trait.Registrar.html
+///
+/// A rough sketch of the operational phases:
+///
+/// 1. Ensure the request is valid based on the basic requirements (includes required parameters)
+/// 2. Authenticate the client, only confidential clients may use this grant
+/// 3. Bind the client to its registered parameters and negotiate the scope
+/// 4. Query the backend for a new (bearer) token, without a refresh token
+pub struct ClientCredentials {
+    state: ClientCredentialsState,
+    certificate: Option<ClientCertificate>,
+}
+
+/// Inner state machine for client credentials
+enum ClientCredentialsState {
+    /// State after the request has been validated.
+    Authenticate {
+        client: String,
+        passdata: Option<Vec<u8>>,
+        scope: Option<Scope>,
+    },
+    Binding {
+        client_id: String,
+        scope: Option<Scope>,
+    },
+    Negotiating {
+        bound_client: BoundClient<'static>,
+        scope: Option<Scope>,
+    },
+    Issue {
+        grant: Box<Grant>,
+    },
+    Err(Error),
+}
+
+/// Input injected by the executor into the state machine.
+pub enum Input {
+    /// Positively answer an authentication query.
+    Authenticated,
+    /// The registrar bound the client to its registered parameters.
+    Bound {
+        /// The bound client
+        bound_client: BoundClient<'static>,
+    },
+    /// The registrar negotiated the parameters of the grant.
+    Negotiated {
+        /// The pre grant from the negotiation
+        pre_grant: PreGrant,
+    },
+    /// The token produced by the backend
+    Issued(IssuedToken),
+    /// Advance without input as far as possible, or just retrieve the output again.
+    None,
+}
+
+/// A request by the statemachine to the executor.
+///
+/// Each variant is fulfilled by certain variants of the next inputs as an argument to
+/// `ClientCredentials::advance`. The output of most states is simply repeated if `Input::None` is
+/// provided instead but note that the successful bearer token response is **not** repeated.
+pub enum Output<'machine> {
+    /// The registrar should authenticate a client.
+    ///
+    /// Fulfilled by `Input::Authenticated`. In an unsuccessful case, the executor should not
+    /// continue and discard the flow.
+    Authenticate {
+        /// The to-be-authenticated client.
+        client: &'machine str,
+        /// The supplied passdata/password.
+        passdata: Option<&'machine [u8]>,
+        /// The certificate of the connection, used when no passdata was supplied.
+        certificate: Option<&'machine ClientCertificate>,
+    },
+    /// Ask registrar to bind the client to its default redirect_uri.
+    ///
+    /// Fulfilled by `Input::Bound`.
+    Binding {
+        /// The already authenticated client.
+        client_id: &'machine str,
+    },
+    /// Ask registrar to negotiate the scope.
+    ///
+    /// Fulfilled by `Input::Negotiated`.
+    Negotiate {
+        /// The current bound client
+        bound_client: &'machine BoundClient<'static>,
+        /// The scope, if any
+        scope: Option<Scope>,
+    },
+    /// The issue should issue a new access token
+    ///
+    /// Fullfilled by `Input::Issued`
+    Issue {
+        /// The grant to be used in the token generation
+        grant: &'machine Grant,
+    },
+    /// The state machine finished and a new bearer token was generated
+    ///
+    /// This output **can not** be requested repeatedly, any future `Input` will yield a primitive
+    /// error instead.
+    Ok(BearerToken),
+    /// The state machine finished in an error.
+    ///
+    /// The error will be repeated on *any* following input.
+    Err(Box<Error>),
+}
+
+impl ClientCredentials {
+    /// Create the state machine. validating the request in the process
+    pub fn new(request: &dyn Request) -> Self {
+        let certificate = request.certificate().map(Cow::into_owned);
+        ClientCredentials {
+            state: Self::validate(request, certificate.is_some())
+                .unwrap_or_else(ClientCredentialsState::Err),
+            certificate,
+        }
+    }
+
+    /// Go to next state
+    pub fn advance(&mut self, input: Input) -> Output<'_> {
+        self.state = match (self.take(), input) {
+            (current, Input::None) => current,
+            (ClientCredentialsState::Authenticate { client, scope, .. }, Input::Authenticated) => {
+                ClientCredentialsState::Binding {
+                    client_id: client,
+                    scope,
+                }
+            }
+            (ClientCredentialsState::Binding { scope, .. }, Input::Bound { bound_client }) => {
+                ClientCredentialsState::Negotiating { bound_client, scope }
+            }
+            (ClientCredentialsState::Negotiating { .. }, Input::Negotiated { pre_grant }) => {
+                Self::negotiated(pre_grant, self.certificate.as_ref())
+            }
+            (ClientCredentialsState::Issue { grant }, Input::Issued(token)) => {
+                return Output::Ok(Self::finish(grant, token));
+            }
+            (ClientCredentialsState::Err(err), _) => ClientCredentialsState::Err(err),
+            (_, _) => ClientCredentialsState::Err(Error::primitive(None)),
+        };
+
+        self.output()
+    }
+
+    fn output(&mut self) -> Output<'_> {
+        match &mut self.state {
+            ClientCredentialsState::Err(err) => Output::Err(Box::new(err.clone())),
+            ClientCredentialsState::Authenticate { client, passdata, .. } => Output::Authenticate {
+                client,
+                passdata: passdata.as_deref(),
+                certificate: self.certificate.as_ref(),
+            },
+            ClientCredentialsState::Binding { client_id, .. } => Output::Binding { client_id },
+            ClientCredentialsState::Negotiating { bound_client, scope } => Output::Negotiate {
+                bound_client,
+                scope: scope.clone(),
+            },
+            ClientCredentialsState::Issue { grant } => Output::Issue { grant },
+        }
+    }
+
+    fn take(&mut self) -> ClientCredentialsState {
+        mem::replace(
+            &mut self.state,
+            ClientCredentialsState::Err(Error::primitive(None)),
+        )
+    }
+
+    fn validate(request: &dyn Request, certificate: bool) -> Result<ClientCredentialsState, Error> {
+        if !request.valid() {
+            return Err(Error::invalid());
+        }
+
+        let authorization = request.authorization();
+        let client_id = request.client_id();
+        let client_secret = request.extension("client_secret");
+
+        let mut credentials = Credentials::None;
+        if let Some((client_id, auth)) = &authorization {
+            credentials.authenticate(client_id.as_ref(), auth.as_ref());
+        }
+
+        if let Some(client_id) = &client_id {
+            match &client_secret {
+                Some(auth) if request.allow_credentials_in_body() => {
+                    credentials.authenticate(client_id.as_ref(), auth.as_ref().as_bytes())
+                }
+                // Ignore parameter if not allowed.
+                Some(_) | None => credentials.unauthenticated(client_id.as_ref()),
+            }
+        }
+
+        match request.grant_type() {
+            Some(ref cow) if cow == "client_credentials" => (),
+            None => return Err(Error::invalid()),
+            Some(_) => return Err(Error::invalid_with(AccessTokenErrorType::UnsupportedGrantType)),
+        };
+
+        let (client_id, passdata) = credentials.into_client().ok_or_else(Error::invalid)?;
+
+        // The client credentials grant type MUST only be used by confidential clients.
+        if passdata.is_none() && !certificate {
+            return Err(Error::unauthorized("basic"));
+        }
+
+        let scope = match request.scope() {
+            None => None,
+            Some(scope) => Some(
+                scope
+                    .parse()
+                    .map_err(|_| Error::invalid_with(AccessTokenErrorType::InvalidScope))?,
+            ),
+        };
+
+        Ok(ClientCredentialsState::Authenticate {
+            client: client_id.to_string(),
+            passdata: passdata.map(Vec::from),
+            scope,
+        })
+    }
+
+    fn negotiated(
+        pre_grant: PreGrant, certificate: Option<&ClientCertificate>,
+    ) -> ClientCredentialsState {
+        let mut extensions = Extensions::new();
+        if let Some(certificate) = certificate {
+            let thumbprint = certificate.thumbprint().to_string();
+            extensions.set_raw(mtls::CONFIRMATION.to_string(), Value::public(Some(thumbprint)));
+        }
+
+        // The client is acting on its own behalf, it is the owner of the grant.
+        ClientCredentialsState::Issue {
+            grant: Box::new(Grant {
+                owner_id: pre_grant.client_id.clone(),
+                client_id: pre_grant.client_id,
+                redirect_uri: pre_grant.redirect_uri.into_url(),
+                scope: pre_grant.scope,
+                until: Utc::now() + Duration::hours(1),
+                extensions,
+            }),
+        }
+    }
+
+    fn finish(grant: Box<Grant>, token: IssuedToken) -> BearerToken {
+        // A refresh token SHOULD NOT be included.
+        let token = IssuedToken { refresh: None, ..token };
+        BearerToken(token, grant.scope.to_string())
+    }
+}
+
+/// Try to issue an access token to an authenticated client.
+pub fn client_credentials(handler: &mut dyn Endpoint, request: &dyn Request) -> Result<BearerToken, Error> {
+    enum Requested {
+        None,
+        Authenticate {
+            client: String,
+            passdata: Option<Vec<u8>>,
+            certificate: Option<ClientCertificate>,
+        },
+        Bind { client_id: String },
+        Negotiate { bound_client: BoundClient<'static>, scope: Option<Scope> },
+        Issue { grant: Grant },
+    }
+
+    let mut client_credentials = ClientCredentials::new(request);
+    let mut requested = Requested::None;
+
+    loop {
+        let input = match requested {
+            Requested::None => Input::None,
+            Requested::Authenticate {
+                client,
+                passdata,
+                certificate,
+            } => {
+                authenticate(
+                    handler.registrar(),
+                    &client,
+                    passdata.as_deref(),
+                    certificate.as_ref(),
+                )
+                .map_err(|err| match err {
+                    RegistrarError::Unspecified => Error::unauthorized("basic"),
+                    RegistrarError::PrimitiveError => Error::primitive(None),
+                })?;
+                handler
+                    .registrar()
+                    .check_grant_type(&client, "client_credentials")
+                    .map_err(|err| match err {
+                        RegistrarError::Unspecified => {
+                            Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
+                        }
+                        RegistrarError::PrimitiveError => Error::primitive(None),
+                    })?;
+                Input::Authenticated
+            }
+            Requested::Bind { client_id } => {
+                let client_url = ClientUrl {
+                    client_id: Cow::Owned(client_id),
+                    redirect_uri: None,
+                };
+                let bound_client =
+                    handler
+                        .registrar()
+                        .bound_redirect(client_url)
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => {
+                                Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
+                            }
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        })?;
+                Input::Bound { bound_client }
+            }
+            Requested::Negotiate { bound_client, scope } => {
+                let pre_grant = handler
+                    .registrar()
+                    .negotiate(bound_client, scope)
+                    .map_err(|err| match err {
+                        RegistrarError::Unspecified => {
+                            Error::invalid_with(AccessTokenErrorType::InvalidScope)
+                        }
+                        RegistrarError::PrimitiveError => Error::primitive(None),
+                    })?;
+                Input::Negotiated { pre_grant }
+            }
+            Requested::Issue { grant } => {
+                let token = handler
+                    .issuer()
+                    .issue(grant.clone())
+                    .map_err(|_| Error::primitive(Some(grant)))?;
+                Input::Issued(token)
+            }
+        };
+
+        requested = match client_credentials.advance(input) {
+            Output::Authenticate {
+                client,
+                passdata,
+                certificate,
+            } => Requested::Authenticate {
+                client: client.to_string(),
+                passdata: passdata.map(Vec::from),
+                certificate: certificate.cloned(),
+            },
+            Output::Binding { client_id } => Requested::Bind {
+                client_id: client_id.to_string(),
+            },
+            Output::Negotiate { bound_client, scope } => Requested::Negotiate {
+                bound_client: bound_client.clone(),
+                scope,
+            },
+            Output::Issue { grant } => Requested::Issue { grant: grant.clone() },
+            Output::Ok(token) => return Ok(token),
+            Output::Err(err) => return Err(*err),
+        };
+    }
+}
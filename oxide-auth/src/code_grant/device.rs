@@ -0,0 +1,873 @@
+//! Provides the handling for Device Authorization Grant requests
+//!
+//! The grant consists of three separate requests. The device first asks for a pair of codes at the
+//! device authorization endpoint. The resource owner then enters the user code on a verification
+//! page, where consent is checked as usual. Meanwhile, the device polls the token endpoint with the
+//! device code until the owner has decided. See [Device Authorization Grant] in the rfc.
+//!
+//! [Device Authorization Grant]: https://tools.ietf.org/html/rfc8628
+use std::mem;
+use std::borrow::Cow;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::code_grant::accesstoken::{BearerToken, Credentials, Error};
+use crate::code_grant::error::AccessTokenErrorType;
+use crate::endpoint::Solicitation;
+use crate::primitives::device::{DeviceAuthorizer, DeviceCodes, DeviceStatus};
+use crate::primitives::issuer::{IssuedToken, Issuer};
+use crate::primitives::grant::{Extensions, Grant};
+use crate::primitives::registrar::{
+    BoundClient, ClientMetadata, ClientUrl, PreGrant, Registrar, RegistrarError,
+};
+use crate::primitives::scope::Scope;
+
+/// The `grant_type` with which devices poll for their access token.
+pub const DEVICE_CODE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// Trait based retrieval of parameters necessary for device authorization request handling.
+pub trait Request {
+    /// Received request might not be encoded correctly. This method gives implementors the chance
+    /// to signal that a request was received but its encoding was generally malformed. If this is
+    /// the case, then no other attribute will be queried. This method exists mainly to make
+    /// frontends straightforward by not having them handle special cases for malformed requests.
+    fn valid(&self) -> bool;
+
+    /// User:password of a basic authorization header.
+    fn authorization(&self) -> Option<(Cow<'_, str>, Cow<'_, [u8]>)>;
+
+    /// The client_id, required for public clients.
+    fn client_id(&self) -> Option<Cow<'_, str>>;
+
+    /// Optionally specifies the requested scope
+    fn scope(&self) -> Option<Cow<'_, str>>;
+
+    /// Retrieve an additional parameter used in an extension
+    fn extension(&self, key: &str) -> Option<Cow<'_, str>>;
+
+    /// Credentials in body should only be enabled if use of HTTP Basic is not possible.
+    ///
+    /// Allows the request body to contain the `client_secret` as a form parameter. This is NOT
+    /// RECOMMENDED and need not be supported. The parameters MUST NOT appear in the request URI
+    /// itself.
+    ///
+    /// Under these considerations, support must be explicitely enabled.
+    fn allow_credentials_in_body(&self) -> bool {
+        false
+    }
+}
+
+/// Required functionality to respond to device authorization requests.
+///
+/// Each method will only be invoked exactly once when processing a correct and authorized request,
+/// and potentially less than once when the request is faulty.  These methods should be implemented
+/// by internally using `primitives`, as it is implemented in the `frontend` module.
+pub trait Endpoint {
+    /// Authenticate the client and negotiate the scope of the grant.
+    fn registrar(&self) -> &dyn Registrar;
+
+    /// Return the device authorizer storing the pending grant.
+    fn device_authorizer(&mut self) -> &mut dyn DeviceAuthorizer;
+}
+
+/// Trait based retrieval of parameters necessary for the verification by a resource owner.
+pub trait VerificationRequest {
+    /// Received request might not be encoded correctly. This method gives implementors the chance
+    /// to signal that a request was received but its encoding was generally malformed. If this is
+    /// the case, then no other attribute will be queried.
+    fn valid(&self) -> bool;
+
+    /// The user code entered by the resource owner.
+    fn user_code(&self) -> Option<Cow<'_, str>>;
+}
+
+/// Required functionality to verify pending device authorizations.
+pub trait VerificationEndpoint {
+    /// Return the device authorizer storing the pending grants.
+    fn device_authorizer(&mut self) -> &mut dyn DeviceAuthorizer;
+}
+
+/// Trait based retrieval of parameters necessary for device access token request handling.
+pub trait TokenRequest {
+    /// Received request might not be encoded correctly. This method gives implementors the chance
+    /// to signal that a request was received but its encoding was generally malformed. If this is
+    /// the case, then no other attribute will be queried.
+    fn valid(&self) -> bool;
+
+    /// User:password of a basic authorization header.
+    fn authorization(&self) -> Option<(Cow<'_, str>, Cow<'_, [u8]>)>;
+
+    /// The client_id, required for public clients.
+    fn client_id(&self) -> Option<Cow<'_, str>>;
+
+    /// The device code from the device authorization response.
+    fn device_code(&self) -> Option<Cow<'_, str>>;
+
+    /// Valid requests have this set to "urn:ietf:params:oauth:grant-type:device_code"
+    fn grant_type(&self) -> Option<Cow<'_, str>>;
+
+    /// Retrieve an additional parameter used in an extension
+    fn extension(&self, key: &str) -> Option<Cow<'_, str>>;
+
+    /// Credentials in body should only be enabled if use of HTTP Basic is not possible.
+    ///
+    /// See `Request::allow_credentials_in_body` for details.
+    fn allow_credentials_in_body(&self) -> bool {
+        false
+    }
+}
+
+/// Required functionality to respond to device access token requests.
+pub trait TokenEndpoint {
+    /// Authenticate the polling client.
+    fn registrar(&self) -> &dyn Registrar;
+
+    /// Return the device authorizer storing the pending grants.
+    fn device_authorizer(&mut self) -> &mut dyn DeviceAuthorizer;
+
+    /// Return the issuer instance to create the access token.
+    fn issuer(&mut self) -> &mut dyn Issuer;
+}
+
+/// Device authorization process
+///
+/// This state machine will go through four phases. On creation, the request will be validated and
+/// parameters for the first step will be extracted from it. It will pose some requests in the form
+/// of [`Output`] which should be satisfied with the next [`Input`] data. This will eventually
+/// produce [`IssuedCodes`] or an [`Error`]. Note that the executing environment will need to use a
+/// [`Registrar`] and a [`DeviceAuthorizer`] to which some requests should be forwarded.
+///
+/// [`Input`]: struct.Input.html
+/// [`Output`]: struct.Output.html
+/// [`IssuedCodes`]: struct.IssuedCodes.html
+/// [`Error`]: ../accesstoken/struct.Error.html
+/// [`Registrar`] ../primitives/registrar/trait.Registrar.html
+/// [`DeviceAuthorizer`] ../primitives/device/trait.DeviceAuthorizer.html
+///
+/// A rough sketch of the operational phases:
+///
+/// 1. Ensure the request is valid based on the basic requirements (includes required parameters)
+/// 2. Authenticate the client
+/// 3. Bind the client to its registered parameters and negotiate the scope
+/// 4. Store the pending grant and create its device and user code
+pub struct DeviceAuthorization {
+    state: AuthorizationState,
+}
+
+/// Inner state machine for device authorization
+enum AuthorizationState {
+    Authenticate {
+        client: String,
+        passdata: Option<Vec<u8>>,
+        scope: Option<Scope>,
+    },
+    Binding {
+        client_id: String,
+        scope: Option<Scope>,
+    },
+    Negotiating {
+        bound_client: BoundClient<'static>,
+        scope: Option<Scope>,
+    },
+    Authorize {
+        grant: Box<Grant>,
+    },
+    Err(Error),
+}
+
+/// Input injected by the executor into the device authorization state machine.
+pub enum Input {
+    /// Positively answer an authentication query.
+    Authenticated,
+    /// The registrar bound the client to its registered parameters.
+    Bound {
+        /// The bound client
+        bound_client: BoundClient<'static>,
+    },
+    /// The registrar negotiated the parameters of the grant.
+    Negotiated {
+        /// The pre grant from the negotiation
+        pre_grant: PreGrant,
+    },
+    /// The codes produced by the device authorizer.
+    Authorized(DeviceCodes),
+    /// Advance without input as far as possible, or just retrieve the output again.
+    None,
+}
+
+/// A request by the device authorization state machine to the executor.
+///
+/// Each variant is fulfilled by certain variants of the next inputs as an argument to
+/// `DeviceAuthorization::advance`. The output of most states is simply repeated if `Input::None`
+/// is provided instead but note that the successful response is **not** repeated.
+pub enum Output<'machine> {
+    /// The registrar should authenticate a client.
+    ///
+    /// Fulfilled by `Input::Authenticated`. In an unsuccessful case, the executor should not
+    /// continue and discard the flow.
+    Authenticate {
+        /// The to-be-authenticated client.
+        client: &'machine str,
+        /// The supplied passdata/password.
+        passdata: Option<&'machine [u8]>,
+    },
+    /// Ask registrar to bind the client to its default redirect_uri.
+    ///
+    /// Fulfilled by `Input::Bound`.
+    Binding {
+        /// The already authenticated client.
+        client_id: &'machine str,
+    },
+    /// Ask registrar to negotiate the scope.
+    ///
+    /// Fulfilled by `Input::Negotiated`.
+    Negotiate {
+        /// The current bound client
+        bound_client: &'machine BoundClient<'static>,
+        /// The scope, if any
+        scope: Option<Scope>,
+    },
+    /// The device authorizer should store the pending grant.
+    ///
+    /// Fulfilled by `Input::Authorized`.
+    Authorize {
+        /// The pending grant, without an owner.
+        grant: &'machine Grant,
+    },
+    /// The state machine finished and the device codes were created.
+    ///
+    /// This output **can not** be requested repeatedly, any future `Input` will yield a primitive
+    /// error instead.
+    Ok(IssuedCodes),
+    /// The state machine finished in an error.
+    ///
+    /// The error will be repeated on *any* following input.
+    Err(Box<Error>),
+}
+
+/// The codes of a successful device authorization request.
+#[derive(Clone, Debug)]
+pub struct IssuedCodes {
+    /// The codes created by the device authorizer.
+    pub codes: DeviceCodes,
+
+    /// The expiration of the device code.
+    pub until: DateTime<Utc>,
+}
+
+/// Device authorization response as defined by the rfc.
+///
+/// See [RFC 8628](https://tools.ietf.org/html/rfc8628#section-3.2).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct DeviceAuthorizationResponse {
+    /// The device verification code.
+    pub device_code: String,
+
+    /// The end-user verification code.
+    pub user_code: String,
+
+    /// The end-user verification uri on the authorization server.
+    pub verification_uri: String,
+
+    /// The verification uri that already includes the user code.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verification_uri_complete: Option<String>,
+
+    /// Lifetime in seconds of the device code.
+    pub expires_in: i64,
+
+    /// Minimum amount of seconds the device should wait between polling requests.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval: Option<i64>,
+}
+
+impl DeviceAuthorization {
+    /// Create the state machine. validating the request in the process
+    pub fn new(request: &dyn Request) -> Self {
+        DeviceAuthorization {
+            state: Self::validate(request).unwrap_or_else(AuthorizationState::Err),
+        }
+    }
+
+    /// Go to next state
+    pub fn advance(&mut self, input: Input) -> Output<'_> {
+        self.state = match (self.take(), input) {
+            (current, Input::None) => current,
+            (AuthorizationState::Authenticate { client, scope, .. }, Input::Authenticated) => {
+                AuthorizationState::Binding {
+                    client_id: client,
+                    scope,
+                }
+            }
+            (AuthorizationState::Binding { scope, .. }, Input::Bound { bound_client }) => {
+                AuthorizationState::Negotiating { bound_client, scope }
+            }
+            (AuthorizationState::Negotiating { .. }, Input::Negotiated { pre_grant }) => {
+                Self::negotiated(pre_grant)
+            }
+            (AuthorizationState::Authorize { grant }, Input::Authorized(codes)) => {
+                return Output::Ok(IssuedCodes {
+                    codes,
+                    until: grant.until,
+                });
+            }
+            (AuthorizationState::Err(err), _) => AuthorizationState::Err(err),
+            (_, _) => AuthorizationState::Err(Error::primitive(None)),
+        };
+
+        self.output()
+    }
+
+    fn output(&self) -> Output<'_> {
+        match &self.state {
+            AuthorizationState::Err(err) => Output::Err(Box::new(err.clone())),
+            AuthorizationState::Authenticate { client, passdata, .. } => Output::Authenticate {
+                client,
+                passdata: passdata.as_deref(),
+            },
+            AuthorizationState::Binding { client_id, .. } => Output::Binding { client_id },
+            AuthorizationState::Negotiating { bound_client, scope } => Output::Negotiate {
+                bound_client,
+                scope: scope.clone(),
+            },
+            AuthorizationState::Authorize { grant } => Output::Authorize { grant },
+        }
+    }
+
+    fn take(&mut self) -> AuthorizationState {
+        mem::replace(&mut self.state, AuthorizationState::Err(Error::primitive(None)))
+    }
+
+    fn validate(request: &dyn Request) -> Result<AuthorizationState, Error> {
+        if !request.valid() {
+            return Err(Error::invalid());
+        }
+
+        let authorization = request.authorization();
+        let client_id = request.client_id();
+        let client_secret = request.extension("client_secret");
+
+        let mut credentials = Credentials::None;
+        if let Some((client_id, auth)) = &authorization {
+            credentials.authenticate(client_id.as_ref(), auth.as_ref());
+        }
+
+        if let Some(client_id) = &client_id {
+            match &client_secret {
+                Some(auth) if request.allow_credentials_in_body() => {
+                    credentials.authenticate(client_id.as_ref(), auth.as_ref().as_bytes())
+                }
+                // Ignore parameter if not allowed.
+                Some(_) | None => credentials.unauthenticated(client_id.as_ref()),
+            }
+        }
+        let (client_id, passdata) = credentials.into_client().ok_or_else(Error::invalid)?;
+
+        let scope = match request.scope() {
+            None => None,
+            Some(scope) => Some(
+                scope
+                    .parse()
+                    .map_err(|_| Error::invalid_with(AccessTokenErrorType::InvalidScope))?,
+            ),
+        };
+
+        Ok(AuthorizationState::Authenticate {
+            client: client_id.to_string(),
+            passdata: passdata.map(Vec::from),
+            scope,
+        })
+    }
+
+    fn negotiated(pre_grant: PreGrant) -> AuthorizationState {
+        AuthorizationState::Authorize {
+            grant: Box::new(Grant {
+                // The owner is only known once the request has been approved.
+                owner_id: String::new(),
+                client_id: pre_grant.client_id,
+                redirect_uri: pre_grant.redirect_uri.into_url(),
+                scope: pre_grant.scope,
+                until: Utc::now() + Duration::minutes(10),
+                extensions: Extensions::new(),
+            }),
+        }
+    }
+}
+
+impl IssuedCodes {
+    /// Convert the codes into a json string, viable for being sent over a network with
+    /// `application/json` encoding.
+    ///
+    /// The `verification_uri` is the page on which resource owners enter the user code.
+    pub fn to_json(&self, verification_uri: &Url) -> String {
+        let mut complete = verification_uri.clone();
+        complete
+            .query_pairs_mut()
+            .append_pair("user_code", &self.codes.user_code);
+
+        let remaining = self.until.signed_duration_since(Utc::now());
+        let response = DeviceAuthorizationResponse {
+            device_code: self.codes.device_code.clone(),
+            user_code: self.codes.user_code.clone(),
+            verification_uri: verification_uri.to_string(),
+            verification_uri_complete: Some(complete.to_string()),
+            expires_in: remaining.num_seconds(),
+            interval: Some(self.codes.interval.num_seconds()),
+        };
+
+        serde_json::to_string(&response).unwrap()
+    }
+}
+
+/// Try to create device and user codes for a device authorization request.
+pub fn device_authorization(
+    handler: &mut dyn Endpoint, request: &dyn Request,
+) -> Result<IssuedCodes, Error> {
+    enum Requested {
+        None,
+        Authenticate { client: String, passdata: Option<Vec<u8>> },
+        Bind { client_id: String },
+        Negotiate { bound_client: BoundClient<'static>, scope: Option<Scope> },
+        Authorize { grant: Grant },
+    }
+
+    let mut authorization = DeviceAuthorization::new(request);
+    let mut requested = Requested::None;
+
+    loop {
+        let input = match requested {
+            Requested::None => Input::None,
+            Requested::Authenticate { client, passdata } => {
+                handler
+                    .registrar()
+                    .check(&client, passdata.as_deref())
+                    .map_err(|err| match err {
+                        RegistrarError::Unspecified => Error::unauthorized("basic"),
+                        RegistrarError::PrimitiveError => Error::primitive(None),
+                    })?;
+                handler
+                    .registrar()
+                    .check_grant_type(&client, DEVICE_CODE_GRANT_TYPE)
+                    .map_err(|err| match err {
+                        RegistrarError::Unspecified => {
+                            Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
+                        }
+                        RegistrarError::PrimitiveError => Error::primitive(None),
+                    })?;
+                Input::Authenticated
+            }
+            Requested::Bind { client_id } => {
+                let client_url = ClientUrl {
+                    client_id: Cow::Owned(client_id),
+                    redirect_uri: None,
+                };
+                let bound_client =
+                    handler
+                        .registrar()
+                        .bound_redirect(client_url)
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => {
+                                Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
+                            }
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        })?;
+                Input::Bound { bound_client }
+            }
+            Requested::Negotiate { bound_client, scope } => {
+                let pre_grant = handler
+                    .registrar()
+                    .negotiate(bound_client, scope)
+                    .map_err(|err| match err {
+                        RegistrarError::Unspecified => {
+                            Error::invalid_with(AccessTokenErrorType::InvalidScope)
+                        }
+                        RegistrarError::PrimitiveError => Error::primitive(None),
+                    })?;
+                Input::Negotiated { pre_grant }
+            }
+            Requested::Authorize { grant } => {
+                let codes = handler
+                    .device_authorizer()
+                    .authorize(grant.clone())
+                    .map_err(|()| Error::primitive(Some(grant)))?;
+                Input::Authorized(codes)
+            }
+        };
+
+        requested = match authorization.advance(input) {
+            Output::Authenticate { client, passdata } => Requested::Authenticate {
+                client: client.to_string(),
+                passdata: passdata.map(Vec::from),
+            },
+            Output::Binding { client_id } => Requested::Bind {
+                client_id: client_id.to_string(),
+            },
+            Output::Negotiate { bound_client, scope } => Requested::Negotiate {
+                bound_client: bound_client.clone(),
+                scope,
+            },
+            Output::Authorize { grant } => Requested::Authorize { grant: grant.clone() },
+            Output::Ok(codes) => return Ok(codes),
+            Output::Err(err) => return Err(*err),
+        };
+    }
+}
+
+/// A device authorization waiting for the decision of a resource owner.
+///
+/// Created by `device_verification` when a resource owner entered a valid user code.
+pub struct PendingDevice {
+    user_code: String,
+    pre_grant: PreGrant,
+}
+
+/// Errors while verifying a user code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VerificationError {
+    /// The request was malformed or did not contain a user code.
+    Invalid,
+
+    /// The user code is unknown, has expired or was already used.
+    Unknown,
+
+    /// An underlying primitive operation did not complete successfully.
+    PrimitiveError,
+}
+
+/// Look up the pending device authorization for the user code entered by a resource owner.
+pub fn device_verification(
+    handler: &mut dyn VerificationEndpoint, request: &dyn VerificationRequest,
+) -> Result<PendingDevice, VerificationError> {
+    if !request.valid() {
+        return Err(VerificationError::Invalid);
+    }
+
+    let user_code = request.user_code().ok_or(VerificationError::Invalid)?;
+    let grant = handler
+        .device_authorizer()
+        .pending(&user_code)
+        .map_err(|()| VerificationError::PrimitiveError)?
+        .ok_or(VerificationError::Unknown)?;
+
+    Ok(PendingDevice {
+        user_code: user_code.into_owned(),
+        pre_grant: PreGrant {
+            client_id: grant.client_id,
+            redirect_uri: grant.redirect_uri.into(),
+            scope: grant.scope,
+            metadata: ClientMetadata::default(),
+        },
+    })
+}
+
+impl PendingDevice {
+    /// Reference this pending state as a solicitation.
+    pub fn as_solicitation(&self) -> Solicitation<'_> {
+        Solicitation::new(&self.pre_grant)
+    }
+
+    /// Retrieve a reference to the negotiated parameters (e.g. scope). These should be displayed
+    /// to the resource owner when asking for his authorization.
+    ///
+    /// The pending grant does not record the client metadata, it is left empty.
+    pub fn pre_grant(&self) -> &PreGrant {
+        &self.pre_grant
+    }
+
+    /// Inform the backend about consent from a resource owner.
+    ///
+    /// The device receives its access token when it next polls the token endpoint.
+    pub fn authorize(
+        self, handler: &mut dyn VerificationEndpoint, owner_id: Cow<str>,
+    ) -> Result<(), VerificationError> {
+        handler
+            .device_authorizer()
+            .approve(&self.user_code, &owner_id)
+            .map_err(|()| VerificationError::PrimitiveError)
+    }
+
+    /// Denies the request, the device will be told so when it next polls the token endpoint.
+    pub fn deny(self, handler: &mut dyn VerificationEndpoint) -> Result<(), VerificationError> {
+        handler
+            .device_authorizer()
+            .deny(&self.user_code)
+            .map_err(|()| VerificationError::PrimitiveError)
+    }
+}
+
+/// Device access token issuing process
+///
+/// This state machine will go through three phases. On creation, the request will be validated
+/// and parameters for the first step will be extracted from it. It will pose some requests in the
+/// form of [`TokenOutput`] which should be satisfied with the next [`TokenInput`] data. This will
+/// eventually produce a [`BearerToken`] or an [`Error`]. While the resource owner has not yet
+/// decided, the error tells the device to continue polling.
+///
+/// [`TokenInput`]: struct.TokenInput.html
+/// [`TokenOutput`]: struct.TokenOutput.html
+/// [`BearerToken`]: ../accesstoken/struct.BearerToken.html
+/// [`Error`]: ../accesstoken/struct.Error.html
+///
+/// A rough sketch of the operational phases:
+///
+/// 1. Ensure the request is valid based on the basic requirements (includes required parameters)
+/// 2. Authenticate the client
+/// 3. Poll the device authorizer for the decision of the owner
+/// 4. Query the backend for a new (bearer) token
+pub struct DeviceToken {
+    state: TokenState,
+}
+
+/// Inner state machine for device token requests
+enum TokenState {
+    Authenticate {
+        client: String,
+        passdata: Option<Vec<u8>>,
+        device_code: String,
+    },
+    Poll {
+        client: String,
+        device_code: String,
+    },
+    Issue {
+        grant: Box<Grant>,
+    },
+    Err(Error),
+}
+
+/// Input injected by the executor into the device token state machine.
+pub enum TokenInput {
+    /// Positively answer an authentication query.
+    Authenticated,
+    /// The status of the device code, `None` if it was unknown.
+    Polled(Option<DeviceStatus>),
+    /// The token produced by the backend
+    Issued(IssuedToken),
+    /// Advance without input as far as possible, or just retrieve the output again.
+    None,
+}
+
+/// A request by the device token state machine to the executor.
+///
+/// Each variant is fulfilled by certain variants of the next inputs as an argument to
+/// `DeviceToken::advance`. The output of most states is simply repeated if `TokenInput::None` is
+/// provided instead but note that the successful bearer token response is **not** repeated.
+pub enum TokenOutput<'machine> {
+    /// The registrar should authenticate a client.
+    ///
+    /// Fulfilled by `TokenInput::Authenticated`. In an unsuccessful case, the executor should not
+    /// continue and discard the flow.
+    Authenticate {
+        /// The to-be-authenticated client.
+        client: &'machine str,
+        /// The supplied passdata/password.
+        passdata: Option<&'machine [u8]>,
+    },
+    /// The device authorizer should report the status of the device code.
+    ///
+    /// Fulfilled by `TokenInput::Polled`.
+    Poll {
+        /// The device code from the request.
+        device_code: &'machine str,
+    },
+    /// The issue should issue a new access token
+    ///
+    /// Fullfilled by `TokenInput::Issued`
+    Issue {
+        /// The grant to be used in the token generation
+        grant: &'machine Grant,
+    },
+    /// The state machine finished and a new bearer token was generated
+    ///
+    /// This output **can not** be requested repeatedly, any future `Input` will yield a primitive
+    /// error instead.
+    Ok(BearerToken),
+    /// The state machine finished in an error.
+    ///
+    /// The error will be repeated on *any* following input.
+    Err(Box<Error>),
+}
+
+impl DeviceToken {
+    /// Create the state machine. validating the request in the process
+    pub fn new(request: &dyn TokenRequest) -> Self {
+        DeviceToken {
+            state: Self::validate(request).unwrap_or_else(TokenState::Err),
+        }
+    }
+
+    /// Go to next state
+    pub fn advance(&mut self, input: TokenInput) -> TokenOutput<'_> {
+        self.state = match (self.take(), input) {
+            (current, TokenInput::None) => current,
+            (
+                TokenState::Authenticate {
+                    client, device_code, ..
+                },
+                TokenInput::Authenticated,
+            ) => TokenState::Poll { client, device_code },
+            (TokenState::Poll { client, .. }, TokenInput::Polled(status)) => {
+                Self::polled(client, status).unwrap_or_else(TokenState::Err)
+            }
+            (TokenState::Issue { grant }, TokenInput::Issued(token)) => {
+                return TokenOutput::Ok(BearerToken(token, grant.scope.to_string()));
+            }
+            (TokenState::Err(err), _) => TokenState::Err(err),
+            (_, _) => TokenState::Err(Error::primitive(None)),
+        };
+
+        self.output()
+    }
+
+    fn output(&self) -> TokenOutput<'_> {
+        match &self.state {
+            TokenState::Err(err) => TokenOutput::Err(Box::new(err.clone())),
+            TokenState::Authenticate { client, passdata, .. } => TokenOutput::Authenticate {
+                client,
+                passdata: passdata.as_deref(),
+            },
+            TokenState::Poll { device_code, .. } => TokenOutput::Poll { device_code },
+            TokenState::Issue { grant } => TokenOutput::Issue { grant },
+        }
+    }
+
+    fn take(&mut self) -> TokenState {
+        mem::replace(&mut self.state, TokenState::Err(Error::primitive(None)))
+    }
+
+    fn validate(request: &dyn TokenRequest) -> Result<TokenState, Error> {
+        if !request.valid() {
+            return Err(Error::invalid());
+        }
+
+        let authorization = request.authorization();
+        let client_id = request.client_id();
+        let client_secret = request.extension("client_secret");
+
+        let mut credentials = Credentials::None;
+        if let Some((client_id, auth)) = &authorization {
+            credentials.authenticate(client_id.as_ref(), auth.as_ref());
+        }
+
+        if let Some(client_id) = &client_id {
+            match &client_secret {
+                Some(auth) if request.allow_credentials_in_body() => {
+                    credentials.authenticate(client_id.as_ref(), auth.as_ref().as_bytes())
+                }
+                // Ignore parameter if not allowed.
+                Some(_) | None => credentials.unauthenticated(client_id.as_ref()),
+            }
+        }
+
+        match request.grant_type() {
+            Some(ref cow) if cow == DEVICE_CODE_GRANT_TYPE => (),
+            None => return Err(Error::invalid()),
+            Some(_) => return Err(Error::invalid_with(AccessTokenErrorType::UnsupportedGrantType)),
+        };
+
+        let (client_id, passdata) = credentials.into_client().ok_or_else(Error::invalid)?;
+        let device_code = request.device_code().ok_or_else(Error::invalid)?;
+
+        Ok(TokenState::Authenticate {
+            client: client_id.to_string(),
+            passdata: passdata.map(Vec::from),
+            device_code: device_code.into_owned(),
+        })
+    }
+
+    fn polled(client: String, status: Option<DeviceStatus>) -> Result<TokenState, Error> {
+        let mut grant = match status {
+            None => return Err(Error::invalid_with(AccessTokenErrorType::InvalidGrant)),
+            Some(DeviceStatus::Pending) => {
+                return Err(Error::invalid_with(AccessTokenErrorType::AuthorizationPending))
+            }
+            Some(DeviceStatus::SlowDown) => {
+                return Err(Error::invalid_with(AccessTokenErrorType::SlowDown))
+            }
+            Some(DeviceStatus::Expired) => {
+                return Err(Error::invalid_with(AccessTokenErrorType::ExpiredToken))
+            }
+            Some(DeviceStatus::Denied) => {
+                return Err(Error::invalid_with(AccessTokenErrorType::AccessDenied))
+            }
+            Some(DeviceStatus::Approved(grant)) => grant,
+        };
+
+        if grant.client_id != client {
+            return Err(Error::invalid_with(AccessTokenErrorType::InvalidGrant));
+        }
+
+        // The validity of the device code does not carry over to the token.
+        grant.until = Utc::now() + Duration::hours(1);
+        Ok(TokenState::Issue {
+            grant: Box::new(grant),
+        })
+    }
+}
+
+/// Try to redeem a device code for an access token.
+pub fn device_token(handler: &mut dyn TokenEndpoint, request: &dyn TokenRequest) -> Result<BearerToken, Error> {
+    enum Requested {
+        None,
+        Authenticate { client: String, passdata: Option<Vec<u8>> },
+        Poll { device_code: String },
+        Issue { grant: Grant },
+    }
+
+    let mut device_token = DeviceToken::new(request);
+    let mut requested = Requested::None;
+
+    loop {
+        let input = match requested {
+            Requested::None => TokenInput::None,
+            Requested::Authenticate { client, passdata } => {
+                handler
+                    .registrar()
+                    .check(&client, passdata.as_deref())
+                    .map_err(|err| match err {
+                        RegistrarError::Unspecified => Error::unauthorized("basic"),
+                        RegistrarError::PrimitiveError => Error::primitive(None),
+                    })?;
+                handler
+                    .registrar()
+                    .check_grant_type(&client, DEVICE_CODE_GRANT_TYPE)
+                    .map_err(|err| match err {
+                        RegistrarError::Unspecified => {
+                            Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
+                        }
+                        RegistrarError::PrimitiveError => Error::primitive(None),
+                    })?;
+                TokenInput::Authenticated
+            }
+            Requested::Poll { device_code } => {
+                let status = handler
+                    .device_authorizer()
+                    .poll(&device_code)
+                    .map_err(|()| Error::primitive(None))?;
+                TokenInput::Polled(status)
+            }
+            Requested::Issue { grant } => {
+                let token = handler
+                    .issuer()
+                    .issue(grant.clone())
+                    .map_err(|_| Error::primitive(Some(grant)))?;
+                TokenInput::Issued(token)
+            }
+        };
+
+        requested = match device_token.advance(input) {
+            TokenOutput::Authenticate { client, passdata } => Requested::Authenticate {
+                client: client.to_string(),
+                passdata: passdata.map(Vec::from),
+            },
+            TokenOutput::Poll { device_code } => Requested::Poll {
+                device_code: device_code.to_string(),
+            },
+            TokenOutput::Issue { grant } => Requested::Issue { grant: grant.clone() },
+            TokenOutput::Ok(token) => return Ok(token),
+            TokenOutput::Err(err) => return Err(*err),
+        };
+    }
+}
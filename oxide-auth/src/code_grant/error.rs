@@ -141,6 +141,48 @@ pub enum AccessTokenErrorType {
     UnsupportedResponseType,
 
     ServerError,
+
+    /// The authorization request of a device is still pending as the resource owner has not yet
+    /// decided over it. The client should continue polling.
+    ///
+    /// Defined in [RFC 8628](https://tools.ietf.org/html/rfc8628#section-3.5).
+    AuthorizationPending,
+
+    /// The device authorization request is still pending but the client is polling too fast. It
+    /// should continue polling at an increased interval.
+    SlowDown,
+
+    /// The device code has expired and the device authorization session has concluded.
+    ExpiredToken,
+
+    /// The resource owner denied the device authorization request.
+    AccessDenied,
+
+    /// The authorization server does not support the revocation of the presented token type.
+    ///
+    /// Defined in [RFC 7009](https://tools.ietf.org/html/rfc7009#section-2.2.1).
+    UnsupportedTokenType,
+
+    /// The DPoP proof of the request is invalid.
+    ///
+    /// Defined in [RFC 9449](https://tools.ietf.org/html/rfc9449#section-5).
+    InvalidDpopProof,
+
+    /// The registration access token is missing, expired, revoked or does not belong to the
+    /// client whose registration is managed.
+    ///
+    /// Defined in [RFC 6750](https://tools.ietf.org/html/rfc6750#section-3.1).
+    InvalidToken,
+
+    /// The value of one or more redirect uris of a client registration is invalid.
+    ///
+    /// Defined in [RFC 7591](https://tools.ietf.org/html/rfc7591#section-3.2.2).
+    InvalidRedirectUri,
+
+    /// The value of one of the client metadata fields is invalid or the fields are inconsistent.
+    ///
+    /// Defined in [RFC 7591](https://tools.ietf.org/html/rfc7591#section-3.2.2).
+    InvalidClientMetadata,
 }
 
 impl AccessTokenErrorType {
@@ -154,6 +196,15 @@ impl AccessTokenErrorType {
             AccessTokenErrorType::InvalidScope => "invalid_scope",
             AccessTokenErrorType::UnsupportedResponseType => "unsupported_response_type",
             AccessTokenErrorType::ServerError => "server_error",
+            AccessTokenErrorType::AuthorizationPending => "authorization_pending",
+            AccessTokenErrorType::SlowDown => "slow_down",
+            AccessTokenErrorType::ExpiredToken => "expired_token",
+            AccessTokenErrorType::AccessDenied => "access_denied",
+            AccessTokenErrorType::UnsupportedTokenType => "unsupported_token_type",
+            AccessTokenErrorType::InvalidDpopProof => "invalid_dpop_proof",
+            AccessTokenErrorType::InvalidToken => "invalid_token",
+            AccessTokenErrorType::InvalidRedirectUri => "invalid_redirect_uri",
+            AccessTokenErrorType::InvalidClientMetadata => "invalid_client_metadata",
         }
     }
 }
@@ -61,6 +61,15 @@ impl Pkce {
         self.allow_plain = true;
     }
 
+    /// The code challenge methods accepted by this extension.
+    pub fn supported_methods(&self) -> &'static [&'static str] {
+        if self.allow_plain {
+            &["S256", "plain"]
+        } else {
+            &["S256"]
+        }
+    }
+
     /// Create the encoded method for proposed method and challenge.
     ///
     /// The method defaults to `plain` when none is given, effectively offering increased
@@ -0,0 +1,497 @@
+//! Provides the handling for Implicit Grant Requests
+//!
+//! The access token is issued directly in the authorization response, after the resource owner
+//! consented, and delivered in the fragment of the redirect url. Errors that can be reported to the
+//! client are encoded in the fragment as well. See [Implicit Grant] in the rfc.
+//!
+//! [Implicit Grant]: https://tools.ietf.org/html/rfc6749#section-4.2
+use std::borrow::Cow;
+use std::result::Result as StdResult;
+
+use url::{form_urlencoded, Url};
+use chrono::{Duration, Utc};
+
+use crate::code_grant::authorization::{Error, ErrorUrl, Extension, Request};
+use crate::code_grant::error::{AuthorizationError, AuthorizationErrorType};
+use crate::primitives::issuer::Issuer;
+use crate::primitives::registrar::{ClientUrl, ExactUrl, Registrar, RegistrarError, PreGrant};
+use crate::primitives::grant::{Extensions, Grant};
+use crate::{endpoint::Scope, endpoint::Solicitation, primitives::registrar::BoundClient};
+
+/// Required functionality to respond to implicit grant requests.
+///
+/// Each method will only be invoked exactly once when processing a correct and authorized request,
+/// and potentially less than once when the request is faulty.  These methods should be implemented
+/// by internally using `primitives`, as it is implemented in the `frontend` module.
+pub trait Endpoint {
+    /// 'Bind' a client and redirect uri from a request to internally approved parameters.
+    fn registrar(&self) -> &dyn Registrar;
+
+    /// An extension implementation of this endpoint.
+    ///
+    /// It is possible to use `&mut ()`.
+    fn extension(&mut self) -> &mut dyn Extension;
+
+    /// Return the issuer instance to create the access token.
+    fn issuer(&mut self) -> &mut dyn Issuer;
+}
+
+/// The result will indicate wether the authorization succeed or not.
+pub struct Implicit {
+    state: ImplicitState,
+    extensions: Option<Extensions>,
+    scope: Option<Scope>,
+}
+
+enum ImplicitState {
+    /// State after request is validated
+    Binding {
+        client_id: String,
+        redirect_uri: Option<ExactUrl>,
+    },
+    Checking {
+        bound_client: BoundClient<'static>,
+    },
+    Extending {
+        bound_client: BoundClient<'static>,
+    },
+    Negotiating {
+        bound_client: BoundClient<'static>,
+    },
+    Pending {
+        pre_grant: PreGrant,
+        state: Option<String>,
+        extensions: Extensions,
+    },
+    Err(Error),
+}
+
+/// Input injected by the executor into the state machine.
+pub enum Input<'machine> {
+    /// Binding of the client succeeded
+    Bound {
+        /// Request is given again to make some additional check that need bound client to run
+        request: &'machine dyn Request,
+        /// The bound client
+        bound_client: BoundClient<'static>,
+    },
+    /// The registrar permits the client to use the implicit grant.
+    Permitted,
+    /// Extension succeeded
+    Extended(Extensions),
+    /// Negotiation done
+    Negotiated {
+        /// The pre grant from the negotiation
+        pre_grant: PreGrant,
+        /// State from the request
+        state: Option<String>,
+    },
+    /// Advance without input as far as possible, or just retrieve the output again.
+    None,
+}
+
+/// A request by the statemachine to the executor.
+///
+/// Each variant is fulfilled by certain variants of the next inputs as an argument to
+/// `Implicit::advance`. The output of most states is simply repeated if `Input::None` is
+/// provided instead.
+pub enum Output<'machine> {
+    /// Ask registrar to bind the client and checks its redirect_uri
+    Bind {
+        /// The to-be-bound client.
+        client_id: String,
+        /// The redirect_uri to check if any
+        redirect_uri: Option<ExactUrl>,
+    },
+    /// Ask registrar if the client may use the implicit grant.
+    ///
+    /// Fulfilled by `Input::Permitted`. The client should be informed with an `unauthorized_client`
+    /// error otherwise.
+    Check {
+        /// The current bound client
+        bound_client: &'machine BoundClient<'static>,
+    },
+    /// Ask for extensions if any
+    Extend,
+    /// Ask registrar to negociate
+    Negotiate {
+        /// The current bound client
+        bound_client: &'machine BoundClient<'static>,
+        /// The scope, if any
+        scope: Option<Scope>,
+    },
+    /// State machine is finished, provides parameters to construct a `Pending`
+    Ok {
+        /// The grant
+        pre_grant: PreGrant,
+        /// The state
+        state: Option<String>,
+        /// The extensions
+        extensions: Extensions,
+    },
+    /// The state machine finished in an error.
+    ///
+    /// The error will be repeated on *any* following input.
+    Err(Error),
+}
+
+impl Implicit {
+    /// Create state machine and validate request
+    pub fn new(request: &dyn Request) -> Self {
+        Implicit {
+            state: Self::validate(request).unwrap_or_else(ImplicitState::Err),
+            extensions: None,
+            scope: None,
+        }
+    }
+
+    /// Go to next state
+    pub fn advance<'req>(&mut self, input: Input<'req>) -> Output<'_> {
+        self.state = match (self.take(), input) {
+            (current, Input::None) => current,
+            (
+                ImplicitState::Binding { .. },
+                Input::Bound {
+                    request,
+                    bound_client,
+                },
+            ) => self
+                .bound(request, bound_client)
+                .unwrap_or_else(ImplicitState::Err),
+            (ImplicitState::Checking { bound_client }, Input::Permitted) => {
+                ImplicitState::Extending { bound_client }
+            }
+            (ImplicitState::Extending { bound_client }, Input::Extended(grant_extension)) => {
+                self.extended(grant_extension, bound_client)
+            }
+            (ImplicitState::Negotiating { .. }, Input::Negotiated { pre_grant, state }) => {
+                self.negotiated(state, pre_grant)
+            }
+            (ImplicitState::Err(err), _) => ImplicitState::Err(err),
+            (_, _) => ImplicitState::Err(Error::PrimitiveError),
+        };
+
+        self.output()
+    }
+
+    fn output(&self) -> Output<'_> {
+        match &self.state {
+            ImplicitState::Err(err) => Output::Err(err.clone()),
+            ImplicitState::Binding {
+                client_id,
+                redirect_uri,
+            } => Output::Bind {
+                client_id: client_id.to_string(),
+                redirect_uri: (*redirect_uri).clone(),
+            },
+            ImplicitState::Checking { bound_client } => Output::Check { bound_client },
+            ImplicitState::Extending { .. } => Output::Extend,
+            ImplicitState::Negotiating { bound_client } => Output::Negotiate {
+                bound_client: &bound_client,
+                scope: self.scope.clone(),
+            },
+            ImplicitState::Pending {
+                pre_grant,
+                state,
+                extensions,
+            } => Output::Ok {
+                pre_grant: pre_grant.clone(),
+                state: state.clone(),
+                extensions: extensions.clone(),
+            },
+        }
+    }
+
+    fn bound(&mut self, request: &dyn Request, bound_client: BoundClient<'static>) -> Result<ImplicitState> {
+        // It's done here rather than in `validate` because we need bound_client to be sure
+        // `redirect_uri` has a value
+        match request.response_type() {
+            Some(ref method) if method.as_ref() == "token" => (),
+            _ => {
+                let prepared_error = ErrorUrl::with_request(
+                    request,
+                    (*bound_client.redirect_uri).to_url(),
+                    AuthorizationErrorType::UnsupportedResponseType,
+                );
+                return Err(Error::Redirect(prepared_error.in_fragment()));
+            }
+        }
+
+        let scope = request.scope();
+        self.scope = match scope.map(|scope| scope.as_ref().parse()) {
+            None => None,
+            Some(Err(_)) => {
+                let prepared_error = ErrorUrl::with_request(
+                    request,
+                    (*bound_client.redirect_uri).to_url(),
+                    AuthorizationErrorType::InvalidScope,
+                );
+                return Err(Error::Redirect(prepared_error.in_fragment()));
+            }
+            Some(Ok(scope)) => Some(scope),
+        };
+
+        Ok(ImplicitState::Checking { bound_client })
+    }
+
+    fn extended(&mut self, grant_extension: Extensions, bound_client: BoundClient<'static>) -> ImplicitState {
+        self.extensions = Some(grant_extension);
+        ImplicitState::Negotiating { bound_client }
+    }
+
+    fn negotiated(&mut self, state: Option<String>, pre_grant: PreGrant) -> ImplicitState {
+        ImplicitState::Pending {
+            pre_grant,
+            state,
+            extensions: self.extensions.clone().expect("Should have extensions by now"),
+        }
+    }
+
+    fn take(&mut self) -> ImplicitState {
+        std::mem::replace(&mut self.state, ImplicitState::Err(Error::PrimitiveError))
+    }
+
+    fn validate(request: &dyn Request) -> Result<ImplicitState> {
+        if !request.valid() {
+            return Err(Error::Ignore);
+        };
+
+        // Check preconditions
+        let client_id = request.client_id().ok_or(Error::Ignore)?;
+        let redirect_uri: Option<Cow<ExactUrl>> = match request.redirect_uri() {
+            None => None,
+            Some(ref uri) => {
+                let parsed = uri.parse().map_err(|_| Error::Ignore)?;
+                Some(Cow::Owned(parsed))
+            }
+        };
+
+        Ok(ImplicitState::Binding {
+            client_id: client_id.into_owned(),
+            redirect_uri: redirect_uri.map(|uri| uri.into_owned()),
+        })
+    }
+}
+
+/// Retrieve allowed scope and redirect url from the registrar.
+///
+/// Checks the validity of any given input as the registrar instance communicates the registrated
+/// parameters, and that the client is permitted to use the implicit grant. The registrar can also
+/// set or override the requested (default) scope of the client. This will result in a pending
+/// request that must be authorized by the resource owner before a token is issued. If the client
+/// is not registered, the request will otherwise be ignored, if the request has some other
+/// syntactical error, the client is contacted at its redirect url with an error response in the
+/// fragment.
+pub fn implicit(handler: &mut dyn Endpoint, request: &dyn Request) -> self::Result<Pending> {
+    enum Requested {
+        None,
+        Bind {
+            client_id: String,
+            redirect_uri: Option<ExactUrl>,
+        },
+        Check {
+            client_id: String,
+            redirect_uri: Url,
+        },
+        Extend,
+        Negotiate {
+            client_id: String,
+            redirect_uri: Url,
+            scope: Option<Scope>,
+        },
+    }
+
+    let mut implicit = Implicit::new(request);
+    let mut requested = Requested::None;
+    let mut the_redirect_uri = None;
+
+    loop {
+        let input = match requested {
+            Requested::None => Input::None,
+            Requested::Bind {
+                client_id,
+                redirect_uri,
+            } => {
+                let client_url = ClientUrl {
+                    client_id: Cow::Owned(client_id),
+                    redirect_uri: redirect_uri.map(Cow::Owned),
+                };
+                let bound_client = match handler.registrar().bound_redirect(client_url) {
+                    Err(RegistrarError::Unspecified) => return Err(Error::Ignore),
+                    Err(RegistrarError::PrimitiveError) => return Err(Error::PrimitiveError),
+                    Ok(pre_grant) => pre_grant,
+                };
+                the_redirect_uri = Some(bound_client.redirect_uri.clone().into_owned());
+                Input::Bound {
+                    request,
+                    bound_client,
+                }
+            }
+            Requested::Check {
+                client_id,
+                redirect_uri,
+            } => {
+                handler
+                    .registrar()
+                    .check_response_type(&client_id, "token")
+                    .map_err(|err| match err {
+                        RegistrarError::PrimitiveError => Error::PrimitiveError,
+                        RegistrarError::Unspecified => {
+                            let prepared_error = ErrorUrl::with_request(
+                                request,
+                                redirect_uri,
+                                AuthorizationErrorType::UnauthorizedClient,
+                            );
+                            Error::Redirect(prepared_error.in_fragment())
+                        }
+                    })?;
+                Input::Permitted
+            }
+            Requested::Extend => {
+                let grant_extension = match handler.extension().extend(request) {
+                    Ok(extension_data) => extension_data,
+                    Err(()) => {
+                        let prepared_error = ErrorUrl::with_request(
+                            request,
+                            the_redirect_uri.unwrap().into(),
+                            AuthorizationErrorType::InvalidRequest,
+                        );
+                        return Err(Error::Redirect(prepared_error.in_fragment()));
+                    }
+                };
+                Input::Extended(grant_extension)
+            }
+            Requested::Negotiate {
+                client_id,
+                redirect_uri,
+                scope,
+            } => {
+                let bound_client = BoundClient {
+                    client_id: Cow::Owned(client_id),
+                    redirect_uri: Cow::Owned(redirect_uri.clone().into()),
+                };
+                let pre_grant = handler
+                    .registrar()
+                    .negotiate(bound_client, scope)
+                    .map_err(|err| match err {
+                        RegistrarError::PrimitiveError => Error::PrimitiveError,
+                        RegistrarError::Unspecified => {
+                            let prepared_error = ErrorUrl::with_request(
+                                request,
+                                redirect_uri,
+                                AuthorizationErrorType::InvalidScope,
+                            );
+                            Error::Redirect(prepared_error.in_fragment())
+                        }
+                    })?;
+                Input::Negotiated {
+                    pre_grant,
+                    state: request.state().map(|s| s.into_owned()),
+                }
+            }
+        };
+
+        requested = match implicit.advance(input) {
+            Output::Bind {
+                client_id,
+                redirect_uri,
+            } => Requested::Bind {
+                client_id,
+                redirect_uri,
+            },
+            Output::Check { bound_client } => Requested::Check {
+                client_id: bound_client.client_id.clone().into_owned(),
+                redirect_uri: bound_client.redirect_uri.to_url(),
+            },
+            Output::Extend => Requested::Extend,
+            Output::Negotiate { bound_client, scope } => Requested::Negotiate {
+                client_id: bound_client.client_id.clone().into_owned(),
+                redirect_uri: bound_client.redirect_uri.to_url(),
+                scope,
+            },
+            Output::Ok {
+                pre_grant,
+                state,
+                extensions,
+            } => {
+                return Ok(Pending {
+                    pre_grant,
+                    state,
+                    extensions,
+                })
+            }
+            Output::Err(e) => return Err(e),
+        };
+    }
+}
+
+/// Represents a valid, currently pending implicit grant request not bound to an owner. The
+/// frontend can signal a reponse using this object.
+// Don't ever implement `Clone` here. It's to make it very
+// hard for the user toaccidentally respond to a request in two conflicting ways. This has
+// potential security impact if it could be both denied and authorized.
+pub struct Pending {
+    pre_grant: PreGrant,
+    state: Option<String>,
+    extensions: Extensions,
+}
+
+impl Pending {
+    /// Reference this pending state as a solicitation.
+    pub fn as_solicitation(&self) -> Solicitation<'_> {
+        Solicitation {
+            grant: Cow::Borrowed(&self.pre_grant),
+            state: self.state.as_ref().map(|s| Cow::Borrowed(&**s)),
+        }
+    }
+
+    /// Denies the request, which redirects to the client for which the request originated.
+    pub fn deny(self) -> Result<Url> {
+        let url = self.pre_grant.redirect_uri;
+        let mut error = AuthorizationError::default();
+        error.set_type(AuthorizationErrorType::AccessDenied);
+        let error = ErrorUrl::new(url.into_url(), self.state.as_deref(), error);
+        Err(Error::Redirect(error.in_fragment()))
+    }
+
+    /// Inform the backend about consent from a resource owner.
+    ///
+    /// Use negotiated parameters to issue an access token for an owner. The token is returned in
+    /// the fragment of the redirect url, no refresh token is issued. The endpoint SHOULD be the
+    /// same endpoint as was used to create the pending request.
+    pub fn authorize(self, handler: &mut dyn Endpoint, owner_id: Cow<str>) -> Result<Url> {
+        let mut url = self.pre_grant.redirect_uri.to_url();
+        let scope = self.pre_grant.scope.to_string();
+
+        let token = handler
+            .issuer()
+            .issue(Grant {
+                owner_id: owner_id.into_owned(),
+                client_id: self.pre_grant.client_id,
+                redirect_uri: self.pre_grant.redirect_uri.into_url(),
+                scope: self.pre_grant.scope,
+                until: Utc::now() + Duration::hours(1),
+                extensions: self.extensions,
+            })
+            .map_err(|()| Error::PrimitiveError)?;
+
+        let expires_in = token.until.signed_duration_since(Utc::now()).num_seconds();
+        let fragment = form_urlencoded::Serializer::new(String::new())
+            .append_pair("access_token", &token.token)
+            .append_pair("token_type", "bearer")
+            .append_pair("expires_in", &expires_in.to_string())
+            .append_pair("scope", &scope)
+            .extend_pairs(self.state.map(|v| ("state", v)))
+            .finish();
+        url.set_fragment(Some(&fragment));
+        Ok(url)
+    }
+
+    /// Retrieve a reference to the negotiated parameters (e.g. scope). These should be displayed
+    /// to the resource owner when asking for his authorization.
+    pub fn pre_grant(&self) -> &PreGrant {
+        &self.pre_grant
+    }
+}
+
+type Result<T> = StdResult<T, Error>;
@@ -0,0 +1,470 @@
+//! Provides the handling for Token Introspection requests
+//!
+//! A protected resource, running separately from the authorization server, queries the state and
+//! meta information of a token that was presented to it. The caller authenticates either as a
+//! client or with a bearer token of its own. Tokens that are unknown, expired or revoked are simply
+//! reported as inactive. See [Token Introspection] in the rfc.
+//!
+//! [Token Introspection]: https://tools.ietf.org/html/rfc7662
+use std::mem;
+use std::borrow::Cow;
+
+use chrono::Utc;
+use serde_json::{self, Map, Value as JsonValue};
+
+use crate::code_grant::accesstoken::{Credentials, Error};
+use crate::code_grant::revoke::TokenTypeHint;
+use crate::primitives::dpop;
+use crate::primitives::mtls;
+use crate::primitives::issuer::{Issuer, TokenType};
+use crate::primitives::grant::Grant;
+use crate::primitives::registrar::{Registrar, RegistrarError};
+use crate::primitives::scope::Scope;
+
+/// Trait based retrieval of parameters necessary for introspection request handling.
+pub trait Request {
+    /// Received request might not be encoded correctly. This method gives implementors the chance
+    /// to signal that a request was received but its encoding was generally malformed. If this is
+    /// the case, then no other attribute will be queried. This method exists mainly to make
+    /// frontends straightforward by not having them handle special cases for malformed requests.
+    fn valid(&self) -> bool;
+
+    /// User:password of a basic authorization header.
+    fn authorization(&self) -> Option<(Cow<'_, str>, Cow<'_, [u8]>)>;
+
+    /// The token of a bearer authorization header.
+    ///
+    /// Must not be present together with basic authorization.
+    fn bearer(&self) -> Option<Cow<'_, str>>;
+
+    /// The client_id, optional parameter for public clients.
+    fn client_id(&self) -> Option<Cow<'_, str>>;
+
+    /// The token that should be introspected.
+    fn token(&self) -> Option<Cow<'_, str>>;
+
+    /// Optionally, a hint about the type of the token.
+    fn token_type_hint(&self) -> Option<Cow<'_, str>>;
+
+    /// Retrieve an additional parameter used in an extension
+    fn extension(&self, key: &str) -> Option<Cow<'_, str>>;
+
+    /// Credentials in body should only be enabled if use of HTTP Basic is not possible.
+    ///
+    /// Allows the request body to contain the `client_secret` as a form parameter. This is NOT
+    /// RECOMMENDED and need not be supported. The parameters MUST NOT appear in the request URI
+    /// itself.
+    ///
+    /// Under these considerations, support must be explicitely enabled.
+    fn allow_credentials_in_body(&self) -> bool {
+        false
+    }
+}
+
+/// Required functionality to respond to introspection requests.
+///
+/// Each method will only be invoked exactly once when processing a correct and authorized request,
+/// and potentially less than once when the request is faulty.  These methods should be implemented
+/// by internally using `primitives`, as it is implemented in the `frontend` module.
+pub trait Endpoint {
+    /// Authenticate callers presenting client credentials.
+    fn registrar(&self) -> &dyn Registrar;
+
+    /// Return the issuer instance to recover the tokens.
+    fn issuer(&mut self) -> &mut dyn Issuer;
+
+    /// The list of possible scopes of which a bearer token of the caller must fulfill one.
+    fn scopes(&mut self) -> &[Scope];
+}
+
+/// Token introspection process
+///
+/// This state machine will go through three phases. On creation, the request will be validated and
+/// parameters for the first step will be extracted from it. It will pose some requests in the form
+/// of [`Output`] which should be satisfied with the next [`Input`] data. This will eventually
+/// produce an [`Introspected`] token or an [`Error`]. Note that the executing environment will
+/// need to use a [`Registrar`] and an [`Issuer`] to which some requests should be forwarded.
+///
+/// [`Input`]: struct.Input.html
+/// [`Output`]: struct.Output.html
+/// [`Introspected`]: enum.Introspected.html
+/// [`Error`]: ../accesstoken/struct.Error.html
+/// [`Issuer`] ../primitives/issuer/trait.Issuer.html
+/// [`Registrar`] ../primitives/registrar/trait.Registrar.html
+///
+/// A rough sketch of the operational phases:
+///
+/// 1. Ensure the request is valid based on the basic requirements (includes required parameters)
+/// 2. Authenticate the caller, as a client or by its bearer token and the required scopes
+/// 3. Recover the grant of the token, trying the hinted kind of token first
+pub struct Introspection {
+    state: IntrospectionState,
+}
+
+/// Inner state machine for the token introspection
+enum IntrospectionState {
+    /// State after the request has been validated, for callers with client credentials.
+    Authenticate {
+        client: String,
+        passdata: Option<Vec<u8>>,
+        token: String,
+        hint: TokenTypeHint,
+    },
+    /// State after the request has been validated, for callers with a bearer token.
+    RecoverCaller {
+        bearer: String,
+        token: String,
+        hint: TokenTypeHint,
+    },
+    /// The caller grant was recovered and its scope is checked.
+    CheckCaller {
+        caller: Box<Grant>,
+        token: String,
+        hint: TokenTypeHint,
+    },
+    /// Recover the token as the `current` kind, then as the `remaining` kind if any.
+    Recover {
+        token: String,
+        current: TokenTypeHint,
+        remaining: Option<TokenTypeHint>,
+    },
+    Err(Error),
+}
+
+/// Input injected by the executor into the state machine.
+pub enum Input<'req> {
+    /// Positively answer an authentication query.
+    Authenticated,
+    /// The grant recovered from the bearer token of the caller or the introspected token.
+    ///
+    /// Is `None` if the issuer does not know the token as the requested kind.
+    Recovered(Option<Grant>),
+    /// The scopes of which the caller needs to fulfill one.
+    Scopes(&'req [Scope]),
+    /// Advance without input as far as possible, or just retrieve the output again.
+    None,
+}
+
+/// A request by the statemachine to the executor.
+///
+/// Each variant is fulfilled by certain variants of the next inputs as an argument to
+/// `Introspection::advance`. The output of most states is simply repeated if `Input::None` is
+/// provided instead but note that the successful response is **not** repeated.
+pub enum Output<'machine> {
+    /// The registrar should authenticate a client.
+    ///
+    /// Fulfilled by `Input::Authenticated`. In an unsuccessful case, the executor should not
+    /// continue and discard the flow.
+    Authenticate {
+        /// The to-be-authenticated client.
+        client: &'machine str,
+        /// The supplied passdata/password.
+        passdata: Option<&'machine [u8]>,
+    },
+    /// The issuer should recover the grant of the access token authorizing the caller.
+    ///
+    /// Fulfilled by `Input::Recovered`.
+    RecoverCaller {
+        /// The bearer token of the caller.
+        token: &'machine str,
+    },
+    /// The executor must determine the scopes required from callers.
+    ///
+    /// Fulfilled by `Input::Scopes`.
+    DetermineScopes,
+    /// The issuer should recover the grant of the introspected token as the given kind.
+    ///
+    /// Fulfilled by `Input::Recovered`.
+    Recover {
+        /// The token supplied in the request.
+        token: &'machine str,
+        /// The kind of token to look up.
+        kind: TokenTypeHint,
+    },
+    /// The state machine finished and the state of the token is known.
+    ///
+    /// This output **can not** be requested repeatedly, any future `Input` will yield a primitive
+    /// error instead.
+    Ok(Introspected),
+    /// The state machine finished in an error.
+    ///
+    /// The error will be repeated on *any* following input.
+    Err(Box<Error>),
+}
+
+/// The state of an introspected token.
+#[derive(Clone, Debug)]
+pub enum Introspected {
+    /// The token is currently valid.
+    Active {
+        /// The grant of the token.
+        grant: Box<Grant>,
+        /// The kind of the token.
+        kind: TokenTypeHint,
+    },
+    /// The token is unknown, expired or has been revoked.
+    Inactive,
+}
+
+impl Introspection {
+    /// Create the state machine. validating the request in the process
+    pub fn new(request: &dyn Request) -> Self {
+        Introspection {
+            state: Self::validate(request).unwrap_or_else(IntrospectionState::Err),
+        }
+    }
+
+    /// Go to next state
+    pub fn advance(&mut self, input: Input) -> Output<'_> {
+        self.state = match (self.take(), input) {
+            (current, Input::None) => current,
+            (IntrospectionState::Authenticate { token, hint, .. }, Input::Authenticated) => {
+                Self::recover(token, hint)
+            }
+            (IntrospectionState::RecoverCaller { token, hint, .. }, Input::Recovered(caller)) => {
+                match caller {
+                    Some(caller) if caller.until > Utc::now() => IntrospectionState::CheckCaller {
+                        caller: Box::new(caller),
+                        token,
+                        hint,
+                    },
+                    _ => IntrospectionState::Err(Error::unauthorized("Bearer")),
+                }
+            }
+            (IntrospectionState::CheckCaller { caller, token, hint }, Input::Scopes(scopes)) => {
+                if scopes.iter().any(|scope| scope.allow_access(&caller.scope)) {
+                    Self::recover(token, hint)
+                } else {
+                    IntrospectionState::Err(Error::unauthorized("Bearer"))
+                }
+            }
+            (
+                IntrospectionState::Recover {
+                    token,
+                    current,
+                    remaining,
+                },
+                Input::Recovered(grant),
+            ) => match (grant, remaining) {
+                (Some(grant), _) => {
+                    return Output::Ok(Introspected::new(grant, current));
+                }
+                (None, Some(next)) => IntrospectionState::Recover {
+                    token,
+                    current: next,
+                    remaining: None,
+                },
+                (None, None) => return Output::Ok(Introspected::Inactive),
+            },
+            (IntrospectionState::Err(err), _) => IntrospectionState::Err(err),
+            (_, _) => IntrospectionState::Err(Error::primitive(None)),
+        };
+
+        self.output()
+    }
+
+    fn output(&self) -> Output<'_> {
+        match &self.state {
+            IntrospectionState::Err(err) => Output::Err(Box::new(err.clone())),
+            IntrospectionState::Authenticate { client, passdata, .. } => Output::Authenticate {
+                client,
+                passdata: passdata.as_deref(),
+            },
+            IntrospectionState::RecoverCaller { bearer, .. } => Output::RecoverCaller { token: bearer },
+            IntrospectionState::CheckCaller { .. } => Output::DetermineScopes,
+            IntrospectionState::Recover { token, current, .. } => Output::Recover {
+                token,
+                kind: *current,
+            },
+        }
+    }
+
+    fn take(&mut self) -> IntrospectionState {
+        mem::replace(&mut self.state, IntrospectionState::Err(Error::primitive(None)))
+    }
+
+    fn validate(request: &dyn Request) -> Result<IntrospectionState, Error> {
+        if !request.valid() {
+            return Err(Error::invalid());
+        }
+
+        let token = request.token().ok_or_else(Error::invalid)?.into_owned();
+        let hint = request
+            .token_type_hint()
+            .and_then(|hint| TokenTypeHint::from_hint(&hint))
+            .unwrap_or(TokenTypeHint::AccessToken);
+
+        let authorization = request.authorization();
+        if let Some(bearer) = request.bearer() {
+            if authorization.is_some() {
+                return Err(Error::invalid());
+            }
+
+            return Ok(IntrospectionState::RecoverCaller {
+                bearer: bearer.into_owned(),
+                token,
+                hint,
+            });
+        }
+
+        let client_id = request.client_id();
+        let client_secret = request.extension("client_secret");
+
+        let mut credentials = Credentials::None;
+        if let Some((client_id, auth)) = &authorization {
+            credentials.authenticate(client_id.as_ref(), auth.as_ref());
+        }
+
+        if let Some(client_id) = &client_id {
+            match &client_secret {
+                Some(auth) if request.allow_credentials_in_body() => {
+                    credentials.authenticate(client_id.as_ref(), auth.as_ref().as_bytes())
+                }
+                // Ignore parameter if not allowed.
+                Some(_) | None => credentials.unauthenticated(client_id.as_ref()),
+            }
+        }
+
+        let (client_id, passdata) = credentials.into_client().ok_or_else(Error::invalid)?;
+
+        Ok(IntrospectionState::Authenticate {
+            client: client_id.to_string(),
+            passdata: passdata.map(Vec::from),
+            token,
+            hint,
+        })
+    }
+
+    fn recover(token: String, hint: TokenTypeHint) -> IntrospectionState {
+        IntrospectionState::Recover {
+            token,
+            current: hint,
+            remaining: Some(hint.other()),
+        }
+    }
+}
+
+impl Introspected {
+    /// The introspection result of a recovered grant, inactive if it has expired.
+    fn new(grant: Grant, kind: TokenTypeHint) -> Self {
+        if grant.until <= Utc::now() {
+            return Introspected::Inactive;
+        }
+
+        Introspected::Active {
+            grant: Box::new(grant),
+            kind,
+        }
+    }
+
+    /// Convert the state into a json string, viable for being sent over a network with
+    /// `application/json` encoding.
+    ///
+    /// Active tokens are described by the members `scope`, `client_id`, `sub`, `exp` and, for
+    /// access tokens, `token_type`. Grants bound to a DPoP key or a client certificate also
+    /// describe its thumbprint in `cnf`. All public extensions of the grant are included as
+    /// additional members, where extensions without a value are represented as `true`. Extensions
+    /// can not replace any of the standard members.
+    pub fn to_json(&self) -> String {
+        let mut response = Map::new();
+
+        if let Introspected::Active { grant, kind } = self {
+            response.insert("active".into(), JsonValue::Bool(true));
+            response.insert("scope".into(), grant.scope.to_string().into());
+            response.insert("client_id".into(), grant.client_id.clone().into());
+            response.insert("sub".into(), grant.owner_id.clone().into());
+            response.insert("exp".into(), grant.until.timestamp().into());
+            let confirmation = dpop::confirmation(grant);
+            if *kind == TokenTypeHint::AccessToken {
+                let token_type = match confirmation {
+                    Some(_) => TokenType::DPoP,
+                    None => TokenType::Bearer,
+                };
+                response.insert("token_type".into(), token_type.as_str().into());
+            }
+
+            let mut cnf = Map::new();
+            if let Some(thumbprint) = confirmation {
+                cnf.insert("jkt".into(), thumbprint.into());
+            }
+
+            if let Some(thumbprint) = mtls::confirmation(grant) {
+                cnf.insert("x5t#S256".into(), thumbprint.into());
+            }
+
+            if !cnf.is_empty() {
+                response.insert("cnf".into(), cnf.into());
+            }
+
+            for (key, value) in grant.extensions.public() {
+                if response.contains_key(key) || key == dpop::CONFIRMATION || key == mtls::CONFIRMATION {
+                    continue;
+                }
+
+                let value = value.map_or(JsonValue::Bool(true), JsonValue::from);
+                response.insert(key.to_string(), value);
+            }
+        } else {
+            response.insert("active".into(), JsonValue::Bool(false));
+        }
+
+        serde_json::to_string(&response).unwrap()
+    }
+}
+
+/// Introspect the token of a request.
+pub fn introspect(handler: &mut dyn Endpoint, request: &dyn Request) -> Result<Introspected, Error> {
+    enum Requested {
+        None,
+        Authenticate { client: String, passdata: Option<Vec<u8>> },
+        Recover { token: String, kind: TokenTypeHint },
+        Scopes,
+    }
+
+    let mut introspection = Introspection::new(request);
+    let mut requested = Requested::None;
+
+    loop {
+        let input = match requested {
+            Requested::None => Input::None,
+            Requested::Authenticate { client, passdata } => {
+                handler
+                    .registrar()
+                    .check(&client, passdata.as_deref())
+                    .map_err(|err| match err {
+                        RegistrarError::Unspecified => Error::unauthorized("basic"),
+                        RegistrarError::PrimitiveError => Error::primitive(None),
+                    })?;
+                Input::Authenticated
+            }
+            Requested::Recover { token, kind } => {
+                let issuer = handler.issuer();
+                let grant = match kind {
+                    TokenTypeHint::AccessToken => issuer.recover_token(&token),
+                    TokenTypeHint::RefreshToken => issuer.recover_refresh(&token),
+                }
+                .map_err(|()| Error::primitive(None))?;
+                Input::Recovered(grant)
+            }
+            Requested::Scopes => Input::Scopes(handler.scopes()),
+        };
+
+        requested = match introspection.advance(input) {
+            Output::Authenticate { client, passdata } => Requested::Authenticate {
+                client: client.to_string(),
+                passdata: passdata.map(Vec::from),
+            },
+            Output::RecoverCaller { token } => Requested::Recover {
+                token: token.to_string(),
+                kind: TokenTypeHint::AccessToken,
+            },
+            Output::DetermineScopes => Requested::Scopes,
+            Output::Recover { token, kind } => Requested::Recover {
+                token: token.to_string(),
+                kind,
+            },
+            Output::Ok(introspected) => return Ok(introspected),
+            Output::Err(err) => return Err(*err),
+        };
+    }
+}
@@ -0,0 +1,469 @@
+//! Provides the handling for JWT Bearer Assertion Grant requests
+//!
+//! The client presents an assertion signed by a trusted issuer, usually an identity provider of a
+//! partner, and receives an access token for the subject of the assertion. See [JWT Bearer Grant]
+//! in the rfc.
+//!
+//! [JWT Bearer Grant]: https://tools.ietf.org/html/rfc7523#section-2.1
+use std::mem;
+use std::borrow::Cow;
+
+use chrono::{Duration, Utc};
+
+use crate::code_grant::accesstoken::{BearerToken, Credentials, Error};
+use crate::code_grant::error::AccessTokenErrorType;
+use crate::primitives::issuer::{IssuedToken, Issuer};
+use crate::primitives::grant::{Extensions, Grant};
+use crate::primitives::jwt::{AssertionVerifier, TrustedIssuers, VerifiedAssertion};
+use crate::primitives::registrar::{BoundClient, ClientUrl, PreGrant, Registrar, RegistrarError};
+use crate::primitives::scope::Scope;
+
+/// The `grant_type` with which clients present a JWT assertion.
+pub const JWT_BEARER_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:jwt-bearer";
+
+/// Trait based retrieval of parameters necessary for JWT bearer request handling.
+pub trait Request {
+    /// Received request might not be encoded correctly. This method gives implementors the chance
+    /// to signal that a request was received but its encoding was generally malformed. If this is
+    /// the case, then no other attribute will be queried. This method exists mainly to make
+    /// frontends straightforward by not having them handle special cases for malformed requests.
+    fn valid(&self) -> bool;
+
+    /// User:password of a basic authorization header.
+    fn authorization(&self) -> Option<(Cow<'_, str>, Cow<'_, [u8]>)>;
+
+    /// The client_id, optional parameter for public clients.
+    fn client_id(&self) -> Option<Cow<'_, str>>;
+
+    /// The signed JWT presented by the client.
+    fn assertion(&self) -> Option<Cow<'_, str>>;
+
+    /// Optionally specifies the requested scope
+    fn scope(&self) -> Option<Cow<'_, str>>;
+
+    /// Valid requests have this set to "urn:ietf:params:oauth:grant-type:jwt-bearer"
+    fn grant_type(&self) -> Option<Cow<'_, str>>;
+
+    /// Retrieve an additional parameter used in an extension
+    fn extension(&self, key: &str) -> Option<Cow<'_, str>>;
+
+    /// Credentials in body should only be enabled if use of HTTP Basic is not possible.
+    ///
+    /// Allows the request body to contain the `client_secret` as a form parameter. This is NOT
+    /// RECOMMENDED and need not be supported. The parameters MUST NOT appear in the request URI
+    /// itself.
+    ///
+    /// Under these considerations, support must be explicitely enabled.
+    fn allow_credentials_in_body(&self) -> bool {
+        false
+    }
+}
+
+/// Required functionality to respond to JWT bearer requests.
+///
+/// Each method will only be invoked exactly once when processing a correct and authorized request,
+/// and potentially less than once when the request is faulty.  These methods should be implemented
+/// by internally using `primitives`, as it is implemented in the `frontend` module.
+pub trait Endpoint {
+    /// Authenticate the client and negotiate the scope of the grant.
+    fn registrar(&self) -> &dyn Registrar;
+
+    /// Check the signature and claims of the assertion.
+    fn verifier(&self) -> &dyn AssertionVerifier;
+
+    /// The issuers whose assertions are accepted.
+    fn trusted_issuers(&self) -> &TrustedIssuers;
+
+    /// Return the issuer instance to create the access token.
+    fn issuer(&mut self) -> &mut dyn Issuer;
+}
+
+/// JWT bearer token issuing process
+///
+/// This state machine will go through five phases. On creation, the request will be validated and
+/// parameters for the first step will be extracted from it. It will pose some requests in the form
+/// of [`Output`] which should be satisfied with the next [`Input`] data. This will eventually
+/// produce a [`BearerToken`] or an [`Error`]. Note that the executing environment will need to use
+/// a [`Registrar`], an [`AssertionVerifier`] and an [`Issuer`] to which some requests should be
+/// forwarded.
+///
+/// [`Input`]: struct.Input.html
+/// [`Output`]: struct.Output.html
+/// [`BearerToken`]: ../accesstoken/struct.BearerToken.html
+/// [`Error`]: ../accesstoken/struct.Error.html
+/// [`Issuer`] ../primitives/issuer/trait.Issuer.html
+/// [`Registrar`] ../primitives/registrar/trait.Registrar.html
+/// [`AssertionVerifier`] ../primitives/jwt/trait.AssertionVerifier.html
+///
+/// A rough sketch of the operational phases:
+///
+/// 1. Ensure the request is valid based on the basic requirements (includes required parameters)
+/// 2. Authenticate the client
+/// 3. Verify the assertion against the trusted issuers
+/// 4. Bind the client to its registered parameters and negotiate the scope
+/// 5. Query the backend for a new (bearer) token
+pub struct JwtBearer {
+    state: JwtBearerState,
+}
+
+/// Inner state machine for the JWT bearer grant
+enum JwtBearerState {
+    /// State after the request has been validated.
+    Authenticate {
+        client: String,
+        passdata: Option<Vec<u8>>,
+        assertion: String,
+        scope: Option<Scope>,
+    },
+    Verify {
+        client: String,
+        assertion: String,
+        scope: Option<Scope>,
+    },
+    Binding {
+        client_id: String,
+        owner_id: String,
+        scope: Option<Scope>,
+    },
+    Negotiating {
+        bound_client: BoundClient<'static>,
+        owner_id: String,
+        scope: Option<Scope>,
+    },
+    Issue {
+        grant: Box<Grant>,
+    },
+    Err(Error),
+}
+
+/// Input injected by the executor into the state machine.
+pub enum Input {
+    /// Positively answer an authentication query.
+    Authenticated,
+    /// The result of verifying the assertion.
+    ///
+    /// Contains the asserted identity if the assertion was accepted.
+    Verified(Option<VerifiedAssertion>),
+    /// The registrar bound the client to its registered parameters.
+    Bound {
+        /// The bound client
+        bound_client: BoundClient<'static>,
+    },
+    /// The registrar negotiated the parameters of the grant.
+    Negotiated {
+        /// The pre grant from the negotiation
+        pre_grant: PreGrant,
+    },
+    /// The token produced by the backend
+    Issued(IssuedToken),
+    /// Advance without input as far as possible, or just retrieve the output again.
+    None,
+}
+
+/// A request by the statemachine to the executor.
+///
+/// Each variant is fulfilled by certain variants of the next inputs as an argument to
+/// `JwtBearer::advance`. The output of most states is simply repeated if `Input::None` is
+/// provided instead but note that the successful bearer token response is **not** repeated.
+pub enum Output<'machine> {
+    /// The registrar should authenticate a client.
+    ///
+    /// Fulfilled by `Input::Authenticated`. In an unsuccessful case, the executor should not
+    /// continue and discard the flow.
+    Authenticate {
+        /// The to-be-authenticated client.
+        client: &'machine str,
+        /// The supplied passdata/password.
+        passdata: Option<&'machine [u8]>,
+    },
+    /// The verifier should check the assertion against the trusted issuers.
+    ///
+    /// Fulfilled by `Input::Verified`.
+    Verify {
+        /// The assertion supplied in the request.
+        assertion: &'machine str,
+    },
+    /// Ask registrar to bind the client to its default redirect_uri.
+    ///
+    /// Fulfilled by `Input::Bound`.
+    Binding {
+        /// The already authenticated client.
+        client_id: &'machine str,
+    },
+    /// Ask registrar to negotiate the scope.
+    ///
+    /// Fulfilled by `Input::Negotiated`.
+    Negotiate {
+        /// The current bound client
+        bound_client: &'machine BoundClient<'static>,
+        /// The scope, if any
+        scope: Option<Scope>,
+    },
+    /// The issue should issue a new access token
+    ///
+    /// Fullfilled by `Input::Issued`
+    Issue {
+        /// The grant to be used in the token generation
+        grant: &'machine Grant,
+    },
+    /// The state machine finished and a new bearer token was generated
+    ///
+    /// This output **can not** be requested repeatedly, any future `Input` will yield a primitive
+    /// error instead.
+    Ok(BearerToken),
+    /// The state machine finished in an error.
+    ///
+    /// The error will be repeated on *any* following input.
+    Err(Box<Error>),
+}
+
+impl JwtBearer {
+    /// Create the state machine. validating the request in the process
+    pub fn new(request: &dyn Request) -> Self {
+        JwtBearer {
+            state: Self::validate(request).unwrap_or_else(JwtBearerState::Err),
+        }
+    }
+
+    /// Go to next state
+    pub fn advance(&mut self, input: Input) -> Output<'_> {
+        self.state = match (self.take(), input) {
+            (current, Input::None) => current,
+            (
+                JwtBearerState::Authenticate {
+                    client,
+                    assertion,
+                    scope,
+                    ..
+                },
+                Input::Authenticated,
+            ) => JwtBearerState::Verify {
+                client,
+                assertion,
+                scope,
+            },
+            (JwtBearerState::Verify { client, scope, .. }, Input::Verified(verified)) => {
+                Self::verified(client, scope, verified).unwrap_or_else(JwtBearerState::Err)
+            }
+            (
+                JwtBearerState::Binding { owner_id, scope, .. },
+                Input::Bound { bound_client },
+            ) => JwtBearerState::Negotiating {
+                bound_client,
+                owner_id,
+                scope,
+            },
+            (JwtBearerState::Negotiating { owner_id, .. }, Input::Negotiated { pre_grant }) => {
+                Self::negotiated(owner_id, pre_grant)
+            }
+            (JwtBearerState::Issue { grant }, Input::Issued(token)) => {
+                return Output::Ok(BearerToken(token, grant.scope.to_string()));
+            }
+            (JwtBearerState::Err(err), _) => JwtBearerState::Err(err),
+            (_, _) => JwtBearerState::Err(Error::primitive(None)),
+        };
+
+        self.output()
+    }
+
+    fn output(&self) -> Output<'_> {
+        match &self.state {
+            JwtBearerState::Err(err) => Output::Err(Box::new(err.clone())),
+            JwtBearerState::Authenticate { client, passdata, .. } => Output::Authenticate {
+                client,
+                passdata: passdata.as_deref(),
+            },
+            JwtBearerState::Verify { assertion, .. } => Output::Verify { assertion },
+            JwtBearerState::Binding { client_id, .. } => Output::Binding { client_id },
+            JwtBearerState::Negotiating {
+                bound_client, scope, ..
+            } => Output::Negotiate {
+                bound_client,
+                scope: scope.clone(),
+            },
+            JwtBearerState::Issue { grant } => Output::Issue { grant },
+        }
+    }
+
+    fn take(&mut self) -> JwtBearerState {
+        mem::replace(&mut self.state, JwtBearerState::Err(Error::primitive(None)))
+    }
+
+    fn validate(request: &dyn Request) -> Result<JwtBearerState, Error> {
+        if !request.valid() {
+            return Err(Error::invalid());
+        }
+
+        let authorization = request.authorization();
+        let client_id = request.client_id();
+        let client_secret = request.extension("client_secret");
+
+        let mut credentials = Credentials::None;
+        if let Some((client_id, auth)) = &authorization {
+            credentials.authenticate(client_id.as_ref(), auth.as_ref());
+        }
+
+        if let Some(client_id) = &client_id {
+            match &client_secret {
+                Some(auth) if request.allow_credentials_in_body() => {
+                    credentials.authenticate(client_id.as_ref(), auth.as_ref().as_bytes())
+                }
+                // Ignore parameter if not allowed.
+                Some(_) | None => credentials.unauthenticated(client_id.as_ref()),
+            }
+        }
+
+        match request.grant_type() {
+            Some(ref cow) if cow == JWT_BEARER_GRANT_TYPE => (),
+            None => return Err(Error::invalid()),
+            Some(_) => return Err(Error::invalid_with(AccessTokenErrorType::UnsupportedGrantType)),
+        };
+
+        let (client_id, passdata) = credentials.into_client().ok_or_else(Error::invalid)?;
+        let assertion = request.assertion().ok_or_else(Error::invalid)?;
+
+        let scope = match request.scope() {
+            None => None,
+            Some(scope) => Some(
+                scope
+                    .parse()
+                    .map_err(|_| Error::invalid_with(AccessTokenErrorType::InvalidScope))?,
+            ),
+        };
+
+        Ok(JwtBearerState::Authenticate {
+            client: client_id.to_string(),
+            passdata: passdata.map(Vec::from),
+            assertion: assertion.into_owned(),
+            scope,
+        })
+    }
+
+    fn verified(
+        client_id: String, scope: Option<Scope>, verified: Option<VerifiedAssertion>,
+    ) -> Result<JwtBearerState, Error> {
+        let verified = verified.ok_or_else(|| Error::invalid_with(AccessTokenErrorType::InvalidGrant))?;
+
+        Ok(JwtBearerState::Binding {
+            client_id,
+            owner_id: verified.subject,
+            scope,
+        })
+    }
+
+    fn negotiated(owner_id: String, pre_grant: PreGrant) -> JwtBearerState {
+        JwtBearerState::Issue {
+            grant: Box::new(Grant {
+                owner_id,
+                client_id: pre_grant.client_id,
+                redirect_uri: pre_grant.redirect_uri.into_url(),
+                scope: pre_grant.scope,
+                until: Utc::now() + Duration::hours(1),
+                extensions: Extensions::new(),
+            }),
+        }
+    }
+}
+
+/// Try to issue an access token for a JWT assertion.
+pub fn jwt_bearer(handler: &mut dyn Endpoint, request: &dyn Request) -> Result<BearerToken, Error> {
+    enum Requested {
+        None,
+        Authenticate { client: String, passdata: Option<Vec<u8>> },
+        Verify { assertion: String },
+        Bind { client_id: String },
+        Negotiate { bound_client: BoundClient<'static>, scope: Option<Scope> },
+        Issue { grant: Grant },
+    }
+
+    let mut jwt_bearer = JwtBearer::new(request);
+    let mut requested = Requested::None;
+
+    loop {
+        let input = match requested {
+            Requested::None => Input::None,
+            Requested::Authenticate { client, passdata } => {
+                handler
+                    .registrar()
+                    .check(&client, passdata.as_deref())
+                    .map_err(|err| match err {
+                        RegistrarError::Unspecified => Error::unauthorized("basic"),
+                        RegistrarError::PrimitiveError => Error::primitive(None),
+                    })?;
+                handler
+                    .registrar()
+                    .check_grant_type(&client, JWT_BEARER_GRANT_TYPE)
+                    .map_err(|err| match err {
+                        RegistrarError::Unspecified => {
+                            Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
+                        }
+                        RegistrarError::PrimitiveError => Error::primitive(None),
+                    })?;
+                Input::Authenticated
+            }
+            Requested::Verify { assertion } => {
+                let verified = handler
+                    .verifier()
+                    .verify(&assertion, handler.trusted_issuers())
+                    .map_err(|()| Error::primitive(None))?;
+                Input::Verified(verified)
+            }
+            Requested::Bind { client_id } => {
+                let client_url = ClientUrl {
+                    client_id: Cow::Owned(client_id),
+                    redirect_uri: None,
+                };
+                let bound_client =
+                    handler
+                        .registrar()
+                        .bound_redirect(client_url)
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => {
+                                Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
+                            }
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        })?;
+                Input::Bound { bound_client }
+            }
+            Requested::Negotiate { bound_client, scope } => {
+                let pre_grant = handler
+                    .registrar()
+                    .negotiate(bound_client, scope)
+                    .map_err(|err| match err {
+                        RegistrarError::Unspecified => {
+                            Error::invalid_with(AccessTokenErrorType::InvalidScope)
+                        }
+                        RegistrarError::PrimitiveError => Error::primitive(None),
+                    })?;
+                Input::Negotiated { pre_grant }
+            }
+            Requested::Issue { grant } => {
+                let token = handler
+                    .issuer()
+                    .issue(grant.clone())
+                    .map_err(|_| Error::primitive(Some(grant)))?;
+                Input::Issued(token)
+            }
+        };
+
+        requested = match jwt_bearer.advance(input) {
+            Output::Authenticate { client, passdata } => Requested::Authenticate {
+                client: client.to_string(),
+                passdata: passdata.map(Vec::from),
+            },
+            Output::Verify { assertion } => Requested::Verify {
+                assertion: assertion.to_string(),
+            },
+            Output::Binding { client_id } => Requested::Bind {
+                client_id: client_id.to_string(),
+            },
+            Output::Negotiate { bound_client, scope } => Requested::Negotiate {
+                bound_client: bound_client.clone(),
+                scope,
+            },
+            Output::Issue { grant } => Requested::Issue { grant: grant.clone() },
+            Output::Ok(token) => return Ok(token),
+            Output::Err(err) => return Err(*err),
+        };
+    }
+}
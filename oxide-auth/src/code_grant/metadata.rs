@@ -0,0 +1,215 @@
+//! Provides the authorization server metadata document.
+//!
+//! Clients discover the endpoints and capabilities of an authorization server by requesting a
+//! json document at the well-known uri `/.well-known/oauth-authorization-server`. The locations
+//! of the endpoints are configured by the server while the supported grant types, response types
+//! and extensions can be derived from the configured endpoint itself. See [Authorization Server
+//! Metadata] in the rfc.
+//!
+//! [Authorization Server Metadata]: https://tools.ietf.org/html/rfc8414
+use serde::Serialize;
+use serde_json;
+use url::Url;
+
+/// The metadata describing an authorization server.
+///
+/// Only the `issuer` is required on construction, the locations of the other endpoints should be
+/// added as they are deployed. The lists of supported values are usually filled by the
+/// `MetadataFlow` from the primitives of the endpoint and by extensions, additional values can
+/// still be added manually. Adding a value is idempotent.
+#[derive(Clone, Debug, Serialize)]
+pub struct Metadata {
+    issuer: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    authorization_endpoint: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token_endpoint: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    jwks_uri: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    registration_endpoint: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    revocation_endpoint: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    introspection_endpoint: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    device_authorization_endpoint: Option<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    scopes_supported: Vec<String>,
+
+    response_types_supported: Vec<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    grant_types_supported: Vec<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    token_endpoint_auth_methods_supported: Vec<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    code_challenge_methods_supported: Vec<String>,
+}
+
+/// A system of addons advertising their capabilities.
+///
+/// An endpoint not having any extension may use `&mut ()` as the result of system.
+pub trait Extension {
+    /// Add the values supported by the extensions to the metadata.
+    fn extend(&mut self, metadata: &mut Metadata);
+}
+
+impl Extension for () {
+    fn extend(&mut self, _: &mut Metadata) {}
+}
+
+impl Metadata {
+    /// Describe the authorization server identified by the issuer url.
+    ///
+    /// The issuer must use the `https` scheme and not contain a query or fragment component.
+    pub fn new(issuer: Url) -> Self {
+        Metadata {
+            issuer: issuer.into(),
+            authorization_endpoint: None,
+            token_endpoint: None,
+            jwks_uri: None,
+            registration_endpoint: None,
+            revocation_endpoint: None,
+            introspection_endpoint: None,
+            device_authorization_endpoint: None,
+            scopes_supported: Vec::new(),
+            response_types_supported: Vec::new(),
+            grant_types_supported: Vec::new(),
+            token_endpoint_auth_methods_supported: Vec::new(),
+            code_challenge_methods_supported: Vec::new(),
+        }
+    }
+
+    /// Set the location of the authorization endpoint.
+    pub fn with_authorization_endpoint(self, url: Url) -> Self {
+        Metadata {
+            authorization_endpoint: Some(url.into()),
+            ..self
+        }
+    }
+
+    /// Set the location of the token endpoint.
+    pub fn with_token_endpoint(self, url: Url) -> Self {
+        Metadata {
+            token_endpoint: Some(url.into()),
+            ..self
+        }
+    }
+
+    /// Set the location of the json web key set of the server.
+    pub fn with_jwks_uri(self, url: Url) -> Self {
+        Metadata {
+            jwks_uri: Some(url.into()),
+            ..self
+        }
+    }
+
+    /// Set the location of the dynamic client registration endpoint.
+    pub fn with_registration_endpoint(self, url: Url) -> Self {
+        Metadata {
+            registration_endpoint: Some(url.into()),
+            ..self
+        }
+    }
+
+    /// Set the location of the token revocation endpoint.
+    pub fn with_revocation_endpoint(self, url: Url) -> Self {
+        Metadata {
+            revocation_endpoint: Some(url.into()),
+            ..self
+        }
+    }
+
+    /// Set the location of the token introspection endpoint.
+    pub fn with_introspection_endpoint(self, url: Url) -> Self {
+        Metadata {
+            introspection_endpoint: Some(url.into()),
+            ..self
+        }
+    }
+
+    /// Set the location of the device authorization endpoint.
+    pub fn with_device_authorization_endpoint(self, url: Url) -> Self {
+        Metadata {
+            device_authorization_endpoint: Some(url.into()),
+            ..self
+        }
+    }
+
+    /// Advertise a scope token that clients can request.
+    pub fn add_scope(&mut self, scope: &str) {
+        Self::add(&mut self.scopes_supported, scope)
+    }
+
+    /// Advertise a `response_type` of the authorization endpoint.
+    pub fn add_response_type(&mut self, response_type: &str) {
+        Self::add(&mut self.response_types_supported, response_type)
+    }
+
+    /// Advertise a `grant_type` of the token endpoint.
+    pub fn add_grant_type(&mut self, grant_type: &str) {
+        Self::add(&mut self.grant_types_supported, grant_type)
+    }
+
+    /// Advertise a method of client authentication at the token endpoint.
+    pub fn add_token_endpoint_auth_method(&mut self, method: &str) {
+        Self::add(&mut self.token_endpoint_auth_methods_supported, method)
+    }
+
+    /// Advertise a PKCE `code_challenge_method`.
+    pub fn add_code_challenge_method(&mut self, method: &str) {
+        Self::add(&mut self.code_challenge_methods_supported, method)
+    }
+
+    /// The identifier of the authorization server.
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    /// The advertised scope tokens.
+    pub fn scopes_supported(&self) -> &[String] {
+        &self.scopes_supported
+    }
+
+    /// The advertised response types of the authorization endpoint.
+    pub fn response_types_supported(&self) -> &[String] {
+        &self.response_types_supported
+    }
+
+    /// The advertised grant types of the token endpoint.
+    pub fn grant_types_supported(&self) -> &[String] {
+        &self.grant_types_supported
+    }
+
+    /// The advertised methods of client authentication at the token endpoint.
+    pub fn token_endpoint_auth_methods_supported(&self) -> &[String] {
+        &self.token_endpoint_auth_methods_supported
+    }
+
+    /// The advertised PKCE code challenge methods.
+    pub fn code_challenge_methods_supported(&self) -> &[String] {
+        &self.code_challenge_methods_supported
+    }
+
+    /// Convert the metadata into a json string.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    fn add(list: &mut Vec<String>, value: &str) {
+        if !list.iter().any(|existing| existing == value) {
+            list.push(value.to_string());
+        }
+    }
+}
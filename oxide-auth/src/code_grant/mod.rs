@@ -29,7 +29,17 @@
 
 pub mod accesstoken;
 pub mod authorization;
+pub mod client_credentials;
+pub mod device;
 pub mod error;
 pub mod extensions;
+pub mod implicit;
+pub mod introspection;
+pub mod jwt_bearer;
+pub mod metadata;
+pub mod password;
 pub mod refresh;
+pub mod registration;
 pub mod resource;
+pub mod revoke;
+pub mod token_exchange;
@@ -0,0 +1,499 @@
+//! Provides the handling for Resource Owner Password Credentials Requests
+//!
+//! See [Resource Owner Password Credentials Grant] in the rfc.
+//!
+//! [Resource Owner Password Credentials Grant]: https://tools.ietf.org/html/rfc6749#section-4.3
+use std::mem;
+use std::borrow::Cow;
+
+use chrono::{Duration, Utc};
+
+use crate::code_grant::accesstoken::{BearerToken, Credentials, Error};
+use crate::code_grant::error::AccessTokenErrorType;
+use crate::primitives::authenticator::OwnerAuthenticator;
+use crate::primitives::issuer::{IssuedToken, Issuer};
+use crate::primitives::grant::{Extensions, Grant};
+use crate::primitives::registrar::{BoundClient, ClientUrl, PreGrant, Registrar, RegistrarError};
+use crate::primitives::scope::Scope;
+
+/// Trait based retrieval of parameters necessary for password request handling.
+pub trait Request {
+    /// Received request might not be encoded correctly. This method gives implementors the chance
+    /// to signal that a request was received but its encoding was generally malformed. If this is
+    /// the case, then no other attribute will be queried. This method exists mainly to make
+    /// frontends straightforward by not having them handle special cases for malformed requests.
+    fn valid(&self) -> bool;
+
+    /// User:password of a basic authorization header.
+    fn authorization(&self) -> Option<(Cow<'_, str>, Cow<'_, [u8]>)>;
+
+    /// The client_id, optional parameter for public clients.
+    fn client_id(&self) -> Option<Cow<'_, str>>;
+
+    /// The username of the resource owner.
+    fn username(&self) -> Option<Cow<'_, str>>;
+
+    /// The password of the resource owner.
+    fn password(&self) -> Option<Cow<'_, str>>;
+
+    /// Optionally specifies the requested scope
+    fn scope(&self) -> Option<Cow<'_, str>>;
+
+    /// Valid requests have this set to "password"
+    fn grant_type(&self) -> Option<Cow<'_, str>>;
+
+    /// Retrieve an additional parameter used in an extension
+    fn extension(&self, key: &str) -> Option<Cow<'_, str>>;
+
+    /// Credentials in body should only be enabled if use of HTTP Basic is not possible.
+    ///
+    /// Allows the request body to contain the `client_secret` as a form parameter. This is NOT
+    /// RECOMMENDED and need not be supported. The parameters MUST NOT appear in the request URI
+    /// itself.
+    ///
+    /// Under these considerations, support must be explicitely enabled.
+    fn allow_credentials_in_body(&self) -> bool {
+        false
+    }
+}
+
+/// Required functionality to respond to password requests.
+///
+/// Each method will only be invoked exactly once when processing a correct and authorized request,
+/// and potentially less than once when the request is faulty.  These methods should be implemented
+/// by internally using `primitives`, as it is implemented in the `frontend` module.
+pub trait Endpoint {
+    /// Authenticate the client, check its permission and negotiate the scope of the grant.
+    fn registrar(&self) -> &dyn Registrar;
+
+    /// Check the credentials of the resource owner.
+    fn authenticator(&self) -> &dyn OwnerAuthenticator;
+
+    /// Return the issuer instance to create the access token.
+    fn issuer(&mut self) -> &mut dyn Issuer;
+}
+
+/// Password token issuing process
+///
+/// This state machine will go through six phases. On creation, the request will be validated and
+/// parameters for the first step will be extracted from it. It will pose some requests in the form
+/// of [`Output`] which should be satisfied with the next [`Input`] data. This will eventually
+/// produce a [`BearerToken`] or an [`Error`]. Note that the executing environment will need to use
+/// a [`Registrar`], an [`OwnerAuthenticator`] and an [`Issuer`] to which some requests should be
+/// forwarded.
+///
+/// [`Input`]: struct.Input.html
+/// [`Output`]: struct.Output.html
+/// [`BearerToken`]: ../accesstoken/struct.BearerToken.html
+/// [`Error`]: ../accesstoken/struct.Error.html
+/// [`Issuer`] ../primitives/issuer/trait.Issuer.html
+/// [`Registrar`] ../primitives/registrar/trait.Registrar.html
+/// [`OwnerAuthenticator`] ../primitives/authenticator/trait.OwnerAuthenticator.html
+///
+/// A rough sketch of the operational phases:
+///
+/// 1. Ensure the request is valid based on the basic requirements (includes required parameters)
+/// 2. Authenticate the client
+/// 3. Check that the client has been permitted to use this grant
+/// 4. Authenticate the resource owner with the supplied credentials
+/// 5. Bind the client to its registered parameters and negotiate the scope
+/// 6. Query the backend for a new (bearer) token
+pub struct Password {
+    state: PasswordState,
+}
+
+/// Inner state machine for the password grant
+enum PasswordState {
+    /// State after the request has been validated.
+    Authenticate {
+        client: String,
+        passdata: Option<Vec<u8>>,
+        owner: OwnerCredentials,
+    },
+    Permit {
+        client: String,
+        owner: OwnerCredentials,
+    },
+    AuthenticateOwner {
+        client: String,
+        owner: OwnerCredentials,
+    },
+    Binding {
+        client_id: String,
+        owner_id: String,
+        scope: Option<Scope>,
+    },
+    Negotiating {
+        bound_client: BoundClient<'static>,
+        owner_id: String,
+        scope: Option<Scope>,
+    },
+    Issue {
+        grant: Box<Grant>,
+    },
+    Err(Error),
+}
+
+/// Credentials and requested scope of the resource owner, taken from the request.
+struct OwnerCredentials {
+    username: String,
+    password: String,
+    scope: Option<Scope>,
+}
+
+/// Input injected by the executor into the state machine.
+pub enum Input {
+    /// Positively answer an authentication query.
+    Authenticated,
+    /// The client is permitted to use the password grant.
+    Permitted,
+    /// The result of checking the credentials of the owner.
+    ///
+    /// Contains the `owner_id` of the resource owner if the credentials were correct.
+    OwnerAuthenticated(Option<String>),
+    /// The registrar bound the client to its registered parameters.
+    Bound {
+        /// The bound client
+        bound_client: BoundClient<'static>,
+    },
+    /// The registrar negotiated the parameters of the grant.
+    Negotiated {
+        /// The pre grant from the negotiation
+        pre_grant: PreGrant,
+    },
+    /// The token produced by the backend
+    Issued(IssuedToken),
+    /// Advance without input as far as possible, or just retrieve the output again.
+    None,
+}
+
+/// A request by the statemachine to the executor.
+///
+/// Each variant is fulfilled by certain variants of the next inputs as an argument to
+/// `Password::advance`. The output of most states is simply repeated if `Input::None` is
+/// provided instead but note that the successful bearer token response is **not** repeated.
+pub enum Output<'machine> {
+    /// The registrar should authenticate a client.
+    ///
+    /// Fulfilled by `Input::Authenticated`. In an unsuccessful case, the executor should not
+    /// continue and discard the flow.
+    Authenticate {
+        /// The to-be-authenticated client.
+        client: &'machine str,
+        /// The supplied passdata/password.
+        passdata: Option<&'machine [u8]>,
+    },
+    /// The registrar should check that the client may use the password grant.
+    ///
+    /// Fulfilled by `Input::Permitted`.
+    Permit {
+        /// The authenticated client.
+        client: &'machine str,
+    },
+    /// The authenticator should check the credentials of the resource owner.
+    ///
+    /// Fulfilled by `Input::OwnerAuthenticated`.
+    AuthenticateOwner {
+        /// The username supplied in the request.
+        username: &'machine str,
+        /// The password supplied in the request.
+        password: &'machine str,
+    },
+    /// Ask registrar to bind the client to its default redirect_uri.
+    ///
+    /// Fulfilled by `Input::Bound`.
+    Binding {
+        /// The already authenticated client.
+        client_id: &'machine str,
+    },
+    /// Ask registrar to negotiate the scope.
+    ///
+    /// Fulfilled by `Input::Negotiated`.
+    Negotiate {
+        /// The current bound client
+        bound_client: &'machine BoundClient<'static>,
+        /// The scope, if any
+        scope: Option<Scope>,
+    },
+    /// The issue should issue a new access token
+    ///
+    /// Fullfilled by `Input::Issued`
+    Issue {
+        /// The grant to be used in the token generation
+        grant: &'machine Grant,
+    },
+    /// The state machine finished and a new bearer token was generated
+    ///
+    /// This output **can not** be requested repeatedly, any future `Input` will yield a primitive
+    /// error instead.
+    Ok(BearerToken),
+    /// The state machine finished in an error.
+    ///
+    /// The error will be repeated on *any* following input.
+    Err(Box<Error>),
+}
+
+impl Password {
+    /// Create the state machine. validating the request in the process
+    pub fn new(request: &dyn Request) -> Self {
+        Password {
+            state: Self::validate(request).unwrap_or_else(PasswordState::Err),
+        }
+    }
+
+    /// Go to next state
+    pub fn advance(&mut self, input: Input) -> Output<'_> {
+        self.state = match (self.take(), input) {
+            (current, Input::None) => current,
+            (PasswordState::Authenticate { client, owner, .. }, Input::Authenticated) => {
+                PasswordState::Permit { client, owner }
+            }
+            (PasswordState::Permit { client, owner }, Input::Permitted) => {
+                PasswordState::AuthenticateOwner { client, owner }
+            }
+            (PasswordState::AuthenticateOwner { client, owner }, Input::OwnerAuthenticated(owner_id)) => {
+                Self::owner_authenticated(client, owner, owner_id).unwrap_or_else(PasswordState::Err)
+            }
+            (
+                PasswordState::Binding { owner_id, scope, .. },
+                Input::Bound { bound_client },
+            ) => PasswordState::Negotiating {
+                bound_client,
+                owner_id,
+                scope,
+            },
+            (PasswordState::Negotiating { owner_id, .. }, Input::Negotiated { pre_grant }) => {
+                Self::negotiated(owner_id, pre_grant)
+            }
+            (PasswordState::Issue { grant }, Input::Issued(token)) => {
+                return Output::Ok(Self::finish(grant, token));
+            }
+            (PasswordState::Err(err), _) => PasswordState::Err(err),
+            (_, _) => PasswordState::Err(Error::primitive(None)),
+        };
+
+        self.output()
+    }
+
+    fn output(&mut self) -> Output<'_> {
+        match &mut self.state {
+            PasswordState::Err(err) => Output::Err(Box::new(err.clone())),
+            PasswordState::Authenticate { client, passdata, .. } => Output::Authenticate {
+                client,
+                passdata: passdata.as_ref().map(Vec::as_slice),
+            },
+            PasswordState::Permit { client, .. } => Output::Permit { client },
+            PasswordState::AuthenticateOwner { owner, .. } => Output::AuthenticateOwner {
+                username: &owner.username,
+                password: &owner.password,
+            },
+            PasswordState::Binding { client_id, .. } => Output::Binding { client_id },
+            PasswordState::Negotiating {
+                bound_client, scope, ..
+            } => Output::Negotiate {
+                bound_client,
+                scope: scope.clone(),
+            },
+            PasswordState::Issue { grant } => Output::Issue { grant },
+        }
+    }
+
+    fn take(&mut self) -> PasswordState {
+        mem::replace(&mut self.state, PasswordState::Err(Error::primitive(None)))
+    }
+
+    fn validate(request: &dyn Request) -> Result<PasswordState, Error> {
+        if !request.valid() {
+            return Err(Error::invalid());
+        }
+
+        let authorization = request.authorization();
+        let client_id = request.client_id();
+        let client_secret = request.extension("client_secret");
+
+        let mut credentials = Credentials::None;
+        if let Some((client_id, auth)) = &authorization {
+            credentials.authenticate(client_id.as_ref(), auth.as_ref());
+        }
+
+        if let Some(client_id) = &client_id {
+            match &client_secret {
+                Some(auth) if request.allow_credentials_in_body() => {
+                    credentials.authenticate(client_id.as_ref(), auth.as_ref().as_bytes())
+                }
+                // Ignore parameter if not allowed.
+                Some(_) | None => credentials.unauthenticated(client_id.as_ref()),
+            }
+        }
+
+        match request.grant_type() {
+            Some(ref cow) if cow == "password" => (),
+            None => return Err(Error::invalid()),
+            Some(_) => return Err(Error::invalid_with(AccessTokenErrorType::UnsupportedGrantType)),
+        };
+
+        let (client_id, passdata) = credentials.into_client().ok_or_else(Error::invalid)?;
+
+        let username = request.username().ok_or_else(Error::invalid)?;
+        let password = request.password().ok_or_else(Error::invalid)?;
+
+        let scope = match request.scope() {
+            None => None,
+            Some(scope) => Some(
+                scope
+                    .parse()
+                    .map_err(|_| Error::invalid_with(AccessTokenErrorType::InvalidScope))?,
+            ),
+        };
+
+        Ok(PasswordState::Authenticate {
+            client: client_id.to_string(),
+            passdata: passdata.map(Vec::from),
+            owner: OwnerCredentials {
+                username: username.into_owned(),
+                password: password.into_owned(),
+                scope,
+            },
+        })
+    }
+
+    fn owner_authenticated(
+        client_id: String, owner: OwnerCredentials, owner_id: Option<String>,
+    ) -> Result<PasswordState, Error> {
+        let owner_id = owner_id.ok_or_else(|| Error::invalid_with(AccessTokenErrorType::InvalidGrant))?;
+
+        Ok(PasswordState::Binding {
+            client_id,
+            owner_id,
+            scope: owner.scope,
+        })
+    }
+
+    fn negotiated(owner_id: String, pre_grant: PreGrant) -> PasswordState {
+        PasswordState::Issue {
+            grant: Box::new(Grant {
+                owner_id,
+                client_id: pre_grant.client_id,
+                redirect_uri: pre_grant.redirect_uri.into_url(),
+                scope: pre_grant.scope,
+                until: Utc::now() + Duration::hours(1),
+                extensions: Extensions::new(),
+            }),
+        }
+    }
+
+    fn finish(grant: Box<Grant>, token: IssuedToken) -> BearerToken {
+        BearerToken(token, grant.scope.to_string())
+    }
+}
+
+/// Try to issue an access token for the credentials of a resource owner.
+pub fn password(handler: &mut dyn Endpoint, request: &dyn Request) -> Result<BearerToken, Error> {
+    enum Requested {
+        None,
+        Authenticate { client: String, passdata: Option<Vec<u8>> },
+        Permit { client: String },
+        AuthenticateOwner { username: String, password: String },
+        Bind { client_id: String },
+        Negotiate { bound_client: BoundClient<'static>, scope: Option<Scope> },
+        Issue { grant: Grant },
+    }
+
+    let mut password = Password::new(request);
+    let mut requested = Requested::None;
+
+    loop {
+        let input = match requested {
+            Requested::None => Input::None,
+            Requested::Authenticate { client, passdata } => {
+                handler
+                    .registrar()
+                    .check(&client, passdata.as_deref())
+                    .map_err(|err| match err {
+                        RegistrarError::Unspecified => Error::unauthorized("basic"),
+                        RegistrarError::PrimitiveError => Error::primitive(None),
+                    })?;
+                Input::Authenticated
+            }
+            Requested::Permit { client } => {
+                handler
+                    .registrar()
+                    .check_grant_type(&client, "password")
+                    .map_err(|err| match err {
+                        RegistrarError::Unspecified => {
+                            Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
+                        }
+                        RegistrarError::PrimitiveError => Error::primitive(None),
+                    })?;
+                Input::Permitted
+            }
+            Requested::AuthenticateOwner { username, password } => {
+                let owner_id = handler
+                    .authenticator()
+                    .authenticate(&username, password.as_bytes())
+                    .map_err(|()| Error::primitive(None))?;
+                Input::OwnerAuthenticated(owner_id)
+            }
+            Requested::Bind { client_id } => {
+                let client_url = ClientUrl {
+                    client_id: Cow::Owned(client_id),
+                    redirect_uri: None,
+                };
+                let bound_client =
+                    handler
+                        .registrar()
+                        .bound_redirect(client_url)
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => {
+                                Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
+                            }
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        })?;
+                Input::Bound { bound_client }
+            }
+            Requested::Negotiate { bound_client, scope } => {
+                let pre_grant = handler
+                    .registrar()
+                    .negotiate(bound_client, scope)
+                    .map_err(|err| match err {
+                        RegistrarError::Unspecified => {
+                            Error::invalid_with(AccessTokenErrorType::InvalidScope)
+                        }
+                        RegistrarError::PrimitiveError => Error::primitive(None),
+                    })?;
+                Input::Negotiated { pre_grant }
+            }
+            Requested::Issue { grant } => {
+                let token = handler
+                    .issuer()
+                    .issue(grant.clone())
+                    .map_err(|_| Error::primitive(Some(grant)))?;
+                Input::Issued(token)
+            }
+        };
+
+        requested = match password.advance(input) {
+            Output::Authenticate { client, passdata } => Requested::Authenticate {
+                client: client.to_string(),
+                passdata: passdata.map(Vec::from),
+            },
+            Output::Permit { client } => Requested::Permit {
+                client: client.to_string(),
+            },
+            Output::AuthenticateOwner { username, password } => Requested::AuthenticateOwner {
+                username: username.to_string(),
+                password: password.to_string(),
+            },
+            Output::Binding { client_id } => Requested::Bind {
+                client_id: client_id.to_string(),
+            },
+            Output::Negotiate { bound_client, scope } => Requested::Negotiate {
+                bound_client: bound_client.clone(),
+                scope,
+            },
+            Output::Issue { grant } => Requested::Issue { grant: grant.clone() },
+            Output::Ok(token) => return Ok(token),
+            Output::Err(err) => return Err(*err),
+        };
+    }
+}
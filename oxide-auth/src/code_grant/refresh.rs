@@ -5,11 +5,13 @@ use std::collections::HashMap;
 use chrono::{Duration, Utc};
 
 use crate::code_grant::{
-    accesstoken::TokenResponse,
+    accesstoken::{authenticate, TokenResponse},
     error::{AccessTokenError, AccessTokenErrorType},
 };
 use crate::primitives::grant::Grant;
-use crate::primitives::issuer::{RefreshedToken, Issuer};
+use crate::primitives::dpop;
+use crate::primitives::mtls::{self, ClientCertificate};
+use crate::primitives::issuer::{RefreshedToken, Issuer, TokenType};
 use crate::primitives::registrar::{Registrar, RegistrarError};
 
 /// Required content of a refresh request.
@@ -38,6 +40,15 @@ pub trait Request {
 
     /// Retrieve an additional parameter used in an extension
     fn extension(&self, key: &str) -> Option<Cow<str>>;
+
+    /// The verified client certificate of a mutual-TLS connection.
+    ///
+    /// Clients that provide no passphrase authenticate with the certificate instead. Grants bound
+    /// to a certificate can only be refreshed with the same certificate. The default implementation
+    /// provides no certificate.
+    fn certificate(&self) -> Option<Cow<'_, ClientCertificate>> {
+        None
+    }
 }
 
 /// The specific endpoint trait for refreshing.
@@ -89,6 +100,7 @@ pub struct BearerToken(RefreshedToken, String);
 #[derive(Debug)]
 pub struct Refresh {
     state: RefreshState,
+    certificate: Option<ClientCertificate>,
 }
 
 /// Inner state machine for refreshing.
@@ -167,6 +179,8 @@ pub enum Output<'a> {
         client: &'a str,
         /// The supplied passdata/password.
         pass: Option<&'a [u8]>,
+        /// The certificate of the connection, used when no passdata was supplied.
+        certificate: Option<&'a ClientCertificate>,
     },
     /// The issuer should try to recover the grant of a refresh token.
     ///
@@ -230,6 +244,7 @@ impl Refresh {
     pub fn new(request: &dyn Request) -> Self {
         Refresh {
             state: initialize(request).unwrap_or_else(RefreshState::Err),
+            certificate: request.certificate().map(Cow::into_owned),
         }
     }
 
@@ -257,12 +272,15 @@ impl Refresh {
                 self.output()
             }
             (RefreshState::Recovering { authenticated, token }, Input::Recovered { scope, grant }) => {
-                self.state = recovered_refresh(scope, authenticated, grant, token)
+                let certificate = self.certificate.as_ref();
+                self.state = recovered_refresh(scope, authenticated, grant, token, certificate)
                     .unwrap_or_else(RefreshState::Err);
                 self.output()
             }
             (RefreshState::CoAuthenticating { grant, token }, Input::Authenticated { scope }) => {
-                self.state = co_authenticated(scope, grant, token).unwrap_or_else(RefreshState::Err);
+                let certificate = self.certificate.as_ref();
+                self.state =
+                    co_authenticated(scope, grant, token, certificate).unwrap_or_else(RefreshState::Err);
                 self.output()
             }
             (RefreshState::Issuing { grant, token: _ }, Input::Refreshed(token)) => {
@@ -296,10 +314,12 @@ impl Refresh {
             RefreshState::Authenticating { client, passdata, .. } => Output::Unauthenticated {
                 client,
                 pass: passdata.as_ref().map(|vec| vec.as_slice()),
+                certificate: self.certificate.as_ref(),
             },
             RefreshState::CoAuthenticating { grant, .. } => Output::Unauthenticated {
                 client: &grant.client_id,
                 pass: None,
+                certificate: self.certificate.as_ref(),
             },
             RefreshState::Recovering { token, .. } => Output::RecoverRefresh { token: &token },
             RefreshState::Issuing { token, grant, .. } => Output::Refresh {
@@ -331,9 +351,18 @@ impl<'req> Input<'req> {
 pub fn refresh(handler: &mut dyn Endpoint, request: &dyn Request) -> Result<BearerToken> {
     enum Requested {
         None,
-        Refresh { token: String, grant: Box<Grant> },
-        RecoverRefresh { token: String },
-        Authenticate { client: String, pass: Option<Vec<u8>> },
+        Refresh {
+            token: String,
+            grant: Box<Grant>,
+        },
+        RecoverRefresh {
+            token: String,
+        },
+        Authenticate {
+            client: String,
+            pass: Option<Vec<u8>>,
+            certificate: Option<ClientCertificate>,
+        },
     }
     let mut refresh = Refresh::new(request);
     let mut requested = Requested::None;
@@ -341,6 +370,15 @@ pub fn refresh(handler: &mut dyn Endpoint, request: &dyn Request) -> Result<Bear
         let input = match requested {
             Requested::None => Input::None,
             Requested::Refresh { token, grant } => {
+                handler
+                    .registrar()
+                    .check_grant_type(&grant.client_id, "refresh_token")
+                    .map_err(|err| match err {
+                        RegistrarError::PrimitiveError => Error::Primitive,
+                        RegistrarError::Unspecified => {
+                            Error::invalid(AccessTokenErrorType::UnauthorizedClient)
+                        }
+                    })?;
                 let refreshed = handler
                     .issuer()
                     .refresh(&token, *grant)
@@ -357,15 +395,21 @@ pub fn refresh(handler: &mut dyn Endpoint, request: &dyn Request) -> Result<Bear
                     grant: recovered.map(Box::new),
                 }
             }
-            Requested::Authenticate { client, pass } => {
-                let _: () =
-                    handler
-                        .registrar()
-                        .check(&client, pass.as_deref())
-                        .map_err(|err| match err {
-                            RegistrarError::PrimitiveError => Error::Primitive,
-                            RegistrarError::Unspecified => Error::unauthorized("basic"),
-                        })?;
+            Requested::Authenticate {
+                client,
+                pass,
+                certificate,
+            } => {
+                let _: () = authenticate(
+                    handler.registrar(),
+                    &client,
+                    pass.as_deref(),
+                    certificate.as_ref(),
+                )
+                .map_err(|err| match err {
+                    RegistrarError::PrimitiveError => Error::Primitive,
+                    RegistrarError::Unspecified => Error::unauthorized("basic"),
+                })?;
                 Input::Authenticated {
                     scope: request.scope(),
                 }
@@ -382,9 +426,14 @@ pub fn refresh(handler: &mut dyn Endpoint, request: &dyn Request) -> Result<Bear
             Output::RecoverRefresh { token } => Requested::RecoverRefresh {
                 token: token.to_string(),
             },
-            Output::Unauthenticated { client, pass } => Requested::Authenticate {
+            Output::Unauthenticated {
+                client,
+                pass,
+                certificate,
+            } => Requested::Authenticate {
                 client: client.to_string(),
                 pass: pass.map(|p| p.to_vec()),
+                certificate: certificate.cloned(),
             },
         };
     }
@@ -429,6 +478,7 @@ fn authenticated(client: String, token: String) -> RefreshState {
 
 fn recovered_refresh(
     scope: Option<Cow<str>>, authenticated: Option<String>, grant: Option<Box<Grant>>, token: String,
+    certificate: Option<&ClientCertificate>,
 ) -> Result<RefreshState> {
     let grant = grant
         // ... is invalid, ... (Section 5.2)
@@ -443,7 +493,7 @@ fn recovered_refresh(
                 // Unauthorized but with BadRequest.
                 Err(Error::invalid(AccessTokenErrorType::InvalidGrant))
             } else {
-                validate(scope, grant, token)
+                validate(scope, grant, token, certificate)
             }
         }
 
@@ -455,16 +505,27 @@ fn recovered_refresh(
     }
 }
 
-fn co_authenticated(scope: Option<Cow<str>>, grant: Box<Grant>, token: String) -> Result<RefreshState> {
-    validate(scope, grant, token)
+fn co_authenticated(
+    scope: Option<Cow<str>>, grant: Box<Grant>, token: String, certificate: Option<&ClientCertificate>,
+) -> Result<RefreshState> {
+    validate(scope, grant, token, certificate)
 }
 
-fn validate(scope: Option<Cow<str>>, grant: Box<Grant>, token: String) -> Result<RefreshState> {
+fn validate(
+    scope: Option<Cow<str>>, grant: Box<Grant>, token: String, certificate: Option<&ClientCertificate>,
+) -> Result<RefreshState> {
     // .. is expired, revoked, ... (Section 5.2)
     if grant.until <= Utc::now() {
         return Err(Error::invalid(AccessTokenErrorType::InvalidGrant));
     }
 
+    // A grant bound to a certificate must be refreshed over a connection with the same one.
+    if let Some(bound) = mtls::confirmation(&grant) {
+        if certificate.map(ClientCertificate::thumbprint) != Some(bound) {
+            return Err(Error::invalid(AccessTokenErrorType::InvalidGrant));
+        }
+    }
+
     let scope = match scope {
         // ... is invalid, unknown, malformed (Section 5.2)
         Some(scope) => Some(
@@ -496,12 +557,18 @@ fn validate(scope: Option<Cow<str>>, grant: Box<Grant>, token: String) -> Result
     Ok(RefreshState::Issuing { grant, token })
 }
 
-fn issued(grant: Box<Grant>, token: RefreshedToken) -> BearerToken {
+fn issued(grant: Box<Grant>, mut token: RefreshedToken) -> BearerToken {
+    // The refreshed token stays bound to the key of the original grant.
+    if dpop::confirmation(&grant).is_some() {
+        token.token_type = TokenType::DPoP;
+    }
+
     BearerToken(token, grant.scope.to_string())
 }
 
 impl Error {
-    fn invalid(kind: AccessTokenErrorType) -> Self {
+    /// Create an error of the given type for an invalid request.
+    pub fn invalid(kind: AccessTokenErrorType) -> Self {
         Error::Invalid(ErrorDescription {
             error: AccessTokenError::new(kind),
         })
@@ -558,9 +625,10 @@ impl BearerToken {
         let token_response = TokenResponse {
             access_token: Some(self.0.token.clone()),
             refresh_token: self.0.refresh.clone(),
-            token_type: Some("bearer".to_owned()),
+            token_type: Some(self.0.token_type.as_str().to_owned()),
             expires_in: Some(remaining.num_seconds()),
             scope: Some(self.1.clone()),
+            issued_token_type: None,
             error: None,
         };
 
@@ -0,0 +1,592 @@
+//! Provides the handling for Dynamic Client Registration requests
+//!
+//! Clients register themselves by posting their metadata as a json document to the registration
+//! endpoint. They receive a client id, a secret if they are confidential, and a registration
+//! access token with which they can later read, update and delete their registration at the
+//! client configuration endpoint. See [Dynamic Client Registration] and [Dynamic Client
+//! Registration Management] in the rfcs.
+//!
+//! [Dynamic Client Registration]: https://tools.ietf.org/html/rfc7591
+//! [Dynamic Client Registration Management]: https://tools.ietf.org/html/rfc7592
+use std::borrow::Cow;
+
+use base64::{encode_config, URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::code_grant::accesstoken::Error;
+use crate::code_grant::error::AccessTokenErrorType;
+use crate::primitives::registrar::{
+    self, Client, ClientType, EncodedClient, ExactUrl, LoopbackUrl, PrivateUseUrl, RegisteredUrl,
+    RegistrarError, RegistrarMut,
+};
+use crate::primitives::scope::Scope;
+
+/// Trait based retrieval of parameters necessary for client registration request handling.
+pub trait Request {
+    /// Received request might not be encoded correctly. This method gives implementors the chance
+    /// to signal that a request was received but its encoding was generally malformed. If this is
+    /// the case, then no other attribute will be queried. This method exists mainly to make
+    /// frontends straightforward by not having them handle special cases for malformed requests.
+    fn valid(&self) -> bool;
+
+    /// The json encoded client metadata of registration and update requests.
+    fn metadata(&self) -> Option<Cow<'_, str>>;
+
+    /// The registration access token of a bearer authorization header.
+    fn registration_access_token(&self) -> Option<Cow<'_, str>>;
+
+    /// The client_id identifying the registration in the client configuration uri.
+    fn client_id(&self) -> Option<Cow<'_, str>>;
+}
+
+/// Required functionality to respond to client registration requests.
+///
+/// These methods should be implemented by internally using `primitives`, as it is implemented in
+/// the `frontend` module.
+pub trait Endpoint {
+    /// The registrar storing the registered clients.
+    fn registrar(&mut self) -> &mut dyn RegistrarMut;
+
+    /// The scope that registered clients may ask for.
+    ///
+    /// It also becomes the default scope of clients that do not ask for a scope.
+    fn scope(&self) -> &Scope;
+}
+
+/// The client metadata as defined by the rfc.
+///
+/// Unknown fields are ignored. The lists and the authentication method are defaulted as the rfc
+/// prescribes when the client omits them. See [RFC 7591](https://tools.ietf.org/html/rfc7591#section-2).
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ClientMetadata {
+    /// The redirect uris, at least one is required.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redirect_uris: Vec<String>,
+
+    /// The descriptive metadata stored along the client, including its grant types, response
+    /// types and authentication method.
+    #[serde(flatten)]
+    pub details: registrar::ClientMetadata,
+
+    /// The space separated scope the client will request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+
+    /// The subject distinguished name of the certificate of a `tls_client_auth` client.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_client_auth_subject_dn: Option<String>,
+}
+
+/// The information about a registered client, returned by all operations except deletion.
+#[derive(Clone, Debug)]
+pub struct ClientInformation {
+    /// The id of the client.
+    pub client_id: String,
+
+    /// The secret of a newly registered confidential client.
+    ///
+    /// Secrets are only stored in their encoded form, so this is only known at registration.
+    pub client_secret: Option<String>,
+
+    /// The time of registration, only known at registration.
+    pub issued_at: Option<DateTime<Utc>>,
+
+    /// The token with which the client manages its registration.
+    pub registration_access_token: String,
+
+    /// The registered metadata, as stored by the registrar.
+    pub metadata: ClientMetadata,
+}
+
+/// Client information response as defined by the rfc.
+///
+/// See [RFC 7591](https://tools.ietf.org/html/rfc7591#section-3.2.1).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ClientInformationResponse {
+    /// The id of the client.
+    pub client_id: String,
+
+    /// The secret of a newly registered confidential client.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+
+    /// Seconds since the epoch at which the client id was issued.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id_issued_at: Option<i64>,
+
+    /// Seconds since the epoch at which the secret expires, `0` as secrets do not expire.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_secret_expires_at: Option<i64>,
+
+    /// The token with which the client manages its registration.
+    pub registration_access_token: String,
+
+    /// The client configuration uri at which the client manages its registration.
+    pub registration_client_uri: String,
+
+    /// The registered metadata.
+    #[serde(flatten)]
+    pub metadata: ClientMetadata,
+}
+
+/// The body of an update request, which additionally repeats the client credentials.
+#[derive(Deserialize)]
+struct UpdateRequest {
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    #[serde(flatten)]
+    metadata: ClientMetadata,
+}
+
+/// Metadata which was checked for consistency and against the policy of the endpoint.
+struct Validated {
+    redirect_uri: RegisteredUrl,
+    additional_redirect_uris: Vec<RegisteredUrl>,
+    method: AuthMethod,
+    implicit_grant: bool,
+    scope: Scope,
+    details: registrar::ClientMetadata,
+}
+
+enum AuthMethod {
+    None,
+    ClientSecret,
+    TlsClientAuth(String),
+}
+
+impl ClientInformation {
+    /// Convert the information into a json string, viable for being sent over a network with
+    /// `application/json` encoding.
+    ///
+    /// The client configuration uri is the `registration_endpoint` with the `client_id` appended
+    /// to its query.
+    pub fn to_json(&self, registration_endpoint: &Url) -> String {
+        let mut registration_client_uri = registration_endpoint.clone();
+        registration_client_uri
+            .query_pairs_mut()
+            .append_pair("client_id", &self.client_id);
+
+        let response = ClientInformationResponse {
+            client_id: self.client_id.clone(),
+            client_secret: self.client_secret.clone(),
+            client_id_issued_at: self.issued_at.map(|issued| issued.timestamp()),
+            client_secret_expires_at: self.client_secret.as_ref().map(|_| 0),
+            registration_access_token: self.registration_access_token.clone(),
+            registration_client_uri: registration_client_uri.to_string(),
+            metadata: self.metadata.clone(),
+        };
+
+        serde_json::to_string(&response).unwrap()
+    }
+}
+
+impl ClientMetadata {
+    /// Describe the registration of a client as it is enforced by the registrar.
+    pub fn from_client(client: &EncodedClient) -> Self {
+        let redirect_uris = std::iter::once(&client.redirect_uri)
+            .chain(client.additional_redirect_uris.iter())
+            .map(|uri| uri.as_str().to_string())
+            .collect();
+
+        let (method, subject) = match &client.encoded_client {
+            ClientType::Public => ("none", None),
+            ClientType::Confidential { .. } => ("client_secret_basic", None),
+            ClientType::TlsClientAuth { subject } => ("tls_client_auth", Some(subject.clone())),
+            ClientType::SelfSignedTlsClientAuth { .. } => ("self_signed_tls_client_auth", None),
+        };
+
+        // Clients registered without these details are described by what the registrar permits.
+        let mut details = client.metadata.clone();
+        let implicit = details.response_types.is_empty()
+            || details
+                .response_types
+                .iter()
+                .any(|response_type| response_type == "token");
+        if details
+            .grant_types
+            .iter()
+            .all(|grant_type| grant_type == "password")
+        {
+            let mut grant_types = vec!["authorization_code", "refresh_token"];
+            if implicit {
+                grant_types.push("implicit");
+            }
+            if !matches!(client.encoded_client, ClientType::Public) {
+                grant_types.push("client_credentials");
+            }
+            let listed = std::mem::take(&mut details.grant_types);
+            details.grant_types = grant_types
+                .into_iter()
+                .map(str::to_string)
+                .chain(listed)
+                .collect();
+        }
+        if details.response_types.is_empty() {
+            details.response_types = vec!["code".to_string(), "token".to_string()];
+        }
+        if details.token_endpoint_auth_method.is_none() {
+            details.token_endpoint_auth_method = Some(method.to_string());
+        }
+
+        let scope = client.default_scope.to_string();
+        ClientMetadata {
+            redirect_uris,
+            details,
+            scope: if scope.is_empty() { None } else { Some(scope) },
+            tls_client_auth_subject_dn: subject,
+        }
+    }
+
+    fn parse(json: &str) -> Result<Self, Error> {
+        serde_json::from_str(json).map_err(|_| invalid_metadata("The metadata is malformed"))
+    }
+
+    fn validate(mut self, allowed: &Scope) -> Result<Validated, Error> {
+        let mut redirect_uris = Vec::new();
+        for uri in &self.redirect_uris {
+            redirect_uris.push(validate_redirect_uri(uri)?);
+        }
+
+        if redirect_uris.is_empty() {
+            return Err(invalid_redirect_uri("At least one redirect uri is required"));
+        }
+
+        let details = &self.details;
+        let uris = [&details.logo_uri, &details.policy_uri, &details.tos_uri];
+        if uris
+            .iter()
+            .filter_map(|uri| uri.as_ref())
+            .any(|uri| uri.parse::<Url>().is_err())
+        {
+            return Err(invalid_metadata("An uri of the client metadata is malformed"));
+        }
+
+        if self.details.grant_types.is_empty() {
+            // Refresh tokens are issued along with the access tokens of the authorization code.
+            self.details.grant_types.push("authorization_code".to_string());
+            self.details.grant_types.push("refresh_token".to_string());
+        }
+
+        if let Some(unsupported) = self.details.grant_types.iter().find(|grant_type| {
+            !matches!(
+                grant_type.as_str(),
+                "authorization_code" | "implicit" | "refresh_token" | "client_credentials"
+            )
+        }) {
+            let description = match unsupported.as_str() {
+                "password" => "The password grant can not be registered dynamically",
+                _ => "A grant type is not supported",
+            };
+            return Err(invalid_metadata(description));
+        }
+
+        let has_grant =
+            |grant_type: &str| self.details.grant_types.iter().any(|known| known == grant_type);
+        let code = has_grant("authorization_code");
+        let implicit = has_grant("implicit");
+        let client_credentials = has_grant("client_credentials");
+
+        if self.details.response_types.is_empty() {
+            if code {
+                self.details.response_types.push("code".to_string());
+            }
+            if implicit {
+                self.details.response_types.push("token".to_string());
+            }
+        }
+
+        let has_response = |response_type: &str| {
+            self.details
+                .response_types
+                .iter()
+                .any(|known| known == response_type)
+        };
+        let consistent = self
+            .details
+            .response_types
+            .iter()
+            .all(|response_type| matches!(response_type.as_str(), "code" | "token"))
+            && has_response("code") == code
+            && has_response("token") == implicit;
+
+        if !consistent {
+            return Err(invalid_metadata(
+                "The response types do not match the grant types",
+            ));
+        }
+
+        let method = match self
+            .details
+            .token_endpoint_auth_method
+            .get_or_insert_with(|| "client_secret_basic".to_string())
+            .as_str()
+        {
+            "none" => AuthMethod::None,
+            "client_secret_basic" | "client_secret_post" => AuthMethod::ClientSecret,
+            "tls_client_auth" => match &self.tls_client_auth_subject_dn {
+                Some(subject) => AuthMethod::TlsClientAuth(subject.clone()),
+                None => {
+                    return Err(invalid_metadata(
+                        "The tls_client_auth method requires tls_client_auth_subject_dn",
+                    ))
+                }
+            },
+            _ => return Err(invalid_metadata("The authentication method is not supported")),
+        };
+
+        if matches!(method, AuthMethod::None) && client_credentials {
+            return Err(invalid_metadata(
+                "The client credentials grant requires an authenticated client",
+            ));
+        }
+
+        let scope = match &self.scope {
+            None => allowed.clone(),
+            Some(scope) => scope
+                .parse::<Scope>()
+                .map_err(|_| invalid_metadata("The scope is malformed"))?,
+        };
+
+        if !allowed.priviledged_to(&scope) {
+            return Err(invalid_metadata("The scope exceeds the permitted scope"));
+        }
+
+        let mut redirect_uris = redirect_uris.into_iter();
+        Ok(Validated {
+            redirect_uri: redirect_uris.next().unwrap(),
+            additional_redirect_uris: redirect_uris.collect(),
+            method,
+            implicit_grant: implicit,
+            scope,
+            details: self.details,
+        })
+    }
+}
+
+impl Validated {
+    fn into_client(self, client_id: &str, client_secret: Option<&str>, token: &str) -> Client {
+        let client = match (self.method, client_secret) {
+            (AuthMethod::ClientSecret, Some(secret)) => {
+                Client::confidential(client_id, self.redirect_uri, self.scope, secret.as_bytes())
+            }
+            (AuthMethod::TlsClientAuth(subject), _) => {
+                Client::tls_client_auth(client_id, self.redirect_uri, self.scope, &subject)
+            }
+            _ => Client::public(client_id, self.redirect_uri, self.scope),
+        };
+
+        let client = client
+            .with_additional_redirect_uris(self.additional_redirect_uris)
+            .with_registration_access_token(token.as_bytes())
+            .with_metadata(self.details);
+
+        match self.implicit_grant {
+            true => client,
+            false => client.without_implicit_grant(),
+        }
+    }
+
+    /// Apply the metadata to an existing registration, keeping its credentials.
+    fn apply(self, mut client: EncodedClient) -> Result<EncodedClient, Error> {
+        client.encoded_client = match (self.method, client.encoded_client) {
+            (AuthMethod::None, ClientType::Public) => ClientType::Public,
+            (AuthMethod::ClientSecret, confidential @ ClientType::Confidential { .. }) => confidential,
+            (AuthMethod::TlsClientAuth(subject), ClientType::TlsClientAuth { .. }) => {
+                ClientType::TlsClientAuth { subject }
+            }
+            _ => return Err(invalid_metadata("The authentication method can not be changed")),
+        };
+
+        client.redirect_uri = self.redirect_uri;
+        client.additional_redirect_uris = self.additional_redirect_uris;
+        client.default_scope = self.scope;
+        client.metadata = self.details;
+        if !self.implicit_grant {
+            client.metadata.remove_implicit_grant();
+        }
+        Ok(client)
+    }
+}
+
+/// Register a new client with the metadata of the request.
+pub fn register(handler: &mut dyn Endpoint, request: &dyn Request) -> Result<ClientInformation, Error> {
+    if !request.valid() {
+        return Err(Error::invalid());
+    }
+
+    let metadata = request.metadata().ok_or_else(Error::invalid)?;
+    let validated = ClientMetadata::parse(&metadata)?.validate(handler.scope())?;
+
+    let client_id = random_string(16);
+    let client_secret = match validated.method {
+        AuthMethod::ClientSecret => Some(random_string(32)),
+        _ => None,
+    };
+    let token = random_string(32);
+
+    let client = validated.into_client(&client_id, client_secret.as_deref(), &token);
+    let registrar = handler.registrar();
+    registrar.register(client).map_err(|_| Error::primitive(None))?;
+    let registered = registrar
+        .client(&client_id)
+        .map_err(|_| Error::primitive(None))?
+        .ok_or_else(|| Error::primitive(None))?;
+
+    Ok(ClientInformation {
+        client_id,
+        client_secret,
+        issued_at: Some(Utc::now()),
+        registration_access_token: token,
+        metadata: ClientMetadata::from_client(&registered),
+    })
+}
+
+/// Read the registration of the client authenticated by the registration access token.
+pub fn read(handler: &mut dyn Endpoint, request: &dyn Request) -> Result<ClientInformation, Error> {
+    let (client, token) = authenticate(handler, request)?;
+
+    Ok(ClientInformation {
+        client_id: client.client_id.clone(),
+        client_secret: None,
+        issued_at: None,
+        registration_access_token: token,
+        metadata: ClientMetadata::from_client(&client),
+    })
+}
+
+/// Replace the metadata of the client authenticated by the registration access token.
+///
+/// The request repeats the `client_id` and may repeat the `client_secret`, both must match the
+/// registration. The authentication method of a client can not be changed.
+pub fn update(handler: &mut dyn Endpoint, request: &dyn Request) -> Result<ClientInformation, Error> {
+    let (client, token) = authenticate(handler, request)?;
+
+    let metadata = request.metadata().ok_or_else(Error::invalid)?;
+    let update: UpdateRequest =
+        serde_json::from_str(&metadata).map_err(|_| invalid_metadata("The metadata is malformed"))?;
+
+    if update.client_id.as_deref() != Some(client.client_id.as_str()) {
+        return Err(Error::invalid());
+    }
+
+    if let Some(secret) = &update.client_secret {
+        handler
+            .registrar()
+            .check(&client.client_id, Some(secret.as_bytes()))
+            .map_err(|err| match err {
+                RegistrarError::Unspecified => Error::invalid(),
+                RegistrarError::PrimitiveError => Error::primitive(None),
+            })?;
+    }
+
+    let updated = update.metadata.validate(handler.scope())?.apply(client)?;
+    let metadata = ClientMetadata::from_client(&updated);
+    let client_id = updated.client_id.clone();
+    handler
+        .registrar()
+        .update(updated)
+        .map_err(|_| Error::primitive(None))?;
+
+    Ok(ClientInformation {
+        client_id,
+        client_secret: None,
+        issued_at: None,
+        registration_access_token: token,
+        metadata,
+    })
+}
+
+/// Delete the registration of the client authenticated by the registration access token.
+pub fn delete(handler: &mut dyn Endpoint, request: &dyn Request) -> Result<(), Error> {
+    let (client, _) = authenticate(handler, request)?;
+
+    handler
+        .registrar()
+        .unregister(&client.client_id)
+        .map_err(|_| Error::primitive(None))
+}
+
+/// Find the client of the configuration uri and check its registration access token.
+fn authenticate(
+    handler: &mut dyn Endpoint, request: &dyn Request,
+) -> Result<(EncodedClient, String), Error> {
+    if !request.valid() {
+        return Err(Error::invalid());
+    }
+
+    let client_id = request.client_id().ok_or_else(Error::invalid)?;
+    let token = request.registration_access_token().ok_or_else(invalid_token)?;
+
+    let registrar = handler.registrar();
+    registrar
+        .check_registration(&client_id, token.as_bytes())
+        .map_err(|err| match err {
+            RegistrarError::Unspecified => invalid_token(),
+            RegistrarError::PrimitiveError => Error::primitive(None),
+        })?;
+
+    // The client may have been removed concurrently, answer as for any unknown client.
+    let client = registrar
+        .client(&client_id)
+        .map_err(|_| Error::primitive(None))?
+        .ok_or_else(invalid_token)?;
+
+    Ok((client, token.into_owned()))
+}
+
+fn validate_redirect_uri(uri: &str) -> Result<RegisteredUrl, Error> {
+    let url: Url = uri
+        .parse()
+        .map_err(|_| invalid_redirect_uri("A redirect uri is malformed"))?;
+
+    if url.fragment().is_some() {
+        return Err(invalid_redirect_uri("A redirect uri must not contain a fragment"));
+    }
+
+    // Native clients may redirect to a loopback address or a private-use scheme, see RFC 8252.
+    match url.scheme() {
+        "https" => ExactUrl::new(uri.to_string())
+            .map(RegisteredUrl::from)
+            .map_err(|_| invalid_redirect_uri("A redirect uri is malformed")),
+        "http" => LoopbackUrl::new(url)
+            .map(RegisteredUrl::from)
+            .map_err(|_| invalid_redirect_uri("A redirect uri must use https or a loopback address")),
+        scheme if scheme.contains('.') => PrivateUseUrl::new(url)
+            .map(RegisteredUrl::from)
+            .map_err(|_| invalid_redirect_uri("A private-use redirect uri is malformed")),
+        _ => Err(invalid_redirect_uri("A redirect uri must use https")),
+    }
+}
+
+fn random_string(len: usize) -> String {
+    let mut bytes = vec![0; len];
+    OsRng
+        .try_fill_bytes(bytes.as_mut_slice())
+        .expect("Failed to generate client credentials");
+    encode_config(&bytes, URL_SAFE_NO_PAD)
+}
+
+fn invalid_metadata(description: &'static str) -> Error {
+    let mut error = Error::invalid_with(AccessTokenErrorType::InvalidClientMetadata);
+    error.description().unwrap().explain(description);
+    error
+}
+
+fn invalid_redirect_uri(description: &'static str) -> Error {
+    let mut error = Error::invalid_with(AccessTokenErrorType::InvalidRedirectUri);
+    error.description().unwrap().explain(description);
+    error
+}
+
+fn invalid_token() -> Error {
+    let mut error = Error::unauthorized("Bearer error=\"invalid_token\"");
+    error
+        .description()
+        .unwrap()
+        .set_type(AccessTokenErrorType::InvalidToken);
+    error
+}
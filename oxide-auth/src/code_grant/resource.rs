@@ -4,8 +4,10 @@ use std::borrow::Cow;
 
 use chrono::Utc;
 
+use crate::primitives::dpop;
 use crate::primitives::issuer::Issuer;
 use crate::primitives::grant::Grant;
+use crate::primitives::mtls;
 use crate::primitives::scope::Scope;
 
 /// Gives additional information about the reason for an access failure.
@@ -72,6 +74,7 @@ pub enum Error {
 }
 
 const BEARER_START: &str = "Bearer ";
+const DPOP_START: &str = "DPoP ";
 
 type Result<T> = std::result::Result<T, Error>;
 
@@ -85,10 +88,27 @@ pub trait Request {
 
     /// The authorization used in the request.
     ///
-    /// Expects the complete `Authorization` HTTP-header, including the qualification as `Bearer`.
-    /// In case the client included multiple forms of authorization, this method MUST return None
-    /// and the request SHOULD be marked as invalid.
+    /// Expects the complete `Authorization` HTTP-header, including the qualification as `Bearer`
+    /// or `DPoP`. In case the client included multiple forms of authorization, this method MUST
+    /// return None and the request SHOULD be marked as invalid.
     fn token(&self) -> Option<Cow<str>>;
+
+    /// The thumbprint of the key proven by a valid DPoP proof of the request.
+    ///
+    /// Only consulted for tokens presented with the `DPoP` scheme, the proof must have been
+    /// created for the presented token. The default implementation does not verify proofs so that
+    /// such tokens are always rejected.
+    fn dpop_thumbprint(&self) -> Option<Cow<'_, str>> {
+        None
+    }
+
+    /// The thumbprint of the verified client certificate of a mutual-TLS connection.
+    ///
+    /// Tokens bound to a certificate are only accepted with the same certificate. The default
+    /// implementation provides no certificate so that such tokens are always rejected.
+    fn certificate_thumbprint(&self) -> Option<Cow<'_, str>> {
+        None
+    }
 }
 
 /// Required functionality to respond to resource requests.
@@ -113,13 +133,35 @@ enum ResourceState {
     /// The initial state.
     New,
     /// State after request has been validated.
-    Internalized { token: String },
+    Internalized {
+        token: String,
+        presentation: Presentation,
+    },
     /// State after scopes have been determined.
-    Recovering { token: String, scopes: Vec<Scope> },
+    Recovering {
+        token: String,
+        scopes: Vec<Scope>,
+        presentation: Presentation,
+    },
     /// State after an error occurred.
     Err(Error),
 }
 
+/// How the client presented its token.
+struct Presentation {
+    scheme: Scheme,
+    /// The thumbprint of the client certificate of the connection.
+    certificate: Option<String>,
+}
+
+/// The authorization scheme of the token.
+enum Scheme {
+    /// As a bearer token, usable by anyone holding it.
+    Bearer,
+    /// Together with a DPoP proof, with the thumbprint of the proven key if the proof was valid.
+    Dpop(Option<String>),
+}
+
 /// An input injected by the executor into the state machine.
 #[derive(Clone)]
 pub enum Input<'req> {
@@ -188,13 +230,18 @@ impl Resource {
             (ResourceState::New, Input::Request { request }) => {
                 validate(request).unwrap_or_else(ResourceState::Err)
             }
-            (ResourceState::Internalized { token }, Input::Scopes(scopes)) => get_scopes(token, scopes),
-            (ResourceState::Recovering { token: _, scopes }, Input::Recovered(grant)) => {
-                match recovered(grant, scopes) {
-                    Ok(grant) => return Output::Ok(Box::new(grant)),
-                    Err(err) => ResourceState::Err(err),
-                }
+            (ResourceState::Internalized { token, presentation }, Input::Scopes(scopes)) => {
+                get_scopes(token, scopes, presentation)
             }
+            (
+                ResourceState::Recovering {
+                    scopes, presentation, ..
+                },
+                Input::Recovered(grant),
+            ) => match recovered(grant, scopes, presentation) {
+                Ok(grant) => return Output::Ok(Box::new(grant)),
+                Err(err) => ResourceState::Err(err),
+            },
             _ => return Output::Err(Error::PrimitiveError),
         };
 
@@ -266,28 +313,39 @@ fn validate(request: &'_ dyn Request) -> Result<ResourceState> {
         }
     };
 
-    if !client_token.starts_with(BEARER_START) {
+    let (scheme, start) = if client_token.starts_with(BEARER_START) {
+        (Scheme::Bearer, BEARER_START.len())
+    } else if client_token.starts_with(DPOP_START) {
+        let thumbprint = request.dpop_thumbprint().map(Cow::into_owned);
+        (Scheme::Dpop(thumbprint), DPOP_START.len())
+    } else {
         return Err(Error::InvalidRequest {
             authenticate: Authenticate::empty(),
         });
-    }
+    };
 
     let token = match client_token {
-        Cow::Borrowed(token) => token[BEARER_START.len()..].to_string(),
-        Cow::Owned(mut token) => token.split_off(BEARER_START.len()),
+        Cow::Borrowed(token) => token[start..].to_string(),
+        Cow::Owned(mut token) => token.split_off(start),
+    };
+
+    let presentation = Presentation {
+        scheme,
+        certificate: request.certificate_thumbprint().map(Cow::into_owned),
     };
 
-    Ok(ResourceState::Internalized { token })
+    Ok(ResourceState::Internalized { token, presentation })
 }
 
-fn get_scopes(token: String, scopes: &'_ [Scope]) -> ResourceState {
+fn get_scopes(token: String, scopes: &'_ [Scope], presentation: Presentation) -> ResourceState {
     ResourceState::Recovering {
         token,
         scopes: scopes.to_owned(),
+        presentation,
     }
 }
 
-fn recovered(grant: Option<Grant>, mut scopes: Vec<Scope>) -> Result<Grant> {
+fn recovered(grant: Option<Grant>, mut scopes: Vec<Scope>, presentation: Presentation) -> Result<Grant> {
     debug!("grant={:?} scopes={:?} ", grant, scopes);
     let grant = match grant {
         Some(grant) => grant,
@@ -314,9 +372,31 @@ fn recovered(grant: Option<Grant>, mut scopes: Vec<Scope>) -> Result<Grant> {
         });
     }
 
+    // A bound token requires a proof with its key, an unbound one must not claim to be bound.
+    let proven = match (dpop::confirmation(&grant), &presentation.scheme) {
+        (None, Scheme::Bearer) => true,
+        (Some(bound), Scheme::Dpop(Some(proven))) => bound == proven,
+        _ => false,
+    };
+
+    // A token bound to a certificate is only accepted over a connection with that certificate.
+    let connected = match mtls::confirmation(&grant) {
+        None => true,
+        Some(bound) => presentation.certificate.as_deref() == Some(bound),
+    };
+
+    if !proven || !connected {
+        return Err(Error::AccessDenied {
+            failure: AccessFailure {
+                code: Some(ErrorCode::InvalidToken),
+            },
+            authenticate: Authenticate::empty(),
+        });
+    }
+
     let allowing = scopes
         .iter()
-        .find(|resource_scope| grant.scope.allow_access(&resource_scope));
+        .find(|resource_scope| resource_scope.allow_access(&grant.scope));
 
     if allowing.is_none() {
         return Err(Error::AccessDenied {
@@ -0,0 +1,386 @@
+//! Provides the handling for Token Revocation requests
+//!
+//! A client notifies the authorization server that an access or refresh token it obtained is no
+//! longer needed, for example when the user logs out. The token, and the other tokens of the same
+//! grant, are invalidated. Invalid tokens do not cause an error response since the client could
+//! not do anything about it. See [Token Revocation] in the rfc.
+//!
+//! [Token Revocation]: https://tools.ietf.org/html/rfc7009
+use std::mem;
+use std::borrow::Cow;
+
+use crate::code_grant::accesstoken::{Credentials, Error};
+use crate::code_grant::error::AccessTokenErrorType;
+use crate::primitives::issuer::Issuer;
+use crate::primitives::grant::Grant;
+use crate::primitives::registrar::{Registrar, RegistrarError};
+
+/// Trait based retrieval of parameters necessary for revocation request handling.
+pub trait Request {
+    /// Received request might not be encoded correctly. This method gives implementors the chance
+    /// to signal that a request was received but its encoding was generally malformed. If this is
+    /// the case, then no other attribute will be queried. This method exists mainly to make
+    /// frontends straightforward by not having them handle special cases for malformed requests.
+    fn valid(&self) -> bool;
+
+    /// User:password of a basic authorization header.
+    fn authorization(&self) -> Option<(Cow<'_, str>, Cow<'_, [u8]>)>;
+
+    /// The client_id, optional parameter for public clients.
+    fn client_id(&self) -> Option<Cow<'_, str>>;
+
+    /// The token that the client wants to get revoked.
+    fn token(&self) -> Option<Cow<'_, str>>;
+
+    /// Optionally, a hint about the type of the token.
+    fn token_type_hint(&self) -> Option<Cow<'_, str>>;
+
+    /// Retrieve an additional parameter used in an extension
+    fn extension(&self, key: &str) -> Option<Cow<'_, str>>;
+
+    /// Credentials in body should only be enabled if use of HTTP Basic is not possible.
+    ///
+    /// Allows the request body to contain the `client_secret` as a form parameter. This is NOT
+    /// RECOMMENDED and need not be supported. The parameters MUST NOT appear in the request URI
+    /// itself.
+    ///
+    /// Under these considerations, support must be explicitely enabled.
+    fn allow_credentials_in_body(&self) -> bool {
+        false
+    }
+}
+
+/// Required functionality to respond to revocation requests.
+///
+/// Each method will only be invoked exactly once when processing a correct and authorized request,
+/// and potentially less than once when the request is faulty.  These methods should be implemented
+/// by internally using `primitives`, as it is implemented in the `frontend` module.
+pub trait Endpoint {
+    /// Authenticate the client.
+    fn registrar(&self) -> &dyn Registrar;
+
+    /// Return the issuer instance to recover and revoke the token.
+    fn issuer(&mut self) -> &mut dyn Issuer;
+}
+
+/// The kinds of tokens that can be revoked or introspected.
+///
+/// A client may hint at the kind of the token with the `token_type_hint` parameter. This only
+/// decides the kind that is looked up first, the other kind is searched as well.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenTypeHint {
+    /// An access token, hinted with `access_token`.
+    AccessToken,
+
+    /// A refresh token, hinted with `refresh_token`.
+    RefreshToken,
+}
+
+/// Token revocation process
+///
+/// This state machine will go through four phases. On creation, the request will be validated and
+/// parameters for the first step will be extracted from it. It will pose some requests in the form
+/// of [`Output`] which should be satisfied with the next [`Input`] data. This will eventually
+/// finish with `Output::Ok` or an [`Error`]. Note that the executing environment will need to use
+/// a [`Registrar`] and an [`Issuer`] to which some requests should be forwarded.
+///
+/// [`Input`]: struct.Input.html
+/// [`Output`]: struct.Output.html
+/// [`Error`]: ../accesstoken/struct.Error.html
+/// [`Issuer`] ../primitives/issuer/trait.Issuer.html
+/// [`Registrar`] ../primitives/registrar/trait.Registrar.html
+///
+/// A rough sketch of the operational phases:
+///
+/// 1. Ensure the request is valid based on the basic requirements (includes required parameters)
+/// 2. Authenticate the client
+/// 3. Recover the grant of the token, trying the hinted kind of token first
+/// 4. Check that the token was issued to the client and revoke it
+pub struct Revoke {
+    state: RevokeState,
+}
+
+/// Inner state machine for the token revocation
+enum RevokeState {
+    /// State after the request has been validated.
+    Authenticate {
+        client: String,
+        passdata: Option<Vec<u8>>,
+        token: String,
+        hint: TokenTypeHint,
+    },
+    /// Recover the token as the `current` kind, then as the `remaining` kind if any.
+    Recover {
+        client: String,
+        token: String,
+        current: TokenTypeHint,
+        remaining: Option<TokenTypeHint>,
+    },
+    Revoke {
+        token: String,
+        kind: TokenTypeHint,
+    },
+    Revoked,
+    Err(Error),
+}
+
+/// Input injected by the executor into the state machine.
+pub enum Input {
+    /// Positively answer an authentication query.
+    Authenticated,
+    /// The grant recovered from the token.
+    ///
+    /// Is `None` if the issuer does not know the token as the requested kind.
+    Recovered(Option<Grant>),
+    /// The issuer invalidated the token.
+    Revoked,
+    /// Advance without input as far as possible, or just retrieve the output again.
+    None,
+}
+
+/// A request by the statemachine to the executor.
+///
+/// Each variant is fulfilled by certain variants of the next inputs as an argument to
+/// `Revoke::advance`. The output of most states is simply repeated if `Input::None` is provided
+/// instead.
+pub enum Output<'machine> {
+    /// The registrar should authenticate a client.
+    ///
+    /// Fulfilled by `Input::Authenticated`. In an unsuccessful case, the executor should not
+    /// continue and discard the flow.
+    Authenticate {
+        /// The to-be-authenticated client.
+        client: &'machine str,
+        /// The supplied passdata/password.
+        passdata: Option<&'machine [u8]>,
+    },
+    /// The issuer should recover the grant of the token as the given kind.
+    ///
+    /// Fulfilled by `Input::Recovered`.
+    Recover {
+        /// The token supplied in the request.
+        token: &'machine str,
+        /// The kind of token to look up.
+        kind: TokenTypeHint,
+    },
+    /// The issuer should revoke the token as the given kind.
+    ///
+    /// Fulfilled by `Input::Revoked`. If the issuer does not support revocation then the executor
+    /// should instead answer with an `unsupported_token_type` error.
+    Revoke {
+        /// The token supplied in the request.
+        token: &'machine str,
+        /// The kind of the token.
+        kind: TokenTypeHint,
+    },
+    /// The state machine finished, the token is no longer valid.
+    Ok,
+    /// The state machine finished in an error.
+    ///
+    /// The error will be repeated on *any* following input.
+    Err(Box<Error>),
+}
+
+impl TokenTypeHint {
+    /// Parse the value of the `token_type_hint` parameter.
+    ///
+    /// Returns `None` for unknown values, which should be ignored by the server.
+    pub fn from_hint(hint: &str) -> Option<Self> {
+        match hint {
+            "access_token" => Some(TokenTypeHint::AccessToken),
+            "refresh_token" => Some(TokenTypeHint::RefreshToken),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn other(self) -> Self {
+        match self {
+            TokenTypeHint::AccessToken => TokenTypeHint::RefreshToken,
+            TokenTypeHint::RefreshToken => TokenTypeHint::AccessToken,
+        }
+    }
+}
+
+impl Revoke {
+    /// Create the state machine. validating the request in the process
+    pub fn new(request: &dyn Request) -> Self {
+        Revoke {
+            state: Self::validate(request).unwrap_or_else(RevokeState::Err),
+        }
+    }
+
+    /// Go to next state
+    pub fn advance(&mut self, input: Input) -> Output<'_> {
+        self.state = match (self.take(), input) {
+            (current, Input::None) => current,
+            (RevokeState::Authenticate { client, token, hint, .. }, Input::Authenticated) => {
+                RevokeState::Recover {
+                    client,
+                    token,
+                    current: hint,
+                    remaining: Some(hint.other()),
+                }
+            }
+            (
+                RevokeState::Recover {
+                    client,
+                    token,
+                    current,
+                    remaining,
+                },
+                Input::Recovered(grant),
+            ) => Self::recovered(client, token, current, remaining, grant)
+                .unwrap_or_else(RevokeState::Err),
+            (RevokeState::Revoke { .. }, Input::Revoked) => RevokeState::Revoked,
+            (RevokeState::Err(err), _) => RevokeState::Err(err),
+            (_, _) => RevokeState::Err(Error::primitive(None)),
+        };
+
+        self.output()
+    }
+
+    fn output(&self) -> Output<'_> {
+        match &self.state {
+            RevokeState::Err(err) => Output::Err(Box::new(err.clone())),
+            RevokeState::Authenticate { client, passdata, .. } => Output::Authenticate {
+                client,
+                passdata: passdata.as_deref(),
+            },
+            RevokeState::Recover { token, current, .. } => Output::Recover {
+                token,
+                kind: *current,
+            },
+            RevokeState::Revoke { token, kind } => Output::Revoke { token, kind: *kind },
+            RevokeState::Revoked => Output::Ok,
+        }
+    }
+
+    fn take(&mut self) -> RevokeState {
+        mem::replace(&mut self.state, RevokeState::Err(Error::primitive(None)))
+    }
+
+    fn validate(request: &dyn Request) -> Result<RevokeState, Error> {
+        if !request.valid() {
+            return Err(Error::invalid());
+        }
+
+        let authorization = request.authorization();
+        let client_id = request.client_id();
+        let client_secret = request.extension("client_secret");
+
+        let mut credentials = Credentials::None;
+        if let Some((client_id, auth)) = &authorization {
+            credentials.authenticate(client_id.as_ref(), auth.as_ref());
+        }
+
+        if let Some(client_id) = &client_id {
+            match &client_secret {
+                Some(auth) if request.allow_credentials_in_body() => {
+                    credentials.authenticate(client_id.as_ref(), auth.as_ref().as_bytes())
+                }
+                // Ignore parameter if not allowed.
+                Some(_) | None => credentials.unauthenticated(client_id.as_ref()),
+            }
+        }
+
+        let (client_id, passdata) = credentials.into_client().ok_or_else(Error::invalid)?;
+        let token = request.token().ok_or_else(Error::invalid)?;
+
+        // Unknown hints are ignored, all kinds of tokens are searched anyways.
+        let hint = request
+            .token_type_hint()
+            .and_then(|hint| TokenTypeHint::from_hint(&hint))
+            .unwrap_or(TokenTypeHint::AccessToken);
+
+        Ok(RevokeState::Authenticate {
+            client: client_id.to_string(),
+            passdata: passdata.map(Vec::from),
+            token: token.into_owned(),
+            hint,
+        })
+    }
+
+    fn recovered(
+        client: String, token: String, current: TokenTypeHint, remaining: Option<TokenTypeHint>,
+        grant: Option<Grant>,
+    ) -> Result<RevokeState, Error> {
+        match (grant, remaining) {
+            (Some(grant), _) if grant.client_id != client => {
+                Err(Error::invalid_with(AccessTokenErrorType::UnauthorizedClient))
+            }
+            (Some(_), _) => Ok(RevokeState::Revoke { token, kind: current }),
+            (None, Some(next)) => Ok(RevokeState::Recover {
+                client,
+                token,
+                current: next,
+                remaining: None,
+            }),
+            // An invalid token is not an error, there is nothing left to revoke.
+            (None, None) => Ok(RevokeState::Revoked),
+        }
+    }
+}
+
+/// Revoke the token of a request.
+pub fn revoke(handler: &mut dyn Endpoint, request: &dyn Request) -> Result<(), Error> {
+    enum Requested {
+        None,
+        Authenticate { client: String, passdata: Option<Vec<u8>> },
+        Recover { token: String, kind: TokenTypeHint },
+        Revoke { token: String, kind: TokenTypeHint },
+    }
+
+    let mut revoke = Revoke::new(request);
+    let mut requested = Requested::None;
+
+    loop {
+        let input = match requested {
+            Requested::None => Input::None,
+            Requested::Authenticate { client, passdata } => {
+                handler
+                    .registrar()
+                    .check(&client, passdata.as_deref())
+                    .map_err(|err| match err {
+                        RegistrarError::Unspecified => Error::unauthorized("basic"),
+                        RegistrarError::PrimitiveError => Error::primitive(None),
+                    })?;
+                Input::Authenticated
+            }
+            Requested::Recover { token, kind } => {
+                let issuer = handler.issuer();
+                let grant = match kind {
+                    TokenTypeHint::AccessToken => issuer.recover_token(&token),
+                    TokenTypeHint::RefreshToken => issuer.recover_refresh(&token),
+                }
+                .map_err(|()| Error::primitive(None))?;
+                Input::Recovered(grant)
+            }
+            Requested::Revoke { token, kind } => {
+                let issuer = handler.issuer();
+                match kind {
+                    TokenTypeHint::AccessToken => issuer.revoke_token(&token),
+                    TokenTypeHint::RefreshToken => issuer.revoke_refresh(&token),
+                }
+                .map_err(|()| Error::invalid_with(AccessTokenErrorType::UnsupportedTokenType))?;
+                Input::Revoked
+            }
+        };
+
+        requested = match revoke.advance(input) {
+            Output::Authenticate { client, passdata } => Requested::Authenticate {
+                client: client.to_string(),
+                passdata: passdata.map(Vec::from),
+            },
+            Output::Recover { token, kind } => Requested::Recover {
+                token: token.to_string(),
+                kind,
+            },
+            Output::Revoke { token, kind } => Requested::Revoke {
+                token: token.to_string(),
+                kind,
+            },
+            Output::Ok => return Ok(()),
+            Output::Err(err) => return Err(*err),
+        };
+    }
+}
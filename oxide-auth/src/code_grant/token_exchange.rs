@@ -0,0 +1,586 @@
+//! Provides the handling for Token Exchange requests
+//!
+//! A client, usually a gateway or another intermediary service, presents an access token of a
+//! user, the subject token, and receives a new access token for the same user. The new token can
+//! not carry more scope than the subject token. If the client also presents a token of its own,
+//! the actor token, the exchange is recorded as delegation in the extensions of the new grant. A
+//! subject token bound to a DPoP key or a client certificate passes its binding on to the new token.
+//! See [Token Exchange] in the rfc.
+//!
+//! [Token Exchange]: https://tools.ietf.org/html/rfc8693
+use std::mem;
+use std::borrow::Cow;
+
+use chrono::{Duration, Utc};
+use serde_json;
+
+use crate::code_grant::accesstoken::{BearerToken, Credentials, Error, TokenResponse};
+use crate::code_grant::error::AccessTokenErrorType;
+use crate::primitives::dpop;
+use crate::primitives::issuer::{IssuedToken, Issuer, TokenType};
+use crate::primitives::grant::{Extensions, Grant, Value};
+use crate::primitives::mtls;
+use crate::primitives::registrar::{BoundClient, ClientUrl, Registrar, RegistrarError};
+use crate::primitives::scope::Scope;
+
+/// The `grant_type` with which clients request a token exchange.
+pub const TOKEN_EXCHANGE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:token-exchange";
+
+/// The token type identifier of access tokens.
+///
+/// This is the only type accepted for subject and actor tokens and the type of all issued tokens.
+pub const ACCESS_TOKEN_TYPE: &str = "urn:ietf:params:oauth:token-type:access_token";
+
+/// The identifier of the public grant extension recording the delegation chain.
+///
+/// Its value is a json array of the owners of all actor tokens, the most recent actor first. This
+/// corresponds to the nesting of the `act` claim in the rfc.
+pub const ACTOR_EXTENSION: &str = "act";
+
+/// Trait based retrieval of parameters necessary for token exchange request handling.
+pub trait Request {
+    /// Received request might not be encoded correctly. This method gives implementors the chance
+    /// to signal that a request was received but its encoding was generally malformed. If this is
+    /// the case, then no other attribute will be queried. This method exists mainly to make
+    /// frontends straightforward by not having them handle special cases for malformed requests.
+    fn valid(&self) -> bool;
+
+    /// User:password of a basic authorization header.
+    fn authorization(&self) -> Option<(Cow<'_, str>, Cow<'_, [u8]>)>;
+
+    /// The client_id, optional parameter for public clients.
+    fn client_id(&self) -> Option<Cow<'_, str>>;
+
+    /// The token representing the party on whose behalf the new token is requested.
+    fn subject_token(&self) -> Option<Cow<'_, str>>;
+
+    /// The type identifier of the subject token.
+    fn subject_token_type(&self) -> Option<Cow<'_, str>>;
+
+    /// Optionally, a token representing the acting party.
+    fn actor_token(&self) -> Option<Cow<'_, str>>;
+
+    /// The type identifier of the actor token, required if an actor token is present.
+    fn actor_token_type(&self) -> Option<Cow<'_, str>>;
+
+    /// Optionally, the type of token that is requested.
+    fn requested_token_type(&self) -> Option<Cow<'_, str>>;
+
+    /// Optionally specifies the requested scope
+    fn scope(&self) -> Option<Cow<'_, str>>;
+
+    /// Valid requests have this set to "urn:ietf:params:oauth:grant-type:token-exchange"
+    fn grant_type(&self) -> Option<Cow<'_, str>>;
+
+    /// Retrieve an additional parameter used in an extension
+    fn extension(&self, key: &str) -> Option<Cow<'_, str>>;
+
+    /// Credentials in body should only be enabled if use of HTTP Basic is not possible.
+    ///
+    /// Allows the request body to contain the `client_secret` as a form parameter. This is NOT
+    /// RECOMMENDED and need not be supported. The parameters MUST NOT appear in the request URI
+    /// itself.
+    ///
+    /// Under these considerations, support must be explicitely enabled.
+    fn allow_credentials_in_body(&self) -> bool {
+        false
+    }
+}
+
+/// Required functionality to respond to token exchange requests.
+///
+/// Each method will only be invoked exactly once when processing a correct and authorized request,
+/// and potentially less than once when the request is faulty.  These methods should be implemented
+/// by internally using `primitives`, as it is implemented in the `frontend` module.
+pub trait Endpoint {
+    /// Authenticate and bind the client.
+    fn registrar(&self) -> &dyn Registrar;
+
+    /// Return the issuer instance to recover the presented tokens and create the new one.
+    fn issuer(&mut self) -> &mut dyn Issuer;
+}
+
+/// Token exchange process
+///
+/// This state machine will go through five phases. On creation, the request will be validated and
+/// parameters for the first step will be extracted from it. It will pose some requests in the form
+/// of [`Output`] which should be satisfied with the next [`Input`] data. This will eventually
+/// produce an [`ExchangedToken`] or an [`Error`]. Note that the executing environment will need
+/// to use a [`Registrar`] and an [`Issuer`] to which some requests should be forwarded.
+///
+/// [`Input`]: struct.Input.html
+/// [`Output`]: struct.Output.html
+/// [`ExchangedToken`]: struct.ExchangedToken.html
+/// [`Error`]: ../accesstoken/struct.Error.html
+/// [`Issuer`] ../primitives/issuer/trait.Issuer.html
+/// [`Registrar`] ../primitives/registrar/trait.Registrar.html
+///
+/// A rough sketch of the operational phases:
+///
+/// 1. Ensure the request is valid based on the basic requirements (includes required parameters)
+/// 2. Authenticate the client
+/// 3. Recover the grant of the subject token and check the requested scope against it
+/// 4. Recover the grant of the actor token, if one was presented
+/// 5. Bind the client to its registered parameters
+/// 6. Query the backend for a new (bearer) token
+pub struct TokenExchange {
+    state: TokenExchangeState,
+}
+
+/// Inner state machine for the token exchange
+enum TokenExchangeState {
+    /// State after the request has been validated.
+    Authenticate {
+        client: String,
+        passdata: Option<Vec<u8>>,
+        subject_token: String,
+        actor_token: Option<String>,
+        scope: Option<Scope>,
+    },
+    RecoverSubject {
+        client: String,
+        subject_token: String,
+        actor_token: Option<String>,
+        scope: Option<Scope>,
+    },
+    RecoverActor {
+        client: String,
+        subject: Box<Grant>,
+        actor_token: String,
+    },
+    Binding {
+        client_id: String,
+        subject: Box<Grant>,
+        actor: Option<String>,
+    },
+    Issue {
+        grant: Box<Grant>,
+    },
+    Err(Error),
+}
+
+/// Input injected by the executor into the state machine.
+pub enum Input {
+    /// Positively answer an authentication query.
+    Authenticated,
+    /// The grant recovered from the subject or actor token.
+    ///
+    /// Is `None` if the issuer does not know the token.
+    Recovered(Option<Grant>),
+    /// The registrar bound the client to its registered parameters.
+    Bound {
+        /// The bound client
+        bound_client: BoundClient<'static>,
+    },
+    /// The token produced by the backend
+    Issued(IssuedToken),
+    /// Advance without input as far as possible, or just retrieve the output again.
+    None,
+}
+
+/// A request by the statemachine to the executor.
+///
+/// Each variant is fulfilled by certain variants of the next inputs as an argument to
+/// `TokenExchange::advance`. The output of most states is simply repeated if `Input::None` is
+/// provided instead but note that the successful token response is **not** repeated.
+pub enum Output<'machine> {
+    /// The registrar should authenticate a client.
+    ///
+    /// Fulfilled by `Input::Authenticated`. In an unsuccessful case, the executor should not
+    /// continue and discard the flow.
+    Authenticate {
+        /// The to-be-authenticated client.
+        client: &'machine str,
+        /// The supplied passdata/password.
+        passdata: Option<&'machine [u8]>,
+    },
+    /// The issuer should recover the grant of the subject token.
+    ///
+    /// Fulfilled by `Input::Recovered`.
+    RecoverSubject {
+        /// The subject token supplied in the request.
+        token: &'machine str,
+    },
+    /// The issuer should recover the grant of the actor token.
+    ///
+    /// Fulfilled by `Input::Recovered`.
+    RecoverActor {
+        /// The actor token supplied in the request.
+        token: &'machine str,
+    },
+    /// Ask registrar to bind the client to its default redirect_uri.
+    ///
+    /// Fulfilled by `Input::Bound`.
+    Binding {
+        /// The already authenticated client.
+        client_id: &'machine str,
+    },
+    /// The issue should issue a new access token
+    ///
+    /// Fullfilled by `Input::Issued`
+    Issue {
+        /// The grant to be used in the token generation
+        grant: &'machine Grant,
+    },
+    /// The state machine finished and a new token was generated
+    ///
+    /// This output **can not** be requested repeatedly, any future `Input` will yield a primitive
+    /// error instead.
+    Ok(ExchangedToken),
+    /// The state machine finished in an error.
+    ///
+    /// The error will be repeated on *any* following input.
+    Err(Box<Error>),
+}
+
+/// Represents the token issued in an exchange and the type identifier of that token.
+pub struct ExchangedToken(pub BearerToken, pub String);
+
+impl TokenExchange {
+    /// Create the state machine. validating the request in the process
+    pub fn new(request: &dyn Request) -> Self {
+        TokenExchange {
+            state: Self::validate(request).unwrap_or_else(TokenExchangeState::Err),
+        }
+    }
+
+    /// Go to next state
+    pub fn advance(&mut self, input: Input) -> Output<'_> {
+        self.state = match (self.take(), input) {
+            (current, Input::None) => current,
+            (
+                TokenExchangeState::Authenticate {
+                    client,
+                    subject_token,
+                    actor_token,
+                    scope,
+                    ..
+                },
+                Input::Authenticated,
+            ) => TokenExchangeState::RecoverSubject {
+                client,
+                subject_token,
+                actor_token,
+                scope,
+            },
+            (
+                TokenExchangeState::RecoverSubject {
+                    client,
+                    actor_token,
+                    scope,
+                    ..
+                },
+                Input::Recovered(subject),
+            ) => Self::subject_recovered(client, actor_token, scope, subject)
+                .unwrap_or_else(TokenExchangeState::Err),
+            (TokenExchangeState::RecoverActor { client, subject, .. }, Input::Recovered(actor)) => {
+                Self::actor_recovered(client, subject, actor).unwrap_or_else(TokenExchangeState::Err)
+            }
+            (
+                TokenExchangeState::Binding { subject, actor, .. },
+                Input::Bound { bound_client },
+            ) => Self::bound(subject, actor, bound_client),
+            (TokenExchangeState::Issue { grant }, Input::Issued(mut token)) => {
+                if dpop::confirmation(&grant).is_some() {
+                    token.token_type = TokenType::DPoP;
+                }
+
+                let token = BearerToken(token, grant.scope.to_string());
+                return Output::Ok(ExchangedToken(token, ACCESS_TOKEN_TYPE.to_string()));
+            }
+            (TokenExchangeState::Err(err), _) => TokenExchangeState::Err(err),
+            (_, _) => TokenExchangeState::Err(Error::primitive(None)),
+        };
+
+        self.output()
+    }
+
+    fn output(&self) -> Output<'_> {
+        match &self.state {
+            TokenExchangeState::Err(err) => Output::Err(Box::new(err.clone())),
+            TokenExchangeState::Authenticate { client, passdata, .. } => Output::Authenticate {
+                client,
+                passdata: passdata.as_deref(),
+            },
+            TokenExchangeState::RecoverSubject { subject_token, .. } => {
+                Output::RecoverSubject { token: subject_token }
+            }
+            TokenExchangeState::RecoverActor { actor_token, .. } => {
+                Output::RecoverActor { token: actor_token }
+            }
+            TokenExchangeState::Binding { client_id, .. } => Output::Binding { client_id },
+            TokenExchangeState::Issue { grant } => Output::Issue { grant },
+        }
+    }
+
+    fn take(&mut self) -> TokenExchangeState {
+        mem::replace(&mut self.state, TokenExchangeState::Err(Error::primitive(None)))
+    }
+
+    fn validate(request: &dyn Request) -> Result<TokenExchangeState, Error> {
+        if !request.valid() {
+            return Err(Error::invalid());
+        }
+
+        let authorization = request.authorization();
+        let client_id = request.client_id();
+        let client_secret = request.extension("client_secret");
+
+        let mut credentials = Credentials::None;
+        if let Some((client_id, auth)) = &authorization {
+            credentials.authenticate(client_id.as_ref(), auth.as_ref());
+        }
+
+        if let Some(client_id) = &client_id {
+            match &client_secret {
+                Some(auth) if request.allow_credentials_in_body() => {
+                    credentials.authenticate(client_id.as_ref(), auth.as_ref().as_bytes())
+                }
+                // Ignore parameter if not allowed.
+                Some(_) | None => credentials.unauthenticated(client_id.as_ref()),
+            }
+        }
+
+        match request.grant_type() {
+            Some(ref cow) if cow == TOKEN_EXCHANGE_GRANT_TYPE => (),
+            None => return Err(Error::invalid()),
+            Some(_) => return Err(Error::invalid_with(AccessTokenErrorType::UnsupportedGrantType)),
+        };
+
+        let (client_id, passdata) = credentials.into_client().ok_or_else(Error::invalid)?;
+        let subject_token = request.subject_token().ok_or_else(Error::invalid)?;
+
+        // Only access tokens issued by this server can be exchanged.
+        match request.subject_token_type() {
+            Some(ref cow) if cow == ACCESS_TOKEN_TYPE => (),
+            _ => return Err(Error::invalid()),
+        }
+
+        let actor_token = request.actor_token();
+        match (&actor_token, request.actor_token_type()) {
+            (None, None) => (),
+            (Some(_), Some(ref cow)) if cow == ACCESS_TOKEN_TYPE => (),
+            _ => return Err(Error::invalid()),
+        }
+
+        match request.requested_token_type() {
+            None => (),
+            Some(ref cow) if cow == ACCESS_TOKEN_TYPE => (),
+            Some(_) => return Err(Error::invalid()),
+        }
+
+        let scope = match request.scope() {
+            None => None,
+            Some(scope) => Some(
+                scope
+                    .parse()
+                    .map_err(|_| Error::invalid_with(AccessTokenErrorType::InvalidScope))?,
+            ),
+        };
+
+        Ok(TokenExchangeState::Authenticate {
+            client: client_id.to_string(),
+            passdata: passdata.map(Vec::from),
+            subject_token: subject_token.into_owned(),
+            actor_token: actor_token.map(Cow::into_owned),
+            scope,
+        })
+    }
+
+    fn subject_recovered(
+        client: String, actor_token: Option<String>, scope: Option<Scope>, subject: Option<Grant>,
+    ) -> Result<TokenExchangeState, Error> {
+        let mut subject = Self::active(subject)?;
+
+        // The new token may only be narrower than the subject token.
+        if let Some(scope) = scope {
+            if !subject.scope.priviledged_to(&scope) {
+                return Err(Error::invalid_with(AccessTokenErrorType::InvalidScope));
+            }
+            subject.scope = scope;
+        }
+
+        Ok(match actor_token {
+            Some(actor_token) => TokenExchangeState::RecoverActor {
+                client,
+                subject,
+                actor_token,
+            },
+            None => TokenExchangeState::Binding {
+                client_id: client,
+                subject,
+                actor: None,
+            },
+        })
+    }
+
+    fn actor_recovered(
+        client: String, subject: Box<Grant>, actor: Option<Grant>,
+    ) -> Result<TokenExchangeState, Error> {
+        let actor = Self::active(actor)?;
+
+        Ok(TokenExchangeState::Binding {
+            client_id: client,
+            subject,
+            actor: Some(actor.owner_id),
+        })
+    }
+
+    fn active(grant: Option<Grant>) -> Result<Box<Grant>, Error> {
+        match grant {
+            Some(grant) if grant.until > Utc::now() => Ok(Box::new(grant)),
+            _ => Err(Error::invalid_with(AccessTokenErrorType::InvalidGrant)),
+        }
+    }
+
+    fn bound(subject: Box<Grant>, actor: Option<String>, bound_client: BoundClient<'static>) -> TokenExchangeState {
+        let Grant {
+            owner_id,
+            scope,
+            until,
+            extensions: subject_extensions,
+            ..
+        } = *subject;
+
+        let mut chain: Vec<String> = subject_extensions
+            .public()
+            .find(|&(key, _)| key == ACTOR_EXTENSION)
+            .and_then(|(_, value)| value)
+            .and_then(|value| serde_json::from_str(value).ok())
+            .unwrap_or_default();
+        if let Some(actor) = actor {
+            chain.insert(0, actor);
+        }
+
+        let mut extensions = Extensions::new();
+        // The exchanged token stays bound to the key or certificate of the subject token.
+        for (key, value) in subject_extensions.public() {
+            if key == dpop::CONFIRMATION || key == mtls::CONFIRMATION {
+                extensions.set_raw(key.to_string(), Value::public(value.map(str::to_string)));
+            }
+        }
+
+        if !chain.is_empty() {
+            let chain = serde_json::to_string(&chain).unwrap();
+            extensions.set_raw(ACTOR_EXTENSION.to_string(), Value::public(Some(chain)));
+        }
+
+        TokenExchangeState::Issue {
+            grant: Box::new(Grant {
+                owner_id,
+                client_id: bound_client.client_id.into_owned(),
+                redirect_uri: bound_client.redirect_uri.into_owned().into_url(),
+                scope,
+                // The exchanged token must not outlive the subject token.
+                until: until.min(Utc::now() + Duration::hours(1)),
+                extensions,
+            }),
+        }
+    }
+}
+
+impl ExchangedToken {
+    /// Convert the token into a json string, viable for being sent over a network with
+    /// `application/json` encoding.
+    pub fn to_json(&self) -> String {
+        let ExchangedToken(BearerToken(token, scope), issued_token_type) = self;
+        let remaining = token.until.signed_duration_since(Utc::now());
+        let token_response = TokenResponse {
+            access_token: Some(token.token.clone()),
+            refresh_token: token.refresh.clone(),
+            token_type: Some(token.token_type.as_str().to_owned()),
+            expires_in: Some(remaining.num_seconds()),
+            scope: Some(scope.clone()),
+            issued_token_type: Some(issued_token_type.clone()),
+            error: None,
+        };
+
+        serde_json::to_string(&token_response).unwrap()
+    }
+}
+
+/// Try to exchange a subject token for a new access token.
+pub fn token_exchange(handler: &mut dyn Endpoint, request: &dyn Request) -> Result<ExchangedToken, Error> {
+    enum Requested {
+        None,
+        Authenticate { client: String, passdata: Option<Vec<u8>> },
+        Recover { token: String },
+        Bind { client_id: String },
+        Issue { grant: Grant },
+    }
+
+    let mut token_exchange = TokenExchange::new(request);
+    let mut requested = Requested::None;
+
+    loop {
+        let input = match requested {
+            Requested::None => Input::None,
+            Requested::Authenticate { client, passdata } => {
+                handler
+                    .registrar()
+                    .check(&client, passdata.as_deref())
+                    .map_err(|err| match err {
+                        RegistrarError::Unspecified => Error::unauthorized("basic"),
+                        RegistrarError::PrimitiveError => Error::primitive(None),
+                    })?;
+                handler
+                    .registrar()
+                    .check_grant_type(&client, TOKEN_EXCHANGE_GRANT_TYPE)
+                    .map_err(|err| match err {
+                        RegistrarError::Unspecified => {
+                            Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
+                        }
+                        RegistrarError::PrimitiveError => Error::primitive(None),
+                    })?;
+                Input::Authenticated
+            }
+            Requested::Recover { token } => {
+                let grant = handler
+                    .issuer()
+                    .recover_token(&token)
+                    .map_err(|()| Error::primitive(None))?;
+                Input::Recovered(grant)
+            }
+            Requested::Bind { client_id } => {
+                let client_url = ClientUrl {
+                    client_id: Cow::Owned(client_id),
+                    redirect_uri: None,
+                };
+                let bound_client =
+                    handler
+                        .registrar()
+                        .bound_redirect(client_url)
+                        .map_err(|err| match err {
+                            RegistrarError::Unspecified => {
+                                Error::invalid_with(AccessTokenErrorType::UnauthorizedClient)
+                            }
+                            RegistrarError::PrimitiveError => Error::primitive(None),
+                        })?;
+                Input::Bound { bound_client }
+            }
+            Requested::Issue { grant } => {
+                let token = handler
+                    .issuer()
+                    .issue(grant.clone())
+                    .map_err(|_| Error::primitive(Some(grant)))?;
+                Input::Issued(token)
+            }
+        };
+
+        requested = match token_exchange.advance(input) {
+            Output::Authenticate { client, passdata } => Requested::Authenticate {
+                client: client.to_string(),
+                passdata: passdata.map(Vec::from),
+            },
+            Output::RecoverSubject { token } | Output::RecoverActor { token } => Requested::Recover {
+                token: token.to_string(),
+            },
+            Output::Binding { client_id } => Requested::Bind {
+                client_id: client_id.to_string(),
+            },
+            Output::Issue { grant } => Requested::Issue { grant: grant.clone() },
+            Output::Ok(token) => return Ok(token),
+            Output::Err(err) => return Err(*err),
+        };
+    }
+}
@@ -49,6 +49,12 @@ struct WrappedRequest<'a, R: WebRequest + 'a> {
 
     /// The credentials-in-body flag from the flow.
     allow_credentials_in_body: bool,
+
+    /// The thumbprint of the key proven by a DPoP proof, an error if the proof was invalid.
+    dpop: Result<Option<String>, ()>,
+
+    /// The client certificate of the connection.
+    certificate: Option<ClientCertificate>,
 }
 
 struct Invalid;
@@ -119,11 +125,12 @@ where
     /// When the registrar, authorizer, or issuer returned by the endpoint is suddenly
     /// `None` when previously it was `Some(_)`.
     pub fn execute(&mut self, mut request: R) -> Result<R::Response, E::Error> {
-
-        let issued = access_token(
-            &mut self.endpoint,
-            &WrappedRequest::new(&mut request, self.allow_credentials_in_body),
+        let wrapped = WrappedRequest::new(
+            &mut request,
+            self.allow_credentials_in_body,
+            self.endpoint.inner.dpop_verifier(),
         );
+        let issued = access_token(&mut self.endpoint, &wrapped);
 
         let token = match issued {
             Err(error) => {
@@ -207,11 +214,13 @@ impl<E: Endpoint<R>, R: WebRequest> TokenEndpoint for WrappedToken<E, R> {
 }
 
 impl<'a, R: WebRequest + 'a> WrappedRequest<'a, R> {
-    pub fn new(request: &'a mut R, credentials: bool) -> Self {
-        Self::new_or_fail(request, credentials).unwrap_or_else(Self::from_err)
+    pub fn new(request: &'a mut R, credentials: bool, dpop: Option<&dyn DpopVerifier>) -> Self {
+        Self::new_or_fail(request, credentials, dpop).unwrap_or_else(Self::from_err)
     }
 
-    fn new_or_fail(request: &'a mut R, credentials: bool) -> Result<Self, FailParse<R::Error>> {
+    fn new_or_fail(
+        request: &'a mut R, credentials: bool, dpop: Option<&dyn DpopVerifier>,
+    ) -> Result<Self, FailParse<R::Error>> {
         // If there is a header, it must parse correctly.
         let authorization = match request.authheader() {
             Err(err) => return Err(FailParse::Err(err)),
@@ -219,12 +228,20 @@ impl<'a, R: WebRequest + 'a> WrappedRequest<'a, R> {
             Ok(None) => None,
         };
 
+        let dpop = dpop_proof(dpop, request, None).map_err(FailParse::Err)?;
+        let certificate = request
+            .certificate()
+            .map_err(FailParse::Err)?
+            .map(Cow::into_owned);
+
         Ok(WrappedRequest {
             request: PhantomData,
             body: request.urlbody().map_err(FailParse::Err)?,
             authorization,
             error: None,
             allow_credentials_in_body: credentials,
+            dpop,
+            certificate,
         })
     }
 
@@ -235,6 +252,8 @@ impl<'a, R: WebRequest + 'a> WrappedRequest<'a, R> {
             authorization: None,
             error: Some(err),
             allow_credentials_in_body: false,
+            dpop: Ok(None),
+            certificate: None,
         }
     }
 
@@ -305,6 +324,14 @@ impl<'a, R: WebRequest> TokenRequest for WrappedRequest<'a, R> {
     fn allow_credentials_in_body(&self) -> bool {
         self.allow_credentials_in_body
     }
+
+    fn dpop_thumbprint(&self) -> Result<Option<Cow<'_, str>>, ()> {
+        self.dpop.clone().map(|thumbprint| thumbprint.map(Cow::Owned))
+    }
+
+    fn certificate(&self) -> Option<Cow<'_, ClientCertificate>> {
+        self.certificate.as_ref().map(Cow::Borrowed)
+    }
 }
 
 impl<E> From<Invalid> for FailParse<E> {
@@ -2,20 +2,16 @@ use crate::code_grant::authorization::{
     authorization_code, Error as AuthorizationError, Extension, Endpoint as AuthorizationEndpoint,
     Request as AuthorizationRequest, Pending,
 };
-
-use crate::token_grant::authorization:: {
-    authorization_token,
-};
-
-use crate::code_grant::accesstoken::{
-    Error as TokenError,
-};
+use crate::code_grant::implicit::{implicit, Endpoint as ImplicitEndpoint, Pending as ImplicitPending};
 
 use super::*;
-use crate::code_grant::error::AccessTokenErrorType;
-use chrono::{Duration, Utc};
 
 /// All relevant methods for handling authorization code requests.
+///
+/// Requests with `response_type=token` are handled as an implicit grant instead, if the endpoint
+/// provides an `Issuer`. The access token is then issued directly after the resource owner
+/// consented and delivered in the fragment of the redirect url. The registrar decides which
+/// clients may use the implicit grant, see `Registrar::check_response_type`.
 pub struct AuthorizationFlow<E, R>
 where
     E: Endpoint<R>,
@@ -47,10 +43,16 @@ where
     R: WebRequest,
 {
     endpoint: &'a mut WrappedAuthorization<E, R>,
-    pending: Pending,
+    pending: PendingGrant,
     request: R,
 }
 
+/// The pending request of either the authorization code or the implicit grant.
+enum PendingGrant {
+    Code(Pending),
+    Implicit(ImplicitPending),
+}
+
 /// A processed authentication request that may be waiting for authorization by the resource owner.
 ///
 /// Note that this borrows from the `AuthorizationFlow` used to create it. You can `finish` the
@@ -140,70 +142,43 @@ where
     /// When the registrar or the authorizer returned by the endpoint is suddenly `None` when
     /// previously it was `Some(_)`.
     pub fn execute(&mut self, mut request: R) -> Result<R::Response, E::Error> {
-        let req = WrappedRequest::new(&mut request);
-        let response_type = match req.response_type() {
-            Some( method) => {method}
-            None => {
-                let error = TokenError::invalid_with(AccessTokenErrorType::UnsupportedResponseType);
-                error!("{:?}", error);
-                return token_error(&mut self.endpoint.inner, &mut request, error);
-            }
+        let negotiated = if self.is_implicit(&mut request) {
+            implicit(&mut self.endpoint, &WrappedRequest::new(&mut request)).map(PendingGrant::Implicit)
+        } else {
+            authorization_code(&mut self.endpoint, &WrappedRequest::new(&mut request))
+                .map(PendingGrant::Code)
         };
 
-        if response_type.clone() == "code" {
-            let negotiated = authorization_code(&mut self.endpoint, &WrappedRequest::new(&mut request));
-
-            let inner = match negotiated {
-                Err(err) => match authorization_error(&mut self.endpoint.inner, &mut request, err) {
-                    Ok(response) => AuthorizationPartialInner::Failed { request, response },
-                    Err(error) => AuthorizationPartialInner::Error { request, error },
-                },
-                Ok(negotiated) => AuthorizationPartialInner::Pending {
-                    pending: AuthorizationPending {
-                        endpoint: &mut self.endpoint,
-                        pending: negotiated,
-                        request,
-                    },
+        let inner = match negotiated {
+            Err(err) => match authorization_error(&mut self.endpoint.inner, &mut request, err) {
+                Ok(response) => AuthorizationPartialInner::Failed { request, response },
+                Err(error) => AuthorizationPartialInner::Error { request, error },
+            },
+            Ok(negotiated) => AuthorizationPartialInner::Pending {
+                pending: AuthorizationPending {
+                    endpoint: &mut self.endpoint,
+                    pending: negotiated,
+                    request,
                 },
-            };
+            },
+        };
 
-            let partial = AuthorizationPartial { inner };
+        let partial = AuthorizationPartial { inner };
 
-            return partial.finish();
-        } else if response_type.clone() == "token" {
-            let token = authorization_token(&mut self.endpoint, &req);
-            match token {
-                Err(err) => {
-                    error!("{:?}", err);
-                    return token_error(&mut self.endpoint.inner, &mut request, err);
-                }
-                Ok(token) => {
-                    let remaining = token.0.until.signed_duration_since(Utc::now());
-                    let redirect_url = format!("{}#access_token={}&token_type={}&expires_in={}&scope={}&state={}",
-                                               req.redirect_uri().unwrap_or_default(),
-                                               token.0.token,
-                                               "Bearer",
-                                               remaining.num_seconds(),
-                                               token.1,
-                                               req.state().unwrap_or_default());
-                    let mut response = self.endpoint.inner.response(
-                        &mut request,
-                        InnerTemplate::Redirect {
-                            authorization_error: None,
-                        }
-                            .into(),
-                    )?;
-                    if let Ok(url) = Url::parse(redirect_url.as_str()) {
-                        response.redirect(url).map_err(|err| self.endpoint.inner.web_error(err))?;
-                        return Ok(response);
-                    }
-                }
-            }
-        }
+        partial.finish()
+    }
 
-        let error = TokenError::invalid_with(AccessTokenErrorType::ServerError);
-        error!("{:?}", error);
-        return token_error(&mut self.endpoint.inner, &mut request, error);
+    /// Check if the request should be handled as an implicit grant.
+    ///
+    /// Only endpoints with an issuer support the implicit grant, other endpoints treat the token
+    /// response type as unsupported in the authorization code flow.
+    fn is_implicit(&mut self, request: &mut R) -> bool {
+        let token = match WrappedRequest::new(request).response_type() {
+            Some(response_type) => response_type == "token",
+            None => false,
+        };
+
+        token && self.endpoint.inner.issuer_mut().is_some()
     }
 }
 
@@ -273,7 +248,10 @@ impl<'a, E: Endpoint<R>, R: WebRequest> AuthorizationPending<'a, E, R> {
 
     /// Denies the request, the client is not allowed access.
     fn deny(mut self) -> (R, Result<R::Response, E::Error>) {
-        let result = self.pending.deny();
+        let result = match self.pending {
+            PendingGrant::Code(pending) => pending.deny(),
+            PendingGrant::Implicit(pending) => pending.deny(),
+        };
         let result = Self::convert_result(result, &mut self.endpoint.inner, &mut self.request);
 
         (self.request, result)
@@ -281,7 +259,10 @@ impl<'a, E: Endpoint<R>, R: WebRequest> AuthorizationPending<'a, E, R> {
 
     /// Tells the system that the resource owner with the given id has approved the grant.
     fn authorize(mut self, who: String) -> (R, Result<R::Response, E::Error>) {
-        let result = self.pending.authorize(self.endpoint, who.into());
+        let result = match self.pending {
+            PendingGrant::Code(pending) => pending.authorize(self.endpoint, who.into()),
+            PendingGrant::Implicit(pending) => pending.authorize(self.endpoint, who.into()),
+        };
         let result = Self::convert_result(result, &mut self.endpoint.inner, &mut self.request);
 
         (self.request, result)
@@ -329,14 +310,32 @@ impl<E: Endpoint<R>, R: WebRequest> AuthorizationEndpoint for WrappedAuthorizati
             .unwrap_or(&mut self.extension_fallback)
     }
 
+}
+
+impl<E: Endpoint<R>, R: WebRequest> ImplicitEndpoint for WrappedAuthorization<E, R> {
+    fn registrar(&self) -> &dyn Registrar {
+        self.inner.registrar().unwrap()
+    }
+
+    fn extension(&mut self) -> &mut dyn Extension {
+        self.inner
+            .extension()
+            .and_then(super::Extension::authorization)
+            .unwrap_or(&mut self.extension_fallback)
+    }
+
     fn issuer(&mut self) -> &mut dyn Issuer {
         self.inner.issuer_mut().unwrap()
     }
+}
 
-    // fn response(&mut self, request: &mut R, kind: Template) -> Result<R::Response, Self::Error> {
-    //
-    //     self.inner.response(request, kind)
-    // }
+impl PendingGrant {
+    fn as_solicitation(&self) -> Solicitation<'_> {
+        match self {
+            PendingGrant::Code(pending) => pending.as_solicitation(),
+            PendingGrant::Implicit(pending) => pending.as_solicitation(),
+        }
+    }
 }
 
 impl<'a, R: WebRequest + 'a> WrappedRequest<'a, R> {
@@ -382,10 +381,6 @@ impl<'a, R: WebRequest + 'a> AuthorizationRequest for WrappedRequest<'a, R> {
         self.query.unique_value("state")
     }
 
-    fn user_id(&self) -> Option<Cow<str>> {
-        self.query.unique_value("user_id")
-    }
-
     fn response_type(&self) -> Option<Cow<str>> {
         self.query.unique_value("response_type")
     }
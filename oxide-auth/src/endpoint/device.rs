@@ -0,0 +1,570 @@
+use std::str::from_utf8;
+use std::marker::PhantomData;
+
+use crate::code_grant::device::{
+    device_authorization, device_token, device_verification, Endpoint as DeviceEndpoint,
+    Request as DeviceRequest, TokenEndpoint as DeviceTokenEndpoint,
+    TokenRequest as DeviceTokenRequest, VerificationEndpoint as DeviceVerificationEndpoint,
+    VerificationError, VerificationRequest as DeviceVerificationRequest,
+};
+use crate::primitives::device::DeviceAuthorizer;
+
+use super::*;
+
+/// Offers device and user codes to devices with limited input capabilities.
+///
+/// The device authenticates itself as a client and receives a device code with which it polls the
+/// token endpoint, see [`DeviceTokenFlow`], and a user code that the resource owner enters at the
+/// verification uri, see [`DeviceVerificationFlow`]. This request MUST be protected by TLS.
+///
+/// Client credentials can be allowed to appear in the request body instead of being
+/// required to be passed as HTTP Basic authorization. This is not recommended and must be
+/// enabled explicitely. See [`allow_credentials_in_body`] for details.
+///
+/// [`DeviceTokenFlow`]: struct.DeviceTokenFlow.html
+/// [`DeviceVerificationFlow`]: struct.DeviceVerificationFlow.html
+/// [`allow_credentials_in_body`]: #method.allow_credentials_in_body
+pub struct DeviceAuthorizationFlow<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    endpoint: WrappedDevice<E, R>,
+    verification_uri: Url,
+    allow_credentials_in_body: bool,
+}
+
+/// Lets a resource owner approve or deny a device through its user code.
+///
+/// The user code is taken from the `user_code` query parameter. Consent is checked with the
+/// `OwnerSolicitor` of the endpoint, exactly as for the authorization code flow, and the decision
+/// is recorded for the device to pick up on its next poll.
+pub struct DeviceVerificationFlow<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    endpoint: WrappedDevice<E, R>,
+}
+
+/// Offers access tokens to devices polling with their device code.
+///
+/// While the resource owner has not yet decided, the device receives an `authorization_pending`
+/// error or a `slow_down` error when polling too fast. Once the owner decided or the device code
+/// expired, it receives an access token or an `access_denied` or `expired_token` error.
+pub struct DeviceTokenFlow<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    endpoint: WrappedDevice<E, R>,
+    allow_credentials_in_body: bool,
+}
+
+struct WrappedDevice<E: Endpoint<R>, R: WebRequest> {
+    inner: E,
+    r_type: PhantomData<R>,
+}
+
+struct WrappedRequest<'a, R: WebRequest + 'a> {
+    /// Original request.
+    request: PhantomData<R>,
+
+    /// The query in the body.
+    body: Cow<'a, dyn QueryParameter + 'static>,
+
+    /// The authorization tuple
+    authorization: Option<Authorization>,
+
+    /// An error if one occurred.
+    error: Option<FailParse<R::Error>>,
+
+    /// The credentials-in-body flag from the flow.
+    allow_credentials_in_body: bool,
+}
+
+struct WrappedVerification<'a, R: WebRequest + 'a> {
+    /// Original request.
+    request: PhantomData<R>,
+
+    /// The query in the url.
+    query: Cow<'a, dyn QueryParameter + 'static>,
+
+    /// An error if one occurred.
+    error: Option<R::Error>,
+}
+
+struct Invalid;
+
+enum FailParse<E> {
+    Invalid,
+    Err(E),
+}
+
+struct Authorization(String, Vec<u8>);
+
+impl<E, R> DeviceAuthorizationFlow<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    /// Check that the endpoint supports the necessary operations for handling requests.
+    ///
+    /// Binds the endpoint to a particular type of request that it supports, for many
+    /// implementations this is probably single type anyways. The endpoint needs to provide
+    /// (return `Some`):
+    ///
+    /// * a `Registrar` from `registrar`
+    /// * a `DeviceAuthorizer` from `device_authorizer_mut`
+    ///
+    /// The `verification_uri` is the page serving the `DeviceVerificationFlow` and is presented
+    /// to the resource owner by the device.
+    ///
+    /// ## Panics
+    ///
+    /// Indirectly `execute` may panic when this flow is instantiated with an inconsistent
+    /// endpoint, for details see the documentation of `Endpoint` and `execute`. For
+    /// consistent endpoints, the panic is instead caught as an error here.
+    pub fn prepare(mut endpoint: E, verification_uri: Url) -> Result<Self, E::Error> {
+        if endpoint.registrar().is_none() {
+            return Err(endpoint.error(OAuthError::PrimitiveError));
+        }
+
+        if endpoint.device_authorizer_mut().is_none() {
+            return Err(endpoint.error(OAuthError::PrimitiveError));
+        }
+
+        Ok(DeviceAuthorizationFlow {
+            endpoint: WrappedDevice {
+                inner: endpoint,
+                r_type: PhantomData,
+            },
+            verification_uri,
+            allow_credentials_in_body: false,
+        })
+    }
+
+    /// Credentials in body should only be enabled if use of HTTP Basic is not possible.
+    ///
+    /// Allows the request body to contain the `client_secret` as a form parameter. This is NOT
+    /// RECOMMENDED and need not be supported. The parameters MUST NOT appear in the request URI
+    /// itself.
+    ///
+    /// Thus support is disabled by default and must be explicitely enabled.
+    pub fn allow_credentials_in_body(&mut self, allow: bool) {
+        self.allow_credentials_in_body = allow;
+    }
+
+    /// Use the checked endpoint to create device and user codes for the client.
+    ///
+    /// ## Panics
+    ///
+    /// When the registrar or device authorizer returned by the endpoint is suddenly `None` when
+    /// previously it was `Some(_)`.
+    pub fn execute(&mut self, mut request: R) -> Result<R::Response, E::Error> {
+        let issued = device_authorization(
+            &mut self.endpoint,
+            &WrappedRequest::new(&mut request, self.allow_credentials_in_body),
+        );
+
+        let codes = match issued {
+            Err(error) => return token_error(&mut self.endpoint.inner, &mut request, error),
+            Ok(codes) => codes,
+        };
+
+        let mut response = self
+            .endpoint
+            .inner
+            .response(&mut request, InnerTemplate::Ok.into())?;
+        response
+            .body_json(&codes.to_json(&self.verification_uri))
+            .map_err(|err| self.endpoint.inner.web_error(err))?;
+        Ok(response)
+    }
+}
+
+impl<E, R> DeviceVerificationFlow<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    /// Check that the endpoint supports the necessary operations for handling requests.
+    ///
+    /// The endpoint needs to provide (return `Some`):
+    ///
+    /// * a `DeviceAuthorizer` from `device_authorizer_mut`
+    /// * an `OwnerSolicitor` from `owner_solicitor`
+    ///
+    /// ## Panics
+    ///
+    /// Indirectly `execute` may panic when this flow is instantiated with an inconsistent
+    /// endpoint, for details see the documentation of `Endpoint` and `execute`. For
+    /// consistent endpoints, the panic is instead caught as an error here.
+    pub fn prepare(mut endpoint: E) -> Result<Self, E::Error> {
+        if endpoint.device_authorizer_mut().is_none() {
+            return Err(endpoint.error(OAuthError::PrimitiveError));
+        }
+
+        if endpoint.owner_solicitor().is_none() {
+            return Err(endpoint.error(OAuthError::PrimitiveError));
+        }
+
+        Ok(DeviceVerificationFlow {
+            endpoint: WrappedDevice {
+                inner: endpoint,
+                r_type: PhantomData,
+            },
+        })
+    }
+
+    /// Use the checked endpoint to let the resource owner decide over a pending device.
+    ///
+    /// Unknown and expired user codes are answered with a Bad Request response. Otherwise, the
+    /// response of the solicitor is returned while it has not yet decided, and a plain text
+    /// confirmation afterwards.
+    ///
+    /// ## Panics
+    ///
+    /// When the device authorizer or solicitor returned by the endpoint is suddenly `None` when
+    /// previously it was `Some(_)`.
+    pub fn execute(&mut self, mut request: R) -> Result<R::Response, E::Error> {
+        let pending = device_verification(&mut self.endpoint, &WrappedVerification::new(&mut request));
+
+        let pending = match pending {
+            Ok(pending) => pending,
+            Err(VerificationError::PrimitiveError) => {
+                return Err(self.endpoint.inner.error(OAuthError::PrimitiveError))
+            }
+            Err(VerificationError::Invalid) => {
+                return self.bad_request(&mut request, "The user code is missing.")
+            }
+            Err(VerificationError::Unknown) => {
+                return self.bad_request(&mut request, "The user code is unknown or has expired.")
+            }
+        };
+
+        let checked = self
+            .endpoint
+            .inner
+            .owner_solicitor()
+            .unwrap()
+            .check_consent(&mut request, pending.as_solicitation());
+
+        let (decided, text) = match checked {
+            OwnerConsent::InProgress(response) => return Ok(response),
+            OwnerConsent::Error(err) => return Err(self.endpoint.inner.web_error(err)),
+            OwnerConsent::Authorized(owner) => (
+                pending.authorize(&mut self.endpoint, owner.into()),
+                "The device has been authorized.",
+            ),
+            OwnerConsent::Denied => (
+                pending.deny(&mut self.endpoint),
+                "The device authorization has been denied.",
+            ),
+        };
+
+        if decided.is_err() {
+            return Err(self.endpoint.inner.error(OAuthError::PrimitiveError));
+        }
+
+        let mut response = self
+            .endpoint
+            .inner
+            .response(&mut request, InnerTemplate::Ok.into())?;
+        response
+            .body_text(text)
+            .map_err(|err| self.endpoint.inner.web_error(err))?;
+        Ok(response)
+    }
+
+    fn bad_request(&mut self, request: &mut R, text: &str) -> Result<R::Response, E::Error> {
+        let mut response = self.endpoint.inner.response(
+            request,
+            InnerTemplate::BadRequest {
+                access_token_error: None,
+            }
+            .into(),
+        )?;
+        response
+            .client_error()
+            .map_err(|err| self.endpoint.inner.web_error(err))?;
+        response
+            .body_text(text)
+            .map_err(|err| self.endpoint.inner.web_error(err))?;
+        Ok(response)
+    }
+}
+
+impl<E, R> DeviceTokenFlow<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    /// Check that the endpoint supports the necessary operations for handling requests.
+    ///
+    /// Binds the endpoint to a particular type of request that it supports, for many
+    /// implementations this is probably single type anyways. The endpoint needs to provide
+    /// (return `Some`):
+    ///
+    /// * a `Registrar` from `registrar`
+    /// * a `DeviceAuthorizer` from `device_authorizer_mut`
+    /// * an `Issuer` from `issuer_mut`
+    ///
+    /// ## Panics
+    ///
+    /// Indirectly `execute` may panic when this flow is instantiated with an inconsistent
+    /// endpoint, for details see the documentation of `Endpoint` and `execute`. For
+    /// consistent endpoints, the panic is instead caught as an error here.
+    pub fn prepare(mut endpoint: E) -> Result<Self, E::Error> {
+        if endpoint.registrar().is_none() {
+            return Err(endpoint.error(OAuthError::PrimitiveError));
+        }
+
+        if endpoint.device_authorizer_mut().is_none() {
+            return Err(endpoint.error(OAuthError::PrimitiveError));
+        }
+
+        if endpoint.issuer_mut().is_none() {
+            return Err(endpoint.error(OAuthError::PrimitiveError));
+        }
+
+        Ok(DeviceTokenFlow {
+            endpoint: WrappedDevice {
+                inner: endpoint,
+                r_type: PhantomData,
+            },
+            allow_credentials_in_body: false,
+        })
+    }
+
+    /// Credentials in body should only be enabled if use of HTTP Basic is not possible.
+    ///
+    /// Allows the request body to contain the `client_secret` as a form parameter. This is NOT
+    /// RECOMMENDED and need not be supported. The parameters MUST NOT appear in the request URI
+    /// itself.
+    ///
+    /// Thus support is disabled by default and must be explicitely enabled.
+    pub fn allow_credentials_in_body(&mut self, allow: bool) {
+        self.allow_credentials_in_body = allow;
+    }
+
+    /// Use the checked endpoint to answer a polling device.
+    ///
+    /// ## Panics
+    ///
+    /// When the registrar, device authorizer or issuer returned by the endpoint is suddenly `None`
+    /// when previously it was `Some(_)`.
+    pub fn execute(&mut self, mut request: R) -> Result<R::Response, E::Error> {
+        let issued = device_token(
+            &mut self.endpoint,
+            &WrappedRequest::new(&mut request, self.allow_credentials_in_body),
+        );
+
+        let token = match issued {
+            Err(error) => return token_error(&mut self.endpoint.inner, &mut request, error),
+            Ok(token) => token,
+        };
+
+        let mut response = self
+            .endpoint
+            .inner
+            .response(&mut request, InnerTemplate::Ok.into())?;
+        response
+            .body_json(&token.to_json())
+            .map_err(|err| self.endpoint.inner.web_error(err))?;
+        Ok(response)
+    }
+}
+
+impl<E: Endpoint<R>, R: WebRequest> DeviceEndpoint for WrappedDevice<E, R> {
+    fn registrar(&self) -> &dyn Registrar {
+        self.inner.registrar().unwrap()
+    }
+
+    fn device_authorizer(&mut self) -> &mut dyn DeviceAuthorizer {
+        self.inner.device_authorizer_mut().unwrap()
+    }
+}
+
+impl<E: Endpoint<R>, R: WebRequest> DeviceVerificationEndpoint for WrappedDevice<E, R> {
+    fn device_authorizer(&mut self) -> &mut dyn DeviceAuthorizer {
+        self.inner.device_authorizer_mut().unwrap()
+    }
+}
+
+impl<E: Endpoint<R>, R: WebRequest> DeviceTokenEndpoint for WrappedDevice<E, R> {
+    fn registrar(&self) -> &dyn Registrar {
+        self.inner.registrar().unwrap()
+    }
+
+    fn device_authorizer(&mut self) -> &mut dyn DeviceAuthorizer {
+        self.inner.device_authorizer_mut().unwrap()
+    }
+
+    fn issuer(&mut self) -> &mut dyn Issuer {
+        self.inner.issuer_mut().unwrap()
+    }
+}
+
+impl<'a, R: WebRequest + 'a> WrappedRequest<'a, R> {
+    pub fn new(request: &'a mut R, credentials: bool) -> Self {
+        Self::new_or_fail(request, credentials).unwrap_or_else(Self::from_err)
+    }
+
+    fn new_or_fail(request: &'a mut R, credentials: bool) -> Result<Self, FailParse<R::Error>> {
+        // If there is a header, it must parse correctly.
+        let authorization = match request.authheader() {
+            Err(err) => return Err(FailParse::Err(err)),
+            Ok(Some(header)) => Self::parse_header(header).map(Some)?,
+            Ok(None) => None,
+        };
+
+        Ok(WrappedRequest {
+            request: PhantomData,
+            body: request.urlbody().map_err(FailParse::Err)?,
+            authorization,
+            error: None,
+            allow_credentials_in_body: credentials,
+        })
+    }
+
+    fn from_err(err: FailParse<R::Error>) -> Self {
+        WrappedRequest {
+            request: PhantomData,
+            body: Cow::Owned(Default::default()),
+            authorization: None,
+            error: Some(err),
+            allow_credentials_in_body: false,
+        }
+    }
+
+    fn parse_header(header: Cow<str>) -> Result<Authorization, Invalid> {
+        let authorization = {
+            if !header.starts_with("Basic ") {
+                return Err(Invalid);
+            }
+
+            let combined = match base64::decode(&header[6..]) {
+                Err(_) => return Err(Invalid),
+                Ok(vec) => vec,
+            };
+
+            let mut split = combined.splitn(2, |&c| c == b':');
+            let client_bin = match split.next() {
+                None => return Err(Invalid),
+                Some(client) => client,
+            };
+            let passwd = match split.next() {
+                None => return Err(Invalid),
+                Some(passwd64) => passwd64,
+            };
+
+            let client = match from_utf8(client_bin) {
+                Err(_) => return Err(Invalid),
+                Ok(client) => client,
+            };
+
+            Authorization(client.to_string(), passwd.to_vec())
+        };
+
+        Ok(authorization)
+    }
+}
+
+impl<'a, R: WebRequest + 'a> WrappedVerification<'a, R> {
+    pub fn new(request: &'a mut R) -> Self {
+        Self::new_or_fail(request).unwrap_or_else(Self::from_err)
+    }
+
+    fn new_or_fail(request: &'a mut R) -> Result<Self, R::Error> {
+        Ok(WrappedVerification {
+            request: PhantomData,
+            query: request.query()?,
+            error: None,
+        })
+    }
+
+    fn from_err(err: R::Error) -> Self {
+        WrappedVerification {
+            request: PhantomData,
+            query: Cow::Owned(Default::default()),
+            error: Some(err),
+        }
+    }
+}
+
+impl<'a, R: WebRequest> DeviceRequest for WrappedRequest<'a, R> {
+    fn valid(&self) -> bool {
+        self.error.is_none()
+    }
+
+    fn authorization(&self) -> Option<(Cow<'_, str>, Cow<'_, [u8]>)> {
+        self.authorization
+            .as_ref()
+            .map(|auth| (auth.0.as_str().into(), auth.1.as_slice().into()))
+    }
+
+    fn client_id(&self) -> Option<Cow<'_, str>> {
+        self.body.unique_value("client_id")
+    }
+
+    fn scope(&self) -> Option<Cow<'_, str>> {
+        self.body.unique_value("scope")
+    }
+
+    fn extension(&self, key: &str) -> Option<Cow<'_, str>> {
+        self.body.unique_value(key)
+    }
+
+    fn allow_credentials_in_body(&self) -> bool {
+        self.allow_credentials_in_body
+    }
+}
+
+impl<'a, R: WebRequest> DeviceTokenRequest for WrappedRequest<'a, R> {
+    fn valid(&self) -> bool {
+        self.error.is_none()
+    }
+
+    fn authorization(&self) -> Option<(Cow<'_, str>, Cow<'_, [u8]>)> {
+        self.authorization
+            .as_ref()
+            .map(|auth| (auth.0.as_str().into(), auth.1.as_slice().into()))
+    }
+
+    fn client_id(&self) -> Option<Cow<'_, str>> {
+        self.body.unique_value("client_id")
+    }
+
+    fn device_code(&self) -> Option<Cow<'_, str>> {
+        self.body.unique_value("device_code")
+    }
+
+    fn grant_type(&self) -> Option<Cow<'_, str>> {
+        self.body.unique_value("grant_type")
+    }
+
+    fn extension(&self, key: &str) -> Option<Cow<'_, str>> {
+        self.body.unique_value(key)
+    }
+
+    fn allow_credentials_in_body(&self) -> bool {
+        self.allow_credentials_in_body
+    }
+}
+
+impl<'a, R: WebRequest> DeviceVerificationRequest for WrappedVerification<'a, R> {
+    fn valid(&self) -> bool {
+        self.error.is_none()
+    }
+
+    fn user_code(&self) -> Option<Cow<'_, str>> {
+        self.query.unique_value("user_code")
+    }
+}
+
+impl<E> From<Invalid> for FailParse<E> {
+    fn from(_: Invalid) -> Self {
+        FailParse::Invalid
+    }
+}
@@ -0,0 +1,45 @@
+use std::marker::PhantomData;
+
+use crate::primitives::jwk::JwkSet;
+
+use super::*;
+
+/// Serves the public keys verifying the tokens of the server as a JSON Web Key Set.
+///
+/// The document is usually published at `/.well-known/jwks.json` and advertised as the `jwks_uri`
+/// of the authorization server metadata, see RFC 7517. No primitive of the endpoint is used, the
+/// keys are taken from the configured `JwkSet`. Construct it for each request, for example with
+/// `JwkSet::from_keyring`, so that rotated keys are published immediately.
+pub struct JwksFlow<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    endpoint: E,
+    keys: JwkSet,
+    r_type: PhantomData<R>,
+}
+
+impl<E, R> JwksFlow<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    /// Publish the given keys.
+    pub fn new(endpoint: E, keys: JwkSet) -> Self {
+        JwksFlow {
+            endpoint,
+            keys,
+            r_type: PhantomData,
+        }
+    }
+
+    /// Respond with the key set as a json document.
+    pub fn execute(&mut self, mut request: R) -> Result<R::Response, E::Error> {
+        let mut response = self.endpoint.response(&mut request, InnerTemplate::Ok.into())?;
+        response
+            .body_json(&self.keys.to_json())
+            .map_err(|err| self.endpoint.web_error(err))?;
+        Ok(response)
+    }
+}
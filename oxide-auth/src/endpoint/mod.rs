@@ -30,9 +30,19 @@
 //! [`Registrar`]: ../../primitives/registrar/trait.Registrar.html
 mod authorization;
 mod accesstoken;
+mod client_credentials;
+mod device;
 mod error;
+mod introspection;
+mod jwks;
+mod jwt_bearer;
+mod metadata;
+mod password;
 mod refresh;
+mod registration;
 mod resource;
+mod revoke;
+mod token_exchange;
 mod query;
 
 #[cfg(test)]
@@ -41,9 +51,14 @@ mod tests;
 use std::borrow::Cow;
 use std::marker::PhantomData;
 
+pub use crate::primitives::authenticator::OwnerAuthenticator;
 pub use crate::primitives::authorizer::Authorizer;
+pub use crate::primitives::device::DeviceAuthorizer;
+pub use crate::primitives::dpop::DpopVerifier;
 pub use crate::primitives::issuer::Issuer;
-pub use crate::primitives::registrar::Registrar;
+pub use crate::primitives::jwt::AssertionVerifier;
+pub use crate::primitives::mtls::ClientCertificate;
+pub use crate::primitives::registrar::{Registrar, RegistrarMut};
 pub use crate::primitives::scope::Scope;
 
 use crate::code_grant::resource::{Error as ResourceError};
@@ -54,13 +69,24 @@ use url::Url;
 // Re-export the extension traits under prefixed names.
 pub use crate::code_grant::authorization::Extension as AuthorizationExtension;
 pub use crate::code_grant::accesstoken::Extension as AccessTokenExtension;
+pub use crate::code_grant::metadata::{Extension as MetadataExtension, Metadata};
 
 pub use crate::primitives::registrar::PreGrant;
 pub use self::authorization::*;
 pub use self::accesstoken::*;
+pub use self::client_credentials::ClientCredentialsFlow;
+pub use self::device::{DeviceAuthorizationFlow, DeviceTokenFlow, DeviceVerificationFlow};
+pub use self::jwks::JwksFlow;
+pub use self::jwt_bearer::JwtBearerFlow;
+pub use self::metadata::MetadataFlow;
+pub use self::password::PasswordFlow;
 pub use self::error::OAuthError;
+pub use self::introspection::IntrospectionFlow;
 pub use self::refresh::RefreshFlow;
+pub use self::registration::RegistrationFlow;
 pub use self::resource::*;
+pub use self::revoke::RevokeFlow;
+pub use self::token_exchange::TokenExchangeFlow;
 pub use self::query::*;
 
 /// Answer from OwnerAuthorizer to indicate the owners choice.
@@ -308,6 +334,39 @@ pub trait WebRequest {
     /// Contents of the authorization header or none if none exists. An Err value indicates a
     /// malformed header or request.
     fn authheader(&mut self) -> Result<Option<Cow<str>>, Self::Error>;
+
+    /// Contents of another header, looked up case-insensitively, or none if none exists.
+    ///
+    /// Used for headers of protocol extensions such as the `DPoP` proof. The default
+    /// implementation does not provide any headers.
+    fn header(&mut self, _name: &str) -> Result<Option<Cow<'_, str>>, Self::Error> {
+        Ok(None)
+    }
+
+    /// The method and the absolute uri of the request.
+    ///
+    /// Required to check that a DPoP proof was created for this request, the default
+    /// implementation does not provide them and all proofs are rejected.
+    fn target(&mut self) -> Result<Option<(Cow<'_, str>, Url)>, Self::Error> {
+        Ok(None)
+    }
+
+    /// The client certificate of a mutual-TLS connection.
+    ///
+    /// The certificate must already have been verified by the server or TLS terminator. Clients
+    /// without a passphrase then authenticate with it and issued tokens are bound to it. The
+    /// default implementation does not provide a certificate.
+    fn certificate(&mut self) -> Result<Option<Cow<'_, ClientCertificate>>, Self::Error> {
+        Ok(None)
+    }
+
+    /// The raw body of requests that carry a json document instead of a form.
+    ///
+    /// Required for the metadata of dynamic client registrations, the default implementation does
+    /// not provide a body and such requests are rejected.
+    fn body(&mut self) -> Result<Option<Cow<'_, str>>, Self::Error> {
+        Ok(None)
+    }
 }
 
 /// Response representation into which the Request is transformed by the code_grant types.
@@ -335,6 +394,20 @@ pub trait WebResponse {
 
     /// Json repsonse data, with media type `aplication/json.
     fn body_json(&mut self, data: &str) -> Result<(), Self::Error>;
+
+    /// Set the response status to 201, after a resource was created.
+    ///
+    /// The default implementation sets the status to 200 instead.
+    fn created(&mut self) -> Result<(), Self::Error> {
+        self.ok()
+    }
+
+    /// Set the response status to 204, for a successful response without a body.
+    ///
+    /// The default implementation sets the status to 200 instead.
+    fn no_content(&mut self) -> Result<(), Self::Error> {
+        self.ok()
+    }
 }
 
 /// Intermediate trait to flow specific extensions.
@@ -354,6 +427,11 @@ pub trait Extension {
     fn access_token(&mut self) -> Option<&mut dyn AccessTokenExtension> {
         None
     }
+
+    /// The handler for advertising extensions in the authorization server metadata.
+    fn metadata(&mut self) -> Option<&mut dyn MetadataExtension> {
+        None
+    }
 }
 
 /// Fuses requests and primitives into a coherent system to give a response.
@@ -384,6 +462,14 @@ pub trait Endpoint<Request: WebRequest> {
     /// have any effect on flows that do not require one.
     fn registrar(&self) -> Option<&dyn Registrar>;
 
+    /// A registrar to which clients can register themselves if this endpoint can access one.
+    ///
+    /// Returning `None` is the default implementation and will fail the dynamic client
+    /// registration flow but does not have any effect on other flows.
+    fn registrar_mut(&mut self) -> Option<&mut dyn RegistrarMut> {
+        None
+    }
+
     /// An authorizer if this endpoint can access one.
     ///
     /// Returning `None` will implicate failing any flow that requires an authorizer but does not
@@ -408,6 +494,38 @@ pub trait Endpoint<Request: WebRequest> {
     /// request.
     fn scopes(&mut self) -> Option<&mut dyn Scopes<Request>>;
 
+    /// An authenticator for the credentials of resource owners.
+    ///
+    /// Returning `None` is the default implementation and will fail the resource owner password
+    /// credentials flow but does not have any effect on other flows.
+    fn owner_authenticator(&self) -> Option<&dyn OwnerAuthenticator> {
+        None
+    }
+
+    /// A device authorizer if this endpoint can access one.
+    ///
+    /// Returning `None` is the default implementation and will fail the flows of the device
+    /// authorization grant but does not have any effect on other flows.
+    fn device_authorizer_mut(&mut self) -> Option<&mut dyn DeviceAuthorizer> {
+        None
+    }
+
+    /// A verifier for the assertions of trusted issuers if this endpoint can access one.
+    ///
+    /// Returning `None` is the default implementation and will fail the JWT bearer flow but does
+    /// not have any effect on other flows.
+    fn assertion_verifier(&self) -> Option<&dyn AssertionVerifier> {
+        None
+    }
+
+    /// A verifier for DPoP proofs if this endpoint can access one.
+    ///
+    /// Returning `None` is the default implementation. Proofs are then ignored and only bearer
+    /// tokens are issued, while resources reject tokens presented with the `DPoP` scheme.
+    fn dpop_verifier(&self) -> Option<&dyn DpopVerifier> {
+        None
+    }
+
     /// Generate a prototype response.
     ///
     /// The endpoint can rely on this being called at most once for each flow, if it wants
@@ -519,6 +637,41 @@ impl<'a> Template<'a> {
             _ => None,
         }
     }
+
+    /// The cause for denying access to a resource.
+    ///
+    /// The `WWW-Authenticate` header of the response is derived from this error.
+    pub fn resource_error(&self) -> Option<&ResourceError> {
+        match &self.inner {
+            InnerTemplate::Unauthorized { error, .. } => error.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// Verify the DPoP proof of a request, if it contains one and the endpoint has a verifier.
+///
+/// The outer error is a web error of the request. The inner error indicates an invalid proof,
+/// including proofs of requests that do not describe their target.
+fn dpop_proof<R: WebRequest>(
+    verifier: Option<&dyn DpopVerifier>, request: &mut R, access_token: Option<&str>,
+) -> Result<Result<Option<String>, ()>, R::Error> {
+    let verifier = match verifier {
+        Some(verifier) => verifier,
+        None => return Ok(Ok(None)),
+    };
+
+    let proof = match request.header("DPoP")? {
+        Some(proof) => proof.into_owned(),
+        None => return Ok(Ok(None)),
+    };
+
+    let thumbprint = match request.target()? {
+        Some((method, uri)) => verifier.verify(&proof, &method, &uri, access_token),
+        None => None,
+    };
+
+    Ok(thumbprint.map(Some).ok_or(()))
 }
 
 /// Reborrow contained optional reference.
@@ -547,6 +700,22 @@ impl<'a, W: WebRequest> WebRequest for &'a mut W {
     fn authheader(&mut self) -> Result<Option<Cow<str>>, Self::Error> {
         (**self).authheader()
     }
+
+    fn header(&mut self, name: &str) -> Result<Option<Cow<'_, str>>, Self::Error> {
+        (**self).header(name)
+    }
+
+    fn target(&mut self) -> Result<Option<(Cow<'_, str>, Url)>, Self::Error> {
+        (**self).target()
+    }
+
+    fn certificate(&mut self) -> Result<Option<Cow<'_, ClientCertificate>>, Self::Error> {
+        (**self).certificate()
+    }
+
+    fn body(&mut self) -> Result<Option<Cow<'_, str>>, Self::Error> {
+        (**self).body()
+    }
 }
 
 impl<'a, R: WebRequest, E: Endpoint<R>> Endpoint<R> for &'a mut E {
@@ -556,6 +725,10 @@ impl<'a, R: WebRequest, E: Endpoint<R>> Endpoint<R> for &'a mut E {
         (**self).registrar()
     }
 
+    fn registrar_mut(&mut self) -> Option<&mut dyn RegistrarMut> {
+        (**self).registrar_mut()
+    }
+
     fn authorizer_mut(&mut self) -> Option<&mut dyn Authorizer> {
         (**self).authorizer_mut()
     }
@@ -572,6 +745,22 @@ impl<'a, R: WebRequest, E: Endpoint<R>> Endpoint<R> for &'a mut E {
         (**self).scopes()
     }
 
+    fn owner_authenticator(&self) -> Option<&dyn OwnerAuthenticator> {
+        (**self).owner_authenticator()
+    }
+
+    fn device_authorizer_mut(&mut self) -> Option<&mut dyn DeviceAuthorizer> {
+        (**self).device_authorizer_mut()
+    }
+
+    fn assertion_verifier(&self) -> Option<&dyn AssertionVerifier> {
+        (**self).assertion_verifier()
+    }
+
+    fn dpop_verifier(&self) -> Option<&dyn DpopVerifier> {
+        (**self).dpop_verifier()
+    }
+
     fn response(&mut self, request: &mut R, kind: Template) -> Result<R::Response, Self::Error> {
         (**self).response(request, kind)
     }
@@ -596,6 +785,10 @@ impl<'a, R: WebRequest, E: Endpoint<R> + 'a> Endpoint<R> for Box<E> {
         (**self).registrar()
     }
 
+    fn registrar_mut(&mut self) -> Option<&mut dyn RegistrarMut> {
+        (**self).registrar_mut()
+    }
+
     fn authorizer_mut(&mut self) -> Option<&mut dyn Authorizer> {
         (**self).authorizer_mut()
     }
@@ -612,6 +805,22 @@ impl<'a, R: WebRequest, E: Endpoint<R> + 'a> Endpoint<R> for Box<E> {
         (**self).scopes()
     }
 
+    fn owner_authenticator(&self) -> Option<&dyn OwnerAuthenticator> {
+        (**self).owner_authenticator()
+    }
+
+    fn device_authorizer_mut(&mut self) -> Option<&mut dyn DeviceAuthorizer> {
+        (**self).device_authorizer_mut()
+    }
+
+    fn assertion_verifier(&self) -> Option<&dyn AssertionVerifier> {
+        (**self).assertion_verifier()
+    }
+
+    fn dpop_verifier(&self) -> Option<&dyn DpopVerifier> {
+        (**self).dpop_verifier()
+    }
+
     fn response(&mut self, request: &mut R, kind: Template) -> Result<R::Response, Self::Error> {
         (**self).response(request, kind)
     }
@@ -4,7 +4,9 @@ use std::str::from_utf8;
 
 use crate::code_grant::refresh::{refresh, Error, Endpoint as RefreshEndpoint, Request};
 use crate::primitives::{registrar::Registrar, issuer::Issuer};
-use super::{Endpoint, InnerTemplate, OAuthError, QueryParameter, WebRequest, WebResponse};
+use super::{
+    ClientCertificate, Endpoint, InnerTemplate, OAuthError, QueryParameter, WebRequest, WebResponse,
+};
 
 /// Takes requests from clients to refresh their access tokens.
 pub struct RefreshFlow<E, R>
@@ -32,6 +34,9 @@ struct WrappedRequest<'a, R: WebRequest + 'a> {
 
     /// An error if one occurred.
     error: Option<InitError<R::Error>>,
+
+    /// The client certificate of the connection.
+    certificate: Option<ClientCertificate>,
 }
 
 enum InitError<E> {
@@ -156,11 +161,17 @@ impl<'a, R: WebRequest + 'a> WrappedRequest<'a, R> {
             Ok(None) => None,
         };
 
+        let certificate = request
+            .certificate()
+            .map_err(InitError::Internal)?
+            .map(Cow::into_owned);
+
         Ok(WrappedRequest {
             request: PhantomData,
             body: request.urlbody().map_err(InitError::Internal)?,
             authorization,
             error: None,
+            certificate,
         })
     }
 
@@ -170,6 +181,7 @@ impl<'a, R: WebRequest + 'a> WrappedRequest<'a, R> {
             body: Cow::Owned(Default::default()),
             authorization: None,
             error: Some(err),
+            certificate: None,
         }
     }
 
@@ -242,4 +254,8 @@ impl<'a, R: WebRequest> Request for WrappedRequest<'a, R> {
     fn extension(&self, key: &str) -> Option<Cow<str>> {
         self.body.unique_value(key)
     }
+
+    fn certificate(&self) -> Option<Cow<'_, ClientCertificate>> {
+        self.certificate.as_ref().map(Cow::Borrowed)
+    }
 }
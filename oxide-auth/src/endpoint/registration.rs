@@ -0,0 +1,247 @@
+use std::marker::PhantomData;
+
+use crate::code_grant::registration::{
+    delete, read, register, update, ClientInformation, Endpoint as RegistrationEndpoint,
+    Request as RegistrationRequest,
+};
+
+use super::*;
+
+/// Registers clients dynamically and lets them manage their registration.
+///
+/// Clients post their metadata as json to the registration endpoint and receive their client id, a
+/// secret if they authenticate with one, and a registration access token. Their registration is
+/// afterwards available at the client configuration uri, the registration endpoint with the
+/// `client_id` in its query. There, requests authenticated with the registration access token as a
+/// bearer token can read, update and delete the registration. See RFC 7591 and RFC 7592. These
+/// requests MUST be protected by TLS.
+///
+/// The frontend dispatches the requests by their method: `POST` to the registration endpoint is a
+/// [`register`] request while `GET`, `PUT` and `DELETE` at the client configuration uri map to
+/// [`read`], [`update`] and [`delete`] respectively.
+///
+/// Registration is open to anyone able to reach the endpoint. Clients can not register for the
+/// resource owner password credentials grant and may only ask for a subset of the scope allowed
+/// by [`set_scope`], which is empty by default.
+///
+/// [`register`]: #method.register
+/// [`read`]: #method.read
+/// [`update`]: #method.update
+/// [`delete`]: #method.delete
+/// [`set_scope`]: #method.set_scope
+pub struct RegistrationFlow<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    endpoint: WrappedRegistration<E, R>,
+    registration_endpoint: Url,
+}
+
+struct WrappedRegistration<E: Endpoint<R>, R: WebRequest> {
+    inner: E,
+    scope: Scope,
+    r_type: PhantomData<R>,
+}
+
+struct WrappedRequest<R: WebRequest> {
+    /// Original request.
+    request: PhantomData<R>,
+
+    /// The json body of the request.
+    body: Option<String>,
+
+    /// The client id of the client configuration uri.
+    client_id: Option<String>,
+
+    /// The token of a bearer authorization header.
+    token: Option<String>,
+
+    /// An error if one occurred.
+    error: Option<R::Error>,
+}
+
+impl<E, R> RegistrationFlow<E, R>
+where
+    E: Endpoint<R>,
+    R: WebRequest,
+{
+    /// Check that the endpoint supports the necessary operations for handling requests.
+    ///
+    /// Binds the endpoint to a particular type of request that it supports, for many
+    /// implementations this is probably single type anyways. The endpoint needs to provide
+    /// (return `Some`):
+    ///
+    /// * a `RegistrarMut` from `registrar_mut`
+    ///
+    /// The `registration_endpoint` is the absolute uri of the registration endpoint, from which the
+    /// client configuration uris are derived.
+    ///
+    /// ## Panics
+    ///
+    /// Indirectly the operations may panic when this flow is instantiated with an inconsistent
+    /// endpoint, for details see the documentation of `Endpoint`. For consistent endpoints, the
+    /// panic is instead caught as an error here.
+    pub fn prepare(mut endpoint: E, registration_endpoint: Url) -> Result<Self, E::Error> {
+        if endpoint.registrar_mut().is_none() {
+            return Err(endpoint.error(OAuthError::PrimitiveError));
+        }
+
+        Ok(RegistrationFlow {
+            endpoint: WrappedRegistration {
+                inner: endpoint,
+                scope: "".parse().unwrap(),
+                r_type: PhantomData,
+            },
+            registration_endpoint,
+        })
+    }
+
+    /// Set the scope that clients may register for.
+    ///
+    /// Clients that do not ask for a scope are registered with this scope as their default scope.
+    pub fn set_scope(&mut self, scope: Scope) {
+        self.endpoint.scope = scope;
+    }
+
+    /// Register a new client with the metadata in the body of the request.
+    ///
+    /// ## Panics
+    ///
+    /// When the registrar returned by the endpoint is suddenly `None` when previously it was
+    /// `Some(_)`.
+    pub fn register(&mut self, mut request: R) -> Result<R::Response, E::Error> {
+        let registered = register(&mut self.endpoint, &WrappedRequest::new(&mut request));
+        match registered {
+            Err(error) => token_error(&mut self.endpoint.inner, &mut request, error),
+            Ok(information) => self.information(request, information, true),
+        }
+    }
+
+    /// Read the registration of the client of the client configuration uri.
+    ///
+    /// ## Panics
+    ///
+    /// When the registrar returned by the endpoint is suddenly `None` when previously it was
+    /// `Some(_)`.
+    pub fn read(&mut self, mut request: R) -> Result<R::Response, E::Error> {
+        let information = read(&mut self.endpoint, &WrappedRequest::new(&mut request));
+        match information {
+            Err(error) => token_error(&mut self.endpoint.inner, &mut request, error),
+            Ok(information) => self.information(request, information, false),
+        }
+    }
+
+    /// Replace the registration of the client with the metadata in the body of the request.
+    ///
+    /// ## Panics
+    ///
+    /// When the registrar returned by the endpoint is suddenly `None` when previously it was
+    /// `Some(_)`.
+    pub fn update(&mut self, mut request: R) -> Result<R::Response, E::Error> {
+        let updated = update(&mut self.endpoint, &WrappedRequest::new(&mut request));
+        match updated {
+            Err(error) => token_error(&mut self.endpoint.inner, &mut request, error),
+            Ok(information) => self.information(request, information, false),
+        }
+    }
+
+    /// Delete the registration of the client of the client configuration uri.
+    ///
+    /// ## Panics
+    ///
+    /// When the registrar returned by the endpoint is suddenly `None` when previously it was
+    /// `Some(_)`.
+    pub fn delete(&mut self, mut request: R) -> Result<R::Response, E::Error> {
+        let deleted = delete(&mut self.endpoint, &WrappedRequest::new(&mut request));
+
+        if let Err(error) = deleted {
+            return token_error(&mut self.endpoint.inner, &mut request, error);
+        }
+
+        let mut response = self
+            .endpoint
+            .inner
+            .response(&mut request, InnerTemplate::Ok.into())?;
+        response
+            .no_content()
+            .map_err(|err| self.endpoint.inner.web_error(err))?;
+        Ok(response)
+    }
+
+    fn information(
+        &mut self, mut request: R, information: ClientInformation, created: bool,
+    ) -> Result<R::Response, E::Error> {
+        let mut response = self
+            .endpoint
+            .inner
+            .response(&mut request, InnerTemplate::Ok.into())?;
+        match created {
+            true => response.created(),
+            false => response.ok(),
+        }
+        .map_err(|err| self.endpoint.inner.web_error(err))?;
+        response
+            .body_json(&information.to_json(&self.registration_endpoint))
+            .map_err(|err| self.endpoint.inner.web_error(err))?;
+        Ok(response)
+    }
+}
+
+impl<E: Endpoint<R>, R: WebRequest> RegistrationEndpoint for WrappedRegistration<E, R> {
+    fn registrar(&mut self) -> &mut dyn RegistrarMut {
+        self.inner.registrar_mut().unwrap()
+    }
+
+    fn scope(&self) -> &Scope {
+        &self.scope
+    }
+}
+
+impl<R: WebRequest> WrappedRequest<R> {
+    pub fn new(request: &mut R) -> Self {
+        Self::new_or_fail(request).unwrap_or_else(Self::from_err)
+    }
+
+    fn new_or_fail(request: &mut R) -> Result<Self, R::Error> {
+        let token = request
+            .authheader()?
+            .and_then(|header| header.strip_prefix("Bearer ").map(str::to_string));
+
+        Ok(WrappedRequest {
+            request: PhantomData,
+            body: request.body()?.map(Cow::into_owned),
+            client_id: request.query()?.unique_value("client_id").map(Cow::into_owned),
+            token,
+            error: None,
+        })
+    }
+
+    fn from_err(err: R::Error) -> Self {
+        WrappedRequest {
+            request: PhantomData,
+            body: None,
+            client_id: None,
+            token: None,
+            error: Some(err),
+        }
+    }
+}
+
+impl<R: WebRequest> RegistrationRequest for WrappedRequest<R> {
+    fn valid(&self) -> bool {
+        self.error.is_none()
+    }
+
+    fn metadata(&self) -> Option<Cow<'_, str>> {
+        self.body.as_deref().map(Cow::Borrowed)
+    }
+
+    fn registration_access_token(&self) -> Option<Cow<'_, str>> {
+        self.token.as_deref().map(Cow::Borrowed)
+    }
+
+    fn client_id(&self) -> Option<Cow<'_, str>> {
+        self.client_id.as_deref().map(Cow::Borrowed)
+    }
+}
@@ -1,7 +1,7 @@
 use crate::primitives::authorizer::{AuthMap, Authorizer};
 use crate::primitives::issuer::TokenMap;
 use crate::primitives::grant::{Grant, Extensions};
-use crate::primitives::registrar::{Client, ClientMap, RegisteredUrl};
+use crate::primitives::registrar::{Client, ClientMap, ClientMetadata, RegisteredUrl};
 
 use crate::frontends::simple::endpoint::access_token_flow;
 
@@ -118,6 +118,21 @@ impl AccessTokenSetup {
         }
     }
 
+    fn test_error(&mut self, request: CraftedRequest, error: &str) {
+        let response = access_token_flow(&self.registrar, &mut self.authorizer, &mut self.issuer)
+            .execute(request)
+            .expect("Expected non-error reponse");
+
+        Self::assert_json_error_set(&response);
+        match response.body {
+            Some(Body::Json(ref json)) => {
+                let content: HashMap<String, String> = serde_json::from_str(json).unwrap();
+                assert_eq!(content.get("error").map(String::as_str), Some(error));
+            }
+            other => panic!("Expected json encoded body, got {:?}", other),
+        }
+    }
+
     fn test_success(&mut self, request: CraftedRequest) {
         let response = access_token_flow(&self.registrar, &mut self.authorizer, &mut self.issuer)
             .execute(request)
@@ -597,3 +612,35 @@ fn private_duplicate_authentication() {
 
     setup.test_simple_error(valid_public);
 }
+
+#[test]
+fn access_request_grant_type_not_permitted() {
+    let mut setup = AccessTokenSetup::private_client();
+    let client = Client::confidential(
+        EXAMPLE_CLIENT_ID,
+        RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+        EXAMPLE_SCOPE.parse().unwrap(),
+        EXAMPLE_PASSPHRASE.as_bytes(),
+    )
+    .with_metadata(ClientMetadata {
+        grant_types: vec!["client_credentials".to_string()],
+        ..ClientMetadata::default()
+    });
+    setup.registrar.register_client(client);
+
+    let request = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![
+                ("grant_type", "authorization_code"),
+                ("code", &setup.authtoken),
+                ("redirect_uri", EXAMPLE_REDIRECT_URI),
+            ]
+            .iter()
+            .to_single_value_query(),
+        ),
+        auth: Some("Basic ".to_string() + &setup.basic_authorization),
+    };
+
+    setup.test_error(request, "unauthorized_client");
+}
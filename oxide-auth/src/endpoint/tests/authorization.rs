@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use crate::primitives::authorizer::AuthMap;
-use crate::primitives::registrar::{Client, ClientMap, RegisteredUrl};
+use crate::primitives::registrar::{Client, ClientMap, ClientMetadata, RegisteredUrl};
 
 use crate::endpoint::{OwnerSolicitor};
 
@@ -292,3 +292,48 @@ fn auth_request_error_malformed_scope() {
 
     AuthorizationSetup::new().test_error_redirect(malformed_scope, Allow(EXAMPLE_OWNER_ID.to_string()));
 }
+
+#[test]
+fn auth_request_error_response_type_not_permitted() {
+    let mut setup = AuthorizationSetup::new();
+    let client = Client::confidential(
+        EXAMPLE_CLIENT_ID,
+        RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+        EXAMPLE_SCOPE.parse().unwrap(),
+        EXAMPLE_PASSPHRASE.as_bytes(),
+    )
+    .with_metadata(ClientMetadata {
+        response_types: vec!["token".to_string()],
+        ..ClientMetadata::default()
+    });
+    setup.registrar.register_client(client);
+
+    let request = CraftedRequest {
+        query: Some(
+            vec![
+                ("response_type", "code"),
+                ("client_id", EXAMPLE_CLIENT_ID),
+                ("redirect_uri", EXAMPLE_REDIRECT_URI),
+            ]
+            .iter()
+            .to_single_value_query(),
+        ),
+        urlbody: None,
+        auth: None,
+    };
+
+    let response = authorization_flow(
+        &mut setup.registrar,
+        &mut setup.authorizer,
+        &mut Allow(EXAMPLE_OWNER_ID.to_string()),
+    )
+    .execute(request)
+    .expect("Expected redirect with error set");
+
+    let location = response.location.expect("Expected a redirect");
+    let query = location.query_pairs().collect::<HashMap<_, _>>();
+    assert_eq!(
+        query.get("error").map(|e| e.as_ref()),
+        Some("unauthorized_client")
+    );
+}
@@ -0,0 +1,381 @@
+use crate::code_grant::accesstoken::TokenResponse;
+use crate::primitives::issuer::{Issuer, TokenMap};
+use crate::primitives::registrar::{Client, ClientMap, ClientMetadata, RegisteredUrl, ScopePolicy};
+use crate::primitives::scope::Scope;
+
+use crate::frontends::simple::endpoint::client_credentials_flow;
+
+use std::collections::HashMap;
+
+use base64;
+use serde_json;
+
+use super::{Body, CraftedRequest, CraftedResponse, Status, TestGenerator, ToSingleValueQuery};
+use super::defaults::*;
+
+struct ClientCredentialsSetup {
+    registrar: ClientMap,
+    issuer: TokenMap<TestGenerator>,
+    basic_authorization: String,
+}
+
+impl ClientCredentialsSetup {
+    fn private_client() -> Self {
+        let mut registrar = ClientMap::new();
+        let issuer = TokenMap::new(TestGenerator("AccessToken".to_string()));
+
+        let client = Client::confidential(
+            EXAMPLE_CLIENT_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+            EXAMPLE_PASSPHRASE.as_bytes(),
+        );
+        registrar.register_client(client);
+
+        let basic_authorization =
+            base64::encode(&format!("{}:{}", EXAMPLE_CLIENT_ID, EXAMPLE_PASSPHRASE));
+
+        ClientCredentialsSetup {
+            registrar,
+            issuer,
+            basic_authorization,
+        }
+    }
+
+    fn public_client() -> Self {
+        let mut registrar = ClientMap::new();
+        let issuer = TokenMap::new(TestGenerator("AccessToken".to_string()));
+
+        let client = Client::public(
+            EXAMPLE_CLIENT_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+        );
+        registrar.register_client(client);
+
+        let basic_authorization =
+            base64::encode(&format!("{}:{}", EXAMPLE_CLIENT_ID, EXAMPLE_PASSPHRASE));
+
+        ClientCredentialsSetup {
+            registrar,
+            issuer,
+            basic_authorization,
+        }
+    }
+
+    fn assert_json_error_set(response: &CraftedResponse) {
+        match &response.body {
+            Some(Body::Json(ref json)) => {
+                let content: HashMap<String, String> = serde_json::from_str(json).unwrap();
+                assert!(content.get("error").is_some(), "Error not set in json response");
+            }
+            other => panic!("Expected json encoded body, got {:?}", other),
+        }
+
+        match response.status {
+            Status::Unauthorized => (),
+            Status::BadRequest => (),
+            _ => panic!("Expected error status, got {:?}", response),
+        }
+    }
+
+    fn test_simple_error(&mut self, request: CraftedRequest) {
+        match client_credentials_flow(&self.registrar, &mut self.issuer).execute(request) {
+            Ok(ref response) => Self::assert_json_error_set(response),
+            resp => panic!("Expected non-error reponse, got {:?}", resp),
+        }
+    }
+
+    fn test_success(&mut self, request: CraftedRequest) -> TokenResponse {
+        let response = client_credentials_flow(&self.registrar, &mut self.issuer)
+            .execute(request)
+            .expect("Expected non-error reponse");
+
+        self.assert_ok_access_token(response)
+    }
+
+    fn test_success_body_credentials(&mut self, request: CraftedRequest) -> TokenResponse {
+        let mut flow = client_credentials_flow(&self.registrar, &mut self.issuer);
+        flow.allow_credentials_in_body(true);
+        let response = flow.execute(request).expect("Expected non-error response");
+        self.assert_ok_access_token(response)
+    }
+
+    fn assert_ok_access_token(&mut self, response: CraftedResponse) -> TokenResponse {
+        assert_eq!(response.status, Status::Ok);
+        let token = match response.body {
+            Some(Body::Json(ref json)) => serde_json::from_str::<TokenResponse>(json).unwrap(),
+            other => panic!("Expected json encoded body, got {:?}", other),
+        };
+
+        assert!(
+            token.refresh_token.is_none(),
+            "Client credentials must not offer refresh"
+        );
+        let access_token = token.access_token.as_ref().expect("Expected an access token");
+        let grant = self
+            .issuer
+            .recover_token(access_token)
+            .unwrap()
+            .expect("Issued token should be recoverable");
+        assert_eq!(grant.client_id, EXAMPLE_CLIENT_ID);
+        assert_eq!(grant.owner_id, EXAMPLE_CLIENT_ID);
+        token
+    }
+}
+
+#[test]
+fn client_credentials_success() {
+    let mut setup = ClientCredentialsSetup::private_client();
+
+    let valid = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![("grant_type", "client_credentials")]
+                .iter()
+                .to_single_value_query(),
+        ),
+        auth: Some("Basic ".to_string() + &setup.basic_authorization),
+    };
+
+    let token = setup.test_success(valid);
+    let scope: Option<Scope> = token.scope.as_ref().map(|scope| scope.parse().unwrap());
+    assert_eq!(scope, EXAMPLE_SCOPE.parse().ok());
+}
+
+#[test]
+fn client_credentials_success_with_scope() {
+    let mut setup = ClientCredentialsSetup::private_client();
+
+    let valid = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![("grant_type", "client_credentials"), ("scope", "example")]
+                .iter()
+                .to_single_value_query(),
+        ),
+        auth: Some("Basic ".to_string() + &setup.basic_authorization),
+    };
+
+    let token = setup.test_success(valid);
+    let scope: Option<Scope> = token.scope.as_ref().map(|scope| scope.parse().unwrap());
+    assert_eq!(scope, "example".parse().ok());
+}
+
+#[test]
+fn client_credentials_downscoped() {
+    let mut setup = ClientCredentialsSetup::private_client();
+
+    // The token response reports the narrower scope that was actually granted.
+    let larger = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![("grant_type", "client_credentials"), ("scope", "example admin")]
+                .iter()
+                .to_single_value_query(),
+        ),
+        auth: Some("Basic ".to_string() + &setup.basic_authorization),
+    };
+
+    let token = setup.test_success(larger);
+    let scope: Option<Scope> = token.scope.as_ref().map(|scope| scope.parse().unwrap());
+    assert_eq!(scope, "example".parse().ok());
+
+    let disjoint = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![("grant_type", "client_credentials"), ("scope", "admin")]
+                .iter()
+                .to_single_value_query(),
+        ),
+        auth: Some("Basic ".to_string() + &setup.basic_authorization),
+    };
+
+    setup.test_simple_error(disjoint);
+}
+
+#[test]
+fn client_credentials_strict_scope() {
+    let mut setup = ClientCredentialsSetup::private_client();
+    setup.registrar.set_scope_policy(ScopePolicy::Strict);
+
+    let larger = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![("grant_type", "client_credentials"), ("scope", "example admin")]
+                .iter()
+                .to_single_value_query(),
+        ),
+        auth: Some("Basic ".to_string() + &setup.basic_authorization),
+    };
+
+    setup.test_simple_error(larger);
+}
+
+#[test]
+fn client_credentials_body_credentials() {
+    let mut setup = ClientCredentialsSetup::private_client();
+
+    let valid = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![
+                ("grant_type", "client_credentials"),
+                ("client_id", EXAMPLE_CLIENT_ID),
+                ("client_secret", EXAMPLE_PASSPHRASE),
+            ]
+            .iter()
+            .to_single_value_query(),
+        ),
+        auth: None,
+    };
+
+    setup.test_success_body_credentials(valid.clone());
+    // Not allowed unless explicitely enabled.
+    setup.test_simple_error(valid);
+}
+
+#[test]
+fn client_credentials_public_client() {
+    let mut setup = ClientCredentialsSetup::public_client();
+
+    // Public clients can not authenticate and must not use this grant.
+    let public_client = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![
+                ("grant_type", "client_credentials"),
+                ("client_id", EXAMPLE_CLIENT_ID),
+            ]
+            .iter()
+            .to_single_value_query(),
+        ),
+        auth: None,
+    };
+
+    setup.test_simple_error(public_client);
+
+    let public_authorization = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![("grant_type", "client_credentials")]
+                .iter()
+                .to_single_value_query(),
+        ),
+        auth: Some("Basic ".to_string() + &setup.basic_authorization),
+    };
+
+    setup.test_simple_error(public_authorization);
+}
+
+#[test]
+fn client_credentials_wrong_password() {
+    let mut setup = ClientCredentialsSetup::private_client();
+
+    let wrong_password = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![("grant_type", "client_credentials")]
+                .iter()
+                .to_single_value_query(),
+        ),
+        auth: Some(
+            "Basic ".to_string()
+                + &base64::encode(&format!("{}:{}", EXAMPLE_CLIENT_ID, "NotTheRightPassphrase")),
+        ),
+    };
+
+    setup.test_simple_error(wrong_password);
+}
+
+#[test]
+fn client_credentials_missing_authentication() {
+    let mut setup = ClientCredentialsSetup::private_client();
+
+    let missing_authentication = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![("grant_type", "client_credentials")]
+                .iter()
+                .to_single_value_query(),
+        ),
+        auth: None,
+    };
+
+    setup.test_simple_error(missing_authentication);
+}
+
+#[test]
+fn client_credentials_wrong_grant_type() {
+    let mut setup = ClientCredentialsSetup::private_client();
+
+    let wrong_grant_type = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![("grant_type", "authorization_code")]
+                .iter()
+                .to_single_value_query(),
+        ),
+        auth: Some("Basic ".to_string() + &setup.basic_authorization),
+    };
+
+    setup.test_simple_error(wrong_grant_type);
+}
+
+#[test]
+fn client_credentials_invalid_scope() {
+    let mut setup = ClientCredentialsSetup::private_client();
+
+    let invalid_scope = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![("grant_type", "client_credentials"), ("scope", "\"no quotes\"")]
+                .iter()
+                .to_single_value_query(),
+        ),
+        auth: Some("Basic ".to_string() + &setup.basic_authorization),
+    };
+
+    setup.test_simple_error(invalid_scope);
+}
+
+#[test]
+fn client_credentials_grant_type_not_permitted() {
+    let mut setup = ClientCredentialsSetup::private_client();
+    let client = Client::confidential(
+        EXAMPLE_CLIENT_ID,
+        RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+        EXAMPLE_SCOPE.parse().unwrap(),
+        EXAMPLE_PASSPHRASE.as_bytes(),
+    )
+    .with_metadata(ClientMetadata {
+        grant_types: vec!["authorization_code".to_string()],
+        ..ClientMetadata::default()
+    });
+    setup.registrar.register_client(client);
+
+    let request = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![("grant_type", "client_credentials")]
+                .iter()
+                .to_single_value_query(),
+        ),
+        auth: Some("Basic ".to_string() + &setup.basic_authorization),
+    };
+
+    let response = client_credentials_flow(&setup.registrar, &mut setup.issuer)
+        .execute(request)
+        .expect("Expected non-error reponse");
+    ClientCredentialsSetup::assert_json_error_set(&response);
+    match response.body {
+        Some(Body::Json(ref json)) => {
+            let content: HashMap<String, String> = serde_json::from_str(json).unwrap();
+            assert_eq!(
+                content.get("error").map(String::as_str),
+                Some("unauthorized_client")
+            );
+        }
+        other => panic!("Expected json encoded body, got {:?}", other),
+    }
+}
@@ -0,0 +1,339 @@
+use crate::code_grant::accesstoken::TokenResponse;
+use crate::code_grant::device::{DeviceAuthorizationResponse, DEVICE_CODE_GRANT_TYPE};
+use crate::primitives::device::{DeviceAuthorizer, DeviceCodes, DeviceMap, DeviceStatus};
+use crate::primitives::grant::Grant;
+use crate::primitives::issuer::{Issuer, TokenMap};
+use crate::primitives::registrar::{Client, ClientMap, ClientMetadata, RegisteredUrl};
+use crate::primitives::scope::Scope;
+
+use crate::frontends::simple::endpoint::{
+    device_authorization_flow, device_token_flow, device_verification_flow,
+};
+
+use std::collections::HashMap;
+
+use serde_json;
+use url::Url;
+
+use super::{Allow, Body, CraftedRequest, CraftedResponse, Deny, Status, TestGenerator, ToSingleValueQuery};
+use super::defaults::*;
+
+const EXAMPLE_VERIFICATION_URI: &str = "https://example.com/device";
+
+struct DeviceSetup {
+    registrar: ClientMap,
+    devices: DeviceMap<TestGenerator>,
+    issuer: TokenMap<TestGenerator>,
+}
+
+/// A device authorizer whose device codes have always expired.
+struct Expired;
+
+impl DeviceAuthorizer for Expired {
+    fn authorize(&mut self, _: Grant) -> Result<DeviceCodes, ()> {
+        Err(())
+    }
+
+    fn pending(&mut self, _: &str) -> Result<Option<Grant>, ()> {
+        Ok(None)
+    }
+
+    fn approve(&mut self, _: &str, _: &str) -> Result<(), ()> {
+        Err(())
+    }
+
+    fn deny(&mut self, _: &str) -> Result<(), ()> {
+        Err(())
+    }
+
+    fn poll(&mut self, _: &str) -> Result<Option<DeviceStatus>, ()> {
+        Ok(Some(DeviceStatus::Expired))
+    }
+}
+
+impl DeviceSetup {
+    fn new() -> Self {
+        let mut registrar = ClientMap::new();
+        registrar.register_client(Client::public(
+            EXAMPLE_CLIENT_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+        ));
+
+        DeviceSetup {
+            registrar,
+            devices: DeviceMap::new(TestGenerator("DeviceCode".to_string())),
+            issuer: TokenMap::new(TestGenerator("AccessToken".to_string())),
+        }
+    }
+
+    fn authorize(&mut self) -> DeviceAuthorizationResponse {
+        let request = CraftedRequest {
+            query: None,
+            urlbody: Some(
+                vec![("client_id", EXAMPLE_CLIENT_ID)]
+                    .iter()
+                    .to_single_value_query(),
+            ),
+            auth: None,
+        };
+
+        let verification_uri: Url = EXAMPLE_VERIFICATION_URI.parse().unwrap();
+        let response = device_authorization_flow(&self.registrar, &mut self.devices, verification_uri)
+            .execute(request)
+            .expect("Expected non-error response");
+
+        assert_eq!(response.status, Status::Ok);
+        match response.body {
+            Some(Body::Json(ref json)) => serde_json::from_str(json).unwrap(),
+            other => panic!("Expected json encoded body, got {:?}", other),
+        }
+    }
+
+    fn verify(&mut self, user_code: &str, allow: bool) -> CraftedResponse {
+        let request = CraftedRequest {
+            query: Some(vec![("user_code", user_code)].iter().to_single_value_query()),
+            urlbody: None,
+            auth: None,
+        };
+
+        let response = if allow {
+            device_verification_flow(&mut self.devices, &mut Allow(EXAMPLE_OWNER_ID.to_string()))
+                .execute(request)
+        } else {
+            device_verification_flow(&mut self.devices, &mut Deny).execute(request)
+        };
+
+        response.expect("Expected non-error response")
+    }
+
+    fn poll_request(device_code: &str) -> CraftedRequest {
+        CraftedRequest {
+            query: None,
+            urlbody: Some(
+                vec![
+                    ("grant_type", DEVICE_CODE_GRANT_TYPE),
+                    ("device_code", device_code),
+                    ("client_id", EXAMPLE_CLIENT_ID),
+                ]
+                .iter()
+                .to_single_value_query(),
+            ),
+            auth: None,
+        }
+    }
+
+    fn assert_json_error(response: &CraftedResponse, error: &str) {
+        match &response.body {
+            Some(Body::Json(ref json)) => {
+                let content: HashMap<String, String> = serde_json::from_str(json).unwrap();
+                assert_eq!(content.get("error").map(String::as_str), Some(error));
+            }
+            other => panic!("Expected json encoded body, got {:?}", other),
+        }
+
+        match response.status {
+            Status::Unauthorized => (),
+            Status::BadRequest => (),
+            _ => panic!("Expected error status, got {:?}", response),
+        }
+    }
+
+    fn test_poll_error(&mut self, request: CraftedRequest, error: &str) {
+        match device_token_flow(&self.registrar, &mut self.devices, &mut self.issuer).execute(request) {
+            Ok(ref response) => Self::assert_json_error(response, error),
+            resp => panic!("Expected non-error reponse, got {:?}", resp),
+        }
+    }
+
+    fn test_poll_success(&mut self, device_code: &str) -> TokenResponse {
+        let response = device_token_flow(&self.registrar, &mut self.devices, &mut self.issuer)
+            .execute(Self::poll_request(device_code))
+            .expect("Expected non-error reponse");
+
+        assert_eq!(response.status, Status::Ok);
+        let token = match response.body {
+            Some(Body::Json(ref json)) => serde_json::from_str::<TokenResponse>(json).unwrap(),
+            other => panic!("Expected json encoded body, got {:?}", other),
+        };
+
+        let access_token = token.access_token.as_ref().expect("Expected an access token");
+        let grant = self
+            .issuer
+            .recover_token(access_token)
+            .unwrap()
+            .expect("Issued token should be recoverable");
+        assert_eq!(grant.client_id, EXAMPLE_CLIENT_ID);
+        assert_eq!(grant.owner_id, EXAMPLE_OWNER_ID);
+        token
+    }
+}
+
+#[test]
+fn device_authorization_response() {
+    let mut setup = DeviceSetup::new();
+    let codes = setup.authorize();
+
+    assert_eq!(codes.device_code, "DeviceCode");
+    assert_eq!(codes.verification_uri, EXAMPLE_VERIFICATION_URI);
+    let complete = format!("{}?user_code={}", EXAMPLE_VERIFICATION_URI, codes.user_code);
+    assert_eq!(codes.verification_uri_complete, Some(complete));
+    assert!(codes.expires_in > 0);
+    assert_eq!(codes.interval, Some(5));
+}
+
+#[test]
+fn device_unknown_client() {
+    let mut setup = DeviceSetup::new();
+    let request = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![("client_id", "SomeOtherClient")]
+                .iter()
+                .to_single_value_query(),
+        ),
+        auth: None,
+    };
+
+    let verification_uri: Url = EXAMPLE_VERIFICATION_URI.parse().unwrap();
+    match device_authorization_flow(&setup.registrar, &mut setup.devices, verification_uri).execute(request) {
+        Ok(ref response) => DeviceSetup::assert_json_error(response, "invalid_client"),
+        resp => panic!("Expected non-error reponse, got {:?}", resp),
+    }
+}
+
+#[test]
+fn device_approved() {
+    let mut setup = DeviceSetup::new();
+    let codes = setup.authorize();
+
+    setup.test_poll_error(
+        DeviceSetup::poll_request(&codes.device_code),
+        "authorization_pending",
+    );
+
+    let verified = setup.verify(&codes.user_code, true);
+    assert_eq!(verified.status, Status::Ok);
+    match verified.body {
+        Some(Body::Text(ref text)) => assert_eq!(text, "The device has been authorized."),
+        ref other => panic!("Expected a text body, got {:?}", other),
+    }
+
+    let token = setup.test_poll_success(&codes.device_code);
+    let scope: Option<Scope> = token.scope.as_ref().map(|scope| scope.parse().unwrap());
+    assert_eq!(scope, EXAMPLE_SCOPE.parse().ok());
+
+    // The device code can only be redeemed once.
+    setup.test_poll_error(DeviceSetup::poll_request(&codes.device_code), "invalid_grant");
+}
+
+#[test]
+fn device_denied() {
+    let mut setup = DeviceSetup::new();
+    let codes = setup.authorize();
+
+    let verified = setup.verify(&codes.user_code, false);
+    assert_eq!(verified.status, Status::Ok);
+    match verified.body {
+        Some(Body::Text(ref text)) => assert_eq!(text, "The device authorization has been denied."),
+        ref other => panic!("Expected a text body, got {:?}", other),
+    }
+
+    setup.test_poll_error(DeviceSetup::poll_request(&codes.device_code), "access_denied");
+}
+
+#[test]
+fn device_slow_down() {
+    let mut setup = DeviceSetup::new();
+    let codes = setup.authorize();
+
+    setup.test_poll_error(
+        DeviceSetup::poll_request(&codes.device_code),
+        "authorization_pending",
+    );
+    setup.test_poll_error(DeviceSetup::poll_request(&codes.device_code), "slow_down");
+}
+
+#[test]
+fn device_expired() {
+    let mut setup = DeviceSetup::new();
+    let request = DeviceSetup::poll_request("DeviceCode");
+
+    match device_token_flow(&setup.registrar, &mut Expired, &mut setup.issuer).execute(request) {
+        Ok(ref response) => DeviceSetup::assert_json_error(response, "expired_token"),
+        resp => panic!("Expected non-error reponse, got {:?}", resp),
+    }
+}
+
+#[test]
+fn device_unknown_user_code() {
+    let mut setup = DeviceSetup::new();
+    setup.authorize();
+
+    let verified = setup.verify("UNKNOWNC", true);
+    assert_eq!(verified.status, Status::BadRequest);
+}
+
+#[test]
+fn device_wrong_grant_type() {
+    let mut setup = DeviceSetup::new();
+    let codes = setup.authorize();
+
+    let wrong_grant_type = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![
+                ("grant_type", "client_credentials"),
+                ("device_code", codes.device_code.as_str()),
+                ("client_id", EXAMPLE_CLIENT_ID),
+            ]
+            .iter()
+            .to_single_value_query(),
+        ),
+        auth: None,
+    };
+
+    setup.test_poll_error(wrong_grant_type, "unsupported_grant_type");
+}
+
+#[test]
+fn device_grant_type_not_permitted() {
+    let mut setup = DeviceSetup::new();
+    let codes = setup.authorize();
+    setup.verify(&codes.user_code, true);
+
+    let client = Client::public(
+        EXAMPLE_CLIENT_ID,
+        RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+        EXAMPLE_SCOPE.parse().unwrap(),
+    )
+    .with_metadata(ClientMetadata {
+        grant_types: vec!["authorization_code".to_string()],
+        ..ClientMetadata::default()
+    });
+    setup.registrar.register_client(client);
+
+    // Neither new device codes nor the token of an already approved one are handed out.
+    let request = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![("client_id", EXAMPLE_CLIENT_ID)]
+                .iter()
+                .to_single_value_query(),
+        ),
+        auth: None,
+    };
+
+    let verification_uri: Url = EXAMPLE_VERIFICATION_URI.parse().unwrap();
+    match device_authorization_flow(&setup.registrar, &mut setup.devices, verification_uri)
+        .execute(request)
+    {
+        Ok(ref response) => DeviceSetup::assert_json_error(response, "unauthorized_client"),
+        resp => panic!("Expected non-error reponse, got {:?}", resp),
+    }
+
+    setup.test_poll_error(
+        DeviceSetup::poll_request(&codes.device_code),
+        "unauthorized_client",
+    );
+}
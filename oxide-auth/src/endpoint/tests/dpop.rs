@@ -0,0 +1,259 @@
+use crate::primitives::authorizer::{AuthMap, Authorizer};
+use crate::primitives::dpop::{self, DpopMap};
+use crate::primitives::generator::{Assertion, AssertionKind, RandomGenerator};
+use crate::primitives::grant::{Extensions, Grant};
+use crate::primitives::issuer::{Issuer, TokenMap};
+use crate::primitives::jwk::Jwk;
+use crate::primitives::registrar::{Client, ClientMap, RegisteredUrl};
+use crate::primitives::scope::Scope;
+
+use crate::frontends::simple::endpoint::{Generic, Vacant};
+use crate::frontends::simple::request::{Request, Response, Status};
+
+use std::collections::HashMap;
+
+use base64::{encode_config, URL_SAFE_NO_PAD};
+use chrono::{Duration, Utc};
+use ring::rand::SystemRandom;
+use ring::signature::Ed25519KeyPair;
+use serde_json::{json, Value as JsonValue};
+use sha2::{Digest, Sha256};
+
+use super::defaults::*;
+
+const TOKEN_URI: &str = "https://server.example/token";
+const RESOURCE_URI: &str = "https://server.example/resource";
+
+struct DpopSetup {
+    registrar: ClientMap,
+    authorizer: AuthMap<RandomGenerator>,
+    issuer: TokenMap<RandomGenerator>,
+    verifier: DpopMap,
+    key: Assertion,
+    authtoken: String,
+}
+
+impl DpopSetup {
+    fn new() -> Self {
+        let mut registrar = ClientMap::new();
+        let mut authorizer = AuthMap::new(RandomGenerator::new(16));
+        let issuer = TokenMap::new(RandomGenerator::new(16));
+
+        registrar.register_client(Client::public(
+            EXAMPLE_CLIENT_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+        ));
+
+        let authtoken = authorizer
+            .authorize(Grant {
+                client_id: EXAMPLE_CLIENT_ID.to_string(),
+                owner_id: EXAMPLE_OWNER_ID.to_string(),
+                redirect_uri: EXAMPLE_REDIRECT_URI.parse().unwrap(),
+                scope: EXAMPLE_SCOPE.parse().unwrap(),
+                until: Utc::now() + Duration::hours(1),
+                extensions: Extensions::new(),
+            })
+            .unwrap();
+
+        DpopSetup {
+            registrar,
+            authorizer,
+            issuer,
+            verifier: DpopMap::new(),
+            key: Self::generate_key(),
+            authtoken,
+        }
+    }
+
+    fn generate_key() -> Assertion {
+        let key = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        Assertion::new(AssertionKind::Ed25519, key.as_ref())
+    }
+
+    fn proof(key: &Assertion, method: &str, uri: &str, access_token: Option<&str>) -> String {
+        let header = json!({
+            "typ": "dpop+jwt",
+            "alg": "EdDSA",
+            "jwk": Jwk::from_assertion(key).unwrap(),
+        });
+
+        let mut claims = json!({
+            "jti": rand::random::<u64>().to_string(),
+            "htm": method,
+            "htu": uri,
+            "iat": Utc::now().timestamp(),
+        });
+        if let Some(access_token) = access_token {
+            let hash = Sha256::digest(access_token.as_bytes());
+            claims["ath"] = encode_config(hash, URL_SAFE_NO_PAD).into();
+        }
+
+        let encode = |value: &JsonValue| encode_config(value.to_string(), URL_SAFE_NO_PAD);
+        let signed = format!("{}.{}", encode(&header), encode(&claims));
+        let signature = key.sign_detached(signed.as_bytes()).unwrap();
+        format!("{}.{}", signed, encode_config(&signature, URL_SAFE_NO_PAD))
+    }
+
+    fn token_request(&self, proof: Option<String>) -> Request {
+        let mut urlbody = HashMap::new();
+        urlbody.insert("grant_type".to_string(), "authorization_code".to_string());
+        urlbody.insert("code".to_string(), self.authtoken.clone());
+        urlbody.insert("redirect_uri".to_string(), EXAMPLE_REDIRECT_URI.to_string());
+        urlbody.insert("client_id".to_string(), EXAMPLE_CLIENT_ID.to_string());
+
+        let mut headers = HashMap::new();
+        if let Some(proof) = proof {
+            headers.insert("DPoP".to_string(), proof);
+        }
+
+        Request {
+            urlbody,
+            headers,
+            target: Some(("POST".to_string(), TOKEN_URI.parse().unwrap())),
+            ..Request::default()
+        }
+    }
+
+    fn resource_request(auth: String, proof: Option<String>) -> Request {
+        let mut headers = HashMap::new();
+        if let Some(proof) = proof {
+            // Header names are matched case-insensitively.
+            headers.insert("dpop".to_string(), proof);
+        }
+
+        Request {
+            auth: Some(auth),
+            headers,
+            target: Some(("GET".to_string(), RESOURCE_URI.parse().unwrap())),
+            ..Request::default()
+        }
+    }
+
+    fn token(&mut self, request: Request) -> (Status, JsonValue) {
+        let response: Response = Generic {
+            registrar: &self.registrar,
+            authorizer: &mut self.authorizer,
+            issuer: &mut self.issuer,
+            solicitor: Vacant,
+            scopes: Vacant,
+            response: Vacant,
+        }
+        .with_dpop_verifier(&self.verifier)
+        .access_token_flow()
+        .execute(request)
+        .unwrap();
+
+        let body = response.body.expect("Token response without body");
+        (response.status, serde_json::from_str(body.as_str()).unwrap())
+    }
+
+    fn resource(&mut self, request: Request) -> Result<Grant, ()> {
+        let scopes: Vec<Scope> = vec![EXAMPLE_SCOPE.parse().unwrap()];
+        Generic {
+            registrar: Vacant,
+            authorizer: Vacant,
+            issuer: &mut self.issuer,
+            solicitor: Vacant,
+            scopes,
+            response: Vacant,
+        }
+        .with_dpop_verifier(&self.verifier)
+        .resource_flow()
+        .execute(request)
+        .map_err(|_| ())
+    }
+}
+
+#[test]
+fn dpop_bound_token() {
+    let mut setup = DpopSetup::new();
+    let proof = DpopSetup::proof(&setup.key, "POST", TOKEN_URI, None);
+    let (status, body) = setup.token(setup.token_request(Some(proof)));
+
+    assert_eq!(status, Status::Ok);
+    assert_eq!(body["token_type"], "DPoP");
+    let token = body["access_token"].as_str().unwrap().to_string();
+
+    let thumbprint = Jwk::from_assertion(&setup.key).unwrap().thumbprint();
+    let grant = setup.issuer.recover_token(&token).unwrap().unwrap();
+    assert_eq!(dpop::confirmation(&grant), thumbprint.as_deref());
+
+    let proof = DpopSetup::proof(&setup.key, "GET", RESOURCE_URI, Some(&token));
+    let request = DpopSetup::resource_request(format!("DPoP {}", token), Some(proof));
+    assert!(setup.resource(request).is_ok());
+}
+
+#[test]
+fn dpop_bound_token_requires_proof() {
+    let mut setup = DpopSetup::new();
+    let proof = DpopSetup::proof(&setup.key, "POST", TOKEN_URI, None);
+    let (_, body) = setup.token(setup.token_request(Some(proof)));
+    let token = body["access_token"].as_str().unwrap().to_string();
+
+    // Presented as a plain bearer token.
+    let proof = DpopSetup::proof(&setup.key, "GET", RESOURCE_URI, Some(&token));
+    let request = DpopSetup::resource_request(format!("Bearer {}", token), Some(proof));
+    assert!(setup.resource(request).is_err());
+
+    // Without any proof.
+    let request = DpopSetup::resource_request(format!("DPoP {}", token), None);
+    assert!(setup.resource(request).is_err());
+
+    // With a proof of a different key.
+    let other = DpopSetup::generate_key();
+    let proof = DpopSetup::proof(&other, "GET", RESOURCE_URI, Some(&token));
+    let request = DpopSetup::resource_request(format!("DPoP {}", token), Some(proof));
+    assert!(setup.resource(request).is_err());
+
+    // With a proof for a different token.
+    let proof = DpopSetup::proof(&setup.key, "GET", RESOURCE_URI, Some("other"));
+    let request = DpopSetup::resource_request(format!("DPoP {}", token), Some(proof));
+    assert!(setup.resource(request).is_err());
+
+    // Replaying a proof that was already accepted.
+    let proof = DpopSetup::proof(&setup.key, "GET", RESOURCE_URI, Some(&token));
+    let request = DpopSetup::resource_request(format!("DPoP {}", token), Some(proof));
+    assert!(setup.resource(request.clone()).is_ok());
+    assert!(setup.resource(request).is_err());
+}
+
+#[test]
+fn dpop_invalid_proof() {
+    let mut setup = DpopSetup::new();
+    let proof = DpopSetup::proof(&setup.key, "POST", RESOURCE_URI, None);
+    let (status, body) = setup.token(setup.token_request(Some(proof)));
+
+    assert_eq!(status, Status::BadRequest);
+    assert_eq!(body["error"], "invalid_dpop_proof");
+}
+
+#[test]
+fn dpop_unbound_token() {
+    let mut setup = DpopSetup::new();
+    let (status, body) = setup.token(setup.token_request(None));
+
+    assert_eq!(status, Status::Ok);
+    assert_eq!(body["token_type"], "bearer");
+    let token = body["access_token"].as_str().unwrap().to_string();
+
+    // An unbound token must not be presented as bound to a key.
+    let proof = DpopSetup::proof(&setup.key, "GET", RESOURCE_URI, Some(&token));
+    let request = DpopSetup::resource_request(format!("DPoP {}", token), Some(proof));
+    assert!(setup.resource(request).is_err());
+
+    let request = DpopSetup::resource_request(format!("Bearer {}", token), None);
+    assert!(setup.resource(request).is_ok());
+}
+
+#[test]
+fn dpop_target_required() {
+    let mut setup = DpopSetup::new();
+    let proof = DpopSetup::proof(&setup.key, "POST", TOKEN_URI, None);
+    let mut request = setup.token_request(Some(proof));
+    request.target = None;
+
+    let (status, body) = setup.token(request);
+    assert_eq!(status, Status::BadRequest);
+    assert_eq!(body["error"], "invalid_dpop_proof");
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use crate::primitives::authorizer::AuthMap;
+use crate::primitives::generator::RandomGenerator;
+use crate::primitives::issuer::{Issuer, TokenMap};
+use crate::primitives::registrar::{Client, ClientMap, ClientMetadata, RegisteredUrl};
+use crate::primitives::scope::Scope;
+
+use crate::endpoint::OwnerSolicitor;
+use crate::frontends::simple::endpoint::{Generic, Vacant};
+
+use url::{form_urlencoded, Url};
+
+use super::{CraftedRequest, CraftedResponse, Status, TestGenerator, ToSingleValueQuery};
+use super::{Allow, Deny};
+use super::defaults::*;
+
+const EXAMPLE_STATE: &str = "S0m3 st&te";
+
+struct ImplicitSetup {
+    registrar: ClientMap,
+    authorizer: AuthMap<TestGenerator>,
+    issuer: TokenMap<RandomGenerator>,
+}
+
+impl ImplicitSetup {
+    fn new(client: Client) -> ImplicitSetup {
+        let mut registrar = ClientMap::new();
+        registrar.register_client(client);
+        ImplicitSetup {
+            registrar,
+            authorizer: AuthMap::new(TestGenerator("AuthToken".to_string())),
+            issuer: TokenMap::new(RandomGenerator::new(16)),
+        }
+    }
+
+    fn client() -> Client {
+        Client::public(
+            EXAMPLE_CLIENT_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+        )
+    }
+
+    fn request(extra: Vec<(&str, &str)>) -> CraftedRequest {
+        let mut query = vec![
+            ("response_type", "token"),
+            ("client_id", EXAMPLE_CLIENT_ID),
+            ("redirect_uri", EXAMPLE_REDIRECT_URI),
+            ("state", EXAMPLE_STATE),
+        ];
+        query.extend(extra);
+        CraftedRequest {
+            query: Some(query.iter().to_single_value_query()),
+            urlbody: None,
+            auth: None,
+        }
+    }
+
+    fn execute<P>(&mut self, request: CraftedRequest, mut solicitor: P) -> CraftedResponse
+    where
+        P: OwnerSolicitor<CraftedRequest>,
+    {
+        Generic {
+            registrar: &self.registrar,
+            authorizer: &mut self.authorizer,
+            issuer: &mut self.issuer,
+            solicitor: &mut solicitor,
+            scopes: Vacant,
+            response: Vacant,
+        }
+        .authorization_flow()
+        .execute(request)
+        .expect("Should not error")
+    }
+
+    /// The parameters in the fragment of the redirect, ensuring that the query is left untouched.
+    fn fragment(response: &CraftedResponse) -> HashMap<String, String> {
+        assert_eq!(response.status, Status::Redirect);
+        let url: &Url = response.location.as_ref().expect("Expected a redirect");
+        assert!(
+            url.query_pairs().next().is_none(),
+            "Expected no parameters in query: {}",
+            url
+        );
+        let fragment = url.fragment().expect("Expected a fragment");
+        form_urlencoded::parse(fragment.as_bytes()).into_owned().collect()
+    }
+
+    fn test_success(&mut self, request: CraftedRequest) -> HashMap<String, String> {
+        let response = self.execute(request, Allow(EXAMPLE_OWNER_ID.to_string()));
+        let fragment = Self::fragment(&response);
+        assert_eq!(fragment.get("error"), None);
+        assert_eq!(fragment.get("token_type").map(String::as_str), Some("bearer"));
+        assert_eq!(fragment.get("state").map(String::as_str), Some(EXAMPLE_STATE));
+        assert_eq!(fragment.get("refresh_token"), None);
+
+        let token = fragment.get("access_token").expect("Expected an access token");
+        let grant = self
+            .issuer
+            .recover_token(token)
+            .unwrap()
+            .expect("Issued token should be recoverable");
+        assert_eq!(grant.client_id, EXAMPLE_CLIENT_ID);
+        assert_eq!(grant.owner_id, EXAMPLE_OWNER_ID);
+        fragment
+    }
+
+    fn test_error<P>(&mut self, request: CraftedRequest, solicitor: P, error: &str)
+    where
+        P: OwnerSolicitor<CraftedRequest>,
+    {
+        let response = self.execute(request, solicitor);
+        let fragment = Self::fragment(&response);
+        assert_eq!(fragment.get("error").map(String::as_str), Some(error));
+        assert_eq!(fragment.get("state").map(String::as_str), Some(EXAMPLE_STATE));
+        assert_eq!(fragment.get("access_token"), None);
+    }
+}
+
+#[test]
+fn implicit_success() {
+    let mut setup = ImplicitSetup::new(ImplicitSetup::client());
+    let fragment = setup.test_success(ImplicitSetup::request(vec![]));
+    let scope: Scope = fragment.get("scope").unwrap().parse().unwrap();
+    assert_eq!(scope, EXAMPLE_SCOPE.parse().unwrap());
+}
+
+#[test]
+fn implicit_ignores_user_id() {
+    // The owner is always determined by the solicitor, never by the request itself.
+    let mut setup = ImplicitSetup::new(ImplicitSetup::client());
+    setup.test_success(ImplicitSetup::request(vec![("user_id", "Mallory")]));
+}
+
+#[test]
+fn implicit_denied() {
+    let mut setup = ImplicitSetup::new(ImplicitSetup::client());
+    setup.test_error(ImplicitSetup::request(vec![]), Deny, "access_denied");
+}
+
+#[test]
+fn implicit_disabled_client() {
+    let mut setup = ImplicitSetup::new(ImplicitSetup::client().without_implicit_grant());
+    setup.test_error(
+        ImplicitSetup::request(vec![]),
+        Allow(EXAMPLE_OWNER_ID.to_string()),
+        "unauthorized_client",
+    );
+}
+
+#[test]
+fn implicit_response_type_not_permitted() {
+    let client = ImplicitSetup::client().with_metadata(ClientMetadata {
+        response_types: vec!["code".to_string()],
+        ..ClientMetadata::default()
+    });
+    let mut setup = ImplicitSetup::new(client);
+    setup.test_error(
+        ImplicitSetup::request(vec![]),
+        Allow(EXAMPLE_OWNER_ID.to_string()),
+        "unauthorized_client",
+    );
+}
+
+#[test]
+fn implicit_malformed_scope() {
+    let mut setup = ImplicitSetup::new(ImplicitSetup::client());
+    setup.test_error(
+        ImplicitSetup::request(vec![("scope", "\"no quotes (0x22) allowed\"")]),
+        Allow(EXAMPLE_OWNER_ID.to_string()),
+        "invalid_scope",
+    );
+}
+
+#[test]
+fn implicit_without_issuer() {
+    // Endpoints without an issuer do not support the token response type.
+    let mut setup = ImplicitSetup::new(ImplicitSetup::client());
+    let response = Generic {
+        registrar: &setup.registrar,
+        authorizer: &mut setup.authorizer,
+        issuer: Vacant,
+        solicitor: &mut Allow(EXAMPLE_OWNER_ID.to_string()),
+        scopes: Vacant,
+        response: Vacant,
+    }
+    .authorization_flow()
+    .execute(ImplicitSetup::request(vec![]))
+    .expect("Should not error");
+
+    let url = response.location.expect("Expected a redirect");
+    assert_eq!(url.fragment(), None);
+    let query: HashMap<_, _> = url.query_pairs().into_owned().collect();
+    assert_eq!(
+        query.get("error").map(String::as_str),
+        Some("unsupported_response_type")
+    );
+}
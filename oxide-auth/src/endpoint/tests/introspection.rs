@@ -0,0 +1,240 @@
+use crate::primitives::generator::RandomGenerator;
+use crate::primitives::issuer::{IssuedToken, Issuer, TokenMap};
+use crate::primitives::grant::{Extensions, Grant, Value};
+use crate::primitives::registrar::{Client, ClientMap, RegisteredUrl};
+use crate::primitives::scope::Scope;
+
+use crate::frontends::simple::endpoint::introspection_flow;
+
+use std::collections::HashMap;
+
+use chrono::{Duration, Utc};
+use serde_json::{self, Value as JsonValue};
+
+use super::{Body, CraftedRequest, CraftedResponse, Status, ToSingleValueQuery};
+use super::defaults::*;
+
+struct IntrospectionSetup {
+    registrar: ClientMap,
+    issuer: TokenMap<RandomGenerator>,
+    issued: IssuedToken,
+    scopes: Vec<Scope>,
+    authorization: String,
+}
+
+impl IntrospectionSetup {
+    fn new() -> Self {
+        let mut registrar = ClientMap::new();
+        registrar.register_client(Client::confidential(
+            EXAMPLE_CLIENT_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+            EXAMPLE_PASSPHRASE.as_bytes(),
+        ));
+
+        let mut issuer = TokenMap::new(RandomGenerator::new(16));
+        let mut grant = Self::grant(EXAMPLE_SCOPE);
+        grant
+            .extensions
+            .set_raw("audience".to_string(), Value::public(Some("api".to_string())));
+        grant
+            .extensions
+            .set_raw("confirmed".to_string(), Value::public(None));
+        grant
+            .extensions
+            .set_raw("secret".to_string(), Value::private(Some("hidden".to_string())));
+        let issued = issuer.issue(grant).unwrap();
+
+        let authorization = "Basic ".to_string()
+            + &base64::encode(&format!("{}:{}", EXAMPLE_CLIENT_ID, EXAMPLE_PASSPHRASE));
+
+        IntrospectionSetup {
+            registrar,
+            issuer,
+            issued,
+            scopes: vec!["introspect".parse().unwrap()],
+            authorization,
+        }
+    }
+
+    fn grant(scope: &str) -> Grant {
+        Grant {
+            client_id: EXAMPLE_CLIENT_ID.to_string(),
+            owner_id: EXAMPLE_OWNER_ID.to_string(),
+            redirect_uri: EXAMPLE_REDIRECT_URI.parse().unwrap(),
+            scope: scope.parse().unwrap(),
+            until: Utc::now() + Duration::hours(1),
+            extensions: Extensions::new(),
+        }
+    }
+
+    fn request(&self, token: &str, hint: Option<&str>) -> CraftedRequest {
+        let mut params = vec![("token", token)];
+        params.extend(hint.map(|hint| ("token_type_hint", hint)));
+        CraftedRequest {
+            query: None,
+            urlbody: Some(params.iter().to_single_value_query()),
+            auth: Some(self.authorization.clone()),
+        }
+    }
+
+    fn execute(&mut self, request: CraftedRequest) -> CraftedResponse {
+        introspection_flow(&self.registrar, &mut self.issuer, &self.scopes)
+            .execute(request)
+            .expect("Expected non-error reponse")
+    }
+
+    fn test_error(&mut self, request: CraftedRequest, error: &str) {
+        let response = self.execute(request);
+        match &response.body {
+            Some(Body::Json(ref json)) => {
+                let content: HashMap<String, String> = serde_json::from_str(json).unwrap();
+                assert_eq!(content.get("error").map(String::as_str), Some(error));
+            }
+            other => panic!("Expected json encoded body, got {:?}", other),
+        }
+
+        match response.status {
+            Status::Unauthorized => (),
+            Status::BadRequest => (),
+            _ => panic!("Expected error status, got {:?}", response),
+        }
+    }
+
+    fn test_success(&mut self, request: CraftedRequest) -> HashMap<String, JsonValue> {
+        let response = self.execute(request);
+        assert_eq!(response.status, Status::Ok);
+        match &response.body {
+            Some(Body::Json(ref json)) => serde_json::from_str(json).unwrap(),
+            other => panic!("Expected json encoded body, got {:?}", other),
+        }
+    }
+
+    fn assert_inactive(content: &HashMap<String, JsonValue>) {
+        assert_eq!(content.get("active"), Some(&JsonValue::Bool(false)));
+        assert_eq!(content.len(), 1);
+    }
+}
+
+#[test]
+fn introspect_access_token() {
+    let mut setup = IntrospectionSetup::new();
+    let request = setup.request(&setup.issued.token, Some("access_token"));
+    let content = setup.test_success(request);
+
+    assert_eq!(content.get("active"), Some(&JsonValue::Bool(true)));
+    let scope = content.get("scope").and_then(JsonValue::as_str).unwrap();
+    assert_eq!(scope.parse::<Scope>().unwrap(), EXAMPLE_SCOPE.parse().unwrap());
+    assert_eq!(
+        content.get("client_id"),
+        Some(&JsonValue::from(EXAMPLE_CLIENT_ID))
+    );
+    assert_eq!(content.get("sub"), Some(&JsonValue::from(EXAMPLE_OWNER_ID)));
+    assert_eq!(content.get("token_type"), Some(&JsonValue::from("bearer")));
+    assert_eq!(
+        content.get("exp"),
+        Some(&JsonValue::from(setup.issued.until.timestamp()))
+    );
+    assert_eq!(content.get("audience"), Some(&JsonValue::from("api")));
+    assert_eq!(content.get("confirmed"), Some(&JsonValue::Bool(true)));
+    assert!(!content.contains_key("secret"));
+}
+
+#[test]
+fn introspect_refresh_token() {
+    let mut setup = IntrospectionSetup::new();
+    let refresh = setup.issued.refresh.clone().unwrap();
+
+    // The hint only decides the order of the lookup.
+    for hint in &[Some("refresh_token"), Some("access_token"), None] {
+        let request = setup.request(&refresh, *hint);
+        let content = setup.test_success(request);
+        assert_eq!(content.get("active"), Some(&JsonValue::Bool(true)));
+        assert_eq!(
+            content.get("client_id"),
+            Some(&JsonValue::from(EXAMPLE_CLIENT_ID))
+        );
+        assert!(!content.contains_key("token_type"));
+    }
+}
+
+#[test]
+fn introspect_unknown_token() {
+    let mut setup = IntrospectionSetup::new();
+    let request = setup.request("NotAnIssuedToken", None);
+    let content = setup.test_success(request);
+    IntrospectionSetup::assert_inactive(&content);
+}
+
+#[test]
+fn introspect_expired_token() {
+    let mut setup = IntrospectionSetup::new();
+    let mut grant = IntrospectionSetup::grant(EXAMPLE_SCOPE);
+    grant.until = Utc::now() - Duration::hours(1);
+    setup.issued = setup.issuer.issue(grant).unwrap();
+
+    let request = setup.request(&setup.issued.token, None);
+    let content = setup.test_success(request);
+    IntrospectionSetup::assert_inactive(&content);
+}
+
+#[test]
+fn introspect_with_bearer_caller() {
+    let mut setup = IntrospectionSetup::new();
+    let caller = setup
+        .issuer
+        .issue(IntrospectionSetup::grant("introspect"))
+        .unwrap();
+    setup.authorization = "Bearer ".to_string() + &caller.token;
+
+    let request = setup.request(&setup.issued.token, None);
+    let content = setup.test_success(request);
+    assert_eq!(content.get("active"), Some(&JsonValue::Bool(true)));
+}
+
+#[test]
+fn introspect_bearer_caller_without_scope() {
+    let mut setup = IntrospectionSetup::new();
+    let caller = setup
+        .issuer
+        .issue(IntrospectionSetup::grant(EXAMPLE_SCOPE))
+        .unwrap();
+    setup.authorization = "Bearer ".to_string() + &caller.token;
+
+    let request = setup.request(&setup.issued.token, None);
+    setup.test_error(request, "invalid_client");
+}
+
+#[test]
+fn introspect_unknown_bearer_caller() {
+    let mut setup = IntrospectionSetup::new();
+    setup.authorization = "Bearer NotAnIssuedToken".to_string();
+
+    let request = setup.request(&setup.issued.token, None);
+    setup.test_error(request, "invalid_client");
+}
+
+#[test]
+fn introspect_wrong_client_passphrase() {
+    let mut setup = IntrospectionSetup::new();
+    setup.authorization = "Basic ".to_string()
+        + &base64::encode(&format!("{}:{}", EXAMPLE_CLIENT_ID, "NotTheRightPassphrase"));
+
+    let request = setup.request(&setup.issued.token, None);
+    setup.test_error(request, "invalid_client");
+}
+
+#[test]
+fn introspect_missing_token() {
+    let mut setup = IntrospectionSetup::new();
+    let request = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![("token_type_hint", "access_token")]
+                .iter()
+                .to_single_value_query(),
+        ),
+        auth: Some(setup.authorization.clone()),
+    };
+    setup.test_error(request, "invalid_request");
+}
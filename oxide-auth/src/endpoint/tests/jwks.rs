@@ -0,0 +1,66 @@
+use crate::primitives::generator::{Assertion, AssertionKind, Keyring};
+use crate::primitives::jwk::JwkSet;
+
+use crate::frontends::simple::endpoint::{Generic, Vacant};
+
+use ring::rand::SystemRandom;
+use ring::signature::Ed25519KeyPair;
+use serde_json::{self, Value as JsonValue};
+
+use super::{Body, CraftedRequest, Status};
+
+fn vacant() -> Generic<Vacant, Vacant, Vacant, Vacant, Vacant, Vacant> {
+    Generic {
+        registrar: Vacant,
+        authorizer: Vacant,
+        issuer: Vacant,
+        solicitor: Vacant,
+        scopes: Vacant,
+        response: Vacant,
+    }
+}
+
+fn request() -> CraftedRequest {
+    CraftedRequest {
+        query: None,
+        urlbody: None,
+        auth: None,
+    }
+}
+
+fn document(keys: JwkSet) -> JsonValue {
+    let response = vacant()
+        .jwks_flow(keys)
+        .execute(request())
+        .expect("Expected non-error response");
+
+    assert_eq!(response.status, Status::Ok);
+    match &response.body {
+        Some(Body::Json(ref json)) => serde_json::from_str(json).unwrap(),
+        other => panic!("Expected json encoded body, got {:?}", other),
+    }
+}
+
+#[test]
+fn jwks_publishes_keyring() {
+    let keyring = Keyring::new("secret", Assertion::new(AssertionKind::HmacSha256, b"secret"));
+    let key = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+    keyring
+        .rotate("signing", Assertion::new(AssertionKind::Ed25519, key.as_ref()))
+        .unwrap();
+
+    let document = document(JwkSet::from_keyring(&keyring));
+    let keys = document["keys"].as_array().unwrap();
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0]["kid"], "signing");
+    assert_eq!(keys[0]["kty"], "OKP");
+    assert_eq!(keys[0]["alg"], "EdDSA");
+    // The secret of the symmetric key is never published.
+    assert!(!document.to_string().contains("secret"));
+}
+
+#[test]
+fn jwks_empty() {
+    let document = document(JwkSet::new());
+    assert_eq!(document["keys"], JsonValue::Array(vec![]));
+}
@@ -0,0 +1,212 @@
+use crate::code_grant::accesstoken::TokenResponse;
+use crate::code_grant::jwt_bearer::JWT_BEARER_GRANT_TYPE;
+use crate::primitives::issuer::{Issuer, TokenMap};
+use crate::primitives::jwt::{IssuerKey, JwtVerifier, TrustedIssuers};
+use crate::primitives::registrar::{Client, ClientMap, ClientMetadata, RegisteredUrl};
+
+use crate::frontends::simple::endpoint::jwt_bearer_flow;
+
+use std::collections::HashMap;
+
+use base64::{self, encode_config, URL_SAFE_NO_PAD};
+use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac, NewMac};
+use serde_json;
+use sha2::Sha256;
+
+use super::{Body, CraftedRequest, CraftedResponse, Status, TestGenerator, ToSingleValueQuery};
+use super::defaults::*;
+
+const EXAMPLE_AUDIENCE: &str = "https://example.com/token";
+const EXAMPLE_ISSUER: &str = "https://idp.example";
+const EXAMPLE_ISSUER_SECRET: &[u8] = b"a shared secret of the identity provider";
+
+struct JwtBearerSetup {
+    registrar: ClientMap,
+    issuer: TokenMap<TestGenerator>,
+    trusted: TrustedIssuers,
+    basic_authorization: String,
+}
+
+impl JwtBearerSetup {
+    fn new() -> Self {
+        let mut registrar = ClientMap::new();
+        registrar.register_client(Client::confidential(
+            EXAMPLE_CLIENT_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+            EXAMPLE_PASSPHRASE.as_bytes(),
+        ));
+
+        let mut trusted = TrustedIssuers::new(EXAMPLE_AUDIENCE);
+        trusted.trust(EXAMPLE_ISSUER, IssuerKey::Hs256(EXAMPLE_ISSUER_SECRET.to_vec()));
+
+        let basic_authorization =
+            base64::encode(&format!("{}:{}", EXAMPLE_CLIENT_ID, EXAMPLE_PASSPHRASE));
+
+        JwtBearerSetup {
+            registrar,
+            issuer: TokenMap::new(TestGenerator("AccessToken".to_string())),
+            trusted,
+            basic_authorization,
+        }
+    }
+
+    fn assertion(issuer: &str, secret: &[u8]) -> String {
+        let header = r#"{"alg":"HS256","typ":"JWT"}"#;
+        let claims = format!(
+            r#"{{"iss":"{}","sub":"{}","aud":"{}","exp":{}}}"#,
+            issuer,
+            EXAMPLE_OWNER_ID,
+            EXAMPLE_AUDIENCE,
+            (Utc::now() + Duration::minutes(5)).timestamp()
+        );
+        let signed = format!(
+            "{}.{}",
+            encode_config(header.as_bytes(), URL_SAFE_NO_PAD),
+            encode_config(claims.as_bytes(), URL_SAFE_NO_PAD)
+        );
+        let mut mac = Hmac::<Sha256>::new_varkey(secret).unwrap();
+        mac.update(signed.as_bytes());
+        let signature = mac.finalize().into_bytes();
+        format!("{}.{}", signed, encode_config(&signature, URL_SAFE_NO_PAD))
+    }
+
+    fn request(&self, grant_type: &str, assertion: &str) -> CraftedRequest {
+        CraftedRequest {
+            query: None,
+            urlbody: Some(
+                vec![("grant_type", grant_type), ("assertion", assertion)]
+                    .iter()
+                    .to_single_value_query(),
+            ),
+            auth: Some("Basic ".to_string() + &self.basic_authorization),
+        }
+    }
+
+    fn assert_json_error(response: &CraftedResponse, error: &str) {
+        match &response.body {
+            Some(Body::Json(ref json)) => {
+                let content: HashMap<String, String> = serde_json::from_str(json).unwrap();
+                assert_eq!(content.get("error").map(String::as_str), Some(error));
+            }
+            other => panic!("Expected json encoded body, got {:?}", other),
+        }
+
+        match response.status {
+            Status::Unauthorized => (),
+            Status::BadRequest => (),
+            _ => panic!("Expected error status, got {:?}", response),
+        }
+    }
+
+    fn test_error(&mut self, request: CraftedRequest, error: &str) {
+        let trusted = self.trusted.clone();
+        match jwt_bearer_flow(&self.registrar, &JwtVerifier, &mut self.issuer, trusted).execute(request) {
+            Ok(ref response) => Self::assert_json_error(response, error),
+            resp => panic!("Expected non-error reponse, got {:?}", resp),
+        }
+    }
+
+    fn test_success(&mut self, request: CraftedRequest) -> TokenResponse {
+        let trusted = self.trusted.clone();
+        let response = jwt_bearer_flow(&self.registrar, &JwtVerifier, &mut self.issuer, trusted)
+            .execute(request)
+            .expect("Expected non-error reponse");
+
+        assert_eq!(response.status, Status::Ok);
+        let token = match response.body {
+            Some(Body::Json(ref json)) => serde_json::from_str::<TokenResponse>(json).unwrap(),
+            other => panic!("Expected json encoded body, got {:?}", other),
+        };
+
+        let access_token = token.access_token.as_ref().expect("Expected an access token");
+        let grant = self
+            .issuer
+            .recover_token(access_token)
+            .unwrap()
+            .expect("Issued token should be recoverable");
+        assert_eq!(grant.client_id, EXAMPLE_CLIENT_ID);
+        assert_eq!(grant.owner_id, EXAMPLE_OWNER_ID);
+        token
+    }
+}
+
+#[test]
+fn jwt_bearer_success() {
+    let mut setup = JwtBearerSetup::new();
+    let assertion = JwtBearerSetup::assertion(EXAMPLE_ISSUER, EXAMPLE_ISSUER_SECRET);
+    let valid = setup.request(JWT_BEARER_GRANT_TYPE, &assertion);
+    setup.test_success(valid);
+}
+
+#[test]
+fn jwt_bearer_wrong_signature() {
+    let mut setup = JwtBearerSetup::new();
+    let assertion = JwtBearerSetup::assertion(EXAMPLE_ISSUER, b"not the secret of the issuer");
+    let forged = setup.request(JWT_BEARER_GRANT_TYPE, &assertion);
+    setup.test_error(forged, "invalid_grant");
+}
+
+#[test]
+fn jwt_bearer_untrusted_issuer() {
+    let mut setup = JwtBearerSetup::new();
+    let assertion = JwtBearerSetup::assertion("https://untrusted.example", EXAMPLE_ISSUER_SECRET);
+    let untrusted = setup.request(JWT_BEARER_GRANT_TYPE, &assertion);
+    setup.test_error(untrusted, "invalid_grant");
+}
+
+#[test]
+fn jwt_bearer_wrong_client_passphrase() {
+    let mut setup = JwtBearerSetup::new();
+    setup.basic_authorization =
+        base64::encode(&format!("{}:{}", EXAMPLE_CLIENT_ID, "NotTheRightPassphrase"));
+    let assertion = JwtBearerSetup::assertion(EXAMPLE_ISSUER, EXAMPLE_ISSUER_SECRET);
+    let wrong_passphrase = setup.request(JWT_BEARER_GRANT_TYPE, &assertion);
+    setup.test_error(wrong_passphrase, "invalid_client");
+}
+
+#[test]
+fn jwt_bearer_missing_assertion() {
+    let mut setup = JwtBearerSetup::new();
+
+    let missing_assertion = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![("grant_type", JWT_BEARER_GRANT_TYPE)]
+                .iter()
+                .to_single_value_query(),
+        ),
+        auth: Some("Basic ".to_string() + &setup.basic_authorization),
+    };
+
+    setup.test_error(missing_assertion, "invalid_request");
+}
+
+#[test]
+fn jwt_bearer_wrong_grant_type() {
+    let mut setup = JwtBearerSetup::new();
+    let assertion = JwtBearerSetup::assertion(EXAMPLE_ISSUER, EXAMPLE_ISSUER_SECRET);
+    let wrong_grant_type = setup.request("client_credentials", &assertion);
+    setup.test_error(wrong_grant_type, "unsupported_grant_type");
+}
+
+#[test]
+fn jwt_bearer_grant_type_not_permitted() {
+    let mut setup = JwtBearerSetup::new();
+    setup.registrar.register_client(
+        Client::confidential(
+            EXAMPLE_CLIENT_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+            EXAMPLE_PASSPHRASE.as_bytes(),
+        )
+        .with_metadata(ClientMetadata {
+            grant_types: vec!["client_credentials".to_string()],
+            ..ClientMetadata::default()
+        }),
+    );
+    let assertion = JwtBearerSetup::assertion(EXAMPLE_ISSUER, EXAMPLE_ISSUER_SECRET);
+    let valid = setup.request(JWT_BEARER_GRANT_TYPE, &assertion);
+    setup.test_error(valid, "unauthorized_client");
+}
@@ -0,0 +1,176 @@
+use crate::primitives::authenticator::OwnerMap;
+use crate::primitives::authorizer::AuthMap;
+use crate::primitives::generator::RandomGenerator;
+use crate::primitives::issuer::TokenMap;
+use crate::primitives::registrar::{Client, ClientMap, RegisteredUrl};
+use crate::primitives::scope::Scope;
+
+use crate::endpoint::{Metadata, MetadataFlow};
+use crate::frontends::simple::extensions::{AddonList, Extended, Pkce};
+use crate::frontends::simple::endpoint::{Generic, Vacant};
+
+use std::collections::HashMap;
+
+use serde_json::{self, Value as JsonValue};
+
+use super::{Allow, Body, CraftedRequest, CraftedResponse, Status, TestGenerator};
+use super::defaults::*;
+
+struct MetadataSetup {
+    registrar: ClientMap,
+    authorizer: AuthMap<TestGenerator>,
+    issuer: TokenMap<RandomGenerator>,
+    authenticator: OwnerMap,
+    scopes: Vec<Scope>,
+}
+
+impl MetadataSetup {
+    fn new() -> Self {
+        let mut registrar = ClientMap::new();
+        registrar.register_client(Client::confidential(
+            EXAMPLE_CLIENT_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+            EXAMPLE_PASSPHRASE.as_bytes(),
+        ));
+
+        MetadataSetup {
+            registrar,
+            authorizer: AuthMap::new(TestGenerator("AuthToken".to_string())),
+            issuer: TokenMap::new(RandomGenerator::new(16)),
+            authenticator: OwnerMap::new(),
+            scopes: vec![EXAMPLE_SCOPE.parse().unwrap()],
+        }
+    }
+
+    fn metadata() -> Metadata {
+        Metadata::new("https://example.com".parse().unwrap())
+            .with_authorization_endpoint("https://example.com/authorize".parse().unwrap())
+            .with_token_endpoint("https://example.com/token".parse().unwrap())
+    }
+
+    fn request() -> CraftedRequest {
+        CraftedRequest {
+            query: None,
+            urlbody: None,
+            auth: None,
+        }
+    }
+
+    fn document(response: CraftedResponse) -> HashMap<String, JsonValue> {
+        assert_eq!(response.status, Status::Ok);
+        match &response.body {
+            Some(Body::Json(ref json)) => serde_json::from_str(json).unwrap(),
+            other => panic!("Expected json encoded body, got {:?}", other),
+        }
+    }
+
+    fn list<'a>(document: &'a HashMap<String, JsonValue>, key: &str) -> Vec<&'a str> {
+        match document.get(key) {
+            Some(JsonValue::Array(values)) => values.iter().map(|v| v.as_str().unwrap()).collect(),
+            None => Vec::new(),
+            other => panic!("Expected a list of strings for {}, got {:?}", key, other),
+        }
+    }
+}
+
+#[test]
+fn metadata_authorization_server() {
+    let mut setup = MetadataSetup::new();
+
+    let mut pkce = Pkce::optional();
+    pkce.allow_plain();
+    let mut extensions = AddonList::new();
+    extensions.push_code(pkce);
+
+    let endpoint = Generic {
+        registrar: &setup.registrar,
+        authorizer: &mut setup.authorizer,
+        issuer: &mut setup.issuer,
+        solicitor: Allow(EXAMPLE_OWNER_ID.to_string()),
+        scopes: &setup.scopes[..],
+        response: Vacant,
+    };
+    let endpoint = Extended::extend_with(endpoint, extensions);
+
+    let response = MetadataFlow::new(endpoint, MetadataSetup::metadata())
+        .execute(MetadataSetup::request())
+        .expect("Expected non-error response");
+    let document = MetadataSetup::document(response);
+
+    assert_eq!(
+        document.get("issuer"),
+        Some(&JsonValue::from("https://example.com/"))
+    );
+    assert_eq!(
+        document.get("token_endpoint"),
+        Some(&JsonValue::from("https://example.com/token"))
+    );
+    assert!(!document.contains_key("revocation_endpoint"));
+
+    assert_eq!(
+        MetadataSetup::list(&document, "response_types_supported"),
+        vec!["code", "token"]
+    );
+
+    let grant_types = MetadataSetup::list(&document, "grant_types_supported");
+    assert!(grant_types.contains(&"authorization_code"));
+    assert!(grant_types.contains(&"implicit"));
+    assert!(grant_types.contains(&"refresh_token"));
+    assert!(grant_types.contains(&"client_credentials"));
+    assert!(!grant_types.contains(&"password"));
+
+    assert_eq!(
+        MetadataSetup::list(&document, "code_challenge_methods_supported"),
+        vec!["S256", "plain"]
+    );
+    assert_eq!(
+        MetadataSetup::list(&document, "token_endpoint_auth_methods_supported"),
+        vec!["client_secret_basic"]
+    );
+
+    let mut scopes = MetadataSetup::list(&document, "scopes_supported");
+    scopes.sort();
+    assert_eq!(scopes, vec!["default", "example"]);
+}
+
+#[test]
+fn metadata_token_endpoint_only() {
+    let mut setup = MetadataSetup::new();
+
+    let endpoint = Generic {
+        registrar: &setup.registrar,
+        authorizer: Vacant,
+        issuer: &mut setup.issuer,
+        solicitor: Vacant,
+        scopes: Vacant,
+        response: Vacant,
+    }
+    .with_authenticator(&setup.authenticator);
+
+    let mut flow = MetadataFlow::new(endpoint, MetadataSetup::metadata());
+    flow.allow_credentials_in_body(true);
+    let response = flow
+        .execute(MetadataSetup::request())
+        .expect("Expected non-error response");
+    let document = MetadataSetup::document(response);
+
+    // Required by the specification even when empty.
+    assert_eq!(
+        document.get("response_types_supported"),
+        Some(&JsonValue::Array(vec![]))
+    );
+
+    let grant_types = MetadataSetup::list(&document, "grant_types_supported");
+    assert!(grant_types.contains(&"password"));
+    assert!(grant_types.contains(&"client_credentials"));
+    assert!(!grant_types.contains(&"authorization_code"));
+    assert!(!grant_types.contains(&"implicit"));
+
+    assert_eq!(
+        MetadataSetup::list(&document, "token_endpoint_auth_methods_supported"),
+        vec!["client_secret_basic", "client_secret_post"]
+    );
+    assert!(!document.contains_key("code_challenge_methods_supported"));
+    assert!(!document.contains_key("scopes_supported"));
+}
@@ -218,6 +218,19 @@ pub mod defaults {
 
 mod authorization;
 mod access_token;
+mod client_credentials;
+mod device;
+mod dpop;
+mod implicit;
+mod jwks;
+mod jwt_bearer;
+mod mtls;
+mod metadata;
+mod password;
 mod resource;
+mod introspection;
+mod revoke;
+mod token_exchange;
 mod refresh;
+mod registration;
 mod pkce;
@@ -0,0 +1,241 @@
+use crate::primitives::authorizer::{AuthMap, Authorizer};
+use crate::primitives::generator::RandomGenerator;
+use crate::primitives::grant::{Extensions, Grant};
+use crate::primitives::issuer::{Issuer, TokenMap};
+use crate::primitives::mtls::{self, ClientCertificate};
+use crate::primitives::registrar::{Client, ClientMap, RegisteredUrl};
+use crate::primitives::scope::Scope;
+
+use crate::frontends::simple::endpoint::{Generic, Vacant};
+use crate::frontends::simple::request::{Request, Response, Status};
+
+use std::collections::HashMap;
+
+use chrono::{Duration, Utc};
+use serde_json::Value as JsonValue;
+
+use super::defaults::*;
+
+const PARTNER_ID: &str = "Partner";
+const PARTNER_SUBJECT: &str = "CN=partner.example,O=Partner";
+const SELF_SIGNED_ID: &str = "SelfSigned";
+
+struct MtlsSetup {
+    registrar: ClientMap,
+    authorizer: AuthMap<RandomGenerator>,
+    issuer: TokenMap<RandomGenerator>,
+    partner: ClientCertificate,
+    self_signed: ClientCertificate,
+}
+
+impl MtlsSetup {
+    fn new() -> Self {
+        let mut registrar = ClientMap::new();
+        let partner = ClientCertificate::from_der(b"partner certificate").with_subject(PARTNER_SUBJECT);
+        let self_signed = ClientCertificate::from_der(b"self-signed certificate");
+
+        registrar.register_client(Client::tls_client_auth(
+            PARTNER_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+            PARTNER_SUBJECT,
+        ));
+        registrar.register_client(Client::self_signed_tls_client_auth(
+            SELF_SIGNED_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+            self_signed.thumbprint(),
+        ));
+
+        MtlsSetup {
+            registrar,
+            authorizer: AuthMap::new(RandomGenerator::new(16)),
+            issuer: TokenMap::new(RandomGenerator::new(16)),
+            partner,
+            self_signed,
+        }
+    }
+
+    fn request(urlbody: &[(&str, &str)], certificate: Option<&ClientCertificate>) -> Request {
+        let urlbody: HashMap<_, _> = urlbody
+            .iter()
+            .map(|&(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+
+        Request {
+            urlbody,
+            certificate: certificate.cloned(),
+            ..Request::default()
+        }
+    }
+
+    fn client_credentials(client_id: &str, certificate: Option<&ClientCertificate>) -> Request {
+        Self::request(
+            &[("grant_type", "client_credentials"), ("client_id", client_id)],
+            certificate,
+        )
+    }
+
+    fn generic(
+        &mut self,
+    ) -> Generic<&ClientMap, &mut AuthMap<RandomGenerator>, &mut TokenMap<RandomGenerator>> {
+        Generic {
+            registrar: &self.registrar,
+            authorizer: &mut self.authorizer,
+            issuer: &mut self.issuer,
+            solicitor: Vacant,
+            scopes: Vacant,
+            response: Vacant,
+        }
+    }
+
+    fn json(response: Response) -> (Status, JsonValue) {
+        let body = response.body.expect("Token response without body");
+        (response.status, serde_json::from_str(body.as_str()).unwrap())
+    }
+
+    fn resource(&mut self, token: &str, certificate: Option<&ClientCertificate>) -> Result<Grant, ()> {
+        let scopes: Vec<Scope> = vec![EXAMPLE_SCOPE.parse().unwrap()];
+        Generic {
+            registrar: Vacant,
+            authorizer: Vacant,
+            issuer: &mut self.issuer,
+            solicitor: Vacant,
+            scopes,
+            response: Vacant,
+        }
+        .resource_flow()
+        .execute(Request {
+            auth: Some(format!("Bearer {}", token)),
+            certificate: certificate.cloned(),
+            ..Request::default()
+        })
+        .map_err(|_| ())
+    }
+}
+
+#[test]
+fn mtls_client_credentials() {
+    let mut setup = MtlsSetup::new();
+    let partner = setup.partner.clone();
+    let request = MtlsSetup::client_credentials(PARTNER_ID, Some(&partner));
+    let (status, body) = MtlsSetup::json(
+        setup
+            .generic()
+            .client_credentials_flow()
+            .execute(request)
+            .unwrap(),
+    );
+
+    assert_eq!(status, Status::Ok);
+    assert_eq!(body["token_type"], "bearer");
+    let token = body["access_token"].as_str().unwrap().to_string();
+
+    let grant = setup.issuer.recover_token(&token).unwrap().unwrap();
+    assert_eq!(mtls::confirmation(&grant), Some(partner.thumbprint()));
+
+    assert!(setup.resource(&token, Some(&partner)).is_ok());
+    assert!(setup.resource(&token, None).is_err());
+
+    let other = setup.self_signed.clone();
+    assert!(setup.resource(&token, Some(&other)).is_err());
+}
+
+#[test]
+fn mtls_self_signed_client() {
+    let mut setup = MtlsSetup::new();
+    let self_signed = setup.self_signed.clone();
+    let request = MtlsSetup::client_credentials(SELF_SIGNED_ID, Some(&self_signed));
+    let (status, body) = MtlsSetup::json(
+        setup
+            .generic()
+            .client_credentials_flow()
+            .execute(request)
+            .unwrap(),
+    );
+
+    assert_eq!(status, Status::Ok);
+    let token = body["access_token"].as_str().unwrap().to_string();
+    assert!(setup.resource(&token, Some(&self_signed)).is_ok());
+}
+
+#[test]
+fn mtls_wrong_certificate() {
+    let mut setup = MtlsSetup::new();
+
+    // The subject of a certificate is not checked against self-signed registrations, nor the
+    // thumbprint against issued ones.
+    let wrong_subject = ClientCertificate::from_der(b"partner certificate").with_subject("CN=other");
+    let requests = vec![
+        MtlsSetup::client_credentials(PARTNER_ID, Some(&wrong_subject)),
+        MtlsSetup::client_credentials(PARTNER_ID, Some(&setup.self_signed)),
+        MtlsSetup::client_credentials(SELF_SIGNED_ID, Some(&setup.partner)),
+        MtlsSetup::client_credentials(PARTNER_ID, None),
+    ];
+
+    for request in requests {
+        let response = setup
+            .generic()
+            .client_credentials_flow()
+            .execute(request)
+            .unwrap();
+        assert_eq!(response.status, Status::Unauthorized);
+    }
+}
+
+#[test]
+fn mtls_bound_refresh() {
+    let mut setup = MtlsSetup::new();
+    let code = setup
+        .authorizer
+        .authorize(Grant {
+            client_id: PARTNER_ID.to_string(),
+            owner_id: EXAMPLE_OWNER_ID.to_string(),
+            redirect_uri: EXAMPLE_REDIRECT_URI.parse().unwrap(),
+            scope: EXAMPLE_SCOPE.parse().unwrap(),
+            until: Utc::now() + Duration::hours(1),
+            extensions: Extensions::new(),
+        })
+        .unwrap();
+
+    let partner = setup.partner.clone();
+    let request = MtlsSetup::request(
+        &[
+            ("grant_type", "authorization_code"),
+            ("code", &code),
+            ("redirect_uri", EXAMPLE_REDIRECT_URI),
+            ("client_id", PARTNER_ID),
+        ],
+        Some(&partner),
+    );
+    let (status, body) = MtlsSetup::json(setup.generic().access_token_flow().execute(request).unwrap());
+    assert_eq!(status, Status::Ok);
+    let refresh = body["refresh_token"].as_str().unwrap().to_string();
+
+    // Another client can not refresh the token, even with a valid certificate of its own.
+    let self_signed = setup.self_signed.clone();
+    let refresh_request = |certificate| {
+        MtlsSetup::request(
+            &[("grant_type", "refresh_token"), ("refresh_token", &refresh)],
+            Some(certificate),
+        )
+    };
+    let response = setup
+        .generic()
+        .refresh_flow()
+        .execute(refresh_request(&self_signed))
+        .unwrap();
+    assert_ne!(response.status, Status::Ok);
+
+    let response = setup
+        .generic()
+        .refresh_flow()
+        .execute(refresh_request(&partner))
+        .unwrap();
+    let (status, body) = MtlsSetup::json(response);
+    assert_eq!(status, Status::Ok);
+
+    let token = body["access_token"].as_str().unwrap().to_string();
+    assert!(setup.resource(&token, Some(&partner)).is_ok());
+    assert!(setup.resource(&token, None).is_err());
+}
@@ -0,0 +1,226 @@
+use crate::code_grant::accesstoken::TokenResponse;
+use crate::primitives::authenticator::OwnerMap;
+use crate::primitives::issuer::{Issuer, TokenMap};
+use crate::primitives::registrar::{Client, ClientMap, ClientMetadata, RegisteredUrl};
+use crate::primitives::scope::Scope;
+
+use crate::frontends::simple::endpoint::password_flow;
+
+use std::collections::HashMap;
+
+use base64;
+use serde_json;
+
+use super::{Body, CraftedRequest, CraftedResponse, Status, TestGenerator, ToSingleValueQuery};
+use super::defaults::*;
+
+const EXAMPLE_USERNAME: &str = "alice";
+const EXAMPLE_PASSWORD: &str = "correct horse battery staple";
+
+struct PasswordSetup {
+    registrar: ClientMap,
+    authenticator: OwnerMap,
+    issuer: TokenMap<TestGenerator>,
+    basic_authorization: String,
+}
+
+impl PasswordSetup {
+    fn new() -> Self {
+        Self::with_client(
+            Client::confidential(
+                EXAMPLE_CLIENT_ID,
+                RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+                EXAMPLE_SCOPE.parse().unwrap(),
+                EXAMPLE_PASSPHRASE.as_bytes(),
+            )
+            .with_password_grant(),
+        )
+    }
+
+    fn not_permitted() -> Self {
+        Self::with_client(Client::confidential(
+            EXAMPLE_CLIENT_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+            EXAMPLE_PASSPHRASE.as_bytes(),
+        ))
+    }
+
+    fn with_client(client: Client) -> Self {
+        let mut registrar = ClientMap::new();
+        let mut authenticator = OwnerMap::new();
+        let issuer = TokenMap::new(TestGenerator("AccessToken".to_string()));
+
+        registrar.register_client(client);
+        authenticator.register_owner(EXAMPLE_USERNAME, EXAMPLE_PASSWORD.as_bytes(), EXAMPLE_OWNER_ID);
+
+        let basic_authorization =
+            base64::encode(&format!("{}:{}", EXAMPLE_CLIENT_ID, EXAMPLE_PASSPHRASE));
+
+        PasswordSetup {
+            registrar,
+            authenticator,
+            issuer,
+            basic_authorization,
+        }
+    }
+
+    fn assert_json_error(response: &CraftedResponse, error: &str) {
+        match &response.body {
+            Some(Body::Json(ref json)) => {
+                let content: HashMap<String, String> = serde_json::from_str(json).unwrap();
+                assert_eq!(content.get("error").map(String::as_str), Some(error));
+            }
+            other => panic!("Expected json encoded body, got {:?}", other),
+        }
+
+        match response.status {
+            Status::Unauthorized => (),
+            Status::BadRequest => (),
+            _ => panic!("Expected error status, got {:?}", response),
+        }
+    }
+
+    fn test_error(&mut self, request: CraftedRequest, error: &str) {
+        match password_flow(&self.registrar, &self.authenticator, &mut self.issuer).execute(request) {
+            Ok(ref response) => Self::assert_json_error(response, error),
+            resp => panic!("Expected non-error reponse, got {:?}", resp),
+        }
+    }
+
+    fn test_success(&mut self, request: CraftedRequest) -> TokenResponse {
+        let response = password_flow(&self.registrar, &self.authenticator, &mut self.issuer)
+            .execute(request)
+            .expect("Expected non-error reponse");
+
+        assert_eq!(response.status, Status::Ok);
+        let token = match response.body {
+            Some(Body::Json(ref json)) => serde_json::from_str::<TokenResponse>(json).unwrap(),
+            other => panic!("Expected json encoded body, got {:?}", other),
+        };
+
+        let access_token = token.access_token.as_ref().expect("Expected an access token");
+        let grant = self
+            .issuer
+            .recover_token(access_token)
+            .unwrap()
+            .expect("Issued token should be recoverable");
+        assert_eq!(grant.client_id, EXAMPLE_CLIENT_ID);
+        assert_eq!(grant.owner_id, EXAMPLE_OWNER_ID);
+        token
+    }
+
+    fn request(&self, username: &str, password: &str) -> CraftedRequest {
+        CraftedRequest {
+            query: None,
+            urlbody: Some(
+                vec![
+                    ("grant_type", "password"),
+                    ("username", username),
+                    ("password", password),
+                ]
+                .iter()
+                .to_single_value_query(),
+            ),
+            auth: Some("Basic ".to_string() + &self.basic_authorization),
+        }
+    }
+}
+
+#[test]
+fn password_success() {
+    let mut setup = PasswordSetup::new();
+    let valid = setup.request(EXAMPLE_USERNAME, EXAMPLE_PASSWORD);
+    let token = setup.test_success(valid);
+    let scope: Option<Scope> = token.scope.as_ref().map(|scope| scope.parse().unwrap());
+    assert_eq!(scope, EXAMPLE_SCOPE.parse().ok());
+    assert!(token.refresh_token.is_some());
+}
+
+#[test]
+fn password_client_not_permitted() {
+    let mut setup = PasswordSetup::not_permitted();
+    let valid = setup.request(EXAMPLE_USERNAME, EXAMPLE_PASSWORD);
+    setup.test_error(valid, "unauthorized_client");
+}
+
+#[test]
+fn password_grant_type_not_permitted() {
+    // Enabling the password grant does not override the registered grant types.
+    let mut setup = PasswordSetup::with_client(
+        Client::confidential(
+            EXAMPLE_CLIENT_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+            EXAMPLE_PASSPHRASE.as_bytes(),
+        )
+        .with_password_grant()
+        .with_metadata(ClientMetadata {
+            grant_types: vec!["authorization_code".to_string()],
+            ..ClientMetadata::default()
+        }),
+    );
+    let valid = setup.request(EXAMPLE_USERNAME, EXAMPLE_PASSWORD);
+    setup.test_error(valid, "unauthorized_client");
+}
+
+#[test]
+fn password_wrong_owner_password() {
+    let mut setup = PasswordSetup::new();
+    let wrong_password = setup.request(EXAMPLE_USERNAME, "NotTheRightPassword");
+    setup.test_error(wrong_password, "invalid_grant");
+}
+
+#[test]
+fn password_unknown_owner() {
+    let mut setup = PasswordSetup::new();
+    let unknown_owner = setup.request("mallory", EXAMPLE_PASSWORD);
+    setup.test_error(unknown_owner, "invalid_grant");
+}
+
+#[test]
+fn password_wrong_client_passphrase() {
+    let mut setup = PasswordSetup::new();
+    setup.basic_authorization =
+        base64::encode(&format!("{}:{}", EXAMPLE_CLIENT_ID, "NotTheRightPassphrase"));
+    let wrong_passphrase = setup.request(EXAMPLE_USERNAME, EXAMPLE_PASSWORD);
+    setup.test_error(wrong_passphrase, "invalid_client");
+}
+
+#[test]
+fn password_missing_username() {
+    let mut setup = PasswordSetup::new();
+
+    let missing_username = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![("grant_type", "password"), ("password", EXAMPLE_PASSWORD)]
+                .iter()
+                .to_single_value_query(),
+        ),
+        auth: Some("Basic ".to_string() + &setup.basic_authorization),
+    };
+
+    setup.test_error(missing_username, "invalid_request");
+}
+
+#[test]
+fn password_wrong_grant_type() {
+    let mut setup = PasswordSetup::new();
+
+    let wrong_grant_type = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![
+                ("grant_type", "client_credentials"),
+                ("username", EXAMPLE_USERNAME),
+                ("password", EXAMPLE_PASSWORD),
+            ]
+            .iter()
+            .to_single_value_query(),
+        ),
+        auth: Some("Basic ".to_string() + &setup.basic_authorization),
+    };
+
+    setup.test_error(wrong_grant_type, "unsupported_grant_type");
+}
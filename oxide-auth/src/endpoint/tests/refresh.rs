@@ -1,7 +1,7 @@
 use crate::primitives::issuer::{Issuer, IssuedToken, RefreshedToken, TokenMap, TokenType};
 use crate::primitives::generator::RandomGenerator;
 use crate::primitives::grant::{Grant, Extensions};
-use crate::primitives::registrar::{Client, ClientMap, RegisteredUrl};
+use crate::primitives::registrar::{Client, ClientMap, ClientMetadata, RegisteredUrl};
 
 use std::collections::HashMap;
 
@@ -157,6 +157,18 @@ impl RefreshTokenSetup {
         self.assert_only_error(body);
     }
 
+    /// The client is not permitted to refresh its tokens.
+    fn assert_unauthorized_client(&mut self, request: CraftedRequest) {
+        let response = refresh_flow(&self.registrar, &mut self.issuer)
+            .execute(request)
+            .expect("Expected non-failed reponse");
+        let body = self.assert_json_body(&response);
+        assert_eq!(response.status, Status::BadRequest);
+
+        assert_eq!(body.get("error").map(String::as_str), Some("unauthorized_client"));
+        self.assert_only_error(body);
+    }
+
     /// Check that the request failed with 401.
     fn assert_wrong_authentication(&mut self, request: CraftedRequest) {
         let response = refresh_flow(&self.registrar, &mut self.issuer)
@@ -243,6 +255,84 @@ fn access_valid_private() {
     setup.access_resource(new_token.token);
 }
 
+#[test]
+fn refresh_grant_type_not_permitted() {
+    let mut setup = RefreshTokenSetup::public_client();
+    let client = Client::public(
+        EXAMPLE_CLIENT_ID,
+        RegisteredUrl::Exact(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+        EXAMPLE_SCOPE.parse().unwrap(),
+    )
+    .with_metadata(ClientMetadata {
+        grant_types: vec!["authorization_code".to_string()],
+        ..ClientMetadata::default()
+    });
+    setup.registrar.register_client(client);
+
+    let request = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![
+                ("grant_type", "refresh_token"),
+                ("refresh_token", &setup.refresh_token),
+            ]
+            .iter()
+            .to_single_value_query(),
+        ),
+        auth: None,
+    };
+
+    setup.assert_unauthorized_client(request);
+}
+
+#[test]
+fn public_rotated_reuse() {
+    let mut setup = RefreshTokenSetup::public_client();
+    setup.issuer.rotate_refresh_tokens(true);
+
+    let refresh_with = |token: &str| CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![("grant_type", "refresh_token"), ("refresh_token", token)]
+                .iter()
+                .to_single_value_query(),
+        ),
+        auth: None,
+    };
+
+    let first_refresh = setup.refresh_token.clone();
+    let rotated = setup.assert_success(refresh_with(&first_refresh));
+    let second_refresh = rotated.refresh.expect("Expected a rotated refresh token");
+    assert_ne!(second_refresh, first_refresh);
+    setup.access_resource(rotated.token.clone());
+
+    // The old refresh token was replaced, using it again revokes the new tokens as well.
+    setup.assert_invalid_grant(refresh_with(&first_refresh));
+    assert_eq!(setup.issuer.recover_token(&rotated.token), Ok(None));
+    setup.assert_invalid_grant(refresh_with(&second_refresh));
+}
+
+#[test]
+fn public_session_expired() {
+    let mut setup = RefreshTokenSetup::public_client();
+    setup.issuer.session_lifetime(Some(Duration::zero()));
+
+    let expired = CraftedRequest {
+        query: None,
+        urlbody: Some(
+            vec![
+                ("grant_type", "refresh_token"),
+                ("refresh_token", &setup.refresh_token),
+            ]
+            .iter()
+            .to_single_value_query(),
+        ),
+        auth: None,
+    };
+
+    setup.assert_invalid_grant(expired);
+}
+
 #[test]
 fn public_private_invalid_grant() {
     let mut setup = RefreshTokenSetup::public_client();
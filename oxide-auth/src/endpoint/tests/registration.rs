@@ -0,0 +1,315 @@
+use crate::primitives::registrar::{ClientMap, ClientUrl, ExactUrl, RegisteredUrl, Registrar, RegistrarMut};
+use crate::primitives::scope::Scope;
+
+use crate::frontends::simple::endpoint::registration_flow;
+use crate::frontends::simple::request::{Body, Request, Response, Status};
+
+use std::borrow::Cow;
+
+use serde_json::Value as JsonValue;
+
+use super::defaults::*;
+
+const REGISTRATION_ENDPOINT: &str = "https://example.com/register";
+
+struct RegistrationSetup {
+    registrar: ClientMap,
+}
+
+impl RegistrationSetup {
+    fn new() -> Self {
+        RegistrationSetup {
+            registrar: ClientMap::new(),
+        }
+    }
+
+    fn register(&mut self, metadata: Option<&str>) -> Response {
+        let mut flow = registration_flow(&mut self.registrar, REGISTRATION_ENDPOINT.parse().unwrap());
+        flow.set_scope(EXAMPLE_SCOPE.parse().unwrap());
+        flow.register(Request {
+            body: metadata.map(str::to_string),
+            ..Request::default()
+        })
+        .unwrap()
+    }
+
+    fn registered(&mut self, metadata: &str) -> JsonValue {
+        let (status, body) = Self::json(self.register(Some(metadata)));
+        assert_eq!(status, Status::Created);
+        body
+    }
+
+    fn configuration(client_id: &str, token: &str, metadata: Option<String>) -> Request {
+        let request = Request::new()
+            .with_query("client_id", client_id)
+            .with_auth(&format!("Bearer {}", token));
+
+        match metadata {
+            Some(metadata) => request.with_body(&metadata),
+            None => request,
+        }
+    }
+
+    fn read(&mut self, client_id: &str, token: &str) -> Response {
+        registration_flow(&mut self.registrar, REGISTRATION_ENDPOINT.parse().unwrap())
+            .read(Self::configuration(client_id, token, None))
+            .unwrap()
+    }
+
+    fn update(&mut self, client_id: &str, token: &str, metadata: String) -> Response {
+        let mut flow = registration_flow(&mut self.registrar, REGISTRATION_ENDPOINT.parse().unwrap());
+        flow.set_scope(EXAMPLE_SCOPE.parse().unwrap());
+        flow.update(Self::configuration(client_id, token, Some(metadata)))
+            .unwrap()
+    }
+
+    fn delete(&mut self, client_id: &str, token: &str) -> Response {
+        registration_flow(&mut self.registrar, REGISTRATION_ENDPOINT.parse().unwrap())
+            .delete(Self::configuration(client_id, token, None))
+            .unwrap()
+    }
+
+    fn json(response: Response) -> (Status, JsonValue) {
+        let body = match response.body {
+            Some(Body::Json(body)) => body,
+            other => panic!("Expected a json body, got {:?}", other),
+        };
+        (response.status, serde_json::from_str(&body).unwrap())
+    }
+
+    fn assert_error(response: Response, error: &str) {
+        let (status, body) = Self::json(response);
+        assert_eq!(status, Status::BadRequest);
+        assert_eq!(body["error"], error);
+    }
+
+    fn assert_invalid_token(response: Response) {
+        assert_eq!(response.status, Status::Unauthorized);
+        assert_eq!(
+            response.www_authenticate.as_deref(),
+            Some("Bearer error=\"invalid_token\"")
+        );
+    }
+}
+
+fn credentials(body: &JsonValue) -> (String, String) {
+    (
+        body["client_id"].as_str().unwrap().to_string(),
+        body["registration_access_token"].as_str().unwrap().to_string(),
+    )
+}
+
+#[test]
+fn register_confidential_client() {
+    let mut setup = RegistrationSetup::new();
+    let body = setup.registered(r#"{"redirect_uris":["https://client.example/endpoint"]}"#);
+
+    let (client_id, _) = credentials(&body);
+    let secret = body["client_secret"].as_str().unwrap();
+    assert_eq!(body["client_secret_expires_at"], 0);
+    assert_eq!(body["token_endpoint_auth_method"], "client_secret_basic");
+    assert_eq!(body["grant_types"][0], "authorization_code");
+    assert_eq!(body["response_types"][0], "code");
+    let scope: Scope = body["scope"].as_str().unwrap().parse().unwrap();
+    assert_eq!(scope, EXAMPLE_SCOPE.parse().unwrap());
+    assert_eq!(
+        body["registration_client_uri"],
+        format!("{}?client_id={}", REGISTRATION_ENDPOINT, client_id)
+    );
+
+    assert!(setup.registrar.check(&client_id, Some(secret.as_bytes())).is_ok());
+    assert!(setup.registrar.check(&client_id, Some(b"wrong")).is_err());
+}
+
+#[test]
+fn register_public_client() {
+    let mut setup = RegistrationSetup::new();
+    let body = setup.registered(
+        r#"{"redirect_uris":["http://127.0.0.1:8080/cb"],"token_endpoint_auth_method":"none","scope":"example"}"#,
+    );
+
+    let (client_id, _) = credentials(&body);
+    assert!(body.get("client_secret").is_none());
+    assert_eq!(body["token_endpoint_auth_method"], "none");
+    assert_eq!(body["scope"], "example");
+    assert!(setup.registrar.check(&client_id, None).is_ok());
+}
+
+#[test]
+fn register_native_client() {
+    let mut setup = RegistrationSetup::new();
+    let body = setup.registered(
+        r#"{"redirect_uris":["http://127.0.0.1/cb","com.example.app:/cb"],"token_endpoint_auth_method":"none"}"#,
+    );
+
+    let (client_id, _) = credentials(&body);
+    let stored = setup.registrar.client(&client_id).unwrap().unwrap();
+    assert!(matches!(stored.redirect_uri, RegisteredUrl::Loopback(_)));
+    assert!(matches!(
+        stored.additional_redirect_uris[..],
+        [RegisteredUrl::PrivateUse(_)]
+    ));
+}
+
+#[test]
+fn register_client_metadata() {
+    let mut setup = RegistrationSetup::new();
+    let body = setup.registered(
+        r#"{"redirect_uris":["https://client.example/endpoint"],"client_name":"Example","logo_uri":"https://client.example/logo.png","contacts":["admin@client.example"],"token_endpoint_auth_method":"client_secret_post"}"#,
+    );
+
+    assert_eq!(body["client_name"], "Example");
+    assert_eq!(body["logo_uri"], "https://client.example/logo.png");
+    assert_eq!(body["contacts"][0], "admin@client.example");
+    assert_eq!(body["token_endpoint_auth_method"], "client_secret_post");
+    assert!(body.get("tos_uri").is_none());
+
+    let (client_id, _) = credentials(&body);
+    let stored = setup.registrar.client(&client_id).unwrap().unwrap();
+    assert_eq!(stored.metadata.client_name.as_deref(), Some("Example"));
+    assert_eq!(
+        stored.metadata.grant_types,
+        vec!["authorization_code".to_string(), "refresh_token".to_string()]
+    );
+    assert_eq!(stored.metadata.response_types, vec!["code".to_string()]);
+    assert_eq!(
+        stored.metadata.token_endpoint_auth_method.as_deref(),
+        Some("client_secret_post")
+    );
+}
+
+#[test]
+fn register_invalid_redirect_uri() {
+    let mut setup = RegistrationSetup::new();
+    for metadata in &[
+        r#"{"redirect_uris":[]}"#,
+        r#"{"redirect_uris":["https://client.example/endpoint#fragment"]}"#,
+        r#"{"redirect_uris":["http://client.example/endpoint"]}"#,
+        r#"{"redirect_uris":["http://localhost:8080/cb"]}"#,
+        r#"{"redirect_uris":["com.example.app/cb"]}"#,
+        r#"{"redirect_uris":["com.example.app://host/cb"]}"#,
+        r#"{"redirect_uris":["not a url"]}"#,
+    ] {
+        RegistrationSetup::assert_error(setup.register(Some(metadata)), "invalid_redirect_uri");
+    }
+}
+
+#[test]
+fn register_invalid_metadata() {
+    let mut setup = RegistrationSetup::new();
+    for metadata in &[
+        r#"{"redirect_uris":["https://client.example/endpoint"],"grant_types":["password"]}"#,
+        r#"{"redirect_uris":["https://client.example/endpoint"],"response_types":["token"]}"#,
+        r#"{"redirect_uris":["https://client.example/endpoint"],"grant_types":["client_credentials"],"token_endpoint_auth_method":"none"}"#,
+        r#"{"redirect_uris":["https://client.example/endpoint"],"scope":"example admin"}"#,
+        r#"{"redirect_uris":"https://client.example/endpoint"}"#,
+        r#"{"redirect_uris":["https://client.example/endpoint"],"logo_uri":"not a url"}"#,
+    ] {
+        RegistrationSetup::assert_error(setup.register(Some(metadata)), "invalid_client_metadata");
+    }
+}
+
+#[test]
+fn register_without_metadata() {
+    let mut setup = RegistrationSetup::new();
+    RegistrationSetup::assert_error(setup.register(None), "invalid_request");
+}
+
+#[test]
+fn read_registration() {
+    let mut setup = RegistrationSetup::new();
+    let registered = setup.registered(r#"{"redirect_uris":["https://client.example/endpoint"]}"#);
+    let (client_id, token) = credentials(&registered);
+
+    let (status, body) = RegistrationSetup::json(setup.read(&client_id, &token));
+    assert_eq!(status, Status::Ok);
+    assert_eq!(body["client_id"], client_id.as_str());
+    assert_eq!(body["redirect_uris"], registered["redirect_uris"]);
+    assert!(body.get("client_secret").is_none());
+
+    RegistrationSetup::assert_invalid_token(setup.read(&client_id, "wrong"));
+    RegistrationSetup::assert_invalid_token(setup.read("unknown", &token));
+}
+
+#[test]
+fn update_registration() {
+    let mut setup = RegistrationSetup::new();
+    let registered = setup.registered(r#"{"redirect_uris":["https://client.example/endpoint"]}"#);
+    let (client_id, token) = credentials(&registered);
+    let secret = registered["client_secret"].as_str().unwrap();
+
+    let metadata = format!(
+        r#"{{"client_id":"{}","client_secret":"{}","redirect_uris":["https://client.example/other"]}}"#,
+        client_id, secret
+    );
+    let (status, body) = RegistrationSetup::json(setup.update(&client_id, &token, metadata));
+    assert_eq!(status, Status::Ok);
+    assert_eq!(body["redirect_uris"][0], "https://client.example/other");
+
+    let bound = |uri: &str| ClientUrl {
+        client_id: Cow::Owned(client_id.clone()),
+        redirect_uri: Some(Cow::Owned(ExactUrl::new(uri.to_string()).unwrap())),
+    };
+    assert!(setup
+        .registrar
+        .bound_redirect(bound("https://client.example/other"))
+        .is_ok());
+    assert!(setup
+        .registrar
+        .bound_redirect(bound(EXAMPLE_REDIRECT_URI))
+        .is_err());
+
+    // The secret survives the update.
+    assert!(setup.registrar.check(&client_id, Some(secret.as_bytes())).is_ok());
+}
+
+#[test]
+fn update_registration_rejected() {
+    let mut setup = RegistrationSetup::new();
+    let registered = setup.registered(r#"{"redirect_uris":["https://client.example/endpoint"]}"#);
+    let (client_id, token) = credentials(&registered);
+
+    let method = format!(
+        r#"{{"client_id":"{}","redirect_uris":["https://client.example/endpoint"],"token_endpoint_auth_method":"none"}}"#,
+        client_id
+    );
+    RegistrationSetup::assert_error(
+        setup.update(&client_id, &token, method),
+        "invalid_client_metadata",
+    );
+
+    let mismatch = r#"{"client_id":"other","redirect_uris":["https://client.example/endpoint"]}"#;
+    RegistrationSetup::assert_error(
+        setup.update(&client_id, &token, mismatch.to_string()),
+        "invalid_request",
+    );
+
+    let secret = format!(
+        r#"{{"client_id":"{}","client_secret":"wrong","redirect_uris":["https://client.example/endpoint"]}}"#,
+        client_id
+    );
+    RegistrationSetup::assert_error(setup.update(&client_id, &token, secret), "invalid_request");
+
+    let unchanged = setup.registrar.client(&client_id).unwrap().unwrap();
+    assert_eq!(unchanged.redirect_uri.as_str(), EXAMPLE_REDIRECT_URI);
+}
+
+#[test]
+fn delete_registration() {
+    let mut setup = RegistrationSetup::new();
+    let registered = setup.registered(r#"{"redirect_uris":["https://client.example/endpoint"]}"#);
+    let (client_id, token) = credentials(&registered);
+    let secret = registered["client_secret"].as_str().unwrap();
+
+    RegistrationSetup::assert_invalid_token(setup.delete(&client_id, "wrong"));
+
+    let response = setup.delete(&client_id, &token);
+    assert_eq!(response.status, Status::NoContent);
+    assert!(response.body.is_none());
+
+    RegistrationSetup::assert_invalid_token(setup.read(&client_id, &token));
+    assert!(setup
+        .registrar
+        .check(&client_id, Some(secret.as_bytes()))
+        .is_err());
+}
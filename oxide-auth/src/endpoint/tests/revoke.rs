@@ -0,0 +1,200 @@
+use crate::primitives::generator::RandomGenerator;
+use crate::primitives::issuer::{IssuedToken, Issuer, TokenMap, TokenSigner};
+use crate::primitives::grant::{Extensions, Grant};
+use crate::primitives::registrar::{Client, ClientMap, RegisteredUrl};
+
+use crate::frontends::simple::endpoint::revoke_flow;
+
+use std::collections::HashMap;
+
+use chrono::{Duration, Utc};
+use serde_json;
+
+use super::{Body, CraftedRequest, CraftedResponse, Status, ToSingleValueQuery};
+use super::defaults::*;
+
+struct RevokeSetup {
+    registrar: ClientMap,
+    issuer: TokenMap<RandomGenerator>,
+    issued: IssuedToken,
+    basic_authorization: String,
+}
+
+impl RevokeSetup {
+    fn new() -> Self {
+        let mut registrar = ClientMap::new();
+        registrar.register_client(Client::confidential(
+            EXAMPLE_CLIENT_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+            EXAMPLE_PASSPHRASE.as_bytes(),
+        ));
+
+        let mut issuer = TokenMap::new(RandomGenerator::new(16));
+        let issued = issuer.issue(Self::grant(EXAMPLE_CLIENT_ID)).unwrap();
+
+        let basic_authorization =
+            base64::encode(&format!("{}:{}", EXAMPLE_CLIENT_ID, EXAMPLE_PASSPHRASE));
+
+        RevokeSetup {
+            registrar,
+            issuer,
+            issued,
+            basic_authorization,
+        }
+    }
+
+    fn grant(client_id: &str) -> Grant {
+        Grant {
+            client_id: client_id.to_string(),
+            owner_id: EXAMPLE_OWNER_ID.to_string(),
+            redirect_uri: EXAMPLE_REDIRECT_URI.parse().unwrap(),
+            scope: EXAMPLE_SCOPE.parse().unwrap(),
+            until: Utc::now() + Duration::hours(1),
+            extensions: Extensions::new(),
+        }
+    }
+
+    fn request(&self, token: &str, hint: Option<&str>) -> CraftedRequest {
+        let mut params = vec![("token", token)];
+        params.extend(hint.map(|hint| ("token_type_hint", hint)));
+        CraftedRequest {
+            query: None,
+            urlbody: Some(params.iter().to_single_value_query()),
+            auth: Some("Basic ".to_string() + &self.basic_authorization),
+        }
+    }
+
+    fn refresh(&self) -> &str {
+        self.issued.refresh.as_ref().unwrap()
+    }
+
+    fn assert_json_error(response: &CraftedResponse, error: &str) {
+        match &response.body {
+            Some(Body::Json(ref json)) => {
+                let content: HashMap<String, String> = serde_json::from_str(json).unwrap();
+                assert_eq!(content.get("error").map(String::as_str), Some(error));
+            }
+            other => panic!("Expected json encoded body, got {:?}", other),
+        }
+
+        match response.status {
+            Status::Unauthorized => (),
+            Status::BadRequest => (),
+            _ => panic!("Expected error status, got {:?}", response),
+        }
+    }
+
+    fn test_error(&mut self, request: CraftedRequest, error: &str) {
+        match revoke_flow(&self.registrar, &mut self.issuer).execute(request) {
+            Ok(ref response) => Self::assert_json_error(response, error),
+            resp => panic!("Expected non-error reponse, got {:?}", resp),
+        }
+    }
+
+    fn test_success(&mut self, request: CraftedRequest) {
+        let response = revoke_flow(&self.registrar, &mut self.issuer)
+            .execute(request)
+            .expect("Expected non-error reponse");
+
+        assert_eq!(response.status, Status::Ok);
+        assert!(response.body.is_none());
+    }
+
+    fn assert_revoked(&self, revoked: bool) {
+        let access = self.issuer.recover_token(&self.issued.token).unwrap();
+        let refresh = self.issuer.recover_refresh(self.refresh()).unwrap();
+        assert_eq!(access.is_none(), revoked);
+        assert_eq!(refresh.is_none(), revoked);
+    }
+}
+
+#[test]
+fn revoke_access_token() {
+    let mut setup = RevokeSetup::new();
+    let request = setup.request(&setup.issued.token, Some("access_token"));
+    setup.test_success(request);
+    setup.assert_revoked(true);
+}
+
+#[test]
+fn revoke_refresh_token() {
+    let mut setup = RevokeSetup::new();
+    let request = setup.request(setup.refresh(), Some("refresh_token"));
+    setup.test_success(request);
+    setup.assert_revoked(true);
+}
+
+#[test]
+fn revoke_wrong_or_missing_hint() {
+    // The hint only decides the order of the lookup.
+    let mut setup = RevokeSetup::new();
+    let request = setup.request(setup.refresh(), Some("access_token"));
+    setup.test_success(request);
+    setup.assert_revoked(true);
+
+    let mut setup = RevokeSetup::new();
+    let request = setup.request(&setup.issued.token, Some("refresh_token"));
+    setup.test_success(request);
+    setup.assert_revoked(true);
+
+    let mut setup = RevokeSetup::new();
+    let request = setup.request(setup.refresh(), Some("unknown_hint"));
+    setup.test_success(request);
+    setup.assert_revoked(true);
+
+    let mut setup = RevokeSetup::new();
+    let request = setup.request(setup.refresh(), None);
+    setup.test_success(request);
+    setup.assert_revoked(true);
+}
+
+#[test]
+fn revoke_unknown_token() {
+    let mut setup = RevokeSetup::new();
+    let request = setup.request("NotAnIssuedToken", None);
+    setup.test_success(request);
+    setup.assert_revoked(false);
+}
+
+#[test]
+fn revoke_other_client_token() {
+    let mut setup = RevokeSetup::new();
+    setup.issued = setup.issuer.issue(RevokeSetup::grant("OtherClient")).unwrap();
+    let request = setup.request(&setup.issued.token, None);
+    setup.test_error(request, "unauthorized_client");
+    setup.assert_revoked(false);
+}
+
+#[test]
+fn revoke_wrong_client_passphrase() {
+    let mut setup = RevokeSetup::new();
+    setup.basic_authorization =
+        base64::encode(&format!("{}:{}", EXAMPLE_CLIENT_ID, "NotTheRightPassphrase"));
+    let request = setup.request(&setup.issued.token, None);
+    setup.test_error(request, "invalid_client");
+    setup.assert_revoked(false);
+}
+
+#[test]
+fn revoke_missing_token() {
+    let mut setup = RevokeSetup::new();
+    let request = CraftedRequest {
+        query: None,
+        urlbody: Some(vec![("token_type_hint", "access_token")].iter().to_single_value_query()),
+        auth: Some("Basic ".to_string() + &setup.basic_authorization),
+    };
+    setup.test_error(request, "invalid_request");
+}
+
+#[test]
+fn revoke_unsupported_by_issuer() {
+    let setup = RevokeSetup::new();
+    let mut signer = TokenSigner::ephemeral();
+    let token = signer.issue(RevokeSetup::grant(EXAMPLE_CLIENT_ID)).unwrap().token;
+
+    let response = revoke_flow(&setup.registrar, &mut signer)
+        .execute(setup.request(&token, None))
+        .expect("Expected non-error reponse");
+    RevokeSetup::assert_json_error(&response, "unsupported_token_type");
+}
@@ -0,0 +1,295 @@
+use crate::code_grant::accesstoken::TokenResponse;
+use crate::code_grant::token_exchange::{ACCESS_TOKEN_TYPE, ACTOR_EXTENSION, TOKEN_EXCHANGE_GRANT_TYPE};
+use crate::primitives::dpop;
+use crate::primitives::generator::RandomGenerator;
+use crate::primitives::issuer::{Issuer, TokenMap, TokenType};
+use crate::primitives::grant::{Extensions, Grant, Value};
+use crate::primitives::mtls;
+use crate::primitives::registrar::{Client, ClientMap, ClientMetadata, RegisteredUrl};
+use crate::primitives::scope::Scope;
+
+use crate::frontends::simple::endpoint::token_exchange_flow;
+
+use std::collections::HashMap;
+
+use chrono::{Duration, Utc};
+use serde_json;
+
+use super::{Body, CraftedRequest, CraftedResponse, Status, ToSingleValueQuery};
+use super::defaults::*;
+
+const EXAMPLE_ACTOR: &str = "Gateway";
+
+struct TokenExchangeSetup {
+    registrar: ClientMap,
+    issuer: TokenMap<RandomGenerator>,
+    subject_token: String,
+    actor_token: String,
+    basic_authorization: String,
+}
+
+impl TokenExchangeSetup {
+    fn new() -> Self {
+        let mut registrar = ClientMap::new();
+        registrar.register_client(Client::confidential(
+            EXAMPLE_CLIENT_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+            EXAMPLE_PASSPHRASE.as_bytes(),
+        ));
+
+        let mut issuer = TokenMap::new(RandomGenerator::new(16));
+        let subject_token = issuer
+            .issue(Self::grant("OtherClient", EXAMPLE_OWNER_ID))
+            .unwrap()
+            .token;
+        let actor_token = issuer
+            .issue(Self::grant(EXAMPLE_CLIENT_ID, EXAMPLE_ACTOR))
+            .unwrap()
+            .token;
+
+        let basic_authorization =
+            base64::encode(&format!("{}:{}", EXAMPLE_CLIENT_ID, EXAMPLE_PASSPHRASE));
+
+        TokenExchangeSetup {
+            registrar,
+            issuer,
+            subject_token,
+            actor_token,
+            basic_authorization,
+        }
+    }
+
+    fn grant(client_id: &str, owner_id: &str) -> Grant {
+        Grant {
+            client_id: client_id.to_string(),
+            owner_id: owner_id.to_string(),
+            redirect_uri: EXAMPLE_REDIRECT_URI.parse().unwrap(),
+            scope: EXAMPLE_SCOPE.parse().unwrap(),
+            until: Utc::now() + Duration::hours(1),
+            extensions: Extensions::new(),
+        }
+    }
+
+    fn request(&self, params: Vec<(&str, &str)>) -> CraftedRequest {
+        CraftedRequest {
+            query: None,
+            urlbody: Some(params.iter().to_single_value_query()),
+            auth: Some("Basic ".to_string() + &self.basic_authorization),
+        }
+    }
+
+    fn subject_request(&self, subject_token: &str, scope: Option<&str>) -> CraftedRequest {
+        let mut params = vec![
+            ("grant_type", TOKEN_EXCHANGE_GRANT_TYPE),
+            ("subject_token", subject_token),
+            ("subject_token_type", ACCESS_TOKEN_TYPE),
+        ];
+        params.extend(scope.map(|scope| ("scope", scope)));
+        self.request(params)
+    }
+
+    fn actor_request(&self, subject_token: &str) -> CraftedRequest {
+        self.request(vec![
+            ("grant_type", TOKEN_EXCHANGE_GRANT_TYPE),
+            ("subject_token", subject_token),
+            ("subject_token_type", ACCESS_TOKEN_TYPE),
+            ("actor_token", &self.actor_token),
+            ("actor_token_type", ACCESS_TOKEN_TYPE),
+        ])
+    }
+
+    fn assert_json_error(response: &CraftedResponse, error: &str) {
+        match &response.body {
+            Some(Body::Json(ref json)) => {
+                let content: HashMap<String, String> = serde_json::from_str(json).unwrap();
+                assert_eq!(content.get("error").map(String::as_str), Some(error));
+            }
+            other => panic!("Expected json encoded body, got {:?}", other),
+        }
+
+        match response.status {
+            Status::Unauthorized => (),
+            Status::BadRequest => (),
+            _ => panic!("Expected error status, got {:?}", response),
+        }
+    }
+
+    fn test_error(&mut self, request: CraftedRequest, error: &str) {
+        match token_exchange_flow(&self.registrar, &mut self.issuer).execute(request) {
+            Ok(ref response) => Self::assert_json_error(response, error),
+            resp => panic!("Expected non-error reponse, got {:?}", resp),
+        }
+    }
+
+    /// Exchange the token and return the grant of the issued token.
+    fn test_success(&mut self, request: CraftedRequest) -> Grant {
+        let response = token_exchange_flow(&self.registrar, &mut self.issuer)
+            .execute(request)
+            .expect("Expected non-error reponse");
+
+        assert_eq!(response.status, Status::Ok);
+        let token = match response.body {
+            Some(Body::Json(ref json)) => serde_json::from_str::<TokenResponse>(json).unwrap(),
+            other => panic!("Expected json encoded body, got {:?}", other),
+        };
+        assert_eq!(token.issued_token_type.as_deref(), Some(ACCESS_TOKEN_TYPE));
+
+        let access_token = token.access_token.as_ref().expect("Expected an access token");
+        let grant = self
+            .issuer
+            .recover_token(access_token)
+            .unwrap()
+            .expect("Issued token should be recoverable");
+        assert_eq!(grant.client_id, EXAMPLE_CLIENT_ID);
+        assert_eq!(grant.owner_id, EXAMPLE_OWNER_ID);
+
+        let token_type = match dpop::confirmation(&grant) {
+            Some(_) => TokenType::DPoP,
+            None => TokenType::Bearer,
+        };
+        assert_eq!(token.token_type.as_deref(), Some(token_type.as_str()));
+
+        let scope: Option<Scope> = token.scope.as_ref().map(|scope| scope.parse().unwrap());
+        assert_eq!(scope.as_ref(), Some(&grant.scope));
+        grant
+    }
+
+    fn actors(grant: &Grant) -> Option<Vec<String>> {
+        grant
+            .extensions
+            .public()
+            .find(|&(key, _)| key == ACTOR_EXTENSION)
+            .map(|(_, value)| serde_json::from_str(value.unwrap()).unwrap())
+    }
+}
+
+#[test]
+fn token_exchange_narrower_scope() {
+    let mut setup = TokenExchangeSetup::new();
+    let valid = setup.subject_request(&setup.subject_token, Some("example"));
+    let grant = setup.test_success(valid);
+    assert_eq!(grant.scope, "example".parse().unwrap());
+    assert_eq!(TokenExchangeSetup::actors(&grant), None);
+}
+
+#[test]
+fn token_exchange_subject_scope() {
+    let mut setup = TokenExchangeSetup::new();
+    let valid = setup.subject_request(&setup.subject_token, None);
+    let grant = setup.test_success(valid);
+    assert_eq!(grant.scope, EXAMPLE_SCOPE.parse().unwrap());
+}
+
+#[test]
+fn token_exchange_scope_too_large() {
+    let mut setup = TokenExchangeSetup::new();
+    let larger = setup.subject_request(&setup.subject_token, Some("example default more"));
+    setup.test_error(larger, "invalid_scope");
+}
+
+#[test]
+fn token_exchange_delegation_chain() {
+    let mut setup = TokenExchangeSetup::new();
+    let delegated = setup.actor_request(&setup.subject_token);
+    let grant = setup.test_success(delegated);
+    assert_eq!(
+        TokenExchangeSetup::actors(&grant),
+        Some(vec![EXAMPLE_ACTOR.to_string()])
+    );
+
+    // Exchanging the delegated token again extends the chain, most recent actor first.
+    setup.actor_token = setup
+        .issuer
+        .issue(TokenExchangeSetup::grant(EXAMPLE_CLIENT_ID, "Downstream"))
+        .unwrap()
+        .token;
+    let delegated_token = setup.issuer.issue(grant).unwrap().token;
+    let redelegated = setup.actor_request(&delegated_token);
+    let grant = setup.test_success(redelegated);
+    assert_eq!(
+        TokenExchangeSetup::actors(&grant),
+        Some(vec!["Downstream".to_string(), EXAMPLE_ACTOR.to_string()])
+    );
+}
+
+#[test]
+fn token_exchange_bound_subject() {
+    let mut setup = TokenExchangeSetup::new();
+
+    for key in [dpop::CONFIRMATION, mtls::CONFIRMATION].iter() {
+        let mut subject = TokenExchangeSetup::grant("OtherClient", EXAMPLE_OWNER_ID);
+        subject
+            .extensions
+            .set_raw(key.to_string(), Value::public(Some("Thumbprint".to_string())));
+        let subject_token = setup.issuer.issue(subject).unwrap().token;
+
+        // The exchanged token must not shed the binding of the subject token.
+        let grant = setup.test_success(setup.subject_request(&subject_token, None));
+        let binding: Vec<_> = grant.extensions.public().collect();
+        assert_eq!(binding, vec![(*key, Some("Thumbprint"))]);
+    }
+}
+
+#[test]
+fn token_exchange_unknown_tokens() {
+    let mut setup = TokenExchangeSetup::new();
+    let unknown_subject = setup.subject_request("NotAnIssuedToken", None);
+    setup.test_error(unknown_subject, "invalid_grant");
+
+    setup.actor_token = "NotAnIssuedToken".to_string();
+    let unknown_actor = setup.actor_request(&setup.subject_token);
+    setup.test_error(unknown_actor, "invalid_grant");
+}
+
+#[test]
+fn token_exchange_unsupported_token_type() {
+    let mut setup = TokenExchangeSetup::new();
+    let subject_token = setup.subject_token.clone();
+    let id_token = setup.request(vec![
+        ("grant_type", TOKEN_EXCHANGE_GRANT_TYPE),
+        ("subject_token", &subject_token),
+        ("subject_token_type", "urn:ietf:params:oauth:token-type:id_token"),
+    ]);
+    setup.test_error(id_token, "invalid_request");
+}
+
+#[test]
+fn token_exchange_wrong_client_passphrase() {
+    let mut setup = TokenExchangeSetup::new();
+    setup.basic_authorization =
+        base64::encode(&format!("{}:{}", EXAMPLE_CLIENT_ID, "NotTheRightPassphrase"));
+    let wrong_passphrase = setup.subject_request(&setup.subject_token, None);
+    setup.test_error(wrong_passphrase, "invalid_client");
+}
+
+#[test]
+fn token_exchange_wrong_grant_type() {
+    let mut setup = TokenExchangeSetup::new();
+    let subject_token = setup.subject_token.clone();
+    let wrong_grant_type = setup.request(vec![
+        ("grant_type", "client_credentials"),
+        ("subject_token", &subject_token),
+        ("subject_token_type", ACCESS_TOKEN_TYPE),
+    ]);
+    setup.test_error(wrong_grant_type, "unsupported_grant_type");
+}
+
+#[test]
+fn token_exchange_grant_type_not_permitted() {
+    let mut setup = TokenExchangeSetup::new();
+    setup.registrar.register_client(
+        Client::confidential(
+            EXAMPLE_CLIENT_ID,
+            RegisteredUrl::Semantic(EXAMPLE_REDIRECT_URI.parse().unwrap()),
+            EXAMPLE_SCOPE.parse().unwrap(),
+            EXAMPLE_PASSPHRASE.as_bytes(),
+        )
+        .with_metadata(ClientMetadata {
+            grant_types: vec!["client_credentials".to_string()],
+            ..ClientMetadata::default()
+        }),
+    );
+    let valid = setup.subject_request(&setup.subject_token, None);
+    setup.test_error(valid, "unauthorized_client");
+}
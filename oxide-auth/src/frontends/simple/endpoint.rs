@@ -7,18 +7,28 @@
 //! [`Generic`]: ./struct.Generic.html
 //! [`Endpoint`]: ../../endpoint/trait.Endpoint.html
 
+use crate::primitives::authenticator::OwnerAuthenticator;
 use crate::primitives::authorizer::Authorizer;
+use crate::primitives::device::DeviceAuthorizer;
+use crate::primitives::dpop::DpopVerifier;
 use crate::primitives::issuer::Issuer;
-use crate::primitives::registrar::Registrar;
+use crate::primitives::jwk::JwkSet;
+use crate::primitives::jwt::{AssertionVerifier, TrustedIssuers};
+use crate::primitives::registrar::{Registrar, RegistrarMut};
 use crate::primitives::scope::Scope;
 
-use crate::endpoint::{AccessTokenFlow, AuthorizationFlow, ResourceFlow, RefreshFlow};
+use crate::endpoint::{AccessTokenFlow, AuthorizationFlow, ClientCredentialsFlow, PasswordFlow, ResourceFlow, RefreshFlow};
+use crate::endpoint::{DeviceAuthorizationFlow, DeviceTokenFlow, DeviceVerificationFlow, JwtBearerFlow};
+use crate::endpoint::TokenExchangeFlow;
+use crate::endpoint::{IntrospectionFlow, JwksFlow, RegistrationFlow, RevokeFlow};
 use crate::endpoint::{Endpoint, Extension, OAuthError, PreGrant, Template, Scopes};
 use crate::endpoint::{OwnerConsent, OwnerSolicitor, Solicitation};
 use crate::endpoint::WebRequest;
 
 use std::marker::PhantomData;
 
+use url::Url;
+
 /// Errors either caused by the underlying web types or the library.
 #[derive(Debug)]
 pub enum Error<W: WebRequest> {
@@ -125,6 +135,69 @@ impl<E, Error> ErrorInto<E, Error> {
     }
 }
 
+/// Adds an `OwnerAuthenticator` to another endpoint.
+///
+/// All other primitives are taken from the inner endpoint. This makes it possible to use the
+/// resource owner password credentials flow with endpoints such as `Generic` that do not provide
+/// an authenticator themselves.
+pub struct WithAuthenticator<E, A> {
+    /// The endpoint providing all other primitives.
+    pub inner: E,
+
+    /// The authenticator for the credentials of resource owners.
+    pub authenticator: A,
+}
+
+/// Adds a `DeviceAuthorizer` to another endpoint.
+///
+/// All other primitives are taken from the inner endpoint. This makes it possible to use the flows
+/// of the device authorization grant with endpoints such as `Generic` that do not provide a device
+/// authorizer themselves.
+pub struct WithDeviceAuthorizer<E, D> {
+    /// The endpoint providing all other primitives.
+    pub inner: E,
+
+    /// The device authorizer storing pending device authorizations.
+    pub device_authorizer: D,
+}
+
+/// Adds an `AssertionVerifier` to another endpoint.
+///
+/// All other primitives are taken from the inner endpoint. This makes it possible to use the JWT
+/// bearer flow with endpoints such as `Generic` that do not provide a verifier themselves.
+pub struct WithAssertionVerifier<E, V> {
+    /// The endpoint providing all other primitives.
+    pub inner: E,
+
+    /// The verifier checking the assertions of trusted issuers.
+    pub verifier: V,
+}
+
+/// Adds a `DpopVerifier` to another endpoint.
+///
+/// All other primitives are taken from the inner endpoint. The access token flow of the result
+/// binds tokens to the key of a DPoP proof and its resource flow accepts such tokens.
+pub struct WithDpopVerifier<E, V> {
+    /// The endpoint providing all other primitives.
+    pub inner: E,
+
+    /// The verifier checking DPoP proofs.
+    pub verifier: V,
+}
+
+/// Adds a `RegistrarMut` to another endpoint.
+///
+/// The registrar replaces the registrar of the inner endpoint, all other primitives are taken from
+/// it. This makes it possible to use the dynamic client registration flow with endpoints such as
+/// `Generic` that only provide an immutable registrar.
+pub struct WithRegistrarMut<E, R> {
+    /// The endpoint providing all other primitives.
+    pub inner: E,
+
+    /// The registrar to which clients register themselves.
+    pub registrar: R,
+}
+
 /// Marker struct if some primitive is not provided.
 ///
 /// Used in place of other primitives when those are not provided. The exact semantics depend on
@@ -262,6 +335,44 @@ type AccessToken<'a> = Generic<
 >;
 type Refresh<'a> =
     Generic<&'a (dyn Registrar + 'a), Vacant, &'a mut (dyn Issuer + 'a), Vacant, Vacant, Vacant>;
+type ClientCredentials<'a> =
+    Generic<&'a (dyn Registrar + 'a), Vacant, &'a mut (dyn Issuer + 'a), Vacant, Vacant, Vacant>;
+type Password<'a> = WithAuthenticator<
+    Generic<&'a (dyn Registrar + 'a), Vacant, &'a mut (dyn Issuer + 'a), Vacant, Vacant, Vacant>,
+    &'a (dyn OwnerAuthenticator + 'a),
+>;
+type DeviceAuthorization<'a> = WithDeviceAuthorizer<
+    Generic<&'a (dyn Registrar + 'a), Vacant, Vacant, Vacant, Vacant, Vacant>,
+    &'a mut (dyn DeviceAuthorizer + 'a),
+>;
+type DeviceVerification<'a, W> = WithDeviceAuthorizer<
+    Generic<Vacant, Vacant, Vacant, &'a mut (dyn OwnerSolicitor<W> + 'a), Vacant, Vacant>,
+    &'a mut (dyn DeviceAuthorizer + 'a),
+>;
+type DeviceToken<'a> = WithDeviceAuthorizer<
+    Generic<&'a (dyn Registrar + 'a), Vacant, &'a mut (dyn Issuer + 'a), Vacant, Vacant, Vacant>,
+    &'a mut (dyn DeviceAuthorizer + 'a),
+>;
+type JwtBearer<'a> = WithAssertionVerifier<
+    Generic<&'a (dyn Registrar + 'a), Vacant, &'a mut (dyn Issuer + 'a), Vacant, Vacant, Vacant>,
+    &'a (dyn AssertionVerifier + 'a),
+>;
+type TokenExchange<'a> =
+    Generic<&'a (dyn Registrar + 'a), Vacant, &'a mut (dyn Issuer + 'a), Vacant, Vacant, Vacant>;
+type Revoke<'a> =
+    Generic<&'a (dyn Registrar + 'a), Vacant, &'a mut (dyn Issuer + 'a), Vacant, Vacant, Vacant>;
+type Introspection<'a> = Generic<
+    &'a (dyn Registrar + 'a),
+    Vacant,
+    &'a mut (dyn Issuer + 'a),
+    Vacant,
+    &'a [Scope],
+    Vacant,
+>;
+type Registration<'a> = WithRegistrarMut<
+    Generic<Vacant, Vacant, Vacant, Vacant, Vacant, Vacant>,
+    &'a mut (dyn RegistrarMut + 'a),
+>;
 type Resource<'a> = Generic<Vacant, Vacant, &'a mut (dyn Issuer + 'a), Vacant, &'a [Scope], Vacant>;
 
 /// Create an ad-hoc authorization flow.
@@ -385,6 +496,325 @@ where
     }
 }
 
+/// Create an ad-hoc client credentials flow.
+///
+/// Since all necessary primitives are expected in the function syntax, this is guaranteed to never
+/// fail or panic, compared to preparing one with `ClientCredentialsFlow`.
+///
+/// But this is not as versatile and extensible, so it should be used with care.  The fact that it
+/// only takes references is a conscious choice to maintain forwards portability while encouraging
+/// the transition to custom `Endpoint` implementations instead.
+pub fn client_credentials_flow<'a, W>(
+    registrar: &'a dyn Registrar, issuer: &'a mut dyn Issuer,
+) -> ClientCredentialsFlow<ClientCredentials<'a>, W>
+where
+    W: WebRequest,
+    W::Response: Default,
+{
+    let flow = ClientCredentialsFlow::prepare(Generic {
+        registrar,
+        authorizer: Vacant,
+        issuer,
+        solicitor: Vacant,
+        scopes: Vacant,
+        response: Vacant,
+    });
+
+    match flow {
+        Err(_) => unreachable!(),
+        Ok(flow) => flow,
+    }
+}
+
+/// Create an ad-hoc token exchange flow.
+///
+/// Since all necessary primitives are expected in the function syntax, this is guaranteed to never
+/// fail or panic, compared to preparing one with `TokenExchangeFlow`.
+///
+/// But this is not as versatile and extensible, so it should be used with care.  The fact that it
+/// only takes references is a conscious choice to maintain forwards portability while encouraging
+/// the transition to custom `Endpoint` implementations instead.
+pub fn token_exchange_flow<'a, W>(
+    registrar: &'a dyn Registrar, issuer: &'a mut dyn Issuer,
+) -> TokenExchangeFlow<TokenExchange<'a>, W>
+where
+    W: WebRequest,
+    W::Response: Default,
+{
+    let flow = TokenExchangeFlow::prepare(Generic {
+        registrar,
+        authorizer: Vacant,
+        issuer,
+        solicitor: Vacant,
+        scopes: Vacant,
+        response: Vacant,
+    });
+
+    match flow {
+        Err(_) => unreachable!(),
+        Ok(flow) => flow,
+    }
+}
+
+/// Create an ad-hoc token introspection flow.
+///
+/// Since all necessary primitives are expected in the function syntax, this is guaranteed to never
+/// fail or panic, compared to preparing one with `IntrospectionFlow`. Resource servers presenting
+/// a bearer token instead of client credentials need to have been granted one of the `scopes`.
+///
+/// But this is not as versatile and extensible, so it should be used with care.  The fact that it
+/// only takes references is a conscious choice to maintain forwards portability while encouraging
+/// the transition to custom `Endpoint` implementations instead.
+pub fn introspection_flow<'a, W>(
+    registrar: &'a dyn Registrar, issuer: &'a mut dyn Issuer, scopes: &'a [Scope],
+) -> IntrospectionFlow<Introspection<'a>, W>
+where
+    W: WebRequest,
+    W::Response: Default,
+{
+    let flow = IntrospectionFlow::prepare(Generic {
+        registrar,
+        authorizer: Vacant,
+        issuer,
+        solicitor: Vacant,
+        scopes,
+        response: Vacant,
+    });
+
+    match flow {
+        Err(_) => unreachable!(),
+        Ok(flow) => flow,
+    }
+}
+
+/// Create an ad-hoc token revocation flow.
+///
+/// Since all necessary primitives are expected in the function syntax, this is guaranteed to never
+/// fail or panic, compared to preparing one with `RevokeFlow`.
+///
+/// But this is not as versatile and extensible, so it should be used with care.  The fact that it
+/// only takes references is a conscious choice to maintain forwards portability while encouraging
+/// the transition to custom `Endpoint` implementations instead.
+pub fn revoke_flow<'a, W>(registrar: &'a dyn Registrar, issuer: &'a mut dyn Issuer) -> RevokeFlow<Revoke<'a>, W>
+where
+    W: WebRequest,
+    W::Response: Default,
+{
+    let flow = RevokeFlow::prepare(Generic {
+        registrar,
+        authorizer: Vacant,
+        issuer,
+        solicitor: Vacant,
+        scopes: Vacant,
+        response: Vacant,
+    });
+
+    match flow {
+        Err(_) => unreachable!(),
+        Ok(flow) => flow,
+    }
+}
+
+/// Create an ad-hoc dynamic client registration flow.
+///
+/// Since all necessary primitives are expected in the function syntax, this is guaranteed to never
+/// fail or panic, compared to preparing one with `RegistrationFlow`.
+///
+/// But this is not as versatile and extensible, so it should be used with care.  The fact that it
+/// only takes references is a conscious choice to maintain forwards portability while encouraging
+/// the transition to custom `Endpoint` implementations instead.
+pub fn registration_flow<'a, W>(
+    registrar: &'a mut dyn RegistrarMut, registration_endpoint: Url,
+) -> RegistrationFlow<Registration<'a>, W>
+where
+    W: WebRequest,
+    W::Response: Default,
+{
+    let endpoint = WithRegistrarMut {
+        inner: Generic {
+            registrar: Vacant,
+            authorizer: Vacant,
+            issuer: Vacant,
+            solicitor: Vacant,
+            scopes: Vacant,
+            response: Vacant,
+        },
+        registrar,
+    };
+
+    match RegistrationFlow::prepare(endpoint, registration_endpoint) {
+        Err(_) => unreachable!(),
+        Ok(flow) => flow,
+    }
+}
+
+/// Create an ad-hoc resource owner password credentials flow.
+///
+/// Since all necessary primitives are expected in the function syntax, this is guaranteed to never
+/// fail or panic, compared to preparing one with `PasswordFlow`.
+///
+/// But this is not as versatile and extensible, so it should be used with care.  The fact that it
+/// only takes references is a conscious choice to maintain forwards portability while encouraging
+/// the transition to custom `Endpoint` implementations instead.
+pub fn password_flow<'a, W>(
+    registrar: &'a dyn Registrar, authenticator: &'a dyn OwnerAuthenticator, issuer: &'a mut dyn Issuer,
+) -> PasswordFlow<Password<'a>, W>
+where
+    W: WebRequest,
+    W::Response: Default,
+{
+    let flow = PasswordFlow::prepare(WithAuthenticator {
+        inner: Generic {
+            registrar,
+            authorizer: Vacant,
+            issuer,
+            solicitor: Vacant,
+            scopes: Vacant,
+            response: Vacant,
+        },
+        authenticator,
+    });
+
+    match flow {
+        Err(_) => unreachable!(),
+        Ok(flow) => flow,
+    }
+}
+
+/// Create an ad-hoc device authorization flow.
+///
+/// Since all necessary primitives are expected in the function syntax, this is guaranteed to never
+/// fail or panic, compared to preparing one with `DeviceAuthorizationFlow`.
+///
+/// But this is not as versatile and extensible, so it should be used with care.  The fact that it
+/// only takes references is a conscious choice to maintain forwards portability while encouraging
+/// the transition to custom `Endpoint` implementations instead.
+pub fn device_authorization_flow<'a, W>(
+    registrar: &'a dyn Registrar, device_authorizer: &'a mut dyn DeviceAuthorizer, verification_uri: Url,
+) -> DeviceAuthorizationFlow<DeviceAuthorization<'a>, W>
+where
+    W: WebRequest,
+    W::Response: Default,
+{
+    let endpoint = WithDeviceAuthorizer {
+        inner: Generic {
+            registrar,
+            authorizer: Vacant,
+            issuer: Vacant,
+            solicitor: Vacant,
+            scopes: Vacant,
+            response: Vacant,
+        },
+        device_authorizer,
+    };
+
+    match DeviceAuthorizationFlow::prepare(endpoint, verification_uri) {
+        Err(_) => unreachable!(),
+        Ok(flow) => flow,
+    }
+}
+
+/// Create an ad-hoc device verification flow.
+///
+/// Since all necessary primitives are expected in the function syntax, this is guaranteed to never
+/// fail or panic, compared to preparing one with `DeviceVerificationFlow`.
+///
+/// But this is not as versatile and extensible, so it should be used with care.  The fact that it
+/// only takes references is a conscious choice to maintain forwards portability while encouraging
+/// the transition to custom `Endpoint` implementations instead.
+pub fn device_verification_flow<'a, W>(
+    device_authorizer: &'a mut dyn DeviceAuthorizer, solicitor: &'a mut dyn OwnerSolicitor<W>,
+) -> DeviceVerificationFlow<DeviceVerification<'a, W>, W>
+where
+    W: WebRequest,
+    W::Response: Default,
+{
+    let flow = DeviceVerificationFlow::prepare(WithDeviceAuthorizer {
+        inner: Generic {
+            registrar: Vacant,
+            authorizer: Vacant,
+            issuer: Vacant,
+            solicitor,
+            scopes: Vacant,
+            response: Vacant,
+        },
+        device_authorizer,
+    });
+
+    match flow {
+        Err(_) => unreachable!(),
+        Ok(flow) => flow,
+    }
+}
+
+/// Create an ad-hoc device token flow.
+///
+/// Since all necessary primitives are expected in the function syntax, this is guaranteed to never
+/// fail or panic, compared to preparing one with `DeviceTokenFlow`.
+///
+/// But this is not as versatile and extensible, so it should be used with care.  The fact that it
+/// only takes references is a conscious choice to maintain forwards portability while encouraging
+/// the transition to custom `Endpoint` implementations instead.
+pub fn device_token_flow<'a, W>(
+    registrar: &'a dyn Registrar, device_authorizer: &'a mut dyn DeviceAuthorizer,
+    issuer: &'a mut dyn Issuer,
+) -> DeviceTokenFlow<DeviceToken<'a>, W>
+where
+    W: WebRequest,
+    W::Response: Default,
+{
+    let flow = DeviceTokenFlow::prepare(WithDeviceAuthorizer {
+        inner: Generic {
+            registrar,
+            authorizer: Vacant,
+            issuer,
+            solicitor: Vacant,
+            scopes: Vacant,
+            response: Vacant,
+        },
+        device_authorizer,
+    });
+
+    match flow {
+        Err(_) => unreachable!(),
+        Ok(flow) => flow,
+    }
+}
+
+/// Create an ad-hoc JWT bearer flow.
+///
+/// Since all necessary primitives are expected in the function syntax, this is guaranteed to never
+/// fail or panic, compared to preparing one with `JwtBearerFlow`.
+///
+/// But this is not as versatile and extensible, so it should be used with care.  The fact that it
+/// only takes references is a conscious choice to maintain forwards portability while encouraging
+/// the transition to custom `Endpoint` implementations instead.
+pub fn jwt_bearer_flow<'a, W>(
+    registrar: &'a dyn Registrar, verifier: &'a dyn AssertionVerifier, issuer: &'a mut dyn Issuer,
+    trusted: TrustedIssuers,
+) -> JwtBearerFlow<JwtBearer<'a>, W>
+where
+    W: WebRequest,
+    W::Response: Default,
+{
+    let endpoint = WithAssertionVerifier {
+        inner: Generic {
+            registrar,
+            authorizer: Vacant,
+            issuer,
+            solicitor: Vacant,
+            scopes: Vacant,
+            response: Vacant,
+        },
+        verifier,
+    };
+
+    match JwtBearerFlow::prepare(endpoint, trusted) {
+        Err(_) => unreachable!(),
+        Ok(flow) => flow,
+    }
+}
+
 impl<R, A, I, O, C, L> Generic<R, A, I, O, C, L> {
     /// Change the used solicitor.
     pub fn with_solicitor<N>(self, new_solicitor: N) -> Generic<R, A, I, N, C, L> {
@@ -458,40 +888,152 @@ impl<R, A, I, O, C, L> Generic<R, A, I, O, C, L> {
         }
     }
 
-    /// Create a resource access flow.
+    /// Create a client credentials flow.
     ///
-    /// Opposed to `ResourceFlow::prepare` this statically ensures that the construction succeeds.
-    pub fn resource_flow<W: WebRequest>(self) -> ResourceFlow<Self, W>
+    /// Opposed to `ClientCredentialsFlow::prepare` this statically ensures that the construction
+    /// succeeds.
+    pub fn client_credentials_flow<W: WebRequest>(self) -> ClientCredentialsFlow<Self, W>
     where
         Self: Endpoint<W>,
+        R: Registrar,
         I: Issuer,
     {
-        match ResourceFlow::prepare(self) {
+        match ClientCredentialsFlow::prepare(self) {
             Ok(flow) => flow,
             Err(_) => unreachable!(),
         }
     }
 
-    /// Check, statically, that this is an endpoint for some request.
+    /// Create a token exchange flow.
     ///
-    /// This is mainly a utility method intended for compilation and integration tests.
-    pub fn assert<W: WebRequest>(self) -> Self
+    /// Opposed to `TokenExchangeFlow::prepare` this statically ensures that the construction
+    /// succeeds.
+    pub fn token_exchange_flow<W: WebRequest>(self) -> TokenExchangeFlow<Self, W>
     where
         Self: Endpoint<W>,
+        R: Registrar,
+        I: Issuer,
     {
-        self
+        match TokenExchangeFlow::prepare(self) {
+            Ok(flow) => flow,
+            Err(_) => unreachable!(),
+        }
     }
-}
 
-impl<W: WebRequest> Error<W> {
-    /// Convert into a single error type.
+    /// Create a token introspection flow.
     ///
-    /// Note that the additional information whether the error occurred in the web components or
-    /// during the flow needs to be implicitely contained in the types. Otherwise, this information
-    /// is lost and you should use or provide a `From<Error<W>>` implementation instead. This
-    /// method is still useful for frontends providing a standard error type that interacts with
-    /// their web server library.
-    pub fn pack<P>(self) -> P
+    /// Opposed to `IntrospectionFlow::prepare` this statically ensures that the construction
+    /// succeeds.
+    pub fn introspection_flow<W: WebRequest>(self) -> IntrospectionFlow<Self, W>
+    where
+        Self: Endpoint<W>,
+        R: Registrar,
+        I: Issuer,
+    {
+        match IntrospectionFlow::prepare(self) {
+            Ok(flow) => flow,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Create a token revocation flow.
+    ///
+    /// Opposed to `RevokeFlow::prepare` this statically ensures that the construction succeeds.
+    pub fn revoke_flow<W: WebRequest>(self) -> RevokeFlow<Self, W>
+    where
+        Self: Endpoint<W>,
+        R: Registrar,
+        I: Issuer,
+    {
+        match RevokeFlow::prepare(self) {
+            Ok(flow) => flow,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Create a flow publishing the given keys as a JSON Web Key Set.
+    pub fn jwks_flow<W: WebRequest>(self, keys: JwkSet) -> JwksFlow<Self, W>
+    where
+        Self: Endpoint<W>,
+    {
+        JwksFlow::new(self, keys)
+    }
+
+    /// Add an authenticator for resource owners.
+    ///
+    /// The result can be used for the resource owner password credentials flow.
+    pub fn with_authenticator<N>(self, authenticator: N) -> WithAuthenticator<Self, N> {
+        WithAuthenticator {
+            inner: self,
+            authenticator,
+        }
+    }
+
+    /// Add an assertion verifier.
+    ///
+    /// The result can be used for the JWT bearer flow.
+    pub fn with_assertion_verifier<N>(self, verifier: N) -> WithAssertionVerifier<Self, N> {
+        WithAssertionVerifier { inner: self, verifier }
+    }
+
+    /// Add a verifier for DPoP proofs.
+    ///
+    /// The result issues and accepts tokens bound to the keys of clients.
+    pub fn with_dpop_verifier<N>(self, verifier: N) -> WithDpopVerifier<Self, N> {
+        WithDpopVerifier { inner: self, verifier }
+    }
+
+    /// Add a registrar to which clients can register themselves.
+    ///
+    /// The result can be used for the dynamic client registration flow.
+    pub fn with_registrar_mut<N>(self, registrar: N) -> WithRegistrarMut<Self, N> {
+        WithRegistrarMut { inner: self, registrar }
+    }
+
+    /// Add a device authorizer.
+    ///
+    /// The result can be used for the flows of the device authorization grant.
+    pub fn with_device_authorizer<N>(self, device_authorizer: N) -> WithDeviceAuthorizer<Self, N> {
+        WithDeviceAuthorizer {
+            inner: self,
+            device_authorizer,
+        }
+    }
+
+    /// Create a resource access flow.
+    ///
+    /// Opposed to `ResourceFlow::prepare` this statically ensures that the construction succeeds.
+    pub fn resource_flow<W: WebRequest>(self) -> ResourceFlow<Self, W>
+    where
+        Self: Endpoint<W>,
+        I: Issuer,
+    {
+        match ResourceFlow::prepare(self) {
+            Ok(flow) => flow,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Check, statically, that this is an endpoint for some request.
+    ///
+    /// This is mainly a utility method intended for compilation and integration tests.
+    pub fn assert<W: WebRequest>(self) -> Self
+    where
+        Self: Endpoint<W>,
+    {
+        self
+    }
+}
+
+impl<W: WebRequest> Error<W> {
+    /// Convert into a single error type.
+    ///
+    /// Note that the additional information whether the error occurred in the web components or
+    /// during the flow needs to be implicitely contained in the types. Otherwise, this information
+    /// is lost and you should use or provide a `From<Error<W>>` implementation instead. This
+    /// method is still useful for frontends providing a standard error type that interacts with
+    /// their web server library.
+    pub fn pack<P>(self) -> P
     where
         OAuthError: Into<P>,
         W::Error: Into<P>,
@@ -515,6 +1057,10 @@ where
         self.0.registrar()
     }
 
+    fn registrar_mut(&mut self) -> Option<&mut dyn RegistrarMut> {
+        self.0.registrar_mut()
+    }
+
     fn authorizer_mut(&mut self) -> Option<&mut dyn Authorizer> {
         self.0.authorizer_mut()
     }
@@ -531,6 +1077,22 @@ where
         self.0.scopes()
     }
 
+    fn owner_authenticator(&self) -> Option<&dyn OwnerAuthenticator> {
+        self.0.owner_authenticator()
+    }
+
+    fn device_authorizer_mut(&mut self) -> Option<&mut dyn DeviceAuthorizer> {
+        self.0.device_authorizer_mut()
+    }
+
+    fn assertion_verifier(&self) -> Option<&dyn AssertionVerifier> {
+        self.0.assertion_verifier()
+    }
+
+    fn dpop_verifier(&self) -> Option<&dyn DpopVerifier> {
+        self.0.dpop_verifier()
+    }
+
     fn response(&mut self, request: &mut W, kind: Template) -> Result<W::Response, Self::Error> {
         self.0.response(request, kind).map_err(Into::into)
     }
@@ -593,6 +1155,473 @@ where
     }
 }
 
+impl<R, A, I, O, C, L, N> WithAuthenticator<Generic<R, A, I, O, C, L>, N> {
+    /// Create a resource owner password credentials flow.
+    ///
+    /// Opposed to `PasswordFlow::prepare` this statically ensures that the construction succeeds.
+    pub fn password_flow<W: WebRequest>(self) -> PasswordFlow<Self, W>
+    where
+        Self: Endpoint<W>,
+        R: Registrar,
+        I: Issuer,
+        N: OwnerAuthenticator,
+    {
+        match PasswordFlow::prepare(self) {
+            Ok(flow) => flow,
+            Err(_) => unreachable!(),
+        }
+    }
+}
+
+impl<W, E, A> Endpoint<W> for WithAuthenticator<E, A>
+where
+    W: WebRequest,
+    E: Endpoint<W>,
+    A: OwnerAuthenticator,
+{
+    type Error = E::Error;
+
+    fn registrar(&self) -> Option<&dyn Registrar> {
+        self.inner.registrar()
+    }
+
+    fn registrar_mut(&mut self) -> Option<&mut dyn RegistrarMut> {
+        self.inner.registrar_mut()
+    }
+
+    fn authorizer_mut(&mut self) -> Option<&mut dyn Authorizer> {
+        self.inner.authorizer_mut()
+    }
+
+    fn issuer_mut(&mut self) -> Option<&mut dyn Issuer> {
+        self.inner.issuer_mut()
+    }
+
+    fn owner_solicitor(&mut self) -> Option<&mut dyn OwnerSolicitor<W>> {
+        self.inner.owner_solicitor()
+    }
+
+    fn scopes(&mut self) -> Option<&mut dyn Scopes<W>> {
+        self.inner.scopes()
+    }
+
+    fn owner_authenticator(&self) -> Option<&dyn OwnerAuthenticator> {
+        Some(&self.authenticator)
+    }
+
+    fn device_authorizer_mut(&mut self) -> Option<&mut dyn DeviceAuthorizer> {
+        self.inner.device_authorizer_mut()
+    }
+
+    fn assertion_verifier(&self) -> Option<&dyn AssertionVerifier> {
+        self.inner.assertion_verifier()
+    }
+
+    fn dpop_verifier(&self) -> Option<&dyn DpopVerifier> {
+        self.inner.dpop_verifier()
+    }
+
+    fn response(&mut self, request: &mut W, kind: Template) -> Result<W::Response, Self::Error> {
+        self.inner.response(request, kind)
+    }
+
+    fn error(&mut self, err: OAuthError) -> Self::Error {
+        self.inner.error(err)
+    }
+
+    fn web_error(&mut self, err: W::Error) -> Self::Error {
+        self.inner.web_error(err)
+    }
+
+    fn extension(&mut self) -> Option<&mut dyn Extension> {
+        self.inner.extension()
+    }
+}
+
+impl<R, A, I, O, C, L, D> WithDeviceAuthorizer<Generic<R, A, I, O, C, L>, D> {
+    /// Create a device authorization flow.
+    ///
+    /// Opposed to `DeviceAuthorizationFlow::prepare` this statically ensures that the
+    /// construction succeeds.
+    pub fn device_authorization_flow<W: WebRequest>(
+        self, verification_uri: Url,
+    ) -> DeviceAuthorizationFlow<Self, W>
+    where
+        Self: Endpoint<W>,
+        R: Registrar,
+        D: DeviceAuthorizer,
+    {
+        match DeviceAuthorizationFlow::prepare(self, verification_uri) {
+            Ok(flow) => flow,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Create a device verification flow.
+    ///
+    /// Opposed to `DeviceVerificationFlow::prepare` this statically ensures that the construction
+    /// succeeds.
+    pub fn device_verification_flow<W: WebRequest>(self) -> DeviceVerificationFlow<Self, W>
+    where
+        Self: Endpoint<W>,
+        O: OwnerSolicitor<W>,
+        D: DeviceAuthorizer,
+    {
+        match DeviceVerificationFlow::prepare(self) {
+            Ok(flow) => flow,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Create a device token flow.
+    ///
+    /// Opposed to `DeviceTokenFlow::prepare` this statically ensures that the construction
+    /// succeeds.
+    pub fn device_token_flow<W: WebRequest>(self) -> DeviceTokenFlow<Self, W>
+    where
+        Self: Endpoint<W>,
+        R: Registrar,
+        I: Issuer,
+        D: DeviceAuthorizer,
+    {
+        match DeviceTokenFlow::prepare(self) {
+            Ok(flow) => flow,
+            Err(_) => unreachable!(),
+        }
+    }
+}
+
+impl<R, A, I, O, C, L, V> WithAssertionVerifier<Generic<R, A, I, O, C, L>, V> {
+    /// Create a JWT bearer flow.
+    ///
+    /// Opposed to `JwtBearerFlow::prepare` this statically ensures that the construction
+    /// succeeds.
+    pub fn jwt_bearer_flow<W: WebRequest>(self, trusted: TrustedIssuers) -> JwtBearerFlow<Self, W>
+    where
+        Self: Endpoint<W>,
+        R: Registrar,
+        I: Issuer,
+        V: AssertionVerifier,
+    {
+        match JwtBearerFlow::prepare(self, trusted) {
+            Ok(flow) => flow,
+            Err(_) => unreachable!(),
+        }
+    }
+}
+
+impl<W, E, V> Endpoint<W> for WithAssertionVerifier<E, V>
+where
+    W: WebRequest,
+    E: Endpoint<W>,
+    V: AssertionVerifier,
+{
+    type Error = E::Error;
+
+    fn registrar(&self) -> Option<&dyn Registrar> {
+        self.inner.registrar()
+    }
+
+    fn registrar_mut(&mut self) -> Option<&mut dyn RegistrarMut> {
+        self.inner.registrar_mut()
+    }
+
+    fn authorizer_mut(&mut self) -> Option<&mut dyn Authorizer> {
+        self.inner.authorizer_mut()
+    }
+
+    fn issuer_mut(&mut self) -> Option<&mut dyn Issuer> {
+        self.inner.issuer_mut()
+    }
+
+    fn owner_solicitor(&mut self) -> Option<&mut dyn OwnerSolicitor<W>> {
+        self.inner.owner_solicitor()
+    }
+
+    fn scopes(&mut self) -> Option<&mut dyn Scopes<W>> {
+        self.inner.scopes()
+    }
+
+    fn owner_authenticator(&self) -> Option<&dyn OwnerAuthenticator> {
+        self.inner.owner_authenticator()
+    }
+
+    fn device_authorizer_mut(&mut self) -> Option<&mut dyn DeviceAuthorizer> {
+        self.inner.device_authorizer_mut()
+    }
+
+    fn assertion_verifier(&self) -> Option<&dyn AssertionVerifier> {
+        Some(&self.verifier)
+    }
+
+    fn dpop_verifier(&self) -> Option<&dyn DpopVerifier> {
+        self.inner.dpop_verifier()
+    }
+
+    fn response(&mut self, request: &mut W, kind: Template) -> Result<W::Response, Self::Error> {
+        self.inner.response(request, kind)
+    }
+
+    fn error(&mut self, err: OAuthError) -> Self::Error {
+        self.inner.error(err)
+    }
+
+    fn web_error(&mut self, err: W::Error) -> Self::Error {
+        self.inner.web_error(err)
+    }
+
+    fn extension(&mut self) -> Option<&mut dyn Extension> {
+        self.inner.extension()
+    }
+}
+
+impl<W, E, D> Endpoint<W> for WithDeviceAuthorizer<E, D>
+where
+    W: WebRequest,
+    E: Endpoint<W>,
+    D: DeviceAuthorizer,
+{
+    type Error = E::Error;
+
+    fn registrar(&self) -> Option<&dyn Registrar> {
+        self.inner.registrar()
+    }
+
+    fn registrar_mut(&mut self) -> Option<&mut dyn RegistrarMut> {
+        self.inner.registrar_mut()
+    }
+
+    fn authorizer_mut(&mut self) -> Option<&mut dyn Authorizer> {
+        self.inner.authorizer_mut()
+    }
+
+    fn issuer_mut(&mut self) -> Option<&mut dyn Issuer> {
+        self.inner.issuer_mut()
+    }
+
+    fn owner_solicitor(&mut self) -> Option<&mut dyn OwnerSolicitor<W>> {
+        self.inner.owner_solicitor()
+    }
+
+    fn scopes(&mut self) -> Option<&mut dyn Scopes<W>> {
+        self.inner.scopes()
+    }
+
+    fn owner_authenticator(&self) -> Option<&dyn OwnerAuthenticator> {
+        self.inner.owner_authenticator()
+    }
+
+    fn device_authorizer_mut(&mut self) -> Option<&mut dyn DeviceAuthorizer> {
+        Some(&mut self.device_authorizer)
+    }
+
+    fn assertion_verifier(&self) -> Option<&dyn AssertionVerifier> {
+        self.inner.assertion_verifier()
+    }
+
+    fn dpop_verifier(&self) -> Option<&dyn DpopVerifier> {
+        self.inner.dpop_verifier()
+    }
+
+    fn response(&mut self, request: &mut W, kind: Template) -> Result<W::Response, Self::Error> {
+        self.inner.response(request, kind)
+    }
+
+    fn error(&mut self, err: OAuthError) -> Self::Error {
+        self.inner.error(err)
+    }
+
+    fn web_error(&mut self, err: W::Error) -> Self::Error {
+        self.inner.web_error(err)
+    }
+
+    fn extension(&mut self) -> Option<&mut dyn Extension> {
+        self.inner.extension()
+    }
+}
+
+impl<R, A, I, O, C, L, V> WithDpopVerifier<Generic<R, A, I, O, C, L>, V> {
+    /// Create an access token flow issuing tokens bound to DPoP keys.
+    ///
+    /// Opposed to `AccessTokenFlow::prepare` this statically ensures that the construction
+    /// succeeds.
+    pub fn access_token_flow<W: WebRequest>(self) -> AccessTokenFlow<Self, W>
+    where
+        Self: Endpoint<W>,
+        R: Registrar,
+        A: Authorizer,
+        I: Issuer,
+        V: DpopVerifier,
+    {
+        match AccessTokenFlow::prepare(self) {
+            Ok(flow) => flow,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Create a resource access flow accepting tokens bound to DPoP keys.
+    ///
+    /// Opposed to `ResourceFlow::prepare` this statically ensures that the construction succeeds.
+    pub fn resource_flow<W: WebRequest>(self) -> ResourceFlow<Self, W>
+    where
+        Self: Endpoint<W>,
+        I: Issuer,
+        V: DpopVerifier,
+    {
+        match ResourceFlow::prepare(self) {
+            Ok(flow) => flow,
+            Err(_) => unreachable!(),
+        }
+    }
+}
+
+impl<W, E, V> Endpoint<W> for WithDpopVerifier<E, V>
+where
+    W: WebRequest,
+    E: Endpoint<W>,
+    V: DpopVerifier,
+{
+    type Error = E::Error;
+
+    fn registrar(&self) -> Option<&dyn Registrar> {
+        self.inner.registrar()
+    }
+
+    fn registrar_mut(&mut self) -> Option<&mut dyn RegistrarMut> {
+        self.inner.registrar_mut()
+    }
+
+    fn authorizer_mut(&mut self) -> Option<&mut dyn Authorizer> {
+        self.inner.authorizer_mut()
+    }
+
+    fn issuer_mut(&mut self) -> Option<&mut dyn Issuer> {
+        self.inner.issuer_mut()
+    }
+
+    fn owner_solicitor(&mut self) -> Option<&mut dyn OwnerSolicitor<W>> {
+        self.inner.owner_solicitor()
+    }
+
+    fn scopes(&mut self) -> Option<&mut dyn Scopes<W>> {
+        self.inner.scopes()
+    }
+
+    fn owner_authenticator(&self) -> Option<&dyn OwnerAuthenticator> {
+        self.inner.owner_authenticator()
+    }
+
+    fn device_authorizer_mut(&mut self) -> Option<&mut dyn DeviceAuthorizer> {
+        self.inner.device_authorizer_mut()
+    }
+
+    fn assertion_verifier(&self) -> Option<&dyn AssertionVerifier> {
+        self.inner.assertion_verifier()
+    }
+
+    fn dpop_verifier(&self) -> Option<&dyn DpopVerifier> {
+        Some(&self.verifier)
+    }
+
+    fn response(&mut self, request: &mut W, kind: Template) -> Result<W::Response, Self::Error> {
+        self.inner.response(request, kind)
+    }
+
+    fn error(&mut self, err: OAuthError) -> Self::Error {
+        self.inner.error(err)
+    }
+
+    fn web_error(&mut self, err: W::Error) -> Self::Error {
+        self.inner.web_error(err)
+    }
+
+    fn extension(&mut self) -> Option<&mut dyn Extension> {
+        self.inner.extension()
+    }
+}
+
+impl<R, A, I, O, C, L, N> WithRegistrarMut<Generic<R, A, I, O, C, L>, N> {
+    /// Create a dynamic client registration flow.
+    ///
+    /// Opposed to `RegistrationFlow::prepare` this statically ensures that the construction
+    /// succeeds.
+    pub fn registration_flow<W: WebRequest>(self, registration_endpoint: Url) -> RegistrationFlow<Self, W>
+    where
+        Self: Endpoint<W>,
+        N: RegistrarMut,
+    {
+        match RegistrationFlow::prepare(self, registration_endpoint) {
+            Ok(flow) => flow,
+            Err(_) => unreachable!(),
+        }
+    }
+}
+
+impl<W, E, N> Endpoint<W> for WithRegistrarMut<E, N>
+where
+    W: WebRequest,
+    E: Endpoint<W>,
+    N: RegistrarMut,
+{
+    type Error = E::Error;
+
+    fn registrar(&self) -> Option<&dyn Registrar> {
+        Some(&self.registrar)
+    }
+
+    fn registrar_mut(&mut self) -> Option<&mut dyn RegistrarMut> {
+        Some(&mut self.registrar)
+    }
+
+    fn authorizer_mut(&mut self) -> Option<&mut dyn Authorizer> {
+        self.inner.authorizer_mut()
+    }
+
+    fn issuer_mut(&mut self) -> Option<&mut dyn Issuer> {
+        self.inner.issuer_mut()
+    }
+
+    fn owner_solicitor(&mut self) -> Option<&mut dyn OwnerSolicitor<W>> {
+        self.inner.owner_solicitor()
+    }
+
+    fn scopes(&mut self) -> Option<&mut dyn Scopes<W>> {
+        self.inner.scopes()
+    }
+
+    fn owner_authenticator(&self) -> Option<&dyn OwnerAuthenticator> {
+        self.inner.owner_authenticator()
+    }
+
+    fn device_authorizer_mut(&mut self) -> Option<&mut dyn DeviceAuthorizer> {
+        self.inner.device_authorizer_mut()
+    }
+
+    fn assertion_verifier(&self) -> Option<&dyn AssertionVerifier> {
+        self.inner.assertion_verifier()
+    }
+
+    fn dpop_verifier(&self) -> Option<&dyn DpopVerifier> {
+        self.inner.dpop_verifier()
+    }
+
+    fn response(&mut self, request: &mut W, kind: Template) -> Result<W::Response, Self::Error> {
+        self.inner.response(request, kind)
+    }
+
+    fn error(&mut self, err: OAuthError) -> Self::Error {
+        self.inner.error(err)
+    }
+
+    fn web_error(&mut self, err: W::Error) -> Self::Error {
+        self.inner.web_error(err)
+    }
+
+    fn extension(&mut self) -> Option<&mut dyn Extension> {
+        self.inner.extension()
+    }
+}
+
 impl<T: Registrar> OptRegistrar for T {
     fn opt_ref(&self) -> Option<&dyn Registrar> {
         Some(self)
@@ -1,6 +1,10 @@
 use crate::endpoint::{Endpoint, Extension, OAuthError, OwnerSolicitor, Scopes, Template, WebRequest};
+use crate::primitives::authenticator::OwnerAuthenticator;
 use crate::primitives::authorizer::Authorizer;
+use crate::primitives::device::DeviceAuthorizer;
+use crate::primitives::dpop::DpopVerifier;
 use crate::primitives::issuer::Issuer;
+use crate::primitives::jwt::AssertionVerifier;
 use crate::primitives::registrar::Registrar;
 
 use super::AddonList;
@@ -73,6 +77,22 @@ where
         self.inner.scopes()
     }
 
+    fn owner_authenticator(&self) -> Option<&dyn OwnerAuthenticator> {
+        self.inner.owner_authenticator()
+    }
+
+    fn device_authorizer_mut(&mut self) -> Option<&mut dyn DeviceAuthorizer> {
+        self.inner.device_authorizer_mut()
+    }
+
+    fn assertion_verifier(&self) -> Option<&dyn AssertionVerifier> {
+        self.inner.assertion_verifier()
+    }
+
+    fn dpop_verifier(&self) -> Option<&dyn DpopVerifier> {
+        self.inner.dpop_verifier()
+    }
+
     fn response(
         &mut self, request: &mut Request, kind: Template,
     ) -> Result<Request::Response, Self::Error> {
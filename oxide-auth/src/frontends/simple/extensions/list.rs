@@ -4,6 +4,7 @@ use std::sync::Arc;
 use super::{AuthorizationAddon, AccessTokenAddon, AddonResult};
 use crate::code_grant::accesstoken::{Extension as AccessTokenExtension, Request};
 use crate::code_grant::authorization::{Extension as AuthorizationExtension, Request as AuthRequest};
+use crate::code_grant::metadata::{Extension as MetadataExtension, Metadata};
 use crate::endpoint::Extension;
 use crate::primitives::grant::{Extensions, GrantExtension};
 
@@ -68,6 +69,10 @@ impl Extension for AddonList {
     fn access_token(&mut self) -> Option<&mut dyn AccessTokenExtension> {
         Some(self)
     }
+
+    fn metadata(&mut self) -> Option<&mut dyn MetadataExtension> {
+        Some(self)
+    }
 }
 
 impl AccessTokenExtension for AddonList {
@@ -109,6 +114,14 @@ impl AuthorizationExtension for AddonList {
     }
 }
 
+impl MetadataExtension for AddonList {
+    fn extend(&mut self, metadata: &mut Metadata) {
+        for ext in self.authorization.iter() {
+            ext.metadata(metadata);
+        }
+    }
+}
+
 impl fmt::Debug for AddonList {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use std::slice::Iter;
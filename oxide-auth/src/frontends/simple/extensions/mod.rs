@@ -15,6 +15,7 @@ use std::sync::Arc;
 pub use self::extended::Extended;
 pub use self::pkce::Pkce;
 pub use self::list::AddonList;
+use crate::code_grant::metadata::Metadata;
 use crate::primitives::grant::{GrantExtension, Value};
 
 /// Result of extension processing.
@@ -43,6 +44,11 @@ pub trait AuthorizationAddon: GrantExtension {
     /// Extensions can also store their pure existance by initializing the extension struct without
     /// data. Specifically, the data can be used in a corresponding `AccessTokenExtension`.
     fn execute(&self, request: &dyn AuthorizationRequest) -> AddonResult;
+
+    /// Advertise the capabilities of this addon in the authorization server metadata.
+    ///
+    /// The default implementation does not add anything.
+    fn metadata(&self, _: &mut Metadata) {}
 }
 
 /// An extension reacting to an access token request with a provided access token.
@@ -59,6 +65,10 @@ impl<'a, T: AuthorizationAddon + ?Sized> AuthorizationAddon for &'a T {
     fn execute(&self, request: &dyn AuthorizationRequest) -> AddonResult {
         (**self).execute(request)
     }
+
+    fn metadata(&self, metadata: &mut Metadata) {
+        (**self).metadata(metadata)
+    }
 }
 
 impl<'a, T: AuthorizationAddon + ?Sized> AuthorizationAddon for Cow<'a, T>
@@ -68,24 +78,40 @@ where
     fn execute(&self, request: &dyn AuthorizationRequest) -> AddonResult {
         self.as_ref().execute(request)
     }
+
+    fn metadata(&self, metadata: &mut Metadata) {
+        self.as_ref().metadata(metadata)
+    }
 }
 
 impl<T: AuthorizationAddon + ?Sized> AuthorizationAddon for Box<T> {
     fn execute(&self, request: &dyn AuthorizationRequest) -> AddonResult {
         (**self).execute(request)
     }
+
+    fn metadata(&self, metadata: &mut Metadata) {
+        (**self).metadata(metadata)
+    }
 }
 
 impl<T: AuthorizationAddon + ?Sized> AuthorizationAddon for Arc<T> {
     fn execute(&self, request: &dyn AuthorizationRequest) -> AddonResult {
         (**self).execute(request)
     }
+
+    fn metadata(&self, metadata: &mut Metadata) {
+        (**self).metadata(metadata)
+    }
 }
 
 impl<T: AuthorizationAddon + ?Sized> AuthorizationAddon for Rc<T> {
     fn execute(&self, request: &dyn AuthorizationRequest) -> AddonResult {
         (**self).execute(request)
     }
+
+    fn metadata(&self, metadata: &mut Metadata) {
+        (**self).metadata(metadata)
+    }
 }
 
 impl<'a, T: AccessTokenAddon + ?Sized> AccessTokenAddon for &'a T {
@@ -1,5 +1,6 @@
 use super::{AuthorizationAddon, AuthorizationRequest, AccessTokenAddon, AccessTokenRequest};
 use super::{AddonResult, Value};
+use crate::code_grant::metadata::Metadata;
 
 pub use crate::code_grant::extensions::Pkce;
 
@@ -16,6 +17,12 @@ impl AuthorizationAddon for Pkce {
 
         AddonResult::Data(encoded)
     }
+
+    fn metadata(&self, metadata: &mut Metadata) {
+        for method in self.supported_methods() {
+            metadata.add_code_challenge_method(method);
+        }
+    }
 }
 
 impl AccessTokenAddon for Pkce {
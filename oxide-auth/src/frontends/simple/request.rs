@@ -1,7 +1,7 @@
 //! Simple, owning request and response types.
 use std::marker::PhantomData;
 
-use crate::endpoint::{QueryParameter, WebRequest, WebResponse};
+use crate::endpoint::{ClientCertificate, QueryParameter, WebRequest, WebResponse};
 
 use std::borrow::Cow;
 use std::collections::HashMap;
@@ -19,6 +19,18 @@ pub struct Request {
 
     /// Provided authorization header.
     pub auth: Option<String>,
+
+    /// Other provided headers, their names are matched case-insensitively.
+    pub headers: HashMap<String, String>,
+
+    /// The method and absolute uri of the request, if known.
+    pub target: Option<(String, Url)>,
+
+    /// The verified client certificate of the connection.
+    pub certificate: Option<ClientCertificate>,
+
+    /// A raw body, such as a json document.
+    pub body: Option<String>,
 }
 
 /// Open and simple implementation of `WebResponse`.
@@ -47,6 +59,12 @@ pub enum Status {
     /// Http status code 200.
     Ok,
 
+    /// Http status code 201.
+    Created,
+
+    /// Http status code 204.
+    NoContent,
+
     /// Http status code 302.
     Redirect,
 
@@ -79,6 +97,57 @@ pub enum NoError {}
 /// Changes the error type of a web request and response.
 pub struct MapErr<W, F, T>(W, F, PhantomData<T>);
 
+impl Request {
+    /// An empty request, to be filled with the `with_*` methods.
+    ///
+    /// Prefer this over a struct literal, new fields may be added to `Request` in the future.
+    pub fn new() -> Self {
+        Request::default()
+    }
+
+    /// Add a key-value pair to the url query component.
+    pub fn with_query(mut self, key: &str, value: &str) -> Self {
+        self.query.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Add a key-value pair to the `x-www-form-urlencoded` body.
+    pub fn with_urlbody(mut self, key: &str, value: &str) -> Self {
+        self.urlbody.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Set the authorization header.
+    pub fn with_auth(mut self, auth: &str) -> Self {
+        self.auth = Some(auth.to_string());
+        self
+    }
+
+    /// Add another header.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Set the method and absolute uri of the request.
+    pub fn with_target(mut self, method: &str, uri: Url) -> Self {
+        self.target = Some((method.to_string(), uri));
+        self
+    }
+
+    /// Set the verified client certificate of the connection.
+    pub fn with_certificate(mut self, certificate: ClientCertificate) -> Self {
+        self.certificate = Some(certificate);
+        self
+    }
+
+    /// Set a raw body, such as a json document.
+    pub fn with_body(mut self, body: &str) -> Self {
+        self.body = Some(body.to_string());
+        self
+    }
+}
+
 impl Body {
     /// View the content of the body.
     pub fn as_str(&self) -> &str {
@@ -129,6 +198,29 @@ impl WebRequest for Request {
     fn authheader(&mut self) -> Result<Option<Cow<str>>, Self::Error> {
         Ok(self.auth.as_ref().map(|string| Cow::Borrowed(string.as_str())))
     }
+
+    fn header(&mut self, name: &str) -> Result<Option<Cow<'_, str>>, Self::Error> {
+        Ok(self
+            .headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| Cow::Borrowed(value.as_str())))
+    }
+
+    fn target(&mut self) -> Result<Option<(Cow<'_, str>, Url)>, Self::Error> {
+        Ok(self
+            .target
+            .as_ref()
+            .map(|(method, uri)| (Cow::Borrowed(method.as_str()), uri.clone())))
+    }
+
+    fn certificate(&mut self) -> Result<Option<Cow<'_, ClientCertificate>>, Self::Error> {
+        Ok(self.certificate.as_ref().map(Cow::Borrowed))
+    }
+
+    fn body(&mut self) -> Result<Option<Cow<'_, str>>, Self::Error> {
+        Ok(self.body.as_ref().map(|body| Cow::Borrowed(body.as_str())))
+    }
 }
 
 impl WebResponse for Response {
@@ -176,6 +268,22 @@ impl WebResponse for Response {
         self.body = Some(Body::Json(data.to_owned()));
         Ok(())
     }
+
+    /// Set the response status to 201.
+    fn created(&mut self) -> Result<(), Self::Error> {
+        self.status = Status::Created;
+        self.location = None;
+        self.www_authenticate = None;
+        Ok(())
+    }
+
+    /// Set the response status to 204.
+    fn no_content(&mut self) -> Result<(), Self::Error> {
+        self.status = Status::NoContent;
+        self.location = None;
+        self.www_authenticate = None;
+        Ok(())
+    }
 }
 
 impl NoError {
@@ -211,6 +319,22 @@ where
     fn authheader(&mut self) -> Result<Option<Cow<str>>, Self::Error> {
         self.0.authheader().map_err(&mut self.1)
     }
+
+    fn header(&mut self, name: &str) -> Result<Option<Cow<'_, str>>, Self::Error> {
+        self.0.header(name).map_err(&mut self.1)
+    }
+
+    fn target(&mut self) -> Result<Option<(Cow<'_, str>, Url)>, Self::Error> {
+        self.0.target().map_err(&mut self.1)
+    }
+
+    fn certificate(&mut self) -> Result<Option<Cow<'_, ClientCertificate>>, Self::Error> {
+        self.0.certificate().map_err(&mut self.1)
+    }
+
+    fn body(&mut self) -> Result<Option<Cow<'_, str>>, Self::Error> {
+        self.0.body().map_err(&mut self.1)
+    }
 }
 
 impl<W: WebResponse, F, T> WebResponse for MapErr<W, F, T>
@@ -247,4 +371,14 @@ where
     fn body_json(&mut self, data: &str) -> Result<(), Self::Error> {
         self.0.body_json(data).map_err(&mut self.1)
     }
+
+    /// Set the response status to 201.
+    fn created(&mut self) -> Result<(), Self::Error> {
+        self.0.created().map_err(&mut self.1)
+    }
+
+    /// Set the response status to 204.
+    fn no_content(&mut self) -> Result<(), Self::Error> {
+        self.0.no_content().map_err(&mut self.1)
+    }
 }
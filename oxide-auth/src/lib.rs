@@ -73,4 +73,3 @@ pub mod code_grant;
 pub mod endpoint;
 pub mod frontends;
 pub mod primitives;
-mod token_grant;
@@ -0,0 +1,138 @@
+//! Authenticators verify the credentials of resource owners.
+//!
+//! Only the resource owner password credentials grant presents the credentials of an owner to the
+//! authorization server directly. The authenticator checks these and determines the identity of
+//! the owner, under which the grant is then issued. Since the client gets to see the credentials
+//! of the owner, this should be reserved for highly trusted clients and is disabled for every
+//! client that was not explicitely registered for it.
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use once_cell::sync::Lazy;
+
+use super::registrar::{Argon2, PasswordPolicy, RegistrarError};
+
+/// Authenticators check the credentials of resource owners.
+pub trait OwnerAuthenticator {
+    /// Check the username and password of a resource owner.
+    ///
+    /// Returns the `owner_id` under which grants should be issued when the credentials are
+    /// correct and `None` when they are not. An error indicates that the credentials could not
+    /// be checked at all.
+    fn authenticate(&self, username: &str, password: &[u8]) -> Result<Option<String>, ()>;
+}
+
+/// A very simple, in-memory hash map of usernames to owners.
+///
+/// Passwords are stored through a `PasswordPolicy`, the same as the passphrases of confidential
+/// clients in a `ClientMap`.
+#[derive(Default)]
+pub struct OwnerMap {
+    owners: HashMap<String, Owner>,
+    password_policy: Option<Box<dyn PasswordPolicy>>,
+}
+
+struct Owner {
+    owner_id: String,
+    passdata: Vec<u8>,
+}
+
+static DEFAULT_PASSWORD_POLICY: Lazy<Argon2> = Lazy::new(Argon2::default);
+
+impl OwnerMap {
+    /// Create an empty map without any owners in it.
+    pub fn new() -> Self {
+        OwnerMap::default()
+    }
+
+    /// Insert or update the credentials of a resource owner.
+    pub fn register_owner(&mut self, username: &str, password: &[u8], owner_id: &str) {
+        let password_policy = Self::current_policy(&self.password_policy);
+        let owner = Owner {
+            owner_id: owner_id.to_string(),
+            passdata: password_policy.store(username, password),
+        };
+        self.owners.insert(username.to_string(), owner);
+    }
+
+    /// Change how passwords are encoded while stored.
+    ///
+    /// This only affects owners registered after this call.
+    pub fn set_password_policy<P: PasswordPolicy + 'static>(&mut self, new_policy: P) {
+        self.password_policy = Some(Box::new(new_policy))
+    }
+
+    // This is not an instance method because it needs to borrow the box but register needs &mut
+    fn current_policy<'a>(policy: &'a Option<Box<dyn PasswordPolicy>>) -> &'a dyn PasswordPolicy {
+        policy
+            .as_ref()
+            .map(|boxed| &**boxed)
+            .unwrap_or(&*DEFAULT_PASSWORD_POLICY)
+    }
+}
+
+impl OwnerAuthenticator for OwnerMap {
+    fn authenticate(&self, username: &str, password: &[u8]) -> Result<Option<String>, ()> {
+        let owner = match self.owners.get(username) {
+            None => return Ok(None),
+            Some(owner) => owner,
+        };
+
+        let password_policy = Self::current_policy(&self.password_policy);
+        match password_policy.check(username, password, &owner.passdata) {
+            Ok(()) => Ok(Some(owner.owner_id.clone())),
+            Err(RegistrarError::Unspecified) => Ok(None),
+            Err(RegistrarError::PrimitiveError) => Err(()),
+        }
+    }
+}
+
+impl<'s, A: OwnerAuthenticator + ?Sized> OwnerAuthenticator for &'s A {
+    fn authenticate(&self, username: &str, password: &[u8]) -> Result<Option<String>, ()> {
+        (**self).authenticate(username, password)
+    }
+}
+
+impl<'s, A: OwnerAuthenticator + ?Sized> OwnerAuthenticator for &'s mut A {
+    fn authenticate(&self, username: &str, password: &[u8]) -> Result<Option<String>, ()> {
+        (**self).authenticate(username, password)
+    }
+}
+
+impl<A: OwnerAuthenticator + ?Sized> OwnerAuthenticator for Box<A> {
+    fn authenticate(&self, username: &str, password: &[u8]) -> Result<Option<String>, ()> {
+        (**self).authenticate(username, password)
+    }
+}
+
+impl<A: OwnerAuthenticator + ?Sized> OwnerAuthenticator for Rc<A> {
+    fn authenticate(&self, username: &str, password: &[u8]) -> Result<Option<String>, ()> {
+        (**self).authenticate(username, password)
+    }
+}
+
+impl<A: OwnerAuthenticator + ?Sized> OwnerAuthenticator for Arc<A> {
+    fn authenticate(&self, username: &str, password: &[u8]) -> Result<Option<String>, ()> {
+        (**self).authenticate(username, password)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn owner_map() {
+        let mut owners = OwnerMap::new();
+        owners.register_owner("alice", b"correct horse", "owner:alice");
+
+        assert_eq!(
+            owners.authenticate("alice", b"correct horse"),
+            Ok(Some("owner:alice".to_string()))
+        );
+        assert_eq!(owners.authenticate("alice", b"battery staple"), Ok(None));
+        assert_eq!(owners.authenticate("alice", b""), Ok(None));
+        assert_eq!(owners.authenticate("bob", b"correct horse"), Ok(None));
+    }
+}
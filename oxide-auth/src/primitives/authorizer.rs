@@ -9,7 +9,7 @@ use std::collections::HashMap;
 use std::sync::{MutexGuard, RwLockWriteGuard};
 
 use super::grant::Grant;
-use super::generator::TagGrant;
+use super::generator::{TagGrant, TokenHasher};
 
 /// Authorizers create and manage authorization codes.
 ///
@@ -33,6 +33,7 @@ pub struct AuthMap<I: TagGrant = Box<dyn TagGrant + Send + Sync + 'static>> {
     tagger: I,
     usage: u64,
     tokens: HashMap<String, Grant>,
+    hasher: Option<TokenHasher>,
 }
 
 impl<I: TagGrant> AuthMap<I> {
@@ -47,6 +48,26 @@ impl<I: TagGrant> AuthMap<I> {
             tagger,
             usage: 0,
             tokens: HashMap::new(),
+            hasher: None,
+        }
+    }
+
+    /// Create an authorizer that only stores a keyed hash of each code.
+    ///
+    /// Presented codes are hashed before they are looked up, the map itself never holds a code
+    /// that could still be exchanged.
+    pub fn with_hasher(tagger: I, hasher: TokenHasher) -> Self {
+        AuthMap {
+            hasher: Some(hasher),
+            ..Self::new(tagger)
+        }
+    }
+
+    /// The key under which a code is stored.
+    fn key(&self, code: &str) -> String {
+        match &self.hasher {
+            Some(hasher) => hasher.hash(code),
+            None => code.to_string(),
         }
     }
 }
@@ -99,13 +120,13 @@ impl<I: TagGrant> Authorizer for AuthMap<I> {
         // second.
         let next_usage = self.usage.wrapping_add(1);
         let token = self.tagger.tag(next_usage - 1, &grant)?;
-        self.tokens.insert(token.clone(), grant);
+        self.tokens.insert(self.key(&token), grant);
         self.usage = next_usage;
         Ok(token)
     }
 
     fn extract<'a>(&mut self, grant: &'a str) -> Result<Option<Grant>, ()> {
-        Ok(self.tokens.remove(grant))
+        Ok(self.tokens.remove(&self.key(grant)))
     }
 }
 
@@ -114,7 +135,7 @@ impl<I: TagGrant> Authorizer for AuthMap<I> {
 pub mod tests {
     use super::*;
     use chrono::Utc;
-    use crate::primitives::grant::Extensions;
+    use crate::primitives::grant::{Extensions, Value};
     use crate::primitives::generator::{Assertion, AssertionKind, RandomGenerator};
 
     /// Tests some invariants that should be upheld by all authorizers.
@@ -160,6 +181,26 @@ pub mod tests {
         simple_test_suite(&mut storage);
     }
 
+    #[test]
+    fn hashed_test_suite() {
+        let mut storage = AuthMap::with_hasher(RandomGenerator::new(16), TokenHasher::new(b"secret"));
+        simple_test_suite(&mut storage);
+
+        let grant = Grant {
+            owner_id: "Owner".to_string(),
+            client_id: "Client".to_string(),
+            scope: "default".parse().unwrap(),
+            redirect_uri: "https://example.com/redirect_me".parse().unwrap(),
+            until: Utc::now(),
+            extensions: Extensions::new(),
+        };
+
+        // Only the hash of the code is stored.
+        let code = storage.authorize(grant.clone()).unwrap();
+        assert!(!storage.tokens.contains_key(&code));
+        assert_eq!(storage.extract(&code), Ok(Some(grant)));
+    }
+
     #[test]
     fn signing_test_suite() {
         let assertion = Assertion::new(
@@ -170,6 +211,27 @@ pub mod tests {
         simple_test_suite(&mut storage);
     }
 
+    #[test]
+    fn signing_private_extensions() {
+        // Codes only contain the signature, no encryption key is required.
+        let assertion = Assertion::new(AssertionKind::HmacSha256, b"secret");
+        let mut storage = AuthMap::new(assertion);
+        let mut grant = Grant {
+            owner_id: "Owner".to_string(),
+            client_id: "Client".to_string(),
+            scope: "default".parse().unwrap(),
+            redirect_uri: "https://example.com/redirect_me".parse().unwrap(),
+            until: Utc::now(),
+            extensions: Extensions::new(),
+        };
+        grant
+            .extensions
+            .set_raw("secret".into(), Value::private(Some("Hidden".into())));
+
+        let token = storage.authorize(grant.clone()).unwrap();
+        assert_eq!(storage.extract(&token), Ok(Some(grant)));
+    }
+
     #[test]
     #[should_panic]
     fn bad_generator() {
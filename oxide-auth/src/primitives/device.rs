@@ -0,0 +1,425 @@
+//! Device authorizers keep track of pending device authorization requests.
+//!
+//! Devices without a browser or with limited input capabilities ask the resource owner to visit a
+//! verification page on a secondary device and to enter a short user code there. Meanwhile, the
+//! device polls the token endpoint with its device code until the owner has decided. The device
+//! authorizer stores the pending grants between these steps. See [RFC 8628] for details.
+//!
+//! [RFC 8628]: https://tools.ietf.org/html/rfc8628
+use std::collections::HashMap;
+use std::sync::{MutexGuard, RwLockWriteGuard};
+
+use chrono::{Duration, Utc};
+use rand::{rngs::OsRng, Rng};
+
+use super::Time;
+use super::grant::Grant;
+use super::generator::TagGrant;
+
+/// The codes identifying a pending device authorization.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeviceCodes {
+    /// The secret code with which the device polls the token endpoint.
+    pub device_code: String,
+
+    /// The short code the resource owner enters on the verification page.
+    pub user_code: String,
+
+    /// The minimum time the device should wait between polling requests.
+    pub interval: Duration,
+}
+
+/// The state of a device authorization, as seen when polling for the access token.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeviceStatus {
+    /// The resource owner has not yet decided over the request.
+    Pending,
+
+    /// The request is still pending but the device polled faster than its interval permits.
+    ///
+    /// The interval of the device code should be increased when this is returned.
+    SlowDown,
+
+    /// The resource owner approved the request.
+    ///
+    /// Contains the grant with the `owner_id` of the approving owner. The device code is
+    /// invalidated in the process and must not be usable a second time.
+    Approved(Grant),
+
+    /// The resource owner denied the request.
+    Denied,
+
+    /// The device code has expired before the resource owner approved the request.
+    Expired,
+}
+
+/// Device authorizers create and manage pending device authorizations.
+///
+/// The grants passed to `authorize` do not yet have an owner and their `until` denotes the
+/// expiration of the device code. The owner is only determined once a resource owner approves the
+/// request on the verification page.
+pub trait DeviceAuthorizer {
+    /// Store a pending grant, creating a device code and a user code for it.
+    fn authorize(&mut self, grant: Grant) -> Result<DeviceCodes, ()>;
+
+    /// Retrieve the grant still waiting for a decision of the owner, identified by its user code.
+    ///
+    /// Returns `None` for unknown, expired and already decided user codes.
+    fn pending(&mut self, user_code: &str) -> Result<Option<Grant>, ()>;
+
+    /// Record that a resource owner approved the request identified by the user code.
+    fn approve(&mut self, user_code: &str, owner_id: &str) -> Result<(), ()>;
+
+    /// Record that a resource owner denied the request identified by the user code.
+    fn deny(&mut self, user_code: &str) -> Result<(), ()>;
+
+    /// Poll the state of a device code.
+    ///
+    /// Returns `None` for unknown device codes. Devices polling faster than their interval should
+    /// be told to slow down.
+    fn poll(&mut self, device_code: &str) -> Result<Option<DeviceStatus>, ()>;
+}
+
+/// An in-memory hash map.
+///
+/// Device codes are generated with a `TagGrant` while user codes consist of eight random letters
+/// from an alphabet without vowels, as recommended by the rfc. User codes are compared without
+/// regard to case and dashes.
+pub struct DeviceMap<I: TagGrant = Box<dyn TagGrant + Send + Sync + 'static>> {
+    tagger: I,
+    usage: u64,
+    interval: Duration,
+    devices: HashMap<String, Device>,
+    user_codes: HashMap<String, String>,
+}
+
+struct Device {
+    grant: Grant,
+    user_code: String,
+    decision: Decision,
+    interval: Duration,
+    last_poll: Option<Time>,
+}
+
+enum Decision {
+    Pending,
+    Approved,
+    Denied,
+}
+
+const USER_CODE_ALPHABET: &[u8] = b"BCDFGHJKLMNPQRSTVWXZ";
+const USER_CODE_LENGTH: usize = 8;
+
+impl<I: TagGrant> DeviceMap<I> {
+    /// Create a device authorizer generating device codes with the `tagger`.
+    ///
+    /// The map is initially empty and devices are asked to poll at most every five seconds.
+    pub fn new(tagger: I) -> Self {
+        DeviceMap {
+            tagger,
+            usage: 0,
+            interval: Duration::seconds(5),
+            devices: HashMap::new(),
+            user_codes: HashMap::new(),
+        }
+    }
+
+    /// Change the polling interval for devices authorized after this call.
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    fn new_user_code(&self) -> String {
+        let mut rnd = OsRng;
+        loop {
+            let code: String = (0..USER_CODE_LENGTH)
+                .map(|_| USER_CODE_ALPHABET[rnd.gen_range(0, USER_CODE_ALPHABET.len())] as char)
+                .collect();
+            if !self.user_codes.contains_key(&code) {
+                return code;
+            }
+        }
+    }
+
+    fn pending_device(&mut self, user_code: &str) -> Option<&mut Device> {
+        let device_code = self.user_codes.get(&normalize_user_code(user_code))?;
+        let device = self.devices.get_mut(device_code)?;
+        match device.decision {
+            Decision::Pending if device.grant.until > Utc::now() => Some(device),
+            _ => None,
+        }
+    }
+
+    fn remove(&mut self, device_code: &str) -> Option<Device> {
+        let device = self.devices.remove(device_code)?;
+        self.user_codes.remove(&device.user_code);
+        Some(device)
+    }
+}
+
+/// Canonical form of a user code, as entered by a resource owner.
+fn normalize_user_code(user_code: &str) -> String {
+    user_code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+impl<'a, A: DeviceAuthorizer + ?Sized> DeviceAuthorizer for &'a mut A {
+    fn authorize(&mut self, grant: Grant) -> Result<DeviceCodes, ()> {
+        (**self).authorize(grant)
+    }
+
+    fn pending(&mut self, user_code: &str) -> Result<Option<Grant>, ()> {
+        (**self).pending(user_code)
+    }
+
+    fn approve(&mut self, user_code: &str, owner_id: &str) -> Result<(), ()> {
+        (**self).approve(user_code, owner_id)
+    }
+
+    fn deny(&mut self, user_code: &str) -> Result<(), ()> {
+        (**self).deny(user_code)
+    }
+
+    fn poll(&mut self, device_code: &str) -> Result<Option<DeviceStatus>, ()> {
+        (**self).poll(device_code)
+    }
+}
+
+impl<A: DeviceAuthorizer + ?Sized> DeviceAuthorizer for Box<A> {
+    fn authorize(&mut self, grant: Grant) -> Result<DeviceCodes, ()> {
+        (**self).authorize(grant)
+    }
+
+    fn pending(&mut self, user_code: &str) -> Result<Option<Grant>, ()> {
+        (**self).pending(user_code)
+    }
+
+    fn approve(&mut self, user_code: &str, owner_id: &str) -> Result<(), ()> {
+        (**self).approve(user_code, owner_id)
+    }
+
+    fn deny(&mut self, user_code: &str) -> Result<(), ()> {
+        (**self).deny(user_code)
+    }
+
+    fn poll(&mut self, device_code: &str) -> Result<Option<DeviceStatus>, ()> {
+        (**self).poll(device_code)
+    }
+}
+
+impl<'a, A: DeviceAuthorizer + ?Sized> DeviceAuthorizer for MutexGuard<'a, A> {
+    fn authorize(&mut self, grant: Grant) -> Result<DeviceCodes, ()> {
+        (**self).authorize(grant)
+    }
+
+    fn pending(&mut self, user_code: &str) -> Result<Option<Grant>, ()> {
+        (**self).pending(user_code)
+    }
+
+    fn approve(&mut self, user_code: &str, owner_id: &str) -> Result<(), ()> {
+        (**self).approve(user_code, owner_id)
+    }
+
+    fn deny(&mut self, user_code: &str) -> Result<(), ()> {
+        (**self).deny(user_code)
+    }
+
+    fn poll(&mut self, device_code: &str) -> Result<Option<DeviceStatus>, ()> {
+        (**self).poll(device_code)
+    }
+}
+
+impl<'a, A: DeviceAuthorizer + ?Sized> DeviceAuthorizer for RwLockWriteGuard<'a, A> {
+    fn authorize(&mut self, grant: Grant) -> Result<DeviceCodes, ()> {
+        (**self).authorize(grant)
+    }
+
+    fn pending(&mut self, user_code: &str) -> Result<Option<Grant>, ()> {
+        (**self).pending(user_code)
+    }
+
+    fn approve(&mut self, user_code: &str, owner_id: &str) -> Result<(), ()> {
+        (**self).approve(user_code, owner_id)
+    }
+
+    fn deny(&mut self, user_code: &str) -> Result<(), ()> {
+        (**self).deny(user_code)
+    }
+
+    fn poll(&mut self, device_code: &str) -> Result<Option<DeviceStatus>, ()> {
+        (**self).poll(device_code)
+    }
+}
+
+impl<I: TagGrant> DeviceAuthorizer for DeviceMap<I> {
+    fn authorize(&mut self, grant: Grant) -> Result<DeviceCodes, ()> {
+        // See `AuthMap` on the uniqueness of the (usage, grant) tuple.
+        let next_usage = self.usage.wrapping_add(1);
+        let device_code = self.tagger.tag(next_usage - 1, &grant)?;
+        let user_code = self.new_user_code();
+        self.usage = next_usage;
+
+        self.user_codes.insert(user_code.clone(), device_code.clone());
+        self.devices.insert(
+            device_code.clone(),
+            Device {
+                grant,
+                user_code: user_code.clone(),
+                decision: Decision::Pending,
+                interval: self.interval,
+                last_poll: None,
+            },
+        );
+
+        Ok(DeviceCodes {
+            device_code,
+            user_code,
+            interval: self.interval,
+        })
+    }
+
+    fn pending(&mut self, user_code: &str) -> Result<Option<Grant>, ()> {
+        Ok(self.pending_device(user_code).map(|device| device.grant.clone()))
+    }
+
+    fn approve(&mut self, user_code: &str, owner_id: &str) -> Result<(), ()> {
+        if let Some(device) = self.pending_device(user_code) {
+            device.grant.owner_id = owner_id.to_string();
+            device.decision = Decision::Approved;
+        }
+        Ok(())
+    }
+
+    fn deny(&mut self, user_code: &str) -> Result<(), ()> {
+        if let Some(device) = self.pending_device(user_code) {
+            device.decision = Decision::Denied;
+        }
+        Ok(())
+    }
+
+    fn poll(&mut self, device_code: &str) -> Result<Option<DeviceStatus>, ()> {
+        let now = Utc::now();
+        let device = match self.devices.get_mut(device_code) {
+            None => return Ok(None),
+            Some(device) => device,
+        };
+
+        if device.grant.until <= now {
+            self.remove(device_code);
+            return Ok(Some(DeviceStatus::Expired));
+        }
+
+        match device.decision {
+            Decision::Pending => {
+                let too_fast = match device.last_poll {
+                    Some(last_poll) => now - last_poll < device.interval,
+                    None => false,
+                };
+                device.last_poll = Some(now);
+
+                if too_fast {
+                    device.interval = device.interval + Duration::seconds(5);
+                    Ok(Some(DeviceStatus::SlowDown))
+                } else {
+                    Ok(Some(DeviceStatus::Pending))
+                }
+            }
+            Decision::Approved => {
+                let device = self.remove(device_code).unwrap();
+                Ok(Some(DeviceStatus::Approved(device.grant)))
+            }
+            Decision::Denied => {
+                self.remove(device_code);
+                Ok(Some(DeviceStatus::Denied))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::generator::RandomGenerator;
+    use crate::primitives::grant::Extensions;
+
+    fn pending_grant(until: Time) -> Grant {
+        Grant {
+            owner_id: String::new(),
+            client_id: "Client".to_string(),
+            scope: "One two three scopes".parse().unwrap(),
+            redirect_uri: "https://example.com/redirect_me".parse().unwrap(),
+            until,
+            extensions: Extensions::new(),
+        }
+    }
+
+    #[test]
+    fn approve_device() {
+        let mut devices = DeviceMap::new(RandomGenerator::new(16));
+        devices.set_interval(Duration::zero());
+        let codes = devices
+            .authorize(pending_grant(Utc::now() + Duration::minutes(10)))
+            .expect("Authorization should not fail here");
+
+        assert_eq!(codes.user_code.len(), USER_CODE_LENGTH);
+        assert_eq!(devices.poll(&codes.device_code), Ok(Some(DeviceStatus::Pending)));
+
+        // Owners may enter the code with different case and formatting.
+        let entered = format!(
+            "{}-{}",
+            &codes.user_code[..4],
+            codes.user_code[4..].to_lowercase()
+        );
+        let pending = devices
+            .pending(&entered)
+            .unwrap()
+            .expect("User code should identify the pending grant");
+        assert_eq!(pending.client_id, "Client");
+
+        devices.approve(&entered, "Owner").unwrap();
+        assert_eq!(devices.pending(&codes.user_code), Ok(None));
+
+        match devices.poll(&codes.device_code) {
+            Ok(Some(DeviceStatus::Approved(grant))) => assert_eq!(grant.owner_id, "Owner"),
+            other => panic!("Expected approved grant, got {:?}", other),
+        }
+
+        assert_eq!(devices.poll(&codes.device_code), Ok(None));
+    }
+
+    #[test]
+    fn deny_device() {
+        let mut devices = DeviceMap::new(RandomGenerator::new(16));
+        let codes = devices
+            .authorize(pending_grant(Utc::now() + Duration::minutes(10)))
+            .unwrap();
+
+        devices.deny(&codes.user_code).unwrap();
+        assert_eq!(devices.poll(&codes.device_code), Ok(Some(DeviceStatus::Denied)));
+        assert_eq!(devices.poll(&codes.device_code), Ok(None));
+    }
+
+    #[test]
+    fn slow_down_device() {
+        let mut devices = DeviceMap::new(RandomGenerator::new(16));
+        let codes = devices
+            .authorize(pending_grant(Utc::now() + Duration::minutes(10)))
+            .unwrap();
+
+        assert_eq!(devices.poll(&codes.device_code), Ok(Some(DeviceStatus::Pending)));
+        assert_eq!(devices.poll(&codes.device_code), Ok(Some(DeviceStatus::SlowDown)));
+    }
+
+    #[test]
+    fn expired_device() {
+        let mut devices = DeviceMap::new(RandomGenerator::new(16));
+        let codes = devices.authorize(pending_grant(Utc::now())).unwrap();
+
+        assert_eq!(devices.pending(&codes.user_code), Ok(None));
+        assert_eq!(devices.poll(&codes.device_code), Ok(Some(DeviceStatus::Expired)));
+        assert_eq!(devices.poll(&codes.device_code), Ok(None));
+    }
+}
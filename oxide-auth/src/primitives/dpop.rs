@@ -0,0 +1,305 @@
+//! Verifies DPoP proofs that bind tokens to a key of the client.
+//!
+//! A bearer token can be used by anyone who obtains it, for example from a log file. With DPoP, the
+//! client instead proves possession of a private key on every request by sending a short-lived,
+//! signed proof in the `DPoP` header. The token endpoint binds the issued grant to the thumbprint of
+//! that key, stored in the public `cnf.jkt` extension, and resources only accept the token together
+//! with a fresh proof signed by the same key. See [RFC 9449] for details.
+//!
+//! [RFC 9449]: https://tools.ietf.org/html/rfc9449
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use base64::{encode_config, URL_SAFE_NO_PAD};
+use chrono::{Duration, TimeZone, Utc};
+use serde_json::{Map, Value as JsonValue};
+use sha2::{Digest, Sha256};
+
+use super::{Time, Url};
+use super::grant::Grant;
+use super::jwk::Jwk;
+use super::jwt::Compact;
+
+/// The grant extension holding the thumbprint of the key to which the grant is bound.
+pub const CONFIRMATION: &str = "cnf.jkt";
+
+/// Verifiers check the DPoP proofs of requests.
+pub trait DpopVerifier {
+    /// Check a proof for a request with the given method and target uri.
+    ///
+    /// Implementations must verify the signature of the proof with the public key in its header,
+    /// check that the proof was created for this request and has not been used before. When the
+    /// request presents an access token, the proof must also contain its hash. Returns the
+    /// thumbprint of the public key, or `None` if the proof is invalid.
+    fn verify(&self, proof: &str, method: &str, uri: &Url, access_token: Option<&str>)
+        -> Option<String>;
+}
+
+/// Verifies proofs signed with `EdDSA`, `ES256` or `PS256` and remembers their ids.
+///
+/// A proof is only accepted within a short window around its issuing time, five minutes by
+/// default. The ids of accepted proofs are kept until the end of that window to reject replays.
+pub struct DpopMap {
+    max_age: Duration,
+    seen: Mutex<HashMap<String, Time>>,
+}
+
+/// The thumbprint of the key to which a grant is bound, if any.
+pub fn confirmation(grant: &Grant) -> Option<&str> {
+    grant
+        .extensions
+        .public()
+        .find(|&(name, _)| name == CONFIRMATION)
+        .and_then(|(_, thumbprint)| thumbprint)
+}
+
+/// The value of the `ath` claim for an access token.
+fn token_hash(access_token: &str) -> String {
+    encode_config(Sha256::digest(access_token.as_bytes()), URL_SAFE_NO_PAD)
+}
+
+/// Compare two uris without their query and fragment.
+fn same_target(first: &Url, second: &Url) -> bool {
+    let strip = |uri: &Url| {
+        let mut uri = uri.clone();
+        uri.set_query(None);
+        uri.set_fragment(None);
+        uri
+    };
+
+    strip(first) == strip(second)
+}
+
+impl DpopMap {
+    /// A verifier that has not seen any proofs yet.
+    pub fn new() -> Self {
+        DpopMap {
+            max_age: Duration::minutes(5),
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Change the window within which proofs are accepted.
+    ///
+    /// Proofs are rejected if they were issued more than this duration ago or, to account for clock
+    /// skew, would be issued more than this duration in the future.
+    pub fn valid_for(&mut self, max_age: Duration) {
+        self.max_age = max_age;
+    }
+
+    fn check(
+        &self, proof: &str, method: &str, uri: &Url, access_token: Option<&str>, now: Time,
+    ) -> Option<String> {
+        let compact = Compact::split(proof)?;
+        let header: Map<String, JsonValue> = serde_json::from_slice(&compact.header).ok()?;
+        let claims: Map<String, JsonValue> = serde_json::from_slice(&compact.payload).ok()?;
+
+        if header.get("typ").and_then(JsonValue::as_str) != Some("dpop+jwt") {
+            return None;
+        }
+
+        // The header must never contain the private key.
+        let jwk = header.get("jwk").filter(|jwk| jwk.get("d").is_none())?;
+        let jwk: Jwk = serde_json::from_value(jwk.clone()).ok()?;
+        let key = jwk.to_assertion()?;
+
+        if header.get("alg").and_then(JsonValue::as_str) != Some(key.jws_algorithm()) {
+            return None;
+        }
+
+        if !key.verify_detached(compact.signed, &compact.signature) {
+            return None;
+        }
+
+        let string = |key: &str| claims.get(key).and_then(JsonValue::as_str);
+        if string("htm") != Some(method) {
+            return None;
+        }
+
+        let htu = string("htu").and_then(|htu| htu.parse::<Url>().ok())?;
+        if !same_target(&htu, uri) {
+            return None;
+        }
+
+        if string("ath") != access_token.map(token_hash).as_deref() {
+            return None;
+        }
+
+        let issued = claims.get("iat").and_then(JsonValue::as_i64)?;
+        let issued = Utc.timestamp_opt(issued, 0).single()?;
+        // The issue time is chosen by the client, so only the window around now is computed.
+        let oldest = now.checked_sub_signed(self.max_age)?;
+        let newest = now.checked_add_signed(self.max_age)?;
+        if issued < oldest || newest < issued {
+            return None;
+        }
+
+        let thumbprint = jwk.thumbprint()?;
+        let id = string("jti")?;
+
+        let mut seen = self.seen.lock().ok()?;
+        seen.retain(|_, issued| *issued >= oldest);
+        if seen.contains_key(id) {
+            return None;
+        }
+
+        seen.insert(id.to_string(), issued);
+        Some(thumbprint)
+    }
+}
+
+impl Default for DpopMap {
+    fn default() -> Self {
+        DpopMap::new()
+    }
+}
+
+impl DpopVerifier for DpopMap {
+    fn verify(
+        &self, proof: &str, method: &str, uri: &Url, access_token: Option<&str>,
+    ) -> Option<String> {
+        self.check(proof, method, uri, access_token, Utc::now())
+    }
+}
+
+impl<'a, V: DpopVerifier + ?Sized> DpopVerifier for &'a V {
+    fn verify(
+        &self, proof: &str, method: &str, uri: &Url, access_token: Option<&str>,
+    ) -> Option<String> {
+        (**self).verify(proof, method, uri, access_token)
+    }
+}
+
+impl<V: DpopVerifier + ?Sized> DpopVerifier for Box<V> {
+    fn verify(
+        &self, proof: &str, method: &str, uri: &Url, access_token: Option<&str>,
+    ) -> Option<String> {
+        (**self).verify(proof, method, uri, access_token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::generator::{Assertion, AssertionKind};
+    use ring::rand::SystemRandom;
+    use ring::signature::Ed25519KeyPair;
+    use serde_json::json;
+
+    fn key() -> Assertion {
+        let key = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        Assertion::new(AssertionKind::Ed25519, key.as_ref())
+    }
+
+    fn proof(key: &Assertion, header: JsonValue, claims: JsonValue) -> String {
+        let encode = |value: &JsonValue| encode_config(value.to_string(), URL_SAFE_NO_PAD);
+        let signed = format!("{}.{}", encode(&header), encode(&claims));
+        let signature = key.sign_detached(signed.as_bytes()).unwrap();
+        format!("{}.{}", signed, encode_config(&signature, URL_SAFE_NO_PAD))
+    }
+
+    fn header(key: &Assertion) -> JsonValue {
+        json!({
+            "typ": "dpop+jwt",
+            "alg": "EdDSA",
+            "jwk": Jwk::from_assertion(key).unwrap(),
+        })
+    }
+
+    fn claims(jti: &str, iat: i64) -> JsonValue {
+        json!({
+            "jti": jti,
+            "htm": "POST",
+            "htu": "https://server.example/token",
+            "iat": iat,
+        })
+    }
+
+    #[test]
+    fn dpop_valid_proof() {
+        let map = DpopMap::new();
+        let key = key();
+        let uri: Url = "https://server.example/token?ignored=query".parse().unwrap();
+        let now = Utc::now();
+
+        let proof = proof(&key, header(&key), claims("first", now.timestamp()));
+        let thumbprint = Jwk::from_assertion(&key).unwrap().thumbprint();
+        assert_eq!(map.check(&proof, "POST", &uri, None, now), thumbprint);
+
+        // The same proof must not be accepted twice.
+        assert_eq!(map.check(&proof, "POST", &uri, None, now), None);
+    }
+
+    #[test]
+    fn dpop_invalid_proofs() {
+        let map = DpopMap::new();
+        let key = key();
+        let uri: Url = "https://server.example/token".parse().unwrap();
+        let now = Utc::now();
+        let iat = now.timestamp();
+
+        let mut wrong_type = header(&key);
+        wrong_type["typ"] = "JWT".into();
+        let mut wrong_alg = header(&key);
+        wrong_alg["alg"] = "ES256".into();
+        let mut private = header(&key);
+        private["jwk"]["d"] = "secret".into();
+        let mut foreign = header(&key);
+        foreign["jwk"] = json!(Jwk::from_assertion(&self::key()).unwrap());
+
+        let mut wrong_method = claims("method", iat);
+        wrong_method["htm"] = "GET".into();
+        let mut wrong_target = claims("target", iat);
+        wrong_target["htu"] = "https://server.example/other".into();
+        let mut with_hash = claims("hash", iat);
+        with_hash["ath"] = token_hash("token").into();
+
+        let proofs = vec![
+            proof(&key, wrong_type, claims("type", iat)),
+            proof(&key, wrong_alg, claims("alg", iat)),
+            proof(&key, private, claims("private", iat)),
+            proof(&key, foreign, claims("foreign", iat)),
+            proof(&key, header(&key), wrong_method),
+            proof(&key, header(&key), wrong_target),
+            proof(&key, header(&key), with_hash),
+            proof(&key, header(&key), claims("old", iat - 301)),
+            proof(&key, header(&key), claims("future", iat + 301)),
+            "not.a.proof".to_string(),
+        ];
+
+        for proof in proofs {
+            assert_eq!(map.check(&proof, "POST", &uri, None, now), None, "{}", proof);
+        }
+    }
+
+    #[test]
+    fn dpop_out_of_range_iat() {
+        let map = DpopMap::new();
+        let key = key();
+        let uri: Url = "https://server.example/token".parse().unwrap();
+        let now = Utc::now();
+
+        // The first and last second that can be represented, and times beyond them.
+        let out_of_range = vec![-8_334_601_228_800, 8_210_266_876_799, i64::MIN, i64::MAX];
+
+        for iat in out_of_range {
+            let proof = proof(&key, header(&key), claims("range", iat));
+            assert_eq!(map.check(&proof, "POST", &uri, None, now), None, "{}", iat);
+        }
+    }
+
+    #[test]
+    fn dpop_access_token_hash() {
+        let map = DpopMap::new();
+        let key = key();
+        let uri: Url = "https://server.example/token".parse().unwrap();
+        let now = Utc::now();
+
+        let mut with_hash = claims("hash", now.timestamp());
+        with_hash["ath"] = token_hash("token").into();
+        let proof = proof(&key, header(&key), with_hash);
+
+        assert_eq!(map.check(&proof, "POST", &uri, Some("other"), now), None);
+        assert!(map.check(&proof, "POST", &uri, Some("token"), now).is_some());
+    }
+}
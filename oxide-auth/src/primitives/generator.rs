@@ -16,11 +16,18 @@ use super::scope::Scope;
 
 use std::collections::HashMap;
 use std::rc::Rc;
-use std::sync::Arc;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-use base64::{encode, decode};
-use hmac::{crypto_mac::Output, Mac, Hmac, NewMac};
+use base64::{encode, encode_config, decode, URL_SAFE_NO_PAD};
+use hmac::{Mac, Hmac, NewMac};
 use rand::{rngs::OsRng, RngCore, thread_rng};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::signature::{
+    EcdsaKeyPair, Ed25519KeyPair, KeyPair, RsaKeyPair, UnparsedPublicKey, VerificationAlgorithm,
+    ECDSA_P256_SHA256_FIXED, ECDSA_P256_SHA256_FIXED_SIGNING, ED25519, RSA_PSS_2048_8192_SHA256,
+    RSA_PSS_SHA256,
+};
 use serde::{Deserialize, Serialize};
 use rmp_serde;
 
@@ -72,27 +79,94 @@ impl RandomGenerator {
     }
 }
 
+/// Derives the keys under which stores keep their tokens.
+///
+/// The key of a token is its HMAC-SHA256 under a server secret. A store that only keeps these keys
+/// does not reveal any live token through a memory dump or a persisted snapshot, while the secret
+/// makes it impossible to test guesses against such a snapshot. Presented tokens are hashed before
+/// they are looked up.
+#[derive(Clone)]
+pub struct TokenHasher {
+    mac: Hmac<sha2::Sha256>,
+}
+
+impl TokenHasher {
+    /// Hash tokens under the given secret.
+    pub fn new(secret: &[u8]) -> Self {
+        TokenHasher {
+            mac: Hmac::new_varkey(secret).expect("HMAC accepts keys of any length"),
+        }
+    }
+
+    /// The base64url encoded key of a token.
+    pub fn hash(&self, token: &str) -> String {
+        let mut mac = self.mac.clone();
+        mac.update(token.as_bytes());
+        encode_config(mac.finalize().into_bytes(), URL_SAFE_NO_PAD)
+    }
+}
+
 /// Generates tokens by signing its specifics with a private key.
 ///
-/// Tokens produced by the generator include a serialized version of the grant followed by an HMAC
-/// signature.  Since data is not encrypted, this token generator will ERROR if any private
-/// extension is present in the grant.
+/// Tokens produced by the generator include a serialized version of the grant followed by a
+/// signature of the chosen `AssertionKind`.  The grant is readable by the token holder, except for
+/// private extensions which are encrypted with ChaCha20-Poly1305 under a separate key, see
+/// `Assertion::set_encryption_key`. Without such a key, this token generator will ERROR if any
+/// private extension is present in the grant.
 ///
 /// The actual generator is given by a `TaggedAssertion` from `Assertion::tag` which enables
 /// signing the same grant for different uses, i.e. separating authorization from bearer grants and
 /// refresh tokens.
+///
+/// With the asymmetric suites, an assertion constructed by `Assertion::verify_only` from the public
+/// key of another assertion can check its tokens but not produce new ones.
 pub struct Assertion {
-    hasher: Hmac<sha2::Sha256>,
+    kind: AssertionKind,
+    key: AssertionKey,
+    encryption: Option<LessSafeKey>,
+    random: SystemRandom,
 }
 
 /// The cryptographic suite ensuring integrity of tokens.
 #[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum AssertionKind {
     /// Uses [HMAC (RFC 2104)][HMAC] with [SHA-256 (FIPS 180-4)][SHA256] hash.
     ///
     /// [HMAC]: https://tools.ietf.org/html/rfc2104
     /// [SHA256]: https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf
     HmacSha256,
+
+    /// Uses [Ed25519 (RFC 8032)][Ed25519] signatures.
+    ///
+    /// The signing key is a PKCS#8 document, the public key the 32 byte encoded point.
+    ///
+    /// [Ed25519]: https://tools.ietf.org/html/rfc8032
+    Ed25519,
+
+    /// Uses [ECDSA (FIPS 186-4)][ECDSA] over the P-256 curve with SHA-256 hash.
+    ///
+    /// Signatures have the fixed length encoding also used by JWS. The signing key is a PKCS#8
+    /// document, the public key the uncompressed encoded point.
+    ///
+    /// [ECDSA]: https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.186-4.pdf
+    EcdsaP256Sha256,
+
+    /// Uses [RSASSA-PSS (RFC 8017)][PSS] with SHA-256 hash.
+    ///
+    /// The signing key is a PKCS#8 document of at least 2048 bits, the public key the DER encoded
+    /// `RSAPublicKey`.
+    ///
+    /// [PSS]: https://tools.ietf.org/html/rfc8017
+    RsaPssSha256,
+}
+
+enum AssertionKey {
+    Hmac(Hmac<sha2::Sha256>),
+    Ed25519(Ed25519KeyPair),
+    Ecdsa(EcdsaKeyPair),
+    Rsa(RsaKeyPair),
+    Public(Vec<u8>),
 }
 
 #[derive(Serialize, Deserialize)]
@@ -116,16 +190,44 @@ struct SerdeAssertionGrant {
     #[serde(with = "time_serde")]
     until: Time,
 
-    /// The public extensions, private extensions not supported currently
+    /// The public extensions.
     public_extensions: HashMap<String, Option<String>>,
+
+    /// The serialized private extensions, encrypted unless they are only signed.
+    private_extensions: Vec<u8>,
 }
 
 #[derive(Serialize, Deserialize)]
 struct AssertGrant(Vec<u8>, Vec<u8>);
 
+#[derive(Serialize, Deserialize)]
+struct KeyedAssertGrant(String, AssertGrant);
+
 /// Binds a tag to the data. The signature will be unique for data as well as the tag.
 pub struct TaggedAssertion<'a>(&'a Assertion, &'a str);
 
+/// A set of assertions identified by key ids, one of which signs new tokens.
+///
+/// Tokens produced by the keyring embed the id of their signing key and are verified with the same
+/// key. This allows rotating the signing key while tokens of previous keys stay valid until their
+/// key is retired, for example after the longest token lifetime has passed. Verify-only assertions
+/// of keys held elsewhere can be added as well.
+///
+/// Clones of a keyring share their keys. Keep a clone to rotate keys at runtime while another one
+/// is part of an endpoint, for example in a `TokenSigner`.
+#[derive(Clone)]
+pub struct Keyring {
+    inner: Arc<RwLock<Keys>>,
+}
+
+struct Keys {
+    active: String,
+    keys: HashMap<String, Arc<Assertion>>,
+}
+
+/// Binds a tag to the tokens of a keyring, see `TaggedAssertion`.
+pub struct TaggedKeyring<'a>(&'a Keyring, &'a str);
+
 impl Assertion {
     /// Construct an assertion from a custom secret.
     ///
@@ -133,29 +235,106 @@ impl Assertion {
     /// padding or shortening of the supplied key material may be applied in the form dictated by
     /// the signature type. See the respective standards.
     ///
-    /// If future suites are added where this is not possible, this function may panic when supplied
-    /// with an incorrect key length.
-    ///
-    /// Currently, the implementation lacks the ability to really make use of another hasing mechanism than
-    /// hmac + sha256.
+    /// The asymmetric suites expect their private key in the format documented on the respective
+    /// `AssertionKind`. This function panics when supplied with a key it can not parse, use
+    /// `Assertion::try_new` to handle this case instead.
     pub fn new(kind: AssertionKind, key: &[u8]) -> Self {
-        match kind {
-            AssertionKind::HmacSha256 => Assertion {
-                hasher: Hmac::<sha2::Sha256>::new_varkey(key).unwrap(),
-            },
+        Self::try_new(kind, key).expect("Invalid key for the assertion kind")
+    }
+
+    /// Construct an assertion from a custom secret, failing for an invalid key.
+    pub fn try_new(kind: AssertionKind, key: &[u8]) -> Result<Self, ()> {
+        let key = match kind {
+            AssertionKind::HmacSha256 => {
+                AssertionKey::Hmac(Hmac::<sha2::Sha256>::new_varkey(key).map_err(|_| ())?)
+            }
+            AssertionKind::Ed25519 => {
+                AssertionKey::Ed25519(Ed25519KeyPair::from_pkcs8_maybe_unchecked(key).map_err(|_| ())?)
+            }
+            AssertionKind::EcdsaP256Sha256 => AssertionKey::Ecdsa(
+                EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, key).map_err(|_| ())?,
+            ),
+            AssertionKind::RsaPssSha256 => {
+                AssertionKey::Rsa(RsaKeyPair::from_pkcs8(key).map_err(|_| ())?)
+            }
+        };
+
+        Ok(Assertion {
+            kind,
+            key,
+            encryption: None,
+            random: SystemRandom::new(),
+        })
+    }
+
+    /// Construct an assertion that only verifies tokens, from the public key of an asymmetric suite.
+    ///
+    /// The public key is expected in the format documented on the respective `AssertionKind`. It is
+    /// only parsed when verifying, tokens will simply not be valid for a malformed key. Since
+    /// `HmacSha256` has no public key, this fails for that kind.
+    pub fn verify_only(kind: AssertionKind, public_key: &[u8]) -> Result<Self, ()> {
+        if kind == AssertionKind::HmacSha256 {
+            return Err(());
         }
+
+        Ok(Assertion {
+            kind,
+            key: AssertionKey::Public(public_key.to_vec()),
+            encryption: None,
+            random: SystemRandom::new(),
+        })
     }
 
     /// Construct an assertion instance whose tokens are only valid for the program execution.
+    ///
+    /// Private extensions are encrypted with another random key.
     pub fn ephemeral() -> Self {
         // TODO Extract KeySize from currently selected hasher
         let mut rand_bytes: [u8; 32] = [0; 32];
         thread_rng().fill_bytes(&mut rand_bytes);
-        Assertion {
-            hasher: Hmac::<sha2::Sha256>::new_varkey(&rand_bytes).unwrap(),
+        let mut assertion = Assertion::new(AssertionKind::HmacSha256, &rand_bytes);
+
+        thread_rng().fill_bytes(&mut rand_bytes);
+        assertion.set_encryption_key(&rand_bytes).unwrap();
+        assertion
+    }
+
+    /// Encrypt private extensions with a 32 byte key for ChaCha20-Poly1305.
+    ///
+    /// Tokens with private extensions can only be recovered by an assertion with the same key.
+    /// This applies to verify-only assertions as well, which may decrypt but not sign tokens. Since
+    /// nonces are chosen at random, the key should be changed long before 2^32 tokens have been
+    /// produced with it. Fails if the key has the wrong length.
+    pub fn set_encryption_key(&mut self, key: &[u8]) -> Result<(), ()> {
+        let key = UnboundKey::new(&CHACHA20_POLY1305, key).map_err(|_| ())?;
+        self.encryption = Some(LessSafeKey::new(key));
+        Ok(())
+    }
+
+    /// The cryptographic suite of this assertion.
+    pub fn kind(&self) -> AssertionKind {
+        self.kind
+    }
+
+    /// The public key verifying the tokens of this assertion.
+    ///
+    /// The format is documented on the respective `AssertionKind`. This is `None` for
+    /// `HmacSha256`, where the secret key is required for verification.
+    pub fn public_key(&self) -> Option<&[u8]> {
+        match &self.key {
+            AssertionKey::Hmac(_) => None,
+            AssertionKey::Ed25519(pair) => Some(pair.public_key().as_ref()),
+            AssertionKey::Ecdsa(pair) => Some(pair.public_key().as_ref()),
+            AssertionKey::Rsa(pair) => Some(pair.public_key().as_ref()),
+            AssertionKey::Public(key) => Some(key),
         }
     }
 
+    /// Check whether this assertion can sign new tokens.
+    pub fn can_sign(&self) -> bool {
+        !matches!(self.key, AssertionKey::Public(_))
+    }
+
     /// Get a reference to generator for the given tag.
     pub fn tag<'a>(&'a self, tag: &'a str) -> TaggedAssertion<'a> {
         TaggedAssertion(self, tag)
@@ -164,37 +343,240 @@ impl Assertion {
     fn extract<'a>(&self, token: &'a str) -> Result<(Grant, String), ()> {
         let decoded = decode(token).map_err(|_| ())?;
         let assertion: AssertGrant = rmp_serde::from_slice(&decoded).map_err(|_| ())?;
+        self.open(assertion)
+    }
 
-        let mut hasher = self.hasher.clone();
-        hasher.update(&assertion.0);
-        hasher.verify(assertion.1.as_slice()).map_err(|_| ())?;
+    fn open(&self, assertion: AssertGrant) -> Result<(Grant, String), ()> {
+        if !self.verify_detached(&assertion.0, &assertion.1) {
+            return Err(());
+        }
 
         let (_, serde_grant, tag): (u64, SerdeAssertionGrant, String) =
             rmp_serde::from_slice(&assertion.0).map_err(|_| ())?;
 
-        Ok((serde_grant.grant(), tag))
+        Ok((serde_grant.grant(self)?, tag))
+    }
+
+    /// The JWS `alg` identifier of the suite, see RFC 7518 and RFC 8037.
+    pub(crate) fn jws_algorithm(&self) -> &'static str {
+        match self.kind {
+            AssertionKind::HmacSha256 => "HS256",
+            AssertionKind::Ed25519 => "EdDSA",
+            AssertionKind::EcdsaP256Sha256 => "ES256",
+            AssertionKind::RsaPssSha256 => "PS256",
+        }
+    }
+
+    /// Sign arbitrary data, without any envelope.
+    ///
+    /// Fails for verify-only assertions.
+    pub(crate) fn sign_detached(&self, data: &[u8]) -> Result<Vec<u8>, ()> {
+        match &self.key {
+            AssertionKey::Hmac(hasher) => {
+                let mut hasher = hasher.clone();
+                hasher.update(data);
+                Ok(hasher.finalize().into_bytes().to_vec())
+            }
+            AssertionKey::Ed25519(pair) => Ok(pair.sign(data).as_ref().to_vec()),
+            AssertionKey::Ecdsa(pair) => pair
+                .sign(&self.random, data)
+                .map(|signature| signature.as_ref().to_vec())
+                .map_err(|_| ()),
+            AssertionKey::Rsa(pair) => {
+                let mut signature = vec![0; pair.public_modulus_len()];
+                pair.sign(&RSA_PSS_SHA256, &self.random, data, &mut signature)
+                    .map_err(|_| ())?;
+                Ok(signature)
+            }
+            AssertionKey::Public(_) => Err(()),
+        }
+    }
+
+    /// Verify a signature created by `sign_detached`.
+    pub(crate) fn verify_detached(&self, data: &[u8], signature: &[u8]) -> bool {
+        if let AssertionKey::Hmac(hasher) = &self.key {
+            let mut hasher = hasher.clone();
+            hasher.update(data);
+            return hasher.verify(signature).is_ok();
+        }
+
+        let algorithm: &dyn VerificationAlgorithm = match self.kind {
+            AssertionKind::HmacSha256 => return false,
+            AssertionKind::Ed25519 => &ED25519,
+            AssertionKind::EcdsaP256Sha256 => &ECDSA_P256_SHA256_FIXED,
+            AssertionKind::RsaPssSha256 => &RSA_PSS_2048_8192_SHA256,
+        };
+
+        let public_key = match self.public_key() {
+            Some(public_key) => public_key,
+            None => return false,
+        };
+
+        UnparsedPublicKey::new(algorithm, public_key)
+            .verify(data, signature)
+            .is_ok()
     }
 
-    fn signature(&self, data: &[u8]) -> Output<hmac::Hmac<sha2::Sha256>> {
-        let mut hasher = self.hasher.clone();
-        hasher.update(data);
-        hasher.finalize()
+    /// Encrypt data with the extension key, prepending the random nonce.
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>, ()> {
+        let key = self.encryption.as_ref().ok_or(())?;
+        let mut nonce = [0; NONCE_LEN];
+        self.random.fill(&mut nonce).map_err(|_| ())?;
+
+        let mut sealed = data.to_vec();
+        key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut sealed)
+            .map_err(|_| ())?;
+
+        let mut encrypted = nonce.to_vec();
+        encrypted.extend(sealed);
+        Ok(encrypted)
+    }
+
+    fn decrypt(&self, encrypted: &[u8]) -> Result<Vec<u8>, ()> {
+        let key = self.encryption.as_ref().ok_or(())?;
+        if encrypted.len() < NONCE_LEN {
+            return Err(());
+        }
+
+        let (nonce, sealed) = encrypted.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| ())?;
+        let mut sealed = sealed.to_vec();
+        let data = key
+            .open_in_place(nonce, Aad::empty(), &mut sealed)
+            .map_err(|_| ())?;
+        Ok(data.to_vec())
     }
 
     fn counted_signature(&self, counter: u64, grant: &Grant) -> Result<String, ()> {
-        let serde_grant = SerdeAssertionGrant::try_from(grant)?;
+        // Only the signature becomes part of the token, private extensions need no encryption.
+        let serde_grant = SerdeAssertionGrant::signed(grant);
         let tosign = rmp_serde::to_vec(&(serde_grant, counter)).unwrap();
-        let signature = self.signature(&tosign);
-        Ok(base64::encode(&signature.into_bytes()))
+        let signature = self.sign_detached(&tosign)?;
+        Ok(base64::encode(&signature))
     }
 
     fn generate_tagged(&self, counter: u64, grant: &Grant, tag: &str) -> Result<String, ()> {
-        let serde_grant = SerdeAssertionGrant::try_from(grant)?;
+        let assert = self.seal(counter, grant, tag)?;
+        Ok(encode(&rmp_serde::to_vec(&assert).unwrap()))
+    }
+
+    fn seal(&self, counter: u64, grant: &Grant, tag: &str) -> Result<AssertGrant, ()> {
+        let serde_grant = SerdeAssertionGrant::try_from(grant, self)?;
         let tosign = rmp_serde::to_vec(&(counter, serde_grant, tag)).unwrap();
-        let signature = self.signature(&tosign);
-        let assert = AssertGrant(tosign, signature.into_bytes().to_vec());
+        let signature = self.sign_detached(&tosign)?;
+        Ok(AssertGrant(tosign, signature))
+    }
+}
 
-        Ok(encode(&rmp_serde::to_vec(&assert).unwrap()))
+impl Keyring {
+    /// Create a keyring signing with the assertion identified by `kid`.
+    ///
+    /// A keyring created from a verify-only assertion only verifies tokens until it is rotated to
+    /// a signing key.
+    pub fn new(kid: &str, assertion: Assertion) -> Self {
+        let mut keys = HashMap::new();
+        keys.insert(kid.to_string(), Arc::new(assertion));
+
+        Keyring {
+            inner: Arc::new(RwLock::new(Keys {
+                active: kid.to_string(),
+                keys,
+            })),
+        }
+    }
+
+    /// Create a keyring with a random key, whose tokens are only valid for the program execution.
+    pub fn ephemeral() -> Self {
+        Keyring::new("ephemeral", Assertion::ephemeral())
+    }
+
+    /// Sign new tokens with the assertion from now on.
+    ///
+    /// The previously active key stays available for verification until it is retired. Fails if
+    /// the assertion can not sign.
+    pub fn rotate(&self, kid: &str, assertion: Assertion) -> Result<(), ()> {
+        if !assertion.can_sign() {
+            return Err(());
+        }
+
+        let mut keys = self.write();
+        keys.keys.insert(kid.to_string(), Arc::new(assertion));
+        keys.active = kid.to_string();
+        Ok(())
+    }
+
+    /// Accept tokens of an additional key without signing with it.
+    ///
+    /// Replaces a previous key with the same id. Fails if `kid` identifies the active key.
+    pub fn insert(&self, kid: &str, assertion: Assertion) -> Result<(), ()> {
+        let mut keys = self.write();
+        if keys.active == kid {
+            return Err(());
+        }
+
+        keys.keys.insert(kid.to_string(), Arc::new(assertion));
+        Ok(())
+    }
+
+    /// Stop accepting the tokens of a key.
+    ///
+    /// Fails if `kid` identifies the active key, rotate to another key first. Retiring an unknown
+    /// key has no effect.
+    pub fn retire(&self, kid: &str) -> Result<(), ()> {
+        let mut keys = self.write();
+        if keys.active == kid {
+            return Err(());
+        }
+
+        keys.keys.remove(kid);
+        Ok(())
+    }
+
+    /// The id of the key signing new tokens.
+    pub fn active(&self) -> String {
+        self.read().active.clone()
+    }
+
+    /// Get the assertion of a key.
+    pub fn get(&self, kid: &str) -> Option<Arc<Assertion>> {
+        self.read().keys.get(kid).cloned()
+    }
+
+    /// The ids of all keys whose tokens are accepted.
+    pub fn kids(&self) -> Vec<String> {
+        self.read().keys.keys().cloned().collect()
+    }
+
+    /// Get a reference to generator for the given tag.
+    pub fn tag<'a>(&'a self, tag: &'a str) -> TaggedKeyring<'a> {
+        TaggedKeyring(self, tag)
+    }
+
+    pub(crate) fn generate_tagged(&self, counter: u64, grant: &Grant, tag: &str) -> Result<String, ()> {
+        let (kid, assertion) = {
+            let keys = self.read();
+            (keys.active.clone(), keys.keys[&keys.active].clone())
+        };
+
+        let assert = assertion.seal(counter, grant, tag)?;
+        let keyed = KeyedAssertGrant(kid, assert);
+        Ok(encode(rmp_serde::to_vec(&keyed).unwrap()))
+    }
+
+    pub(crate) fn extract(&self, token: &str) -> Result<(Grant, String), ()> {
+        let decoded = decode(token).map_err(|_| ())?;
+        let KeyedAssertGrant(kid, assertion) = rmp_serde::from_slice(&decoded).map_err(|_| ())?;
+        let key = self.get(&kid).ok_or(())?;
+        key.open(assertion)
+    }
+
+    // The keys are consistent at all times, so a panic of another thread can be ignored.
+    fn read(&self) -> RwLockReadGuard<'_, Keys> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Keys> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
     }
 }
 
@@ -221,6 +603,25 @@ impl<'a> TaggedAssertion<'a> {
     }
 }
 
+impl<'a> TaggedKeyring<'a> {
+    /// Sign the grant for this usage with the active key.
+    ///
+    /// See `TaggedAssertion::sign`, the token additionally contains the id of the key.
+    pub fn sign(&self, counter: u64, grant: &Grant) -> Result<String, ()> {
+        self.0.generate_tagged(counter, grant, self.1)
+    }
+
+    /// Retrieve the grant of a token signed by any key of the keyring.
+    ///
+    /// Result in an Err if the key is unknown, the signature is invalid or if the tag does not
+    /// match the expected usage tag.
+    pub fn extract(&self, token: &str) -> Result<Grant, ()> {
+        self.0
+            .extract(token)
+            .and_then(|(token, tag)| if tag == self.1 { Ok(token) } else { Err(()) })
+    }
+}
+
 impl<'a, T: TagGrant + ?Sized + 'a> TagGrant for Box<T> {
     fn tag(&mut self, counter: u64, grant: &Grant) -> Result<String, ()> {
         (&mut **self).tag(counter, grant)
@@ -318,7 +719,7 @@ mod time_serde {
     use chrono::{TimeZone, Utc};
 
     use serde::ser::{Serializer};
-    use serde::de::{Deserialize, Deserializer};
+    use serde::de::{Deserialize, Deserializer, Error};
 
     pub fn serialize<S: Serializer>(time: &Time, serializer: S) -> Result<S::Ok, S::Error> {
         serializer.serialize_i64(time.timestamp())
@@ -326,51 +727,246 @@ mod time_serde {
 
     pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Time, D::Error> {
         let as_timestamp: i64 = <i64>::deserialize(deserializer)?;
-        Ok(Utc.timestamp(as_timestamp, 0))
+        Utc.timestamp_opt(as_timestamp, 0)
+            .single()
+            .ok_or_else(|| Error::custom("timestamp out of range"))
     }
 }
 
 impl SerdeAssertionGrant {
-    fn try_from(grant: &Grant) -> Result<Self, ()> {
-        let mut public_extensions: HashMap<String, Option<String>> = HashMap::new();
+    /// Serialize the grant for a token, encrypting private extensions with the assertion.
+    fn try_from(grant: &Grant, assertion: &Assertion) -> Result<Self, ()> {
+        let private_extensions = match Self::private_extensions(grant) {
+            Some(private) => assertion.encrypt(&private)?,
+            None => Vec::new(),
+        };
+
+        Ok(Self::with_private(grant, private_extensions))
+    }
 
-        if grant.extensions.private().any(|_| true) {
-            return Err(());
+    /// Serialize the grant for a signature only, private extensions are not encrypted.
+    fn signed(grant: &Grant) -> Self {
+        let private_extensions = Self::private_extensions(grant).unwrap_or_default();
+        Self::with_private(grant, private_extensions)
+    }
+
+    fn private_extensions(grant: &Grant) -> Option<Vec<u8>> {
+        let private: HashMap<&str, Option<&str>> = grant.extensions.private().collect();
+        if private.is_empty() {
+            return None;
         }
 
+        Some(rmp_serde::to_vec(&private).unwrap())
+    }
+
+    fn with_private(grant: &Grant, private_extensions: Vec<u8>) -> Self {
+        let mut public_extensions: HashMap<String, Option<String>> = HashMap::new();
+
         for (name, content) in grant.extensions.public() {
             public_extensions.insert(name.to_string(), content.map(str::to_string));
         }
 
-        Ok(SerdeAssertionGrant {
+        SerdeAssertionGrant {
             owner_id: grant.owner_id.clone(),
             client_id: grant.client_id.clone(),
             scope: grant.scope.clone(),
             redirect_uri: grant.redirect_uri.clone(),
             until: grant.until,
             public_extensions,
-        })
+            private_extensions,
+        }
     }
 
-    fn grant(self) -> Grant {
+    fn grant(self, assertion: &Assertion) -> Result<Grant, ()> {
         let mut extensions = Extensions::new();
         for (name, content) in self.public_extensions.into_iter() {
             extensions.set_raw(name, Value::public(content))
         }
-        Grant {
+
+        if !self.private_extensions.is_empty() {
+            let private = assertion.decrypt(&self.private_extensions)?;
+            let private: HashMap<String, Option<String>> =
+                rmp_serde::from_slice(&private).map_err(|_| ())?;
+            for (name, content) in private.into_iter() {
+                extensions.set_raw(name, Value::private(content))
+            }
+        }
+
+        Ok(Grant {
             owner_id: self.owner_id,
             client_id: self.client_id,
             scope: self.scope,
             redirect_uri: self.redirect_uri,
             until: self.until,
             extensions,
-        }
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::primitives::grant::Extensions;
+    use chrono::{Duration, Utc};
+
+    /// A 2048 bit RSA key in PKCS#8 format, ring can not generate these.
+    const RSA_PKCS8: &str = concat!(
+        "MIIEvgIBADANBgkqhkiG9w0BAQEFAASCBKgwggSkAgEAAoIBAQClYyARj7o22ldn7VLDN2ildPxBY3TESqwoaPqL+J/T",
+        "80DpciauoDghXbnW2TsFbI7uiIVGIUFII9iu5Pgq9VsEgl8h+xBsBmO7fziVT4vLQHh/Lx6r9Rlbf8YJh62edttERcfo",
+        "T9YGBTBEDC0CJt9CHmDrNbeiBohJOrGF1UplVZcJ8DmqDKYz+eGFhDQTCD1D0soi1nlTHD0J9drkc01zJW4kyY9Deg0s",
+        "b6VhYqZL8MEhZ0NotEnkLBrgOmVpDm36uf9Vq3dm42NPVnCenKKqvDiQg8lpTrNDSJAekWGJBvXXBi1nyWxVRcaywEzv",
+        "nFlArbfjETV3svXsJmevSGyPAgMBAAECggEARWeMSSJePG+zV3c2EJtbjHfD9nLUyR0nEww+/GdZNzpxZqYTSpwNAh+R",
+        "gfKGfLe6Yaqj9zK0cSAt6fNHm8t8Jy/I2XtBQHwL9V6lsa8AlMqY5pKxFeuU+RttUYmBGCt7x7Ja+ogqYjtiLmRHrQwA",
+        "DACwnnRb/CAuqEB7yBSilFRBhZirjiPHaHL24PyOQF7RxCizlUYxZLQce5Ur9O2HJ6asJp4hgqAIz9MUC8pp77CX64NO",
+        "bIHUXONWGbBfXbRrm6gxB91L7IwwBFxqFYhyqr7zVwL16pfRSmiek3AmHa7HCESOvukZOAHb3nWCrnhvr7y/0E9hpiTF",
+        "L3LdFvv9QQKBgQDlZ5WY0y2dtlgy06xCaIqnAkg0akRso+t3nJNIqYWR3i+NDoS9wg2eO/E8w+Sa126U/60EByk9sqaW",
+        "BLM6UvSNgNHYYmvOnB/L/XFwRi5mcv2oJUubKvC7zXrOdz8gE7V68zbj7+d4UozwsdCIXwjosFIideKKSafkFWN3Byy3",
+        "wQKBgQC4j5dqE3gtNty3hia3q/Em9SFZJdZhO2oeGIeW+MjOYNJqq2EvHu/w2ewct40ZRbFAodW1Hjud720v9PCUOhWG",
+        "6Vs4C1SpJH6VdTJadrSTZZ+mkXIUHpm6Bg3v2/PjYaRCyxdA6N9I/6K3T7kCwgnyN4Gpytlu9u89V/bQhqm4TwKBgQC1",
+        "/Qnxlj6O6jRoBgKXIEY2nZnYcyFbKmNHnaA+ObXr9V61wXnO7B5PlHAZa7J0oElqzLZeBqxR9OMxCgOBVoQJqdNPnSy3",
+        "WKXk92fB0PtIbRsMBKJJM7SM2EL/fhxG/zuukTnPc+emTZ7f58BuHy57Ij3WdrzERhB54fnpWHjCQQKBgB6ZoC80Py6v",
+        "8/Rxx6WYWoltBzRys5K3eSa4RnLfmNuu7fRFqf1YEcxLNXylaj2Qco0WyAoZkyTid6YF5IWPA9a0hi1Ko6TPqjU5kzmw",
+        "iMwPg2LoppNzZ2WSb2kffNZscAGrDpXyhmLiD6Z5D2HHrkJ8tYDjYx2kz5HKLd/xE969AoGBAKGLqOvy5yx1xOzkHEH9",
+        "CBVlK1lRlrAsxK8telq49UOfn9DSluCzhVnkRUvGKc3QKsr9ZMjLKkQ1XL/Q9K4lebZDUgjdk6zE/vZIB+xMSZkzPN0l",
+        "MOlHZO0PnNlYji1qWOjD3eF8AF/KbZej4vZUPFyAgIUkbLr/Le2meuJ9azaz",
+    );
+
+    fn signing_key(kind: AssertionKind) -> Vec<u8> {
+        let random = SystemRandom::new();
+        match kind {
+            AssertionKind::HmacSha256 => b"a shared secret".to_vec(),
+            AssertionKind::Ed25519 => Ed25519KeyPair::generate_pkcs8(&random).unwrap().as_ref().to_vec(),
+            AssertionKind::EcdsaP256Sha256 => {
+                EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &random)
+                    .unwrap()
+                    .as_ref()
+                    .to_vec()
+            }
+            AssertionKind::RsaPssSha256 => decode(RSA_PKCS8).unwrap(),
+        }
+    }
+
+    fn grant() -> Grant {
+        Grant {
+            owner_id: "Owner".to_string(),
+            client_id: "Client".to_string(),
+            scope: "default".parse().unwrap(),
+            redirect_uri: "https://example.com".parse().unwrap(),
+            until: Utc::now() + Duration::hours(1),
+            extensions: Extensions::new(),
+        }
+    }
+
+    const ASYMMETRIC: &[AssertionKind] = &[
+        AssertionKind::Ed25519,
+        AssertionKind::EcdsaP256Sha256,
+        AssertionKind::RsaPssSha256,
+    ];
+
+    #[test]
+    fn assertion_round_trip() {
+        for &kind in [AssertionKind::HmacSha256].iter().chain(ASYMMETRIC) {
+            let assertion = Assertion::new(kind, &signing_key(kind));
+            assert_eq!(assertion.kind(), kind);
+            assert!(assertion.can_sign());
+
+            let token = assertion.tag("token").sign(0, &grant()).unwrap();
+            let recovered = assertion.tag("token").extract(&token).unwrap();
+            assert_eq!(recovered.owner_id, "Owner");
+            assert!(assertion.tag("refresh").extract(&token).is_err());
+
+            // Only the generated keys differ between calls.
+            if kind == AssertionKind::Ed25519 || kind == AssertionKind::EcdsaP256Sha256 {
+                let other = Assertion::new(kind, &signing_key(kind));
+                assert!(other.tag("token").extract(&token).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn assertion_verify_only() {
+        for &kind in ASYMMETRIC {
+            let assertion = Assertion::new(kind, &signing_key(kind));
+            let public_key = assertion.public_key().unwrap();
+            let verifier = Assertion::verify_only(kind, public_key).unwrap();
+            assert!(!verifier.can_sign());
+            assert_eq!(verifier.public_key(), Some(public_key));
+
+            let token = assertion.tag("token").sign(0, &grant()).unwrap();
+            assert!(verifier.tag("token").extract(&token).is_ok());
+            assert!(verifier.tag("token").sign(1, &grant()).is_err());
+
+            let mut tampered = decode(&token).unwrap();
+            let last = tampered.len() - 1;
+            tampered[last] ^= 1;
+            assert!(verifier.tag("token").extract(&encode(&tampered)).is_err());
+        }
+
+        assert!(Assertion::verify_only(AssertionKind::HmacSha256, b"a shared secret").is_err());
+        assert!(Assertion::new(AssertionKind::HmacSha256, b"secret")
+            .public_key()
+            .is_none());
+    }
+
+    #[test]
+    fn assertion_invalid_key() {
+        for &kind in ASYMMETRIC {
+            assert!(Assertion::try_new(kind, b"not a pkcs8 document").is_err());
+        }
+
+        let ed25519 = signing_key(AssertionKind::Ed25519);
+        assert!(Assertion::try_new(AssertionKind::EcdsaP256Sha256, &ed25519).is_err());
+    }
+
+    #[test]
+    fn assertion_private_extensions() {
+        let mut grant = grant();
+        grant
+            .extensions
+            .set_raw("secret".into(), Value::private(Some("PlaintextValue".into())));
+        grant
+            .extensions
+            .set_raw("visible".into(), Value::public(Some("PublicValue".into())));
+
+        let mut assertion = Assertion::new(AssertionKind::HmacSha256, b"a shared secret");
+        assert!(assertion.tag("token").sign(0, &grant).is_err());
+        assert_eq!(assertion.set_encryption_key(b"too short"), Err(()));
+        assertion.set_encryption_key(&[1; 32]).unwrap();
+
+        let token = assertion.tag("token").sign(0, &grant).unwrap();
+        let raw = String::from_utf8_lossy(&decode(&token).unwrap()).into_owned();
+        assert!(!raw.contains("PlaintextValue"));
+        assert!(raw.contains("PublicValue"));
+
+        let recovered = assertion.tag("token").extract(&token).unwrap();
+        let private: Vec<_> = recovered.extensions.private().collect();
+        assert_eq!(private, vec![("secret", Some("PlaintextValue"))]);
+        let public: Vec<_> = recovered.extensions.public().collect();
+        assert_eq!(public, vec![("visible", Some("PublicValue"))]);
+
+        // The signature is valid but the extensions can not be decrypted.
+        let mut other = Assertion::new(AssertionKind::HmacSha256, b"a shared secret");
+        assert!(other.tag("token").extract(&token).is_err());
+        other.set_encryption_key(&[2; 32]).unwrap();
+        assert!(other.tag("token").extract(&token).is_err());
+    }
+
+    #[test]
+    fn assertion_verify_only_decrypts() {
+        let kind = AssertionKind::Ed25519;
+        let mut grant = grant();
+        grant.extensions.set_raw("secret".into(), Value::private(None));
+
+        let mut assertion = Assertion::new(kind, &signing_key(kind));
+        assertion.set_encryption_key(&[1; 32]).unwrap();
+        let token = assertion.tag("token").sign(0, &grant).unwrap();
+
+        let mut verifier = Assertion::verify_only(kind, assertion.public_key().unwrap()).unwrap();
+        assert!(verifier.tag("token").extract(&token).is_err());
+        verifier.set_encryption_key(&[1; 32]).unwrap();
+        let recovered = verifier.tag("token").extract(&token).unwrap();
+        assert_eq!(recovered.extensions.private().count(), 1);
+    }
 
     #[test]
     #[allow(dead_code, unused)]
@@ -379,5 +975,23 @@ mod tests {
         let _ = uses(RandomGenerator::new(16));
         let fake_key = [0u8; 16];
         let _ = uses(Assertion::new(AssertionKind::HmacSha256, &fake_key));
+        let _ = uses(Assertion::new(
+            AssertionKind::Ed25519,
+            &signing_key(AssertionKind::Ed25519),
+        ));
+        let _ = uses(Keyring::ephemeral());
+    }
+
+    #[test]
+    fn out_of_range_time() {
+        #[derive(Deserialize)]
+        struct Timed {
+            #[serde(with = "time_serde")]
+            #[allow(dead_code)]
+            time: Time,
+        }
+
+        assert!(serde_json::from_str::<Timed>(r#"{"time":0}"#).is_ok());
+        assert!(serde_json::from_str::<Timed>(&format!(r#"{{"time":{}}}"#, i64::MAX)).is_err());
     }
 }
@@ -3,16 +3,16 @@
 //! Internally similar to the authorization module, tokens generated here live longer and can be
 //! renewed. There exist two fundamental implementation as well, one utilizing in memory hash maps
 //! while the other uses cryptographic signing.
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::sync::{Arc, MutexGuard, RwLockWriteGuard};
-use std::sync::atomic::{AtomicUsize, Ordering};
-use crate::primitives::registrar::Registrar;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
-use chrono::{Duration, Utc};
+use chrono::{Duration, TimeZone, Utc};
 
 use super::Time;
 use super::grant::Grant;
-use super::generator::{TagGrant, TaggedAssertion, Assertion};
+use super::generator::{TagGrant, TaggedKeyring, Assertion, Keyring, TokenHasher};
 
 /// Issuers create bearer tokens.
 ///
@@ -32,6 +32,24 @@ pub trait Issuer {
 
     /// Get the values corresponding to a refresh token
     fn recover_refresh<'a>(&'a self, _: &'a str) -> Result<Option<Grant>, ()>;
+
+    /// Revoke an access token before it expires.
+    ///
+    /// The refresh token issued along with it may be revoked as well. Issuers that can not revoke
+    /// their tokens return an error, this is the default implementation.
+    fn revoke_token(&mut self, token: &str) -> Result<(), ()> {
+        let _ = token;
+        Err(())
+    }
+
+    /// Revoke a refresh token and the access tokens of its grant.
+    ///
+    /// Issuers that can not revoke their tokens return an error, this is the default
+    /// implementation.
+    fn revoke_refresh(&mut self, refresh: &str) -> Result<(), ()> {
+        let _ = refresh;
+        Err(())
+    }
 }
 
 /// Token parameters returned to a client.
@@ -69,6 +87,21 @@ pub enum TokenType {
     ///
     /// For this variant and its usage see RFC 6750.
     Bearer,
+
+    /// A token bound to a key of the client, presented together with a DPoP proof.
+    ///
+    /// For this variant and its usage see RFC 9449.
+    DPoP,
+}
+
+impl TokenType {
+    /// The name of the type in the `token_type` parameter of token responses.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenType::Bearer => "bearer",
+            TokenType::DPoP => "DPoP",
+        }
+    }
 }
 
 /// Refresh token information returned to a client.
@@ -97,13 +130,31 @@ pub struct RefreshedToken {
 /// The generator is itself trait based and can be chosen during construction. It is assumed to not
 /// be possible (or at least very unlikely during their overlapping lifetime) for two different
 /// grants to generate the same token in the grant tagger.
+///
+/// By default a refresh token stays the same over all refreshes of its grant. With
+/// `rotate_refresh_tokens` each refresh also issues a new refresh token and invalidates the old
+/// one, as recommended for public clients. All tokens descending from one issued grant form a
+/// family. Presenting an already rotated refresh token again hints at a stolen token, so the whole
+/// family with all its access and refresh tokens is revoked.
+///
+/// The refreshes of a family can be bounded by an absolute session lifetime, counted from the
+/// issue of the original grant, and by an idle timeout, counted from its latest refresh.
+///
+/// A map constructed `with_hasher` stores a keyed hash of each token instead of the token itself.
 pub struct TokenMap<G: TagGrant = Box<dyn TagGrant + Send + Sync + 'static>> {
     duration: Option<Duration>,
+    session: SessionLimits,
     generator: G,
     usage: u64,
+    rotate: bool,
     access: HashMap<Arc<str>, Arc<Token>>,
     refresh: HashMap<Arc<str>, Arc<Token>>,
-    register: Arc<Registrar>,
+    /// Refresh tokens that have been replaced by rotation, by their family.
+    rotated: HashMap<Arc<str>, Arc<Family>>,
+    /// Set when a family was revoked but its tokens have not yet been removed.
+    reused: AtomicBool,
+    /// Stores tokens by their hash instead of the token itself, if set.
+    hasher: Option<TokenHasher>,
 }
 
 struct Token {
@@ -115,21 +166,70 @@ struct Token {
 
     /// The grant that was originally granted.
     grant: Grant,
+
+    /// The time of issue or of the latest refresh.
+    refreshed: Time,
+
+    /// The tokens descending from the same issued grant.
+    family: Arc<Family>,
+}
+
+/// Shared by all tokens descending from the same issued grant.
+struct Family {
+    /// The time the original grant was issued.
+    started: Time,
+
+    /// Set once a rotated refresh token of the family was used again.
+    revoked: AtomicBool,
+}
+
+/// Bounds on the time in which a grant can be refreshed.
+#[derive(Clone, Copy, Debug, Default)]
+struct SessionLimits {
+    /// The maximum time after the original grant.
+    lifetime: Option<Duration>,
+
+    /// The maximum time after the latest refresh.
+    idle: Option<Duration>,
 }
 
 impl<G: TagGrant> TokenMap<G> {
     /// Construct a `TokenMap` from the given generator.
-    pub fn new(generator: G, register: Arc<Registrar>) -> Self {
+    pub fn new(generator: G) -> Self {
         Self {
             duration: None,
+            session: SessionLimits::default(),
             generator,
             usage: 0,
+            rotate: false,
             access: HashMap::new(),
             refresh: HashMap::new(),
-            register,
+            rotated: HashMap::new(),
+            reused: AtomicBool::new(false),
+            hasher: None,
+        }
+    }
+
+    /// Construct a `TokenMap` that only stores a keyed hash of each token.
+    ///
+    /// The map never holds a live access or refresh token, presented tokens are hashed before
+    /// they are looked up. Keep the secret of the hasher apart from any persisted snapshot of the
+    /// map.
+    pub fn with_hasher(generator: G, hasher: TokenHasher) -> Self {
+        TokenMap {
+            hasher: Some(hasher),
+            ..Self::new(generator)
         }
     }
 
+    /// Issue a new refresh token on each refresh, invalidating the previous one.
+    ///
+    /// Rotated refresh tokens are remembered. When one is presented again, all tokens descending
+    /// from the same grant are revoked. This does not affect tokens refreshed before the call.
+    pub fn rotate_refresh_tokens(&mut self, rotate: bool) {
+        self.rotate = rotate;
+    }
+
     /// Set the validity of all issued grants to the specified duration.
     pub fn valid_for(&mut self, duration: Duration) {
         self.duration = Some(duration);
@@ -140,6 +240,22 @@ impl<G: TagGrant> TokenMap<G> {
         self.duration = None;
     }
 
+    /// Only allow refreshes within the duration after the original grant was issued.
+    ///
+    /// Refreshed access tokens expire at the end of this lifetime at the latest. By default a
+    /// grant can be refreshed indefinitely.
+    pub fn session_lifetime(&mut self, lifetime: Option<Duration>) {
+        self.session.lifetime = lifetime;
+    }
+
+    /// Only allow refreshes within the duration after the previous refresh of the grant.
+    ///
+    /// The issue of the grant counts as its first refresh. By default a refresh token does not
+    /// expire from lack of use.
+    pub fn session_idle_timeout(&mut self, idle: Option<Duration>) {
+        self.session.idle = idle;
+    }
+
     /// Unconditionally delete grant associated with the token.
     ///
     /// This is the main advantage over signing tokens. By keeping internal state of allowed
@@ -147,8 +263,9 @@ impl<G: TagGrant> TokenMap<G> {
     /// naturally. There is no differentiation between access and refresh tokens since these should
     /// have a marginal probability of colliding.
     pub fn revoke(&mut self, token: &str) {
-        self.access.remove(token);
-        self.refresh.remove(token);
+        let key = self.key(token);
+        self.access.remove(&*key);
+        self.refresh.remove(&*key);
     }
 
     /// Directly associate token with grant.
@@ -157,32 +274,112 @@ impl<G: TagGrant> TokenMap<G> {
     /// is modified (if a `duration` was previously set).
     pub fn import_grant(&mut self, token: String, mut grant: Grant) {
         self.set_duration(&mut grant);
-        let key: Arc<str> = Arc::from(token);
+        let key: Arc<str> = Arc::from(self.key(&token));
         let token = Token::from_access(key.clone(), grant);
         self.access.insert(key, Arc::new(token));
     }
 
+    /// The key under which a token is stored.
+    fn key<'t>(&self, token: &'t str) -> Cow<'t, str> {
+        match &self.hasher {
+            Some(hasher) => Cow::Owned(hasher.hash(token)),
+            None => Cow::Borrowed(token),
+        }
+    }
+
     fn set_duration(&self, grant: &mut Grant) {
         if let Some(duration) = &self.duration {
             grant.until = Utc::now() + *duration;
         }
     }
+
+    /// Revoke the family of a rotated refresh token, if it is one.
+    fn detect_reuse(&self, refresh: &str) {
+        if let Some(family) = self.rotated.get(&*self.key(refresh)) {
+            family.revoked.store(true, Ordering::SeqCst);
+            self.reused.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Remove all tokens of revoked families.
+    fn remove_revoked(&mut self) {
+        if !self.reused.swap(false, Ordering::SeqCst) {
+            return;
+        }
+
+        self.access.retain(|_, token| !token.family.is_revoked());
+        self.refresh.retain(|_, token| !token.family.is_revoked());
+        self.rotated.retain(|_, family| !family.is_revoked());
+    }
+
+    /// Revoke all tokens descending from the same grant as the token.
+    fn revoke_family(&mut self, token: &Token) {
+        token.family.revoked.store(true, Ordering::SeqCst);
+        self.reused.store(true, Ordering::SeqCst);
+        self.remove_revoked();
+    }
+}
+
+impl Family {
+    fn new(started: Time) -> Self {
+        Family {
+            started,
+            revoked: AtomicBool::new(false),
+        }
+    }
+
+    fn is_revoked(&self) -> bool {
+        self.revoked.load(Ordering::SeqCst)
+    }
+}
+
+impl SessionLimits {
+    /// Whether a grant issued at `started` and last refreshed at `refreshed` can be refreshed.
+    fn is_active(&self, started: Time, refreshed: Time) -> bool {
+        let now = Utc::now();
+        let within_lifetime = self.lifetime.map_or(true, |lifetime| now < started + lifetime);
+        let within_idle = self.idle.map_or(true, |idle| now < refreshed + idle);
+        within_lifetime && within_idle
+    }
+
+    /// Limit the expiry of a refreshed access token to the end of the session.
+    fn cap(&self, started: Time, until: Time) -> Time {
+        match self.lifetime {
+            Some(lifetime) => until.min(started + lifetime),
+            None => until,
+        }
+    }
 }
 
 impl Token {
     fn from_access(access: Arc<str>, grant: Grant) -> Self {
+        let now = Utc::now();
         Token {
             access,
             refresh: None,
             grant,
+            refreshed: now,
+            family: Arc::new(Family::new(now)),
         }
     }
 
     fn from_refresh(access: Arc<str>, refresh: Arc<str>, grant: Grant) -> Self {
+        let now = Utc::now();
         Token {
             access,
             refresh: Some(refresh),
             grant,
+            refreshed: now,
+            family: Arc::new(Family::new(now)),
+        }
+    }
+
+    /// The grant, unless the family of the token has been revoked.
+    fn valid_grant(&self) -> Option<Grant> {
+        if self.family.is_revoked() {
+            None
+        } else {
+            Some(self.grant.clone())
         }
     }
 }
@@ -240,6 +437,7 @@ impl IssuedToken {
 
 impl<G: TagGrant> Issuer for TokenMap<G> {
     fn issue(&mut self, mut grant: Grant) -> Result<IssuedToken, ()> {
+        self.remove_revoked();
         self.set_duration(&mut grant);
         // The (usage, grant) tuple needs to be unique. Since this wraps after 2^63 operations, we
         // expect the validity time of the grant to have changed by then. This works when you don't
@@ -262,8 +460,8 @@ impl<G: TagGrant> Issuer for TokenMap<G> {
         };
 
         let until = grant.until;
-        let access_key: Arc<str> = Arc::from(access.clone());
-        let refresh_key: Arc<str> = Arc::from(refresh.clone());
+        let access_key: Arc<str> = Arc::from(self.key(&access));
+        let refresh_key: Arc<str> = Arc::from(self.key(&refresh));
         let token = Token::from_refresh(access_key.clone(), refresh_key.clone(), grant);
         let token = Arc::new(token);
 
@@ -279,20 +477,33 @@ impl<G: TagGrant> Issuer for TokenMap<G> {
     }
 
     fn refresh(&mut self, refresh: &str, mut grant: Grant) -> Result<RefreshedToken, ()> {
+        self.detect_reuse(refresh);
+        self.remove_revoked();
+
         // Remove the old token.
         let (refresh_key, mut token) = self
             .refresh
-            .remove_entry(refresh)
+            .remove_entry(&*self.key(refresh))
             // Should only be called on valid refresh tokens.
             .ok_or(())?;
 
         assert!(Arc::ptr_eq(token.refresh.as_ref().unwrap(), &refresh_key));
+        if !self.session.is_active(token.family.started, token.refreshed) {
+            self.access.remove(&token.access);
+            return Err(());
+        }
+
         self.set_duration(&mut grant);
+        grant.until = self.session.cap(token.family.started, grant.until);
         let until = grant.until;
 
+        if self.rotate {
+            return self.rotate(refresh_key, token, grant);
+        }
+
         let next_usage = self.usage.wrapping_add(1);
         let new_access = self.generator.tag(self.usage, &grant)?;
-        let new_key: Arc<str> = Arc::from(new_access.clone());
+        let new_key: Arc<str> = Arc::from(self.key(&new_access));
 
         if let Some(atoken) = self.access.remove(&token.access) {
             assert!(Arc::ptr_eq(&token, &atoken));
@@ -305,6 +516,7 @@ impl<G: TagGrant> Issuer for TokenMap<G> {
             // Remove the old access token, insert the new.
             mut_token.access = new_key.clone();
             mut_token.grant = grant;
+            mut_token.refreshed = Utc::now();
         }
 
         self.access.insert(new_key, token.clone());
@@ -320,11 +532,74 @@ impl<G: TagGrant> Issuer for TokenMap<G> {
     }
 
     fn recover_token<'a>(&'a self, token: &'a str) -> Result<Option<Grant>, ()> {
-        Ok(self.access.get(token).map(|token| token.grant.clone()))
+        Ok(self
+            .access
+            .get(&*self.key(token))
+            .and_then(|token| token.valid_grant()))
     }
 
     fn recover_refresh<'a>(&'a self, token: &'a str) -> Result<Option<Grant>, ()> {
-        Ok(self.refresh.get(token).map(|token| token.grant.clone()))
+        self.detect_reuse(token);
+        Ok(self
+            .refresh
+            .get(&*self.key(token))
+            .filter(|token| self.session.is_active(token.family.started, token.refreshed))
+            .and_then(|token| token.valid_grant()))
+    }
+
+    fn revoke_token(&mut self, token: &str) -> Result<(), ()> {
+        self.remove_revoked();
+        if let Some(token) = self.access.remove(&*self.key(token)) {
+            if let Some(refresh) = &token.refresh {
+                self.refresh.remove(refresh);
+            }
+            self.revoke_family(&token);
+        }
+        Ok(())
+    }
+
+    fn revoke_refresh(&mut self, refresh: &str) -> Result<(), ()> {
+        self.remove_revoked();
+        if let Some(token) = self.refresh.remove(&*self.key(refresh)) {
+            self.access.remove(&token.access);
+            self.revoke_family(&token);
+        }
+        Ok(())
+    }
+}
+
+impl<G: TagGrant> TokenMap<G> {
+    /// Replace both tokens of a refreshed grant, remembering the old refresh token.
+    fn rotate(
+        &mut self, old_refresh: Arc<str>, token: Arc<Token>, grant: Grant,
+    ) -> Result<RefreshedToken, ()> {
+        let until = grant.until;
+        let next_usage = self.usage.wrapping_add(2);
+        let access = self.generator.tag(self.usage, &grant)?;
+        let refresh = self.generator.tag(self.usage.wrapping_add(1), &grant)?;
+
+        self.access.remove(&token.access);
+        self.rotated.insert(old_refresh, token.family.clone());
+
+        let access_key: Arc<str> = Arc::from(self.key(&access));
+        let refresh_key: Arc<str> = Arc::from(self.key(&refresh));
+        let token = Arc::new(Token {
+            access: access_key.clone(),
+            refresh: Some(refresh_key.clone()),
+            grant,
+            refreshed: Utc::now(),
+            family: token.family.clone(),
+        });
+
+        self.access.insert(access_key, token.clone());
+        self.refresh.insert(refresh_key, token);
+        self.usage = next_usage;
+        Ok(RefreshedToken {
+            token: access,
+            refresh: Some(refresh),
+            until,
+            token_type: TokenType::Bearer,
+        })
     }
 }
 
@@ -332,9 +607,18 @@ impl<G: TagGrant> Issuer for TokenMap<G> {
 ///
 /// Although this token instance allows preservation of memory it also implies that tokens, once
 /// issued, are impossible to revoke.
+///
+/// Tokens are signed by the active key of a `Keyring` and contain its key id. Rotating the keyring
+/// changes the signing key for new tokens while previously issued tokens stay valid until their
+/// key is retired.
+///
+/// Refresh tokens record the time of the original grant and of their own issue. Each refresh
+/// issues a new refresh token but, lacking revocation, the previous ones stay valid. Limit them
+/// with `session_lifetime` and `session_idle_timeout`.
 pub struct TokenSigner {
     duration: Option<Duration>,
-    signer: Assertion,
+    session: SessionLimits,
+    signer: Keyring,
     // FIXME: make this an AtomicU64 once stable.
     counter: AtomicUsize,
     have_refresh: bool,
@@ -343,12 +627,25 @@ pub struct TokenSigner {
 impl TokenSigner {
     /// Construct a signing instance from a private signing key.
     ///
+    /// An assertion constructed by `Assertion::verify_only` recovers tokens of the corresponding
+    /// signing key but fails to issue any tokens itself.
+    ///
     /// Security notice: Never use a password alone to construct the signing key. Instead, generate
     /// a new key using a utility such as `openssl rand` that you then store away securely.
+    ///
+    /// The assertion becomes the only key of a new keyring, with the id `default`.
     pub fn new(secret: Assertion) -> TokenSigner {
+        TokenSigner::with_keyring(Keyring::new("default", secret))
+    }
+
+    /// Construct a signing instance using the keys of a keyring.
+    ///
+    /// The keyring can be rotated through any of its clones, taking effect immediately.
+    pub fn with_keyring(keyring: Keyring) -> TokenSigner {
         TokenSigner {
             duration: None,
-            signer: secret,
+            session: SessionLimits::default(),
+            signer: keyring,
             counter: AtomicUsize::new(0),
             have_refresh: false,
         }
@@ -363,6 +660,11 @@ impl TokenSigner {
         TokenSigner::new(Assertion::ephemeral())
     }
 
+    /// The keyring signing and verifying the tokens.
+    pub fn keyring(&self) -> &Keyring {
+        &self.signer
+    }
+
     /// Set the validity of all issued grants to the specified duration.
     ///
     /// This only affects tokens issued after this call. The default duration is 1 (ONE) hour for
@@ -392,6 +694,21 @@ impl TokenSigner {
         self.have_refresh = refresh;
     }
 
+    /// Only allow refreshes within the duration after the original grant was issued.
+    ///
+    /// Refreshed access tokens expire at the end of this lifetime at the latest. This applies to
+    /// all refresh tokens, including those issued before this call.
+    pub fn session_lifetime(&mut self, lifetime: Option<Duration>) {
+        self.session.lifetime = lifetime;
+    }
+
+    /// Only allow a refresh token to be used within the duration after it was issued.
+    ///
+    /// This applies to all refresh tokens, including those issued before this call.
+    pub fn session_idle_timeout(&mut self, idle: Option<Duration>) {
+        self.session.idle = idle;
+    }
+
     /// Get the next counter value.
     fn next_counter(&self) -> usize {
         // Acquire+Release is overkill. We only need to ensure that each return value occurs at
@@ -405,7 +722,7 @@ impl TokenSigner {
         let second_ctr = self.next_counter() as u64;
 
         let token = self.as_token().sign(first_ctr, grant)?;
-        let refresh = self.as_refresh().sign(second_ctr, grant)?;
+        let refresh = self.sign_refresh(second_ctr, grant, Utc::now())?;
 
         Ok(IssuedToken {
             token,
@@ -423,12 +740,36 @@ impl TokenSigner {
         Ok(IssuedToken::without_refresh(token, grant.until))
     }
 
-    fn as_token(&self) -> TaggedAssertion {
+    fn as_token(&self) -> TaggedKeyring<'_> {
         self.signer.tag("token")
     }
 
-    fn as_refresh(&self) -> TaggedAssertion {
-        self.signer.tag("refresh")
+    /// Sign a refresh token, recording the session start and the current time in its tag.
+    fn sign_refresh(&self, counter: u64, grant: &Grant, started: Time) -> Result<String, ()> {
+        let tag = format!(
+            "refresh:{}:{}",
+            started.timestamp_millis(),
+            Utc::now().timestamp_millis()
+        );
+        self.signer.generate_tagged(counter, grant, &tag)
+    }
+
+    /// Recover a refresh token and the start of its session, if the session is still active.
+    fn extract_refresh(&self, token: &str) -> Option<(Grant, Time)> {
+        let (grant, tag) = self.signer.extract(token).ok()?;
+        let mut parts = tag.split(':');
+        if parts.next() != Some("refresh") {
+            return None;
+        }
+
+        let mut timestamp = || Utc.timestamp_millis_opt(parts.next()?.parse().ok()?).single();
+        let started = timestamp()?;
+        let issued = timestamp()?;
+        if !self.session.is_active(started, issued) {
+            return None;
+        }
+
+        Some((grant, started))
     }
 }
 
@@ -448,6 +789,14 @@ impl<'s, I: Issuer + ?Sized> Issuer for &'s mut I {
     fn recover_refresh<'a>(&'a self, token: &'a str) -> Result<Option<Grant>, ()> {
         (**self).recover_refresh(token)
     }
+
+    fn revoke_token(&mut self, token: &str) -> Result<(), ()> {
+        (**self).revoke_token(token)
+    }
+
+    fn revoke_refresh(&mut self, refresh: &str) -> Result<(), ()> {
+        (**self).revoke_refresh(refresh)
+    }
 }
 
 impl<I: Issuer + ?Sized> Issuer for Box<I> {
@@ -466,6 +815,14 @@ impl<I: Issuer + ?Sized> Issuer for Box<I> {
     fn recover_refresh<'a>(&'a self, token: &'a str) -> Result<Option<Grant>, ()> {
         (**self).recover_refresh(token)
     }
+
+    fn revoke_token(&mut self, token: &str) -> Result<(), ()> {
+        (**self).revoke_token(token)
+    }
+
+    fn revoke_refresh(&mut self, refresh: &str) -> Result<(), ()> {
+        (**self).revoke_refresh(refresh)
+    }
 }
 
 impl<'s, I: Issuer + ?Sized> Issuer for MutexGuard<'s, I> {
@@ -484,6 +841,14 @@ impl<'s, I: Issuer + ?Sized> Issuer for MutexGuard<'s, I> {
     fn recover_refresh<'a>(&'a self, token: &'a str) -> Result<Option<Grant>, ()> {
         (**self).recover_refresh(token)
     }
+
+    fn revoke_token(&mut self, token: &str) -> Result<(), ()> {
+        (**self).revoke_token(token)
+    }
+
+    fn revoke_refresh(&mut self, refresh: &str) -> Result<(), ()> {
+        (**self).revoke_refresh(refresh)
+    }
 }
 
 impl<'s, I: Issuer + ?Sized> Issuer for RwLockWriteGuard<'s, I> {
@@ -502,6 +867,14 @@ impl<'s, I: Issuer + ?Sized> Issuer for RwLockWriteGuard<'s, I> {
     fn recover_refresh<'a>(&'a self, token: &'a str) -> Result<Option<Grant>, ()> {
         (**self).recover_refresh(token)
     }
+
+    fn revoke_token(&mut self, token: &str) -> Result<(), ()> {
+        (**self).revoke_token(token)
+    }
+
+    fn revoke_refresh(&mut self, refresh: &str) -> Result<(), ()> {
+        (**self).revoke_refresh(refresh)
+    }
 }
 
 impl Issuer for TokenSigner {
@@ -509,8 +882,8 @@ impl Issuer for TokenSigner {
         (&mut &*self).issue(grant)
     }
 
-    fn refresh(&mut self, _refresh: &str, _grant: Grant) -> Result<RefreshedToken, ()> {
-        Err(())
+    fn refresh(&mut self, refresh: &str, grant: Grant) -> Result<RefreshedToken, ()> {
+        (&mut &*self).refresh(refresh, grant)
     }
 
     fn recover_token<'a>(&'a self, token: &'a str) -> Result<Option<Grant>, ()> {
@@ -535,8 +908,28 @@ impl<'a> Issuer for &'a TokenSigner {
         }
     }
 
-    fn refresh(&mut self, _refresh: &str, _grant: Grant) -> Result<RefreshedToken, ()> {
-        Err(())
+    fn refresh(&mut self, refresh: &str, mut grant: Grant) -> Result<RefreshedToken, ()> {
+        if !self.have_refresh {
+            return Err(());
+        }
+
+        let (_, started) = self.extract_refresh(refresh).ok_or(())?;
+        if let Some(duration) = &self.duration {
+            grant.until = Utc::now() + *duration;
+        }
+        grant.until = self.session.cap(started, grant.until);
+
+        let first_ctr = self.next_counter() as u64;
+        let second_ctr = self.next_counter() as u64;
+        let token = self.as_token().sign(first_ctr, &grant)?;
+        let refresh = self.sign_refresh(second_ctr, &grant, started)?;
+
+        Ok(RefreshedToken {
+            token,
+            refresh: Some(refresh),
+            until: grant.until,
+            token_type: TokenType::Bearer,
+        })
     }
 
     fn recover_token<'t>(&'t self, token: &'t str) -> Result<Option<Grant>, ()> {
@@ -548,7 +941,7 @@ impl<'a> Issuer for &'a TokenSigner {
             return Ok(None);
         }
 
-        Ok(self.as_refresh().extract(token).ok())
+        Ok(self.extract_refresh(token).map(|(grant, _)| grant))
     }
 }
 
@@ -556,8 +949,10 @@ impl<'a> Issuer for &'a TokenSigner {
 /// Tests for issuer implementations, including those provided here.
 pub mod tests {
     use super::*;
-    use crate::primitives::grant::Extensions;
-    use crate::primitives::generator::RandomGenerator;
+    use crate::primitives::grant::{Extensions, Value};
+    use crate::primitives::generator::{AssertionKind, Keyring, RandomGenerator};
+    use ring::rand::SystemRandom;
+    use ring::signature::Ed25519KeyPair;
     use chrono::{Duration, Utc};
 
     fn grant_template() -> Grant {
@@ -632,6 +1027,292 @@ pub mod tests {
         assert!(token.refreshable());
     }
 
+    #[test]
+    fn random_revocation() {
+        let mut token_map = TokenMap::new(RandomGenerator::new(16));
+        let by_access = token_map.issue(grant_template()).unwrap();
+        let by_refresh = token_map.issue(grant_template()).unwrap();
+
+        // Revoking either token of a grant invalidates both.
+        assert_eq!(token_map.revoke_token(&by_access.token), Ok(()));
+        assert_eq!(token_map.recover_token(&by_access.token), Ok(None));
+        assert_eq!(
+            token_map.recover_refresh(by_access.refresh.as_ref().unwrap()),
+            Ok(None)
+        );
+
+        assert_eq!(
+            token_map.revoke_refresh(by_refresh.refresh.as_ref().unwrap()),
+            Ok(())
+        );
+        assert_eq!(token_map.recover_token(&by_refresh.token), Ok(None));
+        assert_eq!(
+            token_map.recover_refresh(by_refresh.refresh.as_ref().unwrap()),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn random_refresh_rotation() {
+        let mut token_map = TokenMap::new(RandomGenerator::new(16));
+        let issued = token_map.issue(grant_template()).unwrap();
+        let first_refresh = issued.refresh.unwrap();
+
+        // Without rotation the refresh token stays valid.
+        let refreshed = token_map.refresh(&first_refresh, grant_template()).unwrap();
+        assert_eq!(refreshed.refresh, None);
+        assert!(token_map.recover_refresh(&first_refresh).unwrap().is_some());
+
+        token_map.rotate_refresh_tokens(true);
+        let rotated = token_map.refresh(&first_refresh, grant_template()).unwrap();
+        let second_refresh = rotated.refresh.unwrap();
+        assert_ne!(second_refresh, first_refresh);
+        assert_eq!(token_map.recover_token(&refreshed.token), Ok(None));
+        assert!(token_map.recover_token(&rotated.token).unwrap().is_some());
+        assert!(token_map.recover_refresh(&second_refresh).unwrap().is_some());
+
+        let again = token_map.refresh(&second_refresh, grant_template()).unwrap();
+        assert!(token_map.recover_token(&again.token).unwrap().is_some());
+        assert!(token_map
+            .recover_refresh(again.refresh.as_ref().unwrap())
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn random_refresh_reuse() {
+        let mut token_map = TokenMap::new(RandomGenerator::new(16));
+        token_map.rotate_refresh_tokens(true);
+        let other = token_map.issue(grant_template()).unwrap();
+        let issued = token_map.issue(grant_template()).unwrap();
+        let stolen = issued.refresh.unwrap();
+
+        let first = token_map.refresh(&stolen, grant_template()).unwrap();
+        let second = token_map
+            .refresh(first.refresh.as_ref().unwrap(), grant_template())
+            .unwrap();
+
+        // Presenting the rotated token again revokes all descendants.
+        assert_eq!(token_map.recover_refresh(&stolen), Ok(None));
+        assert_eq!(token_map.recover_token(&second.token), Ok(None));
+        assert_eq!(
+            token_map.recover_refresh(second.refresh.as_ref().unwrap()),
+            Ok(None)
+        );
+        assert!(token_map
+            .refresh(second.refresh.as_ref().unwrap(), grant_template())
+            .is_err());
+
+        // Other grants are not affected.
+        assert!(token_map.recover_token(&other.token).unwrap().is_some());
+        let refreshed = token_map.refresh(other.refresh.as_ref().unwrap(), grant_template());
+        assert!(refreshed.is_ok());
+
+        // Refreshing directly with a rotated token is detected as well.
+        let issued = token_map.issue(grant_template()).unwrap();
+        let rotated = token_map
+            .refresh(issued.refresh.as_ref().unwrap(), grant_template())
+            .unwrap();
+        assert!(token_map
+            .refresh(issued.refresh.as_ref().unwrap(), grant_template())
+            .is_err());
+        assert_eq!(token_map.recover_token(&rotated.token), Ok(None));
+        assert_eq!(token_map.rotated.len(), 1);
+    }
+
+    #[test]
+    fn hashed_tokens() {
+        let hasher = TokenHasher::new(b"secret");
+        let mut token_map = TokenMap::with_hasher(RandomGenerator::new(16), hasher.clone());
+        simple_test_suite(&mut token_map);
+
+        token_map.rotate_refresh_tokens(true);
+        let issued = token_map.issue(grant_template()).unwrap();
+        let refresh = issued.refresh.unwrap();
+
+        // Only the hashes of the tokens are stored.
+        assert!(!token_map.access.contains_key(issued.token.as_str()));
+        assert!(!token_map.refresh.contains_key(refresh.as_str()));
+        assert!(token_map.access.contains_key(hasher.hash(&issued.token).as_str()));
+
+        let rotated = token_map.refresh(&refresh, grant_template()).unwrap();
+        assert!(token_map.recover_token(&rotated.token).unwrap().is_some());
+        assert_eq!(token_map.recover_token(&issued.token), Ok(None));
+
+        // Reuse detection works on the hashes as well.
+        assert_eq!(token_map.recover_refresh(&refresh), Ok(None));
+        assert_eq!(token_map.recover_token(&rotated.token), Ok(None));
+
+        token_map.import_grant("imported".to_string(), grant_template());
+        assert!(token_map.recover_token("imported").unwrap().is_some());
+        token_map.revoke("imported");
+        assert_eq!(token_map.recover_token("imported"), Ok(None));
+    }
+
+    #[test]
+    fn random_session_limits() {
+        let mut token_map = TokenMap::new(RandomGenerator::new(16));
+        token_map.valid_for(Duration::days(1));
+        token_map.session_lifetime(Some(Duration::hours(2)));
+        token_map.session_idle_timeout(Some(Duration::hours(1)));
+        let issued = token_map.issue(grant_template()).unwrap();
+        let refresh = issued.refresh.unwrap();
+
+        // Refreshed access tokens do not outlive the session.
+        let refreshed = token_map.refresh(&refresh, grant_template()).unwrap();
+        assert!(refreshed.until <= Utc::now() + Duration::hours(2));
+
+        token_map.session_idle_timeout(Some(Duration::zero()));
+        assert_eq!(token_map.recover_refresh(&refresh), Ok(None));
+        assert!(token_map.recover_token(&refreshed.token).unwrap().is_some());
+
+        token_map.session_idle_timeout(None);
+        assert!(token_map.recover_refresh(&refresh).unwrap().is_some());
+        token_map.session_lifetime(Some(Duration::zero()));
+        assert_eq!(token_map.recover_refresh(&refresh), Ok(None));
+        assert!(token_map.refresh(&refresh, grant_template()).is_err());
+
+        // An expired session is removed.
+        token_map.session_lifetime(None);
+        assert_eq!(token_map.recover_refresh(&refresh), Ok(None));
+        assert_eq!(token_map.recover_token(&refreshed.token), Ok(None));
+    }
+
+    #[test]
+    fn signer_refresh() {
+        let mut signer = TokenSigner::ephemeral();
+        let issued = signer.issue(grant_template()).unwrap();
+        assert!(signer.refresh("NotARefreshToken", grant_template()).is_err());
+
+        signer.generate_refresh_tokens(true);
+        signer.valid_for(Duration::days(1));
+        signer.session_lifetime(Some(Duration::hours(2)));
+        let issued_refresh = signer.issue(grant_template()).unwrap();
+        let refresh = issued_refresh.refresh.unwrap();
+        assert_eq!(signer.recover_refresh(&issued.token), Ok(None));
+
+        let refreshed = signer.refresh(&refresh, grant_template()).unwrap();
+        assert!(refreshed.until <= Utc::now() + Duration::hours(2));
+        assert!(signer.recover_token(&refreshed.token).unwrap().is_some());
+        let next = refreshed.refresh.unwrap();
+        assert!(signer.recover_refresh(&next).unwrap().is_some());
+        // Refresh tokens are not valid as access tokens.
+        assert_eq!(signer.recover_token(&next), Ok(None));
+
+        signer.session_idle_timeout(Some(Duration::zero()));
+        assert_eq!(signer.recover_refresh(&next), Ok(None));
+        assert!(signer.refresh(&next, grant_template()).is_err());
+
+        signer.session_idle_timeout(Some(Duration::hours(1)));
+        assert!(signer.recover_refresh(&next).unwrap().is_some());
+        signer.session_lifetime(Some(Duration::zero()));
+        assert_eq!(signer.recover_refresh(&next), Ok(None));
+        assert_eq!(signer.recover_refresh(&refresh), Ok(None));
+    }
+
+    #[test]
+    fn signer_asymmetric() {
+        let key = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let mut signer = TokenSigner::new(Assertion::new(AssertionKind::Ed25519, key.as_ref()));
+        signer.generate_refresh_tokens(true);
+        simple_test_suite(&mut signer);
+
+        let public_key = signer
+            .keyring()
+            .get("default")
+            .unwrap()
+            .public_key()
+            .unwrap()
+            .to_vec();
+        let mut verifier =
+            TokenSigner::new(Assertion::verify_only(AssertionKind::Ed25519, &public_key).unwrap());
+        let issued = signer.issue(grant_template()).unwrap();
+        let recovered = verifier.recover_token(&issued.token).unwrap();
+        assert_eq!(recovered.map(|grant| grant.owner_id), Some("Owner".to_string()));
+        assert!(verifier.issue(grant_template()).is_err());
+    }
+
+    #[test]
+    fn signer_key_rotation() {
+        let keyring = Keyring::new("first", Assertion::new(AssertionKind::HmacSha256, b"first"));
+        let mut signer = TokenSigner::with_keyring(keyring.clone());
+        let first = signer.issue(grant_template()).unwrap();
+
+        keyring
+            .rotate("second", Assertion::new(AssertionKind::HmacSha256, b"second"))
+            .unwrap();
+        assert_eq!(signer.keyring().active(), "second");
+        let second = signer.issue(grant_template()).unwrap();
+
+        // Tokens of the previous key stay valid until it is retired.
+        assert!(signer.recover_token(&first.token).unwrap().is_some());
+        assert!(signer.recover_token(&second.token).unwrap().is_some());
+
+        assert_eq!(keyring.retire("second"), Err(()));
+        assert_eq!(keyring.retire("first"), Ok(()));
+        assert_eq!(signer.recover_token(&first.token), Ok(None));
+        assert!(signer.recover_token(&second.token).unwrap().is_some());
+
+        // A key with the same id but different secret does not verify the token.
+        keyring
+            .insert("first", Assertion::new(AssertionKind::HmacSha256, b"other"))
+            .unwrap();
+        assert_eq!(signer.recover_token(&first.token), Ok(None));
+    }
+
+    #[test]
+    fn signer_verify_only_keys() {
+        let key = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let assertion = Assertion::new(AssertionKind::Ed25519, key.as_ref());
+        let public_key = assertion.public_key().unwrap().to_vec();
+        let mut signer = TokenSigner::with_keyring(Keyring::new("signing", assertion));
+        let issued = signer.issue(grant_template()).unwrap();
+
+        let keyring = Keyring::ephemeral();
+        let verify_only = Assertion::verify_only(AssertionKind::Ed25519, &public_key).unwrap();
+        keyring.insert("signing", verify_only).unwrap();
+        let verifier = TokenSigner::with_keyring(keyring.clone());
+        assert!(verifier.recover_token(&issued.token).unwrap().is_some());
+
+        // Only signing keys can become active.
+        let verify_only = Assertion::verify_only(AssertionKind::Ed25519, &public_key).unwrap();
+        assert_eq!(keyring.rotate("public", verify_only), Err(()));
+        assert_eq!(keyring.insert(&keyring.active(), Assertion::ephemeral()), Err(()));
+    }
+
+    #[test]
+    fn signer_private_extensions() {
+        let mut signer = TokenSigner::ephemeral();
+        signer.generate_refresh_tokens(true);
+        let mut grant = grant_template();
+        grant
+            .extensions
+            .set_raw("secret".into(), Value::private(Some("Hidden".into())));
+
+        let issued = signer.issue(grant).unwrap();
+        for recovered in vec![
+            signer.recover_token(&issued.token).unwrap(),
+            signer.recover_refresh(issued.refresh.as_ref().unwrap()).unwrap(),
+        ] {
+            let recovered = recovered.unwrap();
+            let private: Vec<_> = recovered.extensions.private().collect();
+            assert_eq!(private, vec![("secret", Some("Hidden"))]);
+        }
+
+        // Without an encryption key the private extensions can not be issued.
+        let mut signer = TokenSigner::new(Assertion::new(AssertionKind::HmacSha256, b"secret"));
+        let mut grant = grant_template();
+        grant.extensions.set_raw("secret".into(), Value::private(None));
+        assert!(signer.issue(grant).is_err());
+    }
+
+    #[test]
+    fn signer_no_revocation() {
+        let mut signer = TokenSigner::ephemeral();
+        let issued = signer.issue(grant_template()).unwrap();
+        assert_eq!(signer.revoke_token(&issued.token), Err(()));
+    }
+
     #[test]
     #[should_panic]
     fn bad_generator() {
@@ -0,0 +1,489 @@
+//! Publishes the verification keys of asymmetric assertions as a JSON Web Key Set.
+//!
+//! Resource servers validating self-contained tokens, for example those of the `JwtIssuer`, fetch
+//! the public keys of the authorization server from a well-known location, usually
+//! `/.well-known/jwks.json` or the `jwks_uri` of the authorization server metadata. The keys of
+//! the `HmacSha256` suite are secret and never published. See [RFC 7517] and [RFC 7518].
+//!
+//! Keys presented by clients, for example in DPoP proofs, are parsed from the same format and can
+//! be converted back into verify-only assertions.
+//!
+//! [RFC 7517]: https://tools.ietf.org/html/rfc7517
+//! [RFC 7518]: https://tools.ietf.org/html/rfc7518
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use base64::{decode_config, encode_config, URL_SAFE_NO_PAD};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::generator::{Assertion, AssertionKind, Keyring};
+
+/// The public key of an assertion in the JSON Web Key format.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Jwk {
+    kty: Cow<'static, str>,
+
+    #[serde(rename = "use", default, skip_serializing_if = "Option::is_none")]
+    use_: Option<Cow<'static, str>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    alg: Option<Cow<'static, str>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kid: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    crv: Option<Cow<'static, str>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    x: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    y: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    n: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    e: Option<String>,
+}
+
+/// A set of public keys, serialized as the document of a `jwks_uri`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct JwkSet {
+    keys: Vec<Jwk>,
+}
+
+impl Jwk {
+    /// Describe the public key of an asymmetric assertion.
+    ///
+    /// Returns `None` for `HmacSha256`, which has no public key, and if the public key of a
+    /// verify-only assertion is malformed.
+    pub fn from_assertion(assertion: &Assertion) -> Option<Self> {
+        let public_key = assertion.public_key()?;
+        let jwk = Jwk {
+            kty: "".into(),
+            use_: Some("sig".into()),
+            alg: Some(assertion.jws_algorithm().into()),
+            kid: None,
+            crv: None,
+            x: None,
+            y: None,
+            n: None,
+            e: None,
+        };
+
+        match assertion.kind() {
+            AssertionKind::Ed25519 if public_key.len() == 32 => Some(Jwk {
+                kty: "OKP".into(),
+                crv: Some("Ed25519".into()),
+                x: Some(encode_config(public_key, URL_SAFE_NO_PAD)),
+                ..jwk
+            }),
+            // An uncompressed point, both coordinates have 32 bytes.
+            AssertionKind::EcdsaP256Sha256 if public_key.len() == 65 && public_key[0] == 4 => {
+                Some(Jwk {
+                    kty: "EC".into(),
+                    crv: Some("P-256".into()),
+                    x: Some(encode_config(&public_key[1..33], URL_SAFE_NO_PAD)),
+                    y: Some(encode_config(&public_key[33..], URL_SAFE_NO_PAD)),
+                    ..jwk
+                })
+            }
+            AssertionKind::RsaPssSha256 => {
+                let (modulus, exponent) = rsa_public_key(public_key)?;
+                Some(Jwk {
+                    kty: "RSA".into(),
+                    n: Some(encode_config(modulus, URL_SAFE_NO_PAD)),
+                    e: Some(encode_config(exponent, URL_SAFE_NO_PAD)),
+                    ..jwk
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Identify the key, for example by the id of a `Keyring`.
+    pub fn with_key_id(self, kid: &str) -> Self {
+        Jwk {
+            kid: Some(kid.to_string()),
+            ..self
+        }
+    }
+
+    /// The id of the key, if any.
+    pub fn key_id(&self) -> Option<&str> {
+        self.kid.as_deref()
+    }
+
+    /// The JWS algorithm for which the key is used, if restricted.
+    pub fn algorithm(&self) -> Option<&str> {
+        self.alg.as_deref()
+    }
+
+    /// Convert the public key into an assertion that only verifies signatures.
+    ///
+    /// Supports `Ed25519` octet key pairs, `P-256` elliptic curve keys and RSA keys, the latter
+    /// only for `PS256`. Returns `None` for other or malformed keys and if the key is restricted
+    /// to a different algorithm than the one its type is used with.
+    pub fn to_assertion(&self) -> Option<Assertion> {
+        let decode = |member: &Option<String>| decode_config(member.as_ref()?, URL_SAFE_NO_PAD).ok();
+
+        let (kind, public_key) = match (&*self.kty, self.crv.as_deref()) {
+            ("OKP", Some("Ed25519")) => {
+                let x = decode(&self.x).filter(|x| x.len() == 32)?;
+                (AssertionKind::Ed25519, x)
+            }
+            ("EC", Some("P-256")) => {
+                let x = decode(&self.x).filter(|x| x.len() == 32)?;
+                let y = decode(&self.y).filter(|y| y.len() == 32)?;
+                let mut point = vec![4];
+                point.extend(x);
+                point.extend(y);
+                (AssertionKind::EcdsaP256Sha256, point)
+            }
+            ("RSA", None) => {
+                let modulus = decode(&self.n)?;
+                let exponent = decode(&self.e)?;
+                (
+                    AssertionKind::RsaPssSha256,
+                    rsa_public_key_der(&modulus, &exponent),
+                )
+            }
+            _ => return None,
+        };
+
+        let assertion = Assertion::verify_only(kind, &public_key).ok()?;
+        match self.algorithm() {
+            Some(alg) if alg != assertion.jws_algorithm() => None,
+            _ => Some(assertion),
+        }
+    }
+
+    /// The SHA-256 thumbprint of the key, base64url encoded.
+    ///
+    /// Only the members required for the type of the key are hashed, as specified by [RFC 7638],
+    /// so that the thumbprint does not depend on the id or other optional members. Returns `None`
+    /// if a required member is missing or the key type is unknown.
+    ///
+    /// [RFC 7638]: https://tools.ietf.org/html/rfc7638
+    pub fn thumbprint(&self) -> Option<String> {
+        let required: &[(&str, &Option<_>)] = match &*self.kty {
+            "OKP" => &[("x", &self.x)],
+            "EC" => &[("x", &self.x), ("y", &self.y)],
+            "RSA" => &[("e", &self.e), ("n", &self.n)],
+            _ => return None,
+        };
+
+        // Ordered lexicographically and serialized without whitespace.
+        let mut members = BTreeMap::new();
+        members.insert("kty", &*self.kty);
+        if self.kty != "RSA" {
+            members.insert("crv", self.crv.as_deref()?);
+        }
+        for (name, value) in required {
+            members.insert(name, value.as_deref()?);
+        }
+
+        let canonical = serde_json::to_string(&members).unwrap();
+        Some(encode_config(
+            Sha256::digest(canonical.as_bytes()),
+            URL_SAFE_NO_PAD,
+        ))
+    }
+}
+
+impl JwkSet {
+    /// An empty set of keys.
+    pub fn new() -> Self {
+        JwkSet::default()
+    }
+
+    /// Publish the public keys of all asymmetric assertions of a keyring.
+    ///
+    /// This includes verify-only keys since their tokens are still accepted. Keys are ordered by
+    /// their id.
+    pub fn from_keyring(keyring: &Keyring) -> Self {
+        let mut kids = keyring.kids();
+        kids.sort();
+
+        let mut set = JwkSet::new();
+        for kid in kids {
+            let jwk = keyring
+                .get(&kid)
+                .and_then(|assertion| Jwk::from_assertion(&assertion));
+            if let Some(jwk) = jwk {
+                set.push(jwk.with_key_id(&kid));
+            }
+        }
+
+        set
+    }
+
+    /// Add a key, replacing a previous key with the same id.
+    pub fn push(&mut self, jwk: Jwk) {
+        if jwk.kid.is_some() {
+            self.keys.retain(|key| key.kid != jwk.kid);
+        }
+
+        self.keys.push(jwk);
+    }
+
+    /// The keys of the set.
+    pub fn keys(&self) -> &[Jwk] {
+        &self.keys
+    }
+
+    /// Convert the key set into a json string.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+/// Split the DER encoding of an `RSAPublicKey` into its modulus and exponent.
+fn rsa_public_key(der: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (sequence, rest) = der_element(der, 0x30)?;
+    if !rest.is_empty() {
+        return None;
+    }
+
+    let (modulus, rest) = der_element(sequence, 0x02)?;
+    let (exponent, rest) = der_element(rest, 0x02)?;
+    if !rest.is_empty() {
+        return None;
+    }
+
+    Some((unsigned(modulus), unsigned(exponent)))
+}
+
+/// Encode a modulus and exponent as the DER encoding of an `RSAPublicKey`.
+fn rsa_public_key_der(modulus: &[u8], exponent: &[u8]) -> Vec<u8> {
+    let mut integers = der_integer(modulus);
+    integers.extend(der_integer(exponent));
+    der_encode(0x30, &integers)
+}
+
+/// Encode a positive integer, adding the sign byte if necessary.
+fn der_integer(integer: &[u8]) -> Vec<u8> {
+    let integer = unsigned(integer);
+    match integer.first() {
+        Some(&first) if first & 0x80 == 0 => der_encode(0x02, integer),
+        _ => {
+            let mut signed = vec![0];
+            signed.extend(integer);
+            der_encode(0x02, &signed)
+        }
+    }
+}
+
+/// Encode one element with its tag and length.
+fn der_encode(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut element = vec![tag];
+    let length = content.len().to_be_bytes();
+    match content.len() {
+        short if short < 0x80 => element.push(short as u8),
+        _ => {
+            let significant: Vec<u8> = length.iter().copied().skip_while(|&byte| byte == 0).collect();
+            element.push(0x80 | significant.len() as u8);
+            element.extend(significant);
+        }
+    }
+
+    element.extend(content);
+    element
+}
+
+/// Parse one element with the expected tag, returning its content and the remaining input.
+fn der_element(input: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    if input.len() < 2 || input[0] != tag {
+        return None;
+    }
+
+    let (length, header) = match input[1] {
+        short if short < 0x80 => (usize::from(short), 2),
+        long => {
+            let count = usize::from(long & 0x7f);
+            if count == 0 || count > 4 || input.len() < 2 + count {
+                return None;
+            }
+
+            let length = input[2..2 + count]
+                .iter()
+                .fold(0, |length, &byte| length << 8 | usize::from(byte));
+            (length, 2 + count)
+        }
+    };
+
+    let end = header.checked_add(length)?;
+    if input.len() < end {
+        return None;
+    }
+
+    Some((&input[header..end], &input[end..]))
+}
+
+/// Strip the sign byte of a positive integer.
+fn unsigned(mut integer: &[u8]) -> &[u8] {
+    while integer.len() > 1 && integer[0] == 0 {
+        integer = &integer[1..];
+    }
+
+    integer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{EcdsaKeyPair, Ed25519KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+    use serde_json::Value as JsonValue;
+
+    fn ed25519() -> Assertion {
+        let key = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        Assertion::new(AssertionKind::Ed25519, key.as_ref())
+    }
+
+    fn ecdsa() -> Assertion {
+        let key = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &SystemRandom::new())
+            .unwrap();
+        Assertion::new(AssertionKind::EcdsaP256Sha256, key.as_ref())
+    }
+
+    fn json(jwk: &Jwk) -> JsonValue {
+        serde_json::to_value(jwk).unwrap()
+    }
+
+    #[test]
+    fn jwk_ed25519() {
+        let assertion = ed25519();
+        let jwk = Jwk::from_assertion(&assertion).unwrap().with_key_id("first");
+        let value = json(&jwk);
+
+        assert_eq!(value["kty"], "OKP");
+        assert_eq!(value["crv"], "Ed25519");
+        assert_eq!(value["alg"], "EdDSA");
+        assert_eq!(value["use"], "sig");
+        assert_eq!(value["kid"], "first");
+        let x = encode_config(assertion.public_key().unwrap(), URL_SAFE_NO_PAD);
+        assert_eq!(value["x"], JsonValue::from(x));
+        assert!(value.get("n").is_none());
+    }
+
+    #[test]
+    fn jwk_ecdsa() {
+        let assertion = ecdsa();
+        let value = json(&Jwk::from_assertion(&assertion).unwrap());
+
+        assert_eq!(value["kty"], "EC");
+        assert_eq!(value["crv"], "P-256");
+        assert_eq!(value["alg"], "ES256");
+        assert!(value.get("kid").is_none());
+
+        let point = assertion.public_key().unwrap();
+        let x = encode_config(&point[1..33], URL_SAFE_NO_PAD);
+        let y = encode_config(&point[33..], URL_SAFE_NO_PAD);
+        assert_eq!(value["x"], JsonValue::from(x));
+        assert_eq!(value["y"], JsonValue::from(y));
+    }
+
+    #[test]
+    fn jwk_rsa() {
+        // A 1024 bit modulus with high bit set, requiring the sign byte, and exponent 65537.
+        let mut der = vec![0x30, 0x81, 0x89, 0x02, 0x81, 0x81, 0x00];
+        der.extend(vec![0xc1; 128]);
+        der.extend(&[0x02, 0x03, 0x01, 0x00, 0x01]);
+
+        let assertion = Assertion::verify_only(AssertionKind::RsaPssSha256, &der).unwrap();
+        let value = json(&Jwk::from_assertion(&assertion).unwrap());
+
+        assert_eq!(value["kty"], "RSA");
+        assert_eq!(value["alg"], "PS256");
+        assert_eq!(value["e"], "AQAB");
+        let n = encode_config(vec![0xc1; 128], URL_SAFE_NO_PAD);
+        assert_eq!(value["n"], JsonValue::from(n));
+
+        let truncated = Assertion::verify_only(AssertionKind::RsaPssSha256, &der[..100]).unwrap();
+        assert_eq!(Jwk::from_assertion(&truncated), None);
+    }
+
+    #[test]
+    fn jwk_set_from_keyring() {
+        let keyring = Keyring::new("secret", Assertion::new(AssertionKind::HmacSha256, b"key"));
+        assert_eq!(JwkSet::from_keyring(&keyring).keys(), &[]);
+
+        keyring.rotate("b-ecdsa", ecdsa()).unwrap();
+        let public_key = ed25519().public_key().unwrap().to_vec();
+        let verify_only = Assertion::verify_only(AssertionKind::Ed25519, &public_key).unwrap();
+        keyring.insert("a-ed25519", verify_only).unwrap();
+
+        let set = JwkSet::from_keyring(&keyring);
+        let kids: Vec<_> = set.keys().iter().map(Jwk::key_id).collect();
+        assert_eq!(kids, vec![Some("a-ed25519"), Some("b-ecdsa")]);
+
+        let document: JsonValue = serde_json::from_str(&set.to_json()).unwrap();
+        assert_eq!(document["keys"].as_array().unwrap().len(), 2);
+        assert_eq!(document["keys"][1]["alg"], "ES256");
+    }
+
+    #[test]
+    fn jwk_set_replaces_key_id() {
+        let mut set = JwkSet::new();
+        set.push(Jwk::from_assertion(&ed25519()).unwrap().with_key_id("key"));
+        set.push(Jwk::from_assertion(&ecdsa()).unwrap().with_key_id("key"));
+        assert_eq!(set.keys().len(), 1);
+        assert_eq!(set.keys()[0].algorithm(), Some("ES256"));
+    }
+
+    #[test]
+    fn jwk_to_assertion() {
+        for assertion in &[ed25519(), ecdsa()] {
+            let jwk = Jwk::from_assertion(assertion).unwrap();
+            let parsed: Jwk = serde_json::from_value(json(&jwk)).unwrap();
+            let verifier = parsed.to_assertion().unwrap();
+            assert_eq!(verifier.kind(), assertion.kind());
+            assert_eq!(verifier.public_key(), assertion.public_key());
+        }
+
+        let mut der = vec![0x30, 0x81, 0x89, 0x02, 0x81, 0x81, 0x00];
+        der.extend(vec![0xc1; 128]);
+        der.extend(&[0x02, 0x03, 0x01, 0x00, 0x01]);
+        let rsa = Assertion::verify_only(AssertionKind::RsaPssSha256, &der).unwrap();
+        let verifier = Jwk::from_assertion(&rsa).unwrap().to_assertion().unwrap();
+        assert_eq!(verifier.public_key(), Some(&der[..]));
+
+        let mismatch: Jwk = serde_json::from_value(serde_json::json!({
+            "kty": "EC",
+            "alg": "EdDSA",
+            "crv": "P-256",
+            "x": json(&Jwk::from_assertion(&ecdsa()).unwrap())["x"],
+            "y": json(&Jwk::from_assertion(&ecdsa()).unwrap())["y"],
+        }))
+        .unwrap();
+        assert!(mismatch.to_assertion().is_none());
+    }
+
+    #[test]
+    fn jwk_thumbprint() {
+        // The example key of RFC 7638, section 3.1.
+        let jwk: Jwk = serde_json::from_value(serde_json::json!({
+            "kty": "RSA",
+            "n": "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSo\
+                  c_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQ\
+                  R0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFT\
+                  WhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw",
+            "e": "AQAB",
+            "alg": "RS256",
+            "kid": "2011-04-29",
+        }))
+        .unwrap();
+        assert_eq!(
+            jwk.thumbprint().as_deref(),
+            Some("NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs")
+        );
+
+        let key = Jwk::from_assertion(&ed25519()).unwrap();
+        let thumbprint = key.thumbprint().unwrap();
+        assert_eq!(key.with_key_id("other").thumbprint(), Some(thumbprint));
+    }
+}
@@ -0,0 +1,863 @@
+//! Verifiers check JWT assertions of trusted identity providers, issuers create JWT access tokens.
+//!
+//! With the JWT bearer grant, a client exchanges an assertion that was signed by a third party, for
+//! example a partner identity provider, for an access token. The authorization server only accepts
+//! assertions from issuers it has been configured to trust, see [`TrustedIssuers`]. The subject of
+//! the assertion becomes the owner of the resulting grant. See [RFC 7523] for details.
+//!
+//! In the other direction, the [`JwtIssuer`] issues access tokens in the JWT profile of [RFC 9068].
+//! Resource servers that know the verification key can validate these tokens themselves, without
+//! asking the authorization server.
+//!
+//! [`TrustedIssuers`]: struct.TrustedIssuers.html
+//! [`JwtIssuer`]: struct.JwtIssuer.html
+//! [RFC 7523]: https://tools.ietf.org/html/rfc7523
+//! [RFC 9068]: https://tools.ietf.org/html/rfc9068
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use base64::{decode_config, encode_config, URL_SAFE_NO_PAD};
+use chrono::{Duration, TimeZone, Utc};
+use hmac::{Hmac, Mac, NewMac};
+use rand::{thread_rng, RngCore};
+use serde::Deserialize;
+use serde_json::{Map, Value as JsonValue};
+use sha2::Sha256;
+
+use super::{Time, Url};
+use super::generator::Assertion;
+use super::grant::{Extensions, Grant, Value};
+use super::jwk::Jwk;
+use super::issuer::{IssuedToken, Issuer, RefreshedToken};
+
+/// Verifiers check the signature and claims of JWT assertions.
+pub trait AssertionVerifier {
+    /// Check an assertion against the keys of the trusted issuers.
+    ///
+    /// Implementations must verify the signature with the key of the issuer named in the `iss`
+    /// claim, ensure that the `aud` claim contains the audience of the trusted issuers and that
+    /// the assertion has not expired according to its `exp` claim. Returns `None` if any of these
+    /// checks fail. An error indicates that the assertion could not be checked at all.
+    fn verify(&self, assertion: &str, trusted: &TrustedIssuers)
+        -> Result<Option<VerifiedAssertion>, ()>;
+}
+
+/// The identity asserted by a successfully verified assertion.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifiedAssertion {
+    /// The trusted issuer that signed the assertion.
+    pub issuer: String,
+
+    /// The subject of the assertion, used as the owner of the grant.
+    pub subject: String,
+}
+
+/// A key with which a trusted issuer signs its assertions.
+#[derive(Clone)]
+#[non_exhaustive]
+pub enum IssuerKey {
+    /// A shared secret for the `HS256` algorithm.
+    Hs256(Vec<u8>),
+
+    /// The public key of an asymmetric algorithm, as published by the issuer.
+    ///
+    /// The key only verifies assertions. It is accepted for the algorithms supported by
+    /// `Jwk::to_assertion`, assertions with keys that can not be converted are rejected.
+    Jwk(Jwk),
+}
+
+/// The configuration of trusted issuers and the expected audience of their assertions.
+#[derive(Clone)]
+pub struct TrustedIssuers {
+    audience: String,
+    leeway: Duration,
+    issuers: HashMap<String, IssuerKey>,
+}
+
+/// Verifies compact JWS assertions signed with the key of a trusted issuer.
+///
+/// Only the algorithms of `IssuerKey` are supported, the `alg` header must match the key of the
+/// issuer. Other algorithms, including the unsecured `none`, are always rejected.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JwtVerifier;
+
+#[derive(Deserialize)]
+struct Header {
+    alg: String,
+}
+
+#[derive(Deserialize)]
+struct Claims {
+    iss: String,
+    sub: String,
+    aud: Audience,
+    exp: i64,
+    #[serde(default)]
+    nbf: Option<i64>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+/// The decoded parts of a compact JWS.
+pub(crate) struct Compact<'a> {
+    pub(crate) header: Vec<u8>,
+    pub(crate) payload: Vec<u8>,
+    pub(crate) signature: Vec<u8>,
+    /// The encoded header and payload, including the separating dot, covered by the signature.
+    pub(crate) signed: &'a [u8],
+}
+
+/// Issues access tokens as signed JWTs, following the profile of RFC 9068.
+///
+/// Tokens carry the type `at+jwt` in their header and the claims `iss`, `aud`, `sub`, `client_id`,
+/// `scope`, `exp`, `iat` and `jti`. Public extensions of the grant are added as further claims, a
+/// public extension without content becomes the claim value `true`. Since the token is not
+/// encrypted, issuing ERRORS if the grant contains any private extension or an extension named
+/// like one of the registered claims.
+///
+/// Recovering a token verifies its signature, type, issuer, audience and expiry. The redirect uri
+/// of the grant is not part of the token, recovered grants carry the issuer url instead. As with
+/// the `TokenSigner`, tokens can neither be refreshed nor revoked.
+pub struct JwtIssuer {
+    signer: Assertion,
+    key_id: Option<String>,
+    issuer: Url,
+    audience: String,
+    duration: Option<Duration>,
+}
+
+/// Claims set by the `JwtIssuer` itself, extensions may not replace them.
+const REGISTERED_CLAIMS: &[&str] = &[
+    "iss",
+    "aud",
+    "sub",
+    "client_id",
+    "scope",
+    "exp",
+    "iat",
+    "nbf",
+    "jti",
+];
+
+impl TrustedIssuers {
+    /// Trust no issuer yet, only accepting assertions intended for `audience`.
+    ///
+    /// The audience should identify this authorization server, usually it is the url of the token
+    /// endpoint.
+    pub fn new(audience: &str) -> Self {
+        TrustedIssuers {
+            audience: audience.to_string(),
+            leeway: Duration::seconds(60),
+            issuers: HashMap::new(),
+        }
+    }
+
+    /// Trust the assertions of an issuer signed with the given key.
+    ///
+    /// Replaces a previously configured key of the same issuer.
+    pub fn trust(&mut self, issuer: &str, key: IssuerKey) {
+        self.issuers.insert(issuer.to_string(), key);
+    }
+
+    /// Change the allowed clock skew for the expiration of assertions.
+    ///
+    /// The default is one minute.
+    pub fn set_leeway(&mut self, leeway: Duration) {
+        self.leeway = leeway;
+    }
+
+    /// The audience that assertions must be intended for.
+    pub fn audience(&self) -> &str {
+        &self.audience
+    }
+
+    /// The allowed clock skew for the expiration of assertions.
+    pub fn leeway(&self) -> Duration {
+        self.leeway
+    }
+
+    /// Get the key of a trusted issuer.
+    pub fn key(&self, issuer: &str) -> Option<&IssuerKey> {
+        self.issuers.get(issuer)
+    }
+}
+
+impl JwtVerifier {
+    fn check_signature(key: &IssuerKey, alg: &str, signed: &[u8], signature: &[u8]) -> bool {
+        match (key, alg) {
+            (IssuerKey::Hs256(secret), "HS256") => {
+                let mut mac = match Hmac::<Sha256>::new_varkey(secret) {
+                    Ok(mac) => mac,
+                    Err(_) => return false,
+                };
+                mac.update(signed);
+                mac.verify(signature).is_ok()
+            }
+            (IssuerKey::Jwk(jwk), alg) => match jwk.to_assertion() {
+                Some(key) => key.jws_algorithm() == alg && key.verify_detached(signed, signature),
+                None => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn check_claims(claims: &Claims, trusted: &TrustedIssuers, now: Time) -> bool {
+        let audience = trusted.audience();
+        let intended = match &claims.aud {
+            Audience::Single(aud) => aud == audience,
+            Audience::Multiple(auds) => auds.iter().any(|aud| aud == audience),
+        };
+
+        // Both claims are chosen by the client, refuse those not representable as a time.
+        let expires = Utc
+            .timestamp_opt(claims.exp, 0)
+            .single()
+            .and_then(|exp| exp.checked_add_signed(trusted.leeway()));
+        let expired = match expires {
+            Some(expires) => expires <= now,
+            None => return false,
+        };
+
+        let premature = match claims.nbf {
+            Some(nbf) => {
+                let valid_from = Utc
+                    .timestamp_opt(nbf, 0)
+                    .single()
+                    .and_then(|nbf| nbf.checked_sub_signed(trusted.leeway()));
+                match valid_from {
+                    Some(valid_from) => valid_from > now,
+                    None => return false,
+                }
+            }
+            None => false,
+        };
+
+        intended && !expired && !premature && !claims.sub.is_empty()
+    }
+}
+
+impl<'a> Compact<'a> {
+    pub(crate) fn split(token: &'a str) -> Option<Self> {
+        let mut parts = token.split('.');
+        let (header, payload, signature) = match (parts.next(), parts.next(), parts.next(), parts.next())
+        {
+            (Some(header), Some(payload), Some(signature), None) => (header, payload, signature),
+            _ => return None,
+        };
+
+        let signed = &token.as_bytes()[..header.len() + 1 + payload.len()];
+        Some(Compact {
+            header: decode_config(header, URL_SAFE_NO_PAD).ok()?,
+            payload: decode_config(payload, URL_SAFE_NO_PAD).ok()?,
+            signature: decode_config(signature, URL_SAFE_NO_PAD).ok()?,
+            signed,
+        })
+    }
+}
+
+impl JwtIssuer {
+    /// Sign tokens with the assertion, identifying this server as `issuer`.
+    ///
+    /// The `audience` identifies the resource servers for which the tokens are intended, they
+    /// should reject tokens for any other audience.
+    pub fn new(signer: Assertion, issuer: Url, audience: &str) -> Self {
+        JwtIssuer {
+            signer,
+            key_id: None,
+            issuer,
+            audience: audience.to_string(),
+            duration: None,
+        }
+    }
+
+    /// Name the signing key in the `kid` header of tokens.
+    ///
+    /// Resource servers use the id to select the key from the published `JwkSet`. Tokens with a
+    /// different key id are no longer recovered.
+    pub fn set_key_id(&mut self, kid: &str) {
+        self.key_id = Some(kid.to_string());
+    }
+
+    /// The public key verifying the issued tokens, for publishing in a `JwkSet`.
+    ///
+    /// This is `None` for the symmetric `HmacSha256` suite.
+    pub fn jwk(&self) -> Option<Jwk> {
+        let jwk = Jwk::from_assertion(&self.signer)?;
+        match &self.key_id {
+            Some(kid) => Some(jwk.with_key_id(kid)),
+            None => Some(jwk),
+        }
+    }
+
+    /// Set the validity of all issued tokens to the specified duration.
+    ///
+    /// This only affects tokens issued after this call. By default, tokens are valid until the
+    /// grant expires.
+    pub fn valid_for(&mut self, duration: Duration) {
+        self.duration = Some(duration);
+    }
+
+    /// Let tokens be valid until their grant expires.
+    pub fn valid_for_default(&mut self) {
+        self.duration = None;
+    }
+
+    fn claims(&self, grant: &Grant, now: Time) -> Result<Map<String, JsonValue>, ()> {
+        if grant.extensions.private().any(|_| true) {
+            return Err(());
+        }
+
+        let mut jti = [0; 16];
+        thread_rng().fill_bytes(&mut jti);
+
+        let mut claims = Map::new();
+        for (name, content) in grant.extensions.public() {
+            if REGISTERED_CLAIMS.contains(&name) {
+                return Err(());
+            }
+
+            let value = match content {
+                Some(content) => JsonValue::from(content),
+                None => JsonValue::Bool(true),
+            };
+            claims.insert(name.to_string(), value);
+        }
+
+        claims.insert("iss".into(), self.issuer.as_str().into());
+        claims.insert("aud".into(), self.audience.as_str().into());
+        claims.insert("sub".into(), grant.owner_id.as_str().into());
+        claims.insert("client_id".into(), grant.client_id.as_str().into());
+        claims.insert("scope".into(), grant.scope.to_string().into());
+        claims.insert("exp".into(), grant.until.timestamp().into());
+        claims.insert("iat".into(), now.timestamp().into());
+        claims.insert("jti".into(), encode_config(jti, URL_SAFE_NO_PAD).into());
+        Ok(claims)
+    }
+
+    fn sign(&self, claims: &Map<String, JsonValue>) -> Result<String, ()> {
+        let mut header = serde_json::json!({
+            "typ": "at+jwt",
+            "alg": self.signer.jws_algorithm(),
+        });
+        if let Some(kid) = &self.key_id {
+            header["kid"] = kid.as_str().into();
+        }
+
+        let signed = format!(
+            "{}.{}",
+            encode_config(header.to_string().as_bytes(), URL_SAFE_NO_PAD),
+            encode_config(
+                JsonValue::Object(claims.clone()).to_string().as_bytes(),
+                URL_SAFE_NO_PAD
+            ),
+        );
+        let signature = self.signer.sign_detached(signed.as_bytes())?;
+        Ok(format!(
+            "{}.{}",
+            signed,
+            encode_config(&signature, URL_SAFE_NO_PAD)
+        ))
+    }
+
+    fn verify(&self, token: &str, now: Time) -> Option<Grant> {
+        let compact = Compact::split(token)?;
+        let header: Map<String, JsonValue> = serde_json::from_slice(&compact.header).ok()?;
+        let claims: Map<String, JsonValue> = serde_json::from_slice(&compact.payload).ok()?;
+
+        let typ = header.get("typ").and_then(JsonValue::as_str)?;
+        if !typ.eq_ignore_ascii_case("at+jwt") && !typ.eq_ignore_ascii_case("application/at+jwt") {
+            return None;
+        }
+
+        if header.get("alg").and_then(JsonValue::as_str) != Some(self.signer.jws_algorithm()) {
+            return None;
+        }
+
+        if header.get("kid").and_then(JsonValue::as_str) != self.key_id.as_deref() {
+            return None;
+        }
+
+        if !self.signer.verify_detached(compact.signed, &compact.signature) {
+            return None;
+        }
+
+        let string = |key: &str| claims.get(key).and_then(JsonValue::as_str);
+        if string("iss") != Some(self.issuer.as_str()) || string("aud") != Some(&self.audience) {
+            return None;
+        }
+
+        let exp = claims.get("exp").and_then(JsonValue::as_i64)?;
+        let until = Utc.timestamp_opt(exp, 0).single()?;
+        if until <= now {
+            return None;
+        }
+
+        let mut extensions = Extensions::new();
+        for (name, value) in claims.iter() {
+            if REGISTERED_CLAIMS.contains(&name.as_str()) {
+                continue;
+            }
+
+            let content = match value {
+                JsonValue::Bool(true) => None,
+                JsonValue::String(content) => Some(content.clone()),
+                other => Some(other.to_string()),
+            };
+            extensions.set_raw(name.clone(), Value::public(content));
+        }
+
+        Some(Grant {
+            owner_id: string("sub")?.to_string(),
+            client_id: string("client_id")?.to_string(),
+            scope: string("scope")?.parse().ok()?,
+            redirect_uri: self.issuer.clone(),
+            until,
+            extensions,
+        })
+    }
+}
+
+impl AssertionVerifier for JwtVerifier {
+    fn verify(
+        &self, assertion: &str, trusted: &TrustedIssuers,
+    ) -> Result<Option<VerifiedAssertion>, ()> {
+        let compact = match Compact::split(assertion) {
+            Some(compact) => compact,
+            None => return Ok(None),
+        };
+
+        let header: Header = match serde_json::from_slice(&compact.header) {
+            Ok(header) => header,
+            Err(_) => return Ok(None),
+        };
+        let claims: Claims = match serde_json::from_slice(&compact.payload) {
+            Ok(claims) => claims,
+            Err(_) => return Ok(None),
+        };
+
+        let key = match trusted.key(&claims.iss) {
+            Some(key) => key,
+            None => return Ok(None),
+        };
+
+        if !Self::check_signature(key, &header.alg, compact.signed, &compact.signature) {
+            return Ok(None);
+        }
+
+        if !Self::check_claims(&claims, trusted, Utc::now()) {
+            return Ok(None);
+        }
+
+        Ok(Some(VerifiedAssertion {
+            issuer: claims.iss,
+            subject: claims.sub,
+        }))
+    }
+}
+
+impl<'s, V: AssertionVerifier + ?Sized> AssertionVerifier for &'s V {
+    fn verify(
+        &self, assertion: &str, trusted: &TrustedIssuers,
+    ) -> Result<Option<VerifiedAssertion>, ()> {
+        (**self).verify(assertion, trusted)
+    }
+}
+
+impl<V: AssertionVerifier + ?Sized> AssertionVerifier for Box<V> {
+    fn verify(
+        &self, assertion: &str, trusted: &TrustedIssuers,
+    ) -> Result<Option<VerifiedAssertion>, ()> {
+        (**self).verify(assertion, trusted)
+    }
+}
+
+impl<V: AssertionVerifier + ?Sized> AssertionVerifier for Rc<V> {
+    fn verify(
+        &self, assertion: &str, trusted: &TrustedIssuers,
+    ) -> Result<Option<VerifiedAssertion>, ()> {
+        (**self).verify(assertion, trusted)
+    }
+}
+
+impl<V: AssertionVerifier + ?Sized> AssertionVerifier for Arc<V> {
+    fn verify(
+        &self, assertion: &str, trusted: &TrustedIssuers,
+    ) -> Result<Option<VerifiedAssertion>, ()> {
+        (**self).verify(assertion, trusted)
+    }
+}
+
+impl Issuer for JwtIssuer {
+    fn issue(&mut self, mut grant: Grant) -> Result<IssuedToken, ()> {
+        let now = Utc::now();
+        if let Some(duration) = &self.duration {
+            grant.until = now + *duration;
+        }
+
+        let claims = self.claims(&grant, now)?;
+        Ok(IssuedToken::without_refresh(self.sign(&claims)?, grant.until))
+    }
+
+    fn refresh(&mut self, _refresh: &str, _grant: Grant) -> Result<RefreshedToken, ()> {
+        Err(())
+    }
+
+    fn recover_token<'a>(&'a self, token: &'a str) -> Result<Option<Grant>, ()> {
+        Ok(self.verify(token, Utc::now()))
+    }
+
+    fn recover_refresh<'a>(&'a self, _: &'a str) -> Result<Option<Grant>, ()> {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::generator::AssertionKind;
+    use crate::primitives::scope::Scope;
+    use ring::rand::SystemRandom;
+    use ring::signature::Ed25519KeyPair;
+
+    const AUDIENCE: &str = "https://example.com/token";
+    const ISSUER: &str = "https://idp.example";
+    const SECRET: &[u8] = b"a shared secret of the identity provider";
+
+    fn trusted() -> TrustedIssuers {
+        let mut trusted = TrustedIssuers::new(AUDIENCE);
+        trusted.trust(ISSUER, IssuerKey::Hs256(SECRET.to_vec()));
+        trusted
+    }
+
+    fn sign(alg: &str, claims: &str, secret: &[u8]) -> String {
+        let header = format!(r#"{{"alg":"{}","typ":"JWT"}}"#, alg);
+        let signed = format!(
+            "{}.{}",
+            encode_config(header.as_bytes(), URL_SAFE_NO_PAD),
+            encode_config(claims.as_bytes(), URL_SAFE_NO_PAD)
+        );
+        let mut mac = Hmac::<Sha256>::new_varkey(secret).unwrap();
+        mac.update(signed.as_bytes());
+        let signature = mac.finalize().into_bytes();
+        format!("{}.{}", signed, encode_config(&signature, URL_SAFE_NO_PAD))
+    }
+
+    fn claims(iss: &str, aud: &str, exp: Time) -> String {
+        format!(
+            r#"{{"iss":"{}","sub":"Owner","aud":{},"exp":{}}}"#,
+            iss,
+            aud,
+            exp.timestamp()
+        )
+    }
+
+    #[test]
+    fn valid_assertion() {
+        let exp = Utc::now() + Duration::minutes(5);
+        let single = sign(
+            "HS256",
+            &claims(ISSUER, &format!("\"{}\"", AUDIENCE), exp),
+            SECRET,
+        );
+        let multiple = sign(
+            "HS256",
+            &claims(ISSUER, &format!("[\"other\",\"{}\"]", AUDIENCE), exp),
+            SECRET,
+        );
+
+        let expected = Some(VerifiedAssertion {
+            issuer: ISSUER.to_string(),
+            subject: "Owner".to_string(),
+        });
+        assert_eq!(JwtVerifier.verify(&single, &trusted()), Ok(expected.clone()));
+        assert_eq!(JwtVerifier.verify(&multiple, &trusted()), Ok(expected));
+    }
+
+    #[test]
+    fn rejected_assertions() {
+        let audience = format!("\"{}\"", AUDIENCE);
+        let exp = Utc::now() + Duration::minutes(5);
+        let expired = Utc::now() - Duration::minutes(5);
+
+        let rejected = vec![
+            sign("HS256", &claims(ISSUER, &audience, exp), b"not the secret"),
+            sign(
+                "HS256",
+                &claims("https://untrusted.example", &audience, exp),
+                SECRET,
+            ),
+            sign("HS256", &claims(ISSUER, "\"https://other.example\"", exp), SECRET),
+            sign("HS256", &claims(ISSUER, &audience, expired), SECRET),
+            sign("HS384", &claims(ISSUER, &audience, exp), SECRET),
+            "not.a.jwt".to_string(),
+        ];
+
+        for assertion in rejected {
+            assert_eq!(JwtVerifier.verify(&assertion, &trusted()), Ok(None));
+        }
+
+        // Times that can not be represented are refused instead of panicking.
+        let out_of_range = vec![
+            format!(
+                r#"{{"iss":"{}","sub":"Owner","aud":{},"exp":{}}}"#,
+                ISSUER,
+                audience,
+                i64::MAX
+            ),
+            format!(
+                r#"{{"iss":"{}","sub":"Owner","aud":{},"exp":{},"nbf":{}}}"#,
+                ISSUER,
+                audience,
+                exp.timestamp(),
+                i64::MIN
+            ),
+        ];
+
+        for claims in out_of_range {
+            let assertion = sign("HS256", &claims, SECRET);
+            assert_eq!(JwtVerifier.verify(&assertion, &trusted()), Ok(None));
+        }
+
+        // The unsecured algorithm must never be accepted.
+        let valid = sign("HS256", &claims(ISSUER, &audience, exp), SECRET);
+        let payload = valid.split('.').nth(1).unwrap();
+        let header = encode_config(br#"{"alg":"none"}"#, URL_SAFE_NO_PAD);
+        let unsecured = format!("{}.{}.", header, payload);
+        assert_eq!(JwtVerifier.verify(&unsecured, &trusted()), Ok(None));
+    }
+
+    fn sign_asymmetric(alg: &str, claims: &str, key: &Assertion) -> String {
+        let header = format!(r#"{{"alg":"{}","typ":"JWT"}}"#, alg);
+        let signed = format!(
+            "{}.{}",
+            encode_config(header.as_bytes(), URL_SAFE_NO_PAD),
+            encode_config(claims.as_bytes(), URL_SAFE_NO_PAD)
+        );
+        let signature = key.sign_detached(signed.as_bytes()).unwrap();
+        format!("{}.{}", signed, encode_config(&signature, URL_SAFE_NO_PAD))
+    }
+
+    #[test]
+    fn asymmetric_assertion() {
+        let ed25519 = || {
+            let key = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+            Assertion::new(AssertionKind::Ed25519, key.as_ref())
+        };
+        let (key, other) = (ed25519(), ed25519());
+
+        let mut trusted = TrustedIssuers::new(AUDIENCE);
+        trusted.trust(ISSUER, IssuerKey::Jwk(Jwk::from_assertion(&key).unwrap()));
+
+        let claims = claims(
+            ISSUER,
+            &format!("\"{}\"", AUDIENCE),
+            Utc::now() + Duration::minutes(5),
+        );
+        let valid = sign_asymmetric("EdDSA", &claims, &key);
+        assert_eq!(
+            JwtVerifier.verify(&valid, &trusted),
+            Ok(Some(VerifiedAssertion {
+                issuer: ISSUER.to_string(),
+                subject: "Owner".to_string(),
+            }))
+        );
+
+        let rejected = vec![
+            sign_asymmetric("EdDSA", &claims, &other),
+            sign_asymmetric("ES256", &claims, &key),
+            // The public key must not be usable as a shared secret.
+            sign("HS256", &claims, key.public_key().unwrap()),
+        ];
+
+        for assertion in rejected {
+            assert_eq!(JwtVerifier.verify(&assertion, &trusted), Ok(None));
+        }
+    }
+
+    fn jwt_issuer(key: &[u8]) -> JwtIssuer {
+        JwtIssuer::new(
+            Assertion::new(AssertionKind::HmacSha256, key),
+            "https://example.com".parse().unwrap(),
+            "https://api.example.com",
+        )
+    }
+
+    fn grant() -> Grant {
+        let mut extensions = Extensions::new();
+        extensions.set_raw("tenant".to_string(), Value::public(Some("acme".to_string())));
+        extensions.set_raw("mfa".to_string(), Value::public(None));
+
+        Grant {
+            owner_id: "Owner".to_string(),
+            client_id: "Client".to_string(),
+            scope: "default example".parse().unwrap(),
+            redirect_uri: "https://client.example/endpoint".parse().unwrap(),
+            until: Utc::now() + Duration::hours(1),
+            extensions,
+        }
+    }
+
+    fn decode_part(token: &str, index: usize) -> Map<String, JsonValue> {
+        let part = token.split('.').nth(index).unwrap();
+        serde_json::from_slice(&decode_config(part, URL_SAFE_NO_PAD).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn jwt_issuer_claims() {
+        let mut issuer = jwt_issuer(SECRET);
+        let grant = grant();
+        let issued = issuer.issue(grant.clone()).unwrap();
+        assert!(!issued.refreshable());
+
+        let header = decode_part(&issued.token, 0);
+        assert_eq!(header.get("typ"), Some(&JsonValue::from("at+jwt")));
+        assert_eq!(header.get("alg"), Some(&JsonValue::from("HS256")));
+
+        let claims = decode_part(&issued.token, 1);
+        let claim = |key: &str| claims.get(key).cloned();
+        assert_eq!(claim("iss"), Some("https://example.com/".into()));
+        assert_eq!(claim("aud"), Some("https://api.example.com".into()));
+        assert_eq!(claim("sub"), Some("Owner".into()));
+        assert_eq!(claim("client_id"), Some("Client".into()));
+        assert_eq!(claim("exp"), Some(grant.until.timestamp().into()));
+        assert_eq!(claim("tenant"), Some("acme".into()));
+        assert_eq!(claim("mfa"), Some(true.into()));
+        assert!(claim("iat").unwrap().is_i64());
+        assert!(claim("jti").unwrap().is_string());
+
+        let scope: Scope = claim("scope").unwrap().as_str().unwrap().parse().unwrap();
+        assert_eq!(scope, grant.scope);
+
+        let second = issuer.issue(grant).unwrap();
+        assert_ne!(issued.token, second.token);
+    }
+
+    #[test]
+    fn jwt_issuer_recover() {
+        let mut issuer = jwt_issuer(SECRET);
+        let grant = grant();
+        let issued = issuer.issue(grant.clone()).unwrap();
+
+        let recovered = issuer
+            .recover_token(&issued.token)
+            .unwrap()
+            .expect("Issued token appears to be invalid");
+        assert_eq!(recovered.owner_id, grant.owner_id);
+        assert_eq!(recovered.client_id, grant.client_id);
+        assert_eq!(recovered.scope, grant.scope);
+        assert_eq!(recovered.until.timestamp(), grant.until.timestamp());
+        assert_eq!(recovered.redirect_uri.as_str(), "https://example.com/");
+
+        let mut public: Vec<_> = recovered.extensions.public().collect();
+        public.sort();
+        assert_eq!(public, vec![("mfa", None), ("tenant", Some("acme"))]);
+
+        assert_eq!(issuer.recover_refresh(&issued.token), Ok(None));
+        assert!(issuer.refresh(&issued.token, grant).is_err());
+    }
+
+    #[test]
+    fn jwt_issuer_rejected_tokens() {
+        let mut issuer = jwt_issuer(SECRET);
+        let issued = issuer.issue(grant()).unwrap();
+
+        let other_key = jwt_issuer(b"not the secret");
+        assert_eq!(other_key.recover_token(&issued.token), Ok(None));
+
+        let other_audience = JwtIssuer::new(
+            Assertion::new(AssertionKind::HmacSha256, SECRET),
+            "https://example.com".parse().unwrap(),
+            "https://other.example.com",
+        );
+        assert_eq!(other_audience.recover_token(&issued.token), Ok(None));
+
+        let mut expired = grant();
+        expired.until = Utc::now() - Duration::minutes(1);
+        let expired = issuer.issue(expired).unwrap();
+        assert_eq!(issuer.recover_token(&expired.token), Ok(None));
+
+        // A plain JWT with valid signature is not an access token.
+        let mut parts: Vec<_> = issued.token.split('.').map(str::to_string).collect();
+        parts[0] = encode_config(br#"{"typ":"JWT","alg":"HS256"}"#, URL_SAFE_NO_PAD);
+        let signed = format!("{}.{}", parts[0], parts[1]);
+        let signature = Assertion::new(AssertionKind::HmacSha256, SECRET)
+            .sign_detached(signed.as_bytes())
+            .unwrap();
+        let plain = format!("{}.{}", signed, encode_config(&signature, URL_SAFE_NO_PAD));
+        assert_eq!(issuer.recover_token(&plain), Ok(None));
+
+        // An expiry that can not be represented as a time is refused instead of panicking.
+        let mut claims = decode_part(&issued.token, 1);
+        claims.insert("exp".into(), i64::MAX.into());
+        let payload = encode_config(serde_json::to_vec(&claims).unwrap(), URL_SAFE_NO_PAD);
+        let header = issued.token.split('.').next().unwrap();
+        let signed = format!("{}.{}", header, payload);
+        let signature = Assertion::new(AssertionKind::HmacSha256, SECRET)
+            .sign_detached(signed.as_bytes())
+            .unwrap();
+        let out_of_range = format!("{}.{}", signed, encode_config(&signature, URL_SAFE_NO_PAD));
+        assert_eq!(issuer.recover_token(&out_of_range), Ok(None));
+
+        assert_eq!(issuer.recover_token("not.a.jwt"), Ok(None));
+    }
+
+    #[test]
+    fn jwt_issuer_rejects_private_extensions() {
+        let mut issuer = jwt_issuer(SECRET);
+
+        let mut private = grant();
+        private
+            .extensions
+            .set_raw("secret".to_string(), Value::private(Some("hidden".to_string())));
+        assert!(issuer.issue(private).is_err());
+
+        let mut registered = grant();
+        registered
+            .extensions
+            .set_raw("sub".to_string(), Value::public(Some("Admin".to_string())));
+        assert!(issuer.issue(registered).is_err());
+    }
+
+    #[test]
+    fn jwt_issuer_key_id() {
+        let key = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let assertion = Assertion::new(AssertionKind::Ed25519, key.as_ref());
+        let mut issuer = JwtIssuer::new(
+            assertion,
+            "https://example.com".parse().unwrap(),
+            "https://api.example.com",
+        );
+        issuer.set_key_id("2020-10");
+
+        let issued = issuer.issue(grant()).unwrap();
+        let header = decode_part(&issued.token, 0);
+        assert_eq!(header.get("alg"), Some(&JsonValue::from("EdDSA")));
+        assert_eq!(header.get("kid"), Some(&JsonValue::from("2020-10")));
+        assert!(issuer.recover_token(&issued.token).unwrap().is_some());
+
+        let jwk = issuer.jwk().unwrap();
+        assert_eq!(jwk.key_id(), Some("2020-10"));
+        assert_eq!(jwk.algorithm(), Some("EdDSA"));
+        assert!(jwt_issuer(SECRET).jwk().is_none());
+
+        issuer.set_key_id("2020-11");
+        assert_eq!(issuer.recover_token(&issued.token), Ok(None));
+    }
+
+    #[test]
+    fn jwt_issuer_duration() {
+        let mut issuer = jwt_issuer(SECRET);
+        issuer.valid_for(Duration::minutes(5));
+        let issued = issuer.issue(grant()).unwrap();
+        assert!(issued.until < Utc::now() + Duration::minutes(6));
+
+        let recovered = issuer.recover_token(&issued.token).unwrap().unwrap();
+        assert_eq!(recovered.until.timestamp(), issued.until.timestamp());
+    }
+}
@@ -36,10 +36,16 @@ use chrono::DateTime;
 use chrono::Utc;
 use url::Url;
 
+pub mod authenticator;
 pub mod authorizer;
+pub mod device;
+pub mod dpop;
 pub mod generator;
 pub mod grant;
 pub mod issuer;
+pub mod jwk;
+pub mod jwt;
+pub mod mtls;
 pub mod registrar;
 pub mod scope;
 
@@ -49,7 +55,9 @@ type Time = DateTime<Utc>;
 pub mod prelude {
     pub use super::authorizer::{Authorizer, AuthMap};
     pub use super::issuer::{IssuedToken, Issuer, TokenMap, TokenSigner};
-    pub use super::generator::{Assertion, TagGrant, RandomGenerator};
-    pub use super::registrar::{Registrar, Client, ClientUrl, ClientMap, PreGrant};
+    pub use super::generator::{Assertion, Keyring, TagGrant, TokenHasher, RandomGenerator};
+    pub use super::registrar::{
+        Registrar, RegistrarMut, Client, ClientMetadata, ClientUrl, ClientMap, PreGrant,
+    };
     pub use super::scope::Scope;
 }
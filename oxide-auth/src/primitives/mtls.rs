@@ -0,0 +1,101 @@
+//! Client certificates of mutual-TLS connections.
+//!
+//! Clients may authenticate with a certificate presented during the TLS handshake instead of a
+//! password, either one issued by a trusted certificate authority (`tls_client_auth`) or a
+//! self-signed one registered with the client (`self_signed_tls_client_auth`). In both cases the
+//! issued tokens are bound to the SHA-256 thumbprint of the certificate, stored in the public
+//! `cnf.x5t#S256` extension, and resources only accept them over a connection with the same
+//! certificate. See [RFC 8705] for details.
+//!
+//! Verifying the certificate chain is left to the server or TLS terminator, this module only deals
+//! with certificates that have already been verified.
+//!
+//! [RFC 8705]: https://tools.ietf.org/html/rfc8705
+use base64::{encode_config, URL_SAFE_NO_PAD};
+use sha2::{Digest, Sha256};
+
+use super::grant::Grant;
+
+/// The grant extension holding the thumbprint of the certificate to which the grant is bound.
+pub const CONFIRMATION: &str = "cnf.x5t#S256";
+
+/// A verified client certificate of the connection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClientCertificate {
+    thumbprint: String,
+    subject: Option<String>,
+}
+
+impl ClientCertificate {
+    /// A certificate in its DER encoding.
+    pub fn from_der(der: &[u8]) -> Self {
+        ClientCertificate {
+            thumbprint: thumbprint(der),
+            subject: None,
+        }
+    }
+
+    /// A certificate of which only the base64url encoded SHA-256 thumbprint is known.
+    ///
+    /// Useful when a TLS terminator forwards the thumbprint instead of the whole certificate.
+    pub fn from_thumbprint(thumbprint: &str) -> Self {
+        ClientCertificate {
+            thumbprint: thumbprint.to_string(),
+            subject: None,
+        }
+    }
+
+    /// Attach the subject distinguished name of the certificate.
+    ///
+    /// Required to authenticate clients with `tls_client_auth`, which are registered with the
+    /// subject of their certificate.
+    pub fn with_subject(mut self, subject: &str) -> Self {
+        self.subject = Some(subject.to_string());
+        self
+    }
+
+    /// The base64url encoded SHA-256 thumbprint of the certificate.
+    pub fn thumbprint(&self) -> &str {
+        &self.thumbprint
+    }
+
+    /// The subject distinguished name, if it is known.
+    pub fn subject(&self) -> Option<&str> {
+        self.subject.as_deref()
+    }
+}
+
+/// The base64url encoded SHA-256 thumbprint of a DER encoded certificate.
+///
+/// This is the value used in the `x5t#S256` confirmation and to register self-signed certificates.
+pub fn thumbprint(der: &[u8]) -> String {
+    encode_config(Sha256::digest(der), URL_SAFE_NO_PAD)
+}
+
+/// The thumbprint of the certificate to which a grant is bound, if any.
+pub fn confirmation(grant: &Grant) -> Option<&str> {
+    grant
+        .extensions
+        .public()
+        .find(|&(name, _)| name == CONFIRMATION)
+        .and_then(|(_, thumbprint)| thumbprint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn certificate_thumbprint() {
+        let certificate = ClientCertificate::from_der(b"certificate").with_subject("CN=client");
+        assert_eq!(
+            certificate.thumbprint(),
+            "A9Zt0Ig1wco_EozOrNHzGslBYwlrIPRFroQoW8CDLXI"
+        );
+        assert_eq!(certificate.subject(), Some("CN=client"));
+        assert_eq!(
+            certificate,
+            ClientCertificate::from_thumbprint(&thumbprint(b"certificate")).with_subject("CN=client")
+        );
+    }
+}
@@ -3,6 +3,7 @@
 //! It will govern their redirect urls and allowed scopes to request tokens for. When an oauth
 //! request turns up, it is the registrars duty to verify the requested scope and redirect url for
 //! consistency in the permissions granted and urls registered.
+use super::mtls::ClientCertificate;
 use super::scope::Scope;
 
 use std::borrow::Cow;
@@ -10,12 +11,14 @@ use std::cmp;
 use std::collections::HashMap;
 use std::fmt;
 use std::iter::{Extend, FromIterator};
+use std::net::IpAddr;
 use std::rc::Rc;
 use std::sync::{Arc, MutexGuard, RwLockWriteGuard};
 
 use argon2::{self, Config};
 use once_cell::sync::Lazy;
 use rand::{RngCore, thread_rng};
+use serde::{Deserialize, Serialize};
 use url::{Url, ParseError as ParseUrlError};
 
 /// Registrars provie a way to interact with clients.
@@ -38,19 +41,83 @@ pub trait Registrar {
     /// request.
     ///
     /// Another common strategy is to set a default scope or return the intersection with another
-    /// scope.
+    /// scope, see `ScopePolicy`.
     fn negotiate(&self, client: BoundClient, scope: Option<Scope>) -> Result<PreGrant, RegistrarError>;
 
     /// Try to login as client with some authentication.
     fn check(&self, client_id: &str, passphrase: Option<&[u8]>) -> Result<(), RegistrarError>;
+
+    /// Try to login as client with the verified certificate of a mutual-TLS connection.
+    ///
+    /// Only consulted when the client did not provide a passphrase. The default implementation
+    /// does not support certificates and refuses all clients.
+    fn check_certificate(
+        &self, client_id: &str, certificate: &ClientCertificate,
+    ) -> Result<(), RegistrarError> {
+        let _ = (client_id, certificate);
+        Err(RegistrarError::Unspecified)
+    }
+
+    /// Check that the client may use a grant type at the token endpoint.
+    ///
+    /// The grant type is named as in the `grant_type` parameter of the token request, such as
+    /// `authorization_code`, `refresh_token` or `urn:ietf:params:oauth:grant-type:device_code`.
+    /// Clients are checked after their authentication and refused with `unauthorized_client`.
+    ///
+    /// The client gets to see the credentials of the resource owner in the `password` grant, so it
+    /// should only be permitted for highly trusted clients. The default implementation refuses all
+    /// clients the `password` grant and permits them all other grant types.
+    fn check_grant_type(&self, client_id: &str, grant_type: &str) -> Result<(), RegistrarError> {
+        let _ = client_id;
+        match grant_type {
+            "password" => Err(RegistrarError::Unspecified),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that the client may use a response type at the authorization endpoint.
+    ///
+    /// The response type is either `code` or `token`, the latter being the implicit grant whose
+    /// access tokens are exposed to the user agent. Clients are checked once their redirect url
+    /// was bound and refused with `unauthorized_client`. The default implementation permits all
+    /// clients.
+    fn check_response_type(&self, client_id: &str, response_type: &str) -> Result<(), RegistrarError> {
+        let _ = (client_id, response_type);
+        Ok(())
+    }
+}
+
+/// Registrars whose clients can be added, changed and removed at runtime.
+///
+/// This is the backend of dynamic client registration, where clients register themselves and
+/// later manage their registration with the registration access token they received. Clients are
+/// handed out and updated in their encoded form, so that a registration can be changed without
+/// knowing the original passphrase of the client.
+pub trait RegistrarMut: Registrar {
+    /// Encode and insert a new client, replacing any client with the same id.
+    fn register(&mut self, client: Client) -> Result<(), RegistrarError>;
+
+    /// The stored registration of a client or `None` if the client is unknown.
+    fn client(&self, client_id: &str) -> Result<Option<EncodedClient>, RegistrarError>;
+
+    /// Replace the registration of a client with an already encoded one.
+    fn update(&mut self, client: EncodedClient) -> Result<(), RegistrarError>;
+
+    /// Remove a client. Removing an unknown client is not an error.
+    fn unregister(&mut self, client_id: &str) -> Result<(), RegistrarError>;
+
+    /// Check the registration access token with which a client manages its registration.
+    fn check_registration(&self, client_id: &str, token: &[u8]) -> Result<(), RegistrarError>;
 }
 
 /// An url that has been registered.
 ///
-/// There are two ways to create this url:
+/// There are four ways to create this url:
 ///
 /// 1. By supplying a string to match _exactly_
 /// 2. By an URL which needs to match semantically.
+/// 3. By a loopback URL of a native app, matching on any port.
+/// 4. By an URL with a private-use scheme of a native app.
 #[non_exhaustive]
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum RegisteredUrl {
@@ -63,6 +130,18 @@ pub enum RegisteredUrl {
     Exact(ExactUrl),
     /// An URL that needs to match the redirect URL semantically.
     Semantic(Url),
+    /// A loopback URL that matches the redirect URL semantically on any port.
+    ///
+    /// See the documentation of [`LoopbackUrl`] for the URLs permitted.
+    ///
+    /// [`LoopbackUrl`]: struct.LoopbackUrl.html
+    Loopback(LoopbackUrl),
+    /// An URL with a private-use scheme that needs to match the redirect URL semantically.
+    ///
+    /// See the documentation of [`PrivateUseUrl`] for the URLs permitted.
+    ///
+    /// [`PrivateUseUrl`]: struct.PrivateUseUrl.html
+    PrivateUse(PrivateUseUrl),
 }
 
 /// A redirect URL that must be matched exactly by the client.
@@ -87,13 +166,60 @@ pub enum RegisteredUrl {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ExactUrl(String);
 
+/// A loopback redirect URL of a native app, as described in [RFC 8252 §7.3].
+///
+/// Native apps receive the redirect on a port of the loopback interface that the operating system
+/// chose when the request was made, so the port can not be registered in advance. Any port
+/// matches, as long as the scheme, host, path and query are the same, e.g. these are all matched
+/// by `http://127.0.0.1/callback`:
+///
+/// * `http://127.0.0.1:49152/callback`
+/// * `http://127.0.0.1:8080/callback`
+///
+/// Only `http` URLs whose host is a loopback IP literal, such as `127.0.0.1` or `[::1]`, are
+/// permitted. The name `localhost` is refused as it could be resolved to another interface or be
+/// opened by another app, see [RFC 8252 §8.3]. The client is redirected to the URL it requested,
+/// including its port.
+///
+/// [RFC 8252 §7.3]: https://tools.ietf.org/html/rfc8252#section-7.3
+/// [RFC 8252 §8.3]: https://tools.ietf.org/html/rfc8252#section-8.3
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoopbackUrl(Url);
+
+/// A redirect URL with a private-use scheme of a native app, as described in [RFC 8252 §7.1].
+///
+/// The scheme must be in reverse domain name notation of a domain the app controls, such as
+/// `com.example.app:/oauth2redirect`, so that it does not collide with the schemes of other apps.
+/// Schemes without a period are refused, which also excludes `http` and `https`. The URL must not
+/// have a host, i.e. only a single slash follows the scheme, and must not have a fragment.
+///
+/// [RFC 8252 §7.1]: https://tools.ietf.org/html/rfc8252#section-7.1
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrivateUseUrl(Url);
+
+/// A URL was refused as a loopback or private-use redirect URL.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InvalidRedirectUrl {
+    /// The URL could not be parsed.
+    Parse(ParseUrlError),
+
+    /// The URL is not an `http` URL on a loopback IP literal.
+    NotLoopback,
+
+    /// The URL does not have a private-use scheme or has a host.
+    NotPrivateUse,
+
+    /// The URL has a fragment.
+    Fragment,
+}
+
 /// A pair of `client_id` and an optional `redirect_uri`.
 ///
 /// Such a pair is received in an Authorization Code Request. A registrar which allows multiple
 /// urls per client can use the optional parameter to choose the correct url. A prominent example
 /// is a native client which uses opens a local port to receive the redirect. Since it can not
-/// necessarily predict the port, the open port needs to be communicated to the server (Note: this
-/// mechanism is not provided by the simple `ClientMap` implementation).
+/// necessarily predict the port, the open port needs to be communicated to the server, see
+/// `RegisteredUrl::Loopback`.
 #[derive(Clone, Debug)]
 pub struct ClientUrl<'a> {
     /// The identifier indicated
@@ -130,6 +256,9 @@ pub struct PreGrant {
 
     /// A scope admissible for the above client.
     pub scope: Scope,
+
+    /// The descriptive metadata of the client, e.g. its name to show on a consent page.
+    pub metadata: ClientMetadata,
 }
 
 /// Handled responses from a registrar.
@@ -165,7 +294,67 @@ pub struct Client {
     redirect_uri: RegisteredUrl,
     additional_redirect_uris: Vec<RegisteredUrl>,
     default_scope: Scope,
+    max_scope: Option<Scope>,
     client_type: ClientType,
+    registration_access_token: Option<Vec<u8>>,
+    metadata: ClientMetadata,
+}
+
+/// Descriptive metadata of a client, in addition to the data required for authorization.
+///
+/// The field names follow the client metadata of [RFC 7591], so that it can be serialized as part
+/// of registration responses or stored by registrars. All fields are optional. The grant types and
+/// response types, when present, restrict the flows the client may use, see
+/// `RegisteredClient::check_grant_type`. The authentication method is informational.
+///
+/// [RFC 7591]: https://tools.ietf.org/html/rfc7591#section-2
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientMetadata {
+    /// The name of the client, as presented to the resource owner.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_name: Option<String>,
+
+    /// An url referencing a logo of the client.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logo_uri: Option<String>,
+
+    /// An url pointing to the policy of the client on how it uses the data of resource owners.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy_uri: Option<String>,
+
+    /// An url pointing to the terms of service of the client.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tos_uri: Option<String>,
+
+    /// Ways to contact the people responsible for the client, typically email addresses.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contacts: Vec<String>,
+
+    /// The grant types the client may use.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub grant_types: Vec<String>,
+
+    /// The response types the client may use at the authorization endpoint.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub response_types: Vec<String>,
+
+    /// How the client authenticates at the token endpoint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_endpoint_auth_method: Option<String>,
+}
+
+impl ClientMetadata {
+    /// Forbid the implicit grant, by removing `token` from the response types.
+    ///
+    /// Since an empty list permits all response types, it is replaced by only `code` in that case.
+    pub fn remove_implicit_grant(&mut self) {
+        if self.response_types.is_empty() {
+            self.response_types.push("code".to_string());
+        }
+        self.response_types
+            .retain(|response_type| response_type != "token");
+        self.grant_types.retain(|grant_type| grant_type != "implicit");
+    }
 }
 
 /// A client whose credentials have been wrapped by a password policy.
@@ -190,8 +379,17 @@ pub struct EncodedClient {
     /// The scope the client gets if none was given.
     pub default_scope: Scope,
 
+    /// The largest scope the client may request, its default scope if not set.
+    pub max_scope: Option<Scope>,
+
     /// The authentication data.
     pub encoded_client: ClientType,
+
+    /// The encoded registration access token, if the client manages its own registration.
+    pub registration_access_token: Option<Vec<u8>>,
+
+    /// The descriptive metadata of the client.
+    pub metadata: ClientMetadata,
 }
 
 /// Recombines an `EncodedClient` and a  `PasswordPolicy` to check authentication.
@@ -211,6 +409,22 @@ pub enum ClientType {
         /// Byte data encoding the password authentication under the used policy.
         passdata: Vec<u8>,
     },
+
+    /// A confidential client authenticated by a certificate of a trusted authority.
+    ///
+    /// This is the `tls_client_auth` method of RFC 8705.
+    TlsClientAuth {
+        /// The expected subject distinguished name of the certificate.
+        subject: String,
+    },
+
+    /// A confidential client authenticated by a registered, self-signed certificate.
+    ///
+    /// This is the `self_signed_tls_client_auth` method of RFC 8705.
+    SelfSignedTlsClientAuth {
+        /// The base64url encoded SHA-256 thumbprint of the certificate.
+        thumbprint: String,
+    },
 }
 
 /// A very simple, in-memory hash map of client ids to Client entries.
@@ -218,6 +432,22 @@ pub enum ClientType {
 pub struct ClientMap {
     clients: HashMap<String, EncodedClient>,
     password_policy: Option<Box<dyn PasswordPolicy>>,
+    scope_policy: ScopePolicy,
+}
+
+/// Decides how a requested scope is reconciled with the scopes a client may receive.
+///
+/// Requests without a scope always receive the default scope of the client. Otherwise the grant is
+/// limited to the maximum scope of the client, see `Client::with_max_scope`. Requests ending up
+/// without any scope token are refused with `invalid_scope`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScopePolicy {
+    /// Silently drop all requested scope tokens the client may not receive.
+    #[default]
+    Downscope,
+
+    /// Refuse requests for any scope token the client may not receive.
+    Strict,
 }
 
 impl fmt::Debug for ClientType {
@@ -225,6 +455,8 @@ impl fmt::Debug for ClientType {
         match self {
             ClientType::Public => write!(f, "<public>"),
             ClientType::Confidential { .. } => write!(f, "<confidential>"),
+            ClientType::TlsClientAuth { subject } => write!(f, "<tls_client_auth {}>", subject),
+            ClientType::SelfSignedTlsClientAuth { .. } => write!(f, "<self_signed_tls_client_auth>"),
         }
     }
 }
@@ -235,6 +467,8 @@ impl RegisteredUrl {
         match self {
             RegisteredUrl::Exact(exact) => &exact.0,
             RegisteredUrl::Semantic(url) => url.as_str(),
+            RegisteredUrl::Loopback(url) => url.0.as_str(),
+            RegisteredUrl::PrivateUse(url) => url.0.as_str(),
         }
     }
 
@@ -243,6 +477,19 @@ impl RegisteredUrl {
         match self {
             RegisteredUrl::Exact(exact) => exact.to_url(),
             RegisteredUrl::Semantic(url) => url.clone(),
+            RegisteredUrl::Loopback(url) => url.0.clone(),
+            RegisteredUrl::PrivateUse(url) => url.0.clone(),
+        }
+    }
+
+    /// The url to redirect to when it matched the url requested by the client.
+    ///
+    /// This is the registered url itself, except for loopback urls where the client is redirected
+    /// to the port it requested.
+    pub fn bind(&self, requested: &ExactUrl) -> RegisteredUrl {
+        match self {
+            RegisteredUrl::Loopback(_) => RegisteredUrl::Exact(requested.clone()),
+            registered => registered.clone(),
         }
     }
 
@@ -264,22 +511,38 @@ impl From<ExactUrl> for RegisteredUrl {
     }
 }
 
+impl From<LoopbackUrl> for RegisteredUrl {
+    fn from(url: LoopbackUrl) -> Self {
+        RegisteredUrl::Loopback(url)
+    }
+}
+
+impl From<PrivateUseUrl> for RegisteredUrl {
+    fn from(url: PrivateUseUrl) -> Self {
+        RegisteredUrl::PrivateUse(url)
+    }
+}
+
 impl From<RegisteredUrl> for Url {
     fn from(url: RegisteredUrl) -> Self {
         match url {
             RegisteredUrl::Exact(exact) => exact.0.parse().expect("was validated"),
             RegisteredUrl::Semantic(url) => url,
+            RegisteredUrl::Loopback(url) => url.0,
+            RegisteredUrl::PrivateUse(url) => url.0,
         }
     }
 }
 
 /// Compares the registered url as an exact string if it was registered as exact, otherwise
-/// semantically.
+/// semantically. Loopback urls match on any port.
 impl cmp::PartialEq<ExactUrl> for RegisteredUrl {
     fn eq(&self, exact: &ExactUrl) -> bool {
         match self {
             RegisteredUrl::Exact(url) => url == exact,
             RegisteredUrl::Semantic(url) => *url == exact.to_url(),
+            RegisteredUrl::Loopback(url) => url.matches(&exact.to_url()),
+            RegisteredUrl::PrivateUse(url) => url.0 == exact.to_url(),
         }
     }
 }
@@ -296,6 +559,8 @@ impl fmt::Display for RegisteredUrl {
         match self {
             RegisteredUrl::Exact(url) => write!(f, "{}", url.to_url()),
             RegisteredUrl::Semantic(url) => write!(f, "{}", url),
+            RegisteredUrl::Loopback(url) => write!(f, "{}", url.0),
+            RegisteredUrl::PrivateUse(url) => write!(f, "{}", url.0),
         }
     }
 }
@@ -327,6 +592,93 @@ impl core::str::FromStr for ExactUrl {
     }
 }
 
+impl LoopbackUrl {
+    /// Try to create a loopback url, the port of which is ignored.
+    pub fn new(url: Url) -> Result<Self, InvalidRedirectUrl> {
+        let loopback = match url.host() {
+            Some(url::Host::Ipv4(ip)) => IpAddr::V4(ip).is_loopback(),
+            Some(url::Host::Ipv6(ip)) => IpAddr::V6(ip).is_loopback(),
+            _ => false,
+        };
+
+        if url.scheme() != "http" || !loopback {
+            return Err(InvalidRedirectUrl::NotLoopback);
+        }
+
+        if url.fragment().is_some() {
+            return Err(InvalidRedirectUrl::Fragment);
+        }
+
+        Ok(LoopbackUrl(url))
+    }
+
+    /// View the url as a string.
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
+    fn matches(&self, requested: &Url) -> bool {
+        let mut requested = requested.clone();
+        match requested.set_port(self.0.port()) {
+            Ok(()) => self.0 == requested,
+            Err(()) => false,
+        }
+    }
+}
+
+impl core::str::FromStr for LoopbackUrl {
+    type Err = InvalidRedirectUrl;
+    fn from_str(st: &str) -> Result<Self, Self::Err> {
+        LoopbackUrl::new(st.parse()?)
+    }
+}
+
+impl PrivateUseUrl {
+    /// Try to create an url with a private-use scheme.
+    pub fn new(url: Url) -> Result<Self, InvalidRedirectUrl> {
+        if !url.scheme().contains('.') || url.has_host() || url.cannot_be_a_base() {
+            return Err(InvalidRedirectUrl::NotPrivateUse);
+        }
+
+        if url.fragment().is_some() {
+            return Err(InvalidRedirectUrl::Fragment);
+        }
+
+        Ok(PrivateUseUrl(url))
+    }
+
+    /// View the url as a string.
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl core::str::FromStr for PrivateUseUrl {
+    type Err = InvalidRedirectUrl;
+    fn from_str(st: &str) -> Result<Self, Self::Err> {
+        PrivateUseUrl::new(st.parse()?)
+    }
+}
+
+impl From<ParseUrlError> for InvalidRedirectUrl {
+    fn from(err: ParseUrlError) -> Self {
+        InvalidRedirectUrl::Parse(err)
+    }
+}
+
+impl fmt::Display for InvalidRedirectUrl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvalidRedirectUrl::Parse(err) => write!(f, "Malformed redirect url: {}", err),
+            InvalidRedirectUrl::NotLoopback => f.write_str("Not an http url on a loopback address"),
+            InvalidRedirectUrl::NotPrivateUse => f.write_str("Not an url with a private-use scheme"),
+            InvalidRedirectUrl::Fragment => f.write_str("A redirect url must not contain a fragment"),
+        }
+    }
+}
+
+impl std::error::Error for InvalidRedirectUrl {}
+
 impl Client {
     /// Create a public client.
     pub fn public(client_id: &str, redirect_uri: RegisteredUrl, default_scope: Scope) -> Client {
@@ -335,7 +687,10 @@ impl Client {
             redirect_uri,
             additional_redirect_uris: vec![],
             default_scope,
+            max_scope: None,
             client_type: ClientType::Public,
+            registration_access_token: None,
+            metadata: ClientMetadata::default(),
         }
     }
 
@@ -348,9 +703,53 @@ impl Client {
             redirect_uri,
             additional_redirect_uris: vec![],
             default_scope,
+            max_scope: None,
             client_type: ClientType::Confidential {
                 passdata: passphrase.to_owned(),
             },
+            registration_access_token: None,
+            metadata: ClientMetadata::default(),
+        }
+    }
+
+    /// Create a confidential client authenticated by a certificate of a trusted authority.
+    ///
+    /// The certificate presented by the client must have the given subject distinguished name.
+    pub fn tls_client_auth(
+        client_id: &str, redirect_uri: RegisteredUrl, default_scope: Scope, subject: &str,
+    ) -> Client {
+        Client {
+            client_id: client_id.to_string(),
+            redirect_uri,
+            additional_redirect_uris: vec![],
+            default_scope,
+            max_scope: None,
+            client_type: ClientType::TlsClientAuth {
+                subject: subject.to_string(),
+            },
+            registration_access_token: None,
+            metadata: ClientMetadata::default(),
+        }
+    }
+
+    /// Create a confidential client authenticated by a self-signed certificate.
+    ///
+    /// The thumbprint is the base64url encoded SHA-256 hash of the certificate, see
+    /// `mtls::thumbprint`.
+    pub fn self_signed_tls_client_auth(
+        client_id: &str, redirect_uri: RegisteredUrl, default_scope: Scope, thumbprint: &str,
+    ) -> Client {
+        Client {
+            client_id: client_id.to_string(),
+            redirect_uri,
+            additional_redirect_uris: vec![],
+            default_scope,
+            max_scope: None,
+            client_type: ClientType::SelfSignedTlsClientAuth {
+                thumbprint: thumbprint.to_string(),
+            },
+            registration_access_token: None,
+            metadata: ClientMetadata::default(),
         }
     }
 
@@ -360,6 +759,52 @@ impl Client {
         self
     }
 
+    /// Allow the client to request scopes beyond its default scope, up to the given one.
+    ///
+    /// Clients without a maximum scope can request at most their default scope.
+    pub fn with_max_scope(mut self, max_scope: Scope) -> Self {
+        self.max_scope = Some(max_scope);
+        self
+    }
+
+    /// Permit the client to use the resource owner password credentials grant.
+    ///
+    /// The client gets to handle the credentials of resource owners directly, so this should only
+    /// be enabled for highly trusted clients, e.g. first-party applications. This adds `password`
+    /// to the grant types of the metadata, so it must come after `with_metadata`.
+    pub fn with_password_grant(mut self) -> Self {
+        let grant_types = &mut self.metadata.grant_types;
+        if !grant_types.iter().any(|grant_type| grant_type == "password") {
+            grant_types.push("password".to_string());
+        }
+        self
+    }
+
+    /// Forbid the client to use the implicit grant.
+    ///
+    /// Clients which are able to use the authorization code grant should be prevented from
+    /// receiving access tokens in the fragment of their redirect url. This removes `token` from
+    /// the response types of the metadata, so it must come after `with_metadata`.
+    pub fn without_implicit_grant(mut self) -> Self {
+        self.metadata.remove_implicit_grant();
+        self
+    }
+
+    /// Allow the client to manage its own registration with the registration access token.
+    ///
+    /// The token is encoded by the password policy just like the passphrase of confidential
+    /// clients.
+    pub fn with_registration_access_token(mut self, token: &[u8]) -> Self {
+        self.registration_access_token = Some(token.to_owned());
+        self
+    }
+
+    /// Describe the client, e.g. with a name to present to resource owners.
+    pub fn with_metadata(mut self, metadata: ClientMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
     /// Obscure the clients authentication data.
     ///
     /// This could apply a one-way function to the passphrase using an adequate password hashing
@@ -371,14 +816,23 @@ impl Client {
             ClientType::Confidential { passdata: passphrase } => ClientType::Confidential {
                 passdata: policy.store(&self.client_id, &passphrase),
             },
+            other => other,
         };
 
+        let client_id = &self.client_id;
+        let registration_access_token = self
+            .registration_access_token
+            .map(|token| policy.store(client_id, &token));
+
         EncodedClient {
             client_id: self.client_id,
             redirect_uri: self.redirect_uri,
             additional_redirect_uris: self.additional_redirect_uris,
             default_scope: self.default_scope,
+            max_scope: self.max_scope,
             encoded_client,
+            registration_access_token,
+            metadata: self.metadata,
         }
     }
 }
@@ -404,6 +858,60 @@ impl<'a> RegisteredClient<'a> {
             _ => Err(RegistrarError::Unspecified),
         }
     }
+
+    /// Try to authenticate with the certificate of a mutual-TLS connection. This check will
+    /// succeed if the client was registered for `tls_client_auth` and the subject matches or for
+    /// `self_signed_tls_client_auth` and the thumbprint matches.
+    pub fn check_certificate(&self, certificate: &ClientCertificate) -> Result<(), RegistrarError> {
+        let matches = match &self.client.encoded_client {
+            ClientType::TlsClientAuth { subject } => certificate.subject() == Some(subject.as_str()),
+            ClientType::SelfSignedTlsClientAuth { thumbprint } => {
+                certificate.thumbprint() == thumbprint.as_str()
+            }
+            _ => false,
+        };
+
+        match matches {
+            true => Ok(()),
+            false => Err(RegistrarError::Unspecified),
+        }
+    }
+
+    /// Check that the grant type is one the client was registered for. Clients that were
+    /// registered without a list of grant types may use all of them, except for the `password`
+    /// grant which must always be listed. Listing only the `password` grant does not restrict the
+    /// client otherwise, see `Client::with_password_grant`.
+    pub fn check_grant_type(&self, grant_type: &str) -> Result<(), RegistrarError> {
+        let registered = &self.client.metadata.grant_types;
+        let listed = registered.iter().any(|known| known == grant_type);
+        let unrestricted = registered.iter().all(|known| known == "password");
+        match listed || (unrestricted && grant_type != "password") {
+            true => Ok(()),
+            false => Err(RegistrarError::Unspecified),
+        }
+    }
+
+    /// Check that the response type is one the client was registered for. Clients that were
+    /// registered without a list of response types may use all of them.
+    pub fn check_response_type(&self, response_type: &str) -> Result<(), RegistrarError> {
+        permitted(&self.client.metadata.response_types, response_type)
+    }
+
+    /// Check the registration access token of the client. Clients registered without one can not
+    /// manage their registration.
+    pub fn check_registration(&self, token: &[u8]) -> Result<(), RegistrarError> {
+        match &self.client.registration_access_token {
+            Some(stored) => self.policy.check(&self.client.client_id, token, stored),
+            None => Err(RegistrarError::Unspecified),
+        }
+    }
+}
+
+fn permitted(registered: &[String], requested: &str) -> Result<(), RegistrarError> {
+    match registered.is_empty() || registered.iter().any(|known| known == requested) {
+        true => Ok(()),
+        false => Err(RegistrarError::Unspecified),
+    }
 }
 
 impl cmp::PartialOrd<Self> for PreGrant {
@@ -466,6 +974,31 @@ impl PasswordPolicy for Argon2 {
 
 static DEFAULT_PASSWORD_POLICY: Lazy<Argon2> = Lazy::new(Argon2::default);
 
+impl ScopePolicy {
+    /// Determine the scope granted to a client for the requested scope.
+    pub fn negotiate(
+        &self, client: &EncodedClient, scope: Option<Scope>,
+    ) -> Result<Scope, RegistrarError> {
+        let requested = match scope {
+            None => return Ok(client.default_scope.clone()),
+            Some(requested) => requested,
+        };
+
+        let max_scope = client.max_scope.as_ref().unwrap_or(&client.default_scope);
+        let granted = match self {
+            ScopePolicy::Downscope => requested.intersection(max_scope),
+            ScopePolicy::Strict if requested.allow_access(max_scope) => requested,
+            ScopePolicy::Strict => return Err(RegistrarError::Unspecified),
+        };
+
+        if granted.iter().next().is_none() {
+            return Err(RegistrarError::Unspecified);
+        }
+
+        Ok(granted)
+    }
+}
+
 impl ClientMap {
     /// Create an empty map without any clients in it.
     pub fn new() -> ClientMap {
@@ -484,6 +1017,11 @@ impl ClientMap {
         self.password_policy = Some(Box::new(new_policy))
     }
 
+    /// Change how requested scopes exceeding the maximum scope of a client are treated.
+    pub fn set_scope_policy(&mut self, new_policy: ScopePolicy) {
+        self.scope_policy = new_policy
+    }
+
     // This is not an instance method because it needs to borrow the box but register needs &mut
     fn current_policy<'a>(policy: &'a Option<Box<dyn PasswordPolicy>>) -> &'a dyn PasswordPolicy {
         policy
@@ -525,6 +1063,20 @@ impl<'s, R: Registrar + ?Sized> Registrar for &'s R {
     fn check(&self, client_id: &str, passphrase: Option<&[u8]>) -> Result<(), RegistrarError> {
         (**self).check(client_id, passphrase)
     }
+
+    fn check_certificate(
+        &self, client_id: &str, certificate: &ClientCertificate,
+    ) -> Result<(), RegistrarError> {
+        (**self).check_certificate(client_id, certificate)
+    }
+
+    fn check_grant_type(&self, client_id: &str, grant_type: &str) -> Result<(), RegistrarError> {
+        (**self).check_grant_type(client_id, grant_type)
+    }
+
+    fn check_response_type(&self, client_id: &str, response_type: &str) -> Result<(), RegistrarError> {
+        (**self).check_response_type(client_id, response_type)
+    }
 }
 
 impl<'s, R: Registrar + ?Sized> Registrar for &'s mut R {
@@ -539,6 +1091,20 @@ impl<'s, R: Registrar + ?Sized> Registrar for &'s mut R {
     fn check(&self, client_id: &str, passphrase: Option<&[u8]>) -> Result<(), RegistrarError> {
         (**self).check(client_id, passphrase)
     }
+
+    fn check_certificate(
+        &self, client_id: &str, certificate: &ClientCertificate,
+    ) -> Result<(), RegistrarError> {
+        (**self).check_certificate(client_id, certificate)
+    }
+
+    fn check_grant_type(&self, client_id: &str, grant_type: &str) -> Result<(), RegistrarError> {
+        (**self).check_grant_type(client_id, grant_type)
+    }
+
+    fn check_response_type(&self, client_id: &str, response_type: &str) -> Result<(), RegistrarError> {
+        (**self).check_response_type(client_id, response_type)
+    }
 }
 
 impl<R: Registrar + ?Sized> Registrar for Box<R> {
@@ -553,6 +1119,20 @@ impl<R: Registrar + ?Sized> Registrar for Box<R> {
     fn check(&self, client_id: &str, passphrase: Option<&[u8]>) -> Result<(), RegistrarError> {
         (**self).check(client_id, passphrase)
     }
+
+    fn check_certificate(
+        &self, client_id: &str, certificate: &ClientCertificate,
+    ) -> Result<(), RegistrarError> {
+        (**self).check_certificate(client_id, certificate)
+    }
+
+    fn check_grant_type(&self, client_id: &str, grant_type: &str) -> Result<(), RegistrarError> {
+        (**self).check_grant_type(client_id, grant_type)
+    }
+
+    fn check_response_type(&self, client_id: &str, response_type: &str) -> Result<(), RegistrarError> {
+        (**self).check_response_type(client_id, response_type)
+    }
 }
 
 impl<R: Registrar + ?Sized> Registrar for Rc<R> {
@@ -567,6 +1147,20 @@ impl<R: Registrar + ?Sized> Registrar for Rc<R> {
     fn check(&self, client_id: &str, passphrase: Option<&[u8]>) -> Result<(), RegistrarError> {
         (**self).check(client_id, passphrase)
     }
+
+    fn check_certificate(
+        &self, client_id: &str, certificate: &ClientCertificate,
+    ) -> Result<(), RegistrarError> {
+        (**self).check_certificate(client_id, certificate)
+    }
+
+    fn check_grant_type(&self, client_id: &str, grant_type: &str) -> Result<(), RegistrarError> {
+        (**self).check_grant_type(client_id, grant_type)
+    }
+
+    fn check_response_type(&self, client_id: &str, response_type: &str) -> Result<(), RegistrarError> {
+        (**self).check_response_type(client_id, response_type)
+    }
 }
 
 impl<R: Registrar + ?Sized> Registrar for Arc<R> {
@@ -581,6 +1175,20 @@ impl<R: Registrar + ?Sized> Registrar for Arc<R> {
     fn check(&self, client_id: &str, passphrase: Option<&[u8]>) -> Result<(), RegistrarError> {
         (**self).check(client_id, passphrase)
     }
+
+    fn check_certificate(
+        &self, client_id: &str, certificate: &ClientCertificate,
+    ) -> Result<(), RegistrarError> {
+        (**self).check_certificate(client_id, certificate)
+    }
+
+    fn check_grant_type(&self, client_id: &str, grant_type: &str) -> Result<(), RegistrarError> {
+        (**self).check_grant_type(client_id, grant_type)
+    }
+
+    fn check_response_type(&self, client_id: &str, response_type: &str) -> Result<(), RegistrarError> {
+        (**self).check_response_type(client_id, response_type)
+    }
 }
 
 impl<'s, R: Registrar + ?Sized + 's> Registrar for MutexGuard<'s, R> {
@@ -595,6 +1203,20 @@ impl<'s, R: Registrar + ?Sized + 's> Registrar for MutexGuard<'s, R> {
     fn check(&self, client_id: &str, passphrase: Option<&[u8]>) -> Result<(), RegistrarError> {
         (**self).check(client_id, passphrase)
     }
+
+    fn check_certificate(
+        &self, client_id: &str, certificate: &ClientCertificate,
+    ) -> Result<(), RegistrarError> {
+        (**self).check_certificate(client_id, certificate)
+    }
+
+    fn check_grant_type(&self, client_id: &str, grant_type: &str) -> Result<(), RegistrarError> {
+        (**self).check_grant_type(client_id, grant_type)
+    }
+
+    fn check_response_type(&self, client_id: &str, response_type: &str) -> Result<(), RegistrarError> {
+        (**self).check_response_type(client_id, response_type)
+    }
 }
 
 impl<'s, R: Registrar + ?Sized + 's> Registrar for RwLockWriteGuard<'s, R> {
@@ -609,6 +1231,108 @@ impl<'s, R: Registrar + ?Sized + 's> Registrar for RwLockWriteGuard<'s, R> {
     fn check(&self, client_id: &str, passphrase: Option<&[u8]>) -> Result<(), RegistrarError> {
         (**self).check(client_id, passphrase)
     }
+
+    fn check_certificate(
+        &self, client_id: &str, certificate: &ClientCertificate,
+    ) -> Result<(), RegistrarError> {
+        (**self).check_certificate(client_id, certificate)
+    }
+
+    fn check_grant_type(&self, client_id: &str, grant_type: &str) -> Result<(), RegistrarError> {
+        (**self).check_grant_type(client_id, grant_type)
+    }
+
+    fn check_response_type(&self, client_id: &str, response_type: &str) -> Result<(), RegistrarError> {
+        (**self).check_response_type(client_id, response_type)
+    }
+}
+
+impl<'s, R: RegistrarMut + ?Sized> RegistrarMut for &'s mut R {
+    fn register(&mut self, client: Client) -> Result<(), RegistrarError> {
+        (**self).register(client)
+    }
+
+    fn client(&self, client_id: &str) -> Result<Option<EncodedClient>, RegistrarError> {
+        (**self).client(client_id)
+    }
+
+    fn update(&mut self, client: EncodedClient) -> Result<(), RegistrarError> {
+        (**self).update(client)
+    }
+
+    fn unregister(&mut self, client_id: &str) -> Result<(), RegistrarError> {
+        (**self).unregister(client_id)
+    }
+
+    fn check_registration(&self, client_id: &str, token: &[u8]) -> Result<(), RegistrarError> {
+        (**self).check_registration(client_id, token)
+    }
+}
+
+impl<R: RegistrarMut + ?Sized> RegistrarMut for Box<R> {
+    fn register(&mut self, client: Client) -> Result<(), RegistrarError> {
+        (**self).register(client)
+    }
+
+    fn client(&self, client_id: &str) -> Result<Option<EncodedClient>, RegistrarError> {
+        (**self).client(client_id)
+    }
+
+    fn update(&mut self, client: EncodedClient) -> Result<(), RegistrarError> {
+        (**self).update(client)
+    }
+
+    fn unregister(&mut self, client_id: &str) -> Result<(), RegistrarError> {
+        (**self).unregister(client_id)
+    }
+
+    fn check_registration(&self, client_id: &str, token: &[u8]) -> Result<(), RegistrarError> {
+        (**self).check_registration(client_id, token)
+    }
+}
+
+impl<'s, R: RegistrarMut + ?Sized + 's> RegistrarMut for MutexGuard<'s, R> {
+    fn register(&mut self, client: Client) -> Result<(), RegistrarError> {
+        (**self).register(client)
+    }
+
+    fn client(&self, client_id: &str) -> Result<Option<EncodedClient>, RegistrarError> {
+        (**self).client(client_id)
+    }
+
+    fn update(&mut self, client: EncodedClient) -> Result<(), RegistrarError> {
+        (**self).update(client)
+    }
+
+    fn unregister(&mut self, client_id: &str) -> Result<(), RegistrarError> {
+        (**self).unregister(client_id)
+    }
+
+    fn check_registration(&self, client_id: &str, token: &[u8]) -> Result<(), RegistrarError> {
+        (**self).check_registration(client_id, token)
+    }
+}
+
+impl<'s, R: RegistrarMut + ?Sized + 's> RegistrarMut for RwLockWriteGuard<'s, R> {
+    fn register(&mut self, client: Client) -> Result<(), RegistrarError> {
+        (**self).register(client)
+    }
+
+    fn client(&self, client_id: &str) -> Result<Option<EncodedClient>, RegistrarError> {
+        (**self).client(client_id)
+    }
+
+    fn update(&mut self, client: EncodedClient) -> Result<(), RegistrarError> {
+        (**self).update(client)
+    }
+
+    fn unregister(&mut self, client_id: &str) -> Result<(), RegistrarError> {
+        (**self).unregister(client_id)
+    }
+
+    fn check_registration(&self, client_id: &str, token: &[u8]) -> Result<(), RegistrarError> {
+        (**self).check_registration(client_id, token)
+    }
 }
 
 impl Registrar for ClientMap {
@@ -628,7 +1352,7 @@ impl Registrar for ClientMap {
                     .chain(alternatives)
                     .find(|&registered| *registered == *url.as_ref())
                 {
-                    registered.clone()
+                    registered.bind(url.as_ref())
                 } else {
                     return Err(RegistrarError::Unspecified);
                 }
@@ -641,16 +1365,18 @@ impl Registrar for ClientMap {
         })
     }
 
-    /// Always overrides the scope with a default scope.
-    fn negotiate(&self, bound: BoundClient, _scope: Option<Scope>) -> Result<PreGrant, RegistrarError> {
+    /// Limits the requested scope to the maximum scope of the client, according to the policy.
+    fn negotiate(&self, bound: BoundClient, scope: Option<Scope>) -> Result<PreGrant, RegistrarError> {
         let client = self
             .clients
             .get(bound.client_id.as_ref())
             .expect("Bound client appears to not have been constructed with this registrar");
+        let scope = self.scope_policy.negotiate(client, scope)?;
         Ok(PreGrant {
             client_id: bound.client_id.into_owned(),
             redirect_uri: bound.redirect_uri.into_owned(),
-            scope: client.default_scope.clone(),
+            scope,
+            metadata: client.metadata.clone(),
         })
     }
 
@@ -666,6 +1392,71 @@ impl Registrar for ClientMap {
 
         Ok(())
     }
+
+    fn check_certificate(
+        &self, client_id: &str, certificate: &ClientCertificate,
+    ) -> Result<(), RegistrarError> {
+        let password_policy = Self::current_policy(&self.password_policy);
+
+        self.clients
+            .get(client_id)
+            .ok_or(RegistrarError::Unspecified)
+            .and_then(|client| {
+                RegisteredClient::new(client, password_policy).check_certificate(certificate)
+            })
+    }
+
+    fn check_grant_type(&self, client_id: &str, grant_type: &str) -> Result<(), RegistrarError> {
+        let password_policy = Self::current_policy(&self.password_policy);
+
+        self.clients
+            .get(client_id)
+            .ok_or(RegistrarError::Unspecified)
+            .and_then(|client| {
+                RegisteredClient::new(client, password_policy).check_grant_type(grant_type)
+            })
+    }
+
+    fn check_response_type(&self, client_id: &str, response_type: &str) -> Result<(), RegistrarError> {
+        let password_policy = Self::current_policy(&self.password_policy);
+
+        self.clients
+            .get(client_id)
+            .ok_or(RegistrarError::Unspecified)
+            .and_then(|client| {
+                RegisteredClient::new(client, password_policy).check_response_type(response_type)
+            })
+    }
+}
+
+impl RegistrarMut for ClientMap {
+    fn register(&mut self, client: Client) -> Result<(), RegistrarError> {
+        self.register_client(client);
+        Ok(())
+    }
+
+    fn client(&self, client_id: &str) -> Result<Option<EncodedClient>, RegistrarError> {
+        Ok(self.clients.get(client_id).cloned())
+    }
+
+    fn update(&mut self, client: EncodedClient) -> Result<(), RegistrarError> {
+        self.clients.insert(client.client_id.clone(), client);
+        Ok(())
+    }
+
+    fn unregister(&mut self, client_id: &str) -> Result<(), RegistrarError> {
+        self.clients.remove(client_id);
+        Ok(())
+    }
+
+    fn check_registration(&self, client_id: &str, token: &[u8]) -> Result<(), RegistrarError> {
+        let password_policy = Self::current_policy(&self.password_policy);
+
+        self.clients
+            .get(client_id)
+            .ok_or(RegistrarError::Unspecified)
+            .and_then(|client| RegisteredClient::new(client, password_policy).check_registration(token))
+    }
 }
 
 #[cfg(test)]
@@ -757,6 +1548,207 @@ mod tests {
         assert!(client.check_authentication(Some(b"")).is_err());
     }
 
+    #[test]
+    fn certificate_client() {
+        let mut client_map = ClientMap::new();
+        let client_url: Url = "https://example.com".parse().unwrap();
+        let issued = ClientCertificate::from_der(b"issued").with_subject("CN=partner");
+        let self_signed = ClientCertificate::from_der(b"self-signed");
+        client_map.register_client(Client::tls_client_auth(
+            "Issued",
+            client_url.clone().into(),
+            "default".parse().unwrap(),
+            "CN=partner",
+        ));
+        client_map.register_client(Client::self_signed_tls_client_auth(
+            "SelfSigned",
+            client_url.clone().into(),
+            "default".parse().unwrap(),
+            self_signed.thumbprint(),
+        ));
+        client_map.register_client(Client::public(
+            "Public",
+            client_url.into(),
+            "default".parse().unwrap(),
+        ));
+
+        assert!(client_map.check_certificate("Issued", &issued).is_ok());
+        assert!(client_map.check_certificate("Issued", &self_signed).is_err());
+        assert!(client_map.check_certificate("SelfSigned", &self_signed).is_ok());
+        assert!(client_map.check_certificate("SelfSigned", &issued).is_err());
+        assert!(client_map.check_certificate("Public", &issued).is_err());
+        assert!(client_map.check_certificate("Unknown", &issued).is_err());
+
+        // Certificate clients can not authenticate without their certificate.
+        assert!(client_map.check("Issued", None).is_err());
+        assert!(client_map.check("SelfSigned", Some(b"")).is_err());
+    }
+
+    #[test]
+    fn password_grant_client() {
+        let mut client_map = ClientMap::new();
+        let client_url: Url = "https://example.com".parse().unwrap();
+        let default = Client::public("Default", client_url.clone().into(), "default".parse().unwrap());
+        let trusted = Client::public("Trusted", client_url.into(), "default".parse().unwrap())
+            .with_password_grant();
+        client_map.register_client(default);
+        client_map.register_client(trusted);
+
+        assert!(client_map.check_grant_type("Default", "password").is_err());
+        assert!(client_map.check_grant_type("Trusted", "password").is_ok());
+        assert!(client_map
+            .check_grant_type("Trusted", "authorization_code")
+            .is_ok());
+        assert!(client_map.check_grant_type("Unknown", "password").is_err());
+    }
+
+    #[test]
+    fn implicit_grant_client() {
+        let mut client_map = ClientMap::new();
+        let client_url: Url = "https://example.com".parse().unwrap();
+        let default = Client::public("Default", client_url.clone().into(), "default".parse().unwrap());
+        let restricted = Client::public("Restricted", client_url.into(), "default".parse().unwrap())
+            .without_implicit_grant();
+        client_map.register_client(default);
+        client_map.register_client(restricted);
+
+        assert!(client_map.check_response_type("Default", "token").is_ok());
+        assert!(client_map.check_response_type("Restricted", "token").is_err());
+        assert!(client_map.check_response_type("Restricted", "code").is_ok());
+        assert!(client_map.check_response_type("Unknown", "token").is_err());
+    }
+
+    #[test]
+    fn client_metadata() {
+        let mut client_map = ClientMap::new();
+        let metadata = ClientMetadata {
+            client_name: Some("Example Client".to_string()),
+            logo_uri: Some("https://example.com/logo.png".to_string()),
+            contacts: vec!["admin@example.com".to_string()],
+            ..ClientMetadata::default()
+        };
+        let client = Client::public(
+            "ClientId",
+            "https://example.com".parse::<Url>().unwrap().into(),
+            "default".parse().unwrap(),
+        )
+        .with_metadata(metadata.clone());
+        client_map.register_client(client);
+
+        let bound = client_map
+            .bound_redirect(ClientUrl {
+                client_id: Cow::from("ClientId"),
+                redirect_uri: None,
+            })
+            .unwrap();
+        let pre_grant = client_map.negotiate(bound, None).unwrap();
+        assert_eq!(pre_grant.metadata, metadata);
+
+        let json = serde_json::to_value(&metadata).unwrap();
+        assert_eq!(json["client_name"], "Example Client");
+        assert!(json.get("policy_uri").is_none());
+        assert!(json.get("grant_types").is_none());
+    }
+
+    #[test]
+    fn permitted_grant_and_response_types() {
+        let mut client_map = ClientMap::new();
+        client_map.register_client(Client::public(
+            "Unrestricted",
+            "https://example.com".parse::<Url>().unwrap().into(),
+            "default".parse().unwrap(),
+        ));
+        client_map.register_client(
+            Client::public(
+                "Restricted",
+                "https://example.com".parse::<Url>().unwrap().into(),
+                "default".parse().unwrap(),
+            )
+            .with_metadata(ClientMetadata {
+                grant_types: vec!["authorization_code".to_string()],
+                response_types: vec!["code".to_string()],
+                ..ClientMetadata::default()
+            }),
+        );
+
+        assert!(client_map
+            .check_grant_type("Unrestricted", "refresh_token")
+            .is_ok());
+        assert!(client_map.check_response_type("Unrestricted", "token").is_ok());
+        assert!(client_map
+            .check_grant_type("Restricted", "authorization_code")
+            .is_ok());
+        assert!(client_map
+            .check_grant_type("Restricted", "refresh_token")
+            .is_err());
+        assert!(client_map.check_response_type("Restricted", "code").is_ok());
+        assert!(client_map.check_response_type("Restricted", "token").is_err());
+        assert!(client_map
+            .check_grant_type("Unknown", "authorization_code")
+            .is_err());
+    }
+
+    #[test]
+    fn negotiate_scope() {
+        let mut client_map = ClientMap::new();
+        client_map.register_client(
+            Client::public(
+                "ClientId",
+                "https://example.com".parse::<Url>().unwrap().into(),
+                "default".parse().unwrap(),
+            )
+            .with_max_scope("default email".parse().unwrap()),
+        );
+        client_map.register_client(Client::public(
+            "Unbounded",
+            "https://example.com".parse::<Url>().unwrap().into(),
+            "default".parse().unwrap(),
+        ));
+
+        let negotiate = |client_map: &ClientMap, client_id: &str, scope: Option<&str>| {
+            let bound = client_map
+                .bound_redirect(ClientUrl {
+                    client_id: Cow::from(client_id.to_string()),
+                    redirect_uri: None,
+                })
+                .unwrap();
+            let scope = scope.map(|scope| scope.parse().unwrap());
+            client_map
+                .negotiate(bound, scope)
+                .map(|pre_grant| pre_grant.scope)
+        };
+
+        // Downscope is the default policy.
+        assert_eq!(
+            negotiate(&client_map, "ClientId", None).unwrap(),
+            "default".parse().unwrap()
+        );
+        assert_eq!(
+            negotiate(&client_map, "ClientId", Some("email")).unwrap(),
+            "email".parse().unwrap()
+        );
+        assert_eq!(
+            negotiate(&client_map, "ClientId", Some("email admin")).unwrap(),
+            "email".parse().unwrap()
+        );
+        assert!(negotiate(&client_map, "ClientId", Some("admin")).is_err());
+        assert!(negotiate(&client_map, "ClientId", Some("")).is_err());
+
+        // Without a maximum scope the default scope is the limit.
+        assert_eq!(
+            negotiate(&client_map, "Unbounded", Some("default email")).unwrap(),
+            "default".parse().unwrap()
+        );
+
+        client_map.set_scope_policy(ScopePolicy::Strict);
+        assert_eq!(
+            negotiate(&client_map, "ClientId", Some("default email")).unwrap(),
+            "default email".parse().unwrap()
+        );
+        assert!(negotiate(&client_map, "ClientId", Some("email admin")).is_err());
+        assert!(negotiate(&client_map, "Unbounded", Some("default email")).is_err());
+    }
+
     #[test]
     fn with_additional_redirect_uris() {
         let client_id = "ClientId";
@@ -799,9 +1791,125 @@ mod tests {
             .is_err());
     }
 
+    #[test]
+    fn loopback_redirect_uri() {
+        for refused in &[
+            "https://127.0.0.1/cb",
+            "http://localhost/cb",
+            "http://example.com/cb",
+            "http://10.0.0.1/cb",
+            "http://127.0.0.1/cb#fragment",
+        ] {
+            assert!(refused.parse::<LoopbackUrl>().is_err(), "{}", refused);
+        }
+
+        let mut client_map = ClientMap::new();
+        let loopback: LoopbackUrl = "http://127.0.0.1/cb".parse().unwrap();
+        let ipv6: LoopbackUrl = "http://[::1]/cb".parse().unwrap();
+        let client = Client::public("ClientId", loopback.into(), "default".parse().unwrap())
+            .with_additional_redirect_uris(vec![ipv6.into()]);
+        client_map.register_client(client);
+
+        let bind = |url: &str| {
+            client_map
+                .bound_redirect(ClientUrl {
+                    client_id: Cow::from("ClientId"),
+                    redirect_uri: Some(Cow::Owned(url.parse().unwrap())),
+                })
+                .map(|bound| bound.redirect_uri.into_owned())
+        };
+
+        // Any port matches and the client is redirected to the port it requested.
+        for requested in &[
+            "http://127.0.0.1/cb",
+            "http://127.0.0.1:49152/cb",
+            "http://[::1]:8080/cb",
+        ] {
+            let bound = bind(requested).unwrap();
+            assert_eq!(bound.as_str(), *requested);
+        }
+
+        for refused in &[
+            "http://127.0.0.1:49152/other",
+            "http://127.0.0.1:49152/cb?query",
+            "https://127.0.0.1:49152/cb",
+            "http://127.0.0.2:49152/cb",
+            "http://localhost:49152/cb",
+        ] {
+            assert!(bind(refused).is_err(), "{}", refused);
+        }
+    }
+
+    #[test]
+    fn private_use_redirect_uri() {
+        for refused in &[
+            "myapp:/cb",
+            "https://example.com/cb",
+            "com.example.app://host/cb",
+            "com.example.app:cb",
+            "com.example.app:/cb#fragment",
+        ] {
+            assert!(refused.parse::<PrivateUseUrl>().is_err(), "{}", refused);
+        }
+
+        let mut client_map = ClientMap::new();
+        let private_use: PrivateUseUrl = "com.example.app:/oauth2redirect".parse().unwrap();
+        let client = Client::public("ClientId", private_use.into(), "default".parse().unwrap());
+        client_map.register_client(client);
+
+        let bind = |url: &str| {
+            client_map.bound_redirect(ClientUrl {
+                client_id: Cow::from("ClientId"),
+                redirect_uri: Some(Cow::Owned(url.parse().unwrap())),
+            })
+        };
+
+        let bound = bind("com.example.app:/oauth2redirect").unwrap();
+        assert_eq!(bound.redirect_uri.as_str(), "com.example.app:/oauth2redirect");
+
+        for refused in &[
+            "com.example.app:/other",
+            "com.example.other:/oauth2redirect",
+            "com.example.app://evil.example/oauth2redirect",
+        ] {
+            assert!(bind(refused).is_err(), "{}", refused);
+        }
+    }
+
     #[test]
     fn client_map() {
         let mut client_map = ClientMap::new();
         simple_test_suite(&mut client_map, ClientMap::register_client);
     }
+
+    #[test]
+    fn client_map_registration() {
+        let mut client_map = ClientMap::new();
+        let token = b"registration access token";
+        let client = Client::public(
+            "ClientId",
+            "https://example.com".parse::<Url>().unwrap().into(),
+            "default".parse().unwrap(),
+        )
+        .with_registration_access_token(token);
+
+        client_map.register(client).unwrap();
+        assert!(client_map.check_registration("ClientId", token).is_ok());
+        assert!(client_map.check_registration("ClientId", b"wrong token").is_err());
+        assert!(client_map.check_registration("UnknownId", token).is_err());
+
+        let mut stored = client_map.client("ClientId").unwrap().unwrap();
+        stored.default_scope = "updated".parse().unwrap();
+        client_map.update(stored).unwrap();
+        assert_eq!(
+            client_map.client("ClientId").unwrap().unwrap().default_scope,
+            "updated".parse().unwrap()
+        );
+        assert!(client_map.check_registration("ClientId", token).is_ok());
+
+        client_map.unregister("ClientId").unwrap();
+        assert!(client_map.client("ClientId").unwrap().is_none());
+        assert!(client_map.check_registration("ClientId", token).is_err());
+        assert!(client_map.check("ClientId", None).is_err());
+    }
 }
@@ -71,6 +71,13 @@ impl Scope {
         self <= rhs
     }
 
+    /// The scope consisting of the scope tokens found in both scopes.
+    pub fn intersection(&self, rhs: &Scope) -> Scope {
+        Scope {
+            tokens: self.tokens.intersection(&rhs.tokens).cloned().collect(),
+        }
+    }
+
     /// Create an iterator over the individual scopes.
     pub fn iter(&self) -> impl Iterator<Item = &str> {
         self.tokens.iter().map(AsRef::as_ref)
@@ -196,6 +203,17 @@ mod tests {
         assert!(!scope_uncmp.allow_access(&scope_base));
     }
 
+    #[test]
+    fn test_intersection() {
+        let scope_base = "cap1 cap2".parse::<Scope>().unwrap();
+        let scope_uncmp = "cap1 cap3".parse::<Scope>().unwrap();
+        let scope_other = "cap4".parse::<Scope>().unwrap();
+
+        assert_eq!(scope_base.intersection(&scope_uncmp), "cap1".parse().unwrap());
+        assert_eq!(scope_base.intersection(&scope_base), scope_base);
+        assert!(scope_base.intersection(&scope_other).iter().next().is_none());
+    }
+
     #[test]
     fn test_iterating() {
         let scope = "cap1 cap2 cap3".parse::<Scope>().unwrap();